@@ -0,0 +1,129 @@
+//! Command-line options. Hand-parsed: the flags are few and flat.
+
+pub const USAGE: &str = "\
+Usage: primordium-cli [options]
+
+Runs the simulation headless on a native GPU and writes stats as CSV.
+
+Options:
+  --ticks N           ticks to run (default 1000)
+  --grid N            grid side in voxels (default 64)
+  --sparse BRICKS     sparse storage with a pool of BRICKS 8³ bricks
+  --preset ID         world preset to seed (default 0)
+  --seed N            random seed for the preset's placements and genomes
+  --load PATH         start from a snapshot saved by export_snapshot
+  --param NAME=VALUE  set a simulation parameter; repeatable
+  --stats-every N     one CSV row every N ticks (default 100, 0 = none)
+  --csv PATH          write the CSV to PATH instead of stdout
+  --save PATH         save a snapshot after the last tick
+  --pipeline-cache DIR
+                      keep the driver's compiled pipelines in DIR between
+                      runs (Vulkan only; ignored elsewhere)
+  --help              show this text
+";
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub ticks: u32,
+    pub grid_size: u32,
+    pub max_bricks: Option<u32>,
+    pub preset: u32,
+    pub seed: Option<u64>,
+    pub load: Option<String>,
+    pub params: Vec<(String, f32)>,
+    pub stats_every: u32,
+    pub csv: Option<String>,
+    pub save: Option<String>,
+    pub pipeline_cache: Option<String>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            ticks: 1000,
+            grid_size: 64,
+            max_bricks: None,
+            preset: 0,
+            seed: None,
+            load: None,
+            params: Vec::new(),
+            stats_every: 100,
+            csv: None,
+            save: None,
+            pipeline_cache: None,
+        }
+    }
+}
+
+impl Args {
+    /// Parse the arguments after the program name. `Ok(None)` for `--help`.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut out = Args::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            if flag == "--help" || flag == "-h" {
+                return Ok(None);
+            }
+            let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+            let number = |v: &str| v.parse::<u32>().map_err(|_| format!("{flag}: '{v}' is not a whole number"));
+            match flag.as_str() {
+                "--ticks" => out.ticks = number(&value)?,
+                "--grid" => out.grid_size = number(&value)?,
+                "--sparse" => out.max_bricks = Some(number(&value)?),
+                "--preset" => out.preset = number(&value)?,
+                "--seed" => out.seed = Some(value.parse().map_err(|_| format!("{flag}: '{value}' is not a whole number"))?),
+                "--load" => out.load = Some(value),
+                "--param" => {
+                    let (name, v) = value.split_once('=').ok_or_else(|| format!("--param: expected NAME=VALUE, got '{value}'"))?;
+                    let v = v.parse::<f32>().map_err(|_| format!("--param {name}: '{v}' is not a number"))?;
+                    if !types::SimParams::default().set_named(name, v) {
+                        return Err(format!("--param: unknown parameter '{name}'"));
+                    }
+                    out.params.push((name.to_string(), v));
+                }
+                "--stats-every" => out.stats_every = number(&value)?,
+                "--csv" => out.csv = Some(value),
+                "--save" => out.save = Some(value),
+                "--pipeline-cache" => out.pipeline_cache = Some(value),
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+        Ok(Some(out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_flags_over_the_defaults() {
+        let args = parse(&[]).unwrap().unwrap();
+        assert_eq!((args.ticks, args.grid_size, args.stats_every, args.max_bricks), (1000, 64, 100, None));
+        let args = parse(&[
+            "--ticks", "50", "--grid", "32", "--sparse", "256", "--seed", "12345678901",
+            "--param", "dt=0.5", "--param", "nutrient_spawn_rate=2", "--csv", "out.csv", "--pipeline-cache", "cache",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!((args.ticks, args.grid_size, args.max_bricks, args.seed), (50, 32, Some(256), Some(12345678901)));
+        assert_eq!(args.params, vec![("dt".to_string(), 0.5), ("nutrient_spawn_rate".to_string(), 2.0)]);
+        assert_eq!(args.csv.as_deref(), Some("out.csv"));
+        assert_eq!(args.pipeline_cache.as_deref(), Some("cache"));
+        assert!(parse(&["--ticks", "5", "--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(parse(&["--ticks"]).unwrap_err(), "--ticks needs a value");
+        assert_eq!(parse(&["--grid", "-4"]).unwrap_err(), "--grid: '-4' is not a whole number");
+        assert_eq!(parse(&["--frobnicate", "1"]).unwrap_err(), "unknown option --frobnicate");
+        assert!(parse(&["--param", "dt"]).unwrap_err().contains("expected NAME=VALUE"));
+        assert!(parse(&["--param", "dt=fast"]).unwrap_err().contains("is not a number"));
+        assert_eq!(parse(&["--param", "no_such=1"]).unwrap_err(), "--param: unknown parameter 'no_such'");
+    }
+}
@@ -0,0 +1,251 @@
+//! Headless native runner: drives `SimEngine` on a native wgpu device with
+//! no canvas, for batch runs that write stats as CSV and exit. Blocking on
+//! the device (`PollType::wait_indefinitely`) is fine here, unlike in the
+//! browser host.
+
+mod args;
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use args::{Args, USAGE};
+use sim_core::{GridConfig, SimEngine, SimStats, WorldSeed};
+
+/// Ticks recorded per submission, within the engine's tick-slot ring.
+const TICKS_PER_SUBMIT: u32 = 32;
+
+const CSV_HEADER: &str = "tick,sim_time,population,total_energy,species_count,other_species,max_energy,toxin_deaths,\
+births,deaths,mean_mutation_rate,mean_age,temp_mean,energy_mean,pheromone_mean,nutrients,waste,walls";
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(&args) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run `future` to completion on this thread.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(out) => return out,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+fn create_device() -> Result<(wgpu::Device, wgpu::Queue, wgpu::AdapterInfo), String> {
+    if wgpu::Instance::enabled_backend_features().is_empty() {
+        return Err("built without a native wgpu backend for this platform".to_string());
+    }
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter: false,
+    }))
+    .map_err(|e| format!("no GPU adapter ({e})"))?;
+    let info = adapter.get_info();
+    eprintln!("GPU adapter: {} ({:?}), backend: {:?}", info.name, info.device_type, info.backend);
+    let adapter_limits = adapter.limits();
+    let (device, queue) = block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("primordium_cli_device"),
+        required_features: adapter.features() & wgpu::Features::PIPELINE_CACHE,
+        required_limits: wgpu::Limits {
+            max_buffer_size: adapter_limits.max_buffer_size,
+            max_storage_buffer_binding_size: adapter_limits.max_storage_buffer_binding_size,
+            ..wgpu::Limits::default()
+        },
+        experimental_features: wgpu::ExperimentalFeatures::default(),
+        memory_hints: wgpu::MemoryHints::Performance,
+        trace: wgpu::Trace::Off,
+    }))
+    .map_err(|e| format!("failed to create device: {e}"))?;
+    Ok((device, queue, info))
+}
+
+/// Map `buffer` and wait for it.
+fn map_blocking(device: &wgpu::Device, buffer: &wgpu::Buffer) -> Result<(), String> {
+    let mapped = Arc::new(AtomicBool::new(false));
+    let flag = mapped.clone();
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| flag.store(result.is_ok(), Ordering::Release));
+    device.poll(wgpu::PollType::wait_indefinitely()).map_err(|e| format!("device poll failed: {e:?}"))?;
+    if mapped.load(Ordering::Acquire) {
+        Ok(())
+    } else {
+        Err("buffer readback failed".to_string())
+    }
+}
+
+fn read_stats(device: &wgpu::Device, engine: &SimEngine) -> Result<SimStats, String> {
+    let staging = engine.stats_staging_buffer();
+    map_blocking(device, staging)?;
+    let stats = SimStats::from_readback(bytemuck::cast_slice(&staging.slice(..).get_mapped_range()));
+    staging.unmap();
+    Ok(stats)
+}
+
+fn csv_row(tick: u32, sim_time: f64, stats: &SimStats) -> String {
+    let opt = |v: Option<f32>| v.map_or(String::new(), |v| v.to_string());
+    format!(
+        "{tick},{sim_time},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        stats.population,
+        stats.total_energy,
+        stats.species_count,
+        stats.other_species,
+        stats.max_energy,
+        stats.toxin_deaths,
+        stats.turnover.births,
+        stats.turnover.deaths,
+        opt(stats.mean_mutation_rate()),
+        opt(stats.mean_age()),
+        opt(stats.mean_temperature()),
+        opt(stats.energy.map(|s| s.mean)),
+        opt(stats.pheromone.map(|s| s.mean)),
+        stats.nutrients,
+        stats.waste,
+        stats.walls,
+    )
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let (device, queue, adapter_info) = create_device()?;
+    let cache_dir = args.pipeline_cache.as_deref().map(std::path::Path::new);
+    let cache_data = cache_dir.and_then(|dir| sim_core::pipeline_cache::load(dir, &adapter_info));
+    let pipeline_cache = cache_dir.and_then(|_| sim_core::pipeline_cache::create(&device, cache_data.as_deref()));
+    let grid = match args.max_bricks {
+        Some(max_bricks) => GridConfig::Sparse { grid_size: args.grid_size, max_bricks },
+        None => GridConfig::Dense { grid_size: args.grid_size },
+    };
+    let mut params = types::SimParams::default();
+    for (name, value) in &args.params {
+        params.set_named(name, *value);
+    }
+    let mut engine = SimEngine::builder()
+        .grid(grid)
+        .params(params.clone())
+        .seed(match args.seed {
+            Some(seed) => WorldSeed::Voxels(sim_core::seed::preset_with(
+                args.grid_size,
+                args.preset,
+                &types::SeedConfig::seeded(args.preset, seed),
+            )),
+            None => WorldSeed::Preset(args.preset),
+        })
+        .stats_enabled(false)
+        .pipeline_cache(pipeline_cache.as_ref())
+        .build(&device, &queue)
+        .map_err(|e| e.to_string())?;
+    // Every pipeline is compiled by now
+    if let (Some(dir), Some(cache)) = (cache_dir, &pipeline_cache) {
+        if let Err(e) = sim_core::pipeline_cache::save(dir, &adapter_info, cache) {
+            eprintln!("warning: pipeline cache not saved: {e}");
+        }
+    }
+    if let Some(ref path) = args.load {
+        let bytes = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+        engine.import_snapshot(&queue, &bytes).map_err(|e| format!("{path}: {e}"))?;
+        // Command-line params win over the snapshot's
+        for (name, value) in &args.params {
+            engine.params.set_named(name, *value);
+        }
+    }
+
+    let mut csv: Box<dyn Write> = match args.csv {
+        Some(ref path) => Box::new(std::fs::File::create(path).map_err(|e| format!("{path}: {e}"))?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let io = |e: std::io::Error| format!("writing CSV: {e}");
+    if args.stats_every > 0 {
+        writeln!(csv, "{CSV_HEADER}").map_err(io)?;
+    }
+
+    let mut remaining = args.ticks;
+    while remaining > 0 {
+        // End each submission on a stats tick, so the staging copy is the last
+        let to_stats = match args.stats_every {
+            0 => remaining,
+            every => every - engine.tick_count() % every,
+        };
+        let batch = remaining.min(TICKS_PER_SUBMIT).min(to_stats);
+        let stats_due = args.stats_every > 0 && batch == to_stats;
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("cli_tick_encoder") });
+        for i in 0..batch {
+            engine.set_stats_enabled(stats_due && i + 1 == batch);
+            let commands = engine.scheduled_commands(1);
+            engine.tick(&mut encoder, &queue, &commands);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        remaining -= batch;
+        if stats_due {
+            let stats = read_stats(&device, &engine)?;
+            writeln!(csv, "{}", csv_row(engine.tick_count(), engine.sim_time_seconds(), &stats)).map_err(io)?;
+        }
+    }
+    csv.flush().map_err(io)?;
+
+    if let Some(ref path) = args.save {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("cli_snapshot_encoder") });
+        let readback = engine.export_snapshot(&device, &mut encoder);
+        queue.submit(std::iter::once(encoder.finish()));
+        for buffer in readback.buffers() {
+            map_blocking(&device, buffer)?;
+        }
+        std::fs::write(path, readback.finish()).map_err(|e| format!("{path}: {e}"))?;
+    }
+    eprintln!("ran {} ticks, now at tick {}", args.ticks, engine.tick_count());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_match_the_header() {
+        let columns = CSV_HEADER.split(',').count();
+        let empty = csv_row(0, 0.0, &SimStats::default());
+        assert_eq!(empty, "0,0,0,0,0,0,0,0,0,0,,,,,,0,0,0");
+        assert_eq!(empty.split(',').count(), columns);
+
+        let stats = SimStats {
+            population: 4,
+            total_energy: 400,
+            species_count: 2,
+            max_energy: 150,
+            mutation_rate_sum: 10,
+            age_sum: 6,
+            nutrients: 7,
+            temperature: Some(types::FieldSummary { min: 0.0, max: 1.0, mean: 0.25 }),
+            turnover: types::Turnover { births: 3, deaths: 1 },
+            ..SimStats::default()
+        };
+        let row = csv_row(120, 1.5, &stats);
+        assert_eq!(row, "120,1.5,4,400,2,0,150,0,3,1,2.5,1.5,0.25,,,7,0,0");
+        assert_eq!(row.split(',').count(), columns);
+    }
+}
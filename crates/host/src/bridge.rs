@@ -1,12 +1,11 @@
 use wasm_bindgen::prelude::*;
 use std::cell::RefCell;
-use glam::Vec4;
 use js_sys;
 
 use crate::App;
 
 thread_local! {
-    pub static APP: RefCell<Option<App>> = RefCell::new(None);
+    pub static APP: RefCell<Option<App>> = const { RefCell::new(None) };
 }
 
 #[wasm_bindgen]
@@ -21,6 +20,60 @@ pub enum Tool {
     Remove = 6,
     HeatSource = 7,
     ColdSource = 8,
+    SphereShell = 9,
+    Cylinder = 10,
+    Plane = 11,
+}
+
+impl Tool {
+    fn from_u32(id: u32) -> Self {
+        match id {
+            1 => Tool::Wall,
+            2 => Tool::EnergySource,
+            3 => Tool::Nutrient,
+            4 => Tool::Seed,
+            5 => Tool::Toxin,
+            6 => Tool::Remove,
+            7 => Tool::HeatSource,
+            8 => Tool::ColdSource,
+            9 => Tool::SphereShell,
+            10 => Tool::Cylinder,
+            11 => Tool::Plane,
+            _ => Tool::None,
+        }
+    }
+
+    /// Shape this tool builds, for the shape tools.
+    pub fn shape_kind(self) -> Option<types::ShapeKind> {
+        match self {
+            Tool::SphereShell => Some(types::ShapeKind::SphereShell),
+            Tool::Cylinder => Some(types::ShapeKind::Cylinder),
+            Tool::Plane => Some(types::ShapeKind::Plane),
+            _ => None,
+        }
+    }
+
+    /// Command this tool issues at grid cell (x, y, z), if any. `thermal` is
+    /// the packed strength/lifetime given to heat and cold sources.
+    pub fn command(self, x: u32, y: u32, z: u32, radius: u32, thermal: u32) -> Option<types::Command> {
+        use types::CommandType::*;
+        let (kind, param) = match self {
+            Tool::Wall => (PlaceVoxel, 1),
+            Tool::EnergySource => (PlaceVoxel, 3),
+            Tool::Nutrient => (PlaceVoxel, 2),
+            Tool::Seed => (SeedProtocells, 500),
+            Tool::Toxin => (ApplyToxin, 128),
+            Tool::Remove => (RemoveVoxel, 0),
+            Tool::HeatSource => (PlaceVoxel, 6),
+            Tool::ColdSource => (PlaceVoxel, 7),
+            Tool::None | Tool::SphereShell | Tool::Cylinder | Tool::Plane => return None,
+        };
+        let param_1 = match self {
+            Tool::HeatSource | Tool::ColdSource => thermal,
+            _ => 0,
+        };
+        Some(types::Command::new(kind, x, y, z, radius, param, param_1))
+    }
 }
 
 #[wasm_bindgen]
@@ -65,12 +118,31 @@ pub fn on_key_down(key: String) {
                 "6" => app.current_tool = Tool::Remove,
                 "7" => app.current_tool = Tool::HeatSource,
                 "8" => app.current_tool = Tool::ColdSource,
-                "t" | "T" => app.overlay_mode = (app.overlay_mode + 1) % 4,
+                "9" => app.current_tool = Tool::SphereShell,
+                "0" => app.current_tool = Tool::Cylinder,
+                "-" => app.current_tool = Tool::Plane,
+                "t" | "T" => app.overlay_mode = (app.overlay_mode + 1) % types::OVERLAY_MODES,
                 "Escape" => app.current_tool = Tool::None,
                 _ => {}
             }
         }
     });
+    crate::fallback::with(|f| match key.as_str() {
+        "p" | "P" => f.timing.toggle_pause(),
+        "n" | "N" => f.timing.request_single_step(),
+        "t" | "T" => {
+            f.overlay_mode = (f.overlay_mode + 1) % types::OVERLAY_MODES;
+            f.mark_dirty();
+        }
+        "Escape" => f.current_tool = Tool::None,
+        "0" => f.current_tool = Tool::Cylinder,
+        "-" => f.current_tool = Tool::Plane,
+        k => {
+            if let Ok(id @ 1..=9) = k.parse::<u32>() {
+                f.current_tool = Tool::from_u32(id);
+            }
+        }
+    });
 }
 
 #[wasm_bindgen]
@@ -80,6 +152,7 @@ pub fn set_paused(paused: bool) {
             app.timing.set_paused(paused);
         }
     });
+    crate::fallback::with(|f| f.timing.set_paused(paused));
 }
 
 #[wasm_bindgen]
@@ -89,6 +162,36 @@ pub fn single_step() {
             app.timing.request_single_step();
         }
     });
+    crate::fallback::with(|f| f.timing.request_single_step());
+}
+
+/// Advance exactly `n` ticks (independent of pause state and tick rate).
+/// The promise resolves with the tick count once the last tick has been
+/// submitted and its stats read back. Multiple requests run in order.
+#[wasm_bindgen]
+pub fn step_ticks(n: u32) -> js_sys::Promise {
+    let mut resolve = None;
+    let promise = js_sys::Promise::new(&mut |res, _rej| resolve = Some(res));
+    let Some(resolve) = resolve else {
+        return promise;
+    };
+
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            if n == 0 {
+                let _ = resolve.call1(&JsValue::UNDEFINED, &JsValue::from(app.sim_engine.tick_count()));
+            } else {
+                app.step_requests.push_back(crate::StepRequest {
+                    remaining: n,
+                    awaiting_stats: false,
+                    resolve,
+                });
+            }
+        } else {
+            let _ = resolve.call1(&JsValue::UNDEFINED, &JsValue::NULL);
+        }
+    });
+    promise
 }
 
 #[wasm_bindgen]
@@ -98,247 +201,2686 @@ pub fn set_tick_rate(rate: f32) {
             app.timing.set_tick_rate(rate);
         }
     });
+    crate::fallback::with(|f| f.timing.set_tick_rate(rate));
 }
 
+/// 0 = real-time priority (drop ticks past the catch-up cap),
+/// 1 = sim-time priority (carry the backlog into later frames).
 #[wasm_bindgen]
-pub fn set_tool(tool_id: u32) {
+pub fn set_timing_mode(mode: u32) {
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            app.current_tool = match tool_id {
-                0 => Tool::None,
-                1 => Tool::Wall,
-                2 => Tool::EnergySource,
-                3 => Tool::Nutrient,
-                4 => Tool::Seed,
-                5 => Tool::Toxin,
-                6 => Tool::Remove,
-                7 => Tool::HeatSource,
-                8 => Tool::ColdSource,
-                _ => Tool::None,
-            };
+            app.timing.set_mode(crate::timing::TimingMode::from_u32(mode));
         }
     });
 }
 
+/// Maximum ticks run per frame. 0 = unlimited.
 #[wasm_bindgen]
-pub fn set_overlay_mode(mode: u32) {
+pub fn set_max_catch_up(max_ticks: u32) {
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            app.overlay_mode = mode;
+            app.timing.set_max_catch_up(max_ticks);
         }
     });
 }
 
+/// Called by JS on `visibilitychange`. Returns true if the caller should keep
+/// driving `frame()` from a timer while hidden (headless background mode).
 #[wasm_bindgen]
-pub fn set_brush_radius(radius: u32) {
+pub fn on_visibility_change(hidden: bool) -> bool {
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            app.brush_radius = radius.min(5);
+            app.timing.set_hidden(hidden)
+        } else {
+            crate::fallback::with(|f| f.timing.set_hidden(hidden)).unwrap_or(false)
+        }
+    })
+}
+
+/// 0 = pause while hidden, 1 = keep simulating headless at a reduced rate.
+#[wasm_bindgen]
+pub fn set_background_mode(mode: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.timing.set_background_mode(crate::timing::BackgroundMode::from_u32(mode));
         }
     });
 }
 
 #[wasm_bindgen]
-pub fn request_pick(canvas_x: f32, canvas_y: f32, canvas_w: f32, canvas_h: f32) {
+pub fn set_background_tick_rate(rate: f32) {
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            let nx = canvas_x / canvas_w;
-            let ny = canvas_y / canvas_h;
-            let gs = app.sim_engine.grid_size();
-            if let Some((x, y, z)) = ray_cast_grid(&app.camera, nx, ny, gs) {
-                app.pick_coords = Some((x, y, z));
-                app.pick_requested = true;
-                app.latest_pick = None;
-            }
+            app.timing.set_background_tick_rate(rate);
         }
     });
 }
 
+/// Redraw only when the volume changed or the camera moved (default on).
 #[wasm_bindgen]
-pub fn get_pick_result() -> JsValue {
+pub fn set_frame_pacing(enabled: bool) {
     APP.with(|app| {
-        let borrow = app.borrow();
-        if let Some(ref app) = *borrow {
-            if let Some(ref pick) = app.latest_pick {
-                let obj = js_sys::Object::new();
-                let _ = js_sys::Reflect::set(&obj, &"x".into(), &JsValue::from(pick.x));
-                let _ = js_sys::Reflect::set(&obj, &"y".into(), &JsValue::from(pick.y));
-                let _ = js_sys::Reflect::set(&obj, &"z".into(), &JsValue::from(pick.z));
-                let _ = js_sys::Reflect::set(&obj, &"voxel_type".into(), &JsValue::from(pick.voxel_type));
-                let _ = js_sys::Reflect::set(&obj, &"energy".into(), &JsValue::from(pick.energy));
-                let _ = js_sys::Reflect::set(&obj, &"age".into(), &JsValue::from(pick.age));
-                let _ = js_sys::Reflect::set(&obj, &"species_id".into(), &JsValue::from(pick.species_id));
-                let genome = js_sys::Array::new();
-                for b in &pick.genome {
-                    genome.push(&JsValue::from(*b));
-                }
-                let _ = js_sys::Reflect::set(&obj, &"genome".into(), &genome);
-                return obj.into();
-            }
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.frame_pacing = enabled;
         }
-        JsValue::NULL
-    })
+    });
 }
 
+/// Submit each frame's render ahead of its simulation ticks, so the GPU
+/// ticks while the render is in flight (default off). The picture lags the
+/// simulation by one frame.
 #[wasm_bindgen]
-pub fn get_stats() -> JsValue {
+pub fn set_async_overlap(enabled: bool) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.async_overlap = enabled;
+        }
+    });
+}
+
+/// Draw at most once every `skip + 1` animation frames (e.g. 1 on a 120 Hz
+/// display for 60 FPS rendering). Simulation ticks are unaffected.
+#[wasm_bindgen]
+pub fn set_frame_skip(skip: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.frame_skip = skip.min(8);
+        }
+    });
+}
+
+/// Target frame time used to decide whether auxiliary passes fit.
+/// 0 disables budgeting (stats and render-texture refresh always run).
+#[wasm_bindgen]
+pub fn set_frame_budget_ms(ms: f32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.budget.set_target_frame_ms(ms);
+        }
+    });
+}
+
+/// Ticks between stats readbacks (default 10), clamped to
+/// 1..=MAX_STATS_INTERVAL; returns the interval set. Births, deaths and the
+/// other per-window counters cover the whole interval. The CPU fallback
+/// measures every frame that ticks.
+#[wasm_bindgen]
+pub fn set_stats_interval(ticks: u32) -> u32 {
+    let ticks = ticks.clamp(1, crate::MAX_STATS_INTERVAL);
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.stats_interval = ticks;
+        }
+    });
+    ticks
+}
+
+/// Ticks per queue submission when many ticks run in one frame; 0 = adaptive.
+#[wasm_bindgen]
+pub fn set_submit_chunk_ticks(ticks: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.budget.set_chunk_ticks(ticks);
+        }
+    });
+}
+
+#[wasm_bindgen]
+pub fn get_timing_info() -> JsValue {
     APP.with(|app| {
         let borrow = app.borrow();
         if let Some(ref app) = *borrow {
-            if let Some(ref stats) = app.latest_stats {
-                let obj = js_sys::Object::new();
-                let _ = js_sys::Reflect::set(&obj, &"population".into(), &JsValue::from(stats.population));
-                let _ = js_sys::Reflect::set(&obj, &"total_energy".into(), &JsValue::from(stats.total_energy));
-                let _ = js_sys::Reflect::set(&obj, &"species_count".into(), &JsValue::from(stats.species_count));
-                let _ = js_sys::Reflect::set(&obj, &"max_energy".into(), &JsValue::from(stats.max_energy));
-                let species = js_sys::Array::new();
-                for (sid, count) in &stats.species_histogram {
-                    let entry = js_sys::Array::new();
-                    entry.push(&JsValue::from(*sid));
-                    entry.push(&JsValue::from(*count));
-                    species.push(&entry);
-                }
-                let _ = js_sys::Reflect::set(&obj, &"species".into(), &species);
-                return obj.into();
+            let t = &app.timing;
+            let obj = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&obj, &"tick_rate".into(), &JsValue::from(t.tick_rate));
+            let _ = js_sys::Reflect::set(&obj, &"mode".into(), &JsValue::from(t.clock.mode as u32));
+            let _ = js_sys::Reflect::set(&obj, &"max_catch_up".into(), &JsValue::from(t.clock.max_catch_up));
+            let _ = js_sys::Reflect::set(&obj, &"dropped_ticks".into(), &JsValue::from(t.clock.dropped_ticks as f64));
+            let _ = js_sys::Reflect::set(&obj, &"backlog_ticks".into(), &JsValue::from(t.backlog_ticks()));
+            let _ = js_sys::Reflect::set(&obj, &"avg_frame_ms".into(), &JsValue::from(app.budget.aux.avg_frame_ms));
+            let costs = js_sys::Object::new();
+            for pass in types::AuxPass::ALL {
+                let _ = js_sys::Reflect::set(&costs, &pass.name().into(), &JsValue::from(app.budget.aux.cost_ms(pass)));
             }
+            let _ = js_sys::Reflect::set(&obj, &"aux_cost_ms".into(), &costs);
+            let _ = js_sys::Reflect::set(&obj, &"over_budget".into(), &JsValue::from(app.budget.is_over_budget()));
+            let _ = js_sys::Reflect::set(&obj, &"tick_ms_estimate".into(), &JsValue::from(app.budget.tick_ms_estimate));
+            let _ = js_sys::Reflect::set(&obj, &"submit_chunk_ticks".into(), &JsValue::from(app.budget.submit_chunk_ticks()));
+            return obj.into();
         }
         JsValue::NULL
     })
 }
 
 #[wasm_bindgen]
-pub fn load_preset(preset_id: u32) {
+pub fn set_tool(tool_id: u32) {
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            app.sim_engine.reset_tick_count();
-            app.sim_engine.initialize_grid_with_preset(&app.gpu.queue, preset_id);
-            app.latest_stats = None;
-            app.stats_tick_counter = 0;
-            app.stats_state = crate::ReadbackState::Idle;
+            app.current_tool = Tool::from_u32(tool_id);
         }
     });
+    crate::fallback::with(|f| f.current_tool = Tool::from_u32(tool_id));
 }
 
 #[wasm_bindgen]
-pub fn run_benchmark() -> u32 {
+pub fn set_overlay_mode(mode: u32) {
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            let count = app.sim_engine.seed_benchmark(&app.gpu.queue);
-            app.latest_stats = None;
-            app.stats_tick_counter = 0;
-            app.stats_state = crate::ReadbackState::Idle;
-            count
-        } else {
-            0
+            app.overlay_mode = mode;
         }
-    })
+    });
+    crate::fallback::with(|f| {
+        f.overlay_mode = mode;
+        f.mark_dirty();
+    });
 }
 
+/// Genome byte (0–15, see `Genome::named_traits`) the trait overlay, mode 4,
+/// colors protocells by; e.g. 7 for predation capability.
 #[wasm_bindgen]
-pub fn get_grid_size() -> u32 {
+pub fn set_overlay_trait(byte: u32) {
+    let byte = byte.min(15);
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.overlay_trait = byte;
+            app.texture_stale = true;
+        }
+    });
+    crate::fallback::with(|f| {
+        f.overlay_trait = byte;
+        f.mark_dirty();
+    });
+}
+
+/// Stretch the temperature overlay over the live temperature range (the
+/// default) or, when disabled, over a fixed 0–1.
+#[wasm_bindgen]
+pub fn set_overlay_auto_scale(enabled: bool) {
     APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let style = types::RenderStyle { fixed_temp_range: !enabled, ..app.renderer.style() };
+            app.renderer.set_style(&app.gpu.device, &app.gpu.queue, style);
+            app.texture_stale = true;
+        }
+    });
+    crate::fallback::with(|f| {
+        f.style.fixed_temp_range = !enabled;
+        f.mark_dirty();
+    });
+}
+
+/// Color scale of the current overlay as data: `{mode, name, min, max,
+/// auto_scale, stops: [{value, color: [r, g, b, a]}]}`, with `min`/`max`
+/// the live temperature range (from the last GPU reduction), 0..max_energy
+/// or 0..1 for population. `null` with no overlay. The species overlay has
+/// no stops; see `get_species_colors`.
+#[wasm_bindgen]
+pub fn get_overlay_legend() -> JsValue {
+    let legend = APP.with(|app| {
         let borrow = app.borrow();
-        if let Some(ref app) = *borrow {
-            app.sim_engine.grid_size()
-        } else {
-            0
+        let app = borrow.as_ref()?;
+        let style = app.renderer.style();
+        Some(legend_inputs(app.overlay_mode, style, app.latest_temp_range, app.sim_engine.params.max_energy))
+    });
+    let legend = legend.or_else(|| {
+        crate::fallback::with(|f| legend_inputs(f.overlay_mode, f.style, f.latest_temp_range, f.sim.params.max_energy))
+    });
+    let Some((Some(legend), auto_scale)) = legend else {
+        return JsValue::NULL;
+    };
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"mode".into(), &JsValue::from(legend.mode));
+    let _ = js_sys::Reflect::set(&obj, &"name".into(), &JsValue::from(legend.name));
+    let _ = js_sys::Reflect::set(&obj, &"min".into(), &JsValue::from(legend.min));
+    let _ = js_sys::Reflect::set(&obj, &"max".into(), &JsValue::from(legend.max));
+    let _ = js_sys::Reflect::set(&obj, &"auto_scale".into(), &JsValue::from(auto_scale));
+    let stops = js_sys::Array::new();
+    for stop in &legend.stops {
+        let entry = js_sys::Object::new();
+        let color = js_sys::Array::new();
+        for c in stop.color {
+            color.push(&JsValue::from(c));
+        }
+        let _ = js_sys::Reflect::set(&entry, &"value".into(), &JsValue::from(stop.value));
+        let _ = js_sys::Reflect::set(&entry, &"color".into(), &color);
+        stops.push(&entry);
+    }
+    let _ = js_sys::Reflect::set(&obj, &"stops".into(), &stops);
+    obj.into()
+}
+
+/// Species overlay (mode 5) colors of the species in the latest stats
+/// histogram, most populous first: `[{species_id, count, color: [r, g, b,
+/// a]}]` in the current palette. Each species keeps its color for the run.
+#[wasm_bindgen]
+pub fn get_species_colors() -> JsValue {
+    let inputs = APP.with(|app| {
+        let borrow = app.borrow();
+        let app = borrow.as_ref()?;
+        Some((app.latest_stats.as_ref().map(|s| s.species_histogram.clone()), app.renderer.style().palette))
+    });
+    let inputs =
+        inputs.or_else(|| crate::fallback::with(|f| (f.latest_stats.as_ref().map(|s| s.species_histogram.clone()), f.style.palette)));
+    let Some((histogram, palette)) = inputs else {
+        return JsValue::NULL;
+    };
+    let out = js_sys::Array::new();
+    for (sid, count) in histogram.unwrap_or_default() {
+        let entry = js_sys::Object::new();
+        let color = js_sys::Array::new();
+        for c in types::species_color(sid, palette) {
+            color.push(&JsValue::from(c));
+        }
+        let _ = js_sys::Reflect::set(&entry, &"species_id".into(), &JsValue::from(sid));
+        let _ = js_sys::Reflect::set(&entry, &"count".into(), &JsValue::from(count));
+        let _ = js_sys::Reflect::set(&entry, &"color".into(), &color);
+        out.push(&entry);
+    }
+    out.into()
+}
+
+/// Legend for `overlay` and whether the temperature ramp is auto-scaled.
+fn legend_inputs(
+    overlay: u32,
+    style: types::RenderStyle,
+    live: Option<types::TempRange>,
+    max_energy: f32,
+) -> (Option<types::OverlayLegend>, bool) {
+    let auto_scale = !style.fixed_temp_range;
+    let scale = live.filter(|_| auto_scale).unwrap_or(types::TempRange::UNIT);
+    (types::overlay_legend(overlay, style.palette, scale, live, max_energy), auto_scale)
+}
+
+/// Show or hide the top-down minimap in the bottom-right corner.
+#[wasm_bindgen]
+pub fn set_minimap_enabled(enabled: bool) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.renderer.set_minimap_enabled(enabled);
+            app.last_drawn_camera = None;
         }
+    });
+}
+
+/// Whether (canvas_x, canvas_y) lands on the minimap, so the page can
+/// skip other click handling there.
+#[wasm_bindgen]
+pub fn is_over_minimap(canvas_x: f32, canvas_y: f32, canvas_w: f32, canvas_h: f32) -> bool {
+    APP.with(|app| {
+        app.borrow()
+            .as_ref()
+            .is_some_and(|app| app.renderer.minimap_hit(canvas_x / canvas_w, canvas_y / canvas_h).is_some())
     })
 }
 
+/// Show the population/species history graph (plotted on the GPU) in the
+/// bottom-left corner. No-op in CPU mode, where the page chart is used.
 #[wasm_bindgen]
-pub fn set_param(name: &str, value: f32) {
+pub fn set_graph_enabled(enabled: bool) {
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            match name {
-                "dt" => app.sim_engine.params.dt = value,
-                "nutrient_spawn_rate" => app.sim_engine.params.nutrient_spawn_rate = value,
-                "waste_decay_ticks" => app.sim_engine.params.waste_decay_ticks = value,
-                "nutrient_recycle_rate" => app.sim_engine.params.nutrient_recycle_rate = value,
-                "movement_energy_cost" => app.sim_engine.params.movement_energy_cost = value,
-                "base_ambient_temp" => app.sim_engine.params.base_ambient_temp = value,
-                "metabolic_cost_base" => app.sim_engine.params.metabolic_cost_base = value,
-                "replication_energy_min" => app.sim_engine.params.replication_energy_min = value,
-                "energy_from_nutrient" => app.sim_engine.params.energy_from_nutrient = value,
-                "energy_from_source" => app.sim_engine.params.energy_from_source = value,
-                "diffusion_rate" => app.sim_engine.params.diffusion_rate = value,
-                "temp_sensitivity" => app.sim_engine.params.temp_sensitivity = value,
-                "predation_energy_fraction" => app.sim_engine.params.predation_energy_fraction = value,
-                "max_energy" => app.sim_engine.params.max_energy = value,
-                _ => {}
-            }
+            app.renderer.set_graph_enabled(enabled);
+            app.last_drawn_camera = None;
         }
     });
 }
 
+/// Debug overlay of the sparse brick allocator: a wireframe per allocated
+/// brick, colored by its visible cells (1) or age (2); 0 turns it off.
+/// Draws nothing in dense mode. Returns false for an unknown mode.
 #[wasm_bindgen]
-pub fn on_mouse_down(canvas_x: f32, canvas_y: f32, canvas_w: f32, canvas_h: f32) {
+pub fn set_brick_overlay(mode: u32) -> bool {
+    let Some(mode) = types::BrickOverlay::from_u32(mode) else {
+        return false;
+    };
     APP.with(|app| {
         if let Some(ref mut app) = *app.borrow_mut() {
-            if app.current_tool == Tool::None {
-                return;
-            }
+            app.renderer.set_brick_overlay(mode);
+            app.last_drawn_camera = None;
+        }
+    });
+    true
+}
 
-            let nx = canvas_x / canvas_w;
-            let ny = canvas_y / canvas_h;
-            let gs = app.sim_engine.grid_size();
+/// Start recording frames into memory, replacing any recording in progress.
+/// `fps` is output frames per simulated second (clamped to 1..=60) and
+/// `scale` the capture size relative to the canvas (0.1..=1.0). Frames are
+/// captured from an offscreen target without the HUD overlays. Returns false
+/// before init.
+#[wasm_bindgen]
+pub fn start_recording(fps: f32, scale: f32) -> bool {
+    let config = types::RecordingConfig::new(fps, scale);
+    let started = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let Some(ref mut app) = *borrow else {
+            return false;
+        };
+        crate::cancel_record_readback(app);
+        app.renderer.set_capture_scale(&app.gpu.device, Some(config.scale));
+        let Some((w, h)) = app.renderer.capture_target().map(|c| c.size()) else {
+            return false;
+        };
+        app.recording = Some(crate::recording::Recording::new(config, app.sim_engine.sim_time_seconds(), w, h));
+        app.last_drawn_camera = None; // draw the first frame even if paused
+        true
+    });
+    started
+        || crate::fallback::with(|f| {
+            let gs = f.sim.grid_size();
+            f.recording = Some(crate::recording::Recording::new(config, f.sim.sim_time_seconds(), gs, gs));
+        })
+        .is_some()
+}
 
-            if let Some((x, y, z)) = ray_cast_grid(&app.camera, nx, ny, gs) {
-                let cmd = match app.current_tool {
-                    Tool::Wall => types::Command::new(
-                        types::CommandType::PlaceVoxel, x, y, z, app.brush_radius, 1, 0,
-                    ),
-                    Tool::EnergySource => types::Command::new(
-                        types::CommandType::PlaceVoxel, x, y, z, app.brush_radius, 3, 0,
-                    ),
-                    Tool::Nutrient => types::Command::new(
-                        types::CommandType::PlaceVoxel, x, y, z, app.brush_radius, 2, 0,
-                    ),
-                    Tool::Seed => types::Command::new(
-                        types::CommandType::SeedProtocells, x, y, z, app.brush_radius, 500, 0,
-                    ),
-                    Tool::Toxin => types::Command::new(
-                        types::CommandType::ApplyToxin, x, y, z, app.brush_radius, 128, 0,
-                    ),
-                    Tool::Remove => types::Command::new(
-                        types::CommandType::RemoveVoxel, x, y, z, app.brush_radius, 0, 0,
-                    ),
-                    Tool::HeatSource => types::Command::new(
-                        types::CommandType::PlaceVoxel, x, y, z, app.brush_radius, 6, 0,
-                    ),
-                    Tool::ColdSource => types::Command::new(
-                        types::CommandType::PlaceVoxel, x, y, z, app.brush_radius, 7, 0,
-                    ),
-                    Tool::None => return,
-                };
-                app.pending_commands.push(cmd);
-            }
+/// Stop recording and return `{fps, width, height, captures, bytes,
+/// truncated, frames: [{sim_time, repeat, data}]}`. Each capture is shown
+/// for `repeat` output frames; `data` is RGBA8 for `ImageData`. Null when
+/// nothing was recording.
+#[wasm_bindgen]
+pub fn stop_recording() -> JsValue {
+    let rec = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        crate::cancel_record_readback(app);
+        app.renderer.set_capture_scale(&app.gpu.device, None);
+        app.recording.take()
+    });
+    let rec = rec.or_else(|| crate::fallback::with(|f| f.recording.take()).flatten());
+    rec.map_or(JsValue::NULL, |r| r.to_js())
+}
+
+/// Progress of the current recording: `{fps, width, height, frames,
+/// captures, bytes, truncated}`, or null when not recording.
+#[wasm_bindgen]
+pub fn get_recording_info() -> JsValue {
+    APP.with(|app| app.borrow().as_ref().map(|app| app.recording.as_ref().map(|r| r.info_js())))
+        .or_else(|| crate::fallback::with(|f| f.recording.as_ref().map(|r| r.info_js())))
+        .flatten()
+        .unwrap_or(JsValue::NULL)
+}
+
+/// Select the material palette: 0 = default, 1 = deuteranopia-safe,
+/// 2 = protanopia-safe. Returns false for an unknown palette.
+#[wasm_bindgen]
+pub fn set_palette(palette: u32) -> bool {
+    let Some(palette) = types::Palette::from_u32(palette) else {
+        return false;
+    };
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let style = types::RenderStyle { palette, ..app.renderer.style() };
+            app.renderer.set_style(&app.gpu.device, &app.gpu.queue, style);
+            app.texture_stale = true;
         }
     });
+    crate::fallback::with(|f| {
+        f.style.palette = palette;
+        f.mark_dirty();
+    });
+    true
 }
 
-/// CPU ray cast: intersect screen point with grid AABB, return nearest grid cell.
-fn ray_cast_grid(camera: &renderer::camera::Camera, nx: f32, ny: f32, grid_size: u32) -> Option<(u32, u32, u32)> {
-    let inv_vp = camera.view_projection_inverse();
-    let gs = grid_size as f32;
+/// Ease the volume toward each new frame instead of snapping, and soften
+/// bright emissive highlights.
+#[wasm_bindgen]
+pub fn set_reduced_flicker(enabled: bool) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let style = types::RenderStyle { reduced_flicker: enabled, ..app.renderer.style() };
+            app.renderer.set_style(&app.gpu.device, &app.gpu.queue, style);
+            app.texture_stale = true;
+        }
+    });
+    crate::fallback::with(|f| {
+        f.style.reduced_flicker = enabled;
+        f.mark_dirty();
+    });
+}
 
-    // Unproject near and far plane points from NDC
-    let ndc_near = Vec4::new(nx * 2.0 - 1.0, 1.0 - ny * 2.0, -1.0, 1.0);
-    let ndc_far = Vec4::new(nx * 2.0 - 1.0, 1.0 - ny * 2.0, 1.0, 1.0);
+/// Map the volume through color/opacity ramps instead of the built-in
+/// colors. `json` maps material names (`types::TRANSFER_MATERIAL_NAMES`,
+/// e.g. "protocell" or the "temperature" overlay) to ramps of
+/// `[at, r, g, b, opacity]` or `{at, color: [r, g, b], opacity}` stops,
+/// all in 0..1; unnamed materials keep default ramps. An empty string or
+/// `null` restores the built-in colors.
+#[wasm_bindgen]
+pub fn set_transfer_function(json: &str) -> Result<(), JsValue> {
+    let transfer = if json.trim().is_empty() { None } else { parse_transfer_function(json)? };
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            if app.renderer.transfer_function().is_some() != transfer.is_some() {
+                crate::cancel_slice_export(app, "render texture layout changed");
+            }
+            app.renderer.set_transfer_function(&app.gpu.device, &app.gpu.queue, transfer.clone());
+            app.texture_stale = true;
+        }
+    });
+    crate::fallback::with(|f| {
+        f.transfer = transfer;
+        f.mark_dirty();
+    });
+    Ok(())
+}
 
-    let w_near = inv_vp * ndc_near;
-    if w_near.w.abs() < 1e-6 {
-        return None;
+fn parse_transfer_function(json: &str) -> Result<Option<types::TransferFunction>, JsValue> {
+    let spec = js_sys::JSON::parse(json)?;
+    if spec.is_null() {
+        return Ok(None);
     }
-    let origin = w_near.truncate() / w_near.w;
-
-    let w_far = inv_vp * ndc_far;
-    if w_far.w.abs() < 1e-6 {
-        return None;
+    if !spec.is_object() || js_sys::Array::is_array(&spec) {
+        return Err(JsValue::from_str("expected a JSON object of material ramps"));
+    }
+    let mut tf = types::TransferFunction::default();
+    for entry in js_sys::Object::entries(&js_sys::Object::from(spec)).iter() {
+        let entry = js_sys::Array::from(&entry);
+        let name = entry.get(0).as_string().unwrap_or_default();
+        let material = types::TransferFunction::material_index(&name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown material '{name}'")))?;
+        let ramp = entry.get(1);
+        if !js_sys::Array::is_array(&ramp) {
+            return Err(JsValue::from_str(&format!("{name}: expected an array of points")));
+        }
+        let number = |v: JsValue| v.as_f64().unwrap_or(f64::NAN);
+        let mut points = Vec::new();
+        for (n, point) in js_sys::Array::from(&ramp).iter().enumerate() {
+            let values: Vec<f64> = if js_sys::Array::is_array(&point) {
+                js_sys::Array::from(&point).iter().map(number).collect()
+            } else if point.is_object() {
+                let get = |field: &str| js_sys::Reflect::get(&point, &field.into()).unwrap_or(JsValue::UNDEFINED);
+                let color = get("color");
+                if !js_sys::Array::is_array(&color) {
+                    return Err(JsValue::from_str(&format!("{name} point {n}: color must be [r, g, b]")));
+                }
+                let mut values = vec![number(get("at"))];
+                values.extend(js_sys::Array::from(&color).iter().map(number));
+                values.push(number(get("opacity")));
+                values
+            } else {
+                return Err(JsValue::from_str(&format!("{name} point {n}: expected an array or object")));
+            };
+            points.push(types::TransferPoint::from_values(&name, n, &values).map_err(|e| JsValue::from_str(&e))?);
+        }
+        tf.set_ramp(material, points).map_err(|e| JsValue::from_str(&e))?;
     }
-    let far_pt = w_far.truncate() / w_far.w;
+    Ok(Some(tf))
+}
+
+#[wasm_bindgen]
+pub fn set_brush_radius(radius: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.brush_radius = radius.min(5);
+        }
+    });
+    crate::fallback::with(|f| f.brush_radius = radius.min(5));
+}
+
+/// Repeat tool edits at their symmetric images: bit 0/1/2 mirror across
+/// the X/Y/Z center plane, bit 3 adds quarter turns about the Y axis
+/// (`types::EditSymmetry`). 0 turns symmetry off.
+#[wasm_bindgen]
+pub fn set_edit_symmetry(bits: u32) {
+    let symmetry = types::EditSymmetry::from_bits(bits);
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.edit_symmetry = symmetry;
+        }
+    });
+    crate::fallback::with(|f| f.edit_symmetry = symmetry);
+}
+
+/// Size and material of the shape tools: sphere shell and cylinder
+/// `radius`, wall `thickness` (at least 1), cylinder `length`, cylinder and
+/// plane `axis` (0 = x, 1 = y, 2 = z) and the `voxel_type` they are built
+/// from.
+#[wasm_bindgen]
+pub fn set_shape_params(radius: u32, thickness: u32, length: u32, axis: u32, voxel_type: u32) {
+    let params = types::ShapeParams {
+        radius: radius.min(types::MAX_SHAPE_RADIUS),
+        thickness: thickness.max(1),
+        length: length.clamp(1, 1024),
+        axis: axis.min(2) as usize,
+        voxel_type: voxel_type.min(7),
+    };
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.shape_params = params;
+        }
+    });
+    crate::fallback::with(|f| f.shape_params = params);
+}
+
+/// Build a shape (0 = sphere shell, 1 = cylinder, 2 = plane) anchored at
+/// (x, y, z) with the current shape params. Its boxes go out over the
+/// following ticks, `MAX_COMMANDS_PER_TICK` per tick, while the sim runs.
+/// Returns the boxes queued.
+#[wasm_bindgen]
+pub fn place_shape(kind: u32, x: u32, y: u32, z: u32) -> u32 {
+    let Some(kind) = types::ShapeKind::from_u32(kind) else {
+        return 0;
+    };
+    let queued = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        let gs = app.sim_engine.grid_size();
+        let cmds = app.shape_params.shape(kind, (x, y, z)).fill_commands(app.shape_params.voxel_type, gs);
+        let cmds = symmetric(app.edit_symmetry, &cmds, gs);
+        let n = cmds.len() as u32;
+        app.queued_commands.extend(cmds);
+        Some(n)
+    });
+    queued
+        .or_else(|| {
+            crate::fallback::with(|f| {
+                let gs = f.sim.grid_size();
+                let cmds = f.shape_params.shape(kind, (x, y, z)).fill_commands(f.shape_params.voxel_type, gs);
+                let cmds = symmetric(f.edit_symmetry, &cmds, gs);
+                let n = cmds.len() as u32;
+                f.queued_commands.extend(cmds);
+                n
+            })
+        })
+        .unwrap_or(0)
+}
+
+/// Freeze (`frozen`) or thaw the box between two corners, clamped to the
+/// grid: frozen voxels, empty ones included, are left as they are by every
+/// tick pass until thawed. Goes out with the edit symmetry on the next tick.
+/// Returns the boxes queued.
+#[wasm_bindgen]
+pub fn freeze_region(x0: u32, y0: u32, z0: u32, x1: u32, y1: u32, z1: u32, frozen: bool) -> u32 {
+    let command = |gs: u32| {
+        let last = gs.saturating_sub(1);
+        let min = (x0.min(x1).min(last), y0.min(y1).min(last), z0.min(z1).min(last));
+        let max = (x0.max(x1).min(last), y0.max(y1).min(last), z0.max(z1).min(last));
+        types::Command::freeze_region(min, max, frozen)
+    };
+    let queued = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        let gs = app.sim_engine.grid_size();
+        let cmds = app.edit_symmetry.apply(&command(gs), gs);
+        let n = cmds.len() as u32;
+        app.queued_commands.extend(cmds);
+        Some(n)
+    });
+    queued
+        .or_else(|| {
+            crate::fallback::with(|f| {
+                let gs = f.sim.grid_size();
+                let cmds = f.edit_symmetry.apply(&command(gs), gs);
+                let n = cmds.len() as u32;
+                f.queued_commands.extend(cmds);
+                n
+            })
+        })
+        .unwrap_or(0)
+}
+
+/// Sparse simulation LOD: bricks more than `near_radius` voxels from the
+/// camera focus tick every `far_interval` ticks; an interval of 0 or 1
+/// ticks every brick every tick. Returns the bricks now ticking at the
+/// reduced rate (0 on dense and CPU engines).
+#[wasm_bindgen]
+pub fn set_sim_lod(near_radius: f32, far_interval: u32) -> u32 {
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let Some(app) = borrow.as_mut() else { return 0 };
+        let lod = types::SimLod::new(near_radius, far_interval);
+        app.sim_engine.set_lod(lod);
+        app.sim_engine.set_lod_focus(app.camera.target.to_array());
+        app.sim_engine.far_brick_count()
+    })
+}
+
+/// `cmds`, each followed by its images under `symmetry`.
+fn symmetric(symmetry: types::EditSymmetry, cmds: &[types::Command], grid_size: u32) -> Vec<types::Command> {
+    cmds.iter().flat_map(|cmd| symmetry.apply(cmd, grid_size)).collect()
+}
+
+/// Strength (0..=1) and lifetime in ticks (0 = permanent) for heat/cold
+/// sources placed with the tools.
+#[wasm_bindgen]
+pub fn set_thermal_source(strength: f32, lifetime_ticks: u32) {
+    let strength = if strength.is_finite() { strength.clamp(0.0, 1.0) } else { 1.0 };
+    let permille = ((strength * types::THERMAL_STRENGTH_FULL as f32).round() as u16).max(1);
+    let packed = types::pack_thermal_source(permille, lifetime_ticks.min(0xFFFF) as u16);
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.thermal_source = packed;
+        }
+    });
+    crate::fallback::with(|f| f.thermal_source = packed);
+}
 
-    let dir = (far_pt - origin).normalize();
+#[wasm_bindgen]
+pub fn request_pick(canvas_x: f32, canvas_y: f32, canvas_w: f32, canvas_h: f32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let nx = canvas_x / canvas_w;
+            let ny = canvas_y / canvas_h;
+            if let Some(ray) = app.camera.screen_ray(nx, ny) {
+                crate::cancel_pick_readback(app);
+                app.pick_ray = Some(ray);
+                app.pick_coords = None;
+                app.pick_requested = true;
+                app.latest_pick = None;
+                app.latest_pick_region = None;
+            }
+        }
+    });
+}
+
+fn pick_to_js(pick: &renderer::PickResult) -> js_sys::Object {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"x".into(), &JsValue::from(pick.x));
+    let _ = js_sys::Reflect::set(&obj, &"y".into(), &JsValue::from(pick.y));
+    let _ = js_sys::Reflect::set(&obj, &"z".into(), &JsValue::from(pick.z));
+    let _ = js_sys::Reflect::set(&obj, &"voxel_type".into(), &JsValue::from(pick.voxel_type));
+    let _ = js_sys::Reflect::set(&obj, &"energy".into(), &JsValue::from(pick.energy));
+    let _ = js_sys::Reflect::set(&obj, &"age".into(), &JsValue::from(pick.age));
+    let _ = js_sys::Reflect::set(&obj, &"species_id".into(), &JsValue::from(pick.species_id));
+    let genome = js_sys::Array::new();
+    for b in &pick.genome {
+        genome.push(&JsValue::from(*b));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"genome".into(), &genome);
+    let traits = js_sys::Object::new();
+    for (name, value) in &pick.traits {
+        let _ = js_sys::Reflect::set(&traits, &(*name).into(), &JsValue::from(*value));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"traits".into(), &traits);
+    let _ = js_sys::Reflect::set(&obj, &"temperature".into(), &JsValue::from(pick.temperature));
+    let _ = js_sys::Reflect::set(&obj, &"scent".into(), &JsValue::from(pick.scent));
+    let _ = js_sys::Reflect::set(&obj, &"toxin".into(), &JsValue::from(pick.toxin));
+    obj
+}
+
+#[wasm_bindgen]
+pub fn get_pick_result() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        if let Some(ref app) = *borrow {
+            if let Some(ref pick) = app.latest_pick {
+                let obj = pick_to_js(pick);
+                if let Some(ref region) = app.latest_pick_region {
+                    let _ = js_sys::Reflect::set(&obj, &"region".into(), &region_to_js(region));
+                }
+                return obj.into();
+            }
+        }
+        JsValue::NULL
+    })
+}
+
+/// Recent picks, oldest first (up to `PICK_HISTORY_LEN`).
+#[wasm_bindgen]
+pub fn get_pick_history() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let history = js_sys::Array::new();
+        if let Some(ref app) = *borrow {
+            for pick in &app.pick_history {
+                history.push(&pick_to_js(pick));
+            }
+        }
+        history.into()
+    })
+}
+
+/// Genome bytes that differ between picks `a` and `b` (indices into
+/// `get_pick_history()`): `[{byte, a, b}, ...]`, or null if either index
+/// is out of range.
+#[wasm_bindgen]
+pub fn compare_picks(a: usize, b: usize) -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let Some(ref app) = *borrow else {
+            return JsValue::NULL;
+        };
+        let (Some(pa), Some(pb)) = (app.pick_history.get(a), app.pick_history.get(b)) else {
+            return JsValue::NULL;
+        };
+        let ga = types::Genome { bytes: pa.genome };
+        let gb = types::Genome { bytes: pb.genome };
+        let diffs = js_sys::Array::new();
+        for (byte, va, vb) in ga.diff(&gb) {
+            let entry = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&entry, &"byte".into(), &JsValue::from(byte as u32));
+            let _ = js_sys::Reflect::set(&entry, &"a".into(), &JsValue::from(va));
+            let _ = js_sys::Reflect::set(&entry, &"b".into(), &JsValue::from(vb));
+            diffs.push(&entry);
+        }
+        diffs.into()
+    })
+}
+
+/// Track the protocell at (x, y, z): its state is re-read with every stats
+/// readback until it dies or is replaced. Returns false for out-of-grid
+/// coordinates or in sparse mode. Replaces any previous watch.
+#[wasm_bindgen]
+pub fn watch_voxel(x: u32, y: u32, z: u32) -> bool {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let gs = app.sim_engine.grid_size();
+            if x >= gs || y >= gs || z >= gs || app.sim_engine.is_sparse() {
+                return false;
+            }
+            crate::cancel_watch_readback(app);
+            app.watch = Some(crate::watch::VoxelWatch::new(x, y, z));
+            return true;
+        }
+        false
+    })
+}
+
+#[wasm_bindgen]
+pub fn unwatch_voxel() {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            crate::cancel_watch_readback(app);
+            app.watch = None;
+        }
+    });
+}
+
+/// Time series of the watched voxel: `{x, y, z, alive, samples: [{tick,
+/// energy, age, species_id, genome}]}`, or null when nothing is watched.
+#[wasm_bindgen]
+pub fn get_watch_series() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        match *borrow {
+            Some(ref app) => app.watch.as_ref().map_or(JsValue::NULL, |w| w.to_js()),
+            None => JsValue::NULL,
+        }
+    })
+}
+
+/// Locate up to `max_results` members of `species_id` with a GPU census
+/// pass. The result arrives a frame or two later via `get_census_result`.
+#[wasm_bindgen]
+pub fn find_species(species_id: u16, max_results: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.census_request = Some(types::CensusQuery::species(species_id, max_results));
+            app.latest_census = None;
+        }
+    });
+}
+
+/// Run a census over voxels matching every given field of `query`:
+/// `{type, species_id, energy_min, energy_max, age_min, age_max,
+/// flags_mask, flags_value, max_results}`. Omitted fields match anything;
+/// `max_results: 0` only counts. Read the result with `get_census_result`.
+#[wasm_bindgen]
+pub fn census_query(query: JsValue) {
+    let field = |name: &str| {
+        js_sys::Reflect::get(&query, &name.into())
+            .ok()
+            .and_then(|v| v.as_f64())
+            .map(|v| v.max(0.0) as u32)
+    };
+    let range = |min: &str, max: &str| {
+        let lo = field(min).unwrap_or(0).min(u16::MAX as u32) as u16;
+        let hi = field(max).unwrap_or(u16::MAX as u32).min(u16::MAX as u32) as u16;
+        (lo, hi)
+    };
+    let flags_mask = field("flags_mask").unwrap_or(0) as u8;
+    let q = types::CensusQuery {
+        voxel_type: field("type").filter(|&t| t <= 7).map(|t| types::VoxelType::from_u8(t as u8)),
+        species_id: field("species_id").unwrap_or(0) as u16,
+        energy: range("energy_min", "energy_max"),
+        age: range("age_min", "age_max"),
+        flags_mask,
+        flags_value: field("flags_value").unwrap_or(0) as u8 & flags_mask,
+        max_results: 0,
+        region: None,
+    };
+    let q = q.with_max_results(field("max_results").unwrap_or(types::MAX_CENSUS_RESULTS));
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.census_request = Some(q);
+            app.latest_census = None;
+        }
+    });
+}
+
+/// Latest census: `{total, coords: [[x, y, z], ...]}`, plus `species_id`
+/// when the query named one; null while a query is pending. `total` counts
+/// matches past the cap.
+#[wasm_bindgen]
+pub fn get_census_result() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let Some(result) = borrow.as_ref().and_then(|app| app.latest_census.as_ref()) else {
+            return JsValue::NULL;
+        };
+        let obj = js_sys::Object::new();
+        if result.query.species_id != 0 {
+            let _ = js_sys::Reflect::set(&obj, &"species_id".into(), &JsValue::from(result.query.species_id));
+        }
+        let _ = js_sys::Reflect::set(&obj, &"total".into(), &JsValue::from(result.total));
+        let coords = js_sys::Array::new();
+        for &(x, y, z) in &result.coords {
+            let entry = js_sys::Array::new();
+            entry.push(&JsValue::from(x));
+            entry.push(&JsValue::from(y));
+            entry.push(&JsValue::from(z));
+            coords.push(&entry);
+        }
+        let _ = js_sys::Reflect::set(&obj, &"coords".into(), &coords);
+        obj.into()
+    })
+}
+
+/// Sample about `n` live protocell genomes (at most `MAX_GENOME_SAMPLES`)
+/// with every stats readback from now on; 0 stops sampling. Returns the
+/// latest sample, `{tick, total, diversity, samples: [{x, y, z, species_id,
+/// genome: [16 bytes]}]}`, or null before the first lands. `total` counts
+/// protocells kept past the cap; `diversity` is the mean fraction of genome
+/// bits two samples differ in.
+#[wasm_bindgen]
+pub fn sample_genomes(n: u32) -> JsValue {
+    let n = n.min(types::MAX_GENOME_SAMPLES);
+    let set = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        app.genome_sample_n = n;
+        Some(app.latest_genome_sample.clone())
+    });
+    let set = set.or_else(|| {
+        crate::fallback::with(|f| {
+            f.genome_sample_n = n;
+            f.latest_genome_sample.clone()
+        })
+    });
+    let Some(set) = set.flatten() else {
+        return JsValue::NULL;
+    };
+    let samples = js_sys::Array::new();
+    for sample in &set.samples {
+        let (x, y, z) = sample.pos;
+        let entry = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&entry, &"x".into(), &JsValue::from(x));
+        let _ = js_sys::Reflect::set(&entry, &"y".into(), &JsValue::from(y));
+        let _ = js_sys::Reflect::set(&entry, &"z".into(), &JsValue::from(z));
+        let _ = js_sys::Reflect::set(&entry, &"species_id".into(), &JsValue::from(sample.genome.species_id()));
+        let genome = js_sys::Uint8Array::from(&sample.genome.bytes[..]);
+        let _ = js_sys::Reflect::set(&entry, &"genome".into(), &genome);
+        samples.push(&entry);
+    }
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"tick".into(), &JsValue::from(set.tick));
+    let _ = js_sys::Reflect::set(&obj, &"total".into(), &JsValue::from(set.total));
+    if let Some(diversity) = set.diversity() {
+        let _ = js_sys::Reflect::set(&obj, &"diversity".into(), &JsValue::from(diversity));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"samples".into(), &samples);
+    obj.into()
+}
+
+/// Stream the whole world to `on_chunk(bytes, offset, total)` in chunks of
+/// about `chunk_size` bytes, one chunk per frame, holding ticks until the
+/// last one. Bytes are packed voxels (`Voxel::pack`, 32 per voxel) in grid
+/// order, or in sparse mode the brick pool followed by the brick table. The
+/// promise resolves with `{tick, grid_size, sparse, bytes, voxel_bytes,
+/// brick_table_bytes}` and rejects if a dump is already running, the world
+/// is reset or migrated, or a chunk fails to read back.
+#[wasm_bindgen]
+pub fn dump_world_async(chunk_size: u32, on_chunk: js_sys::Function) -> js_sys::Promise {
+    let mut callbacks = None;
+    let promise = js_sys::Promise::new(&mut |res, rej| callbacks = Some((res, rej)));
+    let Some((resolve, reject)) = callbacks else {
+        return promise;
+    };
+    let chunk = crate::dump::chunk_bytes(chunk_size);
+
+    let started = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        if app.world_dump.is_some() || app.migration.is_some() {
+            return Some(Err("a dump or migration is already in flight"));
+        }
+        app.world_dump = Some(crate::dump::WorldDump::new(
+            &app.gpu.device,
+            chunk,
+            app.sim_engine.current_read_buffer().size(),
+            app.sim_engine.brick_table().map(|t| t.to_vec()),
+            app.sim_engine.tick_count(),
+            app.sim_engine.grid_size(),
+            crate::dump::DumpSink::Stream(on_chunk.clone()),
+            resolve.clone(),
+            reject.clone(),
+        ));
+        Some(Ok(()))
+    });
+    match started {
+        Some(Ok(())) => {}
+        Some(Err(reason)) => {
+            let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from(format!("World dump refused: {reason}")));
+        }
+        None => {
+            let events = crate::fallback::with(|f| {
+                let bytes: Vec<u8> = f.sim.voxels().iter().flat_map(|v| bytemuck::cast::<_, [u8; 32]>(v.pack())).collect();
+                let tick = f.sim.tick_count();
+                crate::dump::dense_events(bytes, chunk, tick, sim_core::cpu::CPU_GRID_SIZE, on_chunk.clone(), resolve.clone())
+            });
+            match events {
+                Some(events) => crate::dump::deliver(events),
+                None => {
+                    let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from("World dump refused: no simulation"));
+                }
+            }
+        }
+    }
+    promise
+}
+
+/// Export the world as a MagicaVoxel `.vox` model (one model of the whole
+/// grid, z-up, materials colored as rendered and protocells by species) for
+/// MagicaVoxel, Houdini and the like. Reads back like `dump_world_async`,
+/// holding ticks until done, and resolves with the file as a Uint8Array.
+/// Rejects for grids over 256 cells a side, while a dump or migration is in
+/// flight, or if the world is reset or migrated meanwhile.
+#[wasm_bindgen]
+pub fn export_vox() -> js_sys::Promise {
+    let mut callbacks = None;
+    let promise = js_sys::Promise::new(&mut |res, rej| callbacks = Some((res, rej)));
+    let Some((resolve, reject)) = callbacks else {
+        return promise;
+    };
+
+    let started = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        let grid_size = app.sim_engine.grid_size();
+        if grid_size > types::VOX_MAX_SIZE {
+            return Some(Err(format!("grid size {grid_size} exceeds the .vox limit of {}", types::VOX_MAX_SIZE)));
+        }
+        if app.world_dump.is_some() || app.migration.is_some() {
+            return Some(Err("a dump or migration is already in flight".to_string()));
+        }
+        app.world_dump = Some(crate::dump::WorldDump::new(
+            &app.gpu.device,
+            crate::dump::MAX_DUMP_CHUNK_BYTES,
+            app.sim_engine.current_read_buffer().size(),
+            app.sim_engine.brick_table().map(|t| t.to_vec()),
+            app.sim_engine.tick_count(),
+            grid_size,
+            crate::dump::DumpSink::Vox(Vec::new()),
+            resolve.clone(),
+            reject.clone(),
+        ));
+        Some(Ok(()))
+    });
+    match started {
+        Some(Ok(())) => {}
+        Some(Err(reason)) => {
+            let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from(format!("Vox export refused: {reason}")));
+        }
+        None => {
+            let vox = crate::fallback::with(|f| {
+                let gs = f.sim.grid_size();
+                let cells = f.sim.voxels().iter().enumerate().map(|(i, v)| (types::grid_coords(i, gs), *v));
+                types::encode_vox(gs, cells)
+            });
+            match vox {
+                Some(Ok(vox)) => {
+                    let _ = resolve.call1(&JsValue::UNDEFINED, &js_sys::Uint8Array::from(&vox[..]));
+                }
+                Some(Err(e)) => {
+                    let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from(format!("Vox export refused: {e}")));
+                }
+                None => {
+                    let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from("Vox export refused: no simulation"));
+                }
+            }
+        }
+    }
+    promise
+}
+
+/// Snapshot of the view for remote spectators, decoupled from the save
+/// format: the grid downsampled to blocks of 8³, 4³, 2³ or 1³ cells for
+/// `quality` 0..=3, each block one `.vox` palette index, run-length encoded
+/// (layout in `types::view_snapshot`). Reads back like `dump_world_async`,
+/// holding ticks for a few frames, and resolves with the bytes as a
+/// Uint8Array. Rejects while a dump or migration is in flight.
+#[wasm_bindgen]
+pub fn get_view_snapshot(quality: u32) -> js_sys::Promise {
+    let mut callbacks = None;
+    let promise = js_sys::Promise::new(&mut |res, rej| callbacks = Some((res, rej)));
+    let Some((resolve, reject)) = callbacks else {
+        return promise;
+    };
+    let factor = types::view_snapshot_factor(quality);
+
+    let started = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        if app.world_dump.is_some() || app.migration.is_some() {
+            return Some(Err("a dump or migration is already in flight"));
+        }
+        app.world_dump = Some(crate::dump::WorldDump::new(
+            &app.gpu.device,
+            crate::dump::MAX_DUMP_CHUNK_BYTES,
+            app.sim_engine.current_read_buffer().size(),
+            app.sim_engine.brick_table().map(|t| t.to_vec()),
+            app.sim_engine.tick_count(),
+            app.sim_engine.grid_size(),
+            crate::dump::DumpSink::ViewSnapshot { voxels: Vec::new(), factor },
+            resolve.clone(),
+            reject.clone(),
+        ));
+        Some(Ok(()))
+    });
+    match started {
+        Some(Ok(())) => {}
+        Some(Err(reason)) => {
+            let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from(format!("View snapshot refused: {reason}")));
+        }
+        None => {
+            let snapshot = crate::fallback::with(|f| {
+                let gs = f.sim.grid_size();
+                let cells = f.sim.voxels().iter().enumerate().map(|(i, v)| (types::grid_coords(i, gs), *v));
+                types::encode_view_snapshot(gs, factor, f.sim.tick_count(), cells)
+            });
+            match snapshot {
+                Some(snapshot) => {
+                    let _ = resolve.call1(&JsValue::UNDEFINED, &js_sys::Uint8Array::from(&snapshot[..]));
+                }
+                None => {
+                    let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from("View snapshot refused: no simulation"));
+                }
+            }
+        }
+    }
+    promise
+}
+
+/// Export the volume as an image stack along `axis` (0 = x, 1 = y, 2 = z):
+/// `on_slice(png, index, count)` gets each slice as a grid_size² RGBA PNG
+/// of the render texture's colors (the transfer function's, when set), one
+/// slice per frame, holding ticks until the last one. Images have +y up;
+/// y slices are seen from above. The promise resolves with `{axis, count,
+/// width, height, tick}` and rejects if an export or dump is already
+/// running, the world is reset or migrated, or a readback fails.
+#[wasm_bindgen]
+pub fn export_slices(axis: u32, on_slice: js_sys::Function) -> js_sys::Promise {
+    let mut callbacks = None;
+    let promise = js_sys::Promise::new(&mut |res, rej| callbacks = Some((res, rej)));
+    let Some((resolve, reject)) = callbacks else {
+        return promise;
+    };
+    let Some(axis) = types::SliceAxis::from_u32(axis) else {
+        let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from(format!("Slice export refused: unknown axis {axis}")));
+        return promise;
+    };
+
+    let started = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        if app.slice_export.is_some() || app.world_dump.is_some() {
+            return Some(Err("an export or dump is already in flight"));
+        }
+        app.slice_export = Some(crate::slices::SliceExport::new(
+            &app.gpu.device,
+            axis,
+            app.sim_engine.grid_size(),
+            app.renderer.transfer_function().cloned(),
+            app.sim_engine.tick_count(),
+            on_slice.clone(),
+            resolve.clone(),
+            reject.clone(),
+        ));
+        // The export reads the render texture, so make sure it is current
+        app.texture_stale = true;
+        Some(Ok(()))
+    });
+    match started {
+        Some(Ok(())) => {}
+        Some(Err(reason)) => {
+            let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from(format!("Slice export refused: {reason}")));
+        }
+        None => {
+            let events = crate::fallback::with(|f| {
+                let tick = f.sim.tick_count();
+                let gs = f.sim.grid_size();
+                crate::slices::cpu_events(axis, gs, tick, |i| f.slice_image(axis, i), on_slice.clone(), resolve.clone())
+            });
+            match events {
+                Some(events) => crate::dump::deliver(events),
+                None => {
+                    let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from("Slice export refused: no simulation"));
+                }
+            }
+        }
+    }
+    promise
+}
+
+/// Every shader file name, for the development watcher in input.js.
+#[wasm_bindgen]
+pub fn get_shader_names() -> js_sys::Array {
+    types::SHADER_SOURCES.iter().map(|(name, _)| JsValue::from_str(name)).collect()
+}
+
+/// Development builds only: hot-reload the named shader file from `source`.
+/// If the sim pipelines use it they are recompiled and swapped in over the
+/// same buffers; if the new source fails validation the previous source and
+/// pipelines stay and this rejects with the error. Resolves to whether any
+/// pipeline was rebuilt; other files apply when their pipelines are next
+/// created.
+#[wasm_bindgen]
+pub async fn reload_shader(name: String, source: String) -> Result<bool, JsValue> {
+    if !cfg!(debug_assertions) {
+        return Err("shader hot reload is only available in development builds".into());
+    }
+    types::check_wgsl_includes(&source).map_err(|e| format!("{name}: {e}"))?;
+    let previous = types::shader_override(&name);
+    if !types::set_shader_override(&name, Some(&source)) {
+        return Err(format!("unknown shader '{name}'").into());
+    }
+    if !sim_core::pipelines::SIM_SHADER_FILES.contains(&name.as_str()) {
+        return Ok(false);
+    }
+
+    let compiled = APP.with(|app| {
+        let borrow = app.borrow();
+        let app = borrow.as_ref()?;
+        app.gpu.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        Some((app.gpu.device.clone(), app.sim_engine.compile_pipelines(&app.gpu.device)))
+    });
+    let Some((device, pipelines)) = compiled else {
+        types::set_shader_override(&name, previous.as_deref());
+        return Err("app not initialized".into());
+    };
+    if let Some(error) = device.pop_error_scope().await {
+        types::set_shader_override(&name, previous.as_deref());
+        return Err(format!("{name}: {error}").into());
+    }
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut().ok_or_else(|| "app not initialized".to_string())?;
+        app.sim_engine.install_pipelines(&app.gpu.device, pipelines)
+    })?;
+    Ok(true)
+}
+
+/// Drop a named marker at (x, y, z). `color` is `#rrggbb` (white if it does
+/// not parse). Returns the marker id, or undefined for out-of-grid
+/// coordinates or once `MAX_MARKERS` exist.
+#[wasm_bindgen]
+pub fn add_marker(x: u32, y: u32, z: u32, label: String, color: String) -> Option<u32> {
+    let color = types::parse_hex_color(&color).unwrap_or([255, 255, 255]);
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        let gs = app.sim_engine.grid_size();
+        if x >= gs || y >= gs || z >= gs {
+            return None;
+        }
+        let id = app.markers.add((x, y, z), &label, color)?;
+        sync_markers(app);
+        Some(id)
+    })
+}
+
+#[wasm_bindgen]
+pub fn remove_marker(id: u32) -> bool {
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let Some(app) = borrow.as_mut() else {
+            return false;
+        };
+        let removed = app.markers.remove(id);
+        sync_markers(app);
+        removed
+    })
+}
+
+#[wasm_bindgen]
+pub fn clear_markers() {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.markers.clear();
+            sync_markers(app);
+        }
+    });
+}
+
+/// Markers with their current screen position: `[{id, x, y, z, label,
+/// color, screen: [nx, ny] | null}]`. `screen` is in normalized canvas
+/// coordinates (0..1, y down), null when behind the camera.
+#[wasm_bindgen]
+pub fn get_markers() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let list = js_sys::Array::new();
+        let Some(ref app) = *borrow else {
+            return list.into();
+        };
+        for m in app.markers.markers() {
+            let obj = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&obj, &"id".into(), &JsValue::from(m.id));
+            let _ = js_sys::Reflect::set(&obj, &"x".into(), &JsValue::from(m.pos.0));
+            let _ = js_sys::Reflect::set(&obj, &"y".into(), &JsValue::from(m.pos.1));
+            let _ = js_sys::Reflect::set(&obj, &"z".into(), &JsValue::from(m.pos.2));
+            let _ = js_sys::Reflect::set(&obj, &"label".into(), &JsValue::from_str(&m.label));
+            let _ = js_sys::Reflect::set(&obj, &"color".into(), &JsValue::from_str(&m.color_hex()));
+            let center = glam::Vec3::new(m.pos.0 as f32, m.pos.1 as f32, m.pos.2 as f32) + 0.5;
+            let screen = match app.camera.project(center) {
+                Some((nx, ny)) => {
+                    let pair = js_sys::Array::new();
+                    pair.push(&JsValue::from(nx));
+                    pair.push(&JsValue::from(ny));
+                    pair.into()
+                }
+                None => JsValue::NULL,
+            };
+            let _ = js_sys::Reflect::set(&obj, &"screen".into(), &screen);
+            list.push(&obj);
+        }
+        list.into()
+    })
+}
+
+/// Markers as text (see `types::marker`), for saving with a session.
+#[wasm_bindgen]
+pub fn export_markers() -> String {
+    APP.with(|app| app.borrow().as_ref().map(|app| app.markers.export()).unwrap_or_default())
+}
+
+/// Replace all markers with an `export_markers` string. Returns how many
+/// were loaded; throws on a malformed export or out-of-grid marker.
+#[wasm_bindgen]
+pub fn import_markers(text: String) -> Result<u32, JsValue> {
+    let set = types::MarkerSet::import(&text).map_err(|e| JsValue::from_str(&e))?;
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut().ok_or_else(|| JsValue::from_str("not initialized"))?;
+        let gs = app.sim_engine.grid_size();
+        if let Some(m) = set.markers().iter().find(|m| m.pos.0 >= gs || m.pos.1 >= gs || m.pos.2 >= gs) {
+            return Err(JsValue::from_str(&format!("marker '{}' lies outside the {gs}\u{00b3} grid", m.label)));
+        }
+        app.markers = set;
+        sync_markers(app);
+        Ok(app.markers.markers().len() as u32)
+    })
+}
+
+fn sync_markers(app: &mut crate::App) {
+    app.renderer.set_markers(&app.gpu.queue, app.markers.markers());
+    // Markers are drawn with the volume; force a redraw under frame pacing
+    app.last_drawn_camera = None;
+}
+
+/// Start or stop deriving synth parameters from stats readbacks. Disabling
+/// drops the sonifier's history so re-enabling starts fresh.
+#[wasm_bindgen]
+pub fn set_audio_enabled(enabled: bool) {
+    let sonifier = || enabled.then(types::Sonifier::new);
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.sonifier = sonifier();
+            app.latest_synth = None;
+        }
+    });
+    crate::fallback::with(|f| {
+        f.sonifier = sonifier();
+        f.latest_synth = None;
+    });
+}
+
+/// Synth parameters from the latest stats readback: `{gain, pitch_hz,
+/// pulse_hz, noise, detune_cents, voices}`, or null while audio is
+/// disabled or before the first readback.
+#[wasm_bindgen]
+pub fn get_audio_params() -> JsValue {
+    let synth = APP
+        .with(|app| app.borrow().as_ref().and_then(|app| app.latest_synth))
+        .or_else(|| crate::fallback::with(|f| f.latest_synth).flatten());
+    let Some(p) = synth else {
+        return JsValue::NULL;
+    };
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"gain".into(), &JsValue::from(p.gain));
+    let _ = js_sys::Reflect::set(&obj, &"pitch_hz".into(), &JsValue::from(p.pitch_hz));
+    let _ = js_sys::Reflect::set(&obj, &"pulse_hz".into(), &JsValue::from(p.pulse_hz));
+    let _ = js_sys::Reflect::set(&obj, &"noise".into(), &JsValue::from(p.noise));
+    let _ = js_sys::Reflect::set(&obj, &"detune_cents".into(), &JsValue::from(p.detune_cents));
+    let _ = js_sys::Reflect::set(&obj, &"voices".into(), &JsValue::from(p.voices));
+    obj.into()
+}
+
+/// In measure mode successive picks collect points for `get_measurement`.
+/// Toggling either way clears the current measurement.
+#[wasm_bindgen]
+pub fn set_measure_mode(enabled: bool) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.measure_mode = enabled;
+            app.measure_points.clear();
+            app.measure_query = None;
+            app.measure_live = None;
+        }
+    });
+}
+
+/// Current measurement: `{points: [[x, y, z], ...]}`, plus `euclidean` and
+/// `manhattan` between the first two points once picked, and `box: {min,
+/// max, live_count}` once a third point closes it. `live_count` is null
+/// until the region count lands. Null when measure mode is off.
+#[wasm_bindgen]
+pub fn get_measurement() -> JsValue {
+    let coord = |(x, y, z): (u32, u32, u32)| {
+        let entry = js_sys::Array::new();
+        entry.push(&JsValue::from(x));
+        entry.push(&JsValue::from(y));
+        entry.push(&JsValue::from(z));
+        entry
+    };
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let Some(app) = borrow.as_ref().filter(|app| app.measure_mode) else {
+            return JsValue::NULL;
+        };
+        let obj = js_sys::Object::new();
+        let points = js_sys::Array::new();
+        for &p in &app.measure_points {
+            points.push(&coord(p));
+        }
+        let _ = js_sys::Reflect::set(&obj, &"points".into(), &points);
+        if let [a, b, ..] = app.measure_points[..] {
+            let _ = js_sys::Reflect::set(&obj, &"euclidean".into(), &JsValue::from(types::euclidean_distance(a, b)));
+            let _ = js_sys::Reflect::set(&obj, &"manhattan".into(), &JsValue::from(types::manhattan_distance(a, b)));
+        }
+        if let Some((min, max)) = app.measure_query.and_then(|q| q.region) {
+            let bx = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&bx, &"min".into(), &coord(min));
+            let _ = js_sys::Reflect::set(&bx, &"max".into(), &coord(max));
+            let live = app.measure_live.map_or(JsValue::NULL, JsValue::from);
+            let _ = js_sys::Reflect::set(&bx, &"live_count".into(), &live);
+            let _ = js_sys::Reflect::set(&obj, &"box".into(), &bx);
+        }
+        obj.into()
+    })
+}
+
+/// Radius of the neighborhood read back with each pick, clamped to
+/// 0..=3 (a 1³ to 7³ box). Dense mode only.
+#[wasm_bindgen]
+pub fn set_pick_region_radius(radius: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.pick_region_radius = radius.min(renderer::picker::MAX_REGION_RADIUS);
+        }
+    });
+}
+
+fn region_to_js(region: &renderer::PickRegion) -> JsValue {
+    let obj = js_sys::Object::new();
+    let min = js_sys::Array::new();
+    let dims = js_sys::Array::new();
+    for a in 0..3 {
+        min.push(&JsValue::from(region.bounds.min[a]));
+        dims.push(&JsValue::from(region.bounds.dims[a]));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"min".into(), &min);
+    let _ = js_sys::Reflect::set(&obj, &"dims".into(), &dims);
+    let cells = js_sys::Array::new();
+    for cell in &region.cells {
+        let entry = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&entry, &"x".into(), &JsValue::from(cell.x));
+        let _ = js_sys::Reflect::set(&entry, &"y".into(), &JsValue::from(cell.y));
+        let _ = js_sys::Reflect::set(&entry, &"z".into(), &JsValue::from(cell.z));
+        let _ = js_sys::Reflect::set(&entry, &"voxel_type".into(), &JsValue::from(cell.voxel_type));
+        let _ = js_sys::Reflect::set(&entry, &"energy".into(), &JsValue::from(cell.energy));
+        let _ = js_sys::Reflect::set(&entry, &"species_id".into(), &JsValue::from(cell.species_id));
+        let _ = js_sys::Reflect::set(&entry, &"temperature".into(), &JsValue::from(cell.temperature));
+        cells.push(&entry);
+    }
+    let _ = js_sys::Reflect::set(&obj, &"cells".into(), &cells);
+    obj.into()
+}
+
+#[wasm_bindgen]
+pub fn get_stats() -> JsValue {
+    let stats = APP.with(|app| {
+        let borrow = app.borrow();
+        borrow.as_ref().and_then(|app| app.latest_stats.clone())
+    });
+    let stats = stats.or_else(|| crate::fallback::with(|f| f.latest_stats.clone()).flatten());
+    let Some(stats) = stats else {
+        return JsValue::NULL;
+    };
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"population".into(), &JsValue::from(stats.population));
+    let _ = js_sys::Reflect::set(&obj, &"total_energy".into(), &JsValue::from(stats.total_energy));
+    let _ = js_sys::Reflect::set(&obj, &"species_count".into(), &JsValue::from(stats.species_count));
+    let _ = js_sys::Reflect::set(&obj, &"max_energy".into(), &JsValue::from(stats.max_energy));
+    let _ = js_sys::Reflect::set(&obj, &"toxin_deaths".into(), &JsValue::from(stats.toxin_deaths));
+    if let Some(index) = stats.clustering_index() {
+        let _ = js_sys::Reflect::set(&obj, &"clustering".into(), &JsValue::from(index));
+    }
+    if let Some(rate) = stats.mean_mutation_rate() {
+        let _ = js_sys::Reflect::set(&obj, &"mean_mutation_rate".into(), &JsValue::from(rate));
+    }
+    if let Some(age) = stats.mean_age() {
+        let _ = js_sys::Reflect::set(&obj, &"mean_age".into(), &JsValue::from(age));
+    }
+    if let Some(temperature) = stats.mean_temperature() {
+        let _ = js_sys::Reflect::set(&obj, &"mean_temperature".into(), &JsValue::from(temperature));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"births".into(), &JsValue::from(stats.turnover.births));
+    let _ = js_sys::Reflect::set(&obj, &"deaths".into(), &JsValue::from(stats.turnover.deaths));
+    let voxels = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&voxels, &"nutrient".into(), &JsValue::from(stats.nutrients));
+    let _ = js_sys::Reflect::set(&voxels, &"waste".into(), &JsValue::from(stats.waste));
+    let _ = js_sys::Reflect::set(&voxels, &"wall".into(), &JsValue::from(stats.walls));
+    let _ = js_sys::Reflect::set(&obj, &"voxel_counts".into(), &voxels);
+    if let Some(pool) = stats.brick_pool {
+        let _ = js_sys::Reflect::set(&obj, &"brick_pool".into(), &brick_pool_to_js(&pool));
+    }
+    for (name, field) in [("temperature", stats.temperature), ("pheromone", stats.pheromone), ("energy", stats.energy)] {
+        if let Some(field) = field {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &field_summary_to_js(&field));
+        }
+    }
+    let conflicts = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&conflicts, &"placement".into(), &JsValue::from(stats.conflicts.placement));
+    let _ = js_sys::Reflect::set(&conflicts, &"predation".into(), &JsValue::from(stats.conflicts.predation));
+    let _ = js_sys::Reflect::set(&conflicts, &"rejected_placement".into(), &JsValue::from(stats.conflicts.rejected_placement));
+    let _ = js_sys::Reflect::set(&conflicts, &"rejected_predation".into(), &JsValue::from(stats.conflicts.rejected_predation));
+    let _ = js_sys::Reflect::set(&conflicts, &"ticks".into(), &JsValue::from(stats.energy_flow.ticks));
+    if let Some((contested, rejected)) = stats.contention_per_tick() {
+        let _ = js_sys::Reflect::set(&conflicts, &"contested_per_tick".into(), &JsValue::from(contested));
+        let _ = js_sys::Reflect::set(&conflicts, &"rejected_per_tick".into(), &JsValue::from(rejected));
+    }
+    let policy = APP.with(|app| app.borrow().as_ref().map(|app| app.sim_engine.rules.arbitration));
+    let policy = policy.or_else(|| crate::fallback::with(|f| f.sim.rules.arbitration));
+    if let Some(policy) = policy {
+        let _ = js_sys::Reflect::set(&conflicts, &"policy".into(), &JsValue::from(policy.name()));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"conflicts".into(), &conflicts);
+    let species = js_sys::Array::new();
+    for (sid, count) in &stats.species_histogram {
+        let entry = js_sys::Array::new();
+        entry.push(&JsValue::from(*sid));
+        entry.push(&JsValue::from(*count));
+        species.push(&entry);
+    }
+    let _ = js_sys::Reflect::set(&obj, &"species".into(), &species);
+    let _ = js_sys::Reflect::set(&obj, &"other_species".into(), &JsValue::from(stats.other_species));
+    let goal = js_sys::Array::new();
+    for (sid, count) in &stats.goal_histogram {
+        let entry = js_sys::Array::new();
+        entry.push(&JsValue::from(*sid));
+        entry.push(&JsValue::from(*count));
+        goal.push(&entry);
+    }
+    let _ = js_sys::Reflect::set(&obj, &"goal_species".into(), &goal);
+    let _ = js_sys::Reflect::set(&obj, &"goal_other".into(), &JsValue::from(stats.goal_other));
+    let challenge = APP.with(|app| app.borrow().as_ref().map(|app| app.challenge.clone()));
+    let challenge = challenge.or_else(|| crate::fallback::with(|f| f.challenge.clone())).flatten();
+    if let Some(challenge) = challenge {
+        let _ = js_sys::Reflect::set(&obj, &"challenge".into(), &challenge_to_js(&challenge));
+    }
+    obj.into()
+}
+
+/// `{ capacity, allocated, free, high_water, failed_allocations }`.
+pub(crate) fn brick_pool_to_js(pool: &types::BrickPoolStats) -> js_sys::Object {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"capacity".into(), &JsValue::from(pool.capacity));
+    let _ = js_sys::Reflect::set(&obj, &"allocated".into(), &JsValue::from(pool.allocated));
+    let _ = js_sys::Reflect::set(&obj, &"free".into(), &JsValue::from(pool.free()));
+    let _ = js_sys::Reflect::set(&obj, &"high_water".into(), &JsValue::from(pool.high_water));
+    let _ = js_sys::Reflect::set(&obj, &"failed_allocations".into(), &JsValue::from(pool.failed_allocations as f64));
+    obj
+}
+
+/// Free bricks below which a `primordium-pool-pressure` event fires (once,
+/// until the pool recovers); 0 restores the default of a tenth of the pool.
+#[wasm_bindgen]
+pub fn set_pool_alarm_threshold(free_bricks: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.pool_alarm.set_threshold((free_bricks > 0).then_some(free_bricks));
+        }
+    });
+}
+
+/// Energy moved per pathway over the latest stats window, as a flow table:
+/// `{ ticks, inflow, outflow, flows: [{ path, source, target, amount }] }`.
+/// Null before the first stats sample.
+#[wasm_bindgen]
+pub fn get_energy_flow() -> JsValue {
+    let flow = APP.with(|app| {
+        let borrow = app.borrow();
+        borrow.as_ref().map(|app| app.latest_stats.as_ref().map(|s| s.energy_flow))
+    });
+    let flow = flow.or_else(|| crate::fallback::with(|f| f.latest_stats.as_ref().map(|s| s.energy_flow)));
+    let Some(flow) = flow.flatten() else {
+        return JsValue::NULL;
+    };
+    let flows = js_sys::Array::new();
+    for path in types::EnergyPath::ALL {
+        let (source, target) = path.nodes();
+        let row = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&row, &"path".into(), &JsValue::from(path.name()));
+        let _ = js_sys::Reflect::set(&row, &"source".into(), &JsValue::from(source));
+        let _ = js_sys::Reflect::set(&row, &"target".into(), &JsValue::from(target));
+        let _ = js_sys::Reflect::set(&row, &"amount".into(), &JsValue::from(flow.amount(path)));
+        flows.push(&row);
+    }
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"ticks".into(), &JsValue::from(flow.ticks));
+    let _ = js_sys::Reflect::set(&obj, &"inflow".into(), &JsValue::from(flow.inflow() as f64));
+    let _ = js_sys::Reflect::set(&obj, &"outflow".into(), &JsValue::from(flow.outflow() as f64));
+    let _ = js_sys::Reflect::set(&obj, &"flows".into(), &flows);
+    obj.into()
+}
+
+/// Species that appeared in or vanished from the stats histogram at or
+/// after `since_tick`, oldest first. Extinctions of top-5 species also
+/// dispatch a `primordium-extinction` event on `window`.
+#[wasm_bindgen]
+pub fn get_ecology_events(since_tick: u32) -> JsValue {
+    let events = APP.with(|app| {
+        let borrow = app.borrow();
+        borrow.as_ref().map(|app| app.ecology.since(since_tick).copied().collect::<Vec<_>>())
+    });
+    let events = events.or_else(|| crate::fallback::with(|f| f.ecology.since(since_tick).copied().collect()));
+    let arr = js_sys::Array::new();
+    for event in events.unwrap_or_default() {
+        arr.push(&ecology_event_to_js(&event));
+    }
+    arr.into()
+}
+
+/// The species phylogeny as `{nodes: [{species_id, parent, tick}], unlogged,
+/// pruned}`, nodes by id; `parent` is null for roots. A species appears when
+/// a mutated offspring first founds it; extinct leaves are pruned past
+/// `types::MAX_LINEAGE_SPECIES`.
+#[wasm_bindgen]
+pub fn get_lineage_tree() -> JsValue {
+    let tree = APP.with(|app| app.borrow().as_ref().map(|app| app.lineage.clone()));
+    let tree = tree.or_else(|| crate::fallback::with(|f| f.lineage.clone())).unwrap_or_default();
+    let nodes = js_sys::Array::new();
+    for node in tree.nodes() {
+        let obj = js_sys::Object::new();
+        let parent = node.parent.map_or(JsValue::NULL, JsValue::from);
+        let _ = js_sys::Reflect::set(&obj, &"species_id".into(), &JsValue::from(node.species_id));
+        let _ = js_sys::Reflect::set(&obj, &"parent".into(), &parent);
+        let _ = js_sys::Reflect::set(&obj, &"tick".into(), &JsValue::from(node.tick));
+        nodes.push(&obj);
+    }
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"nodes".into(), &nodes);
+    let _ = js_sys::Reflect::set(&obj, &"unlogged".into(), &JsValue::from(tree.unlogged as f64));
+    let _ = js_sys::Reflect::set(&obj, &"pruned".into(), &JsValue::from(tree.pruned as f64));
+    obj.into()
+}
+
+pub(crate) fn ecology_event_to_js(event: &types::EcologyEvent) -> JsValue {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"tick".into(), &JsValue::from(event.tick));
+    let _ = js_sys::Reflect::set(&obj, &"kind".into(), &JsValue::from(event.kind.name()));
+    let _ = js_sys::Reflect::set(&obj, &"species_id".into(), &JsValue::from(event.species_id));
+    let _ = js_sys::Reflect::set(&obj, &"population".into(), &JsValue::from(event.population));
+    let _ = js_sys::Reflect::set(&obj, &"rank".into(), &JsValue::from(event.rank));
+    obj.into()
+}
+
+/// Engine log entries with `seq >= since`, oldest first, as
+/// `{next, entries: [{seq, tick, subsystem, level, message}]}`; poll again
+/// with `next`.
+#[wasm_bindgen]
+pub fn get_engine_log(since: f64) -> JsValue {
+    let since = since.max(0.0) as u64;
+    let collect = |log: &types::EngineLog| (log.next_seq(), log.since(since).cloned().collect::<Vec<_>>());
+    let log = APP.with(|app| app.borrow().as_ref().map(|app| collect(&app.sim_engine.log)));
+    let (next, entries) = log.or_else(|| crate::fallback::with(|f| collect(&f.sim.log))).unwrap_or_default();
+    let arr = js_sys::Array::new();
+    for entry in entries {
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"seq".into(), &JsValue::from(entry.seq as f64));
+        let _ = js_sys::Reflect::set(&obj, &"tick".into(), &JsValue::from(entry.tick));
+        let _ = js_sys::Reflect::set(&obj, &"subsystem".into(), &JsValue::from(entry.subsystem.name()));
+        let _ = js_sys::Reflect::set(&obj, &"level".into(), &JsValue::from(entry.level.name()));
+        let _ = js_sys::Reflect::set(&obj, &"message".into(), &JsValue::from(entry.message));
+        arr.push(&obj);
+    }
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"next".into(), &JsValue::from(next as f64));
+    let _ = js_sys::Reflect::set(&obj, &"entries".into(), &arr);
+    obj.into()
+}
+
+/// Per-species challenge scores, highest first.
+/// `{min, max, mean}` of one scalar field.
+fn field_summary_to_js(field: &types::FieldSummary) -> JsValue {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"min".into(), &JsValue::from(field.min));
+    let _ = js_sys::Reflect::set(&obj, &"max".into(), &JsValue::from(field.max));
+    let _ = js_sys::Reflect::set(&obj, &"mean".into(), &JsValue::from(field.mean));
+    obj.into()
+}
+
+fn challenge_to_js(challenge: &types::ChallengeTracker) -> JsValue {
+    let scores = js_sys::Array::new();
+    for s in challenge.scores() {
+        let entry = js_sys::Object::new();
+        let opt = |t: Option<u32>| t.map_or(JsValue::NULL, JsValue::from);
+        let _ = js_sys::Reflect::set(&entry, &"species".into(), &JsValue::from(s.species_id));
+        let _ = js_sys::Reflect::set(&entry, &"score".into(), &JsValue::from(s.score));
+        let _ = js_sys::Reflect::set(&entry, &"reached_tick".into(), &opt(s.reached_tick));
+        let _ = js_sys::Reflect::set(&entry, &"held_since".into(), &opt(s.held_since));
+        let _ = js_sys::Reflect::set(&entry, &"completed_tick".into(), &opt(s.completed_tick));
+        scores.push(&entry);
+    }
+    scores.into()
+}
+
+#[wasm_bindgen]
+pub fn load_preset(preset_id: u32) {
+    reset_world(
+        |app| app.sim_engine.initialize_grid_with_preset(&app.gpu.queue, preset_id),
+        |f| f.sim.initialize_grid_with_preset(preset_id),
+    );
+}
+
+/// Load preset `preset_id` with its random placements and founder genomes
+/// drawn from `seed` (a whole number; fractions are dropped). The same
+/// seed always rebuilds the same world. Returns the cells written.
+#[wasm_bindgen]
+pub fn load_preset_with_seed(preset_id: u32, seed: f64) -> u32 {
+    let config = types::SeedConfig::seeded(preset_id, seed.max(0.0) as u64);
+    reset_world(
+        |app| {
+            let voxels = sim_core::seed::preset_with(app.sim_engine.grid_size(), preset_id, &config);
+            app.sim_engine.initialize_grid_with_voxels(&app.gpu.queue, &voxels)
+        },
+        |f| {
+            let voxels = sim_core::seed::preset_with(f.sim.grid_size(), preset_id, &config);
+            f.sim.initialize_grid_with_voxels(&voxels)
+        },
+    )
+    .unwrap_or(0)
+}
+
+/// Replace the world with terrain from a grayscale PNG heightmap (color is
+/// reduced to luminance) and a protocell lineage on its surface. `mapping`:
+/// 0 = walls as tall as the brightness under a nutrient layer as dense as
+/// it, 1 = walls only, 2 = a nutrient layer only (see `types::terrain`).
+/// Returns the cells written.
+#[wasm_bindgen]
+pub fn seed_from_image(bytes: &[u8], mapping: u32) -> Result<u32, JsValue> {
+    let mapping = types::TerrainMapping::from_u32(mapping)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown terrain mapping {mapping}")))?;
+    let image = types::decode_png_gray(bytes).map_err(|e| JsValue::from_str(&e))?;
+    let written = reset_world(
+        |app| {
+            let voxels = sim_core::seed::terrain(app.sim_engine.grid_size(), &image, mapping);
+            app.sim_engine.initialize_grid_with_voxels(&app.gpu.queue, &voxels)
+        },
+        |f| {
+            let voxels = sim_core::seed::terrain(f.sim.grid_size(), &image, mapping);
+            f.sim.initialize_grid_with_voxels(&voxels)
+        },
+    );
+    Ok(written.unwrap_or(0))
+}
+
+/// Replace the world with noise terrain and a protocell lineage in its open
+/// cells. `config`: `{style, seed, scale, density, veins}` with `style` 0 =
+/// caves, 1 = pillars, 2 = veins; omitted fields take the style's preset
+/// values (see `types::TerrainConfig`). Returns the cells written.
+#[wasm_bindgen]
+pub fn generate_terrain(config: JsValue) -> Result<u32, JsValue> {
+    let field = |name: &str| js_sys::Reflect::get(&config, &name.into()).ok().and_then(|v| v.as_f64());
+    let style = field("style").unwrap_or(0.0) as u32;
+    let style = types::TerrainStyle::from_u32(style)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown terrain style {style}")))?;
+    let defaults = types::TerrainConfig::for_style(style);
+    let config = types::TerrainConfig {
+        seed: field("seed").map_or(defaults.seed, |v| v as u32),
+        style,
+        scale: field("scale").map_or(defaults.scale, |v| v as f32),
+        density: field("density").map_or(defaults.density, |v| v as f32),
+        veins: field("veins").map_or(defaults.veins, |v| v as f32),
+    };
+    let written = reset_world(
+        |app| {
+            let voxels = sim_core::seed::procedural(app.sim_engine.grid_size(), &config, types::TERRAIN_FOUNDER_DRAWS);
+            app.sim_engine.initialize_grid_with_voxels(&app.gpu.queue, &voxels)
+        },
+        |f| {
+            let voxels = sim_core::seed::procedural(f.sim.grid_size(), &config, types::TERRAIN_FOUNDER_DRAWS);
+            f.sim.initialize_grid_with_voxels(&voxels)
+        },
+    );
+    Ok(written.unwrap_or(0))
+}
+
+/// Replace the world with the voxels of a JSON point list. Each entry is
+/// `[x, y, z, type, energy?, genome?]` or `{x, y, z, type, energy?,
+/// genome?}` with `type` a `VoxelType` code and `genome` up to 16 bytes
+/// (protocells only). Later entries overwrite earlier ones at the same
+/// cell. The whole list is validated before the world is touched. Returns
+/// the cells written.
+#[wasm_bindgen]
+pub fn seed_custom(points_json: &str) -> Result<u32, JsValue> {
+    let gs = get_grid_size();
+    if gs == 0 {
+        return Err(JsValue::from_str("not initialized"));
+    }
+    let list = js_sys::JSON::parse(points_json)?;
+    if !js_sys::Array::is_array(&list) {
+        return Err(JsValue::from_str("expected a JSON array of points"));
+    }
+    let list = js_sys::Array::from(&list);
+    if list.length() as usize > types::MAX_SEED_POINTS {
+        return Err(JsValue::from_str(&format!("more than {} points", types::MAX_SEED_POINTS)));
+    }
+    let numbers = |v: &JsValue| -> Option<Vec<f64>> {
+        js_sys::Array::is_array(v).then(|| js_sys::Array::from(v).iter().map(|x| x.as_f64().unwrap_or(f64::NAN)).collect())
+    };
+    let mut points = Vec::with_capacity(list.length() as usize);
+    for (n, entry) in list.iter().enumerate() {
+        let (values, genome) = if let Some(mut values) = numbers(&entry) {
+            // A sixth element is the genome
+            let genome = (values.len() == 6).then(|| js_sys::Array::from(&entry).get(5));
+            if genome.is_some() {
+                values.truncate(5);
+            }
+            (values, genome)
+        } else if entry.is_object() {
+            let get = |name: &str| js_sys::Reflect::get(&entry, &name.into()).ok().filter(|v| !v.is_undefined());
+            let field = |name: &str| get(name).map_or(f64::NAN, |v| v.as_f64().unwrap_or(f64::NAN));
+            let mut values = vec![field("x"), field("y"), field("z"), field("type")];
+            values.extend(get("energy").map(|v| v.as_f64().unwrap_or(f64::NAN)));
+            (values, get("genome"))
+        } else {
+            return Err(JsValue::from_str(&format!("point {n}: expected an array or object")));
+        };
+        let genome = match genome.filter(|g| !g.is_null()) {
+            None => None,
+            Some(g) => Some(numbers(&g).ok_or_else(|| JsValue::from_str(&format!("point {n}: genome must be an array of bytes")))?),
+        };
+        let point = types::SeedPoint::from_values(n, &values, genome.as_deref(), gs).map_err(|e| JsValue::from_str(&e))?;
+        points.push(point);
+    }
+    let voxels = sim_core::seed::custom(&points);
+    let written = reset_world(
+        |app| app.sim_engine.initialize_grid_with_voxels(&app.gpu.queue, &voxels),
+        |f| f.sim.initialize_grid_with_voxels(&voxels),
+    );
+    Ok(written.unwrap_or(0))
+}
+
+/// Save the run: voxels, temperature, tick count, simulated time and params
+/// in the versioned binary format of `types::snapshot`, as a Uint8Array.
+/// Reads the GPU state back once, without holding ticks.
+#[wasm_bindgen]
+pub async fn export_snapshot() -> Result<js_sys::Uint8Array, JsValue> {
+    let readback = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        let mut encoder = app.gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("snapshot_encoder"),
+        });
+        let readback = app.sim_engine.export_snapshot(&app.gpu.device, &mut encoder);
+        app.gpu.queue.submit(std::iter::once(encoder.finish()));
+        Some(readback)
+    });
+    let bytes = match readback {
+        Some(readback) => {
+            for buffer in readback.buffers() {
+                crate::readback::map_read(buffer).await.map_err(|e| format!("Snapshot readback failed: {e}"))?;
+            }
+            readback.finish()
+        }
+        None => crate::fallback::with(|f| f.sim.export_snapshot()).ok_or("not initialized")?,
+    };
+    Ok(js_sys::Uint8Array::from(&bytes[..]))
+}
+
+/// Restore a run saved by `export_snapshot` into a world of the same grid
+/// size, dense or sparse either way. The snapshot is checked before the
+/// current world is touched. Returns the voxels placed.
+#[wasm_bindgen]
+pub fn import_snapshot(bytes: &[u8]) -> Result<u32, JsValue> {
+    let snapshot = types::WorldSnapshot::decode(bytes).map_err(|e| JsValue::from_str(&e))?;
+    let gs = get_grid_size();
+    if gs == 0 {
+        return Err(JsValue::from_str("not initialized"));
+    }
+    if snapshot.grid_size != gs {
+        return Err(JsValue::from_str(&format!("snapshot is of a {}³ world, this one is {gs}³", snapshot.grid_size)));
+    }
+    let placed = reset_world(
+        |app| app.sim_engine.import_snapshot(&app.gpu.queue, bytes),
+        |f| f.sim.import_snapshot(bytes),
+    );
+    placed.unwrap_or(Ok(0)).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Reset clocks and per-world state around `seed_gpu` or `seed_cpu`, which
+/// replace the world. Returns what the seed that ran returned.
+fn reset_world<R>(
+    seed_gpu: impl FnOnce(&mut App) -> R,
+    seed_cpu: impl FnOnce(&mut crate::fallback::FallbackApp) -> R,
+) -> Option<R> {
+    let seeded = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        app.sim_engine.reset_tick_count();
+        app.timing.reset_clock();
+        let seeded = seed_gpu(app);
+        app.renderer.clear_graph();
+        app.latest_stats = None;
+        app.stats_tick_counter = 0;
+        if let Some(ref mut challenge) = app.challenge {
+            challenge.reset();
+        }
+        app.ecology.clear();
+        app.lineage.clear();
+        app.pool_alarm.rearm();
+        app.latest_genome_sample = None;
+        app.queued_commands.clear();
+        crate::cancel_stats_readback(app);
+        crate::cancel_world_dump(app, "world was reset");
+        crate::cancel_slice_export(app, "world was reset");
+        Some(seeded)
+    });
+    seeded.or_else(|| {
+        crate::fallback::with(|f| {
+            f.sim.reset_tick_count();
+            f.timing.reset_clock();
+            let seeded = seed_cpu(f);
+            f.latest_stats = Some(f.sim.stats());
+            if let Some(ref mut challenge) = f.challenge {
+                challenge.reset();
+            }
+            f.ecology.clear();
+            f.lineage.clear();
+            f.latest_genome_sample = None;
+            f.queued_commands.clear();
+            f.mark_dirty();
+            seeded
+        })
+    })
+}
+
+/// Migrate the world between dense buffers and a sparse brick pool of
+/// `max_bricks` bricks (0 = twice the bricks the world occupies). Upgrading
+/// reads back brick occupancy first (deferred while frames run over
+/// budget), pausing ticks for a few frames.
+/// Returns false if the engine is already in that mode, a migration is
+/// pending, or the downgrade failed.
+#[wasm_bindgen]
+pub fn set_sparse_mode(enabled: bool, max_bricks: u32) -> bool {
+    let changed = APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            if enabled == app.sim_engine.is_sparse() || app.migration.is_some() {
+                return false;
+            }
+            let pass = sim_core::migrate::MigratePass::new(&app.gpu.device, app.sim_engine.grid_size());
+            if enabled {
+                // The frame loop issues the occupancy readback when the
+                // frame budget allows
+                app.migrate_max_bricks = max_bricks;
+                app.migration = Some(pass);
+                true
+            } else {
+                crate::cancel_stats_readback(app);
+                match app.sim_engine.convert_to_dense(&app.gpu.device, &app.gpu.queue, &pass) {
+                    Ok(()) => {
+                        crate::rebind_after_migration(app);
+                        true
+                    }
+                    Err(e) => {
+                        web_sys::console::warn_1(&format!("Dense downgrade failed: {e}").into());
+                        false
+                    }
+                }
+            }
+        } else {
+            false
+        }
+    });
+    // A downgrade may have queued a memory warning
+    crate::emit_memory_warning();
+    changed
+}
+
+#[wasm_bindgen]
+pub fn run_benchmark() -> u32 {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let count = app.sim_engine.seed_benchmark(&app.gpu.queue);
+            app.latest_stats = None;
+            app.stats_tick_counter = 0;
+            app.ecology.clear();
+            app.lineage.clear();
+            crate::cancel_stats_readback(app);
+            crate::cancel_world_dump(app, "world was reset");
+            crate::cancel_slice_export(app, "world was reset");
+            count
+        } else {
+            0
+        }
+    })
+}
+
+/// In-world seconds since the last reset (sum of the `dt` param per tick).
+#[wasm_bindgen]
+pub fn sim_time_seconds() -> f64 {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        if let Some(ref app) = *borrow {
+            app.sim_engine.sim_time_seconds()
+        } else {
+            crate::fallback::with(|f| f.sim.sim_time_seconds()).unwrap_or(0.0)
+        }
+    })
+}
+
+/// Simulation clock vs wall clock since the last reset.
+#[wasm_bindgen]
+pub fn get_clock_info() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        if let Some(ref app) = *borrow {
+            let obj = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&obj, &"ticks".into(), &JsValue::from(app.timing.clock.ticks_since_reset as f64));
+            let _ = js_sys::Reflect::set(&obj, &"tick_count".into(), &JsValue::from(app.sim_engine.tick_count()));
+            let _ = js_sys::Reflect::set(&obj, &"wall_seconds".into(), &JsValue::from(app.timing.clock.wall_elapsed));
+            let _ = js_sys::Reflect::set(&obj, &"sim_seconds".into(), &JsValue::from(app.sim_engine.sim_time_seconds()));
+            let _ = js_sys::Reflect::set(&obj, &"effective_tps".into(), &JsValue::from(app.timing.effective_tps()));
+            return obj.into();
+        }
+        JsValue::NULL
+    })
+}
+
+/// How init picked the engine configuration: tier, mode, grid size, the
+/// reason for any fallback, and every configuration attempted.
+#[wasm_bindgen]
+pub fn get_capability_report() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        if let Some(ref app) = *borrow {
+            app.capability.to_js()
+        } else {
+            crate::fallback::with(|f| f.capability.to_js()).unwrap_or(JsValue::NULL)
+        }
+    })
+}
+
+/// Adapter, limits, tier, engine configuration, allocated buffer sizes and
+/// enabled features, for attaching to bug reports.
+#[wasm_bindgen]
+pub fn get_gpu_report() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        if let Some(ref app) = *borrow {
+            crate::report::gpu_report(app)
+        } else {
+            JsValue::NULL
+        }
+    })
+}
+
+/// Time the compute passes of each frame's last tick with GPU timestamp
+/// queries. Returns whether profiling is on: false on the CPU fallback or
+/// when the adapter lacks timestamp queries.
+#[wasm_bindgen]
+pub fn set_profiling(enabled: bool) -> bool {
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let Some(ref mut app) = *borrow else {
+            return false;
+        };
+        app.profiling = app.sim_engine.set_profiling(&app.gpu.device, &app.gpu.queue, enabled);
+        if !app.profiling {
+            app.latest_profile = None;
+        }
+        app.profiling
+    })
+}
+
+/// Keyframe the world every `interval` ticks into a ring of the last
+/// `count` (at most `sim_core::keyframes::MAX_KEYFRAMES`); 0 for either
+/// turns keyframes off. Each keyframe costs as much GPU memory as the voxel
+/// buffers, so changing the ring drops its captures. Returns the count kept.
+#[wasm_bindgen]
+pub fn set_keyframes(interval: u32, count: u32) -> u32 {
+    let count = if interval == 0 { 0 } else { count.min(sim_core::keyframes::MAX_KEYFRAMES) };
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.keyframes = (interval, count);
+            app.sim_engine.set_keyframes(&app.gpu.device, interval, count);
+        }
+    });
+    count
+}
+
+/// `{ interval, capacity, ticks: [newest, ..., oldest], bytes }`; null in
+/// CPU fallback, which keeps no keyframes.
+#[wasm_bindgen]
+pub fn get_keyframes() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let Some(ref app) = *borrow else {
+            return JsValue::NULL;
+        };
+        let (interval, capacity) = app.sim_engine.keyframe_config().unwrap_or((0, 0));
+        let bytes: u64 = app.sim_engine.allocations().iter().filter(|(label, _)| label.starts_with("keyframe_")).map(|(_, b)| b).sum();
+        let ticks = js_sys::Array::new();
+        for tick in app.sim_engine.keyframe_ticks() {
+            ticks.push(&JsValue::from(tick));
+        }
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"interval".into(), &JsValue::from(interval));
+        let _ = js_sys::Reflect::set(&obj, &"capacity".into(), &JsValue::from(capacity));
+        let _ = js_sys::Reflect::set(&obj, &"ticks".into(), &ticks);
+        let _ = js_sys::Reflect::set(&obj, &"bytes".into(), &JsValue::from(bytes as f64));
+        obj.into()
+    })
+}
+
+/// Return the world to keyframe `n` (0 = newest, see `get_keyframes`),
+/// dropping newer keyframes. Readbacks and queued edits of the abandoned
+/// run are discarded, as on reset. Returns the tick count rewound to.
+#[wasm_bindgen]
+pub fn rewind_to_keyframe(n: u32) -> Result<u32, JsValue> {
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let Some(ref mut app) = *borrow else {
+            return Err(JsValue::from_str("keyframes need the GPU engine"));
+        };
+        let mut encoder = app.gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("rewind_encoder"),
+        });
+        let tick = app.sim_engine.rewind_to_keyframe(&mut encoder, n).map_err(|e| JsValue::from_str(&e))?;
+        app.gpu.queue.submit(std::iter::once(encoder.finish()));
+        app.timing.reset_clock();
+        app.renderer.clear_graph();
+        app.latest_stats = None;
+        app.stats_tick_counter = 0;
+        if let Some(ref mut challenge) = app.challenge {
+            challenge.reset();
+        }
+        app.ecology.clear();
+        app.lineage.clear();
+        app.latest_genome_sample = None;
+        app.queued_commands.clear();
+        crate::cancel_stats_readback(app);
+        crate::cancel_world_dump(app, "world was rewound");
+        crate::cancel_slice_export(app, "world was rewound");
+        Ok(tick)
+    })
+}
+
+/// GPU milliseconds per pass group of the latest profiled tick:
+/// `{ tick, total_ms, passes: { apply_commands, temperature_diffusion,
+/// intent_declaration, resolve_execute, stats_reduction } }`, with passes
+/// that did not run that tick left out. Null until a profile lands.
+#[wasm_bindgen]
+pub fn get_profile() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let Some(timings) = borrow.as_ref().and_then(|app| app.latest_profile.as_ref()) else {
+            return JsValue::NULL;
+        };
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"tick".into(), &JsValue::from(timings.tick));
+        let _ = js_sys::Reflect::set(&obj, &"total_ms".into(), &JsValue::from(timings.total_ms()));
+        let passes = js_sys::Object::new();
+        for pass in sim_core::ProfilePass::ALL {
+            if let Some(ms) = timings.get(pass) {
+                let _ = js_sys::Reflect::set(&passes, &pass.name().into(), &JsValue::from(ms));
+            }
+        }
+        let _ = js_sys::Reflect::set(&obj, &"passes".into(), &passes);
+        obj.into()
+    })
+}
+
+/// Total GPU bytes allocated, per-resource breakdown, and the budget they are
+/// measured against.
+#[wasm_bindgen]
+pub fn get_memory_usage() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        if let Some(ref app) = *borrow {
+            crate::memory::usage_to_js(app)
+        } else {
+            JsValue::NULL
+        }
+    })
+}
+
+/// Memory budget used for warnings (WebGPU does not expose VRAM size).
+#[wasm_bindgen]
+pub fn set_memory_budget_mb(mb: u32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.memory.set_budget_mb(mb);
+            let allocated: u64 = crate::memory::current_allocations(app).iter().map(|(_, b)| b).sum();
+            if let Some(warning) = app.memory.check_usage(allocated) {
+                app.pending_memory_warning = Some(warning);
+            }
+        }
+    });
+    crate::emit_memory_warning();
+}
+
+/// Present mode: 0 auto vsync (default), 1 auto no-vsync (benchmarks),
+/// 2 fifo, 3 fifo-relaxed, 4 immediate, 5 mailbox. Returns the mode in
+/// effect as a string, or null if the request was rejected.
+#[wasm_bindgen]
+pub fn set_present_mode(mode: u32) -> JsValue {
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let Some(ref mut app) = *borrow else {
+            return JsValue::NULL;
+        };
+        let Some(mode) = crate::gpu::present_mode_from_u32(mode) else {
+            web_sys::console::warn_1(&format!("Unknown present mode {mode}").into());
+            return JsValue::NULL;
+        };
+        match app.gpu.set_present_mode(mode) {
+            Ok(effective) => JsValue::from(format!("{effective:?}")),
+            Err(e) => {
+                web_sys::console::warn_1(&e.into());
+                JsValue::NULL
+            }
+        }
+    })
+}
+
+/// Switch the canvas format by WebGPU name ("bgra8unorm", "rgba16float",
+/// ...), or "auto" for the default sRGB-preferring choice. The ray marcher's
+/// output encoding follows the format.
+#[wasm_bindgen]
+pub fn set_surface_format(name: &str) -> bool {
+    APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let Some(ref mut app) = *borrow else {
+            return false;
+        };
+        let format = if name == "auto" {
+            crate::gpu::default_surface_format(&app.gpu.surface_formats)
+        } else {
+            crate::gpu::parse_format(name)
+        };
+        let Some(format) = format else {
+            web_sys::console::warn_1(&format!("Unknown surface format '{name}'").into());
+            return false;
+        };
+        if let Err(e) = app.gpu.set_surface_format(format) {
+            web_sys::console::warn_1(&e.into());
+            return false;
+        }
+        app.renderer.set_surface_format(&app.gpu.device, format);
+        app.last_drawn_camera = None;
+        true
+    })
+}
+
+/// Current format, present mode and output transfer, plus what the surface
+/// supports.
+#[wasm_bindgen]
+pub fn get_surface_info() -> JsValue {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        let Some(ref app) = *borrow else {
+            return JsValue::NULL;
+        };
+        let gpu = &app.gpu;
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"format".into(), &JsValue::from(crate::gpu::format_name(gpu.surface_config.format)));
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"present_mode".into(),
+            &JsValue::from(format!("{:?}", gpu.surface_config.present_mode)),
+        );
+        let transfer = match app.renderer.output_transfer() {
+            renderer::OutputTransfer::Encoded => "encoded",
+            renderer::OutputTransfer::Linear => "linear",
+        };
+        let _ = js_sys::Reflect::set(&obj, &"output_transfer".into(), &JsValue::from(transfer));
+        let formats = js_sys::Array::new();
+        for f in &gpu.surface_formats {
+            formats.push(&JsValue::from(crate::gpu::format_name(*f)));
+        }
+        let _ = js_sys::Reflect::set(&obj, &"formats".into(), &formats);
+        let modes = js_sys::Array::new();
+        for m in &gpu.present_modes {
+            modes.push(&JsValue::from(format!("{m:?}")));
+        }
+        let _ = js_sys::Reflect::set(&obj, &"present_modes".into(), &modes);
+        obj.into()
+    })
+}
+
+/// Schedule a nutrient bloom: every `period` ticks (at tick `phase` of the
+/// period) a fraction `amplitude` of the empty voxels in the box
+/// (x0, y0, z0)..=(x1, y1, z1) become nutrient. Returns the number of
+/// scheduled blooms.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn add_nutrient_bloom(period: u32, phase: u32, amplitude: f32, x0: u32, y0: u32, z0: u32, x1: u32, y1: u32, z1: u32) -> u32 {
+    let bloom = |gs: u32| types::NutrientBloom {
+        period,
+        phase,
+        amplitude,
+        min: (x0.min(x1), y0.min(y1), z0.min(z1)),
+        max: (x0.max(x1).min(gs - 1), y0.max(y1).min(gs - 1), z0.max(z1).min(gs - 1)),
+    };
+    let count = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        borrow.as_mut().map(|app| {
+            let b = bloom(app.sim_engine.grid_size());
+            app.sim_engine.blooms.push(b);
+            app.sim_engine.blooms.len() as u32
+        })
+    });
+    count
+        .or_else(|| {
+            crate::fallback::with(|f| {
+                let b = bloom(f.sim.grid_size());
+                f.sim.blooms.push(b);
+                f.sim.blooms.len() as u32
+            })
+        })
+        .unwrap_or(0)
+}
+
+fn set_goal_params(params: &mut types::SimParams, min: (u32, u32, u32), max: (u32, u32, u32)) {
+    params.goal_min_x = min.0 as f32;
+    params.goal_min_y = min.1 as f32;
+    params.goal_min_z = min.2 as f32;
+    params.goal_max_x = max.0 as f32;
+    params.goal_max_y = max.1 as f32;
+    params.goal_max_z = max.2 as f32;
+}
+
+/// Mark the goal zone (x0, y0, z0)..=(x1, y1, z1) and start scoring species
+/// against it: reaching the zone, then holding `min_population` cells in it
+/// for `hold_ticks` ticks. Replaces any previous zone and resets scores.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn set_goal_zone(x0: u32, y0: u32, z0: u32, x1: u32, y1: u32, z1: u32, hold_ticks: u32, min_population: u32) {
+    let min = (x0.min(x1), y0.min(y1), z0.min(z1));
+    let max = (x0.max(x1), y0.max(y1), z0.max(z1));
+    let rules = types::ChallengeRules { hold_ticks, min_population, ..Default::default() };
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            set_goal_params(&mut app.sim_engine.params, min, max);
+            app.challenge = Some(types::ChallengeTracker::new(rules));
+        }
+    });
+    crate::fallback::with(|f| {
+        set_goal_params(&mut f.sim.params, min, max);
+        f.challenge = Some(types::ChallengeTracker::new(rules));
+    });
+}
+
+#[wasm_bindgen]
+pub fn clear_goal_zone() {
+    let defaults = types::SimParams::default();
+    let clear = |params: &mut types::SimParams| {
+        params.goal_min_x = defaults.goal_min_x;
+        params.goal_min_y = defaults.goal_min_y;
+        params.goal_min_z = defaults.goal_min_z;
+        params.goal_max_x = defaults.goal_max_x;
+        params.goal_max_y = defaults.goal_max_y;
+        params.goal_max_z = defaults.goal_max_z;
+    };
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            clear(&mut app.sim_engine.params);
+            app.challenge = None;
+        }
+    });
+    crate::fallback::with(|f| {
+        clear(&mut f.sim.params);
+        f.challenge = None;
+    });
+}
+
+fn set_dilation_params(params: &mut types::SimParams, min: (f32, f32, f32), max: (f32, f32, f32), factor: f32) {
+    params.dilation_min_x = min.0;
+    params.dilation_min_y = min.1;
+    params.dilation_min_z = min.2;
+    params.dilation_max_x = max.0;
+    params.dilation_max_y = max.1;
+    params.dilation_max_z = max.2;
+    params.dilation_factor = factor;
+}
+
+fn apply_dilation(min: (f32, f32, f32), max: (f32, f32, f32), factor: f32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            set_dilation_params(&mut app.sim_engine.params, min, max, factor);
+            app.texture_stale = true;
+        }
+    });
+    crate::fallback::with(|f| {
+        set_dilation_params(&mut f.sim.params, min, max, factor);
+        f.mark_dirty();
+    });
+}
+
+/// Slow time in the box (x0, y0, z0)..=(x1, y1, z1): cells there run one
+/// tick in every `factor` (clamped to 1..=`MAX_DILATION_FACTOR`), and the
+/// zone is tinted in the plain view. Replaces any previous zone.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn set_time_dilation(x0: u32, y0: u32, z0: u32, x1: u32, y1: u32, z1: u32, factor: u32) {
+    let min = (x0.min(x1) as f32, y0.min(y1) as f32, z0.min(z1) as f32);
+    let max = (x0.max(x1) as f32, y0.max(y1) as f32, z0.max(z1) as f32);
+    apply_dilation(min, max, factor.clamp(1, types::MAX_DILATION_FACTOR) as f32);
+}
+
+#[wasm_bindgen]
+pub fn clear_time_dilation() {
+    let d = types::SimParams::default();
+    apply_dilation(
+        (d.dilation_min_x, d.dilation_min_y, d.dilation_min_z),
+        (d.dilation_max_x, d.dilation_max_y, d.dilation_max_z),
+        d.dilation_factor,
+    );
+}
+
+fn start_scenario(scenario: types::Scenario) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.scenario = Some(types::ScenarioRunner::new(scenario.clone()));
+        }
+    });
+    crate::fallback::with(|f| {
+        f.scenario = Some(types::ScenarioRunner::new(scenario.clone()));
+        f.scenario_advance = 0;
+    });
+}
+
+/// Run a guided scenario script (see `types::scenario` for the format),
+/// replacing any running one. Returns the step count.
+#[wasm_bindgen]
+pub fn load_scenario(text: &str) -> Result<u32, JsValue> {
+    let scenario = types::Scenario::parse(text).map_err(|e| JsValue::from_str(&e))?;
+    let steps = scenario.steps.len() as u32;
+    start_scenario(scenario);
+    Ok(steps)
+}
+
+/// Run the built-in onboarding scenario.
+#[wasm_bindgen]
+pub fn start_tutorial() {
+    if let Ok(scenario) = types::Scenario::parse(types::TUTORIAL_SCENARIO) {
+        start_scenario(scenario);
+    }
+}
+
+/// Satisfy the running scenario's `wait continue` step.
+#[wasm_bindgen]
+pub fn scenario_continue() {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            if let Some(ref mut runner) = app.scenario {
+                runner.press_continue();
+            }
+        }
+    });
+    crate::fallback::with(|f| {
+        if let Some(ref mut runner) = f.scenario {
+            runner.press_continue();
+        }
+    });
+}
+
+#[wasm_bindgen]
+pub fn stop_scenario() {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.scenario = None;
+        }
+    });
+    crate::fallback::with(|f| {
+        f.scenario = None;
+        f.scenario_advance = 0;
+    });
+}
+
+/// `{title, step, steps, message, waiting, finished}` for the running
+/// scenario, or null. `waiting` is the awaited condition in script form
+/// (`"continue"`, `"population >= 50"`, ...) or null.
+#[wasm_bindgen]
+pub fn get_scenario_state() -> JsValue {
+    let runner = APP
+        .with(|app| app.borrow().as_ref().map(|app| app.scenario.clone()))
+        .or_else(|| crate::fallback::with(|f| f.scenario.clone()))
+        .flatten();
+    let Some(r) = runner else {
+        return JsValue::NULL;
+    };
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"title".into(), &JsValue::from(r.title()));
+    let _ = js_sys::Reflect::set(&obj, &"step".into(), &JsValue::from(r.step() as u32));
+    let _ = js_sys::Reflect::set(&obj, &"steps".into(), &JsValue::from(r.step_count() as u32));
+    let _ = js_sys::Reflect::set(&obj, &"message".into(), &JsValue::from(r.message()));
+    let waiting = r.waiting_for().map_or(JsValue::NULL, |c| JsValue::from(c.describe()));
+    let _ = js_sys::Reflect::set(&obj, &"waiting".into(), &waiting);
+    let _ = js_sys::Reflect::set(&obj, &"finished".into(), &JsValue::from(r.finished()));
+    obj.into()
+}
+
+#[wasm_bindgen]
+pub fn clear_nutrient_blooms() {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            app.sim_engine.blooms.clear();
+        }
+    });
+    crate::fallback::with(|f| f.sim.blooms.clear());
+}
+
+#[wasm_bindgen]
+pub fn get_grid_size() -> u32 {
+    APP.with(|app| {
+        let borrow = app.borrow();
+        if let Some(ref app) = *borrow {
+            app.sim_engine.grid_size()
+        } else {
+            crate::fallback::with(|f| f.sim.grid_size()).unwrap_or(0)
+        }
+    })
+}
+
+#[wasm_bindgen]
+pub fn set_param(name: &str, value: f32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let engine = &mut app.sim_engine;
+            set_sim_param(&mut engine.params, name, value);
+            set_rule_param(&mut engine.rules, name, value);
+            if let Some(applied) = clamped_param(&engine.params, &engine.rules, name, value) {
+                engine.log.warn(engine.tick_count(), types::Subsystem::Params, format!("{name} = {value} clamped to {applied}"));
+            }
+        }
+    });
+    crate::fallback::with(|f| {
+        set_sim_param(&mut f.sim.params, name, value);
+        set_rule_param(&mut f.sim.rules, name, value);
+        if let Some(applied) = clamped_param(&f.sim.params, &f.sim.rules, name, value) {
+            let tick = f.sim.tick_count();
+            f.sim.log.warn(tick, types::Subsystem::Params, format!("{name} = {value} clamped to {applied}"));
+        }
+    });
+}
+
+/// Protect `species_id` from starvation and predation, or lift its
+/// protection if it has it. Returns whether the species is protected now.
+#[wasm_bindgen]
+pub fn protect_species(species_id: u16) -> Result<bool, JsValue> {
+    let toggle = |rules: &mut types::RuleParams, log: &mut types::EngineLog, tick: u32| {
+        let protected = rules.toggle_protected(species_id).map_err(|e| JsValue::from_str(&e))?;
+        let verb = if protected { "protected" } else { "unprotected" };
+        log.info(tick, types::Subsystem::Params, format!("species {species_id} {verb}"));
+        Ok(protected)
+    };
+    let result = APP.with(|app| {
+        let mut borrow = app.borrow_mut();
+        let app = borrow.as_mut()?;
+        let engine = &mut app.sim_engine;
+        let tick = engine.tick_count();
+        Some(toggle(&mut engine.rules, &mut engine.log, tick))
+    });
+    result
+        .or_else(|| {
+            crate::fallback::with(|f| {
+                let tick = f.sim.tick_count();
+                toggle(&mut f.sim.rules, &mut f.sim.log, tick)
+            })
+        })
+        .unwrap_or_else(|| Err(JsValue::from_str("not initialized")))
+}
+
+/// Species ids currently protected by `protect_species`.
+#[wasm_bindgen]
+pub fn get_protected_species() -> Vec<u16> {
+    let protected = APP.with(|app| app.borrow().as_ref().map(|app| app.sim_engine.rules.protected().collect()));
+    protected.or_else(|| crate::fallback::with(|f| f.sim.rules.protected().collect())).unwrap_or_default()
+}
+
+/// The value in effect for `name` when it differs from the `value` just set.
+fn clamped_param(params: &types::SimParams, rules: &types::RuleParams, name: &str, value: f32) -> Option<f32> {
+    let applied = match name {
+        "diffusion_substeps" => params.substep_count() as f32,
+        "mutation_rate_min" => rules.mutator.min as f32,
+        "mutation_rate_max" => rules.mutator.max as f32,
+        _ => return None,
+    };
+    (applied != value).then_some(applied)
+}
+
+/// Mutation-rate bounds and the arbitration policy live beside SimParams,
+/// not in it.
+fn set_rule_param(rules: &mut types::RuleParams, name: &str, value: f32) {
+    let bounds = &mut rules.mutator;
+    let byte = value.clamp(0.0, 255.0) as u8;
+    match name {
+        "mutation_rate_min" => *bounds = types::MutatorBounds::new(byte, bounds.max.max(byte)),
+        "mutation_rate_max" => *bounds = types::MutatorBounds::new(bounds.min.min(byte), byte),
+        "arbitration_policy" => {
+            if let Some(policy) = types::ArbitrationPolicy::from_u32(value.max(0.0) as u32) {
+                rules.arbitration = policy;
+            }
+        }
+        _ => {}
+    }
+}
+
+fn set_sim_param(params: &mut types::SimParams, name: &str, value: f32) {
+    match name {
+        "dt" => params.dt = value,
+        "nutrient_spawn_rate" => params.nutrient_spawn_rate = value,
+        "waste_decay_ticks" => params.waste_decay_ticks = value,
+        "nutrient_recycle_rate" => params.nutrient_recycle_rate = value,
+        "movement_energy_cost" => params.movement_energy_cost = value,
+        "base_ambient_temp" => params.base_ambient_temp = value,
+        "metabolic_cost_base" => params.metabolic_cost_base = value,
+        "replication_energy_min" => params.replication_energy_min = value,
+        "energy_from_nutrient" => params.energy_from_nutrient = value,
+        "energy_from_source" => params.energy_from_source = value,
+        "diffusion_rate" => params.diffusion_rate = value,
+        "temp_sensitivity" => params.temp_sensitivity = value,
+        "predation_energy_fraction" => params.predation_energy_fraction = value,
+        "max_energy" => params.max_energy = value,
+        "crowding_threshold" => params.crowding_threshold = value,
+        "crowding_cost" => params.crowding_cost = value,
+        "crowding_blocks_replication" => params.crowding_blocks_replication = value,
+        "nutrient_pattern" => params.nutrient_pattern = value,
+        "nutrient_capacity" => params.nutrient_capacity = value,
+        "nutrient_waste_boost" => params.nutrient_waste_boost = value,
+        "nutrient_front_speed" => params.nutrient_front_speed = value,
+        "pheromone_deposit" => params.pheromone_deposit = value,
+        "pheromone_decay" => params.pheromone_decay = value,
+        "pheromone_spread" => params.pheromone_spread = value,
+        "pheromone_response" => params.pheromone_response = value,
+        "dormancy_temp" => params.dormancy_temp = value,
+        "dormancy_cost_scale" => params.dormancy_cost_scale = value,
+        "heat_shock_temp" => params.heat_shock_temp = value,
+        "heat_shock_damage" => params.heat_shock_damage = value,
+        "storage_capacity_bonus" => params.storage_capacity_bonus = value,
+        "storage_metabolic_cost" => params.storage_metabolic_cost = value,
+        "thermotaxis_response" => params.thermotaxis_response = value,
+        "toxin_secretion_rate" => params.toxin_secretion_rate = value,
+        "toxin_secretion_cost" => params.toxin_secretion_cost = value,
+        "toxin_damage" => params.toxin_damage = value,
+        "toxin_decay" => params.toxin_decay = value,
+        "thermal_boundary" => params.thermal_boundary = value,
+        "diffusion_substeps" => params.diffusion_substeps = value,
+        _ => {}
+    }
+}
+
+#[wasm_bindgen]
+pub fn on_mouse_down(canvas_x: f32, canvas_y: f32, canvas_w: f32, canvas_h: f32) {
+    APP.with(|app| {
+        if let Some(ref mut app) = *app.borrow_mut() {
+            let nx = canvas_x / canvas_w;
+            let ny = canvas_y / canvas_h;
+
+            // Clicks on the minimap move the camera target there instead
+            if let Some((x, z)) = app.renderer.minimap_hit(nx, ny) {
+                app.camera.target.x = x;
+                app.camera.target.z = z;
+                return;
+            }
+            if app.current_tool == Tool::None {
+                return;
+            }
+
+            let gs = app.sim_engine.grid_size();
+
+            if let Some((x, y, z)) = ray_cast_grid(&app.camera, nx, ny, gs) {
+                if let Some(kind) = app.current_tool.shape_kind() {
+                    let shape = app.shape_params.shape(kind, (x, y, z));
+                    let cmds = shape.fill_commands(app.shape_params.voxel_type, gs);
+                    app.queued_commands.extend(symmetric(app.edit_symmetry, &cmds, gs));
+                } else if let Some(cmd) = app.current_tool.command(x, y, z, app.brush_radius, app.thermal_source) {
+                    app.pending_commands.extend(app.edit_symmetry.apply(&cmd, gs));
+                }
+            }
+        }
+    });
+    crate::fallback::with(|f| {
+        if let Some((x, y, z)) = f.cell_at(canvas_x, canvas_y, canvas_w, canvas_h) {
+            let gs = f.sim.grid_size();
+            if let Some(kind) = f.current_tool.shape_kind() {
+                let shape = f.shape_params.shape(kind, (x, y, z));
+                let cmds = shape.fill_commands(f.shape_params.voxel_type, gs);
+                f.queued_commands.extend(symmetric(f.edit_symmetry, &cmds, gs));
+            } else if let Some(cmd) = f.current_tool.command(x, y, z, f.brush_radius, f.thermal_source) {
+                f.pending_commands.extend(f.edit_symmetry.apply(&cmd, gs));
+            }
+        }
+    });
+}
+
+/// CPU ray cast: intersect screen point with grid AABB, return nearest grid cell.
+fn ray_cast_grid(camera: &renderer::camera::Camera, nx: f32, ny: f32, grid_size: u32) -> Option<(u32, u32, u32)> {
+    let (origin, dir) = camera.screen_ray(nx, ny)?;
+    let gs = grid_size as f32;
 
     // Ray-AABB slab intersection with [0, gs]^3
     let mut t_min = f32::NEG_INFINITY;
@@ -0,0 +1,105 @@
+use types::{AuxBudget, AuxPass, AuxPlan};
+
+/// Per-frame budget planner for auxiliary GPU work and tick submission.
+///
+/// Simulation ticks always run; `types::AuxBudget` decides which optional
+/// passes (occupancy readback, minimap heatmap, stats reduction + readback,
+/// render-texture refresh) fit this frame from the smoothed frame interval
+/// and each pass's measured cost.
+pub struct FrameBudget {
+    pub aux: AuxBudget,
+    /// Smoothed cost of one tick in ms: frame interval / ticks that frame
+    /// ran. Includes render and vsync idle time, so it is an upper bound and
+    /// errs toward smaller submission chunks.
+    pub tick_ms_estimate: f32,
+    /// Fixed ticks per queue submission; 0 = adaptive.
+    pub fixed_chunk_ticks: u32,
+    prev_frame_ticks: u32,
+}
+
+/// EMA weight for the newest per-tick cost.
+const AVG_ALPHA: f32 = 0.1;
+
+/// GPU time one command buffer should hold when chunking adaptively.
+const CHUNK_TARGET_MS: f32 = 8.0;
+
+/// Adaptive submission chunk bounds, in ticks.
+const MIN_CHUNK_TICKS: u32 = 1;
+const MAX_CHUNK_TICKS: u32 = 32;
+
+/// Chunk size before any tick cost has been measured.
+pub const DEFAULT_CHUNK_TICKS: u32 = 8;
+
+impl Default for FrameBudget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameBudget {
+    pub fn new() -> Self {
+        Self {
+            aux: AuxBudget::new(),
+            tick_ms_estimate: CHUNK_TARGET_MS / DEFAULT_CHUNK_TICKS as f32,
+            fixed_chunk_ticks: 0,
+            prev_frame_ticks: 0,
+        }
+    }
+
+    pub fn set_target_frame_ms(&mut self, ms: f32) {
+        self.aux.set_target_frame_ms(ms);
+    }
+
+    /// Feed the interval that followed the previous frame, attributing it to
+    /// the ticks that frame ran. Call once per frame, before any early return.
+    pub fn observe_frame(&mut self, dt: f32) {
+        if self.prev_frame_ticks > 0 {
+            let frame_ms = (dt * 1000.0).clamp(0.0, 1000.0);
+            let per_tick = frame_ms / self.prev_frame_ticks as f32;
+            self.tick_ms_estimate += (per_tick - self.tick_ms_estimate) * AVG_ALPHA;
+        }
+        self.prev_frame_ticks = 0;
+    }
+
+    /// Ticks run this frame, for the next `observe_frame`.
+    pub fn record_ticks(&mut self, ticks: u32) {
+        self.prev_frame_ticks = ticks;
+    }
+
+    pub fn set_chunk_ticks(&mut self, ticks: u32) {
+        self.fixed_chunk_ticks = ticks;
+    }
+
+    /// Ticks to encode per queue submission. Splitting long batches keeps
+    /// each command buffer short (TDR safety) and lets the GPU start on the
+    /// first chunk while later ones are still being encoded.
+    pub fn submit_chunk_ticks(&self) -> u32 {
+        // Each tick of a submit takes its own TickParams slot
+        if self.fixed_chunk_ticks > 0 {
+            return self.fixed_chunk_ticks.min(sim_core::uniform::TICK_SLOTS);
+        }
+        let ticks = CHUNK_TARGET_MS / self.tick_ms_estimate.max(0.01);
+        (ticks as u32).clamp(MIN_CHUNK_TICKS, MAX_CHUNK_TICKS)
+    }
+
+    pub fn is_over_budget(&self) -> bool {
+        self.aux.is_over_budget()
+    }
+
+    /// Plan this frame's auxiliary work; `wanted` flags the passes with
+    /// work to do, indexed by `AuxPass`.
+    pub fn plan(&mut self, dt: f32, wanted: [bool; 4]) -> AuxPlan {
+        self.aux.plan(dt, wanted)
+    }
+
+    /// Record a run of `pass` that started at `now_ms()` = `start`
+    /// (host-side encode cost).
+    pub fn record_since(&mut self, pass: AuxPass, start: f64) {
+        self.aux.record_cost(pass, (now_ms() - start) as f32);
+    }
+}
+
+/// `performance.now()`, or 0 without a window.
+pub fn now_ms() -> f64 {
+    web_sys::window().and_then(|w| w.performance()).map_or(0.0, |p| p.now())
+}
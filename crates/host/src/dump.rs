@@ -0,0 +1,272 @@
+//! Chunked full-grid readback for `dump_world_async`. One chunk of the
+//! voxel read buffer is copied to a chunk-sized staging buffer per frame and
+//! handed to JS once mapped, so no single map of the whole grid stalls the
+//! frame loop. Ticks hold until the dump finishes so every chunk comes from
+//! the same state.
+//!
+//! Stream layout: the voxel read buffer (the grid in `grid_index` order, or
+//! the brick pool in sparse mode), then in sparse mode the brick table as
+//! little-endian u32.
+//!
+//! `export_vox` and `get_view_snapshot` run the same readback but keep the
+//! chunks and encode them once the last one arrives.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+use crate::readback::{MapStatus, ReadbackState, READBACK_TIMEOUT_FRAMES};
+
+/// Requested chunk sizes are clamped to this range.
+pub const MIN_DUMP_CHUNK_BYTES: u64 = 64 * 1024;
+pub const MAX_DUMP_CHUNK_BYTES: u64 = 64 * 1024 * 1024;
+/// Chunks are whole voxels and meet wgpu's copy and map alignment.
+const CHUNK_ALIGN: u64 = 256;
+
+/// Chunk size for a requested `chunk_size` in bytes.
+pub fn chunk_bytes(requested: u32) -> u64 {
+    (requested as u64).clamp(MIN_DUMP_CHUNK_BYTES, MAX_DUMP_CHUNK_BYTES) / CHUNK_ALIGN * CHUNK_ALIGN
+}
+
+/// JS callbacks waiting on dump progress, queued while the app is borrowed.
+pub enum DumpEvent {
+    Chunk { on_chunk: js_sys::Function, bytes: Vec<u8>, offset: u64, total: u64 },
+    Done { resolve: js_sys::Function, summary: js_sys::Object },
+    Failed { reject: js_sys::Function, reason: String },
+}
+
+/// Call the queued callbacks: `on_chunk(bytes, offset, total)` per chunk,
+/// then resolve or reject the promise.
+pub fn deliver(events: Vec<DumpEvent>) {
+    for event in events {
+        match event {
+            DumpEvent::Chunk { on_chunk, bytes, offset, total } => {
+                let array = js_sys::Uint8Array::from(&bytes[..]);
+                let _ = on_chunk.call3(&JsValue::UNDEFINED, &array, &JsValue::from(offset as f64), &JsValue::from(total as f64));
+            }
+            DumpEvent::Done { resolve, summary } => {
+                let _ = resolve.call1(&JsValue::UNDEFINED, &summary);
+            }
+            DumpEvent::Failed { reject, reason } => {
+                let _ = reject.call1(&JsValue::UNDEFINED, &JsValue::from(reason));
+            }
+        }
+    }
+}
+
+/// `{tick, grid_size, sparse, bytes, voxel_bytes, brick_table_bytes}`;
+/// `table_bytes` is `None` for a dense world.
+fn summary(tick: u32, grid_size: u32, voxel_bytes: u64, table_bytes: Option<u64>) -> js_sys::Object {
+    let obj = js_sys::Object::new();
+    let table = table_bytes.unwrap_or(0);
+    let _ = js_sys::Reflect::set(&obj, &"tick".into(), &JsValue::from(tick));
+    let _ = js_sys::Reflect::set(&obj, &"grid_size".into(), &JsValue::from(grid_size));
+    let _ = js_sys::Reflect::set(&obj, &"sparse".into(), &JsValue::from(table_bytes.is_some()));
+    let _ = js_sys::Reflect::set(&obj, &"bytes".into(), &JsValue::from((voxel_bytes + table) as f64));
+    let _ = js_sys::Reflect::set(&obj, &"voxel_bytes".into(), &JsValue::from(voxel_bytes as f64));
+    let _ = js_sys::Reflect::set(&obj, &"brick_table_bytes".into(), &JsValue::from(table as f64));
+    obj
+}
+
+/// Events streaming an in-memory dense grid (the CPU fallback) in chunks.
+pub fn dense_events(
+    bytes: Vec<u8>,
+    chunk_bytes: u64,
+    tick: u32,
+    grid_size: u32,
+    on_chunk: js_sys::Function,
+    resolve: js_sys::Function,
+) -> Vec<DumpEvent> {
+    let total = bytes.len() as u64;
+    let mut events: Vec<DumpEvent> = bytes
+        .chunks(chunk_bytes as usize)
+        .enumerate()
+        .map(|(i, chunk)| DumpEvent::Chunk {
+            on_chunk: on_chunk.clone(),
+            bytes: chunk.to_vec(),
+            offset: i as u64 * chunk_bytes,
+            total,
+        })
+        .collect();
+    events.push(DumpEvent::Done { resolve, summary: summary(tick, grid_size, total, None) });
+    events
+}
+
+/// Where mapped chunks go.
+pub enum DumpSink {
+    /// `on_chunk(bytes, offset, total)` per chunk.
+    Stream(js_sys::Function),
+    /// Gathered voxel bytes, encoded as `.vox` once complete.
+    Vox(Vec<u8>),
+    /// Gathered voxel bytes, encoded as a view snapshot with blocks of
+    /// `factor`³ cells once complete.
+    ViewSnapshot { voxels: Vec<u8>, factor: u32 },
+}
+
+pub struct WorldDump {
+    staging: wgpu::Buffer,
+    chunk_bytes: u64,
+    /// Bytes of the voxel read buffer to stream.
+    voxel_bytes: u64,
+    /// Next voxel byte to copy.
+    offset: u64,
+    /// Length of the chunk in staging.
+    inflight: u64,
+    pub state: ReadbackState,
+    ready: Rc<Cell<MapStatus>>,
+    wait_frames: u32,
+    /// Appended after the voxels in sparse mode.
+    brick_table: Option<Vec<u32>>,
+    tick: u32,
+    grid_size: u32,
+    sink: DumpSink,
+    resolve: js_sys::Function,
+    reject: js_sys::Function,
+}
+
+impl WorldDump {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: &wgpu::Device,
+        chunk_bytes: u64,
+        voxel_bytes: u64,
+        brick_table: Option<Vec<u32>>,
+        tick: u32,
+        grid_size: u32,
+        sink: DumpSink,
+        resolve: js_sys::Function,
+        reject: js_sys::Function,
+    ) -> Self {
+        let chunk_bytes = chunk_bytes.min(voxel_bytes.next_multiple_of(CHUNK_ALIGN));
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("world_dump_staging"),
+            size: chunk_bytes,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            staging,
+            chunk_bytes,
+            voxel_bytes,
+            offset: 0,
+            inflight: 0,
+            state: ReadbackState::Idle,
+            ready: Rc::new(Cell::new(MapStatus::Pending)),
+            wait_frames: 0,
+            brick_table,
+            tick,
+            grid_size,
+            sink,
+            resolve,
+            reject,
+        }
+    }
+
+    fn total_bytes(&self) -> u64 {
+        self.voxel_bytes + self.brick_table.as_ref().map_or(0, |t| t.len() as u64 * 4)
+    }
+
+    /// Copy the next chunk of `voxel_buf` into staging unless one is in
+    /// flight.
+    pub fn encode(&mut self, encoder: &mut wgpu::CommandEncoder, voxel_buf: &wgpu::Buffer) {
+        if self.state != ReadbackState::Idle || self.offset >= self.voxel_bytes {
+            return;
+        }
+        self.inflight = self.chunk_bytes.min(self.voxel_bytes - self.offset);
+        encoder.copy_buffer_to_buffer(voxel_buf, self.offset, &self.staging, 0, self.inflight);
+        self.state = ReadbackState::CopyIssued;
+    }
+
+    /// Map the chunk copied this frame, once its encoder was submitted.
+    pub fn request_map(&mut self) {
+        if self.state == ReadbackState::CopyIssued {
+            self.ready = crate::readback::request_map(&self.staging);
+            self.wait_frames = 0;
+            self.state = ReadbackState::MapRequested;
+        }
+    }
+
+    /// Hand a mapped chunk to JS. Returns true once the dump has finished
+    /// or failed.
+    pub fn poll(&mut self, events: &mut Vec<DumpEvent>) -> bool {
+        if self.state != ReadbackState::MapRequested {
+            return false;
+        }
+        match self.ready.get() {
+            MapStatus::Mapped => {
+                let data = self.staging.slice(..self.inflight).get_mapped_range();
+                let bytes = data.to_vec();
+                drop(data);
+                self.staging.unmap();
+                self.state = ReadbackState::Idle;
+                let total = self.total_bytes();
+                match self.sink {
+                    DumpSink::Stream(ref on_chunk) => {
+                        events.push(DumpEvent::Chunk { on_chunk: on_chunk.clone(), bytes, offset: self.offset, total });
+                    }
+                    DumpSink::Vox(ref mut voxels) | DumpSink::ViewSnapshot { ref mut voxels, .. } => voxels.extend(bytes),
+                }
+                self.offset += self.inflight;
+                if self.offset < self.voxel_bytes {
+                    return false;
+                }
+                match self.sink {
+                    DumpSink::Stream(ref on_chunk) => {
+                        if let Some(ref table) = self.brick_table {
+                            let bytes = bytemuck::cast_slice(table).to_vec();
+                            events.push(DumpEvent::Chunk { on_chunk: on_chunk.clone(), bytes, offset: self.offset, total });
+                        }
+                        events.push(DumpEvent::Done { resolve: self.resolve.clone(), summary: self.summary() });
+                    }
+                    DumpSink::Vox(ref voxels) => {
+                        let cells = types::dump_cells(voxels, self.grid_size, self.brick_table.as_deref());
+                        match types::encode_vox(self.grid_size, cells) {
+                            Ok(vox) => {
+                                let summary = js_sys::Uint8Array::from(&vox[..]).into();
+                                events.push(DumpEvent::Done { resolve: self.resolve.clone(), summary });
+                            }
+                            Err(e) => self.fail(events, &e),
+                        }
+                    }
+                    DumpSink::ViewSnapshot { ref voxels, factor } => {
+                        let cells = types::dump_cells(voxels, self.grid_size, self.brick_table.as_deref());
+                        let snapshot = types::encode_view_snapshot(self.grid_size, factor, self.tick, cells);
+                        let summary = js_sys::Uint8Array::from(&snapshot[..]).into();
+                        events.push(DumpEvent::Done { resolve: self.resolve.clone(), summary });
+                    }
+                }
+                true
+            }
+            MapStatus::Failed => {
+                self.fail(events, "chunk readback failed");
+                true
+            }
+            MapStatus::Pending => {
+                self.wait_frames += 1;
+                if self.wait_frames < READBACK_TIMEOUT_FRAMES {
+                    return false;
+                }
+                self.fail(events, "chunk readback timed out");
+                true
+            }
+        }
+    }
+
+    /// Abandon the dump and reject its promise with `reason`.
+    pub fn fail(&mut self, events: &mut Vec<DumpEvent>, reason: &str) {
+        if self.state == ReadbackState::MapRequested && self.ready.get() != MapStatus::Failed {
+            self.staging.unmap();
+        }
+        self.state = ReadbackState::Idle;
+        events.push(DumpEvent::Failed { reject: self.reject.clone(), reason: format!("World dump aborted: {reason}") });
+    }
+
+    fn summary(&self) -> js_sys::Object {
+        let table_bytes = self.brick_table.as_ref().map(|t| t.len() as u64 * 4);
+        summary(self.tick, self.grid_size, self.voxel_bytes, table_bytes)
+    }
+
+    pub fn staging_size(&self) -> u64 {
+        self.staging.size()
+    }
+}
@@ -0,0 +1,498 @@
+//! Degraded mode for browsers without WebGPU.
+//!
+//! Runs the CPU port of the tick (`sim_core::cpu`) at 32³ and draws a
+//! front-to-back projection along -z into a 2D canvas. Only the core
+//! controls (pause, step, tick rate, presets, tools, stats) are wired up;
+//! everything GPU-specific stays a no-op because `APP` is never set.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use sim_core::cpu::{CpuSim, CPU_GRID_SIZE};
+use sim_core::SimStats;
+use types::{Palette, RenderStyle, TempRange, Voxel, VoxelType, CVD_BLUE, CVD_ORANGE, CVD_YELLOW, REDUCED_FLICKER_BLEND};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::bridge::Tool;
+use crate::report::{CapabilityReport, InitAttempt};
+use crate::timing::FrameTiming;
+
+thread_local! {
+    pub static FALLBACK: RefCell<Option<FallbackApp>> = const { RefCell::new(None) };
+}
+
+/// Upper bound on CPU ticks per frame so a slow device can't stall the page.
+const MAX_CPU_TICKS_PER_FRAME: u32 = 2;
+
+/// Background behind empty columns (matches the ray marcher's clear color).
+const BACKGROUND: [f32; 3] = [0.05, 0.05, 0.08];
+
+pub struct FallbackApp {
+    pub sim: CpuSim,
+    pub timing: FrameTiming,
+    pub current_tool: Tool,
+    pub brush_radius: u32,
+    pub thermal_source: u32,
+    pub pending_commands: Vec<types::Command>,
+    pub queued_commands: VecDeque<types::Command>,
+    pub shape_params: types::ShapeParams,
+    pub edit_symmetry: types::EditSymmetry,
+    pub overlay_mode: u32,
+    /// Genome byte the trait overlay (mode 4) colors protocells by.
+    pub overlay_trait: u32,
+    pub style: RenderStyle,
+    /// Ramps the projection is mapped through instead of the built-in colors.
+    pub transfer: Option<types::TransferFunction>,
+    /// Temperature min/max of the last drawn frame.
+    pub latest_temp_range: Option<TempRange>,
+    pub latest_stats: Option<SimStats>,
+    pub sonifier: Option<types::Sonifier>,
+    pub latest_synth: Option<types::SynthParams>,
+    pub challenge: Option<types::ChallengeTracker>,
+    pub ecology: types::EcologyLog,
+    pub lineage: types::LineageTree,
+    pub pending_extinctions: Vec<types::EcologyEvent>,
+    /// Genomes sampled with each stats update (0 = off).
+    pub genome_sample_n: u32,
+    pub latest_genome_sample: Option<types::GenomeSampleSet>,
+    pub scenario: Option<types::ScenarioRunner>,
+    /// Ticks owed to a scenario `advance` step, run even while paused.
+    pub scenario_advance: u32,
+    /// Recorded from `pixels` (grid_size² per frame); `scale` is ignored.
+    pub recording: Option<crate::recording::Recording>,
+    pub capability: CapabilityReport,
+    canvas: HtmlCanvasElement,
+    ctx: CanvasRenderingContext2d,
+    /// grid_size² canvas the projection is written to before scaling up.
+    image_canvas: HtmlCanvasElement,
+    image_ctx: CanvasRenderingContext2d,
+    pixels: Vec<u8>,
+    dirty: bool,
+}
+
+fn context_2d(canvas: &HtmlCanvasElement) -> Result<CanvasRenderingContext2d, String> {
+    canvas
+        .get_context("2d")
+        .map_err(|_| "2d context request failed".to_string())?
+        .ok_or_else(|| "canvas has no 2d context (already bound to WebGPU?)".to_string())?
+        .dyn_into::<CanvasRenderingContext2d>()
+        .map_err(|_| "2d context has unexpected type".to_string())
+}
+
+/// Start the CPU fallback on `canvas`. `gpu_error` is why WebGPU init failed.
+pub fn init(canvas: HtmlCanvasElement, gpu_error: String) -> Result<(), String> {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("no document")?;
+    let ctx = context_2d(&canvas)?;
+    let image_canvas: HtmlCanvasElement = document
+        .create_element("canvas")
+        .map_err(|_| "failed to create offscreen canvas")?
+        .dyn_into()
+        .map_err(|_| "offscreen element is not a canvas")?;
+    let gs = CPU_GRID_SIZE;
+    image_canvas.set_width(gs);
+    image_canvas.set_height(gs);
+    let image_ctx = context_2d(&image_canvas)?;
+
+    let mut sim = CpuSim::new(gs);
+    sim.initialize_grid_with_preset(0);
+    let latest_stats = Some(sim.stats());
+
+    let label = format!("cpu {gs}\u{00b3}");
+    let capability = CapabilityReport {
+        tier: None,
+        sparse_available: false,
+        sparse: false,
+        grid_size: gs,
+        reason: format!("WebGPU unavailable ({gpu_error}); running CPU fallback"),
+        attempts: vec![
+            InitAttempt { label: "webgpu".to_string(), error: Some(gpu_error) },
+            InitAttempt { label, error: None },
+        ],
+    };
+
+    let mut timing = FrameTiming::new();
+    timing.set_max_catch_up(MAX_CPU_TICKS_PER_FRAME);
+
+    let app = FallbackApp {
+        sim,
+        timing,
+        current_tool: Tool::None,
+        brush_radius: 0,
+        thermal_source: 0,
+        pending_commands: Vec::new(),
+        queued_commands: VecDeque::new(),
+        shape_params: types::ShapeParams::default(),
+        edit_symmetry: types::EditSymmetry::default(),
+        overlay_mode: 0,
+        overlay_trait: 0,
+        style: RenderStyle::default(),
+        transfer: None,
+        latest_temp_range: None,
+        latest_stats,
+        sonifier: None,
+        latest_synth: None,
+        challenge: None,
+        ecology: types::EcologyLog::new(),
+        lineage: types::LineageTree::new(),
+        pending_extinctions: Vec::new(),
+        genome_sample_n: 0,
+        latest_genome_sample: None,
+        scenario: None,
+        scenario_advance: 0,
+        recording: None,
+        capability,
+        canvas,
+        ctx,
+        image_canvas,
+        image_ctx,
+        pixels: vec![0; (gs * gs * 4) as usize],
+        dirty: true,
+    };
+    FALLBACK.with(|cell| *cell.borrow_mut() = Some(app));
+    web_sys::console::warn_1(&"Primordium running in degraded CPU mode".into());
+    Ok(())
+}
+
+/// Run `f` against the fallback app if degraded mode is active.
+pub fn with<R>(f: impl FnOnce(&mut FallbackApp) -> R) -> Option<R> {
+    FALLBACK.with(|cell| cell.borrow_mut().as_mut().map(f))
+}
+
+pub fn frame(dt: f32) {
+    with(|app| {
+        app.timing.update(dt);
+        if let Some(ref mut runner) = app.scenario {
+            let stats = crate::scenario_stats(app.sim.tick_count(), app.latest_stats.as_ref());
+            for action in runner.update(&stats, app.sim.grid_size()) {
+                match action {
+                    types::ScenarioAction::Apply(cmd) => app.pending_commands.push(cmd),
+                    types::ScenarioAction::Advance(n) => app.scenario_advance += n,
+                }
+            }
+        }
+        let mut ticks = app.timing.ticks_due(dt).min(MAX_CPU_TICKS_PER_FRAME);
+        if app.scenario_advance > 0 {
+            ticks = app.scenario_advance.min(MAX_CPU_TICKS_PER_FRAME);
+            app.scenario_advance -= ticks;
+            app.timing.refund_ticks();
+        }
+        let mut commands: Vec<types::Command> = app.pending_commands.drain(..).collect();
+        commands.extend(app.sim.scheduled_commands(ticks));
+        if ticks > 0 {
+            let room = types::MAX_COMMANDS_PER_TICK.saturating_sub(commands.len()).min(app.queued_commands.len());
+            commands.extend(app.queued_commands.drain(..room));
+        }
+        for i in 0..ticks {
+            let cmds = if i == 0 { &commands[..] } else { &[] };
+            app.sim.tick(cmds);
+        }
+        if ticks > 0 {
+            app.timing.record_ticks(ticks);
+            let stats = app.sim.stats();
+            app.sim.end_flow_window();
+            if let Some(ref mut challenge) = app.challenge {
+                challenge.update(app.sim.tick_count(), &stats.goal_histogram);
+            }
+            if app.genome_sample_n > 0 {
+                let n = app.genome_sample_n;
+                let population = app.latest_stats.as_ref().map_or(u32::MAX, |s| s.population);
+                let tick = app.sim.tick_count();
+                let threshold = types::sample_threshold(n, population);
+                app.latest_genome_sample =
+                    Some(types::GenomeSampleSet::sample(app.sim.voxels(), CPU_GRID_SIZE, tick, n, threshold, tick));
+            }
+            crate::record_ecology(&mut app.ecology, &mut app.lineage, &mut app.pending_extinctions, app.sim.tick_count(), &stats);
+            if let Some(ref mut sonifier) = app.sonifier {
+                app.latest_synth = Some(sonifier.update(
+                    app.sim.tick_count(),
+                    CPU_GRID_SIZE.pow(3),
+                    stats.population,
+                    stats.toxin_deaths,
+                    &stats.species_histogram,
+                ));
+            }
+            app.latest_stats = Some(stats);
+            app.dirty = true;
+        } else if !commands.is_empty() {
+            // Paused: keep edits for the next tick instead of dropping them
+            app.pending_commands = commands;
+        }
+        if app.dirty && !app.timing.hidden {
+            app.draw();
+        }
+        let sim_time = app.sim.sim_time_seconds();
+        let hidden = app.timing.hidden;
+        if let Some(ref mut rec) = app.recording {
+            // Hidden frames aren't drawn; their output frames carry over
+            if rec.advance(sim_time) && !hidden {
+                let repeat = rec.take_pending();
+                rec.push(sim_time, repeat, app.pixels.clone());
+            }
+        }
+    });
+}
+
+fn lerp3(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+}
+
+/// Color-vision-safe material color, mirroring `cvd_material_rgb`.
+fn cvd_material_rgb(t: VoxelType, palette: Palette) -> [f32; 3] {
+    let protan = palette == Palette::Protanopia;
+    match t {
+        VoxelType::Nutrient => [0.0, 0.62, 0.45],
+        VoxelType::EnergySource if protan => [1.0, 0.96, 0.55],
+        VoxelType::EnergySource => [0.94, 0.89, 0.26],
+        VoxelType::Waste => [0.40, 0.26, 0.36],
+        VoxelType::HeatSource if protan => CVD_ORANGE,
+        VoxelType::HeatSource => [0.84, 0.37, 0.0],
+        VoxelType::ColdSource => CVD_BLUE,
+        _ => [0.5, 0.5, 0.5],
+    }
+}
+
+/// RGBA for one voxel, mirroring update_render_texture.wgsl. The
+/// temperature overlay maps `temp_scale` onto its ramp.
+#[allow(clippy::too_many_arguments)]
+fn voxel_color(
+    v: &Voxel,
+    temp: f32,
+    overlay: u32,
+    overlay_trait: u32,
+    palette: Palette,
+    temp_scale: TempRange,
+    max_energy: f32,
+    waste_decay: f32,
+) -> [f32; 4] {
+    let mut color = match v.voxel_type {
+        VoxelType::Empty => [0.0, 0.0, 0.0, 0.0],
+        VoxelType::Wall => [0.5, 0.5, 0.5, 1.0],
+        VoxelType::Nutrient => [0.2, 0.8, 0.2, 0.8],
+        VoxelType::EnergySource => [1.0, 0.95, 0.2, 1.0],
+        VoxelType::Protocell => {
+            let hue = (v.species_id as f32 * 0.618_034).fract();
+            let val = (v.energy as f32 / max_energy).clamp(0.1, 1.0);
+            let sat = if v.genome.predation_capability() > 128 { 1.0 } else { 0.7 };
+            let mut rgb = hsv_to_rgb(hue, sat, val);
+            if palette != Palette::Default {
+                let ramp = lerp3(CVD_BLUE, CVD_YELLOW, hue);
+                let gray = (ramp[0] + ramp[1] + ramp[2]) / 3.0;
+                rgb = lerp3([gray; 3], ramp, sat).map(|c| c * val);
+            }
+            if v.is_dormant() {
+                for (c, frost) in rgb.iter_mut().zip([0.6, 0.75, 0.95]) {
+                    *c += (frost - *c) * 0.6;
+                }
+            }
+            let [r, g, b] = rgb;
+            [r, g, b, 1.0]
+        }
+        VoxelType::Waste => [0.35, 0.2, 0.1, (1.0 - v.age as f32 / waste_decay).clamp(0.2, 0.9)],
+        VoxelType::HeatSource => [1.0, 0.4, 0.1, 1.0],
+        VoxelType::ColdSource => [0.3, 0.6, 1.0, 1.0],
+    };
+    if palette != Palette::Default && !matches!(v.voxel_type, VoxelType::Empty | VoxelType::Protocell) {
+        let [r, g, b] = cvd_material_rgb(v.voxel_type, palette);
+        color = [r, g, b, color[3]];
+    }
+    match overlay {
+        1 => color = types::temperature_color(temp_scale.normalize(temp), palette),
+        2 => {
+            let e = v.energy as f32 / max_energy;
+            color = types::energy_color(e);
+            if v.voxel_type == VoxelType::Empty {
+                color[3] = 0.0;
+            }
+        }
+        3 => {
+            if v.voxel_type == VoxelType::Protocell {
+                color = types::POPULATION_COLORS[0];
+            } else if v.voxel_type != VoxelType::Empty {
+                color = types::POPULATION_COLORS[1];
+            }
+        }
+        4 => {
+            if v.voxel_type == VoxelType::Protocell {
+                color = types::trait_color(v.genome.bytes[overlay_trait.min(15) as usize] as f32 / 255.0);
+            } else if v.voxel_type != VoxelType::Empty {
+                color = types::POPULATION_COLORS[1];
+            }
+        }
+        5 => {
+            if v.voxel_type == VoxelType::Protocell {
+                color = types::species_color(v.species_id, palette);
+            } else if v.voxel_type != VoxelType::Empty {
+                color = types::POPULATION_COLORS[1];
+            }
+        }
+        _ => {}
+    }
+    color
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let c = v * s;
+    let hp = h * 6.0;
+    let x = c * (1.0 - (hp % 2.0 - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match hp as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    [r + m, g + m, b + m]
+}
+
+impl FallbackApp {
+    /// Composite each (x, y) column from z = max down to 0 and blit the
+    /// result, scaled to fit, onto the main canvas.
+    fn draw(&mut self) {
+        let gs = self.sim.grid_size();
+        self.latest_temp_range = TempRange::of(self.sim.temperatures().iter().copied());
+        let temp_scale = self.temp_scale();
+        for y in 0..gs {
+            for x in 0..gs {
+                let mut rgb = [0.0f32; 3];
+                let mut alpha = 0.0f32;
+                for z in (0..gs).rev() {
+                    let c = self.cell_color(types::grid_index(x, y, z, gs), temp_scale);
+                    if c[3] <= 0.0 {
+                        continue;
+                    }
+                    let w = (1.0 - alpha) * c[3];
+                    for k in 0..3 {
+                        rgb[k] += w * c[k];
+                    }
+                    alpha += w;
+                    if alpha > 0.98 {
+                        break;
+                    }
+                }
+                // Flip y so +y points up, as in the 3D view
+                let p = (((gs - 1 - y) * gs + x) * 4) as usize;
+                for k in 0..3 {
+                    let mut value = (rgb[k] + (1.0 - alpha) * BACKGROUND[k]).clamp(0.0, 1.0);
+                    if self.style.reduced_flicker {
+                        // Soft highlight knee and temporal easing, as on the GPU path
+                        let over = (value - 0.7).max(0.0);
+                        value = value.min(0.7) + over / (1.0 + 2.0 * over);
+                        let prev = self.pixels[p + k] as f32 / 255.0;
+                        value = prev + (value - prev) * REDUCED_FLICKER_BLEND;
+                    }
+                    self.pixels[p + k] = (value * 255.0) as u8;
+                }
+                self.pixels[p + 3] = 255;
+            }
+        }
+
+        let Ok(image) = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&self.pixels), gs, gs) else {
+            return;
+        };
+        let _ = self.image_ctx.put_image_data(&image, 0.0, 0.0);
+
+        let (cw, ch) = (self.canvas.width() as f64, self.canvas.height() as f64);
+        let side = cw.min(ch);
+        self.ctx.set_image_smoothing_enabled(false);
+        self.ctx.set_fill_style_str("#0d0d14");
+        self.ctx.fill_rect(0.0, 0.0, cw, ch);
+        let _ = self.ctx.draw_image_with_html_canvas_element_and_dw_and_dh(
+            &self.image_canvas,
+            (cw - side) / 2.0,
+            (ch - side) / 2.0,
+            side,
+            side,
+        );
+        self.dirty = false;
+    }
+
+    /// Range the temperature overlay is stretched over.
+    fn temp_scale(&self) -> TempRange {
+        match self.latest_temp_range {
+            Some(range) if !self.style.fixed_temp_range => range,
+            _ => TempRange::UNIT,
+        }
+    }
+
+    /// RGBA of cell `idx` under the current overlay and style, or through
+    /// the transfer function when one is set.
+    fn cell_color(&self, idx: usize, temp_scale: TempRange) -> [f32; 4] {
+        let (v, temp) = (&self.sim.voxels()[idx], self.sim.temperatures()[idx]);
+        let max_energy = self.sim.params.max_energy.max(1.0);
+        let waste_decay = self.sim.params.waste_decay_ticks.max(1.0);
+        match &self.transfer {
+            Some(tf) => {
+                let (row, scalar) =
+                    types::transfer_input(v, temp_scale.normalize(temp), self.overlay_mode, max_energy, waste_decay);
+                tf.sample(row, scalar)
+            }
+            None => {
+                let (overlay, trait_byte) = (self.overlay_mode, self.overlay_trait);
+                let color = voxel_color(v, temp, overlay, trait_byte, self.style.palette, temp_scale, max_energy, waste_decay);
+                let gs = self.sim.grid_size() as usize;
+                let pos = ((idx % gs) as u32, (idx / gs % gs) as u32, (idx / (gs * gs)) as u32);
+                let p = &self.sim.params;
+                if self.overlay_mode != 0 || !p.in_dilation_zone(pos) {
+                    return color;
+                }
+                let on_face = [
+                    (pos.0, p.dilation_min_x, p.dilation_max_x),
+                    (pos.1, p.dilation_min_y, p.dilation_max_y),
+                    (pos.2, p.dilation_min_z, p.dilation_max_z),
+                ]
+                .iter()
+                .any(|&(c, lo, hi)| c as f32 == lo || c as f32 == hi);
+                types::dilation_tinted(color, on_face)
+            }
+        }
+    }
+
+    /// RGBA rows of slice `index` along `axis`, as `export_slices` images.
+    pub fn slice_image(&self, axis: types::SliceAxis, index: u32) -> Vec<u8> {
+        let gs = self.sim.grid_size();
+        let copy = types::SliceCopy { axis, index, grid_size: gs, transfer: false };
+        let temp_scale = self.temp_scale();
+        let mut out = Vec::with_capacity((gs * gs * 4) as usize);
+        for row in 0..gs {
+            for col in 0..gs {
+                let (x, y, z) = copy.cell(col, row);
+                let c = self.cell_color(types::grid_index(x, y, z, gs), temp_scale);
+                out.extend(c.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+            }
+        }
+        out
+    }
+
+    /// Map a canvas click to the top-most occupied voxel of that column
+    /// (or the mid plane if the column is empty).
+    pub fn cell_at(&self, canvas_x: f32, canvas_y: f32, canvas_w: f32, canvas_h: f32) -> Option<(u32, u32, u32)> {
+        let gs = self.sim.grid_size();
+        let side = canvas_w.min(canvas_h);
+        if side <= 0.0 {
+            return None;
+        }
+        let u = (canvas_x - (canvas_w - side) / 2.0) / side;
+        let v = (canvas_y - (canvas_h - side) / 2.0) / side;
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return None;
+        }
+        let x = ((u * gs as f32) as u32).min(gs - 1);
+        let y = gs - 1 - ((v * gs as f32) as u32).min(gs - 1);
+        let voxels = self.sim.voxels();
+        let z = (0..gs)
+            .rev()
+            .find(|&z| voxels[types::grid_index(x, y, z, gs)].voxel_type != VoxelType::Empty)
+            .unwrap_or(gs / 2);
+        Some((x, y, z))
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+}
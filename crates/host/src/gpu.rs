@@ -1,7 +1,7 @@
 use wgpu;
 use web_sys::HtmlCanvasElement;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GpuTier {
     Sparse256, // 256³ sparse — discrete GPU with ≥ 50 MB buffer limits
     High,      // 128³ dense  — discrete GPU with sufficient buffer limits
@@ -31,9 +31,184 @@ pub struct GpuContext {
     pub surface_config: wgpu::SurfaceConfiguration,
     pub tier: GpuTier,
     pub grid_size: u32,
+    pub adapter_info: wgpu::AdapterInfo,
+    /// Limits the adapter supports.
+    pub adapter_limits: wgpu::Limits,
+    /// Limits the device was granted.
+    pub limits: wgpu::Limits,
+    pub features: wgpu::Features,
+    /// Driver pipeline cache, when the backend supports one.
+    pub pipeline_cache: Option<wgpu::PipelineCache>,
+    /// Formats and present modes the surface supports.
+    pub surface_formats: Vec<wgpu::TextureFormat>,
+    pub present_modes: Vec<wgpu::PresentMode>,
+}
+
+impl GpuContext {
+    /// Switch present mode. Auto modes resolve to the best supported mode;
+    /// explicit modes must be supported. Returns the mode actually used.
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) -> Result<wgpu::PresentMode, String> {
+        let resolved = resolve_present_mode(mode, &self.present_modes)
+            .ok_or_else(|| format!("present mode {mode:?} not supported (have {:?})", self.present_modes))?;
+        self.surface_config.present_mode = resolved;
+        self.surface.configure(&self.device, &self.surface_config);
+        Ok(resolved)
+    }
+
+    /// Reconfigure the surface with `format`. The caller must rebuild any
+    /// pipelines that render to it.
+    pub fn set_surface_format(&mut self, format: wgpu::TextureFormat) -> Result<(), String> {
+        if !self.surface_formats.contains(&format) {
+            return Err(format!("surface format {} not supported", format_name(format)));
+        }
+        self.surface_config.format = format;
+        self.surface.configure(&self.device, &self.surface_config);
+        Ok(())
+    }
+}
+
+/// Default surface format: first sRGB format if offered, else the preferred one.
+pub fn default_surface_format(formats: &[wgpu::TextureFormat]) -> Option<wgpu::TextureFormat> {
+    formats.iter().find(|f| f.is_srgb()).or(formats.first()).copied()
+}
+
+/// Resolve `Auto*` modes the way wgpu does, so callers can report the result.
+fn resolve_present_mode(mode: wgpu::PresentMode, supported: &[wgpu::PresentMode]) -> Option<wgpu::PresentMode> {
+    use wgpu::PresentMode::*;
+    let candidates: &[wgpu::PresentMode] = match mode {
+        AutoVsync => &[FifoRelaxed, Fifo],
+        AutoNoVsync => &[Immediate, Mailbox, Fifo],
+        _ => return supported.contains(&mode).then_some(mode),
+    };
+    candidates.iter().copied().find(|m| supported.contains(m))
+}
+
+/// Bridge id → present mode: 0 auto vsync, 1 auto no-vsync, 2 fifo,
+/// 3 fifo-relaxed, 4 immediate, 5 mailbox.
+pub fn present_mode_from_u32(v: u32) -> Option<wgpu::PresentMode> {
+    Some(match v {
+        0 => wgpu::PresentMode::AutoVsync,
+        1 => wgpu::PresentMode::AutoNoVsync,
+        2 => wgpu::PresentMode::Fifo,
+        3 => wgpu::PresentMode::FifoRelaxed,
+        4 => wgpu::PresentMode::Immediate,
+        5 => wgpu::PresentMode::Mailbox,
+        _ => return None,
+    })
+}
+
+/// Surface formats by their WebGPU names.
+const FORMAT_NAMES: [(wgpu::TextureFormat, &str); 6] = [
+    (wgpu::TextureFormat::Bgra8Unorm, "bgra8unorm"),
+    (wgpu::TextureFormat::Bgra8UnormSrgb, "bgra8unorm-srgb"),
+    (wgpu::TextureFormat::Rgba8Unorm, "rgba8unorm"),
+    (wgpu::TextureFormat::Rgba8UnormSrgb, "rgba8unorm-srgb"),
+    (wgpu::TextureFormat::Rgba16Float, "rgba16float"),
+    (wgpu::TextureFormat::Rgb10a2Unorm, "rgb10a2unorm"),
+];
+
+pub fn format_name(format: wgpu::TextureFormat) -> &'static str {
+    FORMAT_NAMES
+        .iter()
+        .find(|(f, _)| *f == format)
+        .map_or("other", |(_, name)| *name)
+}
+
+pub fn parse_format(name: &str) -> Option<wgpu::TextureFormat> {
+    FORMAT_NAMES.iter().find(|(_, n)| *n == name).map(|(f, _)| *f)
+}
+
+/// Which adapter to run on, from `init_with_options`.
+#[derive(Debug, Clone, Copy)]
+pub struct AdapterOptions {
+    pub power_preference: wgpu::PowerPreference,
+    pub force_fallback: bool,
+    /// Index into `distinct_adapters`; `None` takes whatever the browser
+    /// returns for `power_preference`.
+    pub adapter_index: Option<u32>,
+}
+
+impl Default for AdapterOptions {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback: false,
+            adapter_index: None,
+        }
+    }
+}
+
+/// WebGPU power preference names → wgpu.
+pub fn parse_power_preference(name: &str) -> Option<wgpu::PowerPreference> {
+    match name {
+        "high-performance" => Some(wgpu::PowerPreference::HighPerformance),
+        "low-power" => Some(wgpu::PowerPreference::LowPower),
+        "none" => Some(wgpu::PowerPreference::None),
+        _ => None,
+    }
 }
 
-pub async fn init_gpu(canvas: HtmlCanvasElement) -> Result<GpuContext, String> {
+fn same_adapter(a: &wgpu::AdapterInfo, b: &wgpu::AdapterInfo) -> bool {
+    a.name == b.name && a.vendor == b.vendor && a.device == b.device && a.device_type == b.device_type
+}
+
+/// WebGPU can't enumerate adapters, so the distinct adapters reachable via
+/// the preferred, high-performance and low-power requests stand in for a
+/// list (typically dGPU then iGPU on dual-GPU laptops).
+async fn distinct_adapters(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'static>,
+    options: &AdapterOptions,
+) -> Vec<wgpu::Adapter> {
+    let mut adapters: Vec<wgpu::Adapter> = Vec::new();
+    let prefs = [
+        options.power_preference,
+        wgpu::PowerPreference::HighPerformance,
+        wgpu::PowerPreference::LowPower,
+    ];
+    for power_preference in prefs {
+        let Ok(adapter) = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference,
+                compatible_surface: Some(surface),
+                force_fallback_adapter: options.force_fallback,
+            })
+            .await
+        else {
+            continue;
+        };
+        let info = adapter.get_info();
+        if !adapters.iter().any(|a| same_adapter(&a.get_info(), &info)) {
+            adapters.push(adapter);
+        }
+    }
+    adapters
+}
+
+async fn request_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'static>,
+    options: &AdapterOptions,
+) -> Result<wgpu::Adapter, String> {
+    let Some(index) = options.adapter_index else {
+        return instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: options.power_preference,
+                compatible_surface: Some(surface),
+                force_fallback_adapter: options.force_fallback,
+            })
+            .await
+            .map_err(|e| format!("No suitable GPU adapter: {e}"));
+    };
+    let adapters = distinct_adapters(instance, surface, options).await;
+    let count = adapters.len();
+    adapters
+        .into_iter()
+        .nth(index as usize)
+        .ok_or_else(|| format!("adapter_index {index} out of range ({count} adapters available)"))
+}
+
+pub async fn init_gpu(canvas: HtmlCanvasElement, options: AdapterOptions) -> Result<GpuContext, String> {
     let width = canvas.client_width().max(1) as u32;
     let height = canvas.client_height().max(1) as u32;
 
@@ -46,14 +221,7 @@ pub async fn init_gpu(canvas: HtmlCanvasElement) -> Result<GpuContext, String> {
         .create_surface(wgpu::SurfaceTarget::Canvas(canvas))
         .map_err(|e| format!("Failed to create surface: {e}"))?;
 
-    let adapter = instance
-        .request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        })
-        .await
-        .map_err(|e| format!("No suitable GPU adapter: {e}"))?;
+    let adapter = request_adapter(&instance, &surface, &options).await?;
 
     let info = adapter.get_info();
     web_sys::console::log_1(
@@ -64,7 +232,8 @@ pub async fn init_gpu(canvas: HtmlCanvasElement) -> Result<GpuContext, String> {
         .into(),
     );
 
-    let limits = adapter.limits();
+    let adapter_limits = adapter.limits();
+    let limits = &adapter_limits;
     web_sys::console::log_1(
         &format!(
             "Max buffer size: {} MB, max storage buffer: {} MB",
@@ -74,18 +243,13 @@ pub async fn init_gpu(canvas: HtmlCanvasElement) -> Result<GpuContext, String> {
         .into(),
     );
 
-    // Detect GPU tier based on adapter type and buffer limits
-    let tier = detect_gpu_tier(&info, &limits);
-    let grid_size = tier.grid_size();
-    web_sys::console::log_1(
-        &format!("GPU tier: {:?}, grid size: {}³", tier, grid_size).into(),
-    );
-
     let (device, queue) = adapter
         .request_device(&wgpu::DeviceDescriptor {
             label: Some("primordium_device"),
-            required_features: wgpu::Features::empty(),
-            required_limits: wgpu::Limits::default(),
+            // Pipeline caching is native-only; WebGPU adapters never report it.
+            // Timestamp queries back the optional pass profiler.
+            required_features: adapter.features() & (wgpu::Features::PIPELINE_CACHE | wgpu::Features::TIMESTAMP_QUERY),
+            required_limits: engine_limits(&adapter_limits),
             experimental_features: wgpu::ExperimentalFeatures::default(),
             memory_hints: wgpu::MemoryHints::Performance,
             trace: wgpu::Trace::Off,
@@ -93,13 +257,23 @@ pub async fn init_gpu(canvas: HtmlCanvasElement) -> Result<GpuContext, String> {
         .await
         .map_err(|e| format!("Failed to create device: {e}"))?;
 
+    // Detect GPU tier from what the device was actually granted
+    let limits = device.limits();
+    let tier = detect_gpu_tier(&info, &limits);
+    let grid_size = tier.grid_size();
+    web_sys::console::log_1(
+        &format!(
+            "GPU tier: {:?}, grid size: {}³ (granted storage binding: {} MB)",
+            tier,
+            grid_size,
+            limits.max_storage_buffer_binding_size / (1024 * 1024),
+        )
+        .into(),
+    );
+
     let surface_caps = surface.get_capabilities(&adapter);
-    let format = surface_caps
-        .formats
-        .iter()
-        .find(|f| f.is_srgb())
-        .copied()
-        .unwrap_or(surface_caps.formats[0]);
+    let format = default_surface_format(&surface_caps.formats)
+        .ok_or("Surface reports no supported formats")?;
 
     let surface_config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -117,6 +291,11 @@ pub async fn init_gpu(canvas: HtmlCanvasElement) -> Result<GpuContext, String> {
         &format!("Surface configured: {width}x{height}, format: {format:?}").into(),
     );
 
+    let features = device.features();
+    // Browsers can't persist driver caches, so start empty; native hosts
+    // seed this from `sim_core::pipeline_cache::load` (see primordium-cli).
+    let pipeline_cache = sim_core::pipeline_cache::create(&device, None);
+
     Ok(GpuContext {
         device,
         queue,
@@ -124,9 +303,32 @@ pub async fn init_gpu(canvas: HtmlCanvasElement) -> Result<GpuContext, String> {
         surface_config,
         tier,
         grid_size,
+        features,
+        pipeline_cache,
+        surface_formats: surface_caps.formats,
+        present_modes: surface_caps.present_modes,
+        adapter_info: info,
+        adapter_limits,
+        limits,
     })
 }
 
+/// Largest single buffer the engine will ever allocate. Requesting more than
+/// this from the adapter buys nothing and may reduce driver headroom.
+pub const ENGINE_MAX_BUFFER_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Device limits to request: the WebGPU defaults, with buffer-size limits
+/// raised to what the adapter supports (clamped to what the engine needs).
+/// The defaults cap storage bindings at 128 MB, which blocks larger grids.
+fn engine_limits(adapter: &wgpu::Limits) -> wgpu::Limits {
+    wgpu::Limits {
+        max_buffer_size: adapter.max_buffer_size.min(ENGINE_MAX_BUFFER_BYTES),
+        max_storage_buffer_binding_size: (adapter.max_storage_buffer_binding_size as u64)
+            .min(ENGINE_MAX_BUFFER_BYTES) as u32,
+        ..wgpu::Limits::default()
+    }
+}
+
 fn detect_gpu_tier(info: &wgpu::AdapterInfo, limits: &wgpu::Limits) -> GpuTier {
     if info.device_type == wgpu::DeviceType::IntegratedGpu {
         return GpuTier::Low;
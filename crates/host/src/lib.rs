@@ -1,24 +1,58 @@
 pub mod gpu;
 pub mod timing;
+pub mod budget;
+pub mod report;
+pub mod memory;
 pub mod bridge;
+pub mod fallback;
+pub mod watch;
+pub mod recording;
+pub mod dump;
+pub mod slices;
+pub mod readback;
 
-use std::cell::Cell;
-use std::rc::Rc;
+use std::collections::VecDeque;
 use wasm_bindgen::prelude::*;
 use renderer::camera::Camera;
 use renderer::Renderer;
 use renderer::VoxelPicker;
-use sim_core::SimEngine;
+use sim_core::census::CensusPass;
+use sim_core::genome_sample::GenomeSamplePass;
+use sim_core::temp_range::TempRangePass;
+use sim_core::migrate::MigratePass;
+use sim_core::{GridConfig, SimEngine};
 use sim_core::SimStats;
+use types::{AuxPass, Feature, FeatureSet};
 use timing::FrameTiming;
+use budget::FrameBudget;
 use bridge::Tool;
+use readback::{Channel, Completed, ReadbackFailure, ReadbackManager, ReadbackState};
 
-/// Async readback state machine: Idle -> CopyIssued -> MapRequested -> Ready
-#[derive(Clone, Copy, PartialEq)]
-pub enum ReadbackState {
-    Idle,
-    CopyIssued,
-    MapRequested,
+/// Extra render-texture refreshes after the volume stops changing, so
+/// reduced-flicker easing reaches the final colors (0.8^24 < 0.5%).
+pub const FLICKER_SETTLE_REFRESHES: u32 = 24;
+
+/// Completed picks kept for `get_pick_history`.
+pub const PICK_HISTORY_LEN: usize = 16;
+
+/// Times a failed or timed-out pick is re-issued before it is dropped.
+pub const PICK_MAX_RETRIES: u32 = 2;
+
+/// Ticks between stats readbacks until `set_stats_interval` changes it.
+pub const DEFAULT_STATS_INTERVAL: u32 = 10;
+/// Longest stats interval `set_stats_interval` accepts.
+pub const MAX_STATS_INTERVAL: u32 = 1000;
+
+/// Ticks a pending `step_ticks` request may run per frame.
+pub const STEP_TICKS_PER_FRAME: u32 = 8;
+
+/// A scripted `step_ticks(n)` request awaiting completion.
+pub struct StepRequest {
+    pub remaining: u32,
+    /// Set once the final tick has been encoded with a stats readback;
+    /// the promise resolves when that readback lands.
+    pub awaiting_stats: bool,
+    pub resolve: js_sys::Function,
 }
 
 pub struct App {
@@ -27,24 +61,205 @@ pub struct App {
     pub renderer: Renderer,
     pub camera: Camera,
     pub timing: FrameTiming,
+    pub budget: FrameBudget,
     pub current_tool: Tool,
     pub brush_radius: u32,
+    /// Heat/cold tool strength and lifetime, packed as in `types::pack_thermal_source`.
+    pub thermal_source: u32,
     pub pending_commands: Vec<types::Command>,
+    /// Shape tool boxes, fed to ticks as command slots allow.
+    pub queued_commands: VecDeque<types::Command>,
+    pub shape_params: types::ShapeParams,
+    /// Mirror/rotational images every tool edit is repeated at.
+    pub edit_symmetry: types::EditSymmetry,
     pub overlay_mode: u32,
+    /// Genome byte the trait overlay (mode 4) colors protocells by.
+    pub overlay_trait: u32,
     pub picker: VoxelPicker,
     pub latest_stats: Option<SimStats>,
+    /// Time each frame's last tick on the GPU; see `set_profiling`.
+    pub profiling: bool,
+    pub latest_profile: Option<sim_core::PassTimings>,
+    /// (interval, count) of the engine's keyframe ring; see `set_keyframes`.
+    pub keyframes: (u32, u32),
+    /// Present while audio is enabled; fed every stats readback.
+    pub sonifier: Option<types::Sonifier>,
+    pub latest_synth: Option<types::SynthParams>,
+    /// Guided scenario being stepped each frame; see `load_scenario`.
+    pub scenario: Option<types::ScenarioRunner>,
+    /// Goal-zone challenge scores; `None` while no goal zone is set.
+    pub challenge: Option<types::ChallengeTracker>,
+    /// Species emergence/extinction events, fed every stats readback.
+    pub ecology: types::EcologyLog,
+    /// Species phylogeny, fed with each stats readback's speciations.
+    pub lineage: types::LineageTree,
+    /// Notable extinctions awaiting dispatch; see `emit_extinctions`.
+    pub pending_extinctions: Vec<types::EcologyEvent>,
+    /// Warns once while few pool bricks are free (sparse only).
+    pub pool_alarm: types::PoolAlarm,
+    /// Pool reading that tripped `pool_alarm`, awaiting dispatch; see
+    /// `emit_pool_pressure`.
+    pub pending_pool_warning: Option<(types::BrickPoolStats, u32)>,
+    /// Memory warning awaiting dispatch; see `emit_memory_warning`.
+    pub pending_memory_warning: Option<String>,
     pub pick_requested: bool,
+    /// Click ray (origin, direction) awaiting the GPU hit march.
+    pub pick_ray: Option<(glam::Vec3, glam::Vec3)>,
+    /// Cell to read back; set once the hit march found a voxel.
     pub pick_coords: Option<(u32, u32, u32)>,
-    pub pick_state: ReadbackState,
-    pub pick_ready: Rc<Cell<bool>>,
+    pub pick_retries: u32,
     pub latest_pick: Option<renderer::PickResult>,
+    /// Last `PICK_HISTORY_LEN` picks, oldest first.
+    pub pick_history: VecDeque<renderer::PickResult>,
+    /// Neighborhood radius read back with each pick (dense mode only).
+    pub pick_region_radius: u32,
+    pub pick_region_bounds: Option<renderer::RegionBounds>,
+    pub latest_pick_region: Option<renderer::PickRegion>,
+    /// User annotations drawn as billboards; see `add_marker`.
+    pub markers: types::MarkerSet,
+    /// Picks feed `measure_points` instead of only the inspector.
+    pub measure_mode: bool,
+    /// Up to three picked cells; see `get_measurement`.
+    pub measure_points: Vec<(u32, u32, u32)>,
+    /// Region count issued for the box spanned by `measure_points`.
+    pub measure_query: Option<types::CensusQuery>,
+    pub measure_live: Option<u32>,
+    /// Voxel re-read with every stats readback; see `watch_voxel`.
+    pub watch: Option<watch::VoxelWatch>,
+    /// Tick the in-flight watch copy was taken at.
+    pub watch_tick: u32,
+    /// Frames captured since `start_recording`; see `stop_recording`.
+    pub recording: Option<recording::Recording>,
+    pub census: CensusPass,
+    /// Query waiting for the next frame; see `find_species`.
+    pub census_request: Option<types::CensusQuery>,
+    /// Query whose result is in (or headed for) census staging.
+    pub census_inflight: Option<types::CensusQuery>,
+    pub latest_census: Option<types::CensusResult>,
+    pub genome_sampler: GenomeSamplePass,
+    /// Genomes sampled with each stats readback (0 = off); see `sample_genomes`.
+    pub genome_sample_n: u32,
+    /// Tick and sample size of the in-flight copy.
+    pub genome_sample_inflight: (u32, u32),
+    pub latest_genome_sample: Option<types::GenomeSampleSet>,
+    /// Temperature min/max, reduced before each temperature-overlay refresh
+    /// and with each stats readback; see `get_overlay_legend`.
+    pub temp_range: TempRangePass,
+    pub latest_temp_range: Option<types::TempRange>,
+    /// Chunked readback started by `dump_world_async`; ticks hold meanwhile.
+    pub world_dump: Option<dump::WorldDump>,
+    /// Slice-by-slice readback started by `export_slices`; ticks hold
+    /// meanwhile. Its callbacks go through `dump_events`.
+    pub slice_export: Option<slices::SliceExport>,
+    /// Dump callbacks to run once the app is no longer borrowed.
+    pub dump_events: Vec<dump::DumpEvent>,
+    /// Dense → sparse upgrade waiting on its brick occupancy readback.
+    /// Ticks pause until it lands so the occupancy stays current.
+    pub migration: Option<MigratePass>,
+    /// Pool size for the pending upgrade; 0 = twice the bricks needed.
+    pub migrate_max_bricks: u32,
     pub stats_tick_counter: u32,
-    pub stats_state: ReadbackState,
-    pub stats_ready: Rc<Cell<bool>>,
+    /// Ticks between stats readbacks; see `set_stats_interval`.
+    pub stats_interval: u32,
+    /// Every GPU → CPU readback channel; see `readback_channels`.
+    pub readbacks: ReadbackManager,
+    pub step_requests: VecDeque<StepRequest>,
+    /// Skip acquire/present when nothing visible changed.
+    pub frame_pacing: bool,
+    /// Draw at most once every `frame_skip + 1` frames.
+    pub frame_skip: u32,
+    pub frames_since_draw: u32,
+    pub last_drawn_camera: Option<Camera>,
+    pub last_overlay_mode: u32,
+    /// Volume changed since the render texture was last refreshed.
+    pub texture_stale: bool,
+    /// Refreshes left before reduced-flicker easing has converged.
+    pub flicker_settle: u32,
+    /// Submit each frame's render ahead of its ticks, so the sim works
+    /// while the render is in flight; the picture lags one frame.
+    pub async_overlap: bool,
+    /// The render texture was refreshed after this frame's render
+    /// (`async_overlap`); the next frame draws it.
+    pub texture_undrawn: bool,
+    pub capability: report::CapabilityReport,
+    pub memory: memory::MemoryTracker,
+}
+
+/// GPU bytes for an engine configuration tried during init: engine
+/// buffers + render texture (of colors: no transfer function is set yet).
+fn candidate_bytes(config: GridConfig, features: FeatureSet) -> u64 {
+    config.estimated_bytes(features) + Renderer::estimated_bytes(config.grid_size(), false)
+}
+
+fn candidate_label(config: GridConfig) -> String {
+    match config {
+        GridConfig::Sparse { grid_size, max_bricks } => {
+            format!("sparse {grid_size}\u{00b3} ({max_bricks} max bricks)")
+        }
+        GridConfig::Dense { grid_size } => format!("dense {grid_size}\u{00b3}"),
+    }
 }
 
 #[wasm_bindgen]
 pub async fn init() -> Result<(), JsValue> {
+    init_app(gpu::AdapterOptions::default(), FeatureSet::ALL).await
+}
+
+/// `init` with adapter selection for multi-GPU machines. `options` is an
+/// object with any of `power_preference` ("high-performance", "low-power",
+/// "none"), `force_fallback` (bool), `adapter_index` (number) and
+/// `disable_features` (array of "temperature", "signals", "toxin_field";
+/// low-end GPUs can drop a subsystem instead of slowing the whole sim).
+#[wasm_bindgen]
+pub async fn init_with_options(options: JsValue) -> Result<(), JsValue> {
+    init_app(parse_adapter_options(&options)?, parse_disabled_features(&options)?).await
+}
+
+fn parse_disabled_features(options: &JsValue) -> Result<FeatureSet, JsValue> {
+    let mut features = FeatureSet::ALL;
+    if options.is_undefined() || options.is_null() {
+        return Ok(features);
+    }
+    let disabled = js_sys::Reflect::get(options, &"disable_features".into()).unwrap_or(JsValue::UNDEFINED);
+    if disabled.is_undefined() || disabled.is_null() {
+        return Ok(features);
+    }
+    if !js_sys::Array::is_array(&disabled) {
+        return Err(JsValue::from_str("disable_features must be an array of feature names"));
+    }
+    for name in js_sys::Array::from(&disabled).iter() {
+        let name = name.as_string().unwrap_or_default();
+        let feature = Feature::from_name(&name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown feature '{name}'")))?;
+        features = features.with(feature, false);
+    }
+    Ok(features)
+}
+
+fn parse_adapter_options(options: &JsValue) -> Result<gpu::AdapterOptions, JsValue> {
+    let mut parsed = gpu::AdapterOptions::default();
+    if options.is_undefined() || options.is_null() {
+        return Ok(parsed);
+    }
+    let get = |key: &str| js_sys::Reflect::get(options, &key.into()).unwrap_or(JsValue::UNDEFINED);
+    let power = get("power_preference");
+    if let Some(name) = power.as_string() {
+        parsed.power_preference = gpu::parse_power_preference(&name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown power_preference '{name}'")))?;
+    }
+    if let Some(force) = get("force_fallback").as_bool() {
+        parsed.force_fallback = force;
+    }
+    if let Some(index) = get("adapter_index").as_f64() {
+        if index < 0.0 || index.fract() != 0.0 {
+            return Err(JsValue::from_str(&format!("invalid adapter_index {index}")));
+        }
+        parsed.adapter_index = Some(index as u32);
+    }
+    Ok(parsed)
+}
+
+async fn init_app(adapter_options: gpu::AdapterOptions, features: FeatureSet) -> Result<(), JsValue> {
     // Get canvas from DOM
     let window = web_sys::window().ok_or("no window")?;
     let document = window.document().ok_or("no document")?;
@@ -64,65 +279,91 @@ pub async fn init() -> Result<(), JsValue> {
 
     web_sys::console::log_1(&format!("Canvas: {width}x{height} (dpr={dpr:.2})").into());
 
-    // Initialize GPU
-    let gpu = gpu::init_gpu(canvas).await.map_err(|e| JsValue::from_str(&e))?;
+    // Initialize GPU. Without WebGPU, fall back to the CPU tick at 32³.
+    let gpu = match gpu::init_gpu(canvas.clone(), adapter_options).await {
+        Ok(gpu) => gpu,
+        Err(e) => {
+            web_sys::console::warn_1(&format!("WebGPU init failed: {e}").into());
+            return fallback::init(canvas, e.clone()).map_err(|fallback_err| {
+                JsValue::from_str(&format!("{e}; CPU fallback also failed: {fallback_err}"))
+            });
+        }
+    };
+
+    // Try engine configurations from the detected tier downward:
+    // sparse 256³ (if the tier allows), dense 128/96/64, then an emergency 48³.
+    // Allocation failures are caught with an out-of-memory error scope so a
+    // tier that passes the limit check but can't actually allocate still falls
+    // through to the next one.
+    let mut candidates: Vec<GridConfig> = Vec::new();
+    if gpu.tier.is_sparse() {
+        candidates.push(GridConfig::Sparse { grid_size: 256, max_bricks: 3200 }); // ~10% occupancy budget
+    }
+    let dense_tiers = [128u32, 96, 64, 48];
+    let start_idx = match gpu.grid_size {
+        256 | 128 => 0,
+        96 => 1,
+        _ => 2,
+    };
+    candidates.extend(dense_tiers[start_idx..].iter().map(|&g| GridConfig::Dense { grid_size: g }));
 
-    // Try grid sizes from detected tier downward, including sparse
+    let memory_tracker = memory::MemoryTracker::new(gpu.tier);
     let mut sim_engine = None;
     let mut grid_size = 0u32;
-
-    // If tier supports sparse 256³, try that first
-    if gpu.tier.is_sparse() {
-        let max_bricks = 3200u32; // ~10% occupancy budget
-        web_sys::console::log_1(&format!("Trying sparse 256³ ({max_bricks} max bricks)...").into());
-        match SimEngine::try_new_sparse(&gpu.device, &gpu.queue, 256, max_bricks) {
+    let mut attempts = Vec::new();
+    for candidate in candidates {
+        let label = candidate_label(candidate);
+        web_sys::console::log_1(&format!("Trying {label}...").into());
+        if let Some(warning) = memory_tracker.check_planned(&label, candidate_bytes(candidate, features), 0) {
+            memory::emit_warning(&warning);
+        }
+        gpu.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        let result = SimEngine::builder()
+            .grid(candidate)
+            .features(features)
+            .pipeline_cache(gpu.pipeline_cache.as_ref())
+            .build(&gpu.device, &gpu.queue)
+            .map_err(|e| e.to_string());
+        let oom = gpu.device.pop_error_scope().await;
+        let result = match (result, oom) {
+            (Ok(_), Some(e)) => Err(format!("out of memory: {e}")),
+            (r, _) => r,
+        };
+        match result {
             Ok(engine) => {
-                grid_size = 256;
+                web_sys::console::log_1(&format!("{label} initialized").into());
+                attempts.push(report::InitAttempt { label, error: None });
+                grid_size = candidate.grid_size();
                 sim_engine = Some(engine);
-                web_sys::console::log_1(&"Sparse 256\u{00b3} initialized".into());
+                break;
             }
             Err(e) => {
-                web_sys::console::warn_1(
-                    &format!("Sparse 256³ failed: {}. Falling back to dense...", e).into(),
-                );
-            }
-        }
-    }
-
-    // Dense fallback: try 128, 96, 64
-    if sim_engine.is_none() {
-        let dense_tiers = [128u32, 96, 64];
-        let start_idx = match gpu.grid_size {
-            256 => 0, // sparse failed, start at 128
-            128 => 0,
-            96 => 1,
-            _ => 2,
-        };
-
-        for &tier_size in &dense_tiers[start_idx..] {
-            web_sys::console::log_1(&format!("Trying dense grid {}³...", tier_size).into());
-            match SimEngine::try_new(&gpu.device, &gpu.queue, tier_size) {
-                Ok(engine) => {
-                    grid_size = tier_size;
-                    sim_engine = Some(engine);
-                    web_sys::console::log_1(
-                        &format!("Grid size: {grid_size}\u{00b3}").into(),
-                    );
-                    break;
-                }
-                Err(e) => {
-                    web_sys::console::warn_1(
-                        &format!("Grid {}³ failed: {}. Trying smaller...", tier_size, e).into(),
-                    );
-                }
+                web_sys::console::warn_1(&format!("{label} failed: {e}. Trying next tier...").into());
+                attempts.push(report::InitAttempt { label, error: Some(e) });
             }
         }
     }
 
-    let mut sim_engine = sim_engine.ok_or_else(|| {
+    let sim_engine = sim_engine.ok_or_else(|| {
         JsValue::from_str("Failed to allocate GPU buffers. GPU may lack sufficient memory.")
     })?;
-    sim_engine.initialize_grid(&gpu.queue);
+
+    let reason = match attempts.iter().rfind(|a| a.error.is_some()) {
+        None => format!("{:?} tier default", gpu.tier),
+        Some(failed) => format!(
+            "fell back after {} failed: {}",
+            failed.label,
+            failed.error.as_deref().unwrap_or_default()
+        ),
+    };
+    let capability = report::CapabilityReport {
+        tier: Some(gpu.tier),
+        sparse_available: gpu.tier.is_sparse(),
+        sparse: sim_engine.is_sparse(),
+        grid_size,
+        reason,
+        attempts,
+    };
 
     // Create renderer (sparse variant if engine is sparse)
     let renderer = if sim_engine.is_sparse() {
@@ -138,29 +379,96 @@ pub async fn init() -> Result<(), JsValue> {
     let timing = FrameTiming::new();
 
     let picker = VoxelPicker::new(&gpu.device);
+    let census = CensusPass::new(&gpu.device);
+    let genome_sampler = GenomeSamplePass::new(&gpu.device);
+    let temp_range = TempRangePass::new(&gpu.device);
 
-    let app = App {
+    let mut app = App {
         gpu,
         sim_engine,
         renderer,
         camera,
         timing,
+        budget: FrameBudget::new(),
         current_tool: Tool::None,
         brush_radius: 0,
+        thermal_source: 0,
         pending_commands: Vec::new(),
+        queued_commands: VecDeque::new(),
+        shape_params: types::ShapeParams::default(),
+        edit_symmetry: types::EditSymmetry::default(),
         overlay_mode: 0,
+        overlay_trait: 0,
         picker,
         latest_stats: None,
+        profiling: false,
+        latest_profile: None,
+        keyframes: (0, 0),
+        sonifier: None,
+        latest_synth: None,
+        scenario: None,
+        challenge: None,
+        ecology: types::EcologyLog::new(),
+        lineage: types::LineageTree::new(),
+        pending_extinctions: Vec::new(),
+        pool_alarm: types::PoolAlarm::default(),
+        pending_pool_warning: None,
+        pending_memory_warning: None,
         pick_requested: false,
+        pick_ray: None,
         pick_coords: None,
-        pick_state: ReadbackState::Idle,
-        pick_ready: Rc::new(Cell::new(false)),
+        pick_retries: 0,
         latest_pick: None,
+        pick_history: VecDeque::new(),
+        pick_region_radius: 2,
+        pick_region_bounds: None,
+        latest_pick_region: None,
+        markers: types::MarkerSet::new(),
+        measure_mode: false,
+        measure_points: Vec::new(),
+        measure_query: None,
+        measure_live: None,
+        watch: None,
+        recording: None,
+        watch_tick: 0,
+        census,
+        census_request: None,
+        census_inflight: None,
+        latest_census: None,
+        genome_sampler,
+        genome_sample_n: 0,
+        genome_sample_inflight: (0, 0),
+        latest_genome_sample: None,
+        temp_range,
+        latest_temp_range: None,
+        world_dump: None,
+        slice_export: None,
+        dump_events: Vec::new(),
+        migration: None,
+        migrate_max_bricks: 0,
         stats_tick_counter: 0,
-        stats_state: ReadbackState::Idle,
-        stats_ready: Rc::new(Cell::new(false)),
+        stats_interval: DEFAULT_STATS_INTERVAL,
+        readbacks: readback_channels(),
+        step_requests: VecDeque::new(),
+        frame_pacing: true,
+        frame_skip: 0,
+        frames_since_draw: 0,
+        last_drawn_camera: None,
+        last_overlay_mode: 0,
+        texture_stale: true,
+        flicker_settle: 0,
+        async_overlap: false,
+        texture_undrawn: false,
+        capability,
+        memory: memory_tracker,
     };
 
+    let allocated: u64 = memory::current_allocations(&app).iter().map(|(_, b)| b).sum();
+    web_sys::console::log_1(&format!("GPU memory allocated: {} MB", allocated / (1024 * 1024)).into());
+    if let Some(warning) = app.memory.check_usage(allocated) {
+        memory::emit_warning(&warning);
+    }
+
     bridge::APP.with(|cell| {
         *cell.borrow_mut() = Some(app);
     });
@@ -171,29 +479,181 @@ pub async fn init() -> Result<(), JsValue> {
 
 #[wasm_bindgen]
 pub fn frame(dt: f32) {
+    frame_inner(dt);
+    // Outside the app borrow, so listeners may call back into the bridge
+    let events = bridge::APP
+        .with(|cell| cell.borrow_mut().as_mut().map(|app| std::mem::take(&mut app.pending_extinctions)));
+    let events = events.or_else(|| fallback::with(|f| std::mem::take(&mut f.pending_extinctions)));
+    emit_extinctions(&events.unwrap_or_default());
+    let pool_warning = bridge::APP.with(|cell| cell.borrow_mut().as_mut().and_then(|app| app.pending_pool_warning.take()));
+    if let Some((pool, threshold)) = pool_warning {
+        emit_pool_pressure(&pool, threshold);
+    }
+    emit_memory_warning();
+    let dump_events = bridge::APP.with(|cell| cell.borrow_mut().as_mut().map(|app| std::mem::take(&mut app.dump_events)));
+    dump::deliver(dump_events.unwrap_or_default());
+}
+
+/// Dispatch the memory warning queued under the app borrow, if any. Call
+/// with `APP` released.
+pub fn emit_memory_warning() {
+    let warning = bridge::APP.with(|cell| cell.borrow_mut().as_mut().and_then(|app| app.pending_memory_warning.take()));
+    if let Some(warning) = warning {
+        memory::emit_warning(&warning);
+    }
+}
+
+/// Whether the recording has output frames due, up to the current sim time.
+fn capture_due(app: &mut App) -> bool {
+    match app.recording {
+        Some(ref mut rec) => rec.advance(app.sim_engine.sim_time_seconds()),
+        None => false,
+    }
+}
+
+/// Ray march the volume texture into `surface_texture`, and into the
+/// capture target when a recording frame is due.
+fn draw_frame(app: &mut App, encoder: &mut wgpu::CommandEncoder, surface_texture: &wgpu::SurfaceTexture, capture_due: bool) {
+    let surface_view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    if app.renderer.brick_overlay() != types::BrickOverlay::Off {
+        app.renderer.set_bricks(&app.gpu.device, &app.gpu.queue, &app.sim_engine.brick_ages());
+    }
+    app.renderer.render_frame(encoder, &surface_view, &app.camera, &app.gpu.queue, &app.gpu.device);
+
+    // Re-draw into the capture target for recording
+    if capture_due && app.readbacks.can_issue(Channel::Record) {
+        if let Some(ref mut rec) = app.recording {
+            if app.renderer.encode_capture(encoder, &app.gpu.device) {
+                rec.inflight = Some((app.sim_engine.sim_time_seconds(), rec.take_pending()));
+                if let Some(capture) = app.renderer.capture_target() {
+                    app.readbacks.issue(Channel::Record, capture.staging_buffer());
+                }
+            }
+        }
+    }
+}
+
+fn frame_inner(dt: f32) {
     bridge::APP.with(|cell| {
         let mut borrow = cell.borrow_mut();
         let app = match borrow.as_mut() {
             Some(app) => app,
-            None => return,
+            None => {
+                fallback::frame(dt);
+                return;
+            }
         };
 
         app.timing.update(dt);
-        let ticks_to_run = app.timing.ticks_due(dt);
+        app.budget.observe_frame(dt);
+
+        // Step the guided scenario before this frame's commands are drained
+        if let Some(ref mut runner) = app.scenario {
+            let stats = scenario_stats(app.sim_engine.tick_count(), app.latest_stats.as_ref());
+            for action in runner.update(&stats, app.sim_engine.grid_size()) {
+                match action {
+                    types::ScenarioAction::Apply(cmd) => app.pending_commands.push(cmd),
+                    types::ScenarioAction::Advance(n) => app.step_requests.push_back(StepRequest {
+                        remaining: n,
+                        awaiting_stats: false,
+                        resolve: js_sys::Function::new_no_args(""),
+                    }),
+                }
+            }
+        }
+        let mut ticks_to_run = app.timing.ticks_due(dt);
+        // Ticks hold while a migration, a world dump or a slice export is in
+        // flight
+        let held = !app.readbacks.is_idle(Channel::Occupancy) || app.world_dump.is_some() || app.slice_export.is_some();
+        if held {
+            ticks_to_run = 0;
+            app.timing.refund_ticks();
+        }
+
+        // A pending step_ticks request overrides the timer. Its final tick
+        // waits until the stats staging buffer is free so the readback that
+        // resolves the promise reflects exactly that tick.
+        let mut force_stats = false;
+        if let Some(step) = app.step_requests.front_mut().filter(|_| !held) {
+            let mut n = 0;
+            if !step.awaiting_stats {
+                n = step.remaining.min(STEP_TICKS_PER_FRAME);
+                if n == step.remaining {
+                    if app.readbacks.can_issue(Channel::Stats) {
+                        force_stats = true;
+                        step.awaiting_stats = true;
+                    } else {
+                        n -= 1;
+                    }
+                }
+                step.remaining -= n;
+            }
+            ticks_to_run = n;
+            // The step replaces the timer's ticks, which stay owed
+            app.timing.refund_ticks();
+        }
+
+        // Frame pacing: only acquire/present when the volume changed or the
+        // camera moved, and at most once every `frame_skip + 1` frames.
+        // Headless (page hidden) never draws.
+        let overlay_changed = app.overlay_mode != app.last_overlay_mode;
+        let volume_changed = app.texture_stale
+            || ticks_to_run > 0
+            || overlay_changed
+            || !app.pending_commands.is_empty();
+        let volume_dirty = volume_changed || app.flicker_settle > 0 || app.texture_undrawn;
+        let camera_moved = app.last_drawn_camera.as_ref() != Some(&app.camera);
+        let render_slot = app.frames_since_draw >= app.frame_skip;
+        let draw = !app.timing.hidden
+            && render_slot
+            && (!app.frame_pacing || volume_dirty || camera_moved);
+        let pick_pending = app.pick_requested && app.readbacks.can_issue(Channel::Pick);
+        let census_pending = app.census_request.is_some() && app.readbacks.can_issue(Channel::Census);
+        let dump_pending = app.world_dump.as_ref().is_some_and(|d| d.state == ReadbackState::Idle)
+            || app.slice_export.as_ref().is_some_and(|e| e.state == ReadbackState::Idle);
+        // A sparse upgrade's occupancy readback not yet issued
+        let occupancy_queued = app.migration.is_some() && app.readbacks.is_idle(Channel::Occupancy);
+
+        if !draw && ticks_to_run == 0 && !pick_pending && !census_pending && !dump_pending && !occupancy_queued {
+            app.frames_since_draw = app.frames_since_draw.saturating_add(1);
+            poll_readbacks(app);
+            return;
+        }
 
         // Get surface texture — don't panic on error
-        let surface_texture = match app.gpu.surface.get_current_texture() {
-            Ok(t) => t,
-            Err(wgpu::SurfaceError::Lost) => {
-                app.gpu.surface.configure(&app.gpu.device, &app.gpu.surface_config);
-                return;
+        let surface_texture = if draw {
+            match app.gpu.surface.get_current_texture() {
+                Ok(t) => Some(t),
+                Err(wgpu::SurfaceError::Lost) => {
+                    app.gpu.surface.configure(&app.gpu.device, &app.gpu.surface_config);
+                    return;
+                }
+                Err(_) => return,
             }
-            Err(_) => return,
+        } else {
+            None
         };
 
-        let surface_view = surface_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        // Async overlap: the render goes out in its own submission ahead
+        // of the ticks, drawing the texture the previous frame refreshed
+        let mut drawn_early = false;
+        let surface_texture = match surface_texture {
+            Some(surface_texture) if app.async_overlap => {
+                let mut render_encoder = app.gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("render_encoder"),
+                });
+                let capture_due = capture_due(app);
+                draw_frame(app, &mut render_encoder, &surface_texture, capture_due);
+                app.gpu.queue.submit(std::iter::once(render_encoder.finish()));
+                surface_texture.present();
+                app.last_drawn_camera = Some(app.camera.clone());
+                app.frames_since_draw = 0;
+                app.texture_undrawn = false;
+                drawn_early = true;
+                None
+            }
+            surface_texture => surface_texture,
+        };
 
         let mut encoder = app
             .gpu
@@ -203,119 +663,653 @@ pub fn frame(dt: f32) {
             });
 
         // Drain pending commands for this frame
-        let commands: Vec<types::Command> = app.pending_commands.drain(..).collect();
+        let mut commands: Vec<types::Command> = app.pending_commands.drain(..).collect();
+        commands.extend(app.sim_engine.scheduled_commands(ticks_to_run));
+        if ticks_to_run > 0 {
+            let room = types::MAX_COMMANDS_PER_TICK.saturating_sub(commands.len()).min(app.queued_commands.len());
+            commands.extend(app.queued_commands.drain(..room));
+        }
 
-        // Set overlay mode in params before ticks
-        app.sim_engine.params.overlay_mode = app.overlay_mode as f32;
+        // Sparse LOD measures brick distance from the camera focus
+        app.sim_engine.set_lod_focus(app.camera.target.to_array());
 
-        // Run simulation ticks (commands applied only on first tick)
+        // Overlay mode and trait byte ride in the tick uniform; a change
+        // rewrites the latest slot so the render texture sees it without a tick.
+        app.sim_engine.set_overlay_mode(&app.gpu.queue, app.overlay_mode, app.overlay_trait);
+
+        // Profiling outlives engine rebuilds (resize, sparse switch)
+        if app.profiling && !app.sim_engine.profiling() {
+            app.profiling = app.sim_engine.set_profiling(&app.gpu.device, &app.gpu.queue, true);
+        }
+        let profile_due = app.sim_engine.profiling() && app.readbacks.can_issue(Channel::Profile);
+        // So does the keyframe ring, though not its captures
+        let (interval, count) = app.keyframes;
+        if count > 0 && app.sim_engine.keyframe_config().is_none() {
+            app.sim_engine.set_keyframes(&app.gpu.device, interval, count);
+        }
+
+        // Plan auxiliary passes; sim ticks always take priority
+        let stats_due = ticks_to_run > 0
+            && app.readbacks.can_issue(Channel::Stats)
+            && app.stats_tick_counter + ticks_to_run >= app.stats_interval;
+        let texture_wanted = draw && (volume_dirty || !app.frame_pacing);
+        let heatmap_missing = draw && app.renderer.minimap_heatmap_missing();
+        let heatmap_wanted = draw
+            && (heatmap_missing
+                || app.renderer.minimap_heatmap_stale()
+                || (texture_wanted && app.renderer.minimap_enabled()));
+        let mut plan = app.budget.plan(dt, [occupancy_queued, heatmap_wanted, stats_due, texture_wanted]);
+        if force_stats {
+            plan.force(AuxPass::Stats);
+        }
+        // A minimap without a heatmap would draw nothing
+        if heatmap_missing {
+            plan.force(AuxPass::Heatmap);
+        }
+        let run_stats = plan.runs(AuxPass::Stats);
+
+        // Run simulation ticks (commands applied only on first tick,
+        // stats reduced only on the last tick of a planned readback).
+        // Long batches are submitted in chunks; the last chunk shares the
+        // frame encoder with the render passes.
+        let chunk = app.budget.submit_chunk_ticks();
         for i in 0..ticks_to_run {
             let cmds = if i == 0 { &commands[..] } else { &[] };
+            app.sim_engine.set_stats_enabled(run_stats && i + 1 == ticks_to_run);
+            if profile_due && i + 1 == ticks_to_run {
+                app.sim_engine.profile_next_tick();
+            }
             app.sim_engine.tick(&mut encoder, &app.gpu.queue, cmds);
+            if (i + 1) % chunk == 0 && i + 1 < ticks_to_run {
+                let full = std::mem::replace(
+                    &mut encoder,
+                    app.gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("frame_encoder"),
+                    }),
+                );
+                app.gpu.queue.submit(std::iter::once(full.finish()));
+            }
+        }
+        app.budget.record_ticks(ticks_to_run);
+        if let Some(staging) = app.sim_engine.take_profile_capture() {
+            app.readbacks.issue(Channel::Profile, staging);
         }
 
-        // Handle pick request: copy voxel data to pick staging buffer
-        if app.pick_requested && app.pick_state == ReadbackState::Idle {
-            if let Some((x, y, z)) = app.pick_coords {
-                let gs = app.sim_engine.grid_size();
-                let idx = types::grid_index(x, y, z, gs);
-                app.picker.request_pick(
+        // Sparse upgrade: read back brick occupancy of the post-tick grid;
+        // ticks hold from here until it lands
+        if plan.runs(AuxPass::Occupancy) {
+            if let Some(ref pass) = app.migration {
+                let start = budget::now_ms();
+                pass.encode_occupancy(&mut encoder, &app.gpu.device, &app.gpu.queue, app.sim_engine.current_read_buffer());
+                app.readbacks.issue(Channel::Occupancy, pass.staging_buffer());
+                app.budget.record_since(AuxPass::Occupancy, start);
+            }
+        }
+
+        // Append the fresh stats to the GPU history graph
+        let stats_start = budget::now_ms();
+        if run_stats && ticks_to_run > 0 {
+            app.renderer.append_graph_sample(&mut encoder, &app.gpu.queue, app.sim_engine.stats_buffer());
+        }
+        if run_stats {
+            app.budget.record_since(AuxPass::Stats, stats_start);
+        }
+
+        // Census query on the post-tick grid
+        if census_pending {
+            if let Some(query) = app.census_request.take() {
+                app.census.encode(
                     &mut encoder,
+                    &app.gpu.device,
+                    &app.gpu.queue,
+                    &query,
                     app.sim_engine.current_read_buffer(),
-                    idx as u32,
+                    app.sim_engine.brick_table_buffer(),
+                    app.sim_engine.grid_size(),
+                    app.sim_engine.params.brick_grid_dim as u32,
                 );
-                app.pick_state = ReadbackState::CopyIssued;
+                app.census_inflight = Some(query);
+                app.readbacks.issue(Channel::Census, app.census.staging_buffer());
             }
         }
 
-        // Track stats readback cadence (every 10 ticks)
+        // Re-read the watched voxel alongside each stats readback
+        if run_stats && app.readbacks.can_issue(Channel::Watch) {
+            if let Some((x, y, z)) = app.watch.as_ref().filter(|w| !w.ended).map(|w| (w.x, w.y, w.z)) {
+                let idx = types::grid_index(x, y, z, app.sim_engine.grid_size());
+                app.picker.request_watch(&mut encoder, app.sim_engine.current_read_buffer(), idx as u32);
+                app.watch_tick = app.sim_engine.tick_count();
+                app.readbacks.issue(Channel::Watch, app.picker.watch_staging_buffer());
+            }
+        }
+
+        // Next chunk of a world dump
+        if let Some(ref mut dump) = app.world_dump {
+            dump.encode(&mut encoder, app.sim_engine.current_read_buffer());
+        }
+
+        // Sample genomes alongside each stats readback, sized against the
+        // last measured population
+        if run_stats && app.genome_sample_n > 0 && app.readbacks.can_issue(Channel::GenomeSample) {
+            let n = app.genome_sample_n;
+            let population = app.latest_stats.as_ref().map_or(u32::MAX, |s| s.population);
+            let tick = app.sim_engine.tick_count();
+            app.genome_sampler.encode(
+                &mut encoder,
+                &app.gpu.device,
+                &app.gpu.queue,
+                app.sim_engine.current_read_buffer(),
+                app.sim_engine.brick_table_buffer(),
+                app.sim_engine.grid_size(),
+                app.sim_engine.params.brick_grid_dim as u32,
+                n,
+                types::sample_threshold(n, population),
+                tick,
+            );
+            app.genome_sample_inflight = (tick, n);
+            app.readbacks.issue(Channel::GenomeSample, app.genome_sampler.staging_buffer());
+        }
+
+        // Track stats readback cadence (every `stats_interval` ticks)
         if ticks_to_run > 0 {
             app.stats_tick_counter += ticks_to_run;
+            app.timing.record_ticks(ticks_to_run);
         }
 
         // Update render texture from current read buffer
-        app.renderer.update_render_texture(
-            &mut encoder,
-            &app.gpu.device,
-            app.sim_engine.current_read_buffer(),
-            app.sim_engine.params_buffer(),
-            app.sim_engine.current_temp_buffer(),
-            app.sim_engine.brick_table_buffer(),
-        );
+        let refresh = plan.runs(AuxPass::RenderTexture);
+        let refresh_start = budget::now_ms();
+
+        // Measure the temperature range the overlay is scaled to; stats
+        // readbacks carry it otherwise
+        if refresh && app.overlay_mode == 1 {
+            let readback = app.readbacks.can_issue(Channel::TempRange);
+            app.temp_range.encode(
+                &mut encoder,
+                &app.gpu.device,
+                &app.gpu.queue,
+                app.sim_engine.current_temp_buffer(),
+                app.sim_engine.brick_table_buffer(),
+                app.sim_engine.grid_size(),
+                app.sim_engine.params.brick_grid_dim as u32,
+                readback,
+            );
+            if readback {
+                app.readbacks.issue(Channel::TempRange, app.temp_range.staging_buffer());
+            }
+        }
+
+        if refresh {
+            // Empty cells show their temperature under that overlay
+            let region = app.sim_engine.active_bounds().filter(|_| app.overlay_mode != 1);
+            app.renderer.set_active_region(region);
+            app.renderer.update_render_texture(
+                &mut encoder,
+                &app.gpu.device,
+                &app.gpu.queue,
+                app.sim_engine.current_read_buffer(),
+                app.sim_engine.params_buffer(),
+                app.sim_engine.current_temp_buffer(),
+                app.temp_range.range_buffer(),
+                app.sim_engine.brick_table_buffer(),
+                app.sim_engine.tick_params_binding(),
+            );
+            app.last_overlay_mode = app.overlay_mode;
+            app.budget.record_since(AuxPass::RenderTexture, refresh_start);
+        }
+        app.texture_stale = volume_changed && !refresh;
+        if refresh {
+            app.texture_undrawn = drawn_early;
+            app.flicker_settle = match (app.renderer.style().reduced_flicker, volume_changed) {
+                (false, _) => 0,
+                (true, true) => FLICKER_SETTLE_REFRESHES,
+                (true, false) => app.flicker_settle.saturating_sub(1),
+            };
+        }
+
+        // Minimap heatmap, from the refreshed texture
+        if plan.runs(AuxPass::Heatmap) {
+            let start = budget::now_ms();
+            app.renderer.encode_minimap_heatmap(&mut encoder, &app.gpu.device, &app.gpu.queue, &app.camera);
+            app.budget.record_since(AuxPass::Heatmap, start);
+        }
+
+        // Next slice of a slice export, from the refreshed texture
+        if let Some(ref mut export) = app.slice_export {
+            export.encode(&mut encoder, app.renderer.volume_texture());
+        }
+
+        // Handle pick request: march the click ray through the volume
+        // texture, then copy the hit voxel to the pick staging buffer
+        if pick_pending {
+            if let Some((x, y, z)) = app.pick_coords {
+                let gs = app.sim_engine.grid_size();
+                let idx = types::grid_index(x, y, z, gs);
+                app.picker.request_pick(
+                    &mut encoder,
+                    app.sim_engine.current_read_buffer(),
+                    app.sim_engine.current_temp_buffer(),
+                    app.sim_engine.current_pheromone_buffer(),
+                    idx as u32,
+                    (x, y, z),
+                    gs,
+                );
+                app.pick_region_bounds = if app.sim_engine.is_sparse() {
+                    None
+                } else {
+                    let bounds = renderer::RegionBounds::around((x, y, z), app.pick_region_radius, gs);
+                    app.picker.request_region(
+                        &mut encoder,
+                        app.sim_engine.current_read_buffer(),
+                        app.sim_engine.current_temp_buffer(),
+                        bounds,
+                        gs,
+                    );
+                    Some(bounds)
+                };
+                app.readbacks.issue(Channel::Pick, app.picker.staging_buffer());
+            } else if let Some(ray) = app.pick_ray {
+                app.picker.request_hit(
+                    &mut encoder,
+                    &app.gpu.device,
+                    &app.gpu.queue,
+                    app.renderer.volume_texture_view(),
+                    &app.camera,
+                    ray,
+                    app.sim_engine.grid_size(),
+                );
+                app.readbacks.issue(Channel::Pick, app.picker.hit_staging_buffer());
+            }
+        }
 
         // Render frame (ray march + wireframe)
-        app.renderer.render_frame(
-            &mut encoder,
-            &surface_view,
-            &app.camera,
-            &app.gpu.queue,
-            &app.gpu.device,
-        );
+        if let Some(ref surface_texture) = surface_texture {
+            let capture_due = capture_due(app);
+            draw_frame(app, &mut encoder, surface_texture, capture_due);
+        }
 
         app.gpu.queue.submit(std::iter::once(encoder.finish()));
-        surface_texture.present();
+        if let Some(surface_texture) = surface_texture {
+            surface_texture.present();
+            app.last_drawn_camera = Some(app.camera.clone());
+            app.frames_since_draw = 0;
+        } else if !drawn_early {
+            app.frames_since_draw = app.frames_since_draw.saturating_add(1);
+        }
 
-        // --- Stats readback state machine ---
-        // Transition CopyIssued -> MapRequested (issue map_async once)
-        if app.stats_tick_counter >= 10 && app.stats_state == ReadbackState::Idle {
-            // Stats copy happens every tick via encoder (always copies to staging).
-            // We just need to request mapping.
+        // The stats copy was encoded by the planned tick; map it with the
+        // rest of this frame's copies
+        if run_stats && app.readbacks.can_issue(Channel::Stats) {
             app.stats_tick_counter = 0;
-            app.stats_ready.set(false);
-            let flag = app.stats_ready.clone();
-            app.sim_engine.stats_staging_buffer().slice(..).map_async(
-                wgpu::MapMode::Read,
-                move |result| {
-                    if result.is_ok() {
-                        flag.set(true);
-                    }
-                },
-            );
-            app.stats_state = ReadbackState::MapRequested;
-        }
-
-        // Transition MapRequested -> Idle (read data when ready)
-        if app.stats_state == ReadbackState::MapRequested && app.stats_ready.get() {
-            let slice = app.sim_engine.stats_staging_buffer().slice(..);
-            let data = slice.get_mapped_range();
-            let words: &[u32] = bytemuck::cast_slice(&data);
-            let mut arr = [0u32; 32];
-            let len = words.len().min(32);
-            arr[..len].copy_from_slice(&words[..len]);
-            drop(data);
-            app.sim_engine.stats_staging_buffer().unmap();
-            app.latest_stats = Some(SimStats::from_words(&arr));
-            app.stats_state = ReadbackState::Idle;
-        }
-
-        // --- Pick readback state machine ---
-        // Transition CopyIssued -> MapRequested
-        if app.pick_state == ReadbackState::CopyIssued {
-            app.pick_ready.set(false);
-            let flag = app.pick_ready.clone();
-            app.picker.staging_buffer().slice(..).map_async(
-                wgpu::MapMode::Read,
-                move |result| {
-                    if result.is_ok() {
-                        flag.set(true);
-                    }
-                },
-            );
-            app.pick_state = ReadbackState::MapRequested;
+            app.readbacks.issue(Channel::Stats, app.sim_engine.stats_staging_buffer());
         }
+        app.readbacks.map_issued();
 
-        // Transition MapRequested -> Idle (read data when ready)
-        if app.pick_state == ReadbackState::MapRequested && app.pick_ready.get() {
-            let slice = app.picker.staging_buffer().slice(..);
-            let data = slice.get_mapped_range();
-            let bytes: Vec<u8> = data.to_vec();
-            drop(data);
-            app.picker.staging_buffer().unmap();
-            if let Some((x, y, z)) = app.pick_coords {
-                app.latest_pick = Some(VoxelPicker::parse_pick(&bytes, x, y, z));
-            }
-            app.pick_requested = false;
-            app.pick_state = ReadbackState::Idle;
+        if let Some(ref mut dump) = app.world_dump {
+            dump.request_map();
         }
+        if let Some(ref mut export) = app.slice_export {
+            export.request_map();
+        }
+
+        poll_readbacks(app);
     });
 }
+
+/// Abandon an in-flight stats readback (e.g. after the grid was reseeded).
+/// Unmapping cancels a pending map so the buffer can be mapped again; a
+/// `step_ticks` promise waiting on it resolves without fresh stats.
+pub fn cancel_stats_readback(app: &mut App) {
+    app.readbacks.cancel(Channel::Stats);
+    resolve_step_awaiting_stats(app);
+}
+
+/// Record a measurement pick. The third point closes the box and issues a
+/// live-protocell count over it; a fourth starts a new measurement.
+fn add_measure_point(app: &mut App, point: (u32, u32, u32)) {
+    if app.measure_points.len() == 3 {
+        app.measure_points.clear();
+        app.measure_query = None;
+        app.measure_live = None;
+    }
+    app.measure_points.push(point);
+    if app.measure_points.len() < 3 {
+        return;
+    }
+    if let Some((min, max)) = types::bounding_box(&app.measure_points) {
+        let query = types::CensusQuery::live_in_region(min, max);
+        app.measure_query = Some(query);
+        app.census_request = Some(query);
+    }
+}
+
+/// Abandon an in-flight pick readback so a new click starts from the hit
+/// stage.
+pub fn cancel_pick_readback(app: &mut App) {
+    app.readbacks.cancel(Channel::Pick);
+    app.pick_retries = 0;
+}
+
+/// Resolve a `step_ticks` promise whose final tick's stats readback landed
+/// (or was given up on).
+fn resolve_step_awaiting_stats(app: &mut App) {
+    if app.step_requests.front().is_some_and(|step| step.awaiting_stats) {
+        if let Some(step) = app.step_requests.pop_front() {
+            let tick = JsValue::from(app.sim_engine.tick_count());
+            let _ = step.resolve.call1(&JsValue::UNDEFINED, &tick);
+        }
+    }
+}
+
+/// Complete any readbacks whose map has resolved (see `readback::poll`)
+/// and drive the world dump's and slice export's readbacks.
+fn poll_readbacks(app: &mut App) {
+    readback::poll(app);
+    if let Some(ref mut dump) = app.world_dump {
+        if dump.poll(&mut app.dump_events) {
+            app.world_dump = None;
+        }
+    }
+    if let Some(ref mut export) = app.slice_export {
+        if export.poll(&mut app.dump_events) {
+            app.slice_export = None;
+        }
+    }
+}
+
+/// Every readback channel with its completion and failure callbacks. One
+/// slot each: a channel re-issues its copy only once the last one landed.
+fn readback_channels() -> ReadbackManager {
+    let mut readbacks = ReadbackManager::new();
+    readbacks.register(Channel::Stats, 1, stats_landed, stats_failed);
+    readbacks.register(Channel::Pick, 1, pick_landed, |app, failure| retry_pick(app, failure.describe()));
+    readbacks.register(Channel::Census, 1, census_landed, census_failed);
+    readbacks.register(Channel::GenomeSample, 1, genome_sample_landed, genome_sample_failed);
+    readbacks.register(Channel::TempRange, 1, temp_range_landed, temp_range_failed);
+    readbacks.register(Channel::Occupancy, 1, occupancy_landed, occupancy_failed);
+    // A missed watch sample is not retried; the next stats interval re-reads
+    readbacks.register(Channel::Watch, 1, watch_landed, |_, _| {});
+    readbacks.register(Channel::Record, 1, record_landed, record_failed);
+    // A lost profile is not retried; the next frame captures another
+    readbacks.register(Channel::Profile, 1, profile_landed, |_, _| {});
+    readbacks
+}
+
+fn stats_landed(app: &mut App, done: Completed) {
+    let mut stats = SimStats::from_readback(&done.words());
+    stats.brick_pool = app.sim_engine.brick_pool();
+    if let Some(pool) = stats.brick_pool.filter(|pool| app.pool_alarm.update(pool)) {
+        app.pending_pool_warning = Some((pool, app.pool_alarm.threshold_for(&pool)));
+    }
+    if let Some(ref mut challenge) = app.challenge {
+        challenge.update(app.sim_engine.tick_count(), &stats.goal_histogram);
+    }
+    if let Some(ref mut sonifier) = app.sonifier {
+        let capacity = app.sim_engine.grid_size().pow(3);
+        app.latest_synth = Some(sonifier.update(
+            app.sim_engine.tick_count(),
+            capacity,
+            stats.population,
+            stats.toxin_deaths,
+            &stats.species_histogram,
+        ));
+    }
+    record_ecology(&mut app.ecology, &mut app.lineage, &mut app.pending_extinctions, app.sim_engine.tick_count(), &stats);
+    app.sim_engine.set_active_bounds(&app.gpu.queue, stats.active_bounds);
+    if let Some(temperature) = stats.temperature {
+        app.latest_temp_range = Some(temperature.range());
+    }
+    app.latest_stats = Some(stats);
+    resolve_step_awaiting_stats(app);
+}
+
+fn stats_failed(app: &mut App, failure: ReadbackFailure) {
+    web_sys::console::warn_1(&format!("Stats readback {}; retrying on next stats tick", failure.describe()).into());
+    resolve_step_awaiting_stats(app);
+}
+
+fn pick_landed(app: &mut App, done: Completed) {
+    let bytes = done.data;
+    app.pick_retries = 0;
+    if let Some((x, y, z)) = app.pick_coords {
+        let pick = VoxelPicker::parse_pick(&bytes, x, y, z);
+        if app.pick_history.len() == PICK_HISTORY_LEN {
+            app.pick_history.pop_front();
+        }
+        app.pick_history.push_back(pick.clone());
+        if app.measure_mode {
+            add_measure_point(app, (x, y, z));
+        }
+        app.latest_pick = Some(pick);
+        app.latest_pick_region = app
+            .pick_region_bounds
+            .map(|bounds| VoxelPicker::parse_region(&bytes, bounds));
+        app.pick_requested = false;
+    } else {
+        // Hit stage done: read the voxel next frame, or stop
+        // if the ray passed through empty space
+        app.pick_coords = VoxelPicker::parse_hit(&bytes);
+        app.pick_requested = app.pick_coords.is_some();
+    }
+}
+
+fn census_landed(app: &mut App, done: Completed) {
+    let words = done.words();
+    if let Some(query) = app.census_inflight.take() {
+        if app.measure_query == Some(query) {
+            app.measure_live = Some(words[0]);
+        }
+        app.latest_census = Some(types::CensusResult::from_words(query, &words));
+    }
+}
+
+fn census_failed(app: &mut App, failure: ReadbackFailure) {
+    web_sys::console::warn_1(&format!("Census readback {}", failure.describe()).into());
+    app.census_inflight = None;
+}
+
+fn genome_sample_landed(app: &mut App, done: Completed) {
+    let (tick, n) = app.genome_sample_inflight;
+    app.latest_genome_sample = Some(types::GenomeSampleSet::from_words(tick, n, &done.words()));
+}
+
+/// A missed sample is not retried; the next stats interval re-samples.
+fn genome_sample_failed(_: &mut App, failure: ReadbackFailure) {
+    if failure == ReadbackFailure::TimedOut {
+        web_sys::console::warn_1(&"Genome sample readback timed out".into());
+    }
+}
+
+fn temp_range_landed(app: &mut App, done: Completed) {
+    app.latest_temp_range = types::TempRange::from_words(&done.words());
+}
+
+/// The next overlay refresh or stats interval measures again.
+fn temp_range_failed(_: &mut App, failure: ReadbackFailure) {
+    if failure == ReadbackFailure::TimedOut {
+        web_sys::console::warn_1(&"Temperature range readback timed out".into());
+    }
+}
+
+fn occupancy_landed(app: &mut App, done: Completed) {
+    if let Some(pass) = app.migration.take() {
+        let occupancy = MigratePass::parse_occupancy(&done.data);
+        finish_sparse_upgrade(app, &pass, &occupancy);
+    }
+}
+
+fn occupancy_failed(app: &mut App, failure: ReadbackFailure) {
+    web_sys::console::warn_1(&format!("Sparse upgrade readback {}", failure.describe()).into());
+    app.migration = None;
+}
+
+fn watch_landed(app: &mut App, done: Completed) {
+    let tick = app.watch_tick;
+    if let Some(ref mut watch) = app.watch {
+        watch.record(tick, &VoxelPicker::parse_voxel(&done.data, watch.x, watch.y, watch.z));
+    }
+}
+
+fn record_landed(app: &mut App, done: Completed) {
+    if let Some(capture) = app.renderer.capture_target() {
+        let rgba = capture.unpack_rgba(&done.data);
+        if let Some(ref mut rec) = app.recording {
+            if let Some((sim_time, repeat)) = rec.inflight.take() {
+                rec.push(sim_time, repeat, rgba);
+            }
+        }
+    }
+}
+
+fn profile_landed(app: &mut App, done: Completed) {
+    if let Some(timings) = app.sim_engine.read_profile(&done.data) {
+        // The budget weighs stats by GPU time too when it is measured
+        if let Some(ms) = timings.get(sim_core::profiler::ProfilePass::Stats) {
+            app.budget.aux.record_cost(AuxPass::Stats, ms);
+        }
+        app.latest_profile = Some(timings);
+    }
+}
+
+fn record_failed(app: &mut App, failure: ReadbackFailure) {
+    web_sys::console::warn_1(&format!("Recording readback {}", failure.describe()).into());
+    cancel_record_readback(app);
+}
+
+/// Live values for scenario conditions; zero counts before the first
+/// stats readback.
+pub fn scenario_stats(tick: u32, stats: Option<&SimStats>) -> types::ScenarioStats {
+    types::ScenarioStats {
+        tick,
+        population: stats.map_or(0, |s| s.population),
+        species_count: stats.map_or(0, |s| s.species_count),
+        total_energy: stats.map_or(0, |s| s.total_energy),
+    }
+}
+
+/// Log species changes and speciations seen in a stats sample, queueing
+/// notable extinctions.
+pub fn record_ecology(
+    log: &mut types::EcologyLog,
+    lineage: &mut types::LineageTree,
+    pending: &mut Vec<types::EcologyEvent>,
+    tick: u32,
+    stats: &SimStats,
+) {
+    let alive: Vec<u16> = stats.species_histogram.iter().map(|&(sid, _)| sid).collect();
+    lineage.record(&stats.speciations, stats.speciations_unlogged, &alive);
+    let events = log.update(tick, &stats.species_histogram);
+    pending.extend(events.into_iter().filter(|e| e.is_notable()));
+}
+
+/// Dispatch a `primordium-extinction` event on `window` for each event,
+/// with the event record (as in `get_ecology_events`) as `detail`.
+fn emit_extinctions(events: &[types::EcologyEvent]) {
+    let Some(window) = web_sys::window().filter(|_| !events.is_empty()) else {
+        return;
+    };
+    for event in events {
+        let init = web_sys::CustomEventInit::new();
+        init.set_detail(&bridge::ecology_event_to_js(event));
+        if let Ok(e) = web_sys::CustomEvent::new_with_event_init_dict("primordium-extinction", &init) {
+            let _ = window.dispatch_event(&e);
+        }
+    }
+}
+
+/// Dispatch a `primordium-pool-pressure` event on `window` with the pool
+/// (as in `get_stats().brick_pool`) and the threshold it fell below as
+/// `detail`.
+fn emit_pool_pressure(pool: &types::BrickPoolStats, threshold: u32) {
+    let free = pool.free();
+    web_sys::console::warn_1(&format!("[pool] {free} of {} bricks free; growth stops when none are", pool.capacity).into());
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let detail = bridge::brick_pool_to_js(pool);
+    let _ = js_sys::Reflect::set(&detail, &"threshold".into(), &JsValue::from(threshold));
+    let init = web_sys::CustomEventInit::new();
+    init.set_detail(&detail);
+    if let Ok(e) = web_sys::CustomEvent::new_with_event_init_dict("primordium-pool-pressure", &init) {
+        let _ = window.dispatch_event(&e);
+    }
+}
+
+/// Convert the engine to sparse once the brick occupancy is known.
+fn finish_sparse_upgrade(app: &mut App, pass: &MigratePass, occupancy: &[u32]) {
+    let dim = app.sim_engine.grid_size() / 8;
+    let max_bricks = match app.migrate_max_bricks {
+        0 => (sim_core::sparse::bricks_needed(occupancy, dim) * 2).clamp(64, dim.pow(3)),
+        n => n,
+    };
+    cancel_stats_readback(app);
+    match app.sim_engine.convert_to_sparse(&app.gpu.device, &app.gpu.queue, pass, max_bricks, occupancy) {
+        Ok(()) => {
+            app.pool_alarm.rearm();
+            rebind_after_migration(app);
+        }
+        Err(e) => web_sys::console::warn_1(&format!("Sparse upgrade failed: {e}").into()),
+    }
+}
+
+/// Abandon an in-flight world dump (the world it was reading changed),
+/// rejecting its promise.
+pub fn cancel_world_dump(app: &mut App, reason: &str) {
+    if let Some(mut dump) = app.world_dump.take() {
+        dump.fail(&mut app.dump_events, reason);
+    }
+}
+
+/// Abandon an in-flight slice export (the volume it was reading changed),
+/// rejecting its promise.
+pub fn cancel_slice_export(app: &mut App, reason: &str) {
+    if let Some(mut export) = app.slice_export.take() {
+        export.fail(&mut app.dump_events, reason);
+    }
+}
+
+/// Point everything that follows the engine's storage layout at the
+/// migrated world and redraw.
+pub fn rebind_after_migration(app: &mut App) {
+    cancel_world_dump(app, "storage layout changed");
+    cancel_slice_export(app, "storage layout changed");
+    let sparse = app.sim_engine.is_sparse();
+    app.renderer.set_sparse(&app.gpu.device, sparse);
+    app.capability.sparse = sparse;
+    app.stats_tick_counter = 0;
+    app.texture_stale = true;
+    app.last_drawn_camera = None;
+    let allocated: u64 = memory::current_allocations(app).iter().map(|(_, b)| b).sum();
+    if let Some(warning) = app.memory.check_usage(allocated) {
+        app.pending_memory_warning = Some(warning);
+    }
+}
+
+/// Abandon an in-flight watch readback (the watch was replaced or removed).
+pub fn cancel_watch_readback(app: &mut App) {
+    app.readbacks.cancel(Channel::Watch);
+}
+
+/// Abandon an in-flight capture readback. Its output frames go back to the
+/// recording so the next capture covers them.
+pub fn cancel_record_readback(app: &mut App) {
+    app.readbacks.cancel(Channel::Record);
+    if let Some(ref mut rec) = app.recording {
+        if let Some((_, repeat)) = rec.inflight.take() {
+            rec.restore_pending(repeat);
+        }
+    }
+}
+
+/// Return a failed pick to Idle. `pick_requested` stays set so the copy is
+/// re-issued next frame, up to `PICK_MAX_RETRIES` times.
+fn retry_pick(app: &mut App, why: &str) {
+    if app.pick_retries < PICK_MAX_RETRIES {
+        app.pick_retries += 1;
+        web_sys::console::warn_1(&format!("Pick readback {why}; retry {}", app.pick_retries).into());
+    } else {
+        web_sys::console::warn_1(&format!("Pick readback {why}; giving up").into());
+        app.pick_requested = false;
+        app.pick_retries = 0;
+    }
+}
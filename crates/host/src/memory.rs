@@ -0,0 +1,143 @@
+use wasm_bindgen::prelude::*;
+
+use crate::gpu::GpuTier;
+use crate::App;
+
+/// Fraction of the budget at which a warning is raised.
+const DEFAULT_WARN_FRACTION: f64 = 0.85;
+
+/// Central GPU memory accounting. WebGPU exposes no VRAM size, so usage is
+/// measured against a configurable budget (defaulted per tier) and against
+/// the device's per-buffer limits.
+pub struct MemoryTracker {
+    pub budget_bytes: u64,
+    pub warn_fraction: f64,
+    /// Set once a budget warning has fired; re-armed when usage drops.
+    warned: bool,
+}
+
+impl MemoryTracker {
+    pub fn new(tier: GpuTier) -> Self {
+        let budget_mb: u64 = match tier {
+            GpuTier::Low => 512,
+            _ => 1024,
+        };
+        Self {
+            budget_bytes: budget_mb * 1024 * 1024,
+            warn_fraction: DEFAULT_WARN_FRACTION,
+            warned: false,
+        }
+    }
+
+    pub fn set_budget_mb(&mut self, mb: u32) {
+        self.budget_bytes = (mb.max(16) as u64) * 1024 * 1024;
+        self.warned = false;
+    }
+
+    fn threshold(&self) -> u64 {
+        (self.budget_bytes as f64 * self.warn_fraction) as u64
+    }
+
+    /// Check usage after allocations change. Returns a warning the first time
+    /// usage crosses the threshold.
+    pub fn check_usage(&mut self, total: u64) -> Option<String> {
+        if total < self.threshold() {
+            self.warned = false;
+            return None;
+        }
+        if self.warned {
+            return None;
+        }
+        self.warned = true;
+        Some(format!(
+            "GPU memory usage {} MB is at {:.0}% of the {} MB budget",
+            total / (1024 * 1024),
+            total as f64 / self.budget_bytes as f64 * 100.0,
+            self.budget_bytes / (1024 * 1024),
+        ))
+    }
+
+    /// Check a planned allocation before making it (engine init, pool growth).
+    pub fn check_planned(&self, label: &str, bytes: u64, current: u64) -> Option<String> {
+        let after = current + bytes;
+        if after >= self.threshold() {
+            Some(format!(
+                "{label} needs {} MB; total would be {} MB of the {} MB budget",
+                bytes / (1024 * 1024),
+                after / (1024 * 1024),
+                self.budget_bytes / (1024 * 1024),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// (label, bytes) for every GPU resource the app owns.
+pub fn current_allocations(app: &App) -> Vec<(&'static str, u64)> {
+    let mut allocs = app.sim_engine.allocations();
+    allocs.extend(app.renderer.allocations());
+    allocs.extend(app.picker.allocations());
+    allocs.extend(app.census.allocations());
+    allocs.extend(app.genome_sampler.allocations());
+    allocs.extend(app.temp_range.allocations());
+    if let Some(ref dump) = app.world_dump {
+        allocs.push(("world_dump_staging", dump.staging_size()));
+    }
+    if let Some(ref export) = app.slice_export {
+        allocs.push(("slice_export_staging", export.staging_size()));
+    }
+    allocs
+}
+
+/// Log a memory warning and dispatch a `primordium-memory-warning` event on
+/// `window` with the message as `detail`. Listeners may call back into the
+/// bridge, so never call this under the `APP` borrow: queue the message in
+/// `App::pending_memory_warning` instead.
+pub fn emit_warning(message: &str) {
+    web_sys::console::warn_1(&format!("[memory] {message}").into());
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let init = web_sys::CustomEventInit::new();
+    init.set_detail(&JsValue::from(message));
+    if let Ok(event) = web_sys::CustomEvent::new_with_event_init_dict("primordium-memory-warning", &init) {
+        let _ = window.dispatch_event(&event);
+    }
+}
+
+pub fn usage_to_js(app: &App) -> JsValue {
+    let allocs = current_allocations(app);
+    let total: u64 = allocs.iter().map(|(_, b)| b).sum();
+    let largest = allocs.iter().max_by_key(|(_, b)| *b).copied().unwrap_or(("", 0));
+
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"total_bytes".into(), &JsValue::from(total as f64));
+    let _ = js_sys::Reflect::set(&obj, &"budget_bytes".into(), &JsValue::from(app.memory.budget_bytes as f64));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"budget_fraction".into(),
+        &JsValue::from(total as f64 / app.memory.budget_bytes as f64),
+    );
+    let _ = js_sys::Reflect::set(&obj, &"largest_buffer".into(), &JsValue::from(largest.0));
+    let _ = js_sys::Reflect::set(&obj, &"largest_buffer_bytes".into(), &JsValue::from(largest.1 as f64));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"max_buffer_size".into(),
+        &JsValue::from(app.gpu.limits.max_buffer_size as f64),
+    );
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"max_storage_buffer_binding_size".into(),
+        &JsValue::from(app.gpu.limits.max_storage_buffer_binding_size),
+    );
+    let arr = js_sys::Array::new();
+    for (label, bytes) in &allocs {
+        let entry = js_sys::Array::new();
+        entry.push(&JsValue::from(*label));
+        entry.push(&JsValue::from(*bytes as f64));
+        arr.push(&entry);
+    }
+    let _ = js_sys::Reflect::set(&obj, &"allocations".into(), &arr);
+    obj.into()
+}
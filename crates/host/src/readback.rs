@@ -0,0 +1,333 @@
+//! Async GPU → CPU readbacks. Every channel (stats, pick, census, ...) runs
+//! the same state machine per staging buffer:
+//!   Idle -> CopyIssued (copy encoded) -> MapRequested (map_async after the
+//!   submit) -> Idle (mapped and handed to the channel's completion
+//!   callback, or failed / timed out and handed to its failure callback)
+//! A channel has a ring of one or more slots, so that many copies can be in
+//! flight at once. A slot maps either the pass's own staging buffer
+//! (`issue`) or one the ring owns (`ring_buffer`).
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::App;
+
+/// Async readback state machine: Idle -> CopyIssued -> MapRequested -> Ready
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReadbackState {
+    Idle,
+    CopyIssued,
+    MapRequested,
+}
+
+/// Outcome of a `map_async`, written by its callback.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MapStatus {
+    Pending,
+    Mapped,
+    Failed,
+}
+
+/// Frames a readback may sit in MapRequested before it is cancelled. The
+/// browser normally resolves a map within a frame or two; anything this old
+/// has lost its callback (device hiccup, tab throttling edge cases).
+pub const READBACK_TIMEOUT_FRAMES: u32 = 120;
+
+/// Map `buffer` for reading. Each request gets its own status cell, so a
+/// late callback from a cancelled map can't complete a newer one.
+pub fn request_map(buffer: &wgpu::Buffer) -> Rc<Cell<MapStatus>> {
+    let status = Rc::new(Cell::new(MapStatus::Pending));
+    let flag = status.clone();
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+        flag.set(if result.is_ok() { MapStatus::Mapped } else { MapStatus::Failed });
+    });
+    status
+}
+
+/// Map `buffer` for reading and wait for it, for one-off readbacks that
+/// resolve a promise rather than a channel.
+pub async fn map_read(buffer: &wgpu::Buffer) -> Result<(), String> {
+    let mut callbacks = None;
+    let promise = js_sys::Promise::new(&mut |res, rej| callbacks = Some((res, rej)));
+    let Some((resolve, reject)) = callbacks else {
+        return Err("map promise was not created".to_string());
+    };
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+        let _ = match result {
+            Ok(()) => resolve.call0(&wasm_bindgen::JsValue::UNDEFINED),
+            Err(e) => reject.call1(&wasm_bindgen::JsValue::UNDEFINED, &e.to_string().into()),
+        };
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.map(|_| ()).map_err(|e| e.as_string().unwrap_or_default())
+}
+
+/// Readback channels, polled in this order each frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Stats,
+    Pick,
+    Census,
+    GenomeSample,
+    TempRange,
+    /// Brick occupancy for a dense → sparse upgrade.
+    Occupancy,
+    Watch,
+    Record,
+    /// GPU pass timestamps; see `set_profiling`.
+    Profile,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 9] = [
+        Channel::Stats,
+        Channel::Pick,
+        Channel::Census,
+        Channel::GenomeSample,
+        Channel::TempRange,
+        Channel::Occupancy,
+        Channel::Watch,
+        Channel::Record,
+        Channel::Profile,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::Stats => "stats",
+            Channel::Pick => "pick",
+            Channel::Census => "census",
+            Channel::GenomeSample => "genome_sample",
+            Channel::TempRange => "temperature_range",
+            Channel::Occupancy => "occupancy",
+            Channel::Watch => "watch",
+            Channel::Record => "record",
+            Channel::Profile => "profile",
+        }
+    }
+}
+
+/// Why a readback produced no data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadbackFailure {
+    /// The map callback reported an error.
+    Failed,
+    /// No callback within `READBACK_TIMEOUT_FRAMES`; the map was cancelled.
+    TimedOut,
+}
+
+impl ReadbackFailure {
+    pub fn describe(self) -> &'static str {
+        match self {
+            ReadbackFailure::Failed => "failed",
+            ReadbackFailure::TimedOut => "timed out",
+        }
+    }
+}
+
+/// A mapped readback, already unmapped.
+pub struct Completed {
+    /// What `issue` returned for this copy.
+    pub seq: u64,
+    pub data: Vec<u8>,
+}
+
+impl Completed {
+    pub fn words(&self) -> Vec<u32> {
+        self.data.chunks_exact(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+    }
+}
+
+pub type OnComplete = fn(&mut App, Completed);
+pub type OnFailure = fn(&mut App, ReadbackFailure);
+
+struct Slot {
+    state: ReadbackState,
+    ready: Rc<Cell<MapStatus>>,
+    wait_frames: u32,
+    /// Buffer the in-flight copy landed in.
+    staging: Option<wgpu::Buffer>,
+    /// Ring-owned staging buffer, reused across copies.
+    owned: Option<wgpu::Buffer>,
+    seq: u64,
+}
+
+impl Slot {
+    fn new() -> Self {
+        Self {
+            state: ReadbackState::Idle,
+            ready: Rc::new(Cell::new(MapStatus::Pending)),
+            wait_frames: 0,
+            staging: None,
+            owned: None,
+            seq: 0,
+        }
+    }
+
+    /// Back to Idle, unmapping a map that may still be pending.
+    fn cancel(&mut self) {
+        if self.state == ReadbackState::MapRequested && self.ready.get() != MapStatus::Failed {
+            if let Some(ref staging) = self.staging {
+                staging.unmap();
+            }
+        }
+        self.ready = Rc::new(Cell::new(MapStatus::Failed));
+        self.state = ReadbackState::Idle;
+        self.wait_frames = 0;
+        self.staging = None;
+    }
+}
+
+struct ChannelState {
+    slots: Vec<Slot>,
+    on_complete: OnComplete,
+    on_failure: OnFailure,
+}
+
+enum Event {
+    Completed(OnComplete, Completed),
+    Failed(OnFailure, ReadbackFailure),
+}
+
+/// Every channel's slots and callbacks; see the module docs.
+pub struct ReadbackManager {
+    channels: Vec<Option<ChannelState>>,
+    next_seq: u64,
+}
+
+impl Default for ReadbackManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadbackManager {
+    pub fn new() -> Self {
+        Self { channels: Channel::ALL.iter().map(|_| None).collect(), next_seq: 1 }
+    }
+
+    /// Give `channel` a ring of `ring_len` slots (at least one) and its
+    /// callbacks, replacing any earlier registration.
+    pub fn register(&mut self, channel: Channel, ring_len: usize, on_complete: OnComplete, on_failure: OnFailure) {
+        if let Some(old) = self.channels[channel as usize].as_mut() {
+            old.slots.iter_mut().for_each(Slot::cancel);
+        }
+        let slots = (0..ring_len.max(1)).map(|_| Slot::new()).collect();
+        self.channels[channel as usize] = Some(ChannelState { slots, on_complete, on_failure });
+    }
+
+    fn slots(&self, channel: Channel) -> &[Slot] {
+        self.channels[channel as usize].as_ref().map_or(&[], |c| &c.slots)
+    }
+
+    fn slots_mut(&mut self, channel: Channel) -> &mut [Slot] {
+        self.channels[channel as usize].as_mut().map_or(&mut [], |c| &mut c.slots)
+    }
+
+    /// Nothing of `channel` in flight.
+    pub fn is_idle(&self, channel: Channel) -> bool {
+        self.slots(channel).iter().all(|s| s.state == ReadbackState::Idle)
+    }
+
+    /// A slot of `channel` is free for another copy.
+    pub fn can_issue(&self, channel: Channel) -> bool {
+        self.slots(channel).iter().any(|s| s.state == ReadbackState::Idle)
+    }
+
+    /// The ring-owned staging buffer of `channel`'s next free slot, sized
+    /// `size` bytes; copy into it, then `issue` it. None if the ring is full.
+    pub fn ring_buffer(&mut self, channel: Channel, device: &wgpu::Device, size: u64) -> Option<wgpu::Buffer> {
+        let slot = self.slots_mut(channel).iter_mut().find(|s| s.state == ReadbackState::Idle)?;
+        if slot.owned.as_ref().is_none_or(|b| b.size() != size) {
+            slot.owned = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{}_readback_staging", channel.name())),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }));
+        }
+        slot.owned.clone()
+    }
+
+    /// Record that a copy into `staging` was encoded; it is mapped by the
+    /// next `map_issued`. Returns the sequence number `Completed` carries,
+    /// or None if the ring is full.
+    pub fn issue(&mut self, channel: Channel, staging: &wgpu::Buffer) -> Option<u64> {
+        let seq = self.next_seq;
+        let slot = self.slots_mut(channel).iter_mut().find(|s| s.state == ReadbackState::Idle)?;
+        slot.state = ReadbackState::CopyIssued;
+        slot.staging = Some(staging.clone());
+        slot.seq = seq;
+        self.next_seq += 1;
+        Some(seq)
+    }
+
+    /// Request maps for every issued copy. Call after the submit that
+    /// carries the copies.
+    pub fn map_issued(&mut self) {
+        for slot in self.channels.iter_mut().flatten().flat_map(|c| c.slots.iter_mut()) {
+            if slot.state == ReadbackState::CopyIssued {
+                if let Some(ref staging) = slot.staging {
+                    slot.ready = request_map(staging);
+                    slot.wait_frames = 0;
+                    slot.state = ReadbackState::MapRequested;
+                }
+            }
+        }
+    }
+
+    /// Abandon everything `channel` has in flight, without callbacks.
+    pub fn cancel(&mut self, channel: Channel) {
+        self.slots_mut(channel).iter_mut().for_each(Slot::cancel);
+    }
+
+    /// Take finished maps (oldest copy first per channel) and age pending ones.
+    fn collect(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        for channel in self.channels.iter_mut().flatten() {
+            let mut order: Vec<usize> = (0..channel.slots.len())
+                .filter(|&i| channel.slots[i].state == ReadbackState::MapRequested)
+                .collect();
+            order.sort_by_key(|&i| channel.slots[i].seq);
+            for i in order {
+                let slot = &mut channel.slots[i];
+                match slot.ready.get() {
+                    MapStatus::Mapped => {
+                        let Some(staging) = slot.staging.take() else { continue };
+                        let data = staging.slice(..).get_mapped_range().to_vec();
+                        staging.unmap();
+                        slot.state = ReadbackState::Idle;
+                        events.push(Event::Completed(channel.on_complete, Completed { seq: slot.seq, data }));
+                    }
+                    MapStatus::Failed => {
+                        slot.cancel();
+                        events.push(Event::Failed(channel.on_failure, ReadbackFailure::Failed));
+                    }
+                    MapStatus::Pending => {
+                        slot.wait_frames += 1;
+                        if slot.wait_frames >= READBACK_TIMEOUT_FRAMES {
+                            slot.cancel();
+                            events.push(Event::Failed(channel.on_failure, ReadbackFailure::TimedOut));
+                        }
+                    }
+                }
+            }
+        }
+        events
+    }
+}
+
+/// Complete any readbacks whose map has resolved, running their callbacks.
+/// Failed maps return to Idle at once; maps whose callback never arrives are
+/// cancelled after `READBACK_TIMEOUT_FRAMES`. Safe to call on frames that
+/// submit no GPU work.
+pub fn poll(app: &mut App) {
+    // Non-blocking: drives callbacks on native backends; on WebGPU the
+    // browser event loop delivers them and this is a no-op.
+    let _ = app.gpu.device.poll(wgpu::PollType::Poll);
+
+    for event in app.readbacks.collect() {
+        match event {
+            Event::Completed(on_complete, done) => on_complete(app, done),
+            Event::Failed(on_failure, failure) => on_failure(app, failure),
+        }
+    }
+}
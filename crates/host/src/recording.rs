@@ -0,0 +1,113 @@
+//! Frame recording synchronized to simulated time.
+//!
+//! Each output frame stands for `1 / fps` simulated seconds. A capture is
+//! taken on the first drawn frame after one or more output frames fall due
+//! and is repeated for every frame it covers, so a paused run records
+//! nothing and a fast run records the ticks it actually simulated.
+
+use wasm_bindgen::prelude::*;
+
+use types::{RecordingClock, RecordingConfig, MAX_RECORDING_BYTES};
+
+pub struct RecordedFrame {
+    /// Simulated time the capture was taken at.
+    pub sim_time: f64,
+    /// Output frames this capture stands for.
+    pub repeat: u32,
+    /// Tightly packed RGBA8, `width * height * 4` bytes.
+    pub rgba: Vec<u8>,
+}
+
+pub struct Recording {
+    pub fps: f32,
+    pub width: u32,
+    pub height: u32,
+    clock: RecordingClock,
+    pub frames: Vec<RecordedFrame>,
+    pub bytes: usize,
+    /// Set once `MAX_RECORDING_BYTES` was reached; no frames are added after.
+    pub truncated: bool,
+    /// Output frames owed to the next capture.
+    pending: u32,
+    /// Sim time and repeat count of the capture being read back.
+    pub inflight: Option<(f64, u32)>,
+}
+
+impl Recording {
+    pub fn new(config: RecordingConfig, start: f64, width: u32, height: u32) -> Self {
+        Self {
+            fps: config.fps,
+            width,
+            height,
+            clock: RecordingClock::new(config.fps, start),
+            frames: Vec::new(),
+            bytes: 0,
+            truncated: false,
+            pending: 0,
+            inflight: None,
+        }
+    }
+
+    /// Advance to `sim_time`; true when a capture is owed.
+    pub fn advance(&mut self, sim_time: f64) -> bool {
+        self.pending += self.clock.frames_due(sim_time);
+        self.pending > 0 && !self.truncated
+    }
+
+    /// Output frames the capture about to be taken stands for.
+    pub fn take_pending(&mut self) -> u32 {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Hand frames back after a lost capture so the next one covers them.
+    pub fn restore_pending(&mut self, repeat: u32) {
+        self.pending += repeat;
+    }
+
+    pub fn push(&mut self, sim_time: f64, repeat: u32, rgba: Vec<u8>) {
+        if self.truncated || repeat == 0 {
+            return;
+        }
+        if self.bytes + rgba.len() > MAX_RECORDING_BYTES {
+            self.truncated = true;
+            return;
+        }
+        self.bytes += rgba.len();
+        self.frames.push(RecordedFrame { sim_time, repeat, rgba });
+    }
+
+    /// Output frames recorded, counting repeats.
+    pub fn output_frames(&self) -> u32 {
+        self.frames.iter().map(|f| f.repeat).sum()
+    }
+
+    /// `{fps, width, height, frames, captures, bytes, truncated}`.
+    pub fn info_js(&self) -> JsValue {
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"fps".into(), &JsValue::from(self.fps));
+        let _ = js_sys::Reflect::set(&obj, &"width".into(), &JsValue::from(self.width));
+        let _ = js_sys::Reflect::set(&obj, &"height".into(), &JsValue::from(self.height));
+        let _ = js_sys::Reflect::set(&obj, &"frames".into(), &JsValue::from(self.output_frames()));
+        let _ = js_sys::Reflect::set(&obj, &"captures".into(), &JsValue::from(self.frames.len() as u32));
+        let _ = js_sys::Reflect::set(&obj, &"bytes".into(), &JsValue::from(self.bytes as f64));
+        let _ = js_sys::Reflect::set(&obj, &"truncated".into(), &JsValue::from(self.truncated));
+        obj.into()
+    }
+
+    /// `info_js` with `frames` replaced by `[{sim_time, repeat, data}]`,
+    /// `data` a `Uint8ClampedArray` ready for `ImageData`.
+    pub fn to_js(&self) -> JsValue {
+        let obj = self.info_js();
+        let frames = js_sys::Array::new();
+        for f in &self.frames {
+            let entry = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&entry, &"sim_time".into(), &JsValue::from(f.sim_time));
+            let _ = js_sys::Reflect::set(&entry, &"repeat".into(), &JsValue::from(f.repeat));
+            let data = js_sys::Uint8ClampedArray::from(&f.rgba[..]);
+            let _ = js_sys::Reflect::set(&entry, &"data".into(), &data);
+            frames.push(&entry);
+        }
+        let _ = js_sys::Reflect::set(&obj, &"frames".into(), &frames);
+        obj
+    }
+}
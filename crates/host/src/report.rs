@@ -0,0 +1,145 @@
+use wasm_bindgen::prelude::*;
+
+use crate::gpu::GpuTier;
+use crate::App;
+
+/// One engine configuration tried during init.
+pub struct InitAttempt {
+    pub label: String,
+    /// `None` on success.
+    pub error: Option<String>,
+}
+
+/// What init chose and why, reported to JS via `get_capability_report()`.
+pub struct CapabilityReport {
+    /// `None` when no WebGPU device was available (CPU fallback).
+    pub tier: Option<GpuTier>,
+    pub sparse_available: bool,
+    pub sparse: bool,
+    pub grid_size: u32,
+    pub reason: String,
+    pub attempts: Vec<InitAttempt>,
+}
+
+impl CapabilityReport {
+    pub fn to_js(&self) -> JsValue {
+        let obj = js_sys::Object::new();
+        let tier = match self.tier {
+            Some(tier) => format!("{tier:?}"),
+            None => "none".to_string(),
+        };
+        let _ = js_sys::Reflect::set(&obj, &"tier".into(), &JsValue::from(tier));
+        let _ = js_sys::Reflect::set(&obj, &"sparse_available".into(), &JsValue::from(self.sparse_available));
+        let _ = js_sys::Reflect::set(&obj, &"degraded".into(), &JsValue::from(self.tier.is_none()));
+        let mode = match (self.tier, self.sparse) {
+            (None, _) => "cpu",
+            (Some(_), true) => "sparse",
+            (Some(_), false) => "dense",
+        };
+        let _ = js_sys::Reflect::set(&obj, &"mode".into(), &JsValue::from(mode));
+        let _ = js_sys::Reflect::set(&obj, &"grid_size".into(), &JsValue::from(self.grid_size));
+        let _ = js_sys::Reflect::set(&obj, &"reason".into(), &JsValue::from(self.reason.as_str()));
+        let attempts = js_sys::Array::new();
+        for a in &self.attempts {
+            let entry = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&entry, &"label".into(), &JsValue::from(a.label.as_str()));
+            let _ = js_sys::Reflect::set(&entry, &"ok".into(), &JsValue::from(a.error.is_none()));
+            if let Some(ref e) = a.error {
+                let _ = js_sys::Reflect::set(&entry, &"error".into(), &JsValue::from(e.as_str()));
+            }
+            attempts.push(&entry);
+        }
+        let _ = js_sys::Reflect::set(&obj, &"attempts".into(), &attempts);
+        obj.into()
+    }
+}
+
+fn limits_to_js(limits: &wgpu::Limits) -> JsValue {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"max_buffer_size".into(), &JsValue::from(limits.max_buffer_size as f64));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"max_storage_buffer_binding_size".into(),
+        &JsValue::from(limits.max_storage_buffer_binding_size),
+    );
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"max_storage_buffers_per_shader_stage".into(),
+        &JsValue::from(limits.max_storage_buffers_per_shader_stage),
+    );
+    let _ = js_sys::Reflect::set(&obj, &"max_texture_dimension_3d".into(), &JsValue::from(limits.max_texture_dimension_3d));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"max_compute_workgroups_per_dimension".into(),
+        &JsValue::from(limits.max_compute_workgroups_per_dimension),
+    );
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"max_compute_invocations_per_workgroup".into(),
+        &JsValue::from(limits.max_compute_invocations_per_workgroup),
+    );
+    obj.into()
+}
+
+fn allocations_to_js(allocs: &[(&'static str, u64)]) -> JsValue {
+    let arr = js_sys::Array::new();
+    for (label, bytes) in allocs {
+        let entry = js_sys::Array::new();
+        entry.push(&JsValue::from(*label));
+        entry.push(&JsValue::from(*bytes as f64));
+        arr.push(&entry);
+    }
+    arr.into()
+}
+
+/// Full system report for bug reports: adapter, limits, tier, chosen engine
+/// configuration, allocated GPU resources and enabled optional features.
+pub fn gpu_report(app: &App) -> JsValue {
+    let gpu = &app.gpu;
+    let info = &gpu.adapter_info;
+    let obj = js_sys::Object::new();
+
+    let adapter = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&adapter, &"name".into(), &JsValue::from(info.name.as_str()));
+    let _ = js_sys::Reflect::set(&adapter, &"vendor".into(), &JsValue::from(info.vendor));
+    let _ = js_sys::Reflect::set(&adapter, &"device".into(), &JsValue::from(info.device));
+    let _ = js_sys::Reflect::set(&adapter, &"device_type".into(), &JsValue::from(format!("{:?}", info.device_type)));
+    let _ = js_sys::Reflect::set(&adapter, &"backend".into(), &JsValue::from(format!("{:?}", info.backend)));
+    let _ = js_sys::Reflect::set(&adapter, &"driver".into(), &JsValue::from(info.driver.as_str()));
+    let _ = js_sys::Reflect::set(&adapter, &"driver_info".into(), &JsValue::from(info.driver_info.as_str()));
+    let _ = js_sys::Reflect::set(&obj, &"adapter".into(), &adapter);
+
+    let _ = js_sys::Reflect::set(&obj, &"adapter_limits".into(), &limits_to_js(&gpu.adapter_limits));
+    let _ = js_sys::Reflect::set(&obj, &"device_limits".into(), &limits_to_js(&gpu.limits));
+
+    let features = js_sys::Array::new();
+    for (name, _) in gpu.features.iter_names() {
+        features.push(&JsValue::from(name));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"features".into(), &features);
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"pipeline_cache".into(),
+        &JsValue::from(gpu.pipeline_cache.is_some()),
+    );
+
+    let sim_features = js_sys::Array::new();
+    for feature in app.sim_engine.features().iter() {
+        sim_features.push(&JsValue::from(feature.name()));
+    }
+    let _ = js_sys::Reflect::set(&obj, &"sim_features".into(), &sim_features);
+
+    let _ = js_sys::Reflect::set(&obj, &"capability".into(), &app.capability.to_js());
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"surface_format".into(),
+        &JsValue::from(format!("{:?}", gpu.surface_config.format)),
+    );
+
+    let allocs = crate::memory::current_allocations(app);
+    let total: u64 = allocs.iter().map(|(_, b)| b).sum();
+    let _ = js_sys::Reflect::set(&obj, &"allocations".into(), &allocations_to_js(&allocs));
+    let _ = js_sys::Reflect::set(&obj, &"allocated_bytes".into(), &JsValue::from(total as f64));
+
+    obj.into()
+}
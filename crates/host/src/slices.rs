@@ -0,0 +1,207 @@
+//! Slice-by-slice readback of the volume texture for `export_slices`. One
+//! slice is copied to staging per frame, then encoded as a PNG and handed
+//! to JS through the dump event queue. Ticks hold until the last slice so
+//! the stack shows a single state.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+use crate::dump::DumpEvent;
+use crate::readback::{MapStatus, ReadbackState, READBACK_TIMEOUT_FRAMES};
+use types::{SliceAxis, SliceCopy, TransferFunction};
+
+/// `{axis, count, width, height, tick}`.
+fn summary(axis: SliceAxis, grid_size: u32, tick: u32) -> js_sys::Object {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"axis".into(), &JsValue::from(axis.name()));
+    let _ = js_sys::Reflect::set(&obj, &"count".into(), &JsValue::from(grid_size));
+    let _ = js_sys::Reflect::set(&obj, &"width".into(), &JsValue::from(grid_size));
+    let _ = js_sys::Reflect::set(&obj, &"height".into(), &JsValue::from(grid_size));
+    let _ = js_sys::Reflect::set(&obj, &"tick".into(), &JsValue::from(tick));
+    obj
+}
+
+/// Events delivering already-colored slices (the CPU fallback): each
+/// `slice(index)` is RGBA rows, top to bottom.
+pub fn cpu_events(
+    axis: SliceAxis,
+    grid_size: u32,
+    tick: u32,
+    slice: impl Fn(u32) -> Vec<u8>,
+    on_slice: js_sys::Function,
+    resolve: js_sys::Function,
+) -> Vec<DumpEvent> {
+    let mut events: Vec<DumpEvent> = (0..grid_size)
+        .map(|i| DumpEvent::Chunk {
+            on_chunk: on_slice.clone(),
+            bytes: types::encode_png_rgba(grid_size, grid_size, &slice(i)),
+            offset: i as u64,
+            total: grid_size as u64,
+        })
+        .collect();
+    events.push(DumpEvent::Done { resolve, summary: summary(axis, grid_size, tick) });
+    events
+}
+
+pub struct SliceExport {
+    axis: SliceAxis,
+    grid_size: u32,
+    staging: wgpu::Buffer,
+    /// Next slice to copy.
+    next: u32,
+    pub state: ReadbackState,
+    ready: Rc<Cell<MapStatus>>,
+    wait_frames: u32,
+    /// Set while the texture holds transfer-function inputs, to color them.
+    transfer: Option<TransferFunction>,
+    tick: u32,
+    on_slice: js_sys::Function,
+    resolve: js_sys::Function,
+    reject: js_sys::Function,
+}
+
+impl SliceExport {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: &wgpu::Device,
+        axis: SliceAxis,
+        grid_size: u32,
+        transfer: Option<TransferFunction>,
+        tick: u32,
+        on_slice: js_sys::Function,
+        resolve: js_sys::Function,
+        reject: js_sys::Function,
+    ) -> Self {
+        let copy = SliceCopy { axis, index: 0, grid_size, transfer: transfer.is_some() };
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("slice_export_staging"),
+            size: copy.buffer_bytes(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            axis,
+            grid_size,
+            staging,
+            next: 0,
+            state: ReadbackState::Idle,
+            ready: Rc::new(Cell::new(MapStatus::Pending)),
+            wait_frames: 0,
+            transfer,
+            tick,
+            on_slice,
+            resolve,
+            reject,
+        }
+    }
+
+    fn copy(&self) -> SliceCopy {
+        SliceCopy { axis: self.axis, index: self.next, grid_size: self.grid_size, transfer: self.transfer.is_some() }
+    }
+
+    /// Copy the next slice of `texture` into staging unless one is in
+    /// flight.
+    pub fn encode(&mut self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        if self.state != ReadbackState::Idle || self.next >= self.grid_size {
+            return;
+        }
+        let copy = self.copy();
+        let ([ox, oy, oz], [w, h, d]) = (copy.origin(), copy.extent());
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: ox, y: oy, z: oz },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.staging,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(copy.bytes_per_row()),
+                    rows_per_image: Some(copy.rows_per_image()),
+                },
+            },
+            wgpu::Extent3d { width: w, height: h, depth_or_array_layers: d },
+        );
+        self.state = ReadbackState::CopyIssued;
+    }
+
+    /// Map the slice copied this frame, once its encoder was submitted.
+    pub fn request_map(&mut self) {
+        if self.state == ReadbackState::CopyIssued {
+            self.ready = crate::readback::request_map(&self.staging);
+            self.wait_frames = 0;
+            self.state = ReadbackState::MapRequested;
+        }
+    }
+
+    /// Hand a mapped slice to JS as a PNG. Returns true once the export has
+    /// finished or failed.
+    pub fn poll(&mut self, events: &mut Vec<DumpEvent>) -> bool {
+        if self.state != ReadbackState::MapRequested {
+            return false;
+        }
+        match self.ready.get() {
+            MapStatus::Mapped => {
+                let data = self.staging.slice(..).get_mapped_range();
+                let rgba = self.copy().gather(&data, |texel| self.color(texel));
+                drop(data);
+                self.staging.unmap();
+                self.state = ReadbackState::Idle;
+                events.push(DumpEvent::Chunk {
+                    on_chunk: self.on_slice.clone(),
+                    bytes: types::encode_png_rgba(self.grid_size, self.grid_size, &rgba),
+                    offset: self.next as u64,
+                    total: self.grid_size as u64,
+                });
+                self.next += 1;
+                if self.next < self.grid_size {
+                    return false;
+                }
+                events.push(DumpEvent::Done {
+                    resolve: self.resolve.clone(),
+                    summary: summary(self.axis, self.grid_size, self.tick),
+                });
+                true
+            }
+            MapStatus::Failed => {
+                self.fail(events, "slice readback failed");
+                true
+            }
+            MapStatus::Pending => {
+                self.wait_frames += 1;
+                if self.wait_frames < READBACK_TIMEOUT_FRAMES {
+                    return false;
+                }
+                self.fail(events, "slice readback timed out");
+                true
+            }
+        }
+    }
+
+    /// RGBA of a texel: as stored, or mapped through the transfer function.
+    fn color(&self, texel: [u8; 4]) -> [u8; 4] {
+        match &self.transfer {
+            Some(tf) if texel[3] > 0 => {
+                tf.sample(texel[0] as usize, texel[1] as f32 / 255.0).map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+            }
+            Some(_) => [0; 4],
+            None => texel,
+        }
+    }
+
+    /// Abandon the export and reject its promise with `reason`.
+    pub fn fail(&mut self, events: &mut Vec<DumpEvent>, reason: &str) {
+        if self.state == ReadbackState::MapRequested && self.ready.get() != MapStatus::Failed {
+            self.staging.unmap();
+        }
+        self.state = ReadbackState::Idle;
+        events.push(DumpEvent::Failed { reject: self.reject.clone(), reason: format!("Slice export aborted: {reason}") });
+    }
+
+    pub fn staging_size(&self) -> u64 {
+        self.staging.size()
+    }
+}
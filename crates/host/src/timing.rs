@@ -1,10 +1,45 @@
+pub use types::{TickClock, TimingMode};
+
+/// What to do while the page is hidden (tab in background).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BackgroundMode {
+    /// Stop ticking entirely; resumes where it left off when visible again.
+    Pause = 0,
+    /// Keep ticking at `background_tick_rate` without rendering.
+    Headless = 1,
+}
+
+impl BackgroundMode {
+    pub fn from_u32(v: u32) -> Self {
+        match v {
+            1 => BackgroundMode::Headless,
+            _ => BackgroundMode::Pause,
+        }
+    }
+}
+
+/// Default tick rate cap while running headless in the background.
+pub const DEFAULT_BACKGROUND_TICK_RATE: f32 = 5.0;
+
 pub struct FrameTiming {
     pub frame_count: u64,
     pub last_dt: f32,
-    pub tick_accumulator: f32,
     pub tick_rate: f32,
     pub paused: bool,
     pub single_step: bool,
+    /// Tick accounting: accumulator, catch-up cap, dropped ticks and the
+    /// measured tick rate.
+    pub clock: TickClock,
+    pub background_mode: BackgroundMode,
+    pub background_tick_rate: f32,
+    /// True while the page is hidden.
+    pub hidden: bool,
+}
+
+impl Default for FrameTiming {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FrameTiming {
@@ -12,20 +47,39 @@ impl FrameTiming {
         Self {
             frame_count: 0,
             last_dt: 0.0,
-            tick_accumulator: 0.0,
             tick_rate: 10.0,
             paused: false,
             single_step: false,
+            clock: TickClock::new(),
+            background_mode: BackgroundMode::Pause,
+            background_tick_rate: DEFAULT_BACKGROUND_TICK_RATE,
+            hidden: false,
         }
     }
 
     pub fn update(&mut self, dt: f32) {
         self.frame_count += 1;
         self.last_dt = dt;
+        self.clock.elapse(dt);
+    }
+
+    pub fn record_ticks(&mut self, ticks: u32) {
+        self.clock.record_ticks(ticks);
+    }
+
+    pub fn reset_clock(&mut self) {
+        self.clock.reset_clock();
+    }
+
+    /// Average ticks per wall-clock second since the last clock reset.
+    pub fn effective_tps(&self) -> f64 {
+        self.clock.effective_tps()
     }
 
     /// Returns how many simulation ticks should run this frame.
     pub fn ticks_due(&mut self, dt: f32) -> u32 {
+        // The previous frame's ticks are no longer refundable
+        self.clock.commit();
         if self.paused && !self.single_step {
             return 0;
         }
@@ -35,22 +89,45 @@ impl FrameTiming {
             return 1;
         }
 
-        let interval = 1.0 / self.tick_rate;
-        self.tick_accumulator += dt;
-
-        // Spiral of death prevention: if we've fallen behind by 3+ intervals, reset
-        if self.tick_accumulator > interval * 3.0 {
-            self.tick_accumulator = 0.0;
-            return 3;
+        if self.hidden && self.background_mode == BackgroundMode::Pause {
+            return 0;
         }
 
-        let mut ticks = 0u32;
-        while self.tick_accumulator >= interval && ticks < 3 {
-            self.tick_accumulator -= interval;
-            ticks += 1;
+        self.clock.advance(dt, self.effective_tick_rate())
+    }
+
+    /// Return the ticks the last `ticks_due` granted from the tick rate, for
+    /// a frame that ran none of them. Single steps are not refunded.
+    pub fn refund_ticks(&mut self) {
+        self.clock.refund();
+    }
+
+    /// Ticks owed but not yet run (only non-zero in `TimingMode::SimTime`).
+    pub fn backlog_ticks(&self) -> u32 {
+        self.clock.backlog_ticks(self.effective_tick_rate())
+    }
+
+    /// Tick rate in effect right now (reduced while hidden).
+    pub fn effective_tick_rate(&self) -> f32 {
+        if self.hidden {
+            self.tick_rate.min(self.background_tick_rate)
+        } else {
+            self.tick_rate
         }
+    }
 
-        ticks
+    /// Returns true if ticks should keep running while hidden.
+    pub fn set_hidden(&mut self, hidden: bool) -> bool {
+        self.hidden = hidden;
+        hidden && self.background_mode == BackgroundMode::Headless && !self.paused
+    }
+
+    pub fn set_background_mode(&mut self, mode: BackgroundMode) {
+        self.background_mode = mode;
+    }
+
+    pub fn set_background_tick_rate(&mut self, rate: f32) {
+        self.background_tick_rate = rate.clamp(0.1, 60.0);
     }
 
     pub fn toggle_pause(&mut self) {
@@ -68,4 +145,12 @@ impl FrameTiming {
     pub fn set_tick_rate(&mut self, rate: f32) {
         self.tick_rate = rate.clamp(1.0, 60.0);
     }
+
+    pub fn set_mode(&mut self, mode: TimingMode) {
+        self.clock.mode = mode;
+    }
+
+    pub fn set_max_catch_up(&mut self, max_ticks: u32) {
+        self.clock.max_catch_up = max_ticks;
+    }
 }
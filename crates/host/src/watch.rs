@@ -0,0 +1,86 @@
+//! Life-history tracking for a single watched voxel.
+//!
+//! The watched cell is re-read alongside every stats readback. Recording
+//! stops once the cell no longer holds the protocell it started with: the
+//! voxel is no longer a protocell, or its age went backwards (a different
+//! cell moved in).
+
+use wasm_bindgen::prelude::*;
+
+use renderer::PickResult;
+
+/// Samples kept per watch; the oldest are dropped beyond this.
+pub const MAX_WATCH_SAMPLES: usize = 4096;
+
+pub struct WatchSample {
+    pub tick: u32,
+    pub energy: u16,
+    pub age: u16,
+    pub species_id: u16,
+    pub genome: [u8; 16],
+}
+
+pub struct VoxelWatch {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+    pub samples: Vec<WatchSample>,
+    /// Set when the watched protocell died or was replaced.
+    pub ended: bool,
+}
+
+impl VoxelWatch {
+    pub fn new(x: u32, y: u32, z: u32) -> Self {
+        Self { x, y, z, samples: Vec::new(), ended: false }
+    }
+
+    /// Append the cell's state at `tick`, or end the watch if the protocell
+    /// is gone.
+    pub fn record(&mut self, tick: u32, pick: &PickResult) {
+        if self.ended {
+            return;
+        }
+        let replaced = self
+            .samples
+            .last()
+            .is_some_and(|last| pick.age < last.age || pick.species_id != last.species_id);
+        if pick.voxel_type != types::VoxelType::Protocell as u8 || replaced {
+            self.ended = true;
+            return;
+        }
+        if self.samples.len() == MAX_WATCH_SAMPLES {
+            self.samples.remove(0);
+        }
+        self.samples.push(WatchSample {
+            tick,
+            energy: pick.energy,
+            age: pick.age,
+            species_id: pick.species_id,
+            genome: pick.genome,
+        });
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"x".into(), &JsValue::from(self.x));
+        let _ = js_sys::Reflect::set(&obj, &"y".into(), &JsValue::from(self.y));
+        let _ = js_sys::Reflect::set(&obj, &"z".into(), &JsValue::from(self.z));
+        let _ = js_sys::Reflect::set(&obj, &"alive".into(), &JsValue::from(!self.ended));
+        let samples = js_sys::Array::new();
+        for s in &self.samples {
+            let entry = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&entry, &"tick".into(), &JsValue::from(s.tick));
+            let _ = js_sys::Reflect::set(&entry, &"energy".into(), &JsValue::from(s.energy));
+            let _ = js_sys::Reflect::set(&entry, &"age".into(), &JsValue::from(s.age));
+            let _ = js_sys::Reflect::set(&entry, &"species_id".into(), &JsValue::from(s.species_id));
+            let genome = js_sys::Array::new();
+            for b in &s.genome {
+                genome.push(&JsValue::from(*b));
+            }
+            let _ = js_sys::Reflect::set(&entry, &"genome".into(), &genome);
+            samples.push(&entry);
+        }
+        let _ = js_sys::Reflect::set(&obj, &"samples".into(), &samples);
+        obj.into()
+    }
+}
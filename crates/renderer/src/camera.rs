@@ -1,5 +1,6 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec3, Vec4};
 
+#[derive(Clone, PartialEq)]
 pub struct Camera {
     pub distance: f32,
     pub yaw: f32,
@@ -79,9 +80,37 @@ impl Camera {
         self.view_projection().inverse()
     }
 
+    /// World-space ray (origin on the near plane, unit direction) through
+    /// normalized canvas coordinates (0..1, y down).
+    pub fn screen_ray(&self, nx: f32, ny: f32) -> Option<(Vec3, Vec3)> {
+        let inv_vp = self.view_projection_inverse();
+        let ndc_near = Vec4::new(nx * 2.0 - 1.0, 1.0 - ny * 2.0, -1.0, 1.0);
+        let ndc_far = Vec4::new(nx * 2.0 - 1.0, 1.0 - ny * 2.0, 1.0, 1.0);
+
+        let w_near = inv_vp * ndc_near;
+        let w_far = inv_vp * ndc_far;
+        if w_near.w.abs() < 1e-6 || w_far.w.abs() < 1e-6 {
+            return None;
+        }
+        let origin = w_near.truncate() / w_near.w;
+        let far_pt = w_far.truncate() / w_far.w;
+        Some((origin, (far_pt - origin).normalize()))
+    }
+
+    /// Normalized canvas coordinates (0..1, y down) of a world point, or
+    /// `None` if it is behind the camera.
+    pub fn project(&self, world: Vec3) -> Option<(f32, f32)> {
+        let clip = self.view_projection() * world.extend(1.0);
+        if clip.w <= self.near {
+            return None;
+        }
+        Some((clip.x / clip.w * 0.5 + 0.5, 0.5 - clip.y / clip.w * 0.5))
+    }
+
     /// Serialize camera uniform data for GPU.
     /// Layout: inv_view_proj (16 floats), camera_pos (3 floats + pad),
-    ///         grid_size (f32), clip_axis (u32 as f32), clip_position (f32), padding (f32)
+    ///         grid_size (f32), clip_axis (u32 as f32), clip_position (f32),
+    ///         output_transfer (f32, filled in by the renderer)
     pub fn to_uniform_bytes(&self, grid_size: u32) -> Vec<u8> {
         let inv_vp = self.view_projection_inverse();
         let eye = self.eye_position();
@@ -104,11 +133,11 @@ impl Camera {
         bytes.extend_from_slice(&eye.y.to_le_bytes());
         bytes.extend_from_slice(&eye.z.to_le_bytes());
         bytes.extend_from_slice(&0.0f32.to_le_bytes()); // padding
-        // grid_size, clip_axis, clip_position, padding
+        // grid_size, clip_axis, clip_position, output_transfer
         bytes.extend_from_slice(&(grid_size as f32).to_le_bytes());
         bytes.extend_from_slice(&clip_axis_val.to_le_bytes());
         bytes.extend_from_slice(&self.clip_position.to_le_bytes());
-        bytes.extend_from_slice(&0.0f32.to_le_bytes()); // padding
+        bytes.extend_from_slice(&0.0f32.to_le_bytes()); // output_transfer
         bytes
     }
 }
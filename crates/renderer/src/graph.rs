@@ -0,0 +1,332 @@
+use wgpu;
+use types::{load_wgsl, ShaderDefs};
+
+
+/// Samples kept in the history ring; one graph column each.
+pub const GRAPH_HISTORY_LEN: u32 = 256;
+/// Words per history sample: a whole stats buffer.
+pub const GRAPH_SAMPLE_WORDS: u32 = types::layout::STATS_WORDS;
+/// Graph texture size in pixels.
+pub const GRAPH_TEXTURE_SIZE: (u32, u32) = (GRAPH_HISTORY_LEN, 96);
+/// Gap between the graph and the surface corner, in surface pixels.
+pub const GRAPH_MARGIN_PX: f32 = 12.0;
+
+const GRAPH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Bottom-left graph viewport (x, y, w, h) in pixels for a surface of
+/// `width` × `height`: native size, shrunk to at most 30% of the width.
+pub fn graph_rect(width: u32, height: u32) -> (f32, f32, f32, f32) {
+    let (tw, th) = GRAPH_TEXTURE_SIZE;
+    let w = (tw as f32).min(width as f32 * 0.3).floor();
+    let h = (w * th as f32 / tw as f32).floor();
+    let y = height as f32 - h - GRAPH_MARGIN_PX;
+    (GRAPH_MARGIN_PX, y.max(0.0), w, h)
+}
+
+/// Population/species history plotted into a small texture on the GPU and
+/// composited over the surface.
+pub struct GraphPipeline {
+    plot_pipeline: wgpu::RenderPipeline,
+    plot_bind_group: wgpu::BindGroup,
+    composite_pipeline: wgpu::RenderPipeline,
+    composite_bgl: wgpu::BindGroupLayout,
+    history: wgpu::Buffer,
+    uniform: wgpu::Buffer,
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    head: u32,
+    count: u32,
+}
+
+fn uniform_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+impl GraphPipeline {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("graph"),
+            source: wgpu::ShaderSource::Wgsl(load_wgsl("graph.wgsl", &ShaderDefs::new()).into()),
+        });
+
+        let plot_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("graph_plot_bgl"),
+            entries: &[
+                // binding 0: history ring (read-only storage)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 1: graph uniform
+                uniform_entry(1),
+            ],
+        });
+
+        let history = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("graph_history"),
+            size: (GRAPH_HISTORY_LEN * GRAPH_SAMPLE_WORDS * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // graph uniform: 2 × vec4 = 32 bytes
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("graph_uniform"),
+            size: 32,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let plot_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("graph_plot_bg"),
+            layout: &plot_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: history.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: uniform.as_entire_binding() },
+            ],
+        });
+
+        let (tw, th) = GRAPH_TEXTURE_SIZE;
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("graph_texture"),
+            size: wgpu::Extent3d { width: tw, height: th, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: GRAPH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let composite_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("graph_composite_bgl"),
+            entries: &[
+                // binding 1: graph uniform
+                uniform_entry(1),
+                // binding 2: graph texture (textureLoad only)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let plot_pipeline = Self::create_pipeline(device, &shader, &plot_bgl, "fs_plot", GRAPH_FORMAT, None);
+        let composite_pipeline = Self::create_pipeline(
+            device,
+            &shader,
+            &composite_bgl,
+            "fs_composite",
+            surface_format,
+            Some(wgpu::BlendState::ALPHA_BLENDING),
+        );
+
+        Self {
+            plot_pipeline,
+            plot_bind_group,
+            composite_pipeline,
+            composite_bgl,
+            history,
+            uniform,
+            texture,
+            texture_view,
+            head: 0,
+            count: 0,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        bgl: &wgpu::BindGroupLayout,
+        entry_point: &str,
+        format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+    ) -> wgpu::RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("graph_pl"),
+            bind_group_layouts: &[bgl],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(entry_point),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Rebuild the composite pipeline for a new surface format, keeping the history.
+    pub fn set_surface_format(&mut self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("graph"),
+            source: wgpu::ShaderSource::Wgsl(load_wgsl("graph.wgsl", &ShaderDefs::new()).into()),
+        });
+        self.composite_pipeline = Self::create_pipeline(
+            device,
+            &shader,
+            &self.composite_bgl,
+            "fs_composite",
+            surface_format,
+            Some(wgpu::BlendState::ALPHA_BLENDING),
+        );
+    }
+
+    /// (history ring, graph texture) bytes.
+    pub fn buffer_sizes(&self) -> (u64, u64) {
+        let tex = self.texture.width() as u64 * self.texture.height() as u64 * 4;
+        (self.history.size(), tex)
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Forget all samples; the next redraw shows an empty graph.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.count = 0;
+    }
+
+    fn write_uniform(&self, queue: &wgpu::Queue, output_transfer: f32) {
+        let (tw, th) = GRAPH_TEXTURE_SIZE;
+        let data = [
+            self.head as f32,
+            self.count as f32,
+            GRAPH_HISTORY_LEN as f32,
+            output_transfer,
+            tw as f32,
+            th as f32,
+            0.0,
+            0.0,
+        ];
+        queue.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&data));
+    }
+
+    /// Copy the stats buffer into the next ring slot and redraw the graph
+    /// texture. Encode after the tick that ran the stats reduction.
+    pub fn append(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        stats_buffer: &wgpu::Buffer,
+        output_transfer: f32,
+    ) {
+        let bytes = (GRAPH_SAMPLE_WORDS * 4) as u64;
+        encoder.copy_buffer_to_buffer(
+            stats_buffer,
+            0,
+            &self.history,
+            self.head as u64 * bytes,
+            bytes.min(stats_buffer.size()),
+        );
+        self.head = (self.head + 1) % GRAPH_HISTORY_LEN;
+        self.count = (self.count + 1).min(GRAPH_HISTORY_LEN);
+        self.redraw(encoder, queue, output_transfer);
+    }
+
+    /// Plot the current history into the graph texture.
+    pub fn redraw(&self, encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, output_transfer: f32) {
+        self.write_uniform(queue, output_transfer);
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("graph_plot_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.texture_view,
+                resolve_target: None,
+                depth_slice: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.plot_pipeline);
+        pass.set_bind_group(0, &self.plot_bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Draw the graph texture into the `rect` (x, y, w, h) viewport.
+    pub fn encode_composite(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_view: &wgpu::TextureView,
+        rect: (f32, f32, f32, f32),
+        output_transfer: f32,
+    ) {
+        self.write_uniform(queue, output_transfer);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("graph_composite_bg"),
+            layout: &self.composite_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 1, resource: self.uniform.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&self.texture_view) },
+            ],
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("graph_composite_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: surface_view,
+                resolve_target: None,
+                depth_slice: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load, // only the viewport is blended over
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        let (x, y, w, h) = rect;
+        pass.set_viewport(x, y, w, h, 0.0, 1.0);
+        pass.set_pipeline(&self.composite_pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1); // full-screen triangle, clipped to the viewport
+    }
+}
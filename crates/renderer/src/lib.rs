@@ -3,21 +3,90 @@ pub mod render_texture;
 pub mod ray_march;
 pub mod wireframe;
 pub mod picker;
+pub mod marker;
+pub mod minimap;
+pub mod capture;
+pub mod graph;
 
 use camera::Camera;
 use render_texture::RenderTexturePipeline;
 use ray_march::RayMarchPipeline;
 use wireframe::WireframePipeline;
-pub use picker::{VoxelPicker, PickResult};
+use marker::MarkerPipeline;
+use minimap::MinimapPipeline;
+use capture::CaptureTarget;
+use graph::GraphPipeline;
+pub use picker::{VoxelPicker, PickResult, PickRegion, RegionBounds};
+use types::{Marker, RenderStyle, TransferFunction};
+
+/// How ray-marched colors (authored as sRGB display values) are written to
+/// the surface. Matches `output_transfer` in ray_march.wgsl.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputTransfer {
+    /// Surface stores values as written (`*unorm`, `rgba16float`).
+    Encoded = 0,
+    /// `*-srgb` surface encodes on write; the shader decodes to linear first.
+    Linear = 1,
+}
+
+impl OutputTransfer {
+    pub fn for_format(format: wgpu::TextureFormat) -> Self {
+        if format.is_srgb() {
+            OutputTransfer::Linear
+        } else {
+            OutputTransfer::Encoded
+        }
+    }
+}
+
+/// Inclusive cell box of the whole grid.
+fn full_grid(grid_size: u32) -> types::CellBox {
+    let last = grid_size - 1;
+    ((0, 0, 0), (last, last, last))
+}
+
+/// Byte offset of `output_transfer` in the camera uniform.
+const OUTPUT_TRANSFER_OFFSET: usize = 92;
 
 pub struct Renderer {
     render_texture: RenderTexturePipeline,
     ray_march: RayMarchPipeline,
     wireframe: WireframePipeline,
+    /// Wireframes of the allocated bricks, colored per `set_brick_overlay`.
+    brick_overlay: types::BrickOverlay,
+    /// Age of the oldest brick set, which the age coloring spans.
+    brick_max_age: u32,
+    markers: MarkerPipeline,
     camera_buffer: wgpu::Buffer,
     wireframe_uniform_buffer: wgpu::Buffer,
+    marker_uniform_buffer: wgpu::Buffer,
+    minimap: MinimapPipeline,
+    minimap_uniform_buffer: wgpu::Buffer,
+    minimap_enabled: bool,
+    /// The volume changed since the minimap heatmap was last rendered.
+    minimap_stale: bool,
+    graph: GraphPipeline,
+    graph_enabled: bool,
+    /// Offscreen target frames are recorded from, while recording.
+    capture: Option<CaptureTarget>,
+    /// Surface size in pixels, for pixel-sized marker billboards.
+    surface_size: (u32, u32),
+    style: RenderStyle,
+    style_buffer: wgpu::Buffer,
+    /// Per-cell color history for reduced flicker; 4 bytes while it is off.
+    history_buffer: wgpu::Buffer,
+    /// Ramps the volume is mapped through instead of the built-in colors.
+    transfer: Option<TransferFunction>,
+    transfer_lut: wgpu::Buffer,
     grid_size: u32,
+    /// Bounds of the non-empty voxels, from the engine; see `set_active_region`.
+    active_region: Option<types::CellBox>,
+    /// Per volume texture: texels outside this box are transparent; ray
+    /// marching is clipped to the front texture's box.
+    texture_regions: [types::CellBox; 2],
     is_sparse: bool,
+    surface_format: wgpu::TextureFormat,
+    output_transfer: OutputTransfer,
 }
 
 impl Renderer {
@@ -41,22 +110,24 @@ impl Renderer {
 
     fn new_inner(
         device: &wgpu::Device,
-        _queue: &wgpu::Queue,
+        queue: &wgpu::Queue,
         surface_config: &wgpu::SurfaceConfiguration,
         grid_size: u32,
         sparse: bool,
     ) -> Self {
         let render_texture = if sparse {
-            RenderTexturePipeline::new_sparse(device, grid_size)
+            RenderTexturePipeline::new_sparse(device, grid_size, false)
         } else {
-            RenderTexturePipeline::new(device, grid_size)
+            RenderTexturePipeline::new(device, grid_size, false)
         };
         let ray_march = RayMarchPipeline::new(device, surface_config.format);
         let wireframe = WireframePipeline::new(device, surface_config.format);
+        let markers = MarkerPipeline::new(device, surface_config.format);
+        let minimap = MinimapPipeline::new(device, surface_config.format);
 
         let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("camera_uniform"),
-            size: 96, // mat4(64) + vec4(16) + vec4(16)
+            size: 144, // mat4(64) + 5 × vec4(16)
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -69,37 +140,419 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
+        // marker uniform: mat4(64) + vec4(16) = 80 bytes
+        let marker_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("marker_uniform"),
+            size: 80,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // minimap uniform: 2 × vec4 = 32 bytes
+        let minimap_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("minimap_uniform"),
+            size: 32,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let style = RenderStyle::default();
+        let style_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("render_style_uniform"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&style_buffer, 0, bytemuck::cast_slice(&style.to_words()));
+
+        let transfer_lut = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("transfer_lut"),
+            size: (types::TRANSFER_MATERIALS * types::TRANSFER_LUT_SIZE * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         Self {
             render_texture,
             ray_march,
             wireframe,
+            brick_overlay: types::BrickOverlay::Off,
+            brick_max_age: 0,
+            markers,
             camera_buffer,
             wireframe_uniform_buffer,
+            marker_uniform_buffer,
+            minimap,
+            minimap_uniform_buffer,
+            minimap_enabled: true,
+            minimap_stale: true,
+            graph: GraphPipeline::new(device, surface_config.format),
+            graph_enabled: false,
+            capture: None,
+            surface_size: (surface_config.width, surface_config.height),
+            style,
+            style_buffer,
+            history_buffer: Self::create_history_buffer(device, 1),
+            transfer: None,
+            transfer_lut,
             grid_size,
+            active_region: None,
+            texture_regions: [full_grid(grid_size); 2],
             is_sparse: sparse,
+            surface_format: surface_config.format,
+            output_transfer: OutputTransfer::for_format(surface_config.format),
+        }
+    }
+
+    /// Rebuild the surface-facing pipelines after the surface format changed.
+    pub fn set_surface_format(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        if format == self.surface_format {
+            return;
+        }
+        self.ray_march = RayMarchPipeline::new(device, format);
+        self.wireframe = WireframePipeline::new(device, format);
+        self.markers.set_surface_format(device, format);
+        self.minimap = MinimapPipeline::new(device, format);
+        self.graph.set_surface_format(device, format);
+        if let Some(c) = &mut self.capture {
+            let (w, h) = c.size();
+            *c = CaptureTarget::new(device, w, h, format);
+        }
+        self.surface_format = format;
+        self.output_transfer = OutputTransfer::for_format(format);
+    }
+
+    /// Switch the render texture pass between dense and brick-pool voxel
+    /// layouts after the engine migrated its world.
+    pub fn set_sparse(&mut self, device: &wgpu::Device, sparse: bool) {
+        if sparse == self.is_sparse {
+            return;
         }
+        let transfer = self.transfer.is_some();
+        self.render_texture = if sparse {
+            RenderTexturePipeline::new_sparse(device, self.grid_size, transfer)
+        } else {
+            RenderTexturePipeline::new(device, self.grid_size, transfer)
+        };
+        self.texture_regions = [full_grid(self.grid_size); 2];
+        self.is_sparse = sparse;
+    }
+
+    /// Box known to hold every non-empty voxel (`SimEngine::active_bounds`),
+    /// used to shrink render texture updates and the ray march interval.
+    /// Pass `None` while empty cells are drawn, as under the temperature
+    /// overlay.
+    pub fn set_active_region(&mut self, region: Option<types::CellBox>) {
+        self.active_region = region;
+    }
+
+    fn create_history_buffer(device: &wgpu::Device, cells: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("render_history"),
+            size: cells * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
     }
 
+    /// Switch palette and reduced flicker. The history buffer is only
+    /// allocated while reduced flicker is on.
+    pub fn set_style(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, style: RenderStyle) {
+        if style.reduced_flicker != self.style.reduced_flicker {
+            let cells = if style.reduced_flicker { (self.grid_size as u64).pow(3) } else { 1 };
+            self.history_buffer = Self::create_history_buffer(device, cells);
+        }
+        self.style = style;
+        self.write_style(queue);
+    }
+
+    /// Style uniform, with the transfer flag in its last word.
+    fn write_style(&self, queue: &wgpu::Queue) {
+        let mut words = self.style.to_words();
+        words[3] = self.transfer.is_some() as u32;
+        queue.write_buffer(&self.style_buffer, 0, bytemuck::cast_slice(&words));
+    }
+
+    /// Map the volume through `transfer`'s color/opacity ramps, or restore
+    /// the built-in colors (palettes, reduced-flicker easing) with `None`.
+    /// Switching between the two replaces the volume textures, which stay
+    /// empty until the next `update_render_texture`.
+    pub fn set_transfer_function(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, transfer: Option<TransferFunction>) {
+        if let Some(tf) = &transfer {
+            queue.write_buffer(&self.transfer_lut, 0, bytemuck::cast_slice(&tf.lut_words()));
+        }
+        if transfer.is_some() != self.transfer.is_some() {
+            self.render_texture.set_transfer(device, transfer.is_some());
+            self.texture_regions = [full_grid(self.grid_size); 2];
+        }
+        self.transfer = transfer;
+        self.minimap_stale = true;
+        self.write_style(queue);
+    }
+
+    pub fn transfer_function(&self) -> Option<&TransferFunction> {
+        self.transfer.as_ref()
+    }
+
+    /// Replace the markers drawn over the volume.
+    pub fn set_markers(&mut self, queue: &wgpu::Queue, markers: &[Marker]) {
+        self.markers.set_markers(queue, markers);
+    }
+
+    pub fn set_minimap_enabled(&mut self, enabled: bool) {
+        self.minimap_stale |= enabled && !self.minimap_enabled;
+        self.minimap_enabled = enabled;
+    }
+
+    /// Minimap viewport side in pixels, if it is shown.
+    fn minimap_side(&self) -> Option<u32> {
+        let side = minimap::minimap_rect(self.surface_size.0, self.surface_size.1).2;
+        (self.minimap_enabled && side >= 32.0).then_some(side as u32)
+    }
+
+    /// Whether the volume changed since the minimap's density heatmap was
+    /// last rendered (see `encode_minimap_heatmap`).
+    pub fn minimap_heatmap_stale(&self) -> bool {
+        self.minimap_side().is_some() && self.minimap_stale
+    }
+
+    /// Whether the shown minimap has no heatmap at its current size and
+    /// would draw nothing until `encode_minimap_heatmap`.
+    pub fn minimap_heatmap_missing(&self) -> bool {
+        self.minimap_side().is_some_and(|side| !self.minimap.has_heatmap(side))
+    }
+
+    /// Render the minimap's top-down density heatmap from the front volume
+    /// texture. `render_frame` draws the cached heatmap every frame, so this
+    /// can run less often than frames are drawn.
+    pub fn encode_minimap_heatmap(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera: &Camera,
+    ) {
+        let Some(side) = self.minimap_side() else { return };
+        self.write_minimap_uniform(queue, camera);
+        self.minimap.encode_heatmap(
+            encoder,
+            device,
+            self.render_texture.front_view(),
+            &self.minimap_uniform_buffer,
+            &self.transfer_lut,
+            side,
+        );
+        self.minimap_stale = false;
+    }
+
+    fn write_minimap_uniform(&self, queue: &wgpu::Queue, camera: &Camera) {
+        let eye = camera.eye_position();
+        let stride = (self.grid_size / minimap::MINIMAP_Y_SAMPLES).max(1) as f32;
+        let transfer_fn = if self.transfer.is_some() { 1.0f32 } else { 0.0 };
+        let mm_data = [
+            self.grid_size as f32,
+            stride,
+            self.output_transfer as u32 as f32,
+            transfer_fn,
+            camera.target.x,
+            camera.target.z,
+            eye.x,
+            eye.z,
+        ];
+        queue.write_buffer(&self.minimap_uniform_buffer, 0, bytemuck::cast_slice(&mm_data));
+    }
+
+    pub fn minimap_enabled(&self) -> bool {
+        self.minimap_enabled
+    }
+
+    /// Grid (x, z) under normalized canvas coordinates (0..1, y down) if
+    /// they fall inside the minimap.
+    pub fn minimap_hit(&self, nx: f32, ny: f32) -> Option<(f32, f32)> {
+        if !self.minimap_enabled {
+            return None;
+        }
+        let (w, h) = self.surface_size;
+        let (x, y, side) = minimap::minimap_rect(w, h);
+        let u = (nx * w as f32 - x) / side;
+        let v = (ny * h as f32 - y) / side;
+        if side <= 0.0 || !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return None;
+        }
+        let gs = self.grid_size as f32;
+        Some((u * gs, v * gs))
+    }
+
+    /// Create (or drop, with `None`) the offscreen capture target at
+    /// `scale` times the surface size.
+    pub fn set_capture_scale(&mut self, device: &wgpu::Device, scale: Option<f32>) {
+        self.capture = scale.map(|scale| {
+            let (w, h) = types::RecordingConfig::new(1.0, scale).capture_size(self.surface_size.0, self.surface_size.1);
+            CaptureTarget::new(device, w, h, self.surface_format)
+        });
+    }
+
+    pub fn capture_target(&self) -> Option<&CaptureTarget> {
+        self.capture.as_ref()
+    }
+
+    /// Show the population/species history HUD.
+    pub fn set_graph_enabled(&mut self, enabled: bool) {
+        self.graph_enabled = enabled;
+    }
+
+    pub fn graph_enabled(&self) -> bool {
+        self.graph_enabled
+    }
+
+    /// Record a history sample from `stats_buffer` (the stats reduction
+    /// output) and replot the graph. Encode after the tick that filled it.
+    pub fn append_graph_sample(&mut self, encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, stats_buffer: &wgpu::Buffer) {
+        let transfer = self.output_transfer as u32 as f32;
+        self.graph.append(encoder, queue, stats_buffer, transfer);
+    }
+
+    /// Drop the graph history (e.g. after a preset reload).
+    /// Draw a wireframe per brick given to `set_bricks`, colored by its
+    /// visible cells or its age; `Off` drops the bricks.
+    pub fn set_brick_overlay(&mut self, mode: types::BrickOverlay) {
+        self.brick_overlay = mode;
+        if mode == types::BrickOverlay::Off {
+            self.wireframe.clear_bricks();
+        }
+    }
+
+    pub fn brick_overlay(&self) -> types::BrickOverlay {
+        self.brick_overlay
+    }
+
+    /// Allocated bricks (brick coordinates, age in ticks) for the overlay;
+    /// ignored while it is off.
+    pub fn set_bricks(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, bricks: &[((u32, u32, u32), u32)]) {
+        if self.brick_overlay == types::BrickOverlay::Off {
+            return;
+        }
+        self.brick_max_age = bricks.iter().map(|&(_, age)| age).max().unwrap_or(0);
+        self.wireframe.set_bricks(device, queue, bricks);
+    }
+
+    pub fn clear_graph(&mut self) {
+        self.graph.clear();
+    }
+
+    pub fn style(&self) -> RenderStyle {
+        self.style
+    }
+
+    pub fn output_transfer(&self) -> OutputTransfer {
+        self.output_transfer
+    }
+
+    /// GPU bytes the renderer allocates for a grid of this size, with the
+    /// volume textures holding transfer inputs or colors.
+    pub fn estimated_bytes(grid_size: u32, transfer: bool) -> u64 {
+        // Two volume textures + camera (144), wireframe (80), marker (80),
+        // minimap (32), style (16) and texture region (32) uniforms + 24 line vertices × vec3<f32> +
+        // marker instances + transfer lookup table + two occupancy
+        // buffers + the largest minimap heatmap. The reduced-flicker
+        // history is opt-in and not counted.
+        2 * render_texture::volume_texture_bytes(grid_size, transfer)
+            + 144 + 80 + 80 + 32 + 16 + 32 + 24 * 12 + types::MAX_MARKERS as u64 * 24
+            + (types::TRANSFER_MATERIALS * types::TRANSFER_LUT_SIZE * 4) as u64
+            + 2 * render_texture::occupancy_bytes(grid_size)
+            + (minimap::MINIMAP_MAX_PX as u64).pow(2) * 4
+    }
+
+    /// (label, bytes) for every GPU buffer and texture owned by the renderer.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        let tex_bytes = self.render_texture.texture_bytes();
+        vec![
+            ("render_tex_3d_a", tex_bytes),
+            ("render_tex_3d_b", tex_bytes),
+            ("camera_uniform", self.camera_buffer.size()),
+            ("wireframe_uniform", self.wireframe_uniform_buffer.size()),
+            ("wireframe_vb", self.wireframe.vertex_buffer_size()),
+            ("brick_overlay_instances", self.wireframe.brick_buffer_size()),
+            ("marker_uniform", self.marker_uniform_buffer.size()),
+            ("marker_instances", self.markers.instance_buffer_size()),
+            ("minimap_uniform", self.minimap_uniform_buffer.size()),
+            ("minimap_heatmap", self.minimap.heatmap_bytes()),
+            ("render_style_uniform", self.style_buffer.size()),
+            ("render_texture_region", self.render_texture.region_buffer_size()),
+            ("render_occupancy_a", render_texture::occupancy_bytes(self.grid_size)),
+            ("render_occupancy_b", render_texture::occupancy_bytes(self.grid_size)),
+            ("render_history", self.history_buffer.size()),
+            ("transfer_lut", self.transfer_lut.size()),
+            ("graph_history", self.graph.buffer_sizes().0),
+            ("graph_texture", self.graph.buffer_sizes().1),
+            ("capture_target", self.capture.as_ref().map_or(0, |c| c.bytes())),
+        ]
+    }
+
+    /// The volume texture last updated, for copies out of it.
+    pub fn volume_texture(&self) -> &wgpu::Texture {
+        self.render_texture.front_texture()
+    }
+
+    /// View of the volume texture last updated, which frames are drawn from.
     pub fn volume_texture_view(&self) -> &wgpu::TextureView {
-        &self.render_texture.texture_view
+        self.render_texture.front_view()
     }
 
+    /// Update the back volume texture and make it the front one. A render
+    /// encoded earlier keeps sampling the other texture, so it need not
+    /// wait for this update.
+    #[allow(clippy::too_many_arguments)]
     pub fn update_render_texture(
-        &self,
+        &mut self,
         encoder: &mut wgpu::CommandEncoder,
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         voxel_buf: &wgpu::Buffer,
         params_buf: &wgpu::Buffer,
         temp_buf: &wgpu::Buffer,
+        temp_range_buf: &wgpu::Buffer,
         brick_table_buf: Option<&wgpu::Buffer>,
+        tick_params: wgpu::BindingResource<'_>,
     ) {
         let bg = if self.is_sparse {
             let bt = brick_table_buf.expect("sparse mode requires brick_table_buf");
-            self.render_texture.create_sparse_bind_group(device, voxel_buf, params_buf, temp_buf, bt)
+            self.render_texture.create_sparse_bind_group(
+                device,
+                voxel_buf,
+                params_buf,
+                temp_buf,
+                &self.style_buffer,
+                &self.history_buffer,
+                temp_range_buf,
+                bt,
+                tick_params,
+            )
         } else {
-            self.render_texture.create_bind_group(device, voxel_buf, params_buf, temp_buf)
+            self.render_texture.create_bind_group(
+                device,
+                voxel_buf,
+                params_buf,
+                temp_buf,
+                &self.style_buffer,
+                &self.history_buffer,
+                temp_range_buf,
+                tick_params,
+            )
         };
-        self.render_texture.encode(encoder, &bg);
+        // Smoothing history is per cell, so reduced flicker keeps full updates
+        let target = match self.active_region {
+            Some(region) if !self.is_sparse && !self.style.reduced_flicker => region,
+            _ => full_grid(self.grid_size),
+        };
+        // Also cover the box this texture last held so voxels that left it
+        // are cleared
+        let back = self.render_texture.back();
+        let dispatch = types::union_box(self.texture_regions[back], target);
+        self.texture_regions[back] = target;
+        self.render_texture.encode(encoder, queue, &bg, dispatch);
+        self.render_texture.swap();
+        self.minimap_stale = true;
     }
 
     pub fn render_frame(
@@ -111,10 +564,23 @@ impl Renderer {
         device: &wgpu::Device,
     ) {
         // Upload camera uniform
-        let camera_data = camera.to_uniform_bytes(self.grid_size);
+        let mut camera_data = camera.to_uniform_bytes(self.grid_size);
+        let transfer = (self.output_transfer as u32 as f32).to_le_bytes();
+        camera_data[OUTPUT_TRANSFER_OFFSET..OUTPUT_TRANSFER_OFFSET + 4].copy_from_slice(&transfer);
+        // reduced_flicker, transfer function flag + padding
+        let flicker = if self.style.reduced_flicker { 1.0f32 } else { 0.0 };
+        let transfer_fn = if self.transfer.is_some() { 1.0f32 } else { 0.0 };
+        for v in [flicker, transfer_fn, 0.0, 0.0] {
+            camera_data.extend_from_slice(&v.to_le_bytes());
+        }
+        // region_min, region_max: box the ray march is clipped to
+        let (lo, hi) = self.texture_regions[self.render_texture.front()];
+        for v in [lo.0, lo.1, lo.2, 0, hi.0 + 1, hi.1 + 1, hi.2 + 1, 0] {
+            camera_data.extend_from_slice(&(v as f32).to_le_bytes());
+        }
         queue.write_buffer(&self.camera_buffer, 0, &camera_data);
 
-        // Upload wireframe uniform (view_proj + grid_size)
+        // Upload wireframe uniform (view_proj + grid_size + brick overlay)
         let vp = camera.view_projection();
         let mut wf_data = Vec::with_capacity(80);
         for col in 0..4 {
@@ -125,21 +591,68 @@ impl Renderer {
             wf_data.extend_from_slice(&c.w.to_le_bytes());
         }
         wf_data.extend_from_slice(&(self.grid_size as f32).to_le_bytes());
-        wf_data.extend_from_slice(&0.0f32.to_le_bytes());
-        wf_data.extend_from_slice(&0.0f32.to_le_bytes());
+        wf_data.extend_from_slice(&(self.brick_overlay as u32 as f32).to_le_bytes());
+        wf_data.extend_from_slice(&(self.brick_max_age as f32).to_le_bytes());
         wf_data.extend_from_slice(&0.0f32.to_le_bytes());
         queue.write_buffer(&self.wireframe_uniform_buffer, 0, &wf_data);
 
-        // Ray march pass
+        if self.markers.instance_count() > 0 {
+            let mut mk_data = wf_data[..64].to_vec();
+            for v in [self.surface_size.0 as f32, self.surface_size.1 as f32, marker::MARKER_SIZE_PX, 0.0] {
+                mk_data.extend_from_slice(&v.to_le_bytes());
+            }
+            queue.write_buffer(&self.marker_uniform_buffer, 0, &mk_data);
+        }
+
+        self.encode_scene(encoder, surface_view, device);
+
+        // Minimap in the bottom-right corner, from the cached heatmap
+        if self.minimap_side().is_some() {
+            let rect = minimap::minimap_rect(self.surface_size.0, self.surface_size.1);
+            self.write_minimap_uniform(queue, camera);
+            self.minimap.encode(encoder, device, surface_view, &self.minimap_uniform_buffer, rect);
+        }
+
+        // Population/species HUD in the bottom-left corner
+        let rect = graph::graph_rect(self.surface_size.0, self.surface_size.1);
+        if self.graph_enabled && rect.3 >= 24.0 {
+            let transfer = self.output_transfer as u32 as f32;
+            self.graph.encode_composite(encoder, device, queue, surface_view, rect, transfer);
+        }
+    }
+
+    /// Re-draw the scene (without HUD overlays) into the capture target and
+    /// copy it to the capture staging buffer. Call after `render_frame` in
+    /// the same encoder so the uniforms match the frame on screen.
+    pub fn encode_capture(&self, encoder: &mut wgpu::CommandEncoder, device: &wgpu::Device) -> bool {
+        let Some(capture) = &self.capture else { return false };
+        self.encode_scene(encoder, capture.view(), device);
+        capture.encode_copy(encoder);
+        true
+    }
+
+    /// Ray march, wireframe and marker passes into `view`.
+    fn encode_scene(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, device: &wgpu::Device) {
         let rm_bg = self.ray_march.create_bind_group(
             device,
-            &self.render_texture.texture_view,
+            self.render_texture.front_view(),
             &self.camera_buffer,
+            &self.transfer_lut,
+            self.render_texture.front_occupancy(),
         );
-        self.ray_march.encode(encoder, surface_view, &rm_bg);
+        self.ray_march.encode(encoder, view, &rm_bg);
 
         // Wireframe pass (over ray march output)
         let wf_bg = self.wireframe.create_bind_group(device, &self.wireframe_uniform_buffer);
-        self.wireframe.encode(encoder, surface_view, &wf_bg);
+        let brick_bg = (self.wireframe.brick_count() > 0).then(|| {
+            self.wireframe.create_brick_bind_group(device, &self.wireframe_uniform_buffer, self.render_texture.front_occupancy())
+        });
+        self.wireframe.encode(encoder, view, &wf_bg, brick_bg.as_ref());
+
+        // Marker billboards (over everything)
+        if self.markers.instance_count() > 0 {
+            let mk_bg = self.markers.create_bind_group(device, &self.marker_uniform_buffer);
+            self.markers.encode(encoder, view, &mk_bg);
+        }
     }
 }
@@ -0,0 +1,178 @@
+use wgpu;
+
+use types::{Marker, MAX_MARKERS};
+use types::{load_wgsl, ShaderDefs};
+
+
+/// Per-instance vertex data: cell center (3 × f32) + color (3 × f32).
+const INSTANCE_STRIDE: u64 = 24;
+
+/// On-screen marker diameter in pixels.
+pub const MARKER_SIZE_PX: f32 = 14.0;
+
+pub struct MarkerPipeline {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    instance_buffer: wgpu::Buffer,
+    instance_count: u32,
+}
+
+impl MarkerPipeline {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("marker_bgl"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline = Self::create_pipeline(device, &bind_group_layout, surface_format);
+
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("marker_instances"),
+            size: MAX_MARKERS as u64 * INSTANCE_STRIDE,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            instance_buffer,
+            instance_count: 0,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        surface_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("marker"),
+            source: wgpu::ShaderSource::Wgsl(load_wgsl("marker.wgsl", &ShaderDefs::new()).into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("marker_pl"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("marker_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: INSTANCE_STRIDE,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 0,
+                            shader_location: 0,
+                        },
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 12,
+                            shader_location: 1,
+                        },
+                    ],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Rebuild the pipeline for a new surface format, keeping the markers.
+    pub fn set_surface_format(&mut self, device: &wgpu::Device, surface_format: wgpu::TextureFormat) {
+        self.pipeline = Self::create_pipeline(device, &self.bind_group_layout, surface_format);
+    }
+
+    pub fn instance_buffer_size(&self) -> u64 {
+        self.instance_buffer.size()
+    }
+
+    /// Upload marker instances; anything past `MAX_MARKERS` is ignored.
+    pub fn set_markers(&mut self, queue: &wgpu::Queue, markers: &[Marker]) {
+        let markers = &markers[..markers.len().min(MAX_MARKERS)];
+        let mut data: Vec<f32> = Vec::with_capacity(markers.len() * 6);
+        for m in markers {
+            data.extend_from_slice(&[m.pos.0 as f32 + 0.5, m.pos.1 as f32 + 0.5, m.pos.2 as f32 + 0.5]);
+            data.extend(m.color.iter().map(|&c| c as f32 / 255.0));
+        }
+        if !data.is_empty() {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&data));
+        }
+        self.instance_count = markers.len() as u32;
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    pub fn create_bind_group(&self, device: &wgpu::Device, uniform_buf: &wgpu::Buffer) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("marker_bg"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buf.as_entire_binding(),
+            }],
+        })
+    }
+
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        surface_view: &wgpu::TextureView,
+        bind_group: &wgpu::BindGroup,
+    ) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("marker_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: surface_view,
+                resolve_target: None,
+                depth_slice: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load, // draw over the volume
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        pass.draw(0..4, 0..self.instance_count);
+    }
+}
@@ -0,0 +1,261 @@
+use wgpu;
+use types::{load_wgsl, ShaderDefs};
+
+
+/// Largest minimap side, in surface pixels.
+pub const MINIMAP_MAX_PX: f32 = 200.0;
+/// Gap between the minimap and the surface corner, in surface pixels.
+pub const MINIMAP_MARGIN_PX: f32 = 12.0;
+/// Height samples per column; larger grids step several cells at a time.
+pub const MINIMAP_Y_SAMPLES: u32 = 64;
+
+/// Bottom-right minimap viewport (x, y, side) in pixels for a surface of
+/// `width` × `height`: at most a quarter of the shorter side.
+pub fn minimap_rect(width: u32, height: u32) -> (f32, f32, f32) {
+    let side = (width.min(height) as f32 * 0.25).min(MINIMAP_MAX_PX).floor();
+    let x = width as f32 - side - MINIMAP_MARGIN_PX;
+    let y = height as f32 - side - MINIMAP_MARGIN_PX;
+    (x.max(0.0), y.max(0.0), side)
+}
+
+/// Format of the cached density heatmap; the composite pass applies the
+/// output transfer.
+const HEATMAP_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+pub struct MinimapPipeline {
+    /// Renders the density heatmap into `heatmap`.
+    density_pipeline: wgpu::RenderPipeline,
+    density_layout: wgpu::BindGroupLayout,
+    /// Draws the cached heatmap and the camera marks into the surface.
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// Cached heatmap and its side in pixels.
+    heatmap: Option<(wgpu::Texture, wgpu::TextureView, u32)>,
+}
+
+impl MinimapPipeline {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("minimap"),
+            source: wgpu::ShaderSource::Wgsl(load_wgsl("minimap.wgsl", &ShaderDefs::new()).into()),
+        });
+
+        let uniform_entry = wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let density_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("minimap_density_bgl"),
+            entries: &[
+                // binding 0: volume texture 3D (textureLoad only)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 1: minimap uniform
+                uniform_entry,
+                // binding 2: transfer function lookup table
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("minimap_bgl"),
+            entries: &[
+                uniform_entry,
+                // binding 3: cached heatmap
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = |label, layout: &wgpu::BindGroupLayout, entry_point, format| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        Self {
+            density_pipeline: pipeline("minimap_density_pipeline", &density_layout, "fs_density", HEATMAP_FORMAT),
+            density_layout,
+            pipeline: pipeline("minimap_pipeline", &bind_group_layout, "fs_main", surface_format),
+            bind_group_layout,
+            heatmap: None,
+        }
+    }
+
+    /// Whether a heatmap `side` pixels across is cached.
+    pub fn has_heatmap(&self, side: u32) -> bool {
+        self.heatmap.as_ref().is_some_and(|&(_, _, s)| s == side)
+    }
+
+    pub fn heatmap_bytes(&self) -> u64 {
+        self.heatmap.as_ref().map_or(0, |&(_, _, s)| s as u64 * s as u64 * 4)
+    }
+
+    /// Render the density heatmap of `volume_view` into the cache,
+    /// (re)creating it at `side` × `side` pixels.
+    pub fn encode_heatmap(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        volume_view: &wgpu::TextureView,
+        uniform_buf: &wgpu::Buffer,
+        transfer_lut: &wgpu::Buffer,
+        side: u32,
+    ) {
+        if !self.has_heatmap(side) {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("minimap_heatmap"),
+                size: wgpu::Extent3d { width: side, height: side, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: HEATMAP_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.heatmap = Some((texture, view, side));
+        }
+        let Some((_, view, _)) = &self.heatmap else { return };
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("minimap_density_bg"),
+            layout: &self.density_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(volume_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: transfer_lut.as_entire_binding(),
+                },
+            ],
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("minimap_density_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                depth_slice: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.density_pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Draw the cached heatmap into the `rect` (x, y, side) viewport of the
+    /// surface; nothing before the first `encode_heatmap`.
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        surface_view: &wgpu::TextureView,
+        uniform_buf: &wgpu::Buffer,
+        rect: (f32, f32, f32),
+    ) {
+        let Some((_, heatmap_view, _)) = &self.heatmap else { return };
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("minimap_bg"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(heatmap_view),
+                },
+            ],
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("minimap_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: surface_view,
+                resolve_target: None,
+                depth_slice: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load, // only the viewport is overwritten
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        let (x, y, side) = rect;
+        pass.set_viewport(x, y, side, side, 0.0, 1.0);
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1); // full-screen triangle, clipped to the viewport
+    }
+}
@@ -1,5 +1,27 @@
+use glam::Vec3;
 use wgpu;
 
+use crate::camera::Camera;
+use types::{load_wgsl, ShaderDefs};
+
+
+/// Minimum texel alpha that counts as a hit (matches the ray marcher's
+/// compositing cutoff).
+const PICK_ALPHA_THRESHOLD: f32 = 0.01;
+
+/// Largest neighborhood radius `request_region` accepts (7³ cells).
+pub const MAX_REGION_RADIUS: u32 = 3;
+const MAX_REGION_CELLS: u64 = (2 * MAX_REGION_RADIUS as u64 + 1).pow(3);
+/// Staging layout: picked voxel record (32), its temperature (4) and
+/// pheromone cell word (4), padding to 48, then region voxel records, then
+/// region temperatures.
+const PICK_TEMP_OFFSET: u64 = 32;
+const PICK_PHEROMONE_OFFSET: u64 = 36;
+const REGION_VOXELS_OFFSET: u64 = 48;
+const REGION_TEMPS_OFFSET: u64 = REGION_VOXELS_OFFSET + MAX_REGION_CELLS * 32;
+const STAGING_SIZE: u64 = REGION_TEMPS_OFFSET + MAX_REGION_CELLS * 4;
+
+#[derive(Clone)]
 pub struct PickResult {
     pub x: u32,
     pub y: u32,
@@ -9,38 +31,349 @@ pub struct PickResult {
     pub age: u16,
     pub species_id: u16,
     pub genome: [u8; 16],
+    /// Named genome traits decoded from `genome`.
+    pub traits: [(&'static str, u8); 17],
+    pub temperature: f32,
+    /// Scent intensity of the pheromone cell holding this voxel.
+    pub scent: u16,
+    /// Allelopathic toxin level of that cell.
+    pub toxin: u8,
 }
 
+/// Grid-clipped box of cells read back around a pick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegionBounds {
+    pub min: [u32; 3],
+    pub dims: [u32; 3],
+}
+
+impl RegionBounds {
+    /// Cube of `radius` around `center`, clipped to the grid.
+    pub fn around(center: (u32, u32, u32), radius: u32, grid_size: u32) -> Self {
+        let radius = radius.min(MAX_REGION_RADIUS);
+        let c = [center.0, center.1, center.2];
+        let mut min = [0; 3];
+        let mut dims = [0; 3];
+        for a in 0..3 {
+            min[a] = c[a].saturating_sub(radius);
+            dims[a] = (c[a] + radius).min(grid_size - 1) - min[a] + 1;
+        }
+        Self { min, dims }
+    }
+
+    pub fn cell_count(&self) -> usize {
+        (self.dims[0] * self.dims[1] * self.dims[2]) as usize
+    }
+}
+
+/// Neighborhood around a pick, x-fastest within the bounds.
+pub struct PickRegion {
+    pub bounds: RegionBounds,
+    /// Temperature is filled in; pheromone fields are left zero.
+    pub cells: Vec<PickResult>,
+}
+
+/// Two-stage pick: a compute pass marches the volume texture along the
+/// click ray to find the first visible voxel (`request_hit`), then that
+/// voxel's record is copied out of the sim buffer (`request_pick`).
 pub struct VoxelPicker {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buf: wgpu::Buffer,
+    hit_buf: wgpu::Buffer,
+    hit_staging_buf: wgpu::Buffer,
     staging_buf: wgpu::Buffer,
+    /// Separate 1-voxel staging for the watched cell, so periodic re-reads
+    /// never collide with a user pick.
+    watch_staging_buf: wgpu::Buffer,
 }
 
 impl VoxelPicker {
     pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("pick"),
+            source: wgpu::ShaderSource::Wgsl(load_wgsl("pick.wgsl", &ShaderDefs::new()).into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pick_bgl"),
+            entries: &[
+                // binding 0: volume texture 3D
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // binding 1: pick ray uniform
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 2: hit output (found, x, y, z)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pick_pl"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("pick_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        // origin(16) + dir(16) + clip(16)
+        let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pick_uniform"),
+            size: 48,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let hit_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pick_hit"),
+            size: 16,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let hit_staging_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pick_hit_staging"),
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
         let staging_buf = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("pick_staging"),
-            size: 32, // 1 voxel = 8 × u32 = 32 bytes
+            size: STAGING_SIZE,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let watch_staging_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("watch_staging"),
+            size: 32,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
-        Self { staging_buf }
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buf,
+            hit_buf,
+            hit_staging_buf,
+            staging_buf,
+            watch_staging_buf,
+        }
+    }
+
+    /// March `volume_view` along the ray and copy the first visible voxel
+    /// into the hit staging buffer. Voxels beyond the camera's clip plane
+    /// are skipped, as in the ray marcher.
+    #[allow(clippy::too_many_arguments)]
+    pub fn request_hit(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        volume_view: &wgpu::TextureView,
+        camera: &Camera,
+        ray: (Vec3, Vec3),
+        grid_size: u32,
+    ) {
+        let (origin, dir) = ray;
+        let clip_axis = camera.clip_axis.map_or(-1.0, |a| a as f32);
+        let uniform: [f32; 12] = [
+            origin.x, origin.y, origin.z, grid_size as f32,
+            dir.x, dir.y, dir.z, PICK_ALPHA_THRESHOLD,
+            clip_axis, camera.clip_position, 0.0, 0.0,
+        ];
+        queue.write_buffer(&self.uniform_buf, 0, bytemuck::cast_slice(&uniform));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pick_bg"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(volume_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.uniform_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.hit_buf.as_entire_binding(),
+                },
+            ],
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("pick_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.hit_buf, 0, &self.hit_staging_buf, 0, 16);
+    }
+
+    /// Copy one voxel record into the watch staging buffer.
+    pub fn request_watch(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        voxel_buf: &wgpu::Buffer,
+        voxel_index: u32,
+    ) {
+        encoder.copy_buffer_to_buffer(voxel_buf, voxel_index as u64 * 32, &self.watch_staging_buf, 0, 32);
+    }
+
+    pub fn watch_staging_buffer(&self) -> &wgpu::Buffer {
+        &self.watch_staging_buf
+    }
+
+    pub fn hit_staging_buffer(&self) -> &wgpu::Buffer {
+        &self.hit_staging_buf
     }
 
+    /// Grid cell found by `request_hit`, or `None` if the ray hit nothing.
+    pub fn parse_hit(data: &[u8]) -> Option<(u32, u32, u32)> {
+        let words: &[u32] = bytemuck::cast_slice(data);
+        (words[0] != 0).then(|| (words[1], words[2], words[3]))
+    }
+
+    /// Copy the voxel record at `voxel_index`, its temperature and the
+    /// pheromone cell covering (x, y, z) into the staging buffer.
+    #[allow(clippy::too_many_arguments)]
     pub fn request_pick(
         &self,
         encoder: &mut wgpu::CommandEncoder,
         voxel_buf: &wgpu::Buffer,
+        temp_buf: &wgpu::Buffer,
+        pheromone_buf: &wgpu::Buffer,
         voxel_index: u32,
+        pos: (u32, u32, u32),
+        grid_size: u32,
     ) {
         let byte_offset = voxel_index as u64 * 32;
         encoder.copy_buffer_to_buffer(voxel_buf, byte_offset, &self.staging_buf, 0, 32);
+        encoder.copy_buffer_to_buffer(temp_buf, voxel_index as u64 * 4, &self.staging_buf, PICK_TEMP_OFFSET, 4);
+        let cell = types::pheromone_index(pos.0, pos.1, pos.2, grid_size) as u64;
+        encoder.copy_buffer_to_buffer(pheromone_buf, cell * 4, &self.staging_buf, PICK_PHEROMONE_OFFSET, 4);
+    }
+
+    /// (label, bytes) for every GPU buffer the picker owns.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("pick_uniform", self.uniform_buf.size()),
+            ("pick_hit", self.hit_buf.size()),
+            ("pick_hit_staging", self.hit_staging_buf.size()),
+            ("pick_staging", self.staging_buf.size()),
+            ("watch_staging", self.watch_staging_buf.size()),
+        ]
+    }
+
+    /// Copy the dense-grid cells in `bounds` (voxel records and
+    /// temperatures) into the staging buffer behind the picked voxel, one
+    /// row of x per copy. Issue alongside `request_pick`.
+    pub fn request_region(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        voxel_buf: &wgpu::Buffer,
+        temp_buf: &wgpu::Buffer,
+        bounds: RegionBounds,
+        grid_size: u32,
+    ) {
+        let [x0, y0, z0] = bounds.min;
+        let [w, h, d] = bounds.dims;
+        let mut row = 0u64;
+        for z in z0..z0 + d {
+            for y in y0..y0 + h {
+                let idx = types::grid_index(x0, y, z, grid_size) as u64;
+                let dst = row * w as u64;
+                encoder.copy_buffer_to_buffer(
+                    voxel_buf,
+                    idx * 32,
+                    &self.staging_buf,
+                    REGION_VOXELS_OFFSET + dst * 32,
+                    w as u64 * 32,
+                );
+                encoder.copy_buffer_to_buffer(
+                    temp_buf,
+                    idx * 4,
+                    &self.staging_buf,
+                    REGION_TEMPS_OFFSET + dst * 4,
+                    w as u64 * 4,
+                );
+                row += 1;
+            }
+        }
     }
 
     pub fn staging_buffer(&self) -> &wgpu::Buffer {
         &self.staging_buf
     }
 
+    /// Decode the region written by `request_region` from mapped staging.
+    pub fn parse_region(data: &[u8], bounds: RegionBounds) -> PickRegion {
+        let n = bounds.cell_count();
+        let [x0, y0, z0] = bounds.min;
+        let [w, h, _] = bounds.dims;
+        let voxels = &data[REGION_VOXELS_OFFSET as usize..];
+        let temps: &[f32] = bytemuck::cast_slice(&data[REGION_TEMPS_OFFSET as usize..]);
+        let cells = (0..n as u32)
+            .map(|i| {
+                let (x, y, z) = (x0 + i % w, y0 + i / w % h, z0 + i / (w * h));
+                let o = i as usize * 32;
+                let mut cell = Self::parse_voxel(&voxels[o..o + 32], x, y, z);
+                cell.temperature = temps[i as usize];
+                cell
+            })
+            .collect();
+        PickRegion { bounds, cells }
+    }
+
+    /// Decode the pick written by `request_pick`: voxel record plus its
+    /// temperature and pheromone cell.
     pub fn parse_pick(data: &[u8], x: u32, y: u32, z: u32) -> PickResult {
+        let mut pick = Self::parse_voxel(&data[..32], x, y, z);
+        let words: &[u32] = bytemuck::cast_slice(&data[..REGION_VOXELS_OFFSET as usize]);
+        pick.temperature = f32::from_bits(words[PICK_TEMP_OFFSET as usize / 4]);
+        let cell = words[PICK_PHEROMONE_OFFSET as usize / 4];
+        pick.scent = types::pheromone_unpack(cell).1 as u16;
+        pick.toxin = types::toxin_level(cell) as u8;
+        pick
+    }
+
+    /// Decode one 32-byte voxel record; temperature and pheromone fields
+    /// are left zero.
+    pub fn parse_voxel(data: &[u8], x: u32, y: u32, z: u32) -> PickResult {
         let words: &[u32] = bytemuck::cast_slice(data);
         let word0 = words[0];
         let word1 = words[1];
@@ -63,6 +396,10 @@ impl VoxelPicker {
             age,
             species_id,
             genome,
+            traits: types::Genome { bytes: genome }.named_traits(),
+            temperature: 0.0,
+            scent: 0,
+            toxin: 0,
         }
     }
 }
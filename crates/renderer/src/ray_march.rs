@@ -1,6 +1,6 @@
 use wgpu;
+use types::{load_wgsl, ShaderDefs};
 
-const RAY_MARCH_WGSL: &str = include_str!("../../../shaders/ray_march.wgsl");
 
 pub struct RayMarchPipeline {
     pipeline: wgpu::RenderPipeline,
@@ -12,7 +12,7 @@ impl RayMarchPipeline {
     pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("ray_march"),
-            source: wgpu::ShaderSource::Wgsl(RAY_MARCH_WGSL.into()),
+            source: wgpu::ShaderSource::Wgsl(load_wgsl("ray_march.wgsl", &ShaderDefs::new()).into()),
         });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -47,6 +47,28 @@ impl RayMarchPipeline {
                     },
                     count: None,
                 },
+                // binding 3: transfer function lookup table
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 4: volume block occupancy, for empty-space skipping
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -104,6 +126,8 @@ impl RayMarchPipeline {
         device: &wgpu::Device,
         volume_view: &wgpu::TextureView,
         camera_buf: &wgpu::Buffer,
+        transfer_lut: &wgpu::Buffer,
+        occupancy: &wgpu::Buffer,
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("ray_march_bg"),
@@ -121,6 +145,14 @@ impl RayMarchPipeline {
                     binding: 2,
                     resource: camera_buf.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: transfer_lut.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: occupancy.as_entire_binding(),
+                },
             ],
         })
     }
@@ -1,20 +1,88 @@
 use wgpu;
+use types::{load_wgsl, ShaderDefs, SPARSE_DEFINE};
 
-const COMMON_WGSL: &str = include_str!("../../../shaders/common.wgsl");
-const BRICK_COMMON_WGSL: &str = include_str!("../../../shaders/brick_common.wgsl");
-const UPDATE_RENDER_TEXTURE_WGSL: &str = include_str!("../../../shaders/update_render_texture.wgsl");
 
 pub struct RenderTexturePipeline {
     pipeline: wgpu::ComputePipeline,
     bind_group_layout: wgpu::BindGroupLayout,
-    pub texture: wgpu::Texture,
-    pub texture_view: wgpu::TextureView,
-    grid_size: u32,
+    /// Two volume textures: updates write the back one while the render
+    /// samples the front one. While a transfer function is set they hold
+    /// its inputs at `types::TRANSFER_CELLS_PER_TEXEL` cells per texel.
+    textures: [wgpu::Texture; 2],
+    texture_views: [wgpu::TextureView; 2],
+    transfer: bool,
+    /// Index of the texture last written, which the render samples.
+    front: usize,
+    /// Per texture, one word per `OCCUPANCY_CELL`³ block of texels: the
+    /// visible texels written, so the ray march can skip empty blocks (and
+    /// the brick overlay shows how full each brick is).
+    occupancy: [wgpu::Buffer; 2],
+    /// Box of grid positions the next update covers (`DispatchRegion`).
+    region_buf: wgpu::Buffer,
+}
+
+fn create_region_buffer(device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("render_texture_region"),
+        size: 32,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Side of an occupancy block, in texels. Matches ray_march.wgsl.
+pub const OCCUPANCY_CELL: u32 = 8;
+
+/// Bytes of one occupancy buffer for a `grid_size`³ texture.
+pub fn occupancy_bytes(grid_size: u32) -> u64 {
+    (grid_size.div_ceil(OCCUPANCY_CELL) as u64).pow(3) * 4
+}
+
+/// Bytes of one `grid_size`³ volume texture (Rgba8Unorm: 4 bytes per
+/// texel, so 2 per cell while it holds transfer inputs).
+pub fn volume_texture_bytes(grid_size: u32, transfer: bool) -> u64 {
+    let width = if transfer { types::transfer_texture_width(grid_size) } else { grid_size };
+    width as u64 * (grid_size as u64).pow(2) * 4
+}
+
+fn create_occupancy_buffers(device: &wgpu::Device, grid_size: u32) -> [wgpu::Buffer; 2] {
+    ["render_occupancy_a", "render_occupancy_b"].map(|label| {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: occupancy_bytes(grid_size),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    })
+}
+
+fn create_volume_textures(device: &wgpu::Device, grid_size: u32, transfer: bool) -> ([wgpu::Texture; 2], [wgpu::TextureView; 2]) {
+    let width = if transfer { types::transfer_texture_width(grid_size) } else { grid_size };
+    let textures = ["render_tex_3d_a", "render_tex_3d_b"].map(|label| {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height: grid_size,
+                depth_or_array_layers: grid_size,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    });
+    let views = [0, 1].map(|i| textures[i].create_view(&wgpu::TextureViewDescriptor::default()));
+    (textures, views)
 }
 
 impl RenderTexturePipeline {
-    pub fn new(device: &wgpu::Device, grid_size: u32) -> Self {
-        let shader_source = format!("{}\n{}", COMMON_WGSL, UPDATE_RENDER_TEXTURE_WGSL);
+    pub fn new(device: &wgpu::Device, grid_size: u32, transfer: bool) -> Self {
+        let shader_source = load_wgsl("update_render_texture.wgsl", &ShaderDefs::new());
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("update_render_texture"),
             source: wgpu::ShaderSource::Wgsl(shader_source.into()),
@@ -67,6 +135,72 @@ impl RenderTexturePipeline {
                     },
                     count: None,
                 },
+                // binding 4: render style uniform
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 5: reduced-flicker history (read-write storage)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 6: dispatch region uniform
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 7: temperature range (read-only storage)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 8: block occupancy of the texture written
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 14: the sim's latest TickParams slot (uniform)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 14,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -85,34 +219,22 @@ impl RenderTexturePipeline {
             cache: None,
         });
 
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("render_tex_3d"),
-            size: wgpu::Extent3d {
-                width: grid_size,
-                height: grid_size,
-                depth_or_array_layers: grid_size,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D3,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (textures, texture_views) = create_volume_textures(device, grid_size, transfer);
 
         Self {
             pipeline,
             bind_group_layout,
-            texture,
-            texture_view,
-            grid_size,
+            textures,
+            texture_views,
+            transfer,
+            front: 0,
+            occupancy: create_occupancy_buffers(device, grid_size),
+            region_buf: create_region_buffer(device),
         }
     }
 
-    pub fn new_sparse(device: &wgpu::Device, grid_size: u32) -> Self {
-        let shader_source = format!("{}\n{}\n{}", COMMON_WGSL, BRICK_COMMON_WGSL, UPDATE_RENDER_TEXTURE_WGSL);
+    pub fn new_sparse(device: &wgpu::Device, grid_size: u32, transfer: bool) -> Self {
+        let shader_source = load_wgsl("update_render_texture.wgsl", &ShaderDefs::new().define(SPARSE_DEFINE));
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("sparse_update_render_texture"),
             source: wgpu::ShaderSource::Wgsl(shader_source.into()),
@@ -161,6 +283,61 @@ impl RenderTexturePipeline {
                     },
                     count: None,
                 },
+                // binding 4: render style uniform
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 5: reduced-flicker history (read-write storage)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 6: dispatch region uniform
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 7: temperature range (read-only storage)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // binding 8: block occupancy of the texture written
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
                 // binding 10: brick_table
                 wgpu::BindGroupLayoutEntry {
                     binding: 10,
@@ -172,6 +349,17 @@ impl RenderTexturePipeline {
                     },
                     count: None,
                 },
+                // binding 14: the sim's latest TickParams slot (uniform)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 14,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -190,39 +378,32 @@ impl RenderTexturePipeline {
             cache: None,
         });
 
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("render_tex_3d"),
-            size: wgpu::Extent3d {
-                width: grid_size,
-                height: grid_size,
-                depth_or_array_layers: grid_size,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D3,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (textures, texture_views) = create_volume_textures(device, grid_size, transfer);
 
         Self {
             pipeline,
             bind_group_layout,
-            texture,
-            texture_view,
-            grid_size,
+            textures,
+            texture_views,
+            transfer,
+            front: 0,
+            occupancy: create_occupancy_buffers(device, grid_size),
+            region_buf: create_region_buffer(device),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_sparse_bind_group(
         &self,
         device: &wgpu::Device,
         voxel_buf: &wgpu::Buffer,
         params_buf: &wgpu::Buffer,
         temp_buf: &wgpu::Buffer,
+        style_buf: &wgpu::Buffer,
+        history_buf: &wgpu::Buffer,
+        temp_range_buf: &wgpu::Buffer,
         brick_table_buf: &wgpu::Buffer,
+        tick_params: wgpu::BindingResource<'_>,
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("sparse_render_texture_bg"),
@@ -234,7 +415,7 @@ impl RenderTexturePipeline {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&self.texture_view),
+                    resource: wgpu::BindingResource::TextureView(&self.texture_views[self.back()]),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
@@ -244,20 +425,49 @@ impl RenderTexturePipeline {
                     binding: 3,
                     resource: temp_buf.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: style_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: history_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: self.region_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: temp_range_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: self.occupancy[self.back()].as_entire_binding(),
+                },
                 wgpu::BindGroupEntry {
                     binding: 10,
                     resource: brick_table_buf.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 14,
+                    resource: tick_params,
+                },
             ],
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_bind_group(
         &self,
         device: &wgpu::Device,
         voxel_buf: &wgpu::Buffer,
         params_buf: &wgpu::Buffer,
         temp_buf: &wgpu::Buffer,
+        style_buf: &wgpu::Buffer,
+        history_buf: &wgpu::Buffer,
+        temp_range_buf: &wgpu::Buffer,
+        tick_params: wgpu::BindingResource<'_>,
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("render_texture_bg"),
@@ -269,7 +479,7 @@ impl RenderTexturePipeline {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&self.texture_view),
+                    resource: wgpu::BindingResource::TextureView(&self.texture_views[self.back()]),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
@@ -279,18 +489,104 @@ impl RenderTexturePipeline {
                     binding: 3,
                     resource: temp_buf.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: style_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: history_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: self.region_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: temp_range_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: self.occupancy[self.back()].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 14,
+                    resource: tick_params,
+                },
             ],
         })
     }
 
-    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup) {
-        let wg = self.grid_size / 4;
+    /// Update the texels of `region` (inclusive cell box) only.
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        bind_group: &wgpu::BindGroup,
+        region: types::CellBox,
+    ) {
+        let (lo, hi) = region;
+        let words = [lo.0, lo.1, lo.2, 0, hi.0 + 1, hi.1 + 1, hi.2 + 1, 0];
+        queue.write_buffer(&self.region_buf, 0, bytemuck::cast_slice(&words));
+        // Texels outside the region are clipped away, so only the region's
+        // blocks need marking
+        encoder.clear_buffer(&self.occupancy[self.back()], 0, None);
+        let groups = |lo: u32, hi: u32| (hi + 1 - lo).div_ceil(types::WORKGROUP_SIZE);
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("update_render_texture_pass"),
             timestamp_writes: None,
         });
         pass.set_pipeline(&self.pipeline);
         pass.set_bind_group(0, bind_group, &[]);
-        pass.dispatch_workgroups(wg, wg, wg);
+        pass.dispatch_workgroups(groups(lo.0, hi.0), groups(lo.1, hi.1), groups(lo.2, hi.2));
+    }
+
+    /// Switch the volume textures between colors and transfer inputs.
+    /// New textures are empty until the next update.
+    pub fn set_transfer(&mut self, device: &wgpu::Device, transfer: bool) {
+        if transfer != self.transfer {
+            let grid_size = self.textures[0].height();
+            (self.textures, self.texture_views) = create_volume_textures(device, grid_size, transfer);
+            self.transfer = transfer;
+        }
+    }
+
+    /// Index of the texture the next update writes.
+    pub fn back(&self) -> usize {
+        1 - self.front
+    }
+
+    pub fn front(&self) -> usize {
+        self.front
+    }
+
+    /// Make the texture just written the one the render samples.
+    pub fn swap(&mut self) {
+        self.front = self.back();
+    }
+
+    /// The texture the render samples.
+    pub fn front_texture(&self) -> &wgpu::Texture {
+        &self.textures[self.front]
+    }
+
+    /// View of the texture the render samples.
+    pub fn front_view(&self) -> &wgpu::TextureView {
+        &self.texture_views[self.front]
+    }
+
+    /// Occupancy of the texture the render samples.
+    pub fn front_occupancy(&self) -> &wgpu::Buffer {
+        &self.occupancy[self.front]
+    }
+
+    /// Bytes of one volume texture.
+    pub fn texture_bytes(&self) -> u64 {
+        volume_texture_bytes(self.textures[0].height(), self.transfer)
+    }
+
+    /// Size of the region uniform, for allocation reports.
+    pub fn region_buffer_size(&self) -> u64 {
+        self.region_buf.size()
     }
 }
@@ -1,7 +1,7 @@
 use wgpu;
 use wgpu::util::DeviceExt;
+use types::{load_wgsl, ShaderDefs};
 
-const WIREFRAME_WGSL: &str = include_str!("../../../shaders/wireframe.wgsl");
 
 // 12 edges of a unit cube, each edge = 2 vertices = 24 vertices total
 #[rustfmt::skip]
@@ -23,18 +23,28 @@ const CUBE_EDGES: [[f32; 3]; 24] = [
     [0.0, 0.0, 1.0], [0.0, 1.0, 1.0],
 ];
 
+/// Per-instance data of the brick overlay: brick coordinates and age in
+/// ticks (4 × f32).
+const BRICK_INSTANCE_STRIDE: u64 = 16;
+
 pub struct WireframePipeline {
     pipeline: wgpu::RenderPipeline,
     bind_group_layout: wgpu::BindGroupLayout,
     vertex_buffer: wgpu::Buffer,
     vertex_count: u32,
+    /// The cube edges again, instanced per allocated brick.
+    brick_pipeline: wgpu::RenderPipeline,
+    brick_bind_group_layout: wgpu::BindGroupLayout,
+    /// Grown to fit; `None` until bricks are first set.
+    brick_instances: Option<wgpu::Buffer>,
+    brick_count: u32,
 }
 
 impl WireframePipeline {
     pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("wireframe"),
-            source: wgpu::ShaderSource::Wgsl(WIREFRAME_WGSL.into()),
+            source: wgpu::ShaderSource::Wgsl(load_wgsl("wireframe.wgsl", &ShaderDefs::new()).into()),
         });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -96,6 +106,86 @@ impl WireframePipeline {
             cache: None,
         });
 
+        let brick_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("brick_overlay_bgl"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let brick_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("brick_overlay_pl"),
+            bind_group_layouts: &[&brick_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let brick_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("brick_overlay_pipeline"),
+            layout: Some(&brick_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_brick"),
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 12,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 0,
+                            shader_location: 0,
+                        }],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: BRICK_INSTANCE_STRIDE,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &[wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x4,
+                            offset: 0,
+                            shader_location: 1,
+                        }],
+                    },
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_brick"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
         // Flatten vertex data
         let vertex_data: Vec<f32> = CUBE_EDGES.iter().flatten().copied().collect();
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -109,9 +199,74 @@ impl WireframePipeline {
             bind_group_layout,
             vertex_buffer,
             vertex_count: 24,
+            brick_pipeline,
+            brick_bind_group_layout,
+            brick_instances: None,
+            brick_count: 0,
         }
     }
 
+    pub fn vertex_buffer_size(&self) -> u64 {
+        self.vertex_buffer.size()
+    }
+
+    pub fn brick_buffer_size(&self) -> u64 {
+        self.brick_instances.as_ref().map_or(0, |b| b.size())
+    }
+
+    pub fn brick_count(&self) -> u32 {
+        self.brick_count
+    }
+
+    /// Upload brick overlay instances: brick coordinates and age in ticks.
+    pub fn set_bricks(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, bricks: &[((u32, u32, u32), u32)]) {
+        self.brick_count = bricks.len() as u32;
+        if bricks.is_empty() {
+            return;
+        }
+        let data: Vec<f32> = bricks.iter().flat_map(|&((x, y, z), age)| [x as f32, y as f32, z as f32, age as f32]).collect();
+        let bytes: &[u8] = bytemuck::cast_slice(&data);
+        if self.brick_buffer_size() < bytes.len() as u64 {
+            self.brick_instances = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("brick_overlay_instances"),
+                size: (bytes.len() as u64).next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if let Some(buffer) = &self.brick_instances {
+            queue.write_buffer(buffer, 0, bytes);
+        }
+    }
+
+    /// Drop the brick overlay instances and their buffer.
+    pub fn clear_bricks(&mut self) {
+        self.brick_instances = None;
+        self.brick_count = 0;
+    }
+
+    pub fn create_brick_bind_group(
+        &self,
+        device: &wgpu::Device,
+        uniform_buf: &wgpu::Buffer,
+        occupancy: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("brick_overlay_bg"),
+            layout: &self.brick_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: occupancy.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
     pub fn create_bind_group(
         &self,
         device: &wgpu::Device,
@@ -127,11 +282,14 @@ impl WireframePipeline {
         })
     }
 
+    /// The bounding box, then the brick overlay if `brick_bind_group` is
+    /// given and bricks are set.
     pub fn encode(
         &self,
         encoder: &mut wgpu::CommandEncoder,
         surface_view: &wgpu::TextureView,
         bind_group: &wgpu::BindGroup,
+        brick_bind_group: Option<&wgpu::BindGroup>,
     ) {
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("wireframe_pass"),
@@ -152,5 +310,13 @@ impl WireframePipeline {
         pass.set_bind_group(0, bind_group, &[]);
         pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         pass.draw(0..self.vertex_count, 0..1);
+        if let Some(instances) = self.brick_instances.as_ref().filter(|_| self.brick_count > 0) {
+            if let Some(brick_bind_group) = brick_bind_group {
+                pass.set_pipeline(&self.brick_pipeline);
+                pass.set_bind_group(0, brick_bind_group, &[]);
+                pass.set_vertex_buffer(1, instances.slice(..));
+                pass.draw(0..self.vertex_count, 0..self.brick_count);
+            }
+        }
     }
 }
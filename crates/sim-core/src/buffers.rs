@@ -1,29 +1,99 @@
 use wgpu;
+use wgpu::util::DeviceExt;
+use types::{Feature, FeatureSet};
 
-const VOXEL_STRIDE: usize = 8; // 8 u32 per voxel = 32 bytes
+const VOXEL_STRIDE: usize = types::layout::VOXEL_STRIDE as usize; // 8 u32 per voxel = 32 bytes
 const BRICK_VOXELS: u64 = 512; // 8³ voxels per brick
 
-// Command buffer layout: word 0 = command_count, words 1-3 = padding,
-// words 4+ = commands at 16-word stride (max 64 commands).
-// Total: (4 + 64*16) * 4 = 4112 bytes, rounded to 4128 for 16-byte alignment.
-const COMMAND_BUF_SIZE: u64 = 4128;
-const STATS_BUF_SIZE: u64 = 128; // 32 × u32 × 4 bytes
+// Command buffer layout: a 4-word header (word 0 = command_count), then
+// commands at 16-word stride (max 64 commands): 4112 bytes.
+const COMMAND_BUF_SIZE: u64 = types::command_byte_offset(types::MAX_COMMANDS_PER_TICK);
+const STATS_BUF_SIZE: u64 = crate::stats::STATS_BYTES;
+const STATS_STAGING_SIZE: u64 = crate::stats::STATS_READBACK_BYTES;
+const ENERGY_FLOW_BUF_SIZE: u64 = crate::stats::ENERGY_FLOW_BYTES;
+const STATS_PARTIALS_SIZE: u64 = crate::stats::STATS_PARTIALS_BYTES;
+/// One brick of ambient temperatures, copied over freshly allocated bricks.
+const AMBIENT_BRICK_SIZE: u64 = BRICK_VOXELS * 4;
+
+fn create_stats_partials_buffer(device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("stats_partials_buf"),
+        size: STATS_PARTIALS_SIZE,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_energy_flow_buffer(device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("energy_flow_buf"),
+        size: ENERGY_FLOW_BUF_SIZE,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Bytes per pheromone buffer: one u32 per coarse cell, same in both modes.
+/// A one-word placeholder while neither signals nor the toxin field is on.
+fn pheromone_buf_size(grid_size: u32, features: FeatureSet) -> u64 {
+    if !features.coarse_field() {
+        return 4;
+    }
+    (types::pheromone_dim(grid_size) as u64).pow(3) * 4
+}
+
+/// Temperature buffers per parity pair: without temperature one buffer
+/// held at ambient serves both.
+fn temp_buffer_count(features: FeatureSet) -> u64 {
+    if features.contains(Feature::Temperature) { 2 } else { 1 }
+}
+
+fn create_pheromone_buffer(
+    device: &wgpu::Device,
+    label: &'static str,
+    grid_size: u32,
+    features: FeatureSet,
+) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: pheromone_buf_size(grid_size, features),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
 
+#[derive(Clone)]
 pub struct VoxelBuffers {
     voxel_buf_a: wgpu::Buffer,
     voxel_buf_b: wgpu::Buffer,
     temp_buf_a: wgpu::Buffer,
     temp_buf_b: wgpu::Buffer,
+    pher_buf_a: wgpu::Buffer,
+    pher_buf_b: wgpu::Buffer,
     intent_buf: wgpu::Buffer,
     command_buf: wgpu::Buffer,
     stats_buf: wgpu::Buffer,
     stats_staging: wgpu::Buffer,
+    stats_partials: wgpu::Buffer,
+    energy_flow_buf: wgpu::Buffer,
     grid_size: u32,
+    features: FeatureSet,
     current_read_is_a: bool,
 }
 
 impl VoxelBuffers {
-    pub fn try_new(device: &wgpu::Device, grid_size: u32) -> Result<Self, String> {
+    /// Bytes `try_new` would allocate for a dense grid of this size.
+    pub fn estimated_bytes(grid_size: u32, features: FeatureSet) -> u64 {
+        let total_voxels = (grid_size as u64).pow(3);
+        // 2 voxel buffers + 1-2 temp buffers + 1 intent buffer + 2 pheromone buffers
+        total_voxels * (VOXEL_STRIDE as u64) * 4 * 2
+            + total_voxels * 4 * (temp_buffer_count(features) + 1)
+            + pheromone_buf_size(grid_size, features) * 2
+            + COMMAND_BUF_SIZE
+            + STATS_BUF_SIZE + STATS_STAGING_SIZE + STATS_PARTIALS_SIZE + ENERGY_FLOW_BUF_SIZE
+    }
+
+    pub fn try_new(device: &wgpu::Device, grid_size: u32, features: FeatureSet) -> Result<Self, String> {
         let total_voxels = (grid_size as u64).pow(3);
         let buf_size = total_voxels * (VOXEL_STRIDE as u64) * 4;
 
@@ -59,18 +129,27 @@ impl VoxelBuffers {
 
         // 1 f32 per voxel for temperature field
         let temp_size = total_voxels * 4;
+        // COPY_SRC: diffusion sub-steps copy the last result back
+        let temp_usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
         let temp_buf_a = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("temp_buf_a"),
             size: temp_size,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let temp_buf_b = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("temp_buf_b"),
-            size: temp_size,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            usage: temp_usage,
             mapped_at_creation: false,
         });
+        let temp_buf_b = if features.contains(Feature::Temperature) {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("temp_buf_b"),
+                size: temp_size,
+                usage: temp_usage,
+                mapped_at_creation: false,
+            })
+        } else {
+            temp_buf_a.clone()
+        };
+
+        let pher_buf_a = create_pheromone_buffer(device, "pher_buf_a", grid_size, features);
+        let pher_buf_b = create_pheromone_buffer(device, "pher_buf_b", grid_size, features);
 
         // 1 u32 per voxel for intent encoding
         let intent_size = total_voxels * 4;
@@ -97,7 +176,7 @@ impl VoxelBuffers {
 
         let stats_staging = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("stats_staging"),
-            size: STATS_BUF_SIZE,
+            size: STATS_STAGING_SIZE,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
@@ -107,17 +186,22 @@ impl VoxelBuffers {
             voxel_buf_b,
             temp_buf_a,
             temp_buf_b,
+            pher_buf_a,
+            pher_buf_b,
             intent_buf,
             command_buf,
             stats_buf,
             stats_staging,
+            stats_partials: create_stats_partials_buffer(device),
+            energy_flow_buf: create_energy_flow_buffer(device),
             grid_size,
+            features,
             current_read_is_a: true,
         })
     }
 
-    pub fn new(device: &wgpu::Device, grid_size: u32) -> Self {
-        Self::try_new(device, grid_size).expect("Failed to allocate voxel buffers")
+    pub fn new(device: &wgpu::Device, grid_size: u32, features: FeatureSet) -> Self {
+        Self::try_new(device, grid_size, features).expect("Failed to allocate voxel buffers")
     }
 
     pub fn buffer_a(&self) -> &wgpu::Buffer {
@@ -184,6 +268,36 @@ impl VoxelBuffers {
         &self.stats_staging
     }
 
+    pub fn stats_partials_buffer(&self) -> &wgpu::Buffer {
+        &self.stats_partials
+    }
+
+    pub fn energy_flow_buffer(&self) -> &wgpu::Buffer {
+        &self.energy_flow_buf
+    }
+
+    /// (label, bytes) for every buffer owned by this set.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("voxel_buf_a", self.voxel_buf_a.size()),
+            ("voxel_buf_b", self.voxel_buf_b.size()),
+            ("temp_buf_a", self.temp_buf_a.size()),
+            ("temp_buf_b", self.temp_buf_b.size()),
+            ("pher_buf_a", self.pher_buf_a.size()),
+            ("pher_buf_b", self.pher_buf_b.size()),
+            ("intent_buf", self.intent_buf.size()),
+            ("command_buf", self.command_buf.size()),
+            ("stats_buf", self.stats_buf.size()),
+            ("stats_staging", self.stats_staging.size()),
+            ("stats_partials", self.stats_partials.size()),
+            ("energy_flow_buf", self.energy_flow_buf.size()),
+        ]
+        .into_iter()
+        // temp_buf_b is temp_buf_a without temperature
+        .filter(|&(label, _)| label != "temp_buf_b" || self.features.contains(Feature::Temperature))
+        .collect()
+    }
+
     pub fn current_temp_read(&self) -> &wgpu::Buffer {
         if self.current_read_is_a {
             &self.temp_buf_a
@@ -199,26 +313,68 @@ impl VoxelBuffers {
             &self.temp_buf_a
         }
     }
+
+    /// Pheromone field written by the most recent tick.
+    pub fn current_pheromone_read(&self) -> &wgpu::Buffer {
+        if self.current_read_is_a {
+            &self.pher_buf_a
+        } else {
+            &self.pher_buf_b
+        }
+    }
+
+    pub fn pheromone_buffer_a(&self) -> &wgpu::Buffer {
+        &self.pher_buf_a
+    }
+
+    pub fn pheromone_buffer_b(&self) -> &wgpu::Buffer {
+        &self.pher_buf_b
+    }
 }
 
 /// Pool-based buffers for sparse 256³ mode.
 /// Instead of dense grid_size³ buffers, uses max_bricks * 512 element pools.
+#[derive(Clone)]
 pub struct SparseVoxelBuffers {
     voxel_pool_a: wgpu::Buffer,
     voxel_pool_b: wgpu::Buffer,
     temp_pool_a: wgpu::Buffer,
     temp_pool_b: wgpu::Buffer,
+    /// Pheromone field is coarse and dense even in sparse mode.
+    pher_buf_a: wgpu::Buffer,
+    pher_buf_b: wgpu::Buffer,
     intent_pool: wgpu::Buffer,
     command_buf: wgpu::Buffer,
     stats_buf: wgpu::Buffer,
     stats_staging: wgpu::Buffer,
+    stats_partials: wgpu::Buffer,
+    energy_flow_buf: wgpu::Buffer,
+    ambient_brick: wgpu::Buffer,
     grid_size: u32,      // logical grid size (256)
     max_bricks: u32,
+    features: FeatureSet,
     current_read_is_a: bool,
 }
 
 impl SparseVoxelBuffers {
-    pub fn try_new(device: &wgpu::Device, grid_size: u32, max_bricks: u32) -> Result<Self, String> {
+    /// Bytes `try_new` would allocate for a pool of `max_bricks` bricks.
+    pub fn estimated_bytes(grid_size: u32, max_bricks: u32, features: FeatureSet) -> u64 {
+        let pool_voxels = max_bricks as u64 * BRICK_VOXELS;
+        // 2 voxel pools + 1-2 temp pools + 1 intent pool + 2 pheromone buffers
+        pool_voxels * (VOXEL_STRIDE as u64) * 4 * 2
+            + pool_voxels * 4 * (temp_buffer_count(features) + 1)
+            + pheromone_buf_size(grid_size, features) * 2
+            + COMMAND_BUF_SIZE
+            + STATS_BUF_SIZE + STATS_STAGING_SIZE + STATS_PARTIALS_SIZE + ENERGY_FLOW_BUF_SIZE
+            + AMBIENT_BRICK_SIZE
+    }
+
+    pub fn try_new(
+        device: &wgpu::Device,
+        grid_size: u32,
+        max_bricks: u32,
+        features: FeatureSet,
+    ) -> Result<Self, String> {
         let pool_voxels = max_bricks as u64 * BRICK_VOXELS;
         let voxel_pool_size = pool_voxels * (VOXEL_STRIDE as u64) * 4;
         let temp_pool_size = pool_voxels * 4;
@@ -256,15 +412,22 @@ impl SparseVoxelBuffers {
         let temp_pool_a = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("temp_pool_a"),
             size: temp_pool_size,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let temp_pool_b = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("temp_pool_b"),
-            size: temp_pool_size,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            usage: usage_rw,
             mapped_at_creation: false,
         });
+        let temp_pool_b = if features.contains(Feature::Temperature) {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("temp_pool_b"),
+                size: temp_pool_size,
+                usage: usage_rw,
+                mapped_at_creation: false,
+            })
+        } else {
+            temp_pool_a.clone()
+        };
+
+        let pher_buf_a = create_pheromone_buffer(device, "pher_buf_a", grid_size, features);
+        let pher_buf_b = create_pheromone_buffer(device, "pher_buf_b", grid_size, features);
 
         let intent_pool = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("intent_pool"),
@@ -289,22 +452,34 @@ impl SparseVoxelBuffers {
 
         let stats_staging = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("stats_staging"),
-            size: STATS_BUF_SIZE,
+            size: STATS_STAGING_SIZE,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
 
+        let ambient_brick = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("ambient_brick"),
+            contents: &crate::AMBIENT_TEMPERATURE.to_le_bytes().repeat(BRICK_VOXELS as usize),
+            usage: wgpu::BufferUsages::COPY_SRC,
+        });
+
         Ok(Self {
             voxel_pool_a,
             voxel_pool_b,
             temp_pool_a,
             temp_pool_b,
+            pher_buf_a,
+            pher_buf_b,
             intent_pool,
             command_buf,
             stats_buf,
             stats_staging,
+            stats_partials: create_stats_partials_buffer(device),
+            energy_flow_buf: create_energy_flow_buffer(device),
+            ambient_brick,
             grid_size,
             max_bricks,
+            features,
             current_read_is_a: true,
         })
     }
@@ -332,6 +507,31 @@ impl SparseVoxelBuffers {
     pub fn command_buffer(&self) -> &wgpu::Buffer { &self.command_buf }
     pub fn stats_buffer(&self) -> &wgpu::Buffer { &self.stats_buf }
     pub fn stats_staging_buffer(&self) -> &wgpu::Buffer { &self.stats_staging }
+    pub fn stats_partials_buffer(&self) -> &wgpu::Buffer { &self.stats_partials }
+    pub fn energy_flow_buffer(&self) -> &wgpu::Buffer { &self.energy_flow_buf }
+    pub fn ambient_brick(&self) -> &wgpu::Buffer { &self.ambient_brick }
+
+    /// (label, bytes) for every buffer owned by this set.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("voxel_pool_a", self.voxel_pool_a.size()),
+            ("voxel_pool_b", self.voxel_pool_b.size()),
+            ("temp_pool_a", self.temp_pool_a.size()),
+            ("temp_pool_b", self.temp_pool_b.size()),
+            ("pher_buf_a", self.pher_buf_a.size()),
+            ("pher_buf_b", self.pher_buf_b.size()),
+            ("intent_pool", self.intent_pool.size()),
+            ("command_buf", self.command_buf.size()),
+            ("stats_buf", self.stats_buf.size()),
+            ("stats_staging", self.stats_staging.size()),
+            ("stats_partials", self.stats_partials.size()),
+            ("energy_flow_buf", self.energy_flow_buf.size()),
+            ("ambient_brick", self.ambient_brick.size()),
+        ]
+        .into_iter()
+        .filter(|&(label, _)| label != "temp_pool_b" || self.features.contains(Feature::Temperature))
+        .collect()
+    }
 
     pub fn temp_pool_a(&self) -> &wgpu::Buffer { &self.temp_pool_a }
     pub fn temp_pool_b(&self) -> &wgpu::Buffer { &self.temp_pool_b }
@@ -343,4 +543,10 @@ impl SparseVoxelBuffers {
     pub fn current_temp_write(&self) -> &wgpu::Buffer {
         if self.current_read_is_a { &self.temp_pool_b } else { &self.temp_pool_a }
     }
+
+    pub fn current_pheromone_read(&self) -> &wgpu::Buffer {
+        if self.current_read_is_a { &self.pher_buf_a } else { &self.pher_buf_b }
+    }
+    pub fn pheromone_buffer_a(&self) -> &wgpu::Buffer { &self.pher_buf_a }
+    pub fn pheromone_buffer_b(&self) -> &wgpu::Buffer { &self.pher_buf_b }
 }
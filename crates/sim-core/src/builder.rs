@@ -0,0 +1,226 @@
+//! `SimEngineBuilder`: the one way to create a `SimEngine`. Collects the
+//! storage layout, initial params and rules, the world to seed and the
+//! optional subsystems, then allocates and seeds the engine in `build`.
+
+use crate::buffers::{SparseVoxelBuffers, VoxelBuffers};
+use crate::pipelines::{SimPipelines, SparsePipelines};
+use crate::region::ActiveRegion;
+use crate::seed::SeedVoxel;
+use crate::sparse::SparseGrid;
+use crate::uniform::ParamsUniform;
+use crate::{DenseMode, SimEngine, SimMode, SparseMode};
+use types::{FeatureSet, RuleParams, SimParams};
+
+/// Storage layout of a new engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridConfig {
+    /// Every cell of a `grid_size`³ grid allocated.
+    Dense { grid_size: u32 },
+    /// 8³ bricks of a `grid_size`³ grid allocated on demand from a pool of
+    /// `max_bricks`.
+    Sparse { grid_size: u32, max_bricks: u32 },
+}
+
+impl GridConfig {
+    pub fn grid_size(self) -> u32 {
+        match self {
+            GridConfig::Dense { grid_size } | GridConfig::Sparse { grid_size, .. } => grid_size,
+        }
+    }
+
+    /// GPU bytes the engine's buffers would take with `features`.
+    pub fn estimated_bytes(self, features: FeatureSet) -> u64 {
+        match self {
+            GridConfig::Dense { grid_size } => SimEngine::estimate_dense_bytes(grid_size, features),
+            GridConfig::Sparse { grid_size, max_bricks } => {
+                SimEngine::estimate_sparse_bytes(grid_size, max_bricks, features)
+            }
+        }
+    }
+
+    fn validate(self) -> Result<(), EngineError> {
+        match self {
+            GridConfig::Dense { grid_size } if grid_size == 0 || grid_size % 4 != 0 => {
+                Err(EngineError::InvalidGridSize { grid_size, multiple_of: 4 })
+            }
+            GridConfig::Sparse { grid_size, .. } if grid_size == 0 || grid_size % 8 != 0 => {
+                Err(EngineError::InvalidGridSize { grid_size, multiple_of: 8 })
+            }
+            GridConfig::Sparse { max_bricks: 0, .. } => Err(EngineError::EmptyBrickPool),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// World written into the engine once it is allocated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldSeed {
+    /// All cells empty at ambient temperature.
+    Empty,
+    /// A `seed::preset` id.
+    Preset(u32),
+    /// Explicit voxels, as for `SimEngine::initialize_grid_with_voxels`.
+    Voxels(Vec<SeedVoxel>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    /// Dense grids need a multiple of 4 (the workgroup size), sparse grids
+    /// a multiple of 8 (the brick size).
+    InvalidGridSize { grid_size: u32, multiple_of: u32 },
+    EmptyBrickPool,
+    /// A buffer exceeded the device limits.
+    Allocation(String),
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::InvalidGridSize { grid_size, multiple_of } => {
+                write!(f, "grid size {grid_size} is not a positive multiple of {multiple_of}")
+            }
+            EngineError::EmptyBrickPool => write!(f, "sparse brick pool has no bricks"),
+            EngineError::Allocation(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+pub struct SimEngineBuilder<'a> {
+    grid: GridConfig,
+    params: SimParams,
+    rules: RuleParams,
+    seed: WorldSeed,
+    features: FeatureSet,
+    stats_enabled: bool,
+    cache: Option<&'a wgpu::PipelineCache>,
+}
+
+impl Default for SimEngineBuilder<'_> {
+    fn default() -> Self {
+        Self {
+            grid: GridConfig::Dense { grid_size: 128 },
+            params: SimParams::default(),
+            rules: RuleParams::default(),
+            seed: WorldSeed::Preset(0),
+            features: FeatureSet::ALL,
+            stats_enabled: true,
+            cache: None,
+        }
+    }
+}
+
+impl<'a> SimEngineBuilder<'a> {
+    pub fn grid(mut self, grid: GridConfig) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn dense(self, grid_size: u32) -> Self {
+        self.grid(GridConfig::Dense { grid_size })
+    }
+
+    pub fn sparse(self, grid_size: u32, max_bricks: u32) -> Self {
+        self.grid(GridConfig::Sparse { grid_size, max_bricks })
+    }
+
+    /// Initial params. The grid fields (grid_size, sparse_mode,
+    /// brick_grid_dim, max_bricks) are taken from the grid config.
+    pub fn params(mut self, params: SimParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn rules(mut self, rules: RuleParams) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// World to seed; preset 0 by default.
+    pub fn seed(mut self, seed: WorldSeed) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Optional subsystems to allocate and run; all by default.
+    pub fn features(mut self, features: FeatureSet) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Initial `SimEngine::set_stats_enabled`; on by default.
+    pub fn stats_enabled(mut self, enabled: bool) -> Self {
+        self.stats_enabled = enabled;
+        self
+    }
+
+    /// Compile pipelines through a driver pipeline cache (see `pipeline_cache`).
+    pub fn pipeline_cache(mut self, cache: Option<&'a wgpu::PipelineCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Allocate, upload and seed the engine.
+    pub fn build(self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<SimEngine, EngineError> {
+        self.grid.validate()?;
+        let grid_size = self.grid.grid_size();
+        let mut params = SimParams { grid_size: grid_size as f32, ..self.params };
+        let region = ActiveRegion::new(device, queue, grid_size);
+
+        let (mode, params_uniform) = match self.grid {
+            GridConfig::Dense { .. } => {
+                params.sparse_mode = 0.0;
+                params.brick_grid_dim = 0.0;
+                params.max_bricks = 0.0;
+                let buffers = VoxelBuffers::try_new(device, grid_size, self.features).map_err(EngineError::Allocation)?;
+                let params_uniform = ParamsUniform::new(device, &params);
+                let pipelines = SimPipelines::new(device, self.cache, self.features);
+                let dense = DenseMode::new(device, &params_uniform, &region, buffers, pipelines);
+                (SimMode::Dense(dense), params_uniform)
+            }
+            GridConfig::Sparse { max_bricks, .. } => {
+                let brick_grid_dim = grid_size / 8;
+                params.sparse_mode = 1.0;
+                params.brick_grid_dim = brick_grid_dim as f32;
+                params.max_bricks = max_bricks as f32;
+                let buffers = SparseVoxelBuffers::try_new(device, grid_size, max_bricks, self.features)
+                    .map_err(EngineError::Allocation)?;
+                let grid = SparseGrid::new(device, brick_grid_dim, max_bricks);
+                let params_uniform = ParamsUniform::new(device, &params);
+                let pipelines = SparsePipelines::new(device, self.cache, self.features);
+                let sparse = SparseMode::new(device, &params_uniform, &region, buffers, grid, pipelines);
+                (SimMode::Sparse(sparse), params_uniform)
+            }
+        };
+
+        let mut engine = SimEngine {
+            mode,
+            params_uniform,
+            params,
+            rules: self.rules,
+            tick_count: 0,
+            sim_time: 0.0,
+            stats_enabled: self.stats_enabled,
+            region,
+            blooms: Vec::new(),
+            log: types::EngineLog::new(),
+            pool_exhausted: false,
+            lod: None,
+            lod_focus: [grid_size as f32 * 0.5; 3],
+            profiler: None,
+            keyframes: None,
+        };
+        engine.upload_params(queue);
+        match &self.seed {
+            WorldSeed::Empty => {
+                engine.initialize_grid_with_voxels(queue, &[]);
+            }
+            WorldSeed::Preset(id) => engine.initialize_grid_with_preset(queue, *id),
+            WorldSeed::Voxels(voxels) => {
+                engine.initialize_grid_with_voxels(queue, voxels);
+            }
+        }
+        Ok(engine)
+    }
+}
@@ -0,0 +1,1409 @@
+//! CPU tick for the degraded path on browsers without WebGPU.
+//!
+//! A dense, single-threaded port of the five compute passes
+//! (apply_commands → temperature → intent → resolve → stats). PRNG seeds,
+//! salts and advance counts match the WGSL so rules and determinism carry
+//! over; only practical at small grids (see `CPU_GRID_SIZE`).
+
+use std::collections::HashMap;
+
+use types::{
+    intent_decode, intent_encode, pheromone_bucket, pheromone_dim, pheromone_index, pheromone_pack, pheromone_unpack,
+    toxin_level, unpack_thermal_source, with_toxin, ActionType, CommandType, Direction, ConflictCounts, Contender, EnergyFlow, EnergyPath, Genome, NutrientBloom, RuleParams, SimParams, Voxel, VoxelType, ADHESION_LINK_MIN,
+    FILL_OVERWRITE, FLAG_DORMANT, FLAG_FROZEN, FLAG_IN_GOAL, FLAG_LINKED, MAX_COMMANDS_PER_TICK, PHEROMONE_CELL, PHEROMONE_MAX, WASTE_FLAG_TOXIN,
+};
+
+use crate::seed;
+use crate::stats::CLUSTER_MIN_NEIGHBORS;
+use crate::SimStats;
+
+/// Grid size used by the CPU fallback.
+pub const CPU_GRID_SIZE: u32 = 32;
+
+/// Face-adjacent offsets in `Direction` order (+x, -x, +y, -y, +z, -z).
+const NEIGHBORS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+const NO_VOXEL: u32 = u32::MAX;
+/// `SimParams::thermal_boundary` modes, as in temperature_diffusion.wgsl.
+const THERMAL_BOUNDARY_AMBIENT: u32 = 1;
+const THERMAL_BOUNDARY_PERIODIC: u32 = 2;
+
+const PLACE_VOXEL: u32 = CommandType::PlaceVoxel as u32;
+const REMOVE_VOXEL: u32 = CommandType::RemoveVoxel as u32;
+const SEED_PROTOCELLS: u32 = CommandType::SeedProtocells as u32;
+const APPLY_TOXIN: u32 = CommandType::ApplyToxin as u32;
+const FILL_REGION: u32 = CommandType::FillRegion as u32;
+const FREEZE_REGION: u32 = CommandType::FreezeRegion as u32;
+
+fn pcg_hash(input: u32) -> u32 {
+    let state = input.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+/// Whether a FillRegion of `density` fills a cell with hash `roll`.
+fn fill_roll(roll: u32, density: u32) -> bool {
+    roll & 0xFF < density || density == 255
+}
+
+fn pcg_next(state: &mut u32) -> u32 {
+    let old = *state;
+    *state = old.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((old >> ((old >> 28) + 4)) ^ old).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn prng_seed(voxel_index: u32, tick_count: u32, grid_size: u32, dispatch_salt: u32) -> u32 {
+    pcg_hash(voxel_index ^ tick_count.wrapping_mul(0x9E37_79B9) ^ grid_size.wrapping_mul(0x85EB_CA6B) ^ dispatch_salt)
+}
+
+/// 0..1 factor as 16-bit fixed point, as `pheromone_q16` in common.wgsl.
+fn pheromone_q16(f: f32) -> u32 {
+    (f.clamp(0.0, 1.0) * 65536.0) as u32
+}
+
+/// Mirrors `is_cold_dormant` in common.wgsl.
+fn is_cold_dormant(local_temp: f32, cold_tolerance: u8, dormancy_temp: f32) -> bool {
+    local_temp < dormancy_temp * (1.0 - cold_tolerance as f32 / 255.0)
+}
+
+fn temp_modifier(local_temp: f32, sensitivity: f32) -> f32 {
+    (1.0 + sensitivity * (local_temp - 0.5)).max(0.1)
+}
+
+const NUTRIENT_PATCH_CELL: f32 = 16.0;
+
+fn lattice_value(x: u32, y: u32, z: u32) -> f32 {
+    let h = pcg_hash(x ^ y.wrapping_mul(0x9E37_79B9) ^ z.wrapping_mul(0x85EB_CA6B) ^ 0x4E55_5452);
+    h as f32 / 4_294_967_295.0
+}
+
+fn patch_noise(pos: (u32, u32, u32)) -> f32 {
+    let p = [pos.0, pos.1, pos.2].map(|c| c as f32 / NUTRIENT_PATCH_CELL);
+    let c = p.map(|v| v.floor() as u32);
+    let t = p.map(|v| {
+        let f = v.fract();
+        f * f * (3.0 - 2.0 * f)
+    });
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let corner = |dx: u32, dy: u32, dz: u32| lattice_value(c[0] + dx, c[1] + dy, c[2] + dz);
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), t[0]);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), t[0]);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), t[0]);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), t[0]);
+    lerp(lerp(x00, x10, t[1]), lerp(x01, x11, t[1]), t[2])
+}
+
+/// Mirrors `nutrient_pattern_weight` in common.wgsl.
+fn nutrient_pattern_weight(pos: (u32, u32, u32), gs: u32, tick: u32, pattern: u32, front_speed: f32) -> f32 {
+    match pattern {
+        1 => ((patch_noise(pos) - 0.5) * 4.0).clamp(0.0, 2.0),
+        2 => {
+            let g = gs as f32;
+            let front = (tick as f32 * front_speed / g).fract() * g;
+            let d = (pos.0 as f32 - front).abs();
+            let dist = d.min(g - d);
+            let width = (g / 16.0).max(1.0);
+            (1.0 - dist / width).max(0.0) * 4.0
+        }
+        _ => 1.0,
+    }
+}
+
+/// Dense CPU simulation with the same double-buffered layout as the GPU engine.
+pub struct CpuSim {
+    grid_size: u32,
+    voxels: [Vec<Voxel>; 2],
+    temps: [Vec<f32>; 2],
+    /// Coarse pheromone field, indexed by `types::pheromone_index`.
+    pheromones: [Vec<u32>; 2],
+    intents: Vec<u32>,
+    /// Index of the read buffer in `voxels` / `temps` / `pheromones`.
+    read: usize,
+    pub params: SimParams,
+    /// Bounds of the mutation-rate gene (byte 3) and conflict arbitration.
+    pub rules: RuleParams,
+    /// Scheduled nutrient pulses; see `scheduled_commands`.
+    pub blooms: Vec<NutrientBloom>,
+    tick_count: u32,
+    sim_time: f64,
+    /// Energy moved per pathway since `end_flow_window`.
+    energy_flow: EnergyFlow,
+    /// Contested cells and rejected intents since `end_flow_window`.
+    conflicts: ConflictCounts,
+    /// Births and deaths since `end_flow_window`.
+    turnover: types::Turnover,
+    /// Speciations since `end_flow_window`, as the GPU speciation log
+    /// holds them: at most `LINEAGE_LOG_CAPACITY`, the rest only counted.
+    speciations: Vec<types::LineageEvent>,
+    speciations_unlogged: u32,
+    /// Notable events (dropped commands); the host adds parameter clamps.
+    pub log: types::EngineLog,
+}
+
+impl CpuSim {
+    pub fn new(grid_size: u32) -> Self {
+        let total = (grid_size as usize).pow(3);
+        let cells = (pheromone_dim(grid_size) as usize).pow(3);
+        let params = SimParams {
+            grid_size: grid_size as f32,
+            ..Default::default()
+        };
+        Self {
+            grid_size,
+            voxels: [vec![Voxel::default(); total], vec![Voxel::default(); total]],
+            temps: [vec![0.5; total], vec![0.5; total]],
+            pheromones: [vec![0; cells], vec![0; cells]],
+            intents: vec![0; total],
+            read: 0,
+            params,
+            rules: RuleParams::default(),
+            blooms: Vec::new(),
+            tick_count: 0,
+            sim_time: 0.0,
+            energy_flow: EnergyFlow::default(),
+            conflicts: ConflictCounts::default(),
+            turnover: types::Turnover::default(),
+            speciations: Vec::new(),
+            speciations_unlogged: 0,
+            log: types::EngineLog::new(),
+        }
+    }
+
+    pub fn grid_size(&self) -> u32 {
+        self.grid_size
+    }
+
+    pub fn tick_count(&self) -> u32 {
+        self.tick_count
+    }
+
+    pub fn sim_time_seconds(&self) -> f64 {
+        self.sim_time
+    }
+
+    pub fn reset_tick_count(&mut self) {
+        self.tick_count = 0;
+        self.sim_time = 0.0;
+    }
+
+    /// Current voxel state, indexed by `types::grid_index`.
+    pub fn voxels(&self) -> &[Voxel] {
+        &self.voxels[self.read]
+    }
+
+    /// Current temperature field, indexed like `voxels`.
+    pub fn temperatures(&self) -> &[f32] {
+        &self.temps[self.read]
+    }
+
+    /// Clear the grid and seed preset `id` (same scenes as the GPU engine).
+    pub fn initialize_grid_with_preset(&mut self, preset: u32) {
+        self.initialize_grid_with_voxels(&seed::preset(self.grid_size, preset));
+    }
+
+    /// Replace the world with `voxels`, skipping cells past the grid.
+    /// Returns the cells written.
+    pub fn initialize_grid_with_voxels(&mut self, voxels: &[seed::SeedVoxel]) -> u32 {
+        let gs = self.grid_size;
+        let read = &mut self.voxels[self.read];
+        read.fill(Voxel::default());
+        let mut written = vec![false; read.len()];
+        for &(x, y, z, words) in voxels {
+            if x < gs && y < gs && z < gs {
+                let idx = types::grid_index(x, y, z, gs);
+                read[idx] = Voxel::unpack(words);
+                written[idx] = true;
+            }
+        }
+        self.temps[self.read].fill(0.5);
+        self.pheromones[self.read].fill(0);
+        self.energy_flow = EnergyFlow::default();
+        self.conflicts = ConflictCounts::default();
+        self.turnover = types::Turnover::default();
+        self.speciations.clear();
+        self.speciations_unlogged = 0;
+        written.iter().filter(|&&w| w).count() as u32
+    }
+
+    /// The current state as an encoded `types::WorldSnapshot`.
+    pub fn export_snapshot(&self) -> Vec<u8> {
+        types::WorldSnapshot {
+            grid_size: self.grid_size,
+            tick_count: self.tick_count,
+            sim_time: self.sim_time,
+            params: self.params.clone(),
+            brick_table: None,
+            voxels: self.voxels().iter().flat_map(|v| v.pack()).flat_map(u32::to_le_bytes).collect(),
+            temperatures: self.temperatures().to_vec(),
+        }
+        .encode()
+    }
+
+    /// Replace the run with an encoded snapshot of a world the same size,
+    /// as `SimEngine::import_snapshot`. Returns the voxels placed.
+    pub fn import_snapshot(&mut self, bytes: &[u8]) -> Result<u32, String> {
+        let snapshot = types::WorldSnapshot::decode(bytes)?;
+        if snapshot.grid_size != self.grid_size {
+            return Err(format!("snapshot is of a {}³ world, this one is {}³", snapshot.grid_size, self.grid_size));
+        }
+        snapshot.apply_params(&mut self.params);
+        let placed = self.initialize_grid_with_voxels(&snapshot.seed_voxels());
+        self.temps[self.read] = snapshot.dense_temperatures();
+        self.tick_count = snapshot.tick_count;
+        self.sim_time = snapshot.sim_time;
+        Ok(placed)
+    }
+
+    /// Start a new energy-flow window, as the GPU stats pass does.
+    pub fn end_flow_window(&mut self) {
+        self.energy_flow = EnergyFlow::default();
+        self.conflicts = ConflictCounts::default();
+        self.turnover = types::Turnover::default();
+        self.speciations.clear();
+        self.speciations_unlogged = 0;
+    }
+
+    /// FillRegion commands for blooms due in the next `ticks` ticks, to be
+    /// applied with the first of them.
+    pub fn scheduled_commands(&self, ticks: u32) -> Vec<types::Command> {
+        types::bloom_commands(&self.blooms, self.tick_count, ticks)
+    }
+
+    pub fn tick(&mut self, commands: &[types::Command]) {
+        if commands.len() > MAX_COMMANDS_PER_TICK {
+            let dropped = commands.len() - MAX_COMMANDS_PER_TICK;
+            self.log.warn(self.tick_count, types::Subsystem::Commands, format!("{dropped} commands over the per-tick limit of {MAX_COMMANDS_PER_TICK} dropped"));
+        }
+        self.apply_commands(commands);
+        self.diffuse_temperature();
+        self.update_pheromones();
+        self.declare_intents();
+        self.resolve_execute();
+        self.read ^= 1;
+        self.tick_count += 1;
+        self.sim_time += self.params.dt as f64;
+    }
+
+    /// Population, energy and the 12 most common species of the current state.
+    pub fn stats(&self) -> SimStats {
+        let mut stats = SimStats {
+            energy_flow: self.energy_flow,
+            conflicts: self.conflicts,
+            turnover: self.turnover,
+            speciations: self.speciations.clone(),
+            speciations_unlogged: self.speciations_unlogged,
+            ..Default::default()
+        };
+        let mut species: HashMap<u16, u32> = HashMap::new();
+        let mut in_goal: HashMap<u16, u32> = HashMap::new();
+        let mut clustered = 0;
+        for (idx, v) in self.voxels().iter().enumerate() {
+            if v.voxel_type == VoxelType::Waste && v.age == 0 && v.flags & WASTE_FLAG_TOXIN != 0 {
+                stats.toxin_deaths += 1;
+            }
+            match v.voxel_type {
+                VoxelType::Nutrient => stats.nutrients += 1,
+                VoxelType::Waste => stats.waste += 1,
+                VoxelType::Wall => stats.walls += 1,
+                _ => {}
+            }
+            if v.voxel_type != VoxelType::Protocell {
+                continue;
+            }
+            stats.population += 1;
+            stats.age_sum += v.age as u32;
+            stats.total_energy += v.energy as u32;
+            stats.max_energy = stats.max_energy.max(v.energy as u32);
+            stats.mutation_rate_sum += v.genome.mutation_rate() as u32;
+            if v.species_id != 0 {
+                let pos = self.coords(idx as u32);
+                let same = (0..6)
+                    .map(|d| self.neighbor(pos, d))
+                    .filter(|&ni| {
+                        ni != NO_VOXEL && {
+                            let n = &self.voxels()[ni as usize];
+                            n.voxel_type == VoxelType::Protocell && n.species_id == v.species_id
+                        }
+                    })
+                    .count() as u32;
+                if same >= CLUSTER_MIN_NEIGHBORS {
+                    clustered += 1;
+                }
+                *species.entry(v.species_id).or_insert(0) += 1;
+                if v.in_goal_zone() {
+                    *in_goal.entry(v.species_id).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut histogram: Vec<(u16, u32)> = species.into_iter().collect();
+        histogram.sort_by_key(|&(sid, count)| (std::cmp::Reverse(count), sid));
+        // Species past the histogram's entries go to the "other" bucket
+        let slots = types::layout::STATS_SPECIES_SLOTS as usize;
+        let overflow = histogram.split_off(slots.min(histogram.len()));
+        stats.other_species = overflow.iter().map(|&(_, count)| count).sum();
+        stats.goal_other = overflow.iter().filter_map(|(sid, _)| in_goal.get(sid)).sum();
+        // Goal counts only for species in the histogram, as on the GPU
+        let mut goal: Vec<(u16, u32)> =
+            histogram.iter().filter_map(|&(sid, _)| in_goal.get(&sid).map(|&n| (sid, n))).collect();
+        goal.sort_by_key(|&(sid, count)| (std::cmp::Reverse(count), sid));
+        stats.goal_histogram = goal;
+        stats.clustered = Some(clustered);
+        stats.species_count = histogram.len() as u32;
+        stats.species_histogram = histogram;
+        // Union of this state and the previous one, as the GPU reduction
+        let [a, b] = &self.voxels;
+        let occupied: Vec<(u32, u32, u32)> = (0..a.len())
+            .filter(|&i| a[i].voxel_type != VoxelType::Empty || b[i].voxel_type != VoxelType::Empty)
+            .map(|i| self.coords(i as u32))
+            .collect();
+        stats.active_bounds = types::bounding_box(&occupied);
+        stats.temperature = types::FieldSummary::of(self.temperatures().iter().copied());
+        stats.pheromone = types::FieldSummary::of(
+            self.pheromones[self.read].iter().map(|&w| pheromone_unpack(w).1 as f32 / PHEROMONE_MAX as f32),
+        );
+        stats.energy = types::FieldSummary::of(
+            self.voxels().iter().filter(|v| v.voxel_type == VoxelType::Protocell).map(|v| v.energy as f32),
+        );
+        stats
+    }
+
+    fn coords(&self, idx: u32) -> (u32, u32, u32) {
+        let gs = self.grid_size;
+        (idx % gs, (idx / gs) % gs, idx / (gs * gs))
+    }
+
+    fn neighbor(&self, pos: (u32, u32, u32), dir: usize) -> u32 {
+        let gs = self.grid_size as i32;
+        let (dx, dy, dz) = NEIGHBORS[dir];
+        let (x, y, z) = (pos.0 as i32 + dx, pos.1 as i32 + dy, pos.2 as i32 + dz);
+        if x < 0 || y < 0 || z < 0 || x >= gs || y >= gs || z >= gs {
+            return NO_VOXEL;
+        }
+        types::grid_index(x as u32, y as u32, z as u32, self.grid_size) as u32
+    }
+
+    /// Frozen cells, and cells of the dilation zone between its ticks,
+    /// keep their state (`cell_held` in the intent and resolve passes).
+    fn held(&self, idx: u32) -> bool {
+        self.voxels[self.read][idx as usize].is_frozen() || self.params.dilation_skips_tick(self.coords(idx), self.tick_count)
+    }
+
+    /// `neighbor`, with held cells absent as in the intent and resolve
+    /// passes.
+    fn live_neighbor(&self, pos: (u32, u32, u32), dir: usize) -> u32 {
+        match self.neighbor(pos, dir) {
+            ni if ni != NO_VOXEL && self.held(ni) => NO_VOXEL,
+            ni => ni,
+        }
+    }
+
+    /// Neighbor in direction `dir`, wrapping around the grid faces.
+    fn wrapped_neighbor(&self, pos: (u32, u32, u32), dir: usize) -> u32 {
+        let gs = self.grid_size as i32;
+        let (dx, dy, dz) = NEIGHBORS[dir];
+        let wrap = |c: u32, d: i32| (c as i32 + d).rem_euclid(gs) as u32;
+        types::grid_index(wrap(pos.0, dx), wrap(pos.1, dy), wrap(pos.2, dz), self.grid_size) as u32
+    }
+
+    /// Pass 1: player commands, applied in place to the read buffer.
+    fn apply_commands(&mut self, commands: &[types::Command]) {
+        if commands.is_empty() {
+            return;
+        }
+        let gs = self.grid_size;
+        let tick = self.tick_count;
+        let nutrient_energy = self.params.energy_from_nutrient as u32;
+        for idx in 0..gs * gs * gs {
+            let (x, y, z) = self.coords(idx);
+            let voxel = &mut self.voxels[self.read][idx as usize];
+            for cmd in commands.iter().take(MAX_COMMANDS_PER_TICK) {
+                if cmd.is_region() {
+                    let (hx, hy, hz) = types::unpack_region_max(cmd.param_1);
+                    if x < cmd.x || y < cmd.y || z < cmd.z || x > hx || y > hy || z > hz {
+                        continue;
+                    }
+                } else {
+                    let dist = x.abs_diff(cmd.x).max(y.abs_diff(cmd.y)).max(z.abs_diff(cmd.z));
+                    if dist > cmd.radius {
+                        continue;
+                    }
+                }
+                if cmd.command_type == FREEZE_REGION {
+                    voxel.flags = if cmd.param_0 != 0 { voxel.flags | FLAG_FROZEN } else { voxel.flags & !FLAG_FROZEN };
+                    continue;
+                }
+                if voxel.is_frozen() {
+                    continue;
+                }
+                match cmd.command_type {
+                    PLACE_VOXEL => {
+                        let vtype = VoxelType::from_u8(cmd.param_0 as u8);
+                        *voxel = match vtype {
+                            VoxelType::HeatSource | VoxelType::ColdSource => {
+                                let (strength, lifetime) = unpack_thermal_source(cmd.param_1);
+                                Voxel::thermal_source(vtype, strength, lifetime)
+                            }
+                            _ => {
+                                let energy = match vtype {
+                                    VoxelType::EnergySource => 500,
+                                    VoxelType::Nutrient => nutrient_energy,
+                                    _ => 0,
+                                };
+                                Voxel { voxel_type: vtype, energy: energy as u16, ..Default::default() }
+                            }
+                        };
+                    }
+                    REMOVE_VOXEL => *voxel = Voxel::default(),
+                    SEED_PROTOCELLS if voxel.voxel_type == VoxelType::Empty => {
+                        let mut rng = prng_seed(idx, tick, gs, 0x3);
+                        let words = [pcg_next(&mut rng), pcg_next(&mut rng), pcg_next(&mut rng), pcg_next(&mut rng)];
+                        let genome = Genome::from_words(words);
+                        *voxel = Voxel {
+                            voxel_type: VoxelType::Protocell,
+                            energy: cmd.param_0.min(0xFFFF) as u16,
+                            species_id: genome.species_id(),
+                            genome,
+                            ..Default::default()
+                        };
+                    }
+                    APPLY_TOXIN
+                        if voxel.voxel_type == VoxelType::Protocell
+                            && (voxel.genome.toxin_resistance() as u32) < cmd.param_0 =>
+                    {
+                        *voxel = Voxel {
+                            voxel_type: VoxelType::Waste,
+                            species_id: voxel.species_id,
+                            ..Default::default()
+                        };
+                    }
+                    FILL_REGION
+                        if (voxel.voxel_type == VoxelType::Empty || cmd.param_0 & FILL_OVERWRITE != 0)
+                            && fill_roll(prng_seed(idx, tick, gs, 0x5), (cmd.param_0 >> 8) & 0xFF) =>
+                    {
+                        let vtype = VoxelType::from_u8(cmd.param_0 as u8);
+                        let energy = if vtype == VoxelType::Nutrient { nutrient_energy } else { 0 };
+                        *voxel = Voxel { voxel_type: vtype, energy: energy as u16, ..Default::default() };
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Pass 2: temperature diffusion, read temps → write temps, in
+    /// `substep_count` sub-steps as on the GPU.
+    fn diffuse_temperature(&mut self) {
+        let substeps = self.params.substep_count();
+        for step in 0..substeps {
+            if step > 0 {
+                let [a, b] = &mut self.temps;
+                let (read, write) = if self.read == 0 { (a, b) } else { (b, a) };
+                read.copy_from_slice(write);
+            }
+            self.diffuse_temperature_step(substeps);
+        }
+    }
+
+    /// One diffusion sub-step of `substeps` per tick.
+    fn diffuse_temperature_step(&mut self, substeps: u32) {
+        let gs = self.grid_size;
+        let (read, write) = (self.read, self.read ^ 1);
+        let rate = (self.params.diffusion_rate / substeps as f32).clamp(0.0, 0.25);
+        for idx in 0..gs * gs * gs {
+            let i = idx as usize;
+            let own = self.temps[read][i];
+            let voxel = &self.voxels[read][i];
+            let t = match voxel.voxel_type {
+                _ if voxel.is_frozen() => own,
+                VoxelType::Wall => own,
+                vtype => {
+                    let pos = self.coords(idx);
+                    let mut sum = 0.0;
+                    let mut count = 0.0;
+                    for d in 0..6 {
+                        let ni = match (self.neighbor(pos, d), self.params.thermal_boundary as u32) {
+                            (NO_VOXEL, THERMAL_BOUNDARY_PERIODIC) => self.wrapped_neighbor(pos, d),
+                            // Ghost cell past the grid face, as in temperature_diffusion.wgsl
+                            (NO_VOXEL, boundary) => {
+                                sum += if boundary == THERMAL_BOUNDARY_AMBIENT { self.params.base_ambient_temp } else { own };
+                                count += 1.0;
+                                continue;
+                            }
+                            (ni, _) => ni,
+                        };
+                        let n = &self.voxels[read][ni as usize];
+                        if n.voxel_type == VoxelType::Wall || n.is_frozen() {
+                            continue;
+                        }
+                        sum += self.temps[read][ni as usize];
+                        count += 1.0;
+                    }
+                    let t = if count > 0.0 { own + rate * (sum / count - own) } else { own };
+                    // mix(t, 1.0 / 0.0, pull) as in temperature_diffusion.wgsl
+                    let pull = 1.0 - (1.0 - voxel.thermal_strength()).powf(1.0 / substeps as f32);
+                    match vtype {
+                        VoxelType::HeatSource => t * (1.0 - pull) + pull,
+                        VoxelType::ColdSource => t * (1.0 - pull),
+                        _ => t,
+                    }
+                }
+            };
+            self.temps[write][i] = t.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Pass 2 (cont.): pheromone and toxin spread, decay and deposits, read → write.
+    fn update_pheromones(&mut self) {
+        let gs = self.grid_size;
+        let pd = pheromone_dim(gs);
+        let (read, write) = (self.read, self.read ^ 1);
+        let spread_q = pheromone_q16(self.params.pheromone_spread);
+        let decay_q = pheromone_q16(self.params.pheromone_decay);
+        let toxin_decay_q = pheromone_q16(self.params.toxin_decay);
+        let deposit = (PHEROMONE_MAX as f32 * self.params.pheromone_deposit.clamp(0.0, 1.0)) as u32;
+        for cz in 0..pd {
+            for cy in 0..pd {
+                for cx in 0..pd {
+                    let base = (cx * PHEROMONE_CELL, cy * PHEROMONE_CELL, cz * PHEROMONE_CELL);
+                    let ci = pheromone_index(base.0, base.1, base.2, gs);
+                    let (mut bucket, mut level) = pheromone_unpack(self.pheromones[read][ci]);
+                    let mut toxin = toxin_level(self.pheromones[read][ci]);
+                    for (dx, dy, dz) in NEIGHBORS {
+                        let n = (cx as i32 + dx, cy as i32 + dy, cz as i32 + dz);
+                        if n.0 < 0 || n.1 < 0 || n.2 < 0 || n.0 >= pd as i32 || n.1 >= pd as i32 || n.2 >= pd as i32 {
+                            continue;
+                        }
+                        let ni = ((n.2 as u32 * pd + n.1 as u32) * pd + n.0 as u32) as usize;
+                        let (nb, nl) = pheromone_unpack(self.pheromones[read][ni]);
+                        let spread = ((nl as u64 * spread_q as u64) >> 16) as u32;
+                        if spread > level {
+                            level = spread;
+                            bucket = nb;
+                        }
+                        toxin = toxin.max(toxin_level(self.pheromones[read][ni]) / 2);
+                    }
+                    level -= ((level as u64 * decay_q as u64) >> 16) as u32;
+                    toxin -= toxin.min((toxin * toxin_decay_q + 0xFFFF) >> 16);
+                    let mut secreted = 0.0f32;
+
+                    let mut signed_level = level as i32;
+                    for i in 0..PHEROMONE_CELL.pow(3) {
+                        let (x, y, z) = (
+                            base.0 + i % PHEROMONE_CELL,
+                            base.1 + (i / PHEROMONE_CELL) % PHEROMONE_CELL,
+                            base.2 + i / (PHEROMONE_CELL * PHEROMONE_CELL),
+                        );
+                        if x >= gs || y >= gs || z >= gs {
+                            continue;
+                        }
+                        let v = &self.voxels[read][types::grid_index(x, y, z, gs)];
+                        if v.voxel_type != VoxelType::Protocell || v.is_frozen() {
+                            continue;
+                        }
+                        secreted += v.genome.toxin_secretion() as f32 / 255.0;
+                        let amount = (deposit * v.genome.pheromone_strength() as u32 / 255) as i32;
+                        let b = pheromone_bucket(v.species_id);
+                        if bucket == 0 || b == bucket {
+                            bucket = b;
+                            signed_level += amount;
+                        } else {
+                            signed_level -= amount;
+                            if signed_level < 0 {
+                                bucket = b;
+                                signed_level = -signed_level;
+                            }
+                        }
+                    }
+                    let level = signed_level.clamp(0, PHEROMONE_MAX as i32) as u32;
+                    let toxin = toxin + (secreted * self.params.toxin_secretion_rate.max(0.0)) as u32;
+                    self.pheromones[write][ci] =
+                        with_toxin(pheromone_pack(if level == 0 { 0 } else { bucket }, level), toxin);
+                }
+            }
+        }
+    }
+
+    /// Scent one step from `pos` in `dir`: +intensity for `own_bucket`,
+    /// -intensity for a competitor's, 0 when unmarked.
+    fn pheromone_score(&self, pos: (u32, u32, u32), dir: usize, own_bucket: u32) -> i32 {
+        let (x, y, z) = Self::neighbor_pos(pos, dir);
+        let (bucket, level) = pheromone_unpack(self.pheromones[self.read ^ 1][pheromone_index(x, y, z, self.grid_size)]);
+        match bucket {
+            0 => 0,
+            b if b == own_bucket => level as i32,
+            _ => -(level as i32),
+        }
+    }
+
+    /// Pass 3: every protocell declares one intent.
+    fn declare_intents(&mut self) {
+        let gs = self.grid_size;
+        let voxels = &self.voxels[self.read];
+        let max_energy = self.params.max_energy as u32;
+        let replication_min = self.params.replication_energy_min as u32;
+        for idx in 0..gs * gs * gs {
+            let v = &voxels[idx as usize];
+            if v.voxel_type != VoxelType::Protocell || self.held(idx) {
+                self.intents[idx as usize] = 0;
+                continue;
+            }
+
+            // Exactly 5 advances, always consumed
+            let mut rng = prng_seed(idx, self.tick_count, gs, 0x1);
+            let roll_move = pcg_next(&mut rng);
+            let roll_dir = pcg_next(&mut rng);
+            let roll_prey = pcg_next(&mut rng);
+            let roll_replicate = pcg_next(&mut rng);
+            let roll_bid = pcg_next(&mut rng);
+
+            let energy = v.energy as u32;
+            let temp = self.temps[self.read ^ 1][idx as usize];
+            let intent = if energy == 0 {
+                intent_encode(ActionType::Die, Direction::Self_, 0)
+            } else if is_cold_dormant(temp, v.genome.cold_tolerance(), self.params.dormancy_temp) {
+                intent_encode(ActionType::Idle, Direction::Self_, 0)
+            } else {
+                let predation = v.genome.predation_capability() as u32;
+                let prey_threshold = (v.genome.predation_aggression() as u32).wrapping_mul(max_energy) / 255;
+                let pos = self.coords(idx);
+                let mut empty_dirs = Vec::with_capacity(6);
+                let mut prey_dirs = Vec::with_capacity(6);
+                let mut food_mask = 0u32;
+                let mut cell_mask = 0u32;
+                let mut crowd = 0u32;
+                for d in 0..6 {
+                    let ni = self.live_neighbor(pos, d);
+                    if ni == NO_VOXEL {
+                        continue;
+                    }
+                    let n = &voxels[ni as usize];
+                    match n.voxel_type {
+                        VoxelType::Empty => empty_dirs.push(d as u32),
+                        VoxelType::Nutrient | VoxelType::EnergySource => food_mask |= 1 << d,
+                        VoxelType::Protocell => {
+                            crowd += 1;
+                            cell_mask |= 1 << d;
+                            if predation > 0 && (n.energy as u32) < prey_threshold {
+                                prey_dirs.push(d as u32);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                let bid = roll_bid % (energy + 1);
+                let threshold = replication_min.wrapping_mul(v.genome.replication_threshold() as u32) / 255;
+                let bias = v.genome.movement_bias() as u32;
+                let chemotaxis = v.genome.chemotaxis_strength() as u32;
+                let follow = (v.genome.pheromone_strength() as f32 * self.params.pheromone_response).clamp(0.0, 255.0) as u32;
+                let crowded = crowd as f32 > self.params.crowding_threshold
+                    && self.params.crowding_blocks_replication > 0.0;
+                let growth_dir = v.growth_dir() as u32;
+                let anchored = v.is_linked() && cell_mask & (1 << (growth_dir ^ 1)) != 0;
+
+                if predation > 0 && !prey_dirs.is_empty() {
+                    let dir = prey_dirs[(roll_prey % prey_dirs.len() as u32) as usize];
+                    intent_encode(ActionType::Predate, Direction::from_u8(dir as u8), bid)
+                } else if energy > threshold && !empty_dirs.is_empty() && !crowded {
+                    let mut dir = empty_dirs[(roll_replicate % empty_dirs.len() as u32) as usize];
+                    if v.is_linked() && (roll_replicate >> 16) % 255 < v.genome.adhesion() as u32 {
+                        if empty_dirs.contains(&growth_dir) {
+                            dir = growth_dir;
+                        } else {
+                            let side: Vec<u32> = empty_dirs.iter().copied().filter(|&d| d != growth_dir ^ 1).collect();
+                            if !side.is_empty() {
+                                dir = side[(roll_replicate % side.len() as u32) as usize];
+                            }
+                        }
+                    }
+                    intent_encode(ActionType::Replicate, Direction::from_u8(dir as u8), bid)
+                } else if roll_move % 256 < bias && !empty_dirs.is_empty() && !anchored {
+                    let food_empty: Vec<u32> = if food_mask != 0 && chemotaxis > 0 {
+                        empty_dirs.iter().copied().filter(|&d| food_mask & (1 << d) != 0).collect()
+                    } else {
+                        Vec::new()
+                    };
+                    let scent_dirs: Vec<u32> = if follow > 0 {
+                        let own_bucket = pheromone_bucket(v.species_id);
+                        let scores: Vec<i32> =
+                            empty_dirs.iter().map(|&d| self.pheromone_score(pos, d as usize, own_bucket)).collect();
+                        let best = scores.iter().copied().max().unwrap_or(0);
+                        let worst = scores.iter().copied().min().unwrap_or(0);
+                        if best > worst {
+                            empty_dirs.iter().zip(&scores).filter(|&(_, &s)| s == best).map(|(&d, _)| d).collect()
+                        } else {
+                            Vec::new()
+                        }
+                    } else {
+                        Vec::new()
+                    };
+                    let thermo_follow = ((v.genome.thermotaxis_strength() * self.params.thermotaxis_response)
+                        .clamp(0.0, 1.0)
+                        * 255.0) as u32;
+                    let thermo_dirs: Vec<u32> = if thermo_follow > 0 {
+                        let temps = &self.temps[self.read ^ 1];
+                        let preferred = v.genome.preferred_temp();
+                        let own_dist = (temps[idx as usize] - preferred).abs();
+                        let dists: Vec<f32> = empty_dirs
+                            .iter()
+                            .map(|&d| (temps[self.neighbor(pos, d as usize) as usize] - preferred).abs())
+                            .collect();
+                        let best = dists.iter().copied().fold(own_dist, f32::min);
+                        if best < own_dist {
+                            empty_dirs.iter().zip(&dists).filter(|&(_, &d)| d == best).map(|(&d, _)| d).collect()
+                        } else {
+                            Vec::new()
+                        }
+                    } else {
+                        Vec::new()
+                    };
+                    let dir = if !food_empty.is_empty() && roll_dir % 255 < chemotaxis {
+                        food_empty[(roll_dir % food_empty.len() as u32) as usize]
+                    } else if !thermo_dirs.is_empty() && (roll_dir >> 8) % 255 < thermo_follow {
+                        thermo_dirs[(roll_dir % thermo_dirs.len() as u32) as usize]
+                    } else if !scent_dirs.is_empty() && (roll_dir >> 16) % 255 < follow {
+                        scent_dirs[(roll_dir % scent_dirs.len() as u32) as usize]
+                    } else {
+                        empty_dirs[(roll_dir % empty_dirs.len() as u32) as usize]
+                    };
+                    intent_encode(ActionType::Move, Direction::from_u8(dir as u8), bid)
+                } else {
+                    intent_encode(ActionType::Idle, Direction::Self_, 0)
+                }
+            };
+            self.intents[idx as usize] = intent;
+        }
+    }
+
+    /// Per-cell, per-tick arbitration seed; `arbitrate` in resolve_execute.wgsl.
+    fn arbitration_seed(&self, pos: (u32, u32, u32)) -> u32 {
+        let gs = self.grid_size;
+        prng_seed(types::grid_index(pos.0, pos.1, pos.2, gs) as u32, self.tick_count, gs, 0x6)
+    }
+
+    /// REPLICATE/MOVE neighbor targeting `pos` that `rules.arbitration`
+    /// picks: `(index, action, direction from pos, contenders)`. Zero bids
+    /// never win.
+    fn contender_winner(&self, pos: (u32, u32, u32)) -> (u32, ActionType, usize, usize) {
+        let mut found = [Contender::default(); 6];
+        let mut actions = [ActionType::NoAction; 6];
+        let mut n = 0;
+        for d in 0..6 {
+            let ni = self.live_neighbor(pos, d);
+            if ni == NO_VOXEL {
+                continue;
+            }
+            let (action, dir, bid) = intent_decode(self.intents[ni as usize]);
+            if !matches!(action, ActionType::Replicate | ActionType::Move) || dir as usize != d ^ 1 || bid == 0 {
+                continue;
+            }
+            found[n] = Contender { dir: d as u32, index: ni, bid };
+            actions[n] = action;
+            n += 1;
+        }
+        match self.rules.arbitration.pick(&found[..n], self.arbitration_seed(pos)) {
+            Some(w) => (found[w].index, actions[w], found[w].dir as usize, n),
+            None => (NO_VOXEL, ActionType::NoAction, 0, n),
+        }
+    }
+
+    /// PREDATE neighbor targeting `pos` that `rules.arbitration` picks, or
+    /// `NO_VOXEL`, and the number of predators. Protected prey has none.
+    fn predation_winner(&self, pos: (u32, u32, u32)) -> (u32, usize) {
+        let prey = &self.voxels[self.read][types::grid_index(pos.0, pos.1, pos.2, self.grid_size)];
+        if self.rules.is_protected(prey.species_id) {
+            return (NO_VOXEL, 0);
+        }
+        let mut found = [Contender::default(); 6];
+        let mut n = 0;
+        for d in 0..6 {
+            let ni = self.live_neighbor(pos, d);
+            if ni == NO_VOXEL {
+                continue;
+            }
+            let (action, dir, bid) = intent_decode(self.intents[ni as usize]);
+            if action != ActionType::Predate || dir as usize != d ^ 1 || bid == 0 {
+                continue;
+            }
+            found[n] = Contender { dir: d as u32, index: ni, bid };
+            n += 1;
+        }
+        let winner = self.rules.arbitration.pick(&found[..n], self.arbitration_seed(pos));
+        (winner.map_or(NO_VOXEL, |w| found[w].index), n)
+    }
+
+    fn neighbor_pos(pos: (u32, u32, u32), dir: usize) -> (u32, u32, u32) {
+        let (dx, dy, dz) = NEIGHBORS[dir];
+        (
+            pos.0.wrapping_add_signed(dx),
+            pos.1.wrapping_add_signed(dy),
+            pos.2.wrapping_add_signed(dz),
+        )
+    }
+
+    /// Energy gained from adjacent energy sources and from adjacent nutrients.
+    fn metabolic_gain(&self, pos: (u32, u32, u32), genome: &Genome) -> (u32, u32) {
+        let voxels = &self.voxels[self.read];
+        let from_source = self.params.energy_from_source as u32;
+        let from_nutrient = self.params.energy_from_nutrient as u32;
+        let (mut light, mut nutrients) = (0u32, 0u32);
+        for d in 0..6 {
+            let ni = self.live_neighbor(pos, d);
+            if ni == NO_VOXEL {
+                continue;
+            }
+            match voxels[ni as usize].voxel_type {
+                VoxelType::EnergySource => {
+                    light += (genome.photosynthetic_rate() as u32).wrapping_mul(from_source) / 255
+                }
+                VoxelType::Nutrient => {
+                    nutrients += (genome.metabolic_efficiency() as u32).wrapping_mul(from_nutrient) / 255
+                }
+                _ => {}
+            }
+        }
+        (light, nutrients)
+    }
+
+    /// Temperature-scaled metabolic cost at voxel `idx`.
+    /// Regrowth threshold for an uncontested empty cell: logistic in adjacent
+    /// nutrients, boosted by adjacent waste, shaped by the spatial pattern.
+    fn nutrient_spawn_threshold(&self, pos: (u32, u32, u32)) -> u32 {
+        let voxels = &self.voxels[self.read];
+        let (mut nutrients, mut wastes) = (0u32, 0u32);
+        for d in 0..6 {
+            let ni = self.live_neighbor(pos, d);
+            if ni == NO_VOXEL {
+                continue;
+            }
+            match voxels[ni as usize].voxel_type {
+                VoxelType::Nutrient => nutrients += 1,
+                VoxelType::Waste => wastes += 1,
+                _ => {}
+            }
+        }
+        let p = &self.params;
+        let capacity = p.nutrient_capacity.max(1.0);
+        let logistic = (1 + nutrients) as f32 * (1.0 - nutrients as f32 / capacity).max(0.0);
+        let boost = 1.0 + p.nutrient_waste_boost * wastes as f32;
+        let weight = nutrient_pattern_weight(pos, self.grid_size, self.tick_count, p.nutrient_pattern as u32, p.nutrient_front_speed);
+        let spawn_p = (p.nutrient_spawn_rate * logistic * boost * weight).clamp(0.0, 1.0);
+        (spawn_p * 4_294_967_295.0) as u32
+    }
+
+    /// Extra cost for protocell neighbors of `pos` beyond `crowding_threshold`.
+    /// `skip_dir` excludes a mover's vacated source cell.
+    fn crowding_penalty(&self, pos: (u32, u32, u32), skip_dir: Option<usize>) -> u32 {
+        let voxels = &self.voxels[self.read];
+        let crowd = (0..6)
+            .filter(|&d| Some(d) != skip_dir)
+            .map(|d| self.live_neighbor(pos, d))
+            .filter(|&ni| ni != NO_VOXEL && voxels[ni as usize].voxel_type == VoxelType::Protocell)
+            .count();
+        let excess = (crowd as f32 - self.params.crowding_threshold).max(0.0);
+        (excess * self.params.crowding_cost) as u32
+    }
+
+    /// Energy after metabolism; protected species keep at least 1.
+    fn keep_alive(&self, energy: u32, species_id: u16) -> u32 {
+        if self.rules.is_protected(species_id) { energy.max(1) } else { energy }
+    }
+
+    /// Per-cell energy ceiling, raised by storage capacity (gene 14).
+    fn energy_cap(&self, genome: &Genome) -> u32 {
+        let bonus = genome.storage_capacity() as f32 / 255.0 * self.params.storage_capacity_bonus.max(0.0);
+        ((self.params.max_energy * (1.0 + bonus)) as u32).min(0xFFFF)
+    }
+
+    fn metabolic_cost(&self, idx: u32, genome: &Genome) -> u32 {
+        let storage = genome.storage_capacity() as f32 / 255.0 * self.params.storage_metabolic_cost.max(0.0);
+        let base = (self.params.metabolic_cost_base * (1.0 + storage)) as u32;
+        let cost = base.wrapping_mul(255 + genome.metabolic_rate() as u32) / 255;
+        let temp = self.temps[self.read ^ 1][idx as usize];
+        (cost as f32 * temp_modifier(temp, self.params.temp_sensitivity)) as u32
+    }
+
+    /// Energy lost to heat shock at `idx` this tick.
+    fn heat_shock(&self, idx: u32) -> u32 {
+        let temp = self.temps[self.read ^ 1][idx as usize];
+        if temp > self.params.heat_shock_temp {
+            self.params.heat_shock_damage as u32
+        } else {
+            0
+        }
+    }
+
+    /// FLAG_IN_GOAL if `pos` lies in the goal zone.
+    fn goal_flag(&self, pos: (u32, u32, u32)) -> u8 {
+        let p = &self.params;
+        let (x, y, z) = (pos.0 as f32, pos.1 as f32, pos.2 as f32);
+        let inside = x >= p.goal_min_x
+            && y >= p.goal_min_y
+            && z >= p.goal_min_z
+            && x <= p.goal_max_x
+            && y <= p.goal_max_y
+            && z <= p.goal_max_z;
+        if inside { FLAG_IN_GOAL } else { 0 }
+    }
+
+    /// Energy lost at `pos` to field toxin above the genome's resistance.
+    fn toxin_damage(&self, pos: (u32, u32, u32), genome: &Genome) -> u32 {
+        let word = self.pheromones[self.read ^ 1][pheromone_index(pos.0, pos.1, pos.2, self.grid_size)];
+        let excess = toxin_level(word).saturating_sub(genome.toxin_resistance() as u32);
+        (self.params.toxin_damage.max(0.0) * excess as f32 / 255.0) as u32
+    }
+
+    /// Energy spent secreting toxin this tick.
+    fn secretion_cost(&self, genome: &Genome) -> u32 {
+        (self.params.toxin_secretion_cost.max(0.0) * genome.toxin_secretion() as f32 / 255.0) as u32
+    }
+
+    /// Pass 4: every voxel resolves contention for itself and writes its next state.
+    fn resolve_execute(&mut self) {
+        let gs = self.grid_size;
+        let read = self.read;
+        let max_energy = self.params.max_energy as u32;
+        let recycle_threshold = (self.params.nutrient_recycle_rate * 4_294_967_295.0) as u32;
+        let nutrient = Voxel {
+            voxel_type: VoxelType::Nutrient,
+            energy: self.params.energy_from_nutrient as u32 as u16,
+            ..Default::default()
+        };
+
+        let mut next = std::mem::take(&mut self.voxels[read ^ 1]);
+        let mut flow = self.energy_flow;
+        let mut conflicts = self.conflicts;
+        let mut turnover = self.turnover;
+        let mut speciations = std::mem::take(&mut self.speciations);
+        let mut unlogged = self.speciations_unlogged;
+        for idx in 0..gs * gs * gs {
+            let pos = self.coords(idx);
+            let v = self.voxels[read][idx as usize];
+            let mut rng = prng_seed(idx, self.tick_count, gs, 0x2);
+            let waste = |species_id, poisoned: bool| Voxel {
+                voxel_type: VoxelType::Waste,
+                flags: if poisoned { WASTE_FLAG_TOXIN } else { 0 },
+                species_id,
+                ..Default::default()
+            };
+
+            next[idx as usize] = match v.voxel_type {
+                _ if self.held(idx) => v,
+                VoxelType::Empty => {
+                    let (winner, action, dir, contenders) = self.contender_winner(pos);
+                    conflicts.add_placement(contenders as u32);
+                    if winner == NO_VOXEL {
+                        if pcg_next(&mut rng) < self.nutrient_spawn_threshold(pos) {
+                            nutrient
+                        } else {
+                            Voxel::default()
+                        }
+                    } else if action == ActionType::Replicate {
+                        let parent = &self.voxels[read][winner as usize];
+                        let split = parent.genome.energy_split_ratio() as u32;
+                        // Stored surplus above max_energy goes to the offspring in full
+                        let parent_base = (parent.energy as u32).min(max_energy);
+                        let stored = parent.energy as u32 - parent_base;
+                        let offspring_energy = (parent_base * (255 - split)) / 255 + stored;
+                        let temp = self.temps[read ^ 1][idx as usize];
+                        let rate = ((parent.genome.mutation_rate() as f32
+                            * temp_modifier(temp, self.params.temp_sensitivity)) as u32)
+                            .min(255);
+                        let mut genome = parent.genome;
+                        for (i, byte) in genome.bytes.iter_mut().enumerate() {
+                            let roll = pcg_next(&mut rng);
+                            if roll & 0xFF < rate {
+                                let new_byte = ((roll >> 8) & 0xFF) as u8;
+                                *byte = if i == 3 { self.rules.mutator.redraw(new_byte) } else { new_byte };
+                            }
+                        }
+                        if genome.species_id() != parent.species_id {
+                            if speciations.len() < types::layout::LINEAGE_LOG_CAPACITY as usize {
+                                speciations.push(types::LineageEvent {
+                                    parent: parent.species_id,
+                                    child: genome.species_id(),
+                                    tick: self.tick_count,
+                                });
+                            } else {
+                                unlogged += 1;
+                            }
+                        }
+                        turnover.births += 1;
+                        let flags = if parent.genome.adhesion() >= ADHESION_LINK_MIN {
+                            FLAG_LINKED | (dir as u8 ^ 1)
+                        } else {
+                            0
+                        };
+                        Voxel {
+                            voxel_type: VoxelType::Protocell,
+                            flags: flags | self.goal_flag(pos),
+                            energy: offspring_energy.min(self.energy_cap(&genome)) as u16,
+                            species_id: genome.species_id(),
+                            genome,
+                            ..Default::default()
+                        }
+                    } else if self.predation_winner(Self::neighbor_pos(pos, dir)).0 != NO_VOXEL {
+                        // Mover is being eaten on its way out
+                        Voxel::default()
+                    } else {
+                        let mover = &self.voxels[read][winner as usize];
+                        let (light, nutrients) = self.metabolic_gain(pos, &mover.genome);
+                        let toxin = self.toxin_damage(pos, &mover.genome);
+                        let cost = self.metabolic_cost(idx, &mover.genome)
+                            + self.crowding_penalty(pos, Some(dir))
+                            + self.heat_shock(idx)
+                            + toxin
+                            + self.secretion_cost(&mover.genome);
+                        let fed = (mover.energy as u32 + light + nutrients).min(self.energy_cap(&mover.genome));
+                        let energy = fed.saturating_sub(self.params.movement_energy_cost as u32).saturating_sub(cost);
+                        let energy = self.keep_alive(energy, mover.species_id);
+                        flow.add_gain(light, nutrients, fed.saturating_sub(mover.energy as u32));
+                        flow.add(EnergyPath::Metabolism, fed.saturating_sub(energy));
+                        if energy == 0 {
+                            turnover.deaths += 1;
+                            waste(mover.species_id, toxin > 0)
+                        } else {
+                            Voxel {
+                                voxel_type: VoxelType::Protocell,
+                                flags: self.goal_flag(pos),
+                                energy: energy as u16,
+                                age: mover.age.saturating_add(1),
+                                species_id: mover.species_id,
+                                genome: mover.genome,
+                                ..Default::default()
+                            }
+                        }
+                    }
+                }
+                VoxelType::Protocell => {
+                    // 16 mutation-slot advances, consumed regardless of branch
+                    for _ in 0..16 {
+                        pcg_next(&mut rng);
+                    }
+                    let (action, dir, _) = intent_decode(self.intents[idx as usize]);
+                    let dir = dir as usize;
+                    let target = if dir < 6 { self.live_neighbor(pos, dir) } else { NO_VOXEL };
+                    let energy = v.energy as u32;
+
+                    let (predator, predators) = self.predation_winner(pos);
+                    conflicts.add_predation(predators as u32);
+                    if predator != NO_VOXEL {
+                        // What the predator doesn't take is lost
+                        let taken = ((energy as f32 * self.params.predation_energy_fraction) as u32).min(energy);
+                        flow.add(EnergyPath::Decay, energy - taken);
+                        turnover.deaths += 1;
+                        waste(v.species_id, false)
+                    } else if action == ActionType::Die && !self.rules.is_protected(v.species_id) {
+                        flow.add(EnergyPath::Decay, energy);
+                        turnover.deaths += 1;
+                        waste(v.species_id, false)
+                    } else {
+                        let mut work_energy = energy;
+                        let mut moved_away = false;
+                        if target != NO_VOXEL {
+                            let target_pos = Self::neighbor_pos(pos, dir);
+                            match action {
+                                ActionType::Predate if self.predation_winner(target_pos).0 == idx => {
+                                    let prey = self.voxels[read][target as usize].energy;
+                                    let gained = (prey as f32 * self.params.predation_energy_fraction) as u32;
+                                    work_energy = (energy + gained).min(self.energy_cap(&v.genome));
+                                    flow.add(EnergyPath::Predation, work_energy.saturating_sub(energy));
+                                }
+                                ActionType::Replicate if self.contender_winner(target_pos).0 == idx => {
+                                    work_energy = (energy.min(max_energy) * v.genome.energy_split_ratio() as u32) / 255;
+                                }
+                                ActionType::Move if self.contender_winner(target_pos).0 == idx => {
+                                    moved_away = true;
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        if moved_away {
+                            Voxel::default()
+                        } else {
+                            let (light, nutrients) = self.metabolic_gain(pos, &v.genome);
+                            let mut cost = self.metabolic_cost(idx, &v.genome) + self.crowding_penalty(pos, None);
+                            let temp = self.temps[read ^ 1][idx as usize];
+                            let dormant = is_cold_dormant(temp, v.genome.cold_tolerance(), self.params.dormancy_temp);
+                            if dormant {
+                                cost = (cost as f32 * self.params.dormancy_cost_scale.max(0.0)) as u32;
+                            }
+                            let toxin = self.toxin_damage(pos, &v.genome);
+                            cost += self.heat_shock(idx) + toxin + self.secretion_cost(&v.genome);
+                            let fed = (work_energy + light + nutrients).min(self.energy_cap(&v.genome));
+                            let energy = self.keep_alive(fed.saturating_sub(cost), v.species_id);
+                            flow.add_gain(light, nutrients, fed.saturating_sub(work_energy));
+                            flow.add(EnergyPath::Metabolism, fed.saturating_sub(energy));
+                            if energy == 0 {
+                                turnover.deaths += 1;
+                                waste(v.species_id, toxin > 0)
+                            } else {
+                                Voxel {
+                                    voxel_type: VoxelType::Protocell,
+                                    flags: (v.flags & !(FLAG_DORMANT | FLAG_IN_GOAL))
+                                        | if dormant { FLAG_DORMANT } else { 0 }
+                                        | self.goal_flag(pos),
+                                    energy: energy as u16,
+                                    age: v.age.saturating_add(1),
+                                    species_id: v.species_id,
+                                    genome: v.genome,
+                                    ..Default::default()
+                                }
+                            }
+                        }
+                    }
+                }
+                VoxelType::Nutrient => {
+                    let eaters = (0..6)
+                        .map(|d| self.live_neighbor(pos, d))
+                        .filter(|&ni| {
+                            ni != NO_VOXEL && self.voxels[read][ni as usize].voxel_type == VoxelType::Protocell
+                        })
+                        .count() as u16;
+                    let energy = v.energy.saturating_sub(eaters);
+                    if energy == 0 {
+                        Voxel::default()
+                    } else {
+                        Voxel {
+                            voxel_type: VoxelType::Nutrient,
+                            energy,
+                            age: v.age.saturating_add(1),
+                            ..Default::default()
+                        }
+                    }
+                }
+                VoxelType::Waste => {
+                    let age = v.age.saturating_add(1);
+                    if age as u32 >= self.params.waste_decay_ticks as u32 {
+                        if pcg_next(&mut rng) < recycle_threshold {
+                            nutrient
+                        } else {
+                            Voxel::default()
+                        }
+                    } else {
+                        Voxel { age, ..waste(v.species_id, false) }
+                    }
+                }
+                VoxelType::HeatSource | VoxelType::ColdSource => v.deplete_thermal_source(),
+                _ => v,
+            };
+        }
+        self.voxels[read ^ 1] = next;
+        flow.ticks += 1;
+        self.energy_flow = flow;
+        self.conflicts = conflicts;
+        self.turnover = turnover;
+        self.speciations = speciations;
+        self.speciations_unlogged = unlogged;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GS: u32 = 8;
+
+    fn idx(pos: (u32, u32, u32)) -> u32 {
+        types::grid_index(pos.0, pos.1, pos.2, GS) as u32
+    }
+
+    /// The first `n` draws of a voxel's stream for `salt`.
+    fn rolls(pos: (u32, u32, u32), tick: u32, salt: u32, n: usize) -> Vec<u32> {
+        let mut rng = prng_seed(idx(pos), tick, GS, salt);
+        (0..n).map(|_| pcg_next(&mut rng)).collect()
+    }
+
+    /// Genome bytes that hash to distinct species for distinct `n`.
+    fn varied(n: u8) -> [u8; 16] {
+        std::array::from_fn(|i| n.wrapping_mul(31).wrapping_add(i as u8 * 7))
+    }
+
+    fn protocell(bytes: [u8; 16], energy: u16) -> Voxel {
+        let genome = Genome { bytes };
+        Voxel {
+            voxel_type: VoxelType::Protocell,
+            energy,
+            species_id: genome.species_id(),
+            genome,
+            ..Default::default()
+        }
+    }
+
+    /// A sim with no nutrient regrowth and `cells` placed in the read buffer.
+    fn sim_with(cells: &[((u32, u32, u32), Voxel)]) -> CpuSim {
+        let mut sim = CpuSim::new(GS);
+        sim.params.nutrient_spawn_rate = 0.0;
+        for &(pos, v) in cells {
+            sim.voxels[sim.read][idx(pos) as usize] = v;
+        }
+        sim
+    }
+
+    /// Resolve `intents` (by position) against the read buffer; returns the
+    /// next state.
+    fn resolve(sim: &mut CpuSim, intents: &[((u32, u32, u32), u32)]) -> Vec<Voxel> {
+        for &(pos, intent) in intents {
+            sim.intents[idx(pos) as usize] = intent;
+        }
+        sim.resolve_execute();
+        sim.voxels[sim.read ^ 1].clone()
+    }
+
+    #[test]
+    fn pcg_next_steps_the_hash_state() {
+        let mut state = 12345;
+        pcg_next(&mut state);
+        assert_eq!(pcg_next(&mut state), pcg_hash(12345));
+        assert_ne!(prng_seed(0, 0, GS, 0x1), prng_seed(0, 0, GS, 0x2));
+        assert_ne!(prng_seed(0, 0, GS, 0x1), prng_seed(0, 1, GS, 0x1));
+    }
+
+    #[test]
+    fn intent_takes_its_five_rolls_in_order() {
+        // Replication threshold 200, no taxis: a lone cell moves or replicates
+        let mut bytes = [0u8; 16];
+        bytes[2] = 255;
+        bytes[4] = 255;
+        let pos = (3, 4, 5);
+
+        let mut sim = sim_with(&[(pos, protocell(bytes, 100))]);
+        sim.tick_count = 7;
+        sim.declare_intents();
+        let r = rolls(pos, 7, 0x1, 5);
+        assert!(r[0] % 256 < 255, "roll_move vetoes the move");
+        let expected = intent_encode(ActionType::Move, Direction::from_u8((r[1] % 6) as u8), r[4] % 101);
+        assert_eq!(sim.intents[idx(pos) as usize], expected);
+
+        let mut sim = sim_with(&[(pos, protocell(bytes, 300))]);
+        sim.tick_count = 7;
+        sim.declare_intents();
+        let expected = intent_encode(ActionType::Replicate, Direction::from_u8((r[3] % 6) as u8), r[4] % 301);
+        assert_eq!(sim.intents[idx(pos) as usize], expected);
+
+        // Out of energy: DIE, no bid
+        let mut sim = sim_with(&[(pos, protocell(bytes, 0))]);
+        sim.declare_intents();
+        assert_eq!(intent_decode(sim.intents[idx(pos) as usize]), (ActionType::Die, Direction::Self_, 0));
+    }
+
+    #[test]
+    fn offspring_mutate_with_the_sixteen_resolve_rolls() {
+        // Mutation rate 255 redraws a byte unless its roll's low byte is 255
+        let mut bytes = [0u8; 16];
+        bytes[3] = 255;
+        bytes[10] = 128;
+        let (parent_pos, child_pos) = ((2, 2, 2), (3, 2, 2));
+        let parent = protocell(bytes, 400);
+        let mut sim = sim_with(&[(parent_pos, parent)]);
+        let next = resolve(&mut sim, &[(parent_pos, intent_encode(ActionType::Replicate, Direction::PosX, 50))]);
+
+        let mut genome = parent.genome;
+        for (byte, roll) in genome.bytes.iter_mut().zip(rolls(child_pos, 0, 0x2, 16)) {
+            if roll & 0xFF < 255 {
+                *byte = (roll >> 8) as u8;
+            }
+        }
+        let child = next[idx(child_pos) as usize];
+        assert_eq!(child.voxel_type, VoxelType::Protocell);
+        assert_eq!((child.genome, child.species_id), (genome, genome.species_id()));
+        // 400 split 127:128, then the parent pays its base metabolic cost of 2
+        assert_eq!(child.energy, 199);
+        assert_eq!(next[idx(parent_pos) as usize].energy, 198);
+        assert_eq!(sim.turnover, types::Turnover { births: 1, deaths: 0 });
+    }
+
+    #[test]
+    fn contested_cells_go_to_the_highest_bid() {
+        let bytes = [0u8; 16];
+        let (low, high, target) = ((1, 2, 2), (3, 2, 2), (2, 2, 2));
+        let mut sim = sim_with(&[(low, protocell(bytes, 100)), (high, protocell(bytes, 100))]);
+        let next = resolve(
+            &mut sim,
+            &[
+                (low, intent_encode(ActionType::Move, Direction::PosX, 5)),
+                (high, intent_encode(ActionType::Move, Direction::NegX, 9)),
+            ],
+        );
+        assert_eq!(next[idx(high) as usize].voxel_type, VoxelType::Empty);
+        // Both pay metabolism; the mover also the movement cost
+        let (stayed, moved) = (next[idx(low) as usize], next[idx(target) as usize]);
+        assert_eq!((stayed.voxel_type, stayed.energy, stayed.age), (VoxelType::Protocell, 98, 1));
+        assert_eq!((moved.voxel_type, moved.energy, moved.age), (VoxelType::Protocell, 93, 1));
+        assert_eq!((sim.conflicts.placement, sim.conflicts.rejected_placement), (1, 1));
+    }
+
+    #[test]
+    fn predators_take_their_share_of_the_prey() {
+        let mut hunter = [0u8; 16];
+        hunter[7] = 255;
+        let prey = protocell([1; 16], 100);
+        let (predator_pos, prey_pos) = ((2, 2, 2), (2, 3, 2));
+        let mut sim = sim_with(&[(predator_pos, protocell(hunter, 100)), (prey_pos, prey)]);
+        let next = resolve(&mut sim, &[(predator_pos, intent_encode(ActionType::Predate, Direction::PosY, 1))]);
+        // Half the prey's 100, less metabolism
+        assert_eq!(next[idx(predator_pos) as usize].energy, 148);
+        let remains = next[idx(prey_pos) as usize];
+        assert_eq!((remains.voxel_type, remains.species_id), (VoxelType::Waste, prey.species_id));
+        assert_eq!(sim.energy_flow.amount(EnergyPath::Predation), 50);
+        assert_eq!(sim.energy_flow.amount(EnergyPath::Decay), 50);
+        assert_eq!(sim.turnover.deaths, 1);
+    }
+
+    #[test]
+    fn stats_total_the_grid() {
+        let a = protocell(varied(1), 10);
+        let b = protocell(varied(2), 40);
+        let mut old = b;
+        old.age = 9;
+        let toxic_waste = Voxel { voxel_type: VoxelType::Waste, flags: WASTE_FLAG_TOXIN, ..Default::default() };
+        let sim = sim_with(&[
+            ((1, 1, 1), a),
+            ((2, 1, 1), a),
+            ((0, 1, 1), a),
+            ((1, 2, 1), a),
+            ((5, 5, 5), b),
+            ((6, 6, 6), old),
+            ((0, 0, 7), Voxel { voxel_type: VoxelType::Nutrient, energy: 50, ..Default::default() }),
+            ((7, 0, 0), toxic_waste),
+            ((7, 7, 7), Voxel { voxel_type: VoxelType::Wall, ..Default::default() }),
+        ]);
+        let stats = sim.stats();
+        assert_eq!((stats.population, stats.total_energy, stats.max_energy), (6, 120, 40));
+        assert_eq!(stats.species_histogram, vec![(a.species_id, 4), (b.species_id, 2)]);
+        assert_eq!((stats.species_count, stats.other_species), (2, 0));
+        assert_eq!((stats.nutrients, stats.waste, stats.walls, stats.toxin_deaths), (1, 1, 1, 1));
+        assert_eq!((stats.age_sum, stats.mutation_rate_sum), (9, 4 * 52 + 2 * 83));
+        // Only (1, 1, 1) has three same-species neighbors
+        assert_eq!(stats.clustered, Some(1));
+        assert_eq!(stats.energy, Some(types::FieldSummary { min: 10.0, max: 40.0, mean: 20.0 }));
+        assert_eq!(stats.active_bounds, types::bounding_box(&[(0, 0, 0), (7, 7, 7)]));
+
+        // Species past the histogram slots are counted as other
+        let slots = types::layout::STATS_SPECIES_SLOTS;
+        let cells: Vec<_> = (0..slots + 2)
+            .map(|i| ((i % GS, i / GS % GS, i / (GS * GS)), protocell(varied(i as u8 + 1), 1)))
+            .collect();
+        let stats = sim_with(&cells).stats();
+        assert_eq!((stats.species_count, stats.other_species, stats.population), (slots, 2, slots + 2));
+    }
+
+    #[test]
+    fn ticks_are_deterministic_and_turnover_balances() {
+        let run = || {
+            let mut sim = CpuSim::new(16);
+            sim.initialize_grid_with_preset(0);
+            let start = sim.stats().population;
+            for _ in 0..20 {
+                sim.tick(&[]);
+            }
+            (start, sim.stats(), sim.export_snapshot())
+        };
+        let (start, stats, snapshot) = run();
+        assert!(start > 0);
+        assert_eq!(stats.population + stats.turnover.deaths, start + stats.turnover.births);
+        assert_eq!(stats.energy_flow.ticks, 20);
+        assert_eq!(run().2, snapshot);
+    }
+}
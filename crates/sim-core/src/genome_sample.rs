@@ -0,0 +1,160 @@
+//! GPU genome sampling pass: copies the coordinates and genomes of about
+//! `n` live protocells into a compact buffer for async readback, so genome
+//! statistics need no full-grid readback.
+
+use crate::census::storage_entry;
+use types::{genome_sample_words, GENOME_SAMPLE_RESULT_BYTES};
+use types::{load_wgsl, ShaderDefs, SPARSE_DEFINE};
+
+
+pub struct GenomeSamplePass {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buf: wgpu::Buffer,
+    result_buf: wgpu::Buffer,
+    staging_buf: wgpu::Buffer,
+    /// Bound at binding 10 in dense mode, where the brick table is unused.
+    placeholder_brick_table: wgpu::Buffer,
+}
+
+impl GenomeSamplePass {
+    pub fn new(device: &wgpu::Device) -> Self {
+        // Shared by both modes, so always compiled against a brick table
+        let source = load_wgsl("genome_sample.wgsl", &ShaderDefs::new().define(SPARSE_DEFINE));
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("genome_sample"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("genome_sample_bgl"),
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(10, true),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("genome_sample_pl"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("genome_sample_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("genome_sample_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("genome_sample_uniform"),
+            size: 32,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let result_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("genome_sample_result"),
+            size: GENOME_SAMPLE_RESULT_BYTES,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("genome_sample_staging"),
+            size: GENOME_SAMPLE_RESULT_BYTES,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let placeholder_brick_table = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("genome_sample_placeholder_brick_table"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buf,
+            result_buf,
+            staging_buf,
+            placeholder_brick_table,
+        }
+    }
+
+    /// Sample up to `max_samples` protocells whose hash under `seed` is at
+    /// most `threshold` (`types::sample_threshold`) and copy the result into
+    /// staging. `brick_table` is `Some` in sparse mode.
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        voxel_buf: &wgpu::Buffer,
+        brick_table: Option<&wgpu::Buffer>,
+        grid_size: u32,
+        brick_grid_dim: u32,
+        max_samples: u32,
+        threshold: u32,
+        seed: u32,
+    ) {
+        let words = genome_sample_words(grid_size, brick_table.is_some(), brick_grid_dim, max_samples, threshold, seed);
+        queue.write_buffer(&self.uniform_buf, 0, bytemuck::cast_slice(&words));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("genome_sample_bg"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: voxel_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: self.result_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: self.uniform_buf.as_entire_binding() },
+                wgpu::BindGroupEntry {
+                    binding: 10,
+                    resource: brick_table.unwrap_or(&self.placeholder_brick_table).as_entire_binding(),
+                },
+            ],
+        });
+
+        // Only the counter needs resetting; slots past it are never read
+        encoder.clear_buffer(&self.result_buf, 0, Some(4));
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("genome_sample_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let groups = grid_size.div_ceil(types::WORKGROUP_SIZE);
+            pass.dispatch_workgroups(groups, groups, groups);
+        }
+        encoder.copy_buffer_to_buffer(&self.result_buf, 0, &self.staging_buf, 0, GENOME_SAMPLE_RESULT_BYTES);
+    }
+
+    pub fn staging_buffer(&self) -> &wgpu::Buffer {
+        &self.staging_buf
+    }
+
+    /// (label, bytes) for every GPU buffer the sampling pass owns.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("genome_sample_uniform", self.uniform_buf.size()),
+            ("genome_sample_result", self.result_buf.size()),
+            ("genome_sample_staging", self.staging_buf.size()),
+            ("genome_sample_placeholder_brick_table", self.placeholder_brick_table.size()),
+        ]
+    }
+}
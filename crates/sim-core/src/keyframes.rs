@@ -0,0 +1,137 @@
+//! Time-rewind keyframes. Every `interval` ticks the tick copies the voxel,
+//! temperature and pheromone buffers it just wrote into the oldest slot of
+//! a ring of GPU buffers; `SimEngine::rewind_to_keyframe` copies one back.
+//! Each slot is as large as the live buffers, so the ring is opt-in.
+
+use std::collections::VecDeque;
+
+use crate::sparse::SparseGrid;
+
+/// Most keyframes a ring may hold.
+pub const MAX_KEYFRAMES: u32 = 64;
+
+struct Keyframe {
+    voxels: wgpu::Buffer,
+    temperatures: wgpu::Buffer,
+    pheromones: wgpu::Buffer,
+    tick_count: u32,
+    sim_time: f64,
+    /// Brick allocation the pool was captured with (sparse only).
+    grid: Option<SparseGrid>,
+}
+
+/// What `KeyframeRing::restore` hands back to the engine.
+pub(crate) struct Restored {
+    pub tick_count: u32,
+    pub sim_time: f64,
+    pub grid: Option<SparseGrid>,
+}
+
+pub(crate) struct KeyframeRing {
+    interval: u32,
+    /// Captured keyframes, oldest first.
+    frames: VecDeque<Keyframe>,
+    /// Slots without a capture, reused before the oldest frame.
+    spare: Vec<Keyframe>,
+}
+
+impl KeyframeRing {
+    /// `count` slots sized like `sources` (voxels, temperatures, pheromones).
+    pub fn new(device: &wgpu::Device, interval: u32, count: u32, sources: [&wgpu::Buffer; 3]) -> Self {
+        let buffer = |label, source: &wgpu::Buffer| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: source.size(),
+                usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let spare = (0..count.min(MAX_KEYFRAMES))
+            .map(|_| Keyframe {
+                voxels: buffer("keyframe_voxels", sources[0]),
+                temperatures: buffer("keyframe_temperatures", sources[1]),
+                pheromones: buffer("keyframe_pheromones", sources[2]),
+                tick_count: 0,
+                sim_time: 0.0,
+                grid: None,
+            })
+            .collect();
+        Self { interval, frames: VecDeque::new(), spare }
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn capacity(&self) -> u32 {
+        (self.frames.len() + self.spare.len()) as u32
+    }
+
+    /// Whether the state after `tick_count` ticks is captured.
+    pub fn due(&self, tick_count: u32) -> bool {
+        tick_count.is_multiple_of(self.interval)
+    }
+
+    /// Copy `sources` into the oldest (or a spare) slot.
+    pub fn capture(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        sources: [&wgpu::Buffer; 3],
+        tick_count: u32,
+        sim_time: f64,
+        grid: Option<&SparseGrid>,
+    ) {
+        let Some(mut frame) = self.spare.pop().or_else(|| self.frames.pop_front()) else {
+            return;
+        };
+        for (source, dest) in sources.into_iter().zip([&frame.voxels, &frame.temperatures, &frame.pheromones]) {
+            encoder.copy_buffer_to_buffer(source, 0, dest, 0, source.size());
+        }
+        frame.tick_count = tick_count;
+        frame.sim_time = sim_time;
+        frame.grid = grid.cloned();
+        self.frames.push_back(frame);
+    }
+
+    /// Tick counts of the captured keyframes, newest first.
+    pub fn ticks(&self) -> Vec<u32> {
+        self.frames.iter().rev().map(|f| f.tick_count).collect()
+    }
+
+    /// Copy keyframe `n` (0 = newest) into `dests`. Newer keyframes are
+    /// dropped: the run continues from `n`, and they are no longer its future.
+    pub fn restore(&mut self, encoder: &mut wgpu::CommandEncoder, n: usize, dests: [&wgpu::Buffer; 3]) -> Result<Restored, String> {
+        let len = self.frames.len();
+        if n >= len {
+            return Err(format!("keyframe {n} requested, {len} captured"));
+        }
+        for _ in 0..n {
+            if let Some(frame) = self.frames.pop_back() {
+                self.spare.push(frame);
+            }
+        }
+        let frame = self.frames.back().expect("n < len");
+        for (source, dest) in [&frame.voxels, &frame.temperatures, &frame.pheromones].into_iter().zip(dests) {
+            encoder.copy_buffer_to_buffer(source, 0, dest, 0, source.size());
+        }
+        Ok(Restored { tick_count: frame.tick_count, sim_time: frame.sim_time, grid: frame.grid.clone() })
+    }
+
+    /// Forget every capture, keeping the buffers.
+    pub fn clear(&mut self) {
+        self.spare.extend(self.frames.drain(..));
+    }
+
+    /// (label, bytes) of the ring's buffers.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        let frame = self.frames.front().or(self.spare.first());
+        let n = self.capacity() as u64;
+        frame.map_or_else(Vec::new, |f| {
+            vec![
+                ("keyframe_voxels", f.voxels.size() * n),
+                ("keyframe_temperatures", f.temperatures.size() * n),
+                ("keyframe_pheromones", f.pheromones.size() * n),
+            ]
+        })
+    }
+}
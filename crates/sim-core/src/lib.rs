@@ -4,14 +4,33 @@ pub mod pipelines;
 pub mod tick;
 pub mod stats;
 pub mod sparse;
+pub mod pipeline_cache;
+pub mod seed;
+pub mod cpu;
+pub mod census;
+pub mod genome_sample;
+pub mod temp_range;
+pub mod migrate;
+pub mod region;
+pub mod builder;
+pub mod snapshot;
+pub mod profiler;
+pub mod keyframes;
 
 pub use stats::SimStats;
+pub use profiler::{PassTimings, ProfilePass};
+pub use builder::{EngineError, GridConfig, SimEngineBuilder, WorldSeed};
 
 use buffers::{VoxelBuffers, SparseVoxelBuffers};
 use uniform::ParamsUniform;
-use pipelines::{SimPipelines, SparsePipelines};
+use pipelines::{gated_entries, GatedPass, SimPipelines, SparsePipelines};
 use sparse::SparseGrid;
-use types::{SimParams, Voxel, VoxelType, Genome};
+use migrate::MigratePass;
+use region::ActiveRegion;
+use types::{FeatureSet, SimParams, Voxel, VoxelType, Genome};
+
+/// Temperature of cells at seeding and of newly allocated bricks.
+pub(crate) const AMBIENT_TEMPERATURE: f32 = 0.5;
 
 /// Dense mode: all bind groups for the 5-dispatch pipeline.
 pub(crate) struct DenseMode {
@@ -27,6 +46,8 @@ pub(crate) struct DenseMode {
     pub(crate) temp_diffusion_bg_odd: wgpu::BindGroup,
     pub(crate) stats_bg_even: wgpu::BindGroup,
     pub(crate) stats_bg_odd: wgpu::BindGroup,
+    pub(crate) field_stats_bg_even: wgpu::BindGroup,
+    pub(crate) field_stats_bg_odd: wgpu::BindGroup,
 }
 
 /// Sparse mode: pool-based buffers + brick_table bind groups.
@@ -44,73 +65,80 @@ pub(crate) struct SparseMode {
     pub(crate) temp_diffusion_bg_odd: wgpu::BindGroup,
     pub(crate) stats_bg_even: wgpu::BindGroup,
     pub(crate) stats_bg_odd: wgpu::BindGroup,
+    pub(crate) field_stats_bg_even: wgpu::BindGroup,
+    pub(crate) field_stats_bg_odd: wgpu::BindGroup,
     pub(crate) border_alloc_counter: u32,
 }
 
-pub(crate) enum SimMode {
-    Dense(DenseMode),
-    Sparse(SparseMode),
-}
-
-pub struct SimEngine {
-    mode: SimMode,
-    params_uniform: ParamsUniform,
-    pub params: SimParams,
-    tick_count: u32,
-}
-
-impl SimEngine {
-    pub fn try_new(device: &wgpu::Device, _queue: &wgpu::Queue, grid_size: u32) -> Result<Self, String> {
-        let mut params = SimParams::default();
-        params.grid_size = grid_size as f32;
-        let buffers = VoxelBuffers::try_new(device, grid_size)?;
-        let params_uniform = ParamsUniform::new(device, &params);
-        let pipelines = SimPipelines::new(device);
-
+impl DenseMode {
+    /// Bind groups for both buffer parities over `buffers`.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        params_uniform: &ParamsUniform,
+        region: &ActiveRegion,
+        buffers: VoxelBuffers,
+        pipelines: SimPipelines,
+    ) -> Self {
         let intent_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("intent_bg_even"),
             layout: &pipelines.intent_declaration_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Intent, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.intent_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.temp_buffer_b().as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let intent_bg_odd = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("intent_bg_odd"),
             layout: &pipelines.intent_declaration_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Intent, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.intent_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.temp_buffer_a().as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let resolve_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("resolve_bg_even"),
             layout: &pipelines.resolve_execute_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Resolve, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.intent_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 4, resource: buffers.temp_buffer_b().as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 12, resource: buffers.energy_flow_buffer().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 13, resource: params_uniform.rules.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let resolve_bg_odd = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("resolve_bg_odd"),
             layout: &pipelines.resolve_execute_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Resolve, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.intent_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 4, resource: buffers.temp_buffer_a().as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 12, resource: buffers.energy_flow_buffer().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 13, resource: params_uniform.rules.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let apply_cmd_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -120,6 +148,7 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.command_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
             ],
         });
 
@@ -130,29 +159,34 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.command_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
             ],
         });
 
         let temp_diffusion_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("temp_diffusion_bg_even"),
             layout: &pipelines.temperature_diffusion_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::TemperatureDiffusion, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.temp_buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.temp_buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: buffers.buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: params_uniform.buffer.as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_b().as_entire_binding() },
+            ]),
         });
 
         let temp_diffusion_bg_odd = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("temp_diffusion_bg_odd"),
             layout: &pipelines.temperature_diffusion_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::TemperatureDiffusion, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.temp_buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.temp_buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: buffers.buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: params_uniform.buffer.as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_a().as_entire_binding() },
+            ]),
         });
 
         let stats_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -162,6 +196,8 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.stats_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: buffers.buffer_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.stats_partials_buffer().as_entire_binding() },
             ],
         });
 
@@ -172,90 +208,128 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.stats_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: buffers.buffer_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.stats_partials_buffer().as_entire_binding() },
             ],
         });
 
-        let dense = DenseMode {
+        // Field stats read the tick's outputs; the shader is compiled with
+        // brick_common, so dense mode binds a placeholder brick table.
+        let placeholder_brick_table = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("field_stats_placeholder_brick_table"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let field_stats_bg = |label, voxels: &wgpu::Buffer, temps: &wgpu::Buffer, pheromones: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &pipelines.field_stats_bgl,
+                entries: &gated_entries(GatedPass::FieldStats, pipelines.features, vec![
+                    wgpu::BindGroupEntry { binding: 0, resource: voxels.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: buffers.stats_buffer().as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 3, resource: temps.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 4, resource: pheromones.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 10, resource: placeholder_brick_table.as_entire_binding() },
+                ]),
+            })
+        };
+        let field_stats_bg_even = field_stats_bg(
+            "field_stats_bg_even", buffers.buffer_b(), buffers.temp_buffer_b(), buffers.pheromone_buffer_b(),
+        );
+        let field_stats_bg_odd = field_stats_bg(
+            "field_stats_bg_odd", buffers.buffer_a(), buffers.temp_buffer_a(), buffers.pheromone_buffer_a(),
+        );
+
+        DenseMode {
             buffers, pipelines,
             intent_bg_even, intent_bg_odd,
             resolve_bg_even, resolve_bg_odd,
             apply_cmd_bg_even, apply_cmd_bg_odd,
             temp_diffusion_bg_even, temp_diffusion_bg_odd,
             stats_bg_even, stats_bg_odd,
-        };
-
-        Ok(Self {
-            mode: SimMode::Dense(dense),
-            params_uniform,
-            params,
-            tick_count: 0,
-        })
+            field_stats_bg_even, field_stats_bg_odd,
+        }
     }
+}
 
-    /// Create a sparse 256³ engine with brick-based storage.
-    pub fn try_new_sparse(device: &wgpu::Device, _queue: &wgpu::Queue, grid_size: u32, max_bricks: u32) -> Result<Self, String> {
-        let brick_grid_dim = grid_size / 8;
-        let mut params = SimParams::default();
-        params.grid_size = grid_size as f32;
-        params.sparse_mode = 1.0;
-        params.brick_grid_dim = brick_grid_dim as f32;
-        params.max_bricks = max_bricks as f32;
-
-        let buffers = SparseVoxelBuffers::try_new(device, grid_size, max_bricks)?;
-        let grid = SparseGrid::new(device, brick_grid_dim, max_bricks);
-        let params_uniform = ParamsUniform::new(device, &params);
-        let pipelines = SparsePipelines::new(device);
-
+impl SparseMode {
+    /// Bind groups for both pool parities over `buffers` and `grid`'s brick table.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        params_uniform: &ParamsUniform,
+        region: &ActiveRegion,
+        buffers: SparseVoxelBuffers,
+        grid: SparseGrid,
+        pipelines: SparsePipelines,
+    ) -> Self {
         let bt = grid.brick_table_buffer();
 
         let intent_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("sparse_intent_bg_even"),
             layout: &pipelines.intent_declaration_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Intent, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.pool_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.intent_pool().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.temp_pool_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let intent_bg_odd = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("sparse_intent_bg_odd"),
             layout: &pipelines.intent_declaration_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Intent, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.pool_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.intent_pool().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.temp_pool_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let resolve_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("sparse_resolve_bg_even"),
             layout: &pipelines.resolve_execute_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Resolve, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.pool_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.pool_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.intent_pool().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 4, resource: buffers.temp_pool_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 12, resource: buffers.energy_flow_buffer().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 13, resource: params_uniform.rules.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let resolve_bg_odd = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("sparse_resolve_bg_odd"),
             layout: &pipelines.resolve_execute_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::Resolve, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.pool_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.pool_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: buffers.intent_pool().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 4, resource: buffers.temp_pool_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
-            ],
+                wgpu::BindGroupEntry { binding: 11, resource: region.binding() },
+                wgpu::BindGroupEntry { binding: 12, resource: buffers.energy_flow_buffer().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 13, resource: params_uniform.rules.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
+            ]),
         });
 
         let apply_cmd_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -266,6 +340,7 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.command_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
             ],
         });
 
@@ -277,31 +352,36 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.command_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 14, resource: params_uniform.tick_binding() },
             ],
         });
 
         let temp_diffusion_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("sparse_temp_diffusion_bg_even"),
             layout: &pipelines.temperature_diffusion_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::TemperatureDiffusion, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.temp_pool_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.temp_pool_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: buffers.pool_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
-            ],
+            ]),
         });
 
         let temp_diffusion_bg_odd = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("sparse_temp_diffusion_bg_odd"),
             layout: &pipelines.temperature_diffusion_bgl,
-            entries: &[
+            entries: &gated_entries(GatedPass::TemperatureDiffusion, pipelines.features, vec![
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.temp_pool_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.temp_pool_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: buffers.pool_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 3, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.pheromone_buffer_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: buffers.pheromone_buffer_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
-            ],
+            ]),
         });
 
         let stats_bg_even = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -311,6 +391,8 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.pool_b().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.stats_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: buffers.pool_a().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.stats_partials_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
             ],
         });
@@ -322,39 +404,319 @@ impl SimEngine {
                 wgpu::BindGroupEntry { binding: 0, resource: buffers.pool_a().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 1, resource: buffers.stats_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: buffers.pool_b().as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: buffers.stats_partials_buffer().as_entire_binding() },
                 wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
             ],
         });
 
-        let sparse = SparseMode {
+        let field_stats_bg = |label, voxels: &wgpu::Buffer, temps: &wgpu::Buffer, pheromones: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &pipelines.field_stats_bgl,
+                entries: &gated_entries(GatedPass::FieldStats, pipelines.features, vec![
+                    wgpu::BindGroupEntry { binding: 0, resource: voxels.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: buffers.stats_buffer().as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 2, resource: params_uniform.buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 3, resource: temps.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 4, resource: pheromones.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 10, resource: bt.as_entire_binding() },
+                ]),
+            })
+        };
+        let field_stats_bg_even = field_stats_bg(
+            "sparse_field_stats_bg_even", buffers.pool_b(), buffers.temp_pool_b(), buffers.pheromone_buffer_b(),
+        );
+        let field_stats_bg_odd = field_stats_bg(
+            "sparse_field_stats_bg_odd", buffers.pool_a(), buffers.temp_pool_a(), buffers.pheromone_buffer_a(),
+        );
+
+        SparseMode {
             buffers, grid, pipelines,
             intent_bg_even, intent_bg_odd,
             resolve_bg_even, resolve_bg_odd,
             apply_cmd_bg_even, apply_cmd_bg_odd,
             temp_diffusion_bg_even, temp_diffusion_bg_odd,
             stats_bg_even, stats_bg_odd,
+            field_stats_bg_even, field_stats_bg_odd,
             border_alloc_counter: 0,
-        };
+        }
+    }
+}
+
+// One per engine, so boxing the larger mode would save nothing
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum SimMode {
+    Dense(DenseMode),
+    Sparse(SparseMode),
+}
+
+/// Sim pipelines compiled by `SimEngine::compile_pipelines`, for the mode
+/// they were compiled for.
+pub enum EnginePipelines {
+    Dense(SimPipelines),
+    Sparse(SparsePipelines),
+}
+
+pub struct SimEngine {
+    mode: SimMode,
+    params_uniform: ParamsUniform,
+    pub params: SimParams,
+    /// Mutation-rate bounds and conflict arbitration, uploaded with `params`.
+    pub rules: types::RuleParams,
+    tick_count: u32,
+    /// Simulated seconds since the last reset: sum of `params.dt` per tick.
+    sim_time: f64,
+    /// When false, `tick` skips the stats reduction pass and staging copy.
+    stats_enabled: bool,
+    /// Measured bounds of non-empty voxels that shrink dense dispatches.
+    region: ActiveRegion,
+    /// Scheduled nutrient pulses; see `scheduled_commands`.
+    pub blooms: Vec<types::NutrientBloom>,
+    /// Notable events: brick pool exhaustion, dropped commands, reclaimed
+    /// bricks. The host adds parameter clamps.
+    pub log: types::EngineLog,
+    /// Whether the last border allocation ran out of pool; logged on change.
+    pool_exhausted: bool,
+    /// Sparse level of detail and the point it measures from, in voxels.
+    lod: Option<types::SimLod>,
+    lod_focus: [f32; 3],
+    /// GPU pass timing, while enabled; see `set_profiling`.
+    profiler: Option<profiler::GpuProfiler>,
+    /// Periodic copies of the world to rewind to; see `set_keyframes`.
+    keyframes: Option<keyframes::KeyframeRing>,
+}
 
-        Ok(Self {
-            mode: SimMode::Sparse(sparse),
-            params_uniform,
-            params,
-            tick_count: 0,
-        })
+impl SimEngine {
+    /// Start configuring a new engine; see `SimEngineBuilder`.
+    pub fn builder<'a>() -> SimEngineBuilder<'a> {
+        SimEngineBuilder::default()
     }
 
-    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, grid_size: u32) -> Self {
-        Self::try_new(device, queue, grid_size).expect("Failed to create SimEngine")
+    /// GPU bytes a dense engine of this size would allocate.
+    pub fn estimate_dense_bytes(grid_size: u32, features: FeatureSet) -> u64 {
+        VoxelBuffers::estimated_bytes(grid_size, features)
+            + SimParams::default().to_bytes().len() as u64
+            + uniform::RULES_BYTES
+            + region::REGION_BUFFER_BYTES
+    }
+
+    /// GPU bytes a sparse engine would allocate (pool + brick table).
+    pub fn estimate_sparse_bytes(grid_size: u32, max_bricks: u32, features: FeatureSet) -> u64 {
+        let brick_grid_dim = grid_size / 8;
+        SparseVoxelBuffers::estimated_bytes(grid_size, max_bricks, features)
+            + SparseGrid::buffer_bytes(brick_grid_dim, max_bricks)
+            + SimParams::default().to_bytes().len() as u64
+            + uniform::RULES_BYTES
+            + region::REGION_BUFFER_BYTES
     }
 
     pub fn is_sparse(&self) -> bool {
         matches!(self.mode, SimMode::Sparse(_))
     }
 
+    /// Tick bricks far from the LOD focus at a reduced rate (sparse only;
+    /// a dense engine keeps it for a later sparse upgrade). Cells of a brick
+    /// sitting out a tick keep their state, and their neighbors treat them
+    /// as absent; the temperature field still updates everywhere.
+    pub fn set_lod(&mut self, lod: Option<types::SimLod>) {
+        self.lod = lod;
+        self.apply_lod();
+    }
+
+    pub fn lod(&self) -> Option<types::SimLod> {
+        self.lod
+    }
+
+    /// Point, in voxels, that LOD distances are measured from; the host
+    /// passes the camera focus.
+    pub fn set_lod_focus(&mut self, focus: [f32; 3]) {
+        self.lod_focus = focus;
+        self.apply_lod();
+    }
+
+    /// Allocated bricks ticking at a reduced rate (0 when dense).
+    pub fn far_brick_count(&self) -> u32 {
+        match &self.mode {
+            SimMode::Sparse(s) => s.grid.far_brick_count(),
+            SimMode::Dense(_) => 0,
+        }
+    }
+
+    fn apply_lod(&mut self) {
+        if let SimMode::Sparse(s) = &mut self.mode {
+            s.grid.set_lod(self.lod, self.lod_focus);
+        }
+    }
+
+    /// Optional subsystems this engine allocates and runs.
+    pub fn features(&self) -> FeatureSet {
+        match &self.mode {
+            SimMode::Dense(d) => d.pipelines.features,
+            SimMode::Sparse(s) => s.pipelines.features,
+        }
+    }
+
+    /// Compile this engine's sim pipelines again from the current shader
+    /// sources (see `types::set_shader_override`), for hot reload. Creation
+    /// errors surface through the device's error scopes, so check them
+    /// before `install_pipelines`.
+    pub fn compile_pipelines(&self, device: &wgpu::Device) -> EnginePipelines {
+        let features = self.features();
+        match &self.mode {
+            SimMode::Dense(_) => EnginePipelines::Dense(SimPipelines::new(device, None, features)),
+            SimMode::Sparse(_) => EnginePipelines::Sparse(SparsePipelines::new(device, None, features)),
+        }
+    }
+
+    /// Swap in pipelines from `compile_pipelines`, rebinding the same
+    /// buffers; the world, tick and params are untouched. Err if the engine
+    /// changed mode since they were compiled.
+    pub fn install_pipelines(&mut self, device: &wgpu::Device, pipelines: EnginePipelines) -> Result<(), String> {
+        match (&mut self.mode, pipelines) {
+            (SimMode::Dense(d), EnginePipelines::Dense(pipelines)) => {
+                let buffers = d.buffers.clone();
+                *d = DenseMode::new(device, &self.params_uniform, &self.region, buffers, pipelines);
+            }
+            (SimMode::Sparse(s), EnginePipelines::Sparse(pipelines)) => {
+                let (buffers, grid, border) = (s.buffers.clone(), s.grid.clone(), s.border_alloc_counter);
+                *s = SparseMode::new(device, &self.params_uniform, &self.region, buffers, grid, pipelines);
+                s.border_alloc_counter = border;
+            }
+            _ => return Err("engine changed mode while its pipelines compiled".into()),
+        }
+        Ok(())
+    }
+
+    /// Upgrade a dense world to a sparse pool of `max_bricks` bricks.
+    /// Bricks are allocated where `occupancy` (per-brick counts from
+    /// `MigratePass::encode_occupancy`) is non-zero, plus their face
+    /// neighbors. Voxels, temperature and pheromones carry over; tick count
+    /// and params are kept. Anything bound to the old buffers (renderer
+    /// mode, readbacks in flight) is the caller's to rebuild.
+    pub fn convert_to_sparse(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        migrate: &MigratePass,
+        max_bricks: u32,
+        occupancy: &[u32],
+    ) -> Result<(), String> {
+        let SimMode::Dense(d) = &self.mode else {
+            return Err("engine is already sparse".into());
+        };
+        let gs = d.buffers.grid_size();
+        let dim = gs / 8;
+        if gs % 8 != 0 || migrate.grid_size() != gs || occupancy.len() != (dim as usize).pow(3) {
+            return Err(format!("occupancy does not match a {gs}³ grid"));
+        }
+        let needed = sparse::bricks_needed(occupancy, dim);
+        if needed > max_bricks {
+            return Err(format!("world needs {needed} bricks, pool holds {max_bricks}"));
+        }
+
+        let mut grid = SparseGrid::new(device, dim, max_bricks);
+        grid.set_lod(self.lod, self.lod_focus);
+        for (i, _) in occupancy.iter().enumerate().filter(|(_, &count)| count > 0) {
+            let i = i as u32;
+            let _ = grid.allocate_brick(i % dim, (i / dim) % dim, i / (dim * dim));
+        }
+        let _ = grid.proactive_border_alloc();
+        grid.upload_if_dirty(queue);
+        // The pools are new, and the migration fills every allocated brick
+        grid.take_fresh_slots();
+        let features = self.features();
+        let buffers = SparseVoxelBuffers::try_new(device, gs, max_bricks, features)?;
+        let pipelines = SparsePipelines::new(device, None, features);
+
+        let sparse = SparseMode::new(device, &self.params_uniform, &self.region, buffers, grid, pipelines);
+        let SimMode::Dense(d) = std::mem::replace(&mut self.mode, SimMode::Sparse(sparse)) else {
+            unreachable!("checked dense above");
+        };
+        self.region.invalidate();
+        self.rebuild_keyframes(device);
+        self.params.sparse_mode = 1.0;
+        self.params.brick_grid_dim = dim as f32;
+        self.params.max_bricks = max_bricks as f32;
+        self.params_uniform.upload(queue, &self.params);
+        // Cells of bricks allocated later start at ambient, as after seeding
+        self.init_temperature(queue);
+
+        if let SimMode::Sparse(s) = &self.mode {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("migrate_to_sparse_encoder"),
+            });
+            migrate.encode_dense_to_sparse(
+                &mut encoder,
+                device,
+                queue,
+                [d.buffers.current_read_buffer(), d.buffers.current_temp_read()],
+                [s.buffers.pool_a(), s.buffers.temp_pool_a()],
+                s.grid.brick_table_buffer(),
+            );
+            let pher = d.buffers.current_pheromone_read();
+            encoder.copy_buffer_to_buffer(pher, 0, s.buffers.pheromone_buffer_a(), 0, pher.size());
+            queue.submit(std::iter::once(encoder.finish()));
+        }
+        Ok(())
+    }
+
+    /// Downgrade a sparse world to dense buffers of the same grid size.
+    /// Cells of unallocated bricks become empty at ambient temperature.
+    /// As with `convert_to_sparse`, the caller rebuilds anything bound to
+    /// the old buffers.
+    pub fn convert_to_dense(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        migrate: &MigratePass,
+    ) -> Result<(), String> {
+        let SimMode::Sparse(s) = &self.mode else {
+            return Err("engine is already dense".into());
+        };
+        let gs = s.buffers.grid_size();
+        if migrate.grid_size() != gs {
+            return Err(format!("migration pass does not match a {gs}³ grid"));
+        }
+        let features = self.features();
+        let buffers = VoxelBuffers::try_new(device, gs, features)?;
+        let pipelines = SimPipelines::new(device, None, features);
+
+        let dense = DenseMode::new(device, &self.params_uniform, &self.region, buffers, pipelines);
+        let SimMode::Sparse(s) = std::mem::replace(&mut self.mode, SimMode::Dense(dense)) else {
+            unreachable!("checked sparse above");
+        };
+        self.region.invalidate();
+        self.rebuild_keyframes(device);
+        let defaults = SimParams::default();
+        self.params.sparse_mode = defaults.sparse_mode;
+        self.params.brick_grid_dim = defaults.brick_grid_dim;
+        self.params.max_bricks = defaults.max_bricks;
+        self.params_uniform.upload(queue, &self.params);
+
+        if let SimMode::Dense(d) = &self.mode {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("migrate_to_dense_encoder"),
+            });
+            // The brick table as last uploaded, i.e. the one the pool was simulated with
+            migrate.encode_sparse_to_dense(
+                &mut encoder,
+                device,
+                queue,
+                [d.buffers.buffer_a(), d.buffers.temp_buffer_a()],
+                [s.buffers.current_read_pool(), s.buffers.current_temp_read()],
+                s.grid.brick_table_buffer(),
+            );
+            let pher = s.buffers.current_pheromone_read();
+            encoder.copy_buffer_to_buffer(pher, 0, d.buffers.pheromone_buffer_a(), 0, pher.size());
+            queue.submit(std::iter::once(encoder.finish()));
+        }
+        Ok(())
+    }
+
     /// Seed the grid with default initial conditions (Petri Dish preset).
     pub fn initialize_grid(&mut self, queue: &wgpu::Queue) {
-        self.seed_petri_dish(queue);
+        self.initialize_grid_with_preset(queue, 0);
     }
 
     pub fn current_read_buffer(&self) -> &wgpu::Buffer {
@@ -389,6 +751,23 @@ impl SimEngine {
         }
     }
 
+    /// Pheromone/toxin field after the most recent tick.
+    pub fn current_pheromone_buffer(&self) -> &wgpu::Buffer {
+        match &self.mode {
+            SimMode::Dense(d) => d.buffers.current_pheromone_read(),
+            SimMode::Sparse(s) => s.buffers.current_pheromone_read(),
+        }
+    }
+
+    /// Stats reduction output (`STATS_WORDS` × u32), valid after a tick
+    /// with stats enabled.
+    pub fn stats_buffer(&self) -> &wgpu::Buffer {
+        match &self.mode {
+            SimMode::Dense(d) => d.buffers.stats_buffer(),
+            SimMode::Sparse(s) => s.buffers.stats_buffer(),
+        }
+    }
+
     pub fn stats_staging_buffer(&self) -> &wgpu::Buffer {
         match &self.mode {
             SimMode::Dense(d) => d.buffers.stats_staging_buffer(),
@@ -400,6 +779,163 @@ impl SimEngine {
         self.tick_count
     }
 
+    /// Upload `params` outside of a tick (e.g. a change while paused).
+    /// Unchanged params are not rewritten.
+    pub fn upload_params(&mut self, queue: &wgpu::Queue) {
+        self.params_uniform.upload(queue, &self.params);
+        self.params_uniform.upload_rules(queue, &self.rules);
+    }
+
+    /// Overlay mode and trait-overlay genome byte for later ticks'
+    /// `TickParams`; also rewrites the latest slot so the render texture sees
+    /// a change made while paused.
+    pub fn set_overlay_mode(&mut self, queue: &wgpu::Queue, overlay_mode: u32, overlay_trait: u32) {
+        self.params_uniform.set_overlay_mode(queue, overlay_mode, overlay_trait);
+    }
+
+    /// The `TickParams` slot of the latest tick, for passes outside the
+    /// tick (the render texture binds it at binding 14).
+    pub fn tick_params_binding(&self) -> wgpu::BindingResource<'_> {
+        self.params_uniform.latest_tick_binding()
+    }
+
+    /// FillRegion commands for blooms due in the next `ticks` ticks. Callers
+    /// pass them with the first tick of the batch, as with player commands.
+    pub fn scheduled_commands(&self, ticks: u32) -> Vec<types::Command> {
+        types::bloom_commands(&self.blooms, self.tick_count, ticks)
+    }
+
+    /// In-world time since the last reset, accumulated from `params.dt`.
+    pub fn sim_time_seconds(&self) -> f64 {
+        self.sim_time
+    }
+
+    /// Enable or disable the stats reduction pass for subsequent ticks.
+    /// The stats staging buffer must not be mapped while stats are enabled.
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats_enabled = enabled;
+    }
+
+    pub fn stats_enabled(&self) -> bool {
+        self.stats_enabled
+    }
+
+    /// Turn GPU pass timing on or off. Returns whether it is on: false when
+    /// the device was created without `TIMESTAMP_QUERY`.
+    pub fn set_profiling(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, enabled: bool) -> bool {
+        if !enabled {
+            self.profiler = None;
+        } else if self.profiler.is_none() {
+            self.profiler = profiler::GpuProfiler::new(device, queue);
+        }
+        self.profiler.is_some()
+    }
+
+    pub fn profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    /// Time the passes of the next tick (no-op while profiling is off).
+    /// Arm only once the previous capture's staging buffer is unmapped.
+    pub fn profile_next_tick(&mut self) {
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.arm();
+        }
+    }
+
+    /// Staging buffer of a profile encoded since the last call; map it after
+    /// the submit and hand the bytes to `read_profile`.
+    pub fn take_profile_capture(&mut self) -> Option<&wgpu::Buffer> {
+        self.profiler.as_mut()?.take_capture()
+    }
+
+    /// Per-pass GPU times from the mapped bytes of `take_profile_capture`.
+    pub fn read_profile(&self, bytes: &[u8]) -> Option<PassTimings> {
+        self.profiler.as_ref()?.read(bytes)
+    }
+
+    /// Capture the world every `interval` ticks into a ring of `count`
+    /// keyframes (at most `keyframes::MAX_KEYFRAMES`), replacing any ring;
+    /// 0 for either frees it. Each keyframe is the size of the voxel,
+    /// temperature and pheromone buffers.
+    pub fn set_keyframes(&mut self, device: &wgpu::Device, interval: u32, count: u32) {
+        self.keyframes = None;
+        if interval > 0 && count > 0 {
+            self.keyframes = Some(keyframes::KeyframeRing::new(device, interval, count, self.keyframe_sources()));
+        }
+    }
+
+    /// (interval, capacity) of the keyframe ring, if any.
+    pub fn keyframe_config(&self) -> Option<(u32, u32)> {
+        self.keyframes.as_ref().map(|k| (k.interval(), k.capacity()))
+    }
+
+    /// Tick counts of the captured keyframes, newest first.
+    pub fn keyframe_ticks(&self) -> Vec<u32> {
+        self.keyframes.as_ref().map_or_else(Vec::new, |k| k.ticks())
+    }
+
+    /// Return the world to keyframe `n` (0 = newest): voxels, temperature,
+    /// pheromones, brick allocation, tick count and simulated time. Params
+    /// stay as they are, and newer keyframes are dropped. Returns the tick
+    /// count rewound to. Readbacks in flight describe the abandoned run.
+    pub fn rewind_to_keyframe(&mut self, encoder: &mut wgpu::CommandEncoder, n: u32) -> Result<u32, String> {
+        let ring = self.keyframes.as_mut().ok_or("keyframes are off")?;
+        let dests = match &self.mode {
+            SimMode::Dense(d) => [d.buffers.buffer_a(), d.buffers.temp_buffer_a(), d.buffers.pheromone_buffer_a()],
+            SimMode::Sparse(s) => [s.buffers.pool_a(), s.buffers.temp_pool_a(), s.buffers.pheromone_buffer_a()],
+        };
+        let restored = ring.restore(encoder, n as usize, dests)?;
+        match &mut self.mode {
+            SimMode::Dense(d) => d.buffers.reset_read_is_a(),
+            SimMode::Sparse(s) => {
+                s.buffers.reset_read_is_a();
+                if let Some(grid) = &restored.grid {
+                    s.grid.restore(grid);
+                    s.grid.record_upload_if_dirty(encoder);
+                }
+            }
+        }
+        let energy_flow = match &self.mode {
+            SimMode::Dense(d) => d.buffers.energy_flow_buffer(),
+            SimMode::Sparse(s) => s.buffers.energy_flow_buffer(),
+        };
+        encoder.clear_buffer(energy_flow, 0, None);
+        self.region.invalidate();
+        self.tick_count = restored.tick_count;
+        self.sim_time = restored.sim_time;
+        Ok(restored.tick_count)
+    }
+
+    /// Same ring, sized for the current buffers; captures are dropped.
+    fn rebuild_keyframes(&mut self, device: &wgpu::Device) {
+        if let Some((interval, count)) = self.keyframe_config() {
+            self.set_keyframes(device, interval, count);
+        }
+    }
+
+    /// Voxel, temperature and pheromone buffers after the most recent tick.
+    fn keyframe_sources(&self) -> [&wgpu::Buffer; 3] {
+        [self.current_read_buffer(), self.current_temp_buffer(), self.current_pheromone_buffer()]
+    }
+
+    /// Hand back `SimStats::active_bounds` from the readback of the latest
+    /// stats tick. Later dense ticks then dispatch intent and resolve over
+    /// those bounds, grown by one cell per tick, instead of the whole grid.
+    /// Call between submissions: it rewrites the region uniform.
+    pub fn set_active_bounds(&mut self, queue: &wgpu::Queue, bounds: Option<types::CellBox>) {
+        self.region.accept(queue, bounds);
+    }
+
+    /// Box holding every non-empty voxel of the current state, while a
+    /// recent measurement bounds it (dense mode only).
+    pub fn active_bounds(&self) -> Option<types::CellBox> {
+        match self.mode {
+            SimMode::Dense(_) => self.region.bounds_at(self.tick_count),
+            SimMode::Sparse(_) => None,
+        }
+    }
+
     pub fn current_write_buffer(&self) -> &wgpu::Buffer {
         match &self.mode {
             SimMode::Dense(d) => d.buffers.current_write_buffer(),
@@ -414,27 +950,86 @@ impl SimEngine {
         }
     }
 
+    /// CPU copy of the brick table in sparse mode.
+    pub fn brick_table(&self) -> Option<&[u32]> {
+        match &self.mode {
+            SimMode::Dense(_) => None,
+            SimMode::Sparse(s) => Some(s.grid.brick_table()),
+        }
+    }
+
+    /// Coordinates and age in ticks of every allocated brick; empty in
+    /// dense mode.
+    pub fn brick_ages(&self) -> Vec<((u32, u32, u32), u32)> {
+        match &self.mode {
+            SimMode::Dense(_) => Vec::new(),
+            SimMode::Sparse(s) => s.grid.allocated_bricks().map(|(b, at)| (b, self.tick_count.saturating_sub(at))).collect(),
+        }
+    }
+
+    /// Brick pool occupancy and pressure counters; `None` in dense mode.
+    pub fn brick_pool(&self) -> Option<types::BrickPoolStats> {
+        match &self.mode {
+            SimMode::Dense(_) => None,
+            SimMode::Sparse(s) => Some(s.grid.pool_stats()),
+        }
+    }
+
+    /// (label, bytes) for every GPU buffer owned by the engine.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        let mut out = match &self.mode {
+            SimMode::Dense(d) => d.buffers.allocations(),
+            SimMode::Sparse(s) => {
+                let mut v = s.buffers.allocations();
+                v.push(("brick_table", s.grid.brick_table_buffer().size()));
+                v
+            }
+        };
+        out.push(("params_uniform", self.params_uniform.buffer.size()));
+        out.push(("rule_params_uniform", self.params_uniform.rules.size()));
+        out.push(("tick_params_uniform", self.params_uniform.tick.size()));
+        out.push(("active_region_uniform", self.region.buffer_size()));
+        if let Some(ring) = &self.keyframes {
+            out.extend(ring.allocations());
+        }
+        out
+    }
+
     pub fn reset_tick_count(&mut self) {
+        self.region.invalidate();
+        if let Some(ring) = self.keyframes.as_mut() {
+            ring.clear();
+        }
         self.tick_count = 0;
+        self.sim_time = 0.0;
         match &mut self.mode {
             SimMode::Dense(d) => d.buffers.reset_read_is_a(),
-            SimMode::Sparse(s) => s.buffers.reset_read_is_a(),
+            SimMode::Sparse(s) => {
+                s.buffers.reset_read_is_a();
+                s.grid.set_clock(0);
+            }
         }
     }
 
-    /// Load a preset by ID: 0=Petri Dish, 1=Gradient, 2=Arena
+    /// Load a preset by ID (see `seed::preset`).
     pub fn initialize_grid_with_preset(&mut self, queue: &wgpu::Queue, preset: u32) {
+        let voxels = seed::preset(self.grid_size(), preset);
+        self.initialize_grid_with_voxels(queue, &voxels);
+    }
+
+    /// Replace the world with `voxels` (later entries overwrite earlier
+    /// ones), as a preset does. Cells past the grid, or past a full brick
+    /// pool in sparse mode, are skipped. Returns the cells written.
+    pub fn initialize_grid_with_voxels(&mut self, queue: &wgpu::Queue, voxels: &[seed::SeedVoxel]) -> u32 {
         self.clear_voxel_buffer_a(queue);
-        match preset {
-            0 => self.seed_petri_dish(queue),
-            1 => self.seed_gradient(queue),
-            2 => self.seed_arena(queue),
-            _ => self.seed_petri_dish(queue),
-        }
+        let written = self.write_voxels(queue, voxels);
+        self.finalize_seed(queue);
+        written
     }
 
     /// Clear the primary voxel buffer (A) to zeros.
     fn clear_voxel_buffer_a(&mut self, queue: &wgpu::Queue) {
+        self.region.invalidate();
         match &mut self.mode {
             SimMode::Dense(d) => {
                 let gs = d.buffers.grid_size();
@@ -448,6 +1043,10 @@ impl SimEngine {
                 let zero_data = vec![0u8; pool_size];
                 queue.write_buffer(s.buffers.pool_a(), 0, &zero_data);
                 // Reset all brick allocations
+                let reclaimed = s.grid.active_brick_count();
+                if reclaimed > 0 {
+                    self.log.info(self.tick_count, types::Subsystem::Bricks, format!("reclaimed {reclaimed} bricks on reset"));
+                }
                 let dim = s.grid.brick_grid_dim();
                 for bz in 0..dim {
                     for by in 0..dim {
@@ -458,8 +1057,15 @@ impl SimEngine {
                         }
                     }
                 }
+                s.grid.reset_pressure();
             }
         }
+        // A new world starts a new energy-flow window
+        let energy_flow = match &self.mode {
+            SimMode::Dense(d) => d.buffers.energy_flow_buffer(),
+            SimMode::Sparse(s) => s.buffers.energy_flow_buffer(),
+        };
+        queue.write_buffer(energy_flow, 0, &vec![0u8; stats::ENERGY_FLOW_BYTES as usize]);
     }
 
     /// Write a single voxel to buffer A (used during seeding).
@@ -477,316 +1083,69 @@ impl SimEngine {
                 if let Some(pool_idx) = s.grid.voxel_pool_index(x, y, z) {
                     let byte_offset = (pool_idx as u64) * 32;
                     queue.write_buffer(s.buffers.pool_a(), byte_offset, bytes);
+                } else {
+                    self.log.warn(self.tick_count, types::Subsystem::Pool, format!("brick pool full: voxel ({x}, {y}, {z}) not placed"));
                 }
             }
         }
     }
 
-    fn seed_petri_dish(&mut self, queue: &wgpu::Queue) {
-        let gs = self.grid_size();
-        let center = gs / 2;
-        let mut voxel_data: Vec<(u32, u32, u32, [u32; 8])> = Vec::new();
-
-        // Walls (5 scattered)
-        for i in 0..5u32 {
-            let x = center.saturating_sub(15) + i * 3;
-            let y = center.saturating_sub(15);
-            let z = center;
-            let v = Voxel {
-                voxel_type: VoxelType::Wall,
-                energy: 0,
-                ..Default::default()
-            };
-            voxel_data.push((x.min(gs - 1), y.min(gs - 1), z, v.pack()));
-        }
-
-        // Nutrient field (scaled to grid size)
-        let nutrient_half = (gs / 10).max(4);
-        for dx in 0..(nutrient_half * 2) {
-            for dy in 0..(nutrient_half * 2) {
-                for dz in 0..(nutrient_half * 2) {
-                    let x = center - nutrient_half + dx;
-                    let y = center - nutrient_half + dy;
-                    let z = center - nutrient_half + dz;
-                    if x < gs && y < gs && z < gs {
-                        let v = Voxel {
-                            voxel_type: VoxelType::Nutrient,
-                            energy: 200,
-                            ..Default::default()
-                        };
-                        voxel_data.push((x, y, z, v.pack()));
-                    }
-                }
-            }
-        }
-
-        // Energy sources (3 near center)
-        for i in 0..3u32 {
-            let x = (center - 1 + i).min(gs - 1);
-            let v = Voxel {
-                voxel_type: VoxelType::EnergySource,
-                energy: 500,
-                ..Default::default()
-            };
-            voxel_data.push((x, center, center, v.pack()));
-        }
-
-        // Protocells (~50 in tight cluster near center)
-        for i in 0..50u32 {
-            let angle = (i as f32) * 0.126;
-            let radius = 1.0 + (i as f32) * 0.08;
-            let layer = (i / 16) as f32;
-            let x = ((center as f32 + angle.cos() * radius).round() as u32).min(gs - 1);
-            let y = ((center as f32 + angle.sin() * radius).round() as u32).min(gs - 1);
-            let z = ((center as f32 - 2.0 + layer).round() as u32).min(gs - 1);
-
-            let mut genome = Genome::default();
-            genome.bytes[0] = (80 + (i % 20) * 8) as u8;
-            genome.bytes[1] = (30 + (i % 15) * 5) as u8;
-            genome.bytes[2] = 200;
-            genome.bytes[3] = (i * 3) as u8;
-            genome.bytes[4] = (60 + (i % 10) * 15) as u8;
-            genome.bytes[5] = (40 + (i % 8) * 20) as u8;
-            genome.bytes[9] = (60 + (i % 10) * 15) as u8;
-            genome.bytes[10] = 128;
-            let species = genome.species_id();
-            let v = Voxel {
-                voxel_type: VoxelType::Protocell,
-                energy: 500,
-                species_id: species,
-                genome,
-                ..Default::default()
-            };
-            voxel_data.push((x, y, z, v.pack()));
-        }
-
-        // Waste (5 voxels)
-        for i in 0..5u32 {
-            let x = (center + 8 + i).min(gs - 1);
-            let y = (center + 8).min(gs - 1);
-            let v = Voxel {
-                voxel_type: VoxelType::Waste,
-                age: i as u16 * 20,
-                ..Default::default()
-            };
-            voxel_data.push((x, y, center, v.pack()));
-        }
-
-        // Heat source
-        {
-            let x = (center + 10).min(gs - 1);
-            let z = (center + 10).min(gs - 1);
-            let v = Voxel {
-                voxel_type: VoxelType::HeatSource,
-                energy: 1000,
-                ..Default::default()
-            };
-            voxel_data.push((x, center, z, v.pack()));
-        }
-
-        // Cold source
-        {
-            let x = center.saturating_sub(10);
-            let z = (center + 10).min(gs - 1);
-            let v = Voxel {
-                voxel_type: VoxelType::ColdSource,
-                energy: 1000,
-                ..Default::default()
-            };
-            voxel_data.push((x, center, z, v.pack()));
-        }
-
-        // Upload all voxels
-        for (x, y, z, words) in &voxel_data {
-            self.write_voxel(queue, *x, *y, *z, words);
-        }
-
-        self.finalize_seed(queue);
-    }
-
-    fn seed_gradient(&mut self, queue: &wgpu::Queue) {
+    /// Write many voxels to buffer A, one write per run of adjacent cells.
+    fn write_voxels(&mut self, queue: &wgpu::Queue, voxels: &[seed::SeedVoxel]) -> u32 {
         let gs = self.grid_size();
-        let mut voxel_data: Vec<(u32, u32, u32, [u32; 8])> = Vec::new();
-
-        // Heat sources along x=0 face
-        for y in (0..gs).step_by((gs / 8) as usize) {
-            for z in (0..gs).step_by((gs / 8) as usize) {
-                let v = Voxel { voxel_type: VoxelType::HeatSource, energy: 1000, ..Default::default() };
-                voxel_data.push((0, y, z, v.pack()));
+        let mut placed: Vec<(u64, &[u32; 8])> = Vec::with_capacity(voxels.len());
+        let mut unplaced = 0;
+        for (x, y, z, words) in voxels {
+            let (x, y, z) = (*x, *y, *z);
+            if x >= gs || y >= gs || z >= gs {
+                continue;
             }
-        }
-
-        // Cold sources along x=gs-1 face
-        for y in (0..gs).step_by((gs / 8) as usize) {
-            for z in (0..gs).step_by((gs / 8) as usize) {
-                let v = Voxel { voxel_type: VoxelType::ColdSource, energy: 1000, ..Default::default() };
-                voxel_data.push((gs - 1, y, z, v.pack()));
-            }
-        }
-
-        // Scattered nutrients in the middle third
-        let third = gs / 3;
-        for dx in 0..third {
-            for dy in (0..gs).step_by(3) {
-                for dz in (0..gs).step_by(3) {
-                    let x = third + dx;
-                    if x < gs && dy < gs && dz < gs {
-                        let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 200, ..Default::default() };
-                        voxel_data.push((x, dy, dz, v.pack()));
-                    }
+            let slot = match &mut self.mode {
+                SimMode::Dense(_) => Some(types::grid_index(x, y, z, gs) as u32),
+                SimMode::Sparse(s) => {
+                    s.grid.ensure_brick_for_voxel(x, y, z);
+                    s.grid.voxel_pool_index(x, y, z)
                 }
-            }
-        }
-
-        // Energy sources in center strip
-        let center = gs / 2;
-        for y in (0..gs).step_by((gs / 6).max(1) as usize) {
-            for z in (0..gs).step_by((gs / 6).max(1) as usize) {
-                let v = Voxel { voxel_type: VoxelType::EnergySource, energy: 500, ..Default::default() };
-                voxel_data.push((center, y, z, v.pack()));
-            }
-        }
-
-        // Protocells scattered across the grid
-        for i in 0..80u32 {
-            let x = (third + (i * 7) % third).min(gs - 1);
-            let y = ((i * 13) % gs).min(gs - 1);
-            let z = ((i * 17) % gs).min(gs - 1);
-
-            let mut genome = Genome::default();
-            genome.bytes[0] = (100 + (i % 15) * 10) as u8;
-            genome.bytes[1] = (40 + (i % 10) * 8) as u8;
-            genome.bytes[2] = 180;
-            genome.bytes[3] = (20 + i * 2) as u8;
-            genome.bytes[4] = (80 + (i % 8) * 15) as u8;
-            genome.bytes[5] = (60 + (i % 6) * 25) as u8;
-            genome.bytes[9] = (50 + (i % 12) * 15) as u8;
-            genome.bytes[10] = 128;
-            let species = genome.species_id();
-            let v = Voxel {
-                voxel_type: VoxelType::Protocell,
-                energy: 500,
-                species_id: species,
-                genome,
-                ..Default::default()
             };
-            voxel_data.push((x, y, z, v.pack()));
-        }
-
-        for (x, y, z, words) in &voxel_data {
-            self.write_voxel(queue, *x, *y, *z, words);
-        }
-
-        self.finalize_seed(queue);
-    }
-
-    fn seed_arena(&mut self, queue: &wgpu::Queue) {
-        let gs = self.grid_size();
-        let center = gs / 2;
-        let mut voxel_data: Vec<(u32, u32, u32, [u32; 8])> = Vec::new();
-
-        let gap = (gs / 16).max(2);
-        for i in 0..gs {
-            for z in 0..gs {
-                if !(center.saturating_sub(gap)..=center + gap).contains(&i) {
-                    let v = Voxel { voxel_type: VoxelType::Wall, ..Default::default() };
-                    voxel_data.push((center, i, z, v.pack()));
-                }
-                if !(center.saturating_sub(gap)..=center + gap).contains(&i) {
-                    let v = Voxel { voxel_type: VoxelType::Wall, ..Default::default() };
-                    voxel_data.push((i, center, z, v.pack()));
-                }
-            }
-        }
-
-        let q_size = center.saturating_sub(1);
-        for dx in (1..q_size).step_by(2) {
-            for dy in (1..q_size).step_by(2) {
-                for dz in (0..gs).step_by(4) {
-                    let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 300, ..Default::default() };
-                    voxel_data.push((dx, dy, dz, v.pack()));
-                }
-            }
-        }
-
-        for i in 0..4u32 {
-            let x = (center + 2 + i * (q_size / 5)).min(gs - 1);
-            let y = q_size / 2;
-            let v = Voxel { voxel_type: VoxelType::HeatSource, energy: 1000, ..Default::default() };
-            voxel_data.push((x, y, center, v.pack()));
-        }
-        for i in 0..6u32 {
-            let x = (center + 2 + i * (q_size / 7)).min(gs - 1);
-            let y = (1 + i * (q_size / 7)).min(center.saturating_sub(2));
-            let v = Voxel { voxel_type: VoxelType::EnergySource, energy: 500, ..Default::default() };
-            voxel_data.push((x, y, center, v.pack()));
-        }
-
-        for i in 0..4u32 {
-            let x = (1 + i * (q_size / 5)).min(center.saturating_sub(2));
-            let y = (center + 2 + i * (q_size / 5)).min(gs - 1);
-            let v = Voxel { voxel_type: VoxelType::ColdSource, energy: 1000, ..Default::default() };
-            voxel_data.push((x, y, center, v.pack()));
-        }
-        for dx in (1..q_size).step_by(6) {
-            for dy in (center + 2..gs.saturating_sub(1)).step_by(6) {
-                let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 100, ..Default::default() };
-                voxel_data.push((dx, dy, center, v.pack()));
+            match slot {
+                Some(slot) => placed.push((slot as u64 * 32, words)),
+                None => unplaced += 1,
             }
         }
-
-        for i in 0..3u32 {
-            let x = (center + 2 + i * (q_size / 4)).min(gs - 1);
-            let y = (center + 2 + i * (q_size / 4)).min(gs - 1);
-            let v = Voxel { voxel_type: VoxelType::EnergySource, energy: 500, ..Default::default() };
-            voxel_data.push((x, y, center, v.pack()));
+        if unplaced > 0 {
+            self.log.warn(self.tick_count, types::Subsystem::Pool, format!("brick pool full: {unplaced} voxels not placed"));
         }
-        for dx in (center + 2..gs.saturating_sub(1)).step_by(4) {
-            for dy in (center + 2..gs.saturating_sub(1)).step_by(4) {
-                let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 200, ..Default::default() };
-                voxel_data.push((dx, dy, center, v.pack()));
+        // Stable, so the last entry for a cell stays last
+        placed.sort_by_key(|&(offset, _)| offset);
+        placed.dedup_by(|later, earlier| {
+            if later.0 == earlier.0 {
+                earlier.1 = later.1;
+                true
+            } else {
+                false
             }
-        }
+        });
 
-        let quadrant_centers = [
-            (center / 2, center / 2),
-            (center + center / 2, center / 2),
-            (center / 2, center + center / 2),
-            (center + center / 2, center + center / 2),
-        ];
-        for (qi, &(qx, qy)) in quadrant_centers.iter().enumerate() {
-            for i in 0..15u32 {
-                let angle = (i as f32) * 0.42;
-                let radius = 1.0 + (i as f32) * 0.15;
-                let x = ((qx as f32 + angle.cos() * radius).round() as u32).min(gs - 1);
-                let y = ((qy as f32 + angle.sin() * radius).round() as u32).min(gs - 1);
-
-                let mut genome = Genome::default();
-                genome.bytes[0] = 80 + (qi as u8) * 30 + (i as u8) * 5;
-                genome.bytes[1] = 40 + (qi as u8) * 20 + (i as u8) * 3;
-                genome.bytes[2] = 200;
-                genome.bytes[3] = 10 + (qi as u8) * 15;
-                genome.bytes[4] = 60 + (i % 8) as u8 * 15;
-                genome.bytes[5] = 40 + (i % 6) as u8 * 20;
-                genome.bytes[9] = 50 + (qi as u8) * 30;
-                genome.bytes[10] = 128;
-                let species = genome.species_id();
-                let v = Voxel {
-                    voxel_type: VoxelType::Protocell,
-                    energy: 500,
-                    species_id: species,
-                    genome,
-                    ..Default::default()
-                };
-                voxel_data.push((x, y, center, v.pack()));
+        let target = match &self.mode {
+            SimMode::Dense(d) => d.buffers.buffer_a(),
+            SimMode::Sparse(s) => s.buffers.pool_a(),
+        };
+        let mut run: Vec<u32> = Vec::new();
+        let mut run_start = 0;
+        for &(offset, words) in &placed {
+            if offset != run_start + run.len() as u64 * 4 {
+                if !run.is_empty() {
+                    queue.write_buffer(target, run_start, bytemuck::cast_slice(&run));
+                }
+                run.clear();
+                run_start = offset;
             }
+            run.extend_from_slice(words);
         }
-
-        for (x, y, z, words) in &voxel_data {
-            self.write_voxel(queue, *x, *y, *z, words);
+        if !run.is_empty() {
+            queue.write_buffer(target, run_start, bytemuck::cast_slice(&run));
         }
-
-        self.finalize_seed(queue);
+        placed.len() as u32
     }
 
     /// Seed ~30% of voxels as protocells for benchmarking. Returns count placed.
@@ -826,6 +1185,7 @@ impl SimEngine {
         }
 
         self.init_temperature(queue);
+        self.clear_pheromones(queue);
         self.reset_tick_count();
         self.params_uniform.upload(queue, &self.params);
         count
@@ -833,18 +1193,51 @@ impl SimEngine {
 
     /// Common finalization after any seeding method.
     fn finalize_seed(&mut self, queue: &wgpu::Queue) {
+        self.region.invalidate();
         // For sparse mode, allocate border bricks and upload table
+        self.alloc_border_bricks();
         if let SimMode::Sparse(s) = &mut self.mode {
-            s.grid.proactive_border_alloc();
             s.grid.upload_if_dirty(queue);
+            // Seeding zeroed and wrote pool A and fills temp pool A below;
+            // the first tick writes both B pools
+            s.grid.take_fresh_slots();
         }
         self.init_temperature(queue);
+        self.clear_pheromones(queue);
         self.params_uniform.upload(queue, &self.params);
     }
 
+    /// Allocate the face neighbors of every allocated brick (sparse only),
+    /// logging when the pool runs out and when it has room again.
+    fn alloc_border_bricks(&mut self) {
+        let SimMode::Sparse(s) = &mut self.mode else {
+            return;
+        };
+        let missing = s.grid.proactive_border_alloc();
+        if (missing > 0) != self.pool_exhausted {
+            let (tick, max_bricks) = (self.tick_count, s.grid.max_bricks());
+            if missing > 0 {
+                self.log.warn(tick, types::Subsystem::Pool, format!("brick pool exhausted ({max_bricks} bricks): {missing} border bricks not allocated"));
+            } else {
+                self.log.info(tick, types::Subsystem::Pool, "brick pool has room for every border brick again");
+            }
+            self.pool_exhausted = missing > 0;
+        }
+    }
+
+    /// Zero the pheromone field both parities read from on the next ticks.
+    fn clear_pheromones(&self, queue: &wgpu::Queue) {
+        let (a, b) = match &self.mode {
+            SimMode::Dense(d) => (d.buffers.pheromone_buffer_a(), d.buffers.pheromone_buffer_b()),
+            SimMode::Sparse(s) => (s.buffers.pheromone_buffer_a(), s.buffers.pheromone_buffer_b()),
+        };
+        let zeros = vec![0u8; a.size() as usize];
+        queue.write_buffer(a, 0, &zeros);
+        queue.write_buffer(b, 0, &zeros);
+    }
+
     fn init_temperature(&self, queue: &wgpu::Queue) {
-        let ambient = 0.5f32;
-        let ambient_bytes = ambient.to_le_bytes();
+        let ambient_bytes = AMBIENT_TEMPERATURE.to_le_bytes();
         match &self.mode {
             SimMode::Dense(d) => {
                 let gs = d.buffers.grid_size();
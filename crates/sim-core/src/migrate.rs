@@ -0,0 +1,238 @@
+//! GPU world migration between dense buffers and a sparse brick pool, plus
+//! the per-brick occupancy count a dense → sparse conversion is planned from.
+
+use types::{load_wgsl, ShaderDefs, SPARSE_DEFINE};
+
+/// Temperature given to cells of unallocated bricks, as in `init_temperature`.
+const AMBIENT_TEMP: f32 = 0.5;
+
+pub struct MigratePass {
+    occupancy_pipeline: wgpu::ComputePipeline,
+    to_sparse_pipeline: wgpu::ComputePipeline,
+    to_dense_pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buf: wgpu::Buffer,
+    occupancy_buf: wgpu::Buffer,
+    staging_buf: wgpu::Buffer,
+    /// Bound at bindings 1, 2, 3 and 10 by the occupancy pass, which only
+    /// reads dense voxels. Separate buffers so writable bindings never alias.
+    placeholders: [wgpu::Buffer; 4],
+    grid_size: u32,
+}
+
+fn storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn brick_count(grid_size: u32) -> u64 {
+    ((grid_size / 8) as u64).pow(3)
+}
+
+impl MigratePass {
+    pub fn new(device: &wgpu::Device, grid_size: u32) -> Self {
+        // Shared by both modes, so always compiled against a brick table
+        let source = load_wgsl("migrate.wgsl", &ShaderDefs::new().define(SPARSE_DEFINE));
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("migrate"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("migrate_bgl"),
+            entries: &[
+                storage_entry(0, false),
+                storage_entry(1, false),
+                storage_entry(2, false),
+                storage_entry(3, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(5, false),
+                storage_entry(10, true),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("migrate_pl"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |label: &'static str, entry_point: &'static str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let occupancy_bytes = brick_count(grid_size) * 4;
+        let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("migrate_uniform"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let occupancy_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("migrate_occupancy"),
+            size: occupancy_bytes,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("migrate_occupancy_staging"),
+            size: occupancy_bytes,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let placeholders = std::array::from_fn(|_| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("migrate_placeholder"),
+                size: 4,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })
+        });
+
+        Self {
+            occupancy_pipeline: pipeline("migrate_occupancy_pipeline", "brick_occupancy_main"),
+            to_sparse_pipeline: pipeline("migrate_to_sparse_pipeline", "dense_to_sparse_main"),
+            to_dense_pipeline: pipeline("migrate_to_dense_pipeline", "sparse_to_dense_main"),
+            bind_group_layout,
+            uniform_buf,
+            occupancy_buf,
+            staging_buf,
+            placeholders,
+            grid_size,
+        }
+    }
+
+    pub fn grid_size(&self) -> u32 {
+        self.grid_size
+    }
+
+    /// Count non-empty voxels of a dense world per 8³ brick and copy the
+    /// counts into staging. Parse the mapped staging buffer with
+    /// `parse_occupancy` and pass it to `SimEngine::convert_to_sparse`.
+    pub fn encode_occupancy(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        dense_voxels: &wgpu::Buffer,
+    ) {
+        let [temps, pool, pool_temps, brick_table] = &self.placeholders;
+        let bind_group = self.bind_group(device, queue, [dense_voxels, temps, pool, pool_temps], brick_table);
+        encoder.clear_buffer(&self.occupancy_buf, 0, None);
+        self.dispatch(encoder, &self.occupancy_pipeline, &bind_group, "migrate_occupancy_pass");
+        encoder.copy_buffer_to_buffer(&self.occupancy_buf, 0, &self.staging_buf, 0, self.occupancy_buf.size());
+    }
+
+    pub fn staging_buffer(&self) -> &wgpu::Buffer {
+        &self.staging_buf
+    }
+
+    /// Per-brick non-empty voxel counts, indexed like the brick table.
+    pub fn parse_occupancy(bytes: &[u8]) -> Vec<u32> {
+        bytes.chunks_exact(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+    }
+
+    /// Copy dense voxels and temperatures into the pool slots of allocated bricks.
+    pub(crate) fn encode_dense_to_sparse(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        dense: [&wgpu::Buffer; 2],
+        pool: [&wgpu::Buffer; 2],
+        brick_table: &wgpu::Buffer,
+    ) {
+        let bind_group = self.bind_group(device, queue, [dense[0], dense[1], pool[0], pool[1]], brick_table);
+        self.dispatch(encoder, &self.to_sparse_pipeline, &bind_group, "migrate_to_sparse_pass");
+    }
+
+    /// Fill dense voxels and temperatures from the pool; cells of
+    /// unallocated bricks become empty at ambient temperature.
+    pub(crate) fn encode_sparse_to_dense(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        dense: [&wgpu::Buffer; 2],
+        pool: [&wgpu::Buffer; 2],
+        brick_table: &wgpu::Buffer,
+    ) {
+        let bind_group = self.bind_group(device, queue, [dense[0], dense[1], pool[0], pool[1]], brick_table);
+        self.dispatch(encoder, &self.to_dense_pipeline, &bind_group, "migrate_to_dense_pass");
+    }
+
+    fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffers: [&wgpu::Buffer; 4],
+        brick_table: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        let words = [self.grid_size, self.grid_size / 8, AMBIENT_TEMP.to_bits(), 0];
+        queue.write_buffer(&self.uniform_buf, 0, bytemuck::cast_slice(&words));
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("migrate_bg"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: buffers[0].as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: buffers[1].as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: buffers[2].as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: buffers[3].as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: self.uniform_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: self.occupancy_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 10, resource: brick_table.as_entire_binding() },
+            ],
+        })
+    }
+
+    fn dispatch(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        bind_group: &wgpu::BindGroup,
+        label: &'static str,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(label),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        let groups = self.grid_size.div_ceil(types::WORKGROUP_SIZE);
+        pass.dispatch_workgroups(groups, groups, groups);
+    }
+
+    /// (label, bytes) for every GPU buffer the migration pass owns.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("migrate_uniform", self.uniform_buf.size()),
+            ("migrate_occupancy", self.occupancy_buf.size()),
+            ("migrate_occupancy_staging", self.staging_buf.size()),
+            ("migrate_placeholders", self.placeholders.iter().map(|b| b.size()).sum()),
+        ]
+    }
+}
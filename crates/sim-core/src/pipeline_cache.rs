@@ -0,0 +1,55 @@
+//! Optional driver pipeline cache.
+//!
+//! Only backends exposing `Features::PIPELINE_CACHE` (currently native Vulkan)
+//! support this; on WebGPU every function here degrades to "no cache" and
+//! pipelines compile as before. Cache blobs are keyed by
+//! `wgpu::util::pipeline_cache_key` so data from a different adapter or driver
+//! is never fed back in.
+
+use std::path::{Path, PathBuf};
+
+/// Create a pipeline cache, seeded from previously saved `data` if given.
+/// Returns `None` if the device lacks `Features::PIPELINE_CACHE`.
+pub fn create(device: &wgpu::Device, data: Option<&[u8]>) -> Option<wgpu::PipelineCache> {
+    if !device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+        return None;
+    }
+    // SAFETY: `data` only ever comes from `load`, which reads blobs written by
+    // `save` from `PipelineCache::get_data` under this adapter's cache key.
+    // `fallback: true` makes wgpu discard data it can't use.
+    let cache = unsafe {
+        device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+            label: Some("primordium_pipeline_cache"),
+            data,
+            fallback: true,
+        })
+    };
+    Some(cache)
+}
+
+/// File under `dir` holding the cache blob for this adapter, if the backend
+/// supports pipeline caching at all.
+pub fn cache_path(dir: &Path, adapter_info: &wgpu::AdapterInfo) -> Option<PathBuf> {
+    wgpu::util::pipeline_cache_key(adapter_info).map(|key| dir.join(key))
+}
+
+/// Read a previously saved cache blob. Missing or unreadable files yield `None`.
+pub fn load(dir: &Path, adapter_info: &wgpu::AdapterInfo) -> Option<Vec<u8>> {
+    let path = cache_path(dir, adapter_info)?;
+    std::fs::read(path).ok()
+}
+
+/// Persist the cache's current contents. Writes to a temp file and renames so
+/// a crash mid-write can't leave a truncated blob behind.
+pub fn save(dir: &Path, adapter_info: &wgpu::AdapterInfo, cache: &wgpu::PipelineCache) -> Result<(), String> {
+    let Some(path) = cache_path(dir, adapter_info) else {
+        return Ok(());
+    };
+    let Some(data) = cache.get_data() else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, &data).map_err(|e| format!("write {}: {e}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("rename {}: {e}", path.display()))
+}
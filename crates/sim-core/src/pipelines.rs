@@ -1,12 +1,17 @@
 use wgpu;
+use types::{load_wgsl, Feature, FeatureSet, ShaderDefs, SPARSE_DEFINE};
 
-const COMMON_WGSL: &str = include_str!("../../../shaders/common.wgsl");
-const BRICK_COMMON_WGSL: &str = include_str!("../../../shaders/brick_common.wgsl");
-const INTENT_DECLARATION_WGSL: &str = include_str!("../../../shaders/intent_declaration.wgsl");
-const RESOLVE_EXECUTE_WGSL: &str = include_str!("../../../shaders/resolve_execute.wgsl");
-const APPLY_COMMANDS_WGSL: &str = include_str!("../../../shaders/apply_commands.wgsl");
-const TEMPERATURE_DIFFUSION_WGSL: &str = include_str!("../../../shaders/temperature_diffusion.wgsl");
-const STATS_REDUCTION_WGSL: &str = include_str!("../../../shaders/stats_reduction.wgsl");
+/// Shader files the sim pipelines are compiled from, includes included.
+pub const SIM_SHADER_FILES: &[&str] = &[
+    "common.wgsl",
+    "brick_common.wgsl",
+    "intent_declaration.wgsl",
+    "resolve_execute.wgsl",
+    "apply_commands.wgsl",
+    "temperature_diffusion.wgsl",
+    "stats_reduction.wgsl",
+    "field_stats.wgsl",
+];
 
 pub struct SimPipelines {
     pub intent_declaration: wgpu::ComputePipeline,
@@ -16,15 +21,26 @@ pub struct SimPipelines {
     pub apply_commands: wgpu::ComputePipeline,
     pub apply_commands_bgl: wgpu::BindGroupLayout,
     pub temperature_diffusion: wgpu::ComputePipeline,
+    /// Diffusion sub-steps after the first (temperature only).
+    pub temperature_substep: wgpu::ComputePipeline,
     pub temperature_diffusion_bgl: wgpu::BindGroupLayout,
     pub stats_reduction: wgpu::ComputePipeline,
+    /// Folds the reduction's partials into the stats buffer; same layout.
+    pub stats_finalize: wgpu::ComputePipeline,
     pub stats_reduction_bgl: wgpu::BindGroupLayout,
+    pub field_stats: wgpu::ComputePipeline,
+    pub field_stats_bgl: wgpu::BindGroupLayout,
+    /// Optional subsystems the pipelines were compiled with.
+    pub features: FeatureSet,
 }
 
 impl SimPipelines {
-    pub fn new(device: &wgpu::Device) -> Self {
+    /// `cache` is an optional driver pipeline cache (see `pipeline_cache`);
+    /// `features` selects the optional subsystems compiled in.
+    pub fn new(device: &wgpu::Device, cache: Option<&wgpu::PipelineCache>, features: FeatureSet) -> Self {
+        let defs = ShaderDefs::new().define_all(features.defines());
         // ---- Intent declaration pipeline ----
-        let intent_source = format!("{}\n{}", COMMON_WGSL, INTENT_DECLARATION_WGSL);
+        let intent_source = load_wgsl("intent_declaration.wgsl", &defs);
         let intent_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("intent_declaration"),
             source: wgpu::ShaderSource::Wgsl(intent_source.into()),
@@ -33,7 +49,7 @@ impl SimPipelines {
         let intent_declaration_bgl =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("intent_declaration_bgl"),
-                entries: &[
+                entries: &gated_layout(GatedPass::Intent, features, &[
                     // binding 0: voxel read buffer (read-only storage)
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
@@ -78,7 +94,22 @@ impl SimPipelines {
                         },
                         count: None,
                     },
-                ],
+                    // binding 4: pheromone field (read-only storage)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // binding 11: dispatch region (uniform, dynamic offset)
+                    region_bgl_entry(),
+                    // binding 14: tick params (uniform, dynamic offset)
+                    tick_bgl_entry(),
+                ]),
             });
 
         let intent_pl = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -94,11 +125,11 @@ impl SimPipelines {
                 module: &intent_shader,
                 entry_point: Some("intent_declaration_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
         // ---- Resolve execute pipeline ----
-        let resolve_source = format!("{}\n{}", COMMON_WGSL, RESOLVE_EXECUTE_WGSL);
+        let resolve_source = load_wgsl("resolve_execute.wgsl", &defs);
         let resolve_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("resolve_execute"),
             source: wgpu::ShaderSource::Wgsl(resolve_source.into()),
@@ -107,7 +138,7 @@ impl SimPipelines {
         let resolve_execute_bgl =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("resolve_execute_bgl"),
-                entries: &[
+                entries: &gated_layout(GatedPass::Resolve, features, &[
                     // binding 0: voxel read buffer (read-only storage)
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
@@ -163,7 +194,26 @@ impl SimPipelines {
                         },
                         count: None,
                     },
-                ],
+                    // binding 5: pheromone/toxin field, post-update (read-only storage)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // binding 11: dispatch region (uniform, dynamic offset)
+                    region_bgl_entry(),
+                    // binding 12: energy-flow counters (read_write storage)
+                    energy_flow_bgl_entry(),
+                    // binding 13: rule params (uniform)
+                    rules_bgl_entry(),
+                    // binding 14: tick params (uniform, dynamic offset)
+                    tick_bgl_entry(),
+                ]),
             });
 
         let resolve_pl = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -179,11 +229,11 @@ impl SimPipelines {
                 module: &resolve_shader,
                 entry_point: Some("resolve_execute_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
         // ---- Apply commands pipeline ----
-        let apply_source = format!("{}\n{}", COMMON_WGSL, APPLY_COMMANDS_WGSL);
+        let apply_source = load_wgsl("apply_commands.wgsl", &defs);
         let apply_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("apply_commands"),
             source: wgpu::ShaderSource::Wgsl(apply_source.into()),
@@ -226,6 +276,8 @@ impl SimPipelines {
                         },
                         count: None,
                     },
+                    // binding 14: tick params (uniform, dynamic offset)
+                    tick_bgl_entry(),
                 ],
             });
 
@@ -242,11 +294,11 @@ impl SimPipelines {
                 module: &apply_shader,
                 entry_point: Some("apply_commands_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
         // ---- Temperature diffusion pipeline ----
-        let temp_source = format!("{}\n{}", COMMON_WGSL, TEMPERATURE_DIFFUSION_WGSL);
+        let temp_source = load_wgsl("temperature_diffusion.wgsl", &defs);
         let temp_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("temperature_diffusion"),
             source: wgpu::ShaderSource::Wgsl(temp_source.into()),
@@ -255,7 +307,7 @@ impl SimPipelines {
         let temperature_diffusion_bgl =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("temperature_diffusion_bgl"),
-                entries: &[
+                entries: &gated_layout(GatedPass::TemperatureDiffusion, features, &[
                     // binding 0: temp read buffer (read-only storage)
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
@@ -300,7 +352,29 @@ impl SimPipelines {
                         },
                         count: None,
                     },
-                ],
+                    // binding 4: pheromone read (read-only storage)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // binding 5: pheromone write (read_write storage)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ]),
             });
 
         let temp_pl = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -316,11 +390,21 @@ impl SimPipelines {
                 module: &temp_shader,
                 entry_point: Some("temperature_diffusion_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
+            });
+
+        let temperature_substep =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("temperature_substep_pipeline"),
+                layout: Some(&temp_pl),
+                module: &temp_shader,
+                entry_point: Some("temperature_substep_main"),
+                compilation_options: Default::default(),
+                cache,
             });
 
         // ---- Stats reduction pipeline ----
-        let stats_source = format!("{}\n{}", COMMON_WGSL, STATS_REDUCTION_WGSL);
+        let stats_source = load_wgsl("stats_reduction.wgsl", &defs);
         let stats_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("stats_reduction"),
             source: wgpu::ShaderSource::Wgsl(stats_source.into()),
@@ -363,6 +447,28 @@ impl SimPipelines {
                         },
                         count: None,
                     },
+                    // binding 3: the tick's input voxel buffer (read-only storage)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // binding 4: per-workgroup partials (read_write storage)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
@@ -379,9 +485,22 @@ impl SimPipelines {
                 module: &stats_shader,
                 entry_point: Some("stats_reduction_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
+        let stats_finalize =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("stats_finalize_pipeline"),
+                layout: Some(&stats_pl),
+                module: &stats_shader,
+                entry_point: Some("stats_finalize_main"),
+                compilation_options: Default::default(),
+                cache,
+            });
+
+        // ---- Field stats pipeline ----
+        let (field_stats, field_stats_bgl) = field_stats_pipeline(device, cache, features, "field_stats");
+
         Self {
             intent_declaration,
             intent_declaration_bgl,
@@ -390,13 +509,129 @@ impl SimPipelines {
             apply_commands,
             apply_commands_bgl,
             temperature_diffusion,
+            temperature_substep,
             temperature_diffusion_bgl,
             stats_reduction,
+            stats_finalize,
             stats_reduction_bgl,
+            field_stats,
+            field_stats_bgl,
+            features,
         }
     }
 }
 
+/// Field stats pipeline, shared by both modes: always compiled with SPARSE,
+/// so dense mode binds a placeholder brick table.
+fn field_stats_pipeline(
+    device: &wgpu::Device,
+    cache: Option<&wgpu::PipelineCache>,
+    features: FeatureSet,
+    label: &str,
+) -> (wgpu::ComputePipeline, wgpu::BindGroupLayout) {
+    let source = load_wgsl(
+        "field_stats.wgsl",
+        &ShaderDefs::new().define(SPARSE_DEFINE).define_all(features.defines()),
+    );
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(&format!("{label}_bgl")),
+        entries: &gated_layout(GatedPass::FieldStats, features, &[
+            // binding 0: the tick's output voxels
+            storage(0, true),
+            // binding 1: stats buffer
+            storage(1, false),
+            // binding 2: sim params uniform
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // bindings 3-4: the tick's output temperature and pheromones
+            storage(3, true),
+            storage(4, true),
+            brick_table_bgl_entry(),
+        ]),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{label}_pl")),
+        bind_group_layouts: &[&bgl],
+        push_constant_ranges: &[],
+    });
+
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(&format!("{label}_pipeline")),
+        layout: Some(&layout),
+        module: &shader,
+        entry_point: Some("field_stats_main"),
+        compilation_options: Default::default(),
+        cache,
+    });
+    (pipeline, bgl)
+}
+
+/// Passes whose bindings depend on the enabled features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GatedPass {
+    Intent,
+    Resolve,
+    TemperatureDiffusion,
+    FieldStats,
+}
+
+/// Whether `binding` of `pass` survives preprocessing with `features`;
+/// mirrors the #ifdef blocks around the shaders' declarations.
+pub(crate) fn has_binding(pass: GatedPass, binding: u32, features: FeatureSet) -> bool {
+    match (pass, binding) {
+        (GatedPass::Intent, 3)
+        | (GatedPass::Resolve, 4)
+        | (GatedPass::TemperatureDiffusion, 0 | 1)
+        | (GatedPass::FieldStats, 3) => features.contains(Feature::Temperature),
+        (GatedPass::Intent, 4) | (GatedPass::FieldStats, 4) => features.contains(Feature::Signals),
+        (GatedPass::Resolve, 5) => features.contains(Feature::ToxinField),
+        (GatedPass::TemperatureDiffusion, 4 | 5) => features.coarse_field(),
+        _ => true,
+    }
+}
+
+/// Layout `entries` of `pass` without the bindings `features` strip.
+fn gated_layout(
+    pass: GatedPass,
+    features: FeatureSet,
+    entries: &[wgpu::BindGroupLayoutEntry],
+) -> Vec<wgpu::BindGroupLayoutEntry> {
+    entries.iter().filter(|e| has_binding(pass, e.binding, features)).cloned().collect()
+}
+
+/// Bind group `entries` of `pass` without the bindings `features` strip.
+pub(crate) fn gated_entries<'a>(
+    pass: GatedPass,
+    features: FeatureSet,
+    entries: Vec<wgpu::BindGroupEntry<'a>>,
+) -> Vec<wgpu::BindGroupEntry<'a>> {
+    entries.into_iter().filter(|e| has_binding(pass, e.binding, features)).collect()
+}
+
 /// Brick table BGL entry for binding 10 (read-only storage).
 fn brick_table_bgl_entry() -> wgpu::BindGroupLayoutEntry {
     wgpu::BindGroupLayoutEntry {
@@ -411,8 +646,66 @@ fn brick_table_bgl_entry() -> wgpu::BindGroupLayoutEntry {
     }
 }
 
-/// Sparse pipelines — same 5 compute shaders but compiled with brick_common.wgsl
-/// prefix and binding 10 for brick_table.
+/// Dispatch region BGL entry for binding 11 (uniform, selected per pass by
+/// dynamic offset; see `region`).
+fn region_bgl_entry() -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 11,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: wgpu::BufferSize::new(32),
+        },
+        count: None,
+    }
+}
+
+/// Energy-flow counter BGL entry for binding 12 of the resolve pass.
+fn energy_flow_bgl_entry() -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 12,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// Rule-params uniform BGL entry for binding 13 of the resolve pass.
+fn rules_bgl_entry() -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 13,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: wgpu::BufferSize::new(crate::uniform::RULES_BYTES),
+        },
+        count: None,
+    }
+}
+
+/// Tick-params BGL entry for binding 14 (uniform, selected per tick by
+/// dynamic offset; see `uniform::ParamsUniform::write_tick`).
+fn tick_bgl_entry() -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 14,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: wgpu::BufferSize::new(crate::uniform::TICK_PARAMS_BYTES),
+        },
+        count: None,
+    }
+}
+
+/// Sparse pipelines — same 5 compute shaders but compiled with SPARSE, which
+/// binds brick_table at binding 10.
 pub struct SparsePipelines {
     pub intent_declaration: wgpu::ComputePipeline,
     pub intent_declaration_bgl: wgpu::BindGroupLayout,
@@ -421,15 +714,26 @@ pub struct SparsePipelines {
     pub apply_commands: wgpu::ComputePipeline,
     pub apply_commands_bgl: wgpu::BindGroupLayout,
     pub temperature_diffusion: wgpu::ComputePipeline,
+    /// Diffusion sub-steps after the first (temperature only).
+    pub temperature_substep: wgpu::ComputePipeline,
     pub temperature_diffusion_bgl: wgpu::BindGroupLayout,
     pub stats_reduction: wgpu::ComputePipeline,
+    /// Folds the reduction's partials into the stats buffer; same layout.
+    pub stats_finalize: wgpu::ComputePipeline,
     pub stats_reduction_bgl: wgpu::BindGroupLayout,
+    pub field_stats: wgpu::ComputePipeline,
+    pub field_stats_bgl: wgpu::BindGroupLayout,
+    /// Optional subsystems the pipelines were compiled with.
+    pub features: FeatureSet,
 }
 
 impl SparsePipelines {
-    pub fn new(device: &wgpu::Device) -> Self {
+    /// `cache` is an optional driver pipeline cache (see `pipeline_cache`);
+    /// `features` selects the optional subsystems compiled in.
+    pub fn new(device: &wgpu::Device, cache: Option<&wgpu::PipelineCache>, features: FeatureSet) -> Self {
+        let defs = ShaderDefs::new().define(SPARSE_DEFINE).define_all(features.defines());
         // ---- Intent declaration pipeline (sparse) ----
-        let intent_source = format!("{}\n{}\n{}", COMMON_WGSL, BRICK_COMMON_WGSL, INTENT_DECLARATION_WGSL);
+        let intent_source = load_wgsl("intent_declaration.wgsl", &defs);
         let intent_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("sparse_intent_declaration"),
             source: wgpu::ShaderSource::Wgsl(intent_source.into()),
@@ -438,7 +742,7 @@ impl SparsePipelines {
         let intent_declaration_bgl =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("sparse_intent_declaration_bgl"),
-                entries: &[
+                entries: &gated_layout(GatedPass::Intent, features, &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
                         visibility: wgpu::ShaderStages::COMPUTE,
@@ -479,8 +783,20 @@ impl SparsePipelines {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    region_bgl_entry(),
                     brick_table_bgl_entry(),
-                ],
+                    tick_bgl_entry(),
+                ]),
             });
 
         let intent_pl = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -496,11 +812,11 @@ impl SparsePipelines {
                 module: &intent_shader,
                 entry_point: Some("intent_declaration_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
         // ---- Resolve execute pipeline (sparse) ----
-        let resolve_source = format!("{}\n{}\n{}", COMMON_WGSL, BRICK_COMMON_WGSL, RESOLVE_EXECUTE_WGSL);
+        let resolve_source = load_wgsl("resolve_execute.wgsl", &defs);
         let resolve_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("sparse_resolve_execute"),
             source: wgpu::ShaderSource::Wgsl(resolve_source.into()),
@@ -509,7 +825,7 @@ impl SparsePipelines {
         let resolve_execute_bgl =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("sparse_resolve_execute_bgl"),
-                entries: &[
+                entries: &gated_layout(GatedPass::Resolve, features, &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
                         visibility: wgpu::ShaderStages::COMPUTE,
@@ -560,8 +876,22 @@ impl SparsePipelines {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    region_bgl_entry(),
+                    energy_flow_bgl_entry(),
+                    rules_bgl_entry(),
                     brick_table_bgl_entry(),
-                ],
+                    tick_bgl_entry(),
+                ]),
             });
 
         let resolve_pl = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -577,11 +907,11 @@ impl SparsePipelines {
                 module: &resolve_shader,
                 entry_point: Some("resolve_execute_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
         // ---- Apply commands pipeline (sparse) ----
-        let apply_source = format!("{}\n{}\n{}", COMMON_WGSL, BRICK_COMMON_WGSL, APPLY_COMMANDS_WGSL);
+        let apply_source = load_wgsl("apply_commands.wgsl", &defs);
         let apply_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("sparse_apply_commands"),
             source: wgpu::ShaderSource::Wgsl(apply_source.into()),
@@ -622,6 +952,7 @@ impl SparsePipelines {
                         count: None,
                     },
                     brick_table_bgl_entry(),
+                    tick_bgl_entry(),
                 ],
             });
 
@@ -638,11 +969,11 @@ impl SparsePipelines {
                 module: &apply_shader,
                 entry_point: Some("apply_commands_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
         // ---- Temperature diffusion pipeline (sparse) ----
-        let temp_source = format!("{}\n{}\n{}", COMMON_WGSL, BRICK_COMMON_WGSL, TEMPERATURE_DIFFUSION_WGSL);
+        let temp_source = load_wgsl("temperature_diffusion.wgsl", &defs);
         let temp_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("sparse_temperature_diffusion"),
             source: wgpu::ShaderSource::Wgsl(temp_source.into()),
@@ -651,7 +982,7 @@ impl SparsePipelines {
         let temperature_diffusion_bgl =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("sparse_temperature_diffusion_bgl"),
-                entries: &[
+                entries: &gated_layout(GatedPass::TemperatureDiffusion, features, &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
                         visibility: wgpu::ShaderStages::COMPUTE,
@@ -692,8 +1023,28 @@ impl SparsePipelines {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                     brick_table_bgl_entry(),
-                ],
+                ]),
             });
 
         let temp_pl = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -709,11 +1060,21 @@ impl SparsePipelines {
                 module: &temp_shader,
                 entry_point: Some("temperature_diffusion_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
+            });
+
+        let temperature_substep =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("sparse_temperature_substep_pipeline"),
+                layout: Some(&temp_pl),
+                module: &temp_shader,
+                entry_point: Some("temperature_substep_main"),
+                compilation_options: Default::default(),
+                cache,
             });
 
         // ---- Stats reduction pipeline (sparse) ----
-        let stats_source = format!("{}\n{}\n{}", COMMON_WGSL, BRICK_COMMON_WGSL, STATS_REDUCTION_WGSL);
+        let stats_source = load_wgsl("stats_reduction.wgsl", &defs);
         let stats_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("sparse_stats_reduction"),
             source: wgpu::ShaderSource::Wgsl(stats_source.into()),
@@ -753,6 +1114,26 @@ impl SparsePipelines {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                     brick_table_bgl_entry(),
                 ],
             });
@@ -770,9 +1151,22 @@ impl SparsePipelines {
                 module: &stats_shader,
                 entry_point: Some("stats_reduction_main"),
                 compilation_options: Default::default(),
-                cache: None,
+                cache,
             });
 
+        let stats_finalize =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("sparse_stats_finalize_pipeline"),
+                layout: Some(&stats_pl),
+                module: &stats_shader,
+                entry_point: Some("stats_finalize_main"),
+                compilation_options: Default::default(),
+                cache,
+            });
+
+        // ---- Field stats pipeline (sparse) ----
+        let (field_stats, field_stats_bgl) = field_stats_pipeline(device, cache, features, "sparse_field_stats");
+
         Self {
             intent_declaration,
             intent_declaration_bgl,
@@ -781,9 +1175,14 @@ impl SparsePipelines {
             apply_commands,
             apply_commands_bgl,
             temperature_diffusion,
+            temperature_substep,
             temperature_diffusion_bgl,
             stats_reduction,
+            stats_finalize,
             stats_reduction_bgl,
+            field_stats,
+            field_stats_bgl,
+            features,
         }
     }
 }
@@ -0,0 +1,170 @@
+//! Optional GPU timestamp profiling of one tick's compute passes. Needs
+//! `wgpu::Features::TIMESTAMP_QUERY`; the host arms a capture, the next tick
+//! writes a begin/end timestamp pair per pass group and resolves them into
+//! a staging buffer, and the mapped bytes parse into `PassTimings`.
+
+/// Pass groups timed per tick, in query order. Temperature spans diffusion
+/// and its sub-steps; stats spans the reduction and field-stats passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfilePass {
+    Commands,
+    Temperature,
+    Intent,
+    Resolve,
+    Stats,
+}
+
+impl ProfilePass {
+    pub const ALL: [ProfilePass; 5] = [
+        ProfilePass::Commands,
+        ProfilePass::Temperature,
+        ProfilePass::Intent,
+        ProfilePass::Resolve,
+        ProfilePass::Stats,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProfilePass::Commands => "apply_commands",
+            ProfilePass::Temperature => "temperature_diffusion",
+            ProfilePass::Intent => "intent_declaration",
+            ProfilePass::Resolve => "resolve_execute",
+            ProfilePass::Stats => "stats_reduction",
+        }
+    }
+
+    fn begin_index(self) -> u32 {
+        self as u32 * 2
+    }
+}
+
+/// Timestamps in the query set: a begin and an end per pass group.
+const QUERY_COUNT: u32 = ProfilePass::ALL.len() as u32 * 2;
+/// Size of the resolve buffer and its staging copy.
+pub const PROFILE_BYTES: u64 = QUERY_COUNT as u64 * 8;
+
+/// GPU milliseconds per pass group of the profiled tick; `None` for groups
+/// that did not run (no commands, fields off, stats skipped).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PassTimings {
+    pub tick: u32,
+    pub ms: [Option<f32>; 5],
+}
+
+impl PassTimings {
+    pub fn get(&self, pass: ProfilePass) -> Option<f32> {
+        self.ms[pass as usize]
+    }
+
+    /// Sum over the groups that ran.
+    pub fn total_ms(&self) -> f32 {
+        self.ms.iter().flatten().sum()
+    }
+}
+
+/// Which passes of the tick being encoded get timestamp writes.
+pub(crate) struct PassTimer<'a> {
+    query_set: Option<&'a wgpu::QuerySet>,
+    /// Bit per `ProfilePass` whose end timestamp was written.
+    pub timed: u32,
+}
+
+impl<'a> PassTimer<'a> {
+    pub fn new(query_set: Option<&'a wgpu::QuerySet>) -> Self {
+        Self { query_set, timed: 0 }
+    }
+
+    /// Timestamp writes for a compute pass of `pass`: `begin` on its first
+    /// pass, `end` on its last (both for a single pass).
+    pub fn writes(&mut self, pass: ProfilePass, begin: bool, end: bool) -> Option<wgpu::ComputePassTimestampWrites<'a>> {
+        let query_set = self.query_set?;
+        if end {
+            self.timed |= 1 << pass as u32;
+        }
+        Some(wgpu::ComputePassTimestampWrites {
+            query_set,
+            beginning_of_pass_write_index: begin.then(|| pass.begin_index()),
+            end_of_pass_write_index: end.then(|| pass.begin_index() + 1),
+        })
+    }
+}
+
+pub struct GpuProfiler {
+    query_set: wgpu::QuerySet,
+    resolve_buf: wgpu::Buffer,
+    staging_buf: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick (`Queue::get_timestamp_period`).
+    period_ns: f32,
+    /// Time the next tick.
+    armed: bool,
+    /// Tick number and `PassTimer::timed` of the capture in the staging
+    /// buffer, until the host takes it.
+    captured: Option<(u32, u32)>,
+    /// Same, for the capture the host is reading back.
+    reading: Option<(u32, u32)>,
+}
+
+impl GpuProfiler {
+    /// None when the device lacks `TIMESTAMP_QUERY`.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("tick_profile_queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let buffer = |label, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor { label: Some(label), size: PROFILE_BYTES, usage, mapped_at_creation: false })
+        };
+        Some(Self {
+            query_set,
+            resolve_buf: buffer("tick_profile_resolve", wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC),
+            staging_buf: buffer("tick_profile_staging", wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ),
+            period_ns: queue.get_timestamp_period(),
+            armed: false,
+            captured: None,
+            reading: None,
+        })
+    }
+
+    pub fn arm(&mut self) {
+        self.armed = true;
+    }
+
+    /// The query set, if this tick is to be timed.
+    pub(crate) fn query_set_for_tick(&self) -> Option<&wgpu::QuerySet> {
+        self.armed.then_some(&self.query_set)
+    }
+
+    /// Resolve the tick's timestamps into the staging buffer and disarm.
+    pub(crate) fn finish_tick(&mut self, encoder: &mut wgpu::CommandEncoder, tick: u32, timed: u32) {
+        encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buf, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buf, 0, &self.staging_buf, 0, PROFILE_BYTES);
+        self.armed = false;
+        self.captured = Some((tick, timed));
+    }
+
+    /// The staging buffer of a capture encoded since the last call, to map.
+    pub fn take_capture(&mut self) -> Option<&wgpu::Buffer> {
+        self.reading = Some(self.captured.take()?);
+        Some(&self.staging_buf)
+    }
+
+    /// Parse the mapped staging bytes of the capture `take_capture` gave out.
+    pub fn read(&self, bytes: &[u8]) -> Option<PassTimings> {
+        let (tick, timed) = self.reading?;
+        let stamps: Vec<u64> = bytes.chunks_exact(8).map(|c| u64::from_le_bytes(c.try_into().unwrap_or_default())).collect();
+        let mut timings = PassTimings { tick, ..PassTimings::default() };
+        for pass in ProfilePass::ALL {
+            let i = pass.begin_index() as usize;
+            if timed & (1 << pass as u32) == 0 || i + 1 >= stamps.len() {
+                continue;
+            }
+            let ticks = stamps[i + 1].saturating_sub(stamps[i]);
+            timings.ms[pass as usize] = Some(ticks as f32 * self.period_ns / 1.0e6);
+        }
+        Some(timings)
+    }
+}
@@ -0,0 +1,123 @@
+//! Active-region tracking for dense ticks. A stats pass bounds the non-empty
+//! voxels of the state it reads and the one before it. A tick moves or copies
+//! a voxel at most one cell, so tick `m + k` after a measurement of state `m`
+//! only needs intent and resolve over that box grown by `k + 1` cells: both
+//! buffers are empty outside it. Nutrient spawning can fill any empty cell,
+//! so the whole grid is dispatched while `nutrient_spawn_rate` is positive.
+
+use types::CellBox;
+
+/// Stride between region slots: WebGPU's minimum uniform offset alignment.
+const SLOT_BYTES: u64 = 256;
+/// `DispatchRegion` in common.wgsl: origin and end as vec4<u32>.
+const REGION_BYTES: u64 = 32;
+/// Ticks a measurement stays usable; later ticks dispatch the whole grid.
+pub const MAX_REGION_MARGIN: u32 = 16;
+/// Size of the region uniform: slot 0 plus one slot per margin.
+pub const REGION_BUFFER_BYTES: u64 = (MAX_REGION_MARGIN as u64 + 1) * SLOT_BYTES;
+
+/// Region uniform slots: slot 0 covers the whole grid, slot k the measured
+/// box grown by k cells. Bound with a dynamic offset so ticks recorded into
+/// one encoder can each use their own slot.
+pub struct ActiveRegion {
+    buffer: wgpu::Buffer,
+    grid_size: u32,
+    /// Tick whose state the latest stats pass measured; dropped when
+    /// anything other than a tick writes the voxel buffers.
+    pending_tick: Option<u32>,
+    /// Accepted measurement and the tick it describes.
+    measured: Option<(CellBox, u32)>,
+}
+
+/// Dynamic offset into the region uniform and workgroup counts of one pass.
+pub(crate) struct RegionDispatch {
+    pub(crate) offset: u32,
+    pub(crate) workgroups: [u32; 3],
+}
+
+fn full_grid(grid_size: u32) -> CellBox {
+    let last = grid_size - 1;
+    ((0, 0, 0), (last, last, last))
+}
+
+fn slot_words(bounds: CellBox) -> [u32; 8] {
+    let (lo, hi) = bounds;
+    [lo.0, lo.1, lo.2, 0, hi.0 + 1, hi.1 + 1, hi.2 + 1, 0]
+}
+
+impl ActiveRegion {
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue, grid_size: u32) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("active_region_uniform"),
+            size: REGION_BUFFER_BYTES,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&buffer, 0, bytemuck::cast_slice(&slot_words(full_grid(grid_size))));
+        Self { buffer, grid_size, pending_tick: None, measured: None }
+    }
+
+    /// Binding for slot 0; passes pick their slot with a dynamic offset.
+    pub(crate) fn binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: 0,
+            size: wgpu::BufferSize::new(REGION_BYTES),
+        })
+    }
+
+    pub(crate) fn buffer_size(&self) -> u64 {
+        self.buffer.size()
+    }
+
+    /// A stats pass ran on the tick that produced state `tick`.
+    pub(crate) fn record_measurement(&mut self, tick: u32) {
+        self.pending_tick = Some(tick);
+    }
+
+    /// The voxel buffers were written outside a tick; forget all bounds.
+    pub(crate) fn invalidate(&mut self) {
+        self.pending_tick = None;
+        self.measured = None;
+    }
+
+    /// Take the bounds read back for the latest measurement and fill the
+    /// grown slots. Ignored if the measurement was invalidated since.
+    pub(crate) fn accept(&mut self, queue: &wgpu::Queue, bounds: Option<CellBox>) {
+        let Some(tick) = self.pending_tick.take() else { return };
+        self.measured = bounds.map(|b| (b, tick));
+        let Some(b) = bounds else { return };
+        let words: Vec<u32> = (1..=MAX_REGION_MARGIN)
+            .flat_map(|k| {
+                let mut slot = [0u32; (SLOT_BYTES / 4) as usize];
+                slot[..8].copy_from_slice(&slot_words(types::expand_box(b, k, self.grid_size)));
+                slot
+            })
+            .collect();
+        queue.write_buffer(&self.buffer, SLOT_BYTES, bytemuck::cast_slice(&words));
+    }
+
+    /// Box holding every non-empty voxel of state `tick`, if measured recently.
+    pub(crate) fn bounds_at(&self, tick: u32) -> Option<CellBox> {
+        let (b, m) = self.measured?;
+        let k = tick.checked_sub(m).filter(|&k| k < MAX_REGION_MARGIN)?;
+        Some(types::expand_box(b, k, self.grid_size))
+    }
+
+    /// Slot and workgroups for the intent and resolve passes of `tick`.
+    pub(crate) fn dispatch_for_tick(&self, tick: u32, whole_grid: bool) -> RegionDispatch {
+        let slot = self
+            .measured
+            .filter(|_| !whole_grid)
+            .and_then(|(b, m)| {
+                let k = tick.checked_sub(m)? + 1;
+                (k <= MAX_REGION_MARGIN).then(|| (k, types::expand_box(b, k, self.grid_size)))
+            });
+        let (k, (lo, hi)) = slot.unwrap_or((0, full_grid(self.grid_size)));
+        let groups = |lo: u32, hi: u32| (hi + 1 - lo).div_ceil(types::WORKGROUP_SIZE);
+        RegionDispatch {
+            offset: k * SLOT_BYTES as u32,
+            workgroups: [groups(lo.0, hi.0), groups(lo.1, hi.1), groups(lo.2, hi.2)],
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Preset scenes as voxel lists, shared by the GPU engine and the CPU
+//! fallback so both start from identical worlds.
+
+use types::{Genome, GrayImage, SeedConfig, TerrainConfig, TerrainMapping, TerrainStyle, Voxel, VoxelType};
+
+/// `(x, y, z, packed voxel)`; later entries overwrite earlier ones.
+pub type SeedVoxel = (u32, u32, u32, [u32; 8]);
+
+/// Voxels for preset `id` (0 = petri dish, 1 = gradient, 2 = arena,
+/// 3 = caves, 4 = pillars, 5 = veins) with its default `SeedConfig`.
+/// Unknown ids fall back to the petri dish.
+pub fn preset(grid_size: u32, id: u32) -> Vec<SeedVoxel> {
+    preset_with(grid_size, id, &SeedConfig::for_preset(id))
+}
+
+/// Voxels for preset `id` built from `config`; the same config always
+/// gives the same world.
+pub fn preset_with(grid_size: u32, id: u32, config: &SeedConfig) -> Vec<SeedVoxel> {
+    match id {
+        1 => gradient(grid_size, config),
+        2 => arena(grid_size, config),
+        3..=5 => {
+            let style = TerrainStyle::from_u32(id - 3).unwrap_or_default();
+            let terrain = TerrainConfig::for_style(style);
+            let terrain = TerrainConfig { seed: terrain.seed.wrapping_add(config.seed_u32()), ..terrain };
+            procedural(grid_size, &terrain, config.protocell_count)
+        }
+        _ => petri_dish(grid_size, config),
+    }
+}
+
+/// Packed protocell with `genome`.
+fn protocell(genome: Genome, energy: u16) -> [u32; 8] {
+    let v = Voxel {
+        voxel_type: VoxelType::Protocell,
+        energy,
+        species_id: genome.species_id(),
+        genome,
+        ..Default::default()
+    };
+    v.pack()
+}
+
+/// Default scene: a nutrient cube around the center with a protocell cluster,
+/// a few walls, energy sources, waste, and one heat and cold source.
+pub fn petri_dish(gs: u32, config: &SeedConfig) -> Vec<SeedVoxel> {
+    let mut rng = config.rng();
+    let center = gs / 2;
+    let mut voxel_data: Vec<SeedVoxel> = Vec::new();
+
+    // Walls (5 scattered)
+    for i in 0..5u32 {
+        let x = center.saturating_sub(15) + i * 3;
+        let y = center.saturating_sub(15);
+        let z = center;
+        let v = Voxel {
+            voxel_type: VoxelType::Wall,
+            energy: 0,
+            ..Default::default()
+        };
+        voxel_data.push((x.min(gs - 1), y.min(gs - 1), z, v.pack()));
+    }
+
+    // Nutrient field (scaled to grid size)
+    let nutrient_half = (gs / 10).max(4);
+    for dx in 0..(nutrient_half * 2) {
+        for dy in 0..(nutrient_half * 2) {
+            for dz in 0..(nutrient_half * 2) {
+                let x = center - nutrient_half + dx;
+                let y = center - nutrient_half + dy;
+                let z = center - nutrient_half + dz;
+                if x < gs && y < gs && z < gs && rng.chance(config.nutrient_density) {
+                    let v = Voxel {
+                        voxel_type: VoxelType::Nutrient,
+                        energy: 200,
+                        ..Default::default()
+                    };
+                    voxel_data.push((x, y, z, v.pack()));
+                }
+            }
+        }
+    }
+
+    // Energy sources (3 near center)
+    for i in 0..3u32 {
+        let x = (center - 1 + i).min(gs - 1);
+        let v = Voxel {
+            voxel_type: VoxelType::EnergySource,
+            energy: 500,
+            ..Default::default()
+        };
+        voxel_data.push((x, center, center, v.pack()));
+    }
+
+    // Protocells in a tight cluster near center, wider for larger counts
+    let spread = ((config.protocell_count as f32).cbrt() as u32).max(2);
+    for _ in 0..config.protocell_count {
+        let x = (center + rng.below(spread * 2)).saturating_sub(spread).min(gs - 1);
+        let y = (center + rng.below(spread * 2)).saturating_sub(spread).min(gs - 1);
+        let z = (center + rng.below(spread)).saturating_sub(2).min(gs - 1);
+
+        let mut genome = Genome::default();
+        genome.bytes[0] = (80 + rng.below(20) * 8) as u8;
+        genome.bytes[1] = (30 + rng.below(15) * 5) as u8;
+        genome.bytes[2] = 200;
+        genome.bytes[3] = rng.below(256) as u8;
+        genome.bytes[4] = (60 + rng.below(10) * 15) as u8;
+        genome.bytes[5] = (40 + rng.below(8) * 20) as u8;
+        genome.bytes[9] = (60 + rng.below(10) * 15) as u8;
+        genome.bytes[10] = 128;
+        genome.bytes[11] = (rng.below(4) * 40) as u8;
+        genome.bytes[12] = if rng.chance(0.2) { 200 } else { 0 };
+        genome.bytes[14] = (rng.below(3) * 90) as u8;
+        voxel_data.push((x, y, z, protocell(genome, config.protocell_energy)));
+    }
+
+    // Waste (5 voxels)
+    for i in 0..5u32 {
+        let x = (center + 8 + i).min(gs - 1);
+        let y = (center + 8).min(gs - 1);
+        let v = Voxel {
+            voxel_type: VoxelType::Waste,
+            age: i as u16 * 20,
+            ..Default::default()
+        };
+        voxel_data.push((x, y, center, v.pack()));
+    }
+
+    // Heat source
+    {
+        let x = (center + 10).min(gs - 1);
+        let z = (center + 10).min(gs - 1);
+        let v = Voxel {
+            voxel_type: VoxelType::HeatSource,
+            energy: 1000,
+            ..Default::default()
+        };
+        voxel_data.push((x, center, z, v.pack()));
+    }
+
+    // Cold source
+    {
+        let x = center.saturating_sub(10);
+        let z = (center + 10).min(gs - 1);
+        let v = Voxel {
+            voxel_type: VoxelType::ColdSource,
+            energy: 1000,
+            ..Default::default()
+        };
+        voxel_data.push((x, center, z, v.pack()));
+    }
+
+    voxel_data
+}
+
+/// Heat on the x=0 face, cold on the far face, nutrients and protocells in
+/// the middle third.
+pub fn gradient(gs: u32, config: &SeedConfig) -> Vec<SeedVoxel> {
+    let mut rng = config.rng();
+    let mut voxel_data: Vec<SeedVoxel> = Vec::new();
+
+    // Heat sources along x=0 face
+    for y in (0..gs).step_by((gs / 8) as usize) {
+        for z in (0..gs).step_by((gs / 8) as usize) {
+            let v = Voxel { voxel_type: VoxelType::HeatSource, energy: 1000, ..Default::default() };
+            voxel_data.push((0, y, z, v.pack()));
+        }
+    }
+
+    // Cold sources along x=gs-1 face
+    for y in (0..gs).step_by((gs / 8) as usize) {
+        for z in (0..gs).step_by((gs / 8) as usize) {
+            let v = Voxel { voxel_type: VoxelType::ColdSource, energy: 1000, ..Default::default() };
+            voxel_data.push((gs - 1, y, z, v.pack()));
+        }
+    }
+
+    // Scattered nutrients in the middle third
+    let third = gs / 3;
+    for x in third..(third * 2).min(gs) {
+        for y in 0..gs {
+            for z in 0..gs {
+                if rng.chance(config.nutrient_density) {
+                    let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 200, ..Default::default() };
+                    voxel_data.push((x, y, z, v.pack()));
+                }
+            }
+        }
+    }
+
+    // Energy sources in center strip
+    let center = gs / 2;
+    for y in (0..gs).step_by((gs / 6).max(1) as usize) {
+        for z in (0..gs).step_by((gs / 6).max(1) as usize) {
+            let v = Voxel { voxel_type: VoxelType::EnergySource, energy: 500, ..Default::default() };
+            voxel_data.push((center, y, z, v.pack()));
+        }
+    }
+
+    // Protocells scattered across the middle third
+    for _ in 0..config.protocell_count {
+        let x = (third + rng.below(third.max(1))).min(gs - 1);
+        let y = rng.below(gs);
+        let z = rng.below(gs);
+
+        let mut genome = Genome::default();
+        genome.bytes[0] = (100 + rng.below(15) * 10) as u8;
+        genome.bytes[1] = (40 + rng.below(10) * 8) as u8;
+        genome.bytes[2] = 180;
+        genome.bytes[3] = rng.below(256) as u8;
+        genome.bytes[4] = (80 + rng.below(8) * 15) as u8;
+        genome.bytes[5] = (60 + rng.below(6) * 25) as u8;
+        genome.bytes[9] = (50 + rng.below(12) * 15) as u8;
+        genome.bytes[10] = 128;
+        genome.bytes[11] = (rng.below(4) * 40) as u8;
+        // Thermotaxis: preferred temperatures spread over the gradient, strong response
+        genome.bytes[15] = ((rng.below(16) << 4) | 12) as u8;
+        voxel_data.push((x, y, z, protocell(genome, config.protocell_energy)));
+    }
+
+    voxel_data
+}
+
+/// Four walled quadrants with different resources, each seeded with its
+/// own protocell lineage.
+pub fn arena(gs: u32, config: &SeedConfig) -> Vec<SeedVoxel> {
+    let mut rng = config.rng();
+    let center = gs / 2;
+    let mut voxel_data: Vec<SeedVoxel> = Vec::new();
+
+    let gap = (gs / 16).max(2);
+    for i in 0..gs {
+        for z in 0..gs {
+            if !(center.saturating_sub(gap)..=center + gap).contains(&i) {
+                let v = Voxel { voxel_type: VoxelType::Wall, ..Default::default() };
+                voxel_data.push((center, i, z, v.pack()));
+            }
+            if !(center.saturating_sub(gap)..=center + gap).contains(&i) {
+                let v = Voxel { voxel_type: VoxelType::Wall, ..Default::default() };
+                voxel_data.push((i, center, z, v.pack()));
+            }
+        }
+    }
+
+    // Rich quadrant: nutrients through its whole depth
+    let q_size = center.saturating_sub(1);
+    for dx in 1..q_size {
+        for dy in 1..q_size {
+            for dz in 0..gs {
+                if rng.chance(config.nutrient_density * 0.5) {
+                    let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 300, ..Default::default() };
+                    voxel_data.push((dx, dy, dz, v.pack()));
+                }
+            }
+        }
+    }
+
+    for i in 0..4u32 {
+        let x = (center + 2 + i * (q_size / 5)).min(gs - 1);
+        let y = q_size / 2;
+        let v = Voxel { voxel_type: VoxelType::HeatSource, energy: 1000, ..Default::default() };
+        voxel_data.push((x, y, center, v.pack()));
+    }
+    for i in 0..6u32 {
+        let x = (center + 2 + i * (q_size / 7)).min(gs - 1);
+        let y = (1 + i * (q_size / 7)).min(center.saturating_sub(2));
+        let v = Voxel { voxel_type: VoxelType::EnergySource, energy: 500, ..Default::default() };
+        voxel_data.push((x, y, center, v.pack()));
+    }
+
+    // Cold quadrant: sparse, poor nutrients on the middle plane
+    for i in 0..4u32 {
+        let x = (1 + i * (q_size / 5)).min(center.saturating_sub(2));
+        let y = (center + 2 + i * (q_size / 5)).min(gs - 1);
+        let v = Voxel { voxel_type: VoxelType::ColdSource, energy: 1000, ..Default::default() };
+        voxel_data.push((x, y, center, v.pack()));
+    }
+    for dx in 1..q_size {
+        for dy in center + 2..gs.saturating_sub(1) {
+            if rng.chance(config.nutrient_density * 0.25) {
+                let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 100, ..Default::default() };
+                voxel_data.push((dx, dy, center, v.pack()));
+            }
+        }
+    }
+
+    // Energy quadrant: nutrients on the middle plane
+    for i in 0..3u32 {
+        let x = (center + 2 + i * (q_size / 4)).min(gs - 1);
+        let y = (center + 2 + i * (q_size / 4)).min(gs - 1);
+        let v = Voxel { voxel_type: VoxelType::EnergySource, energy: 500, ..Default::default() };
+        voxel_data.push((x, y, center, v.pack()));
+    }
+    for dx in center + 2..gs.saturating_sub(1) {
+        for dy in center + 2..gs.saturating_sub(1) {
+            if rng.chance(config.nutrient_density * 0.5) {
+                let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 200, ..Default::default() };
+                voxel_data.push((dx, dy, center, v.pack()));
+            }
+        }
+    }
+
+    let quadrant_centers = [
+        (center / 2, center / 2),
+        (center + center / 2, center / 2),
+        (center / 2, center + center / 2),
+        (center + center / 2, center + center / 2),
+    ];
+    let per_quadrant = config.protocell_count.div_ceil(4);
+    let spread = ((per_quadrant as f32).sqrt() as u32).max(2);
+    for (qi, &(qx, qy)) in quadrant_centers.iter().enumerate() {
+        for _ in 0..per_quadrant {
+            let x = (qx + rng.below(spread * 2)).saturating_sub(spread).min(gs - 1);
+            let y = (qy + rng.below(spread * 2)).saturating_sub(spread).min(gs - 1);
+
+            let mut genome = Genome::default();
+            genome.bytes[0] = 80 + (qi as u8) * 30 + rng.below(15) as u8 * 5;
+            genome.bytes[1] = 40 + (qi as u8) * 20 + rng.below(15) as u8 * 3;
+            genome.bytes[2] = 200;
+            genome.bytes[3] = 10 + (qi as u8) * 15;
+            genome.bytes[4] = 60 + rng.below(8) as u8 * 15;
+            genome.bytes[5] = 40 + rng.below(6) as u8 * 20;
+            genome.bytes[9] = 50 + (qi as u8) * 30;
+            genome.bytes[10] = 128;
+            genome.bytes[11] = 60 + (qi as u8) * 40;
+            // Last quadrant grows filaments
+            genome.bytes[12] = if qi == 3 { 220 } else { 0 };
+            // Second quadrant hoards energy
+            genome.bytes[14] = if qi == 1 { 200 } else { 0 };
+            // First quadrant poisons its neighbours and resists its own toxin
+            if qi == 0 {
+                genome.bytes[6] = 200;
+                genome.bytes[13] = 0x0C;
+            }
+            voxel_data.push((x, y, center, protocell(genome, config.protocell_energy)));
+        }
+    }
+
+    voxel_data
+}
+
+/// Terrain from a heightmap (see `types::terrain`) with a protocell lineage
+/// scattered over its surface.
+pub fn terrain(gs: u32, image: &GrayImage, mapping: TerrainMapping) -> Vec<SeedVoxel> {
+    let mut voxel_data: Vec<SeedVoxel> = Vec::new();
+    let mut surface = vec![0u32; (gs * gs) as usize];
+
+    for z in 0..gs {
+        for x in 0..gs {
+            let brightness = image.sample(x, z, gs);
+            let (walls, nutrients) = mapping.column(brightness, gs);
+            for y in 0..walls {
+                let v = Voxel { voxel_type: VoxelType::Wall, ..Default::default() };
+                voxel_data.push((x, y, z, v.pack()));
+            }
+            for y in nutrients.clone() {
+                if types::keeps_nutrient(x, y, z, gs, brightness) {
+                    let v = Voxel { voxel_type: VoxelType::Nutrient, energy: 200, ..Default::default() };
+                    voxel_data.push((x, y, z, v.pack()));
+                }
+            }
+            surface[(z * gs + x) as usize] = walls.max(nutrients.end);
+        }
+    }
+
+    // Protocells just above the surface of hashed columns
+    for i in 0..64u32 {
+        let h = i.wrapping_mul(2654435761) ^ 0x7E44_A1B3;
+        let x = h % gs;
+        let z = (h >> 16) % gs;
+        let y = surface[(z * gs + x) as usize];
+        if y < gs {
+            voxel_data.push((x, y, z, founder(i)));
+        }
+    }
+
+    voxel_data
+}
+
+/// Noise terrain (see `types::TerrainConfig`) with a protocell lineage in
+/// its open cells, from `draws` hashed placements.
+pub fn procedural(gs: u32, config: &TerrainConfig, draws: u32) -> Vec<SeedVoxel> {
+    let config = config.clamped();
+    let mut voxel_data: Vec<SeedVoxel> = Vec::new();
+    for z in 0..gs {
+        for y in 0..gs {
+            for x in 0..gs {
+                let v = match config.cell(x, y, z) {
+                    Some(VoxelType::Nutrient) => Voxel { voxel_type: VoxelType::Nutrient, energy: 200, ..Default::default() },
+                    Some(voxel_type) => Voxel { voxel_type, ..Default::default() },
+                    None => continue,
+                };
+                voxel_data.push((x, y, z, v.pack()));
+            }
+        }
+    }
+
+    // Protocells in hashed open cells; rock-choked draws are skipped
+    for i in 0..draws {
+        let h = types::pcg_hash(i ^ config.seed.wrapping_mul(0x9E37_79B9));
+        let (x, y, z) = (h % gs, (h >> 10) % gs, (h >> 20) % gs);
+        if config.cell(x, y, z).is_none() {
+            voxel_data.push((x, y, z, founder(i)));
+        }
+    }
+
+    voxel_data
+}
+
+/// Voxels for a validated custom point list, in list order.
+pub fn custom(points: &[types::SeedPoint]) -> Vec<SeedVoxel> {
+    points.iter().map(|p| (p.pos.0, p.pos.1, p.pos.2, p.to_voxel().pack())).collect()
+}
+
+/// Packed protocell `i` of the lineage seeded onto generated terrain.
+fn founder(i: u32) -> [u32; 8] {
+    let mut genome = Genome::default();
+    genome.bytes[0] = (90 + (i % 16) * 8) as u8;
+    genome.bytes[1] = (30 + (i % 12) * 6) as u8;
+    genome.bytes[2] = 200;
+    genome.bytes[3] = (i * 3) as u8;
+    genome.bytes[4] = (60 + (i % 10) * 15) as u8;
+    genome.bytes[5] = (40 + (i % 8) * 20) as u8;
+    genome.bytes[9] = (60 + (i % 10) * 15) as u8;
+    genome.bytes[10] = 128;
+    genome.bytes[11] = ((i % 4) * 40) as u8;
+    let species = genome.species_id();
+    let v = Voxel {
+        voxel_type: VoxelType::Protocell,
+        energy: 500,
+        species_id: species,
+        genome,
+        ..Default::default()
+    };
+    v.pack()
+}
@@ -0,0 +1,111 @@
+//! Whole-run save and restore (`types::WorldSnapshot`). Export copies the
+//! current voxel and temperature buffers into staging buffers the caller
+//! maps; import reseeds the engine from a decoded snapshot, so dense and
+//! sparse snapshots load into either mode.
+
+use crate::{SimEngine, SimMode};
+use types::WorldSnapshot;
+
+/// Copies of one state, waiting to be mapped. Map both `buffers` for
+/// reading once the encoder that `SimEngine::export_snapshot` wrote to is
+/// submitted, then call `finish`.
+pub struct SnapshotReadback {
+    voxels: wgpu::Buffer,
+    temperatures: wgpu::Buffer,
+    grid_size: u32,
+    tick_count: u32,
+    sim_time: f64,
+    params: types::SimParams,
+    brick_table: Option<Vec<u32>>,
+}
+
+impl SnapshotReadback {
+    pub fn buffers(&self) -> [&wgpu::Buffer; 2] {
+        [&self.voxels, &self.temperatures]
+    }
+
+    /// Bytes of both staging buffers.
+    pub fn staging_size(&self) -> u64 {
+        self.voxels.size() + self.temperatures.size()
+    }
+
+    /// The encoded snapshot, from the mapped buffers, which are unmapped.
+    pub fn finish(self) -> Vec<u8> {
+        let voxels = self.voxels.slice(..).get_mapped_range().to_vec();
+        let temperatures = bytemuck::cast_slice::<u8, f32>(&self.temperatures.slice(..).get_mapped_range()).to_vec();
+        self.voxels.unmap();
+        self.temperatures.unmap();
+        WorldSnapshot {
+            grid_size: self.grid_size,
+            tick_count: self.tick_count,
+            sim_time: self.sim_time,
+            params: self.params,
+            brick_table: self.brick_table,
+            voxels,
+            temperatures,
+        }
+        .encode()
+    }
+}
+
+impl SimEngine {
+    /// Copy the current state into new staging buffers; see
+    /// `SnapshotReadback`.
+    pub fn export_snapshot(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) -> SnapshotReadback {
+        let mut staging = |label, source: &wgpu::Buffer| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: source.size(),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            encoder.copy_buffer_to_buffer(source, 0, &buffer, 0, source.size());
+            buffer
+        };
+        SnapshotReadback {
+            voxels: staging("snapshot_voxels_staging", self.current_read_buffer()),
+            temperatures: staging("snapshot_temp_staging", self.current_temp_buffer()),
+            grid_size: self.grid_size(),
+            tick_count: self.tick_count,
+            sim_time: self.sim_time,
+            params: self.params.clone(),
+            brick_table: self.brick_table().map(|t| t.to_vec()),
+        }
+    }
+
+    /// Replace the run with an encoded snapshot of a world the same size:
+    /// voxels, temperature, tick count, simulated time and params (storage
+    /// params stay the engine's). Returns the voxels placed.
+    pub fn import_snapshot(&mut self, queue: &wgpu::Queue, bytes: &[u8]) -> Result<u32, String> {
+        let snapshot = WorldSnapshot::decode(bytes)?;
+        let grid_size = self.grid_size();
+        if snapshot.grid_size != grid_size {
+            return Err(format!("snapshot is of a {}³ world, this one is {grid_size}³", snapshot.grid_size));
+        }
+        snapshot.apply_params(&mut self.params);
+        self.reset_tick_count();
+        let placed = self.initialize_grid_with_voxels(queue, &snapshot.seed_voxels());
+        self.tick_count = snapshot.tick_count;
+        self.sim_time = snapshot.sim_time;
+
+        let temps = snapshot.dense_temperatures();
+        match &self.mode {
+            SimMode::Dense(d) => queue.write_buffer(d.buffers.temp_buffer_a(), 0, bytemuck::cast_slice(&temps)),
+            SimMode::Sparse(s) => {
+                let dim = s.grid.brick_grid_dim();
+                for (b, &slot) in s.grid.brick_table().iter().enumerate() {
+                    if slot == u32::MAX {
+                        continue;
+                    }
+                    let b = b as u32;
+                    let (bx, by, bz) = (b % dim, b / dim % dim, b / (dim * dim));
+                    let brick: Vec<f32> = (0..512u32)
+                        .map(|i| temps[types::grid_index(bx * 8 + i % 8, by * 8 + i / 8 % 8, bz * 8 + i / 64, grid_size)])
+                        .collect();
+                    queue.write_buffer(s.buffers.temp_pool_a(), slot as u64 * 512 * 4, bytemuck::cast_slice(&brick));
+                }
+            }
+        }
+        Ok(placed)
+    }
+}
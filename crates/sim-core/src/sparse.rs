@@ -1,18 +1,91 @@
 use wgpu;
+use wgpu::util::DeviceExt;
+
+/// Bricks a sparse grid allocates for `occupancy` (per-brick voxel counts,
+/// brick table order): occupied bricks plus their face neighbors, as after
+/// `proactive_border_alloc`.
+pub fn bricks_needed(occupancy: &[u32], brick_grid_dim: u32) -> u32 {
+    let dim = brick_grid_dim as i32;
+    let occupied = |x: i32, y: i32, z: i32| {
+        x >= 0 && y >= 0 && z >= 0 && x < dim && y < dim && z < dim
+            && occupancy.get(((z * dim + y) * dim + x) as usize).is_some_and(|&c| c > 0)
+    };
+    let mut count = 0;
+    for z in 0..dim {
+        for y in 0..dim {
+            for x in 0..dim {
+                if occupied(x, y, z)
+                    || occupied(x - 1, y, z) || occupied(x + 1, y, z)
+                    || occupied(x, y - 1, z) || occupied(x, y + 1, z)
+                    || occupied(x, y, z - 1) || occupied(x, y, z + 1)
+                {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+/// Sort and dedup `words` into ranges, merging words at most `max_gap`
+/// apart: rewriting a few unchanged words is cheaper than another write.
+fn coalesce_words(words: &mut Vec<u32>, max_gap: u32) -> Vec<std::ops::Range<u32>> {
+    words.sort_unstable();
+    words.dedup();
+    let mut ranges: Vec<std::ops::Range<u32>> = Vec::new();
+    for &w in words.iter() {
+        match ranges.last_mut() {
+            Some(last) if w <= last.end + max_gap => last.end = w + 1,
+            _ => ranges.push(w..w + 1),
+        }
+    }
+    ranges
+}
 
 /// CPU-managed brick allocation table for sparse 256³ grids.
 /// Maps brick coordinates (8³ voxels each) to pool slot indices.
-/// 0xFFFFFFFF = unallocated brick.
+/// 0xFFFFFFFF = unallocated brick. The GPU buffer holds the table, then
+/// each pool slot's tick interval (see `types::SimLod`), then the number of
+/// allocated bricks and their pool slots (padded to `max_bricks`). Changes
+/// are uploaded as coalesced ranges of the words they touched.
+#[derive(Clone)]
 pub struct SparseGrid {
     brick_table: Vec<u32>,
+    /// Tick interval per pool slot: 1 unless LOD puts the brick far away.
+    slot_intervals: Vec<u32>,
+    /// Level of detail and its focus point, in voxels.
+    lod: Option<(types::SimLod, [f32; 3])>,
     free_list: Vec<u32>,
     brick_grid_dim: u32,
     max_bricks: u32,
     active_brick_count: u32,
+    /// Most bricks allocated at once, and allocations refused for a full
+    /// pool, since `reset_pressure`.
+    high_water: u32,
+    failed_allocations: u64,
+    /// Tick each pool slot was last allocated at, per `set_clock`.
+    allocated_at: Vec<u32>,
+    clock: u32,
+    /// Pool slots of the allocated bricks, unordered (swap-removed on
+    /// deallocation so a change touches few words).
+    allocated: Vec<u32>,
+    /// Position of each pool slot in `allocated`.
+    allocated_pos: Vec<u32>,
+    /// Slots allocated since `take_fresh_slots`; a reused slot's pool cells
+    /// still hold the brick last deallocated from it.
+    fresh_slots: Vec<u32>,
     brick_table_buf: wgpu::Buffer,
+    /// Creates the staging buffers of `record_upload_if_dirty`.
+    device: wgpu::Device,
+    /// Upload the whole buffer, not just `dirty_words`.
     brick_table_dirty: bool,
+    /// Buffer words changed since the last upload.
+    dirty_words: Vec<u32>,
 }
 
+/// Dirty words this close together are uploaded as one range.
+const UPLOAD_MERGE_GAP: u32 = 16;
+
 impl SparseGrid {
     pub fn new(device: &wgpu::Device, brick_grid_dim: u32, max_bricks: u32) -> Self {
         let table_size = (brick_grid_dim as usize).pow(3);
@@ -23,22 +96,49 @@ impl SparseGrid {
 
         let brick_table_buf = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("brick_table"),
-            size: (table_size * 4) as u64,
+            size: Self::buffer_bytes(brick_grid_dim, max_bricks),
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
         Self {
             brick_table,
+            slot_intervals: vec![1; max_bricks as usize],
+            lod: None,
             free_list,
             brick_grid_dim,
             max_bricks,
             active_brick_count: 0,
+            high_water: 0,
+            failed_allocations: 0,
+            allocated_at: vec![0; max_bricks as usize],
+            clock: 0,
+            allocated: Vec::new(),
+            allocated_pos: vec![0; max_bricks as usize],
+            fresh_slots: Vec::new(),
             brick_table_buf,
+            device: device.clone(),
             brick_table_dirty: true, // upload initial state
+            dirty_words: Vec::new(),
         }
     }
 
+    /// Size of the GPU buffer: the table, one interval per pool slot, then
+    /// the allocated-slot count and list.
+    pub fn buffer_bytes(brick_grid_dim: u32, max_bricks: u32) -> u64 {
+        (brick_grid_dim as u64).pow(3) * 4 + max_bricks as u64 * 8 + 4
+    }
+
+    /// Buffer word of a slot's tick interval.
+    fn interval_word(&self, slot: u32) -> u32 {
+        self.brick_table.len() as u32 + slot
+    }
+
+    /// Buffer word of the allocated-slot count; the list follows it.
+    fn allocated_count_word(&self) -> u32 {
+        self.brick_table.len() as u32 + self.max_bricks
+    }
+
     fn table_index(&self, bx: u32, by: u32, bz: u32) -> usize {
         let dim = self.brick_grid_dim as usize;
         (bz as usize) * dim * dim + (by as usize) * dim + (bx as usize)
@@ -50,10 +150,20 @@ impl SparseGrid {
         if self.brick_table[idx] != 0xFFFFFFFF {
             return Some(self.brick_table[idx]); // already allocated
         }
-        let slot = self.free_list.pop()?;
+        let Some(slot) = self.free_list.pop() else {
+            self.failed_allocations += 1;
+            return None;
+        };
         self.brick_table[idx] = slot;
+        self.slot_intervals[slot as usize] = self.lod.map_or(1, |(lod, focus)| lod.brick_interval((bx, by, bz), focus));
         self.active_brick_count += 1;
-        self.brick_table_dirty = true;
+        self.high_water = self.high_water.max(self.active_brick_count);
+        self.allocated_at[slot as usize] = self.clock;
+        self.fresh_slots.push(slot);
+        self.allocated_pos[slot as usize] = self.allocated.len() as u32;
+        self.allocated.push(slot);
+        let count_word = self.allocated_count_word();
+        self.dirty_words.extend([idx as u32, self.interval_word(slot), count_word, count_word + self.allocated.len() as u32]);
         Some(slot)
     }
 
@@ -67,7 +177,14 @@ impl SparseGrid {
         self.brick_table[idx] = 0xFFFFFFFF;
         self.free_list.push(slot);
         self.active_brick_count -= 1;
-        self.brick_table_dirty = true;
+        // The last list entry moves into the freed position
+        let pos = self.allocated_pos[slot as usize];
+        self.allocated.swap_remove(pos as usize);
+        if let Some(&moved) = self.allocated.get(pos as usize) {
+            self.allocated_pos[moved as usize] = pos;
+        }
+        let count_word = self.allocated_count_word();
+        self.dirty_words.extend([idx as u32, count_word, count_word + 1 + pos]);
     }
 
     /// Ensure a brick is allocated for the voxel at (x, y, z).
@@ -92,18 +209,141 @@ impl SparseGrid {
         Some(slot * 512 + local)
     }
 
-    /// Upload brick table to GPU if dirty.
+    /// Upload the words of the brick table, slot intervals and allocated
+    /// slots changed since the last upload, one write per coalesced range
+    /// (everything on the first upload). Queue writes land before the next
+    /// submission, so call this between submissions; ticks use
+    /// `record_upload_if_dirty`.
     pub fn upload_if_dirty(&mut self, queue: &wgpu::Queue) {
-        if !self.brick_table_dirty {
+        for (offset, words) in self.take_uploads() {
+            queue.write_buffer(&self.brick_table_buf, offset, bytemuck::cast_slice(&words));
+        }
+    }
+
+    /// `upload_if_dirty` in `encoder` order: the changed words are staged
+    /// and copied, so ticks recorded earlier into the same submission still
+    /// read the table they were recorded against.
+    pub fn record_upload_if_dirty(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let uploads = self.take_uploads();
+        if uploads.is_empty() {
             return;
         }
-        let bytes: &[u8] = bytemuck::cast_slice(&self.brick_table);
-        queue.write_buffer(&self.brick_table_buf, 0, bytes);
-        self.brick_table_dirty = false;
+        let staged: Vec<u32> = uploads.iter().flat_map(|(_, words)| words.iter().copied()).collect();
+        let staging = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("brick_table_staging"),
+            contents: bytemuck::cast_slice(&staged),
+            usage: wgpu::BufferUsages::COPY_SRC,
+        });
+        let mut src = 0;
+        for (offset, words) in &uploads {
+            let bytes = words.len() as u64 * 4;
+            encoder.copy_buffer_to_buffer(&staging, src, &self.brick_table_buf, *offset, bytes);
+            src += bytes;
+        }
+    }
+
+    /// Byte offset and words of each buffer range to upload.
+    fn take_uploads(&mut self) -> Vec<(u64, Vec<u32>)> {
+        if self.brick_table_dirty {
+            self.brick_table_dirty = false;
+            self.dirty_words.clear();
+            let words = (Self::buffer_bytes(self.brick_grid_dim, self.max_bricks) / 4) as u32;
+            return vec![(0, (0..words).map(|w| self.buffer_word(w)).collect())];
+        }
+        let mut dirty = std::mem::take(&mut self.dirty_words);
+        let uploads = coalesce_words(&mut dirty, UPLOAD_MERGE_GAP)
+            .into_iter()
+            .map(|range| (range.start as u64 * 4, range.map(|w| self.buffer_word(w)).collect()))
+            .collect();
+        // Keep the allocation for the next changes
+        dirty.clear();
+        self.dirty_words = dirty;
+        uploads
+    }
+
+    /// Word `w` of the GPU buffer, from the CPU mirror.
+    fn buffer_word(&self, w: u32) -> u32 {
+        let (table, count_word) = (self.brick_table.len() as u32, self.allocated_count_word());
+        if w < table {
+            self.brick_table[w as usize]
+        } else if w < count_word {
+            self.slot_intervals[(w - table) as usize]
+        } else if w == count_word {
+            self.active_brick_count
+        } else {
+            self.allocated.get((w - count_word - 1) as usize).copied().unwrap_or(0)
+        }
+    }
+
+    /// The allocation changelist: slots allocated since the last call, for
+    /// the caller to clear.
+    pub fn take_fresh_slots(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.fresh_slots)
+    }
+
+    /// Take the allocation of `keyframe`, an earlier clone of this grid,
+    /// keeping the current LOD. The whole buffer is uploaded next; the
+    /// restored pool needs no clearing.
+    pub fn restore(&mut self, keyframe: &SparseGrid) {
+        let (lod, brick_table_buf) = (self.lod, self.brick_table_buf.clone());
+        let (high_water, failed_allocations) = (self.high_water, self.failed_allocations);
+        *self = SparseGrid { brick_table_buf, fresh_slots: Vec::new(), high_water, failed_allocations, ..keyframe.clone() };
+        self.set_lod(lod.map(|(l, _)| l), lod.map_or([0.0; 3], |(_, focus)| focus));
+        self.brick_table_dirty = true;
+    }
+
+    /// Tick that allocations from now on are stamped with.
+    pub fn set_clock(&mut self, tick: u32) {
+        self.clock = tick;
+    }
+
+    /// Brick coordinates and allocation tick of every allocated brick.
+    pub fn allocated_bricks(&self) -> impl Iterator<Item = ((u32, u32, u32), u32)> + '_ {
+        let dim = self.brick_grid_dim;
+        self.brick_table.iter().enumerate().filter(|(_, &slot)| slot != 0xFFFFFFFF).map(move |(i, &slot)| {
+            let i = i as u32;
+            ((i % dim, (i / dim) % dim, i / (dim * dim)), self.allocated_at[slot as usize])
+        })
+    }
+
+    /// Pool slots of the allocated bricks, in no particular order.
+    pub fn allocated_slots(&self) -> impl Iterator<Item = u32> + '_ {
+        self.allocated.iter().copied()
+    }
+
+    /// Tick allocated bricks far from `focus` every `lod.far_interval`
+    /// ticks, or every brick every tick with `None`. Intervals are
+    /// recomputed only when the LOD or its focus changes.
+    pub fn set_lod(&mut self, lod: Option<types::SimLod>, focus: [f32; 3]) {
+        let lod = lod.map(|l| (l, focus));
+        if lod == self.lod {
+            return;
+        }
+        self.lod = lod;
+        let dim = self.brick_grid_dim;
+        for (i, &slot) in self.brick_table.iter().enumerate() {
+            if slot == 0xFFFFFFFF {
+                continue;
+            }
+            let i = i as u32;
+            let brick = (i % dim, (i / dim) % dim, i / (dim * dim));
+            let interval = lod.map_or(1, |(l, focus)| l.brick_interval(brick, focus));
+            if self.slot_intervals[slot as usize] != interval {
+                self.slot_intervals[slot as usize] = interval;
+                let word = self.interval_word(slot);
+                self.dirty_words.push(word);
+            }
+        }
+    }
+
+    /// Allocated bricks ticking at a reduced rate.
+    pub fn far_brick_count(&self) -> u32 {
+        self.brick_table.iter().filter(|&&slot| slot != 0xFFFFFFFF && self.slot_intervals[slot as usize] > 1).count() as u32
     }
 
     /// For each allocated brick, allocate all 6 face-adjacent bricks if not present.
-    pub fn proactive_border_alloc(&mut self) {
+    /// Returns the bricks left unallocated because the pool is full.
+    pub fn proactive_border_alloc(&mut self) -> u32 {
         let dim = self.brick_grid_dim;
         // Collect currently allocated brick coords to avoid mutation during iteration
         let mut allocated = Vec::new();
@@ -124,26 +364,52 @@ impl SparseGrid {
             (0, 0, 1), (0, 0, -1),
         ];
 
+        let mut missing = 0;
         for (bx, by, bz) in allocated {
             for (dx, dy, dz) in &offsets {
                 let nx = bx as i32 + dx;
                 let ny = by as i32 + dy;
                 let nz = bz as i32 + dz;
-                if nx >= 0 && nx < dim as i32 && ny >= 0 && ny < dim as i32 && nz >= 0 && nz < dim as i32 {
-                    let _ = self.allocate_brick(nx as u32, ny as u32, nz as u32);
+                if nx >= 0 && nx < dim as i32 && ny >= 0 && ny < dim as i32 && nz >= 0 && nz < dim as i32
+                    && self.allocate_brick(nx as u32, ny as u32, nz as u32).is_none()
+                {
+                    missing += 1;
                 }
             }
         }
+        missing
     }
 
     pub fn brick_table_buffer(&self) -> &wgpu::Buffer {
         &self.brick_table_buf
     }
 
+    /// Pool slot of each brick (`0xFFFFFFFF` = unallocated); the CPU mirror
+    /// of the brick table buffer.
+    pub fn brick_table(&self) -> &[u32] {
+        &self.brick_table
+    }
+
     pub fn active_brick_count(&self) -> u32 {
         self.active_brick_count
     }
 
+    /// Pool occupancy and pressure counters.
+    pub fn pool_stats(&self) -> types::BrickPoolStats {
+        types::BrickPoolStats {
+            capacity: self.max_bricks,
+            allocated: self.active_brick_count,
+            high_water: self.high_water,
+            failed_allocations: self.failed_allocations,
+        }
+    }
+
+    /// Restart the high-water mark and failure count from the current pool.
+    pub fn reset_pressure(&mut self) {
+        self.high_water = self.active_brick_count;
+        self.failed_allocations = 0;
+    }
+
     pub fn max_bricks(&self) -> u32 {
         self.max_bricks
     }
@@ -1,11 +1,36 @@
-/// Stats readback data parsed from the 128-byte stats_buf.
-/// Layout: 32 × u32 words.
-///   [0] population
-///   [1] total_energy
-///   [2] species_count (unused — derived from histogram)
-///   [3] max_energy
-///   [4..27] species histogram: 12 entries × 2 words (species_id, count)
-///   [28..31] reserved
+use types::layout::StatsSlot;
+
+/// Words in stats_buf (`types::layout::STATS_WORDS`).
+pub const STATS_WORDS: usize = types::layout::STATS_WORDS as usize;
+/// Size of stats_buf and its staging copy.
+pub const STATS_BYTES: u64 = (STATS_WORDS * 4) as u64;
+/// Active-bounds minima are stored as `ACTIVE_MIN_BIAS - min` so that
+/// atomicMax over a zeroed buffer finds them.
+pub const ACTIVE_MIN_BIAS: u32 = 0xFFFF;
+/// Same-species face neighbors that make a protocell count as clustered.
+pub const CLUSTER_MIN_NEIGHBORS: u32 = 3;
+/// Size of the energy-flow buffer: the `types::ENERGY_FLOW_WORDS` counters,
+/// then the speciation log (`types::LINEAGE_LOG_WORDS`).
+pub const ENERGY_FLOW_BYTES: u64 = ((types::ENERGY_FLOW_WORDS + types::LINEAGE_LOG_WORDS) * 4) as u64;
+/// Size of the stats reduction's per-workgroup partials
+/// (`types::layout::STATS_PARTIAL_GROUPS` records).
+pub const STATS_PARTIALS_BYTES: u64 =
+    (types::layout::STATS_PARTIAL_GROUPS * types::layout::STATS_PARTIAL_WORDS * 4) as u64;
+/// Size of the stats staging buffer: stats_buf, then the energy-flow
+/// counters and speciation log of the window the stats pass closed.
+pub const STATS_READBACK_BYTES: u64 = STATS_BYTES + ENERGY_FLOW_BYTES;
+
+/// Stats readback data parsed from stats_buf. Each metric sits at its
+/// `types::layout::StatsSlot`; notable encodings:
+///   SpeciesHistogram: (species_id, count) pairs, GoalHistogram: goal-zone
+///     count for each histogram entry, OtherSpecies: protocells (and those
+///     in the goal zone) of species left out of the histogram
+///   ActiveMax: max x/y/z + 1 (0 = no non-empty voxel; dense only),
+///     ActiveMin: ACTIVE_MIN_BIAS - min x/y/z
+///   Clustered: clustered protocells + 1 (0 = not measured; dense only)
+///   VoxelCounts: nutrient, waste and wall voxels
+///   FieldStats: field min/max/sums (field_stats.wgsl)
+/// A buffer whose Version word is not `STATS_LAYOUT_VERSION` parses as empty.
 #[derive(Debug, Clone, Default)]
 pub struct SimStats {
     pub population: u32,
@@ -13,25 +38,91 @@ pub struct SimStats {
     pub species_count: u32,
     pub max_energy: u32,
     pub species_histogram: Vec<(u16, u32)>,
+    /// Protocells of species the histogram had no room for; while nonzero,
+    /// `species_count` is a lower bound.
+    pub other_species: u32,
+    pub toxin_deaths: u32,
+    /// Protocells inside the goal zone, by species (sorted by count).
+    pub goal_histogram: Vec<(u16, u32)>,
+    /// Goal-zone protocells among `other_species`.
+    pub goal_other: u32,
+    /// Inclusive box of voxels that were non-empty in the measured state
+    /// or the one before it. `None` in sparse mode or an empty world.
+    pub active_bounds: Option<types::CellBox>,
+    /// Protocells with at least `CLUSTER_MIN_NEIGHBORS` same-species face
+    /// neighbors. `None` in sparse mode.
+    pub clustered: Option<u32>,
+    /// Sum of the mutation-rate gene over all protocells.
+    pub mutation_rate_sum: u32,
+    /// Sum of protocell ages (ticks).
+    pub age_sum: u32,
+    pub nutrients: u32,
+    pub waste: u32,
+    pub walls: u32,
+    /// Min/max/mean temperature over all (allocated) cells.
+    pub temperature: Option<types::FieldSummary>,
+    /// Min/max/mean pheromone intensity (0..1) over the coarse cells.
+    pub pheromone: Option<types::FieldSummary>,
+    /// Min/max/mean protocell energy; `None` without protocells.
+    pub energy: Option<types::FieldSummary>,
+    /// Energy moved per pathway since the previous stats pass.
+    pub energy_flow: types::EnergyFlow,
+    /// Contested cells and rejected intents since the previous stats pass.
+    pub conflicts: types::ConflictCounts,
+    /// Births and deaths since the previous stats pass.
+    pub turnover: types::Turnover,
+    /// Speciations since the previous stats pass, in tick order.
+    pub speciations: Vec<types::LineageEvent>,
+    /// Speciations that did not fit the window's log.
+    pub speciations_unlogged: u32,
+    /// Brick pool when the stats landed; not part of the readback, the
+    /// host fills it from `SimEngine::brick_pool`. `None` in dense mode.
+    pub brick_pool: Option<types::BrickPoolStats>,
 }
 
 impl SimStats {
-    pub fn from_words(words: &[u32; 32]) -> Self {
-        let population = words[0];
-        let total_energy = words[1];
-        let max_energy = words[3];
+    pub fn from_words(words: &[u32; STATS_WORDS]) -> Self {
+        let slot = |s: StatsSlot| &words[s.range()];
+        let word = |s: StatsSlot| slot(s)[0];
+        if word(StatsSlot::Version) != types::layout::STATS_LAYOUT_VERSION {
+            return Self::default();
+        }
+        let population = word(StatsSlot::Population);
+        let total_energy = word(StatsSlot::TotalEnergy);
+        let max_energy = word(StatsSlot::MaxEnergy);
 
         let mut species_histogram = Vec::new();
-        for i in 0..12 {
-            let sid = words[4 + i * 2] as u16;
-            let count = words[5 + i * 2];
+        let mut goal_histogram = Vec::new();
+        let in_goal = slot(StatsSlot::GoalHistogram);
+        for (entry, &goal) in slot(StatsSlot::SpeciesHistogram).chunks_exact(2).zip(in_goal) {
+            let (sid, count) = (entry[0] as u16, entry[1]);
             if sid != 0 && count > 0 {
                 species_histogram.push((sid, count));
             }
+            if sid != 0 && goal > 0 {
+                goal_histogram.push((sid, goal));
+            }
         }
-        species_histogram.sort_by(|a, b| b.1.cmp(&a.1));
+        species_histogram.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        goal_histogram.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
 
         let species_count = species_histogram.len() as u32;
+        let other = slot(StatsSlot::OtherSpecies);
+        let counts = slot(StatsSlot::VoxelCounts);
+
+        let (hi, lo) = (slot(StatsSlot::ActiveMax), slot(StatsSlot::ActiveMin));
+        let active_bounds = (hi[0] > 0).then(|| {
+            let min = |a: usize| ACTIVE_MIN_BIAS.saturating_sub(lo[a]);
+            let max = |a: usize| hi[a].saturating_sub(1);
+            ((min(0), min(1), min(2)), (max(0), max(1), max(2)))
+        });
+
+        let fields = types::FieldStats::from_words(slot(StatsSlot::FieldStats));
+        let energy = fields.energy_min.filter(|_| population > 0).map(|min| types::FieldSummary {
+            min: min as f32,
+            max: max_energy as f32,
+            mean: total_energy as f32 / population as f32,
+        });
 
         SimStats {
             population,
@@ -39,6 +130,79 @@ impl SimStats {
             species_count,
             max_energy,
             species_histogram,
+            other_species: other[0],
+            toxin_deaths: word(StatsSlot::ToxinDeaths),
+            goal_histogram,
+            goal_other: other[1],
+            active_bounds,
+            clustered: word(StatsSlot::Clustered).checked_sub(1),
+            mutation_rate_sum: word(StatsSlot::MutationRateSum),
+            age_sum: word(StatsSlot::AgeSum),
+            nutrients: counts[0],
+            waste: counts[1],
+            walls: counts[2],
+            temperature: fields.temperature,
+            pheromone: fields.pheromone,
+            energy,
+            energy_flow: types::EnergyFlow::default(),
+            conflicts: types::ConflictCounts::default(),
+            turnover: types::Turnover::default(),
+            speciations: Vec::new(),
+            speciations_unlogged: 0,
+            brick_pool: None,
         }
     }
+
+    /// Fraction of protocells that are clustered: near 1 for tight colonies,
+    /// near 0 for dispersed cells. `None` when not measured or empty.
+    pub fn clustering_index(&self) -> Option<f32> {
+        let clustered = self.clustered?;
+        (self.population > 0).then(|| clustered as f32 / self.population as f32)
+    }
+
+    /// The `k` most populous species, then the protocells of all others
+    /// (the histogram's tail plus `other_species`).
+    pub fn top_species(&self, k: usize) -> (&[(u16, u32)], u32) {
+        let k = k.min(self.species_histogram.len());
+        let tail: u32 = self.species_histogram[k..].iter().map(|&(_, count)| count).sum();
+        (&self.species_histogram[..k], tail + self.other_species)
+    }
+
+    /// Population-mean mutation-rate gene (0..255); `None` when empty.
+    pub fn mean_mutation_rate(&self) -> Option<f32> {
+        (self.population > 0).then(|| self.mutation_rate_sum as f32 / self.population as f32)
+    }
+
+    /// Mean protocell age in ticks; `None` when empty.
+    pub fn mean_age(&self) -> Option<f32> {
+        (self.population > 0).then(|| self.age_sum as f32 / self.population as f32)
+    }
+
+    /// Mean temperature over all (allocated) cells; `None` without the
+    /// temperature field.
+    pub fn mean_temperature(&self) -> Option<f32> {
+        self.temperature.map(|t| t.mean)
+    }
+
+    /// Cells contested and intents rejected per tick over the stats window;
+    /// `None` before a window has ticked.
+    pub fn contention_per_tick(&self) -> Option<(f32, f32)> {
+        let ticks = self.energy_flow.ticks;
+        Some((self.conflicts.contested_per_tick(ticks)?, self.conflicts.rejected_per_tick(ticks)?))
+    }
+
+    /// Parse a mapped stats staging buffer (`STATS_READBACK_BYTES`); short
+    /// input reads as zeros.
+    pub fn from_readback(words: &[u32]) -> Self {
+        let mut arr = [0u32; STATS_WORDS];
+        let len = words.len().min(STATS_WORDS);
+        arr[..len].copy_from_slice(&words[..len]);
+        let mut stats = Self::from_words(&arr);
+        let counters = words.get(STATS_WORDS..).unwrap_or(&[]);
+        stats.energy_flow = types::EnergyFlow::from_words(counters);
+        stats.conflicts = types::ConflictCounts::from_words(counters);
+        stats.turnover = types::Turnover::from_words(counters);
+        (stats.speciations, stats.speciations_unlogged) = types::parse_lineage_log(counters);
+        stats
+    }
 }
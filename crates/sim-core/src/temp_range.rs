@@ -0,0 +1,167 @@
+//! GPU temperature range pass: reduces the temperature field to its min and
+//! max in a 2-word buffer. The render texture pass reads the buffer directly
+//! to auto-scale the temperature overlay; a staging copy feeds the legend.
+
+use crate::census::storage_entry;
+use types::TEMP_RANGE_WORDS;
+use types::{load_wgsl, ShaderDefs, SPARSE_DEFINE};
+
+
+const RANGE_BYTES: u64 = (TEMP_RANGE_WORDS * 4) as u64;
+
+pub struct TempRangePass {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buf: wgpu::Buffer,
+    range_buf: wgpu::Buffer,
+    staging_buf: wgpu::Buffer,
+    /// Bound at binding 10 in dense mode, where the brick table is unused.
+    placeholder_brick_table: wgpu::Buffer,
+}
+
+impl TempRangePass {
+    pub fn new(device: &wgpu::Device) -> Self {
+        // Shared by both modes, so always compiled against a brick table
+        let source = load_wgsl("temperature_range.wgsl", &ShaderDefs::new().define(SPARSE_DEFINE));
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("temperature_range"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("temperature_range_bgl"),
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(10, true),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("temperature_range_pl"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("temperature_range_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("temperature_range_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("temperature_range_uniform"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Zeroed at creation, which reads as "no range yet"
+        let range_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("temperature_range"),
+            size: RANGE_BYTES,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("temperature_range_staging"),
+            size: RANGE_BYTES,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let placeholder_brick_table = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("temperature_range_placeholder_brick_table"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buf,
+            range_buf,
+            staging_buf,
+            placeholder_brick_table,
+        }
+    }
+
+    /// Reduce `temp_buf` into the range buffer, and copy the result into
+    /// staging when `readback` (staging must not be mapped). `brick_table`
+    /// is `Some` in sparse mode.
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        temp_buf: &wgpu::Buffer,
+        brick_table: Option<&wgpu::Buffer>,
+        grid_size: u32,
+        brick_grid_dim: u32,
+        readback: bool,
+    ) {
+        let words = [grid_size, brick_table.is_some() as u32, brick_grid_dim, 0];
+        queue.write_buffer(&self.uniform_buf, 0, bytemuck::cast_slice(&words));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("temperature_range_bg"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: temp_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: self.range_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: self.uniform_buf.as_entire_binding() },
+                wgpu::BindGroupEntry {
+                    binding: 10,
+                    resource: brick_table.unwrap_or(&self.placeholder_brick_table).as_entire_binding(),
+                },
+            ],
+        });
+
+        encoder.clear_buffer(&self.range_buf, 0, None);
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("temperature_range_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let groups = grid_size.div_ceil(types::WORKGROUP_SIZE);
+            pass.dispatch_workgroups(groups, groups, groups);
+        }
+        if readback {
+            encoder.copy_buffer_to_buffer(&self.range_buf, 0, &self.staging_buf, 0, RANGE_BYTES);
+        }
+    }
+
+    /// The range of the last pass (`types::TempRange::from_words` layout).
+    pub fn range_buffer(&self) -> &wgpu::Buffer {
+        &self.range_buf
+    }
+
+    pub fn staging_buffer(&self) -> &wgpu::Buffer {
+        &self.staging_buf
+    }
+
+    /// (label, bytes) for every GPU buffer the range pass owns.
+    pub fn allocations(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("temperature_range_uniform", self.uniform_buf.size()),
+            ("temperature_range", self.range_buf.size()),
+            ("temperature_range_staging", self.staging_buf.size()),
+            ("temperature_range_placeholder_brick_table", self.placeholder_brick_table.size()),
+        ]
+    }
+}
@@ -1,27 +1,59 @@
 use crate::{SimEngine, SimMode, DenseMode, SparseMode};
+use crate::profiler::{PassTimer, ProfilePass};
+use crate::region::RegionDispatch;
+use types::{Feature, TickParams};
 
 impl SimEngine {
     pub fn tick(&mut self, encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands: &[types::Command]) {
-        // 1. Update tick_count in params and upload
-        self.params.tick_count = self.tick_count as f32;
+        // 1. Upload params and rules if they changed, and this tick's TickParams slot
         self.params_uniform.upload(queue, &self.params);
+        self.params_uniform.upload_rules(queue, &self.rules);
+        let TickParams { overlay_mode, overlay_trait, .. } = self.params_uniform.latest_tick();
+        let tick_offset =
+            self.params_uniform.write_tick(queue, TickParams { tick_count: self.tick_count, overlay_mode, overlay_trait });
 
-        // Upload brick table before any dispatches (sparse only)
+        // Upload brick table and clear newly allocated bricks before any
+        // dispatches (sparse only)
         if let SimMode::Sparse(s) = &mut self.mode {
-            s.grid.upload_if_dirty(queue);
+            s.grid.set_clock(self.tick_count);
+            s.grid.record_upload_if_dirty(encoder);
+            clear_fresh_bricks(encoder, s);
         }
 
+        // Commands may place voxels outside the measured region
+        if !commands.is_empty() {
+            self.region.invalidate();
+        }
+        if commands.len() > types::MAX_COMMANDS_PER_TICK {
+            let dropped = commands.len() - types::MAX_COMMANDS_PER_TICK;
+            self.log.warn(self.tick_count, types::Subsystem::Commands, format!("{dropped} commands over the per-tick limit of {} dropped", types::MAX_COMMANDS_PER_TICK));
+        }
+
+        // Timestamp writes around each pass group when a profile is armed
+        let query_set = self.profiler.as_ref().and_then(|p| p.query_set_for_tick());
+        let profiled = query_set.is_some();
+        let mut timer = PassTimer::new(query_set);
         match &mut self.mode {
-            SimMode::Dense(d) => tick_dense(encoder, queue, commands, d),
-            SimMode::Sparse(s) => tick_sparse(encoder, queue, commands, s),
+            SimMode::Dense(d) => {
+                let region = self.region.dispatch_for_tick(self.tick_count, self.params.nutrient_spawn_rate > 0.0);
+                let substeps = self.params.substep_count();
+                tick_dense(encoder, queue, commands, d, &region, tick_offset, substeps, self.stats_enabled, &mut timer)
+            }
+            SimMode::Sparse(s) => {
+                tick_sparse(encoder, queue, commands, s, tick_offset, self.params.substep_count(), self.stats_enabled, &mut timer)
+            }
+        }
+        let timed = timer.timed;
+        if let Some(profiler) = self.profiler.as_mut().filter(|_| profiled) {
+            profiler.finish_tick(encoder, self.tick_count, timed);
         }
 
         // Post-tick: border allocation for sparse (every ~10 ticks)
         if let SimMode::Sparse(s) = &mut self.mode {
             s.border_alloc_counter += 1;
             if s.border_alloc_counter >= 10 {
-                s.grid.proactive_border_alloc();
                 s.border_alloc_counter = 0;
+                self.alloc_border_bricks();
             }
         }
 
@@ -31,19 +63,46 @@ impl SimEngine {
             SimMode::Sparse(s) => s.buffers.swap(),
         }
         self.tick_count += 1;
+        self.sim_time += self.params.dt as f64;
+        // Keyframe the state just written; the ring is taken out so the
+        // sources can be borrowed from the engine
+        if let Some(mut ring) = self.keyframes.take() {
+            if ring.due(self.tick_count) {
+                let grid = match &self.mode {
+                    SimMode::Dense(_) => None,
+                    SimMode::Sparse(s) => Some(&s.grid),
+                };
+                ring.capture(encoder, self.keyframe_sources(), self.tick_count, self.sim_time, grid);
+            }
+            self.keyframes = Some(ring);
+        }
+        if self.stats_enabled {
+            self.region.record_measurement(self.tick_count);
+        }
     }
 }
 
-fn tick_dense(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands: &[types::Command], d: &DenseMode) {
-    let wg = d.buffers.grid_size() / 4;
+#[allow(clippy::too_many_arguments)]
+fn tick_dense(
+    encoder: &mut wgpu::CommandEncoder,
+    queue: &wgpu::Queue,
+    commands: &[types::Command],
+    d: &DenseMode,
+    region: &RegionDispatch,
+    tick_offset: u32,
+    substeps: u32,
+    run_stats: bool,
+    timer: &mut PassTimer,
+) {
+    let wg = d.buffers.grid_size() / types::WORKGROUP_SIZE;
 
     // 2. Apply player commands (only if commands exist)
-    let command_count = commands.len().min(64) as u32;
+    let command_count = commands.len().min(types::MAX_COMMANDS_PER_TICK) as u32;
     if command_count > 0 {
         queue.write_buffer(d.buffers.command_buffer(), 0, bytemuck::bytes_of(&command_count));
-        for (i, cmd) in commands.iter().take(64).enumerate() {
+        for (i, cmd) in commands.iter().take(types::MAX_COMMANDS_PER_TICK).enumerate() {
             let words = cmd.to_words();
-            let byte_offset = 16 + (i as u64) * 64;
+            let byte_offset = types::command_byte_offset(i);
             queue.write_buffer(d.buffers.command_buffer(), byte_offset, bytemuck::cast_slice(&words));
         }
 
@@ -56,14 +115,16 @@ fn tick_dense(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands:
         {
             let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("apply_commands_pass"),
-                timestamp_writes: None,
+                timestamp_writes: timer.writes(ProfilePass::Commands, true, true),
             });
             pass.set_pipeline(&d.pipelines.apply_commands);
-            pass.set_bind_group(0, apply_cmd_bg, &[]);
+            pass.set_bind_group(0, apply_cmd_bg, &[tick_offset]);
             pass.dispatch_workgroups(wg, wg, wg);
         }
 
-        queue.write_buffer(d.buffers.command_buffer(), 0, bytemuck::bytes_of(&0u32));
+        // Reset the count in encoder order; a queue write would land before
+        // the dispatch above and hide the commands from it.
+        encoder.clear_buffer(d.buffers.command_buffer(), 0, Some(4));
     }
 
     // 3. Temperature diffusion
@@ -73,42 +134,63 @@ fn tick_dense(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands:
         (&d.temp_diffusion_bg_odd, &d.intent_bg_odd, &d.resolve_bg_odd)
     };
 
-    {
+    // Nothing to diffuse or advance with temperature and both fields off
+    let features = d.pipelines.features;
+    // Sub-steps, when they run, close the temperature timing instead
+    let substepped = features.contains(Feature::Temperature) && substeps > 1;
+    if features.contains(Feature::Temperature) || features.coarse_field() {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("temperature_diffusion_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Temperature, true, !substepped),
         });
         pass.set_pipeline(&d.pipelines.temperature_diffusion);
         pass.set_bind_group(0, temp_bg, &[]);
         pass.dispatch_workgroups(wg, wg, wg);
     }
+    if features.contains(Feature::Temperature) {
+        encode_temperature_substeps(
+            encoder,
+            &d.pipelines.temperature_substep,
+            temp_bg,
+            d.buffers.current_temp_read(),
+            d.buffers.current_temp_write(),
+            [wg; 3],
+            substeps,
+            timer,
+        );
+    }
 
     // 4. Clear intent buffer
     encoder.clear_buffer(d.buffers.intent_buffer(), 0, None);
 
-    // 5. Intent declaration
+    // 5. Intent declaration (over the active region only)
+    let [rx, ry, rz] = region.workgroups;
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("intent_declaration_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Intent, true, true),
         });
         pass.set_pipeline(&d.pipelines.intent_declaration);
-        pass.set_bind_group(0, intent_bg, &[]);
-        pass.dispatch_workgroups(wg, wg, wg);
+        pass.set_bind_group(0, intent_bg, &[region.offset, tick_offset]);
+        pass.dispatch_workgroups(rx, ry, rz);
     }
 
-    // 6. Resolve and execute
+    // 6. Resolve and execute (over the active region only)
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("resolve_execute_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Resolve, true, true),
         });
         pass.set_pipeline(&d.pipelines.resolve_execute);
-        pass.set_bind_group(0, resolve_bg, &[]);
-        pass.dispatch_workgroups(wg, wg, wg);
+        pass.set_bind_group(0, resolve_bg, &[region.offset, tick_offset]);
+        pass.dispatch_workgroups(rx, ry, rz);
+    }
+
+    // 7. Stats reduction (skipped when the host has no readback pending)
+    if !run_stats {
+        return;
     }
 
-    // 7. Stats reduction
     encoder.clear_buffer(d.buffers.stats_buffer(), 0, None);
 
     let stats_bg = if d.buffers.current_read_is_a() {
@@ -120,33 +202,69 @@ fn tick_dense(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands:
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("stats_reduction_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Stats, true, false),
         });
         pass.set_pipeline(&d.pipelines.stats_reduction);
         pass.set_bind_group(0, stats_bg, &[]);
-        let total_voxels = (d.buffers.grid_size() as u32).pow(3);
-        let workgroups = (total_voxels + 63) / 64;
-        pass.dispatch_workgroups(workgroups, 1, 1);
+        // Fixed workgroup count: each strides over its own contiguous range
+        // of cells (types::stats_partial_range), one partial per group
+        pass.dispatch_workgroups(types::layout::STATS_PARTIAL_GROUPS, 1, 1);
+    }
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("stats_finalize_pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&d.pipelines.stats_finalize);
+        pass.set_bind_group(0, stats_bg, &[]);
+        pass.dispatch_workgroups(1, 1, 1);
+    }
+
+    let field_stats_bg = if d.buffers.current_read_is_a() {
+        &d.field_stats_bg_even
+    } else {
+        &d.field_stats_bg_odd
+    };
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("field_stats_pass"),
+            timestamp_writes: timer.writes(ProfilePass::Stats, false, true),
+        });
+        pass.set_pipeline(&d.pipelines.field_stats);
+        pass.set_bind_group(0, field_stats_bg, &[]);
+        let groups = d.buffers.grid_size().div_ceil(types::WORKGROUP_SIZE);
+        pass.dispatch_workgroups(groups, groups, groups);
     }
 
-    encoder.copy_buffer_to_buffer(
-        d.buffers.stats_buffer(), 0,
-        d.buffers.stats_staging_buffer(), 0,
-        128,
+    copy_stats_readback(
+        encoder,
+        d.buffers.stats_buffer(),
+        d.buffers.energy_flow_buffer(),
+        d.buffers.stats_staging_buffer(),
     );
 }
 
-fn tick_sparse(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands: &[types::Command], s: &SparseMode) {
+#[allow(clippy::too_many_arguments)]
+fn tick_sparse(
+    encoder: &mut wgpu::CommandEncoder,
+    queue: &wgpu::Queue,
+    commands: &[types::Command],
+    s: &SparseMode,
+    tick_offset: u32,
+    substeps: u32,
+    run_stats: bool,
+    timer: &mut PassTimer,
+) {
     // Sparse dispatch: full 256³ grid, threads in unallocated bricks exit early
-    let wg = s.buffers.grid_size() / 4; // 64 for 256³
+    let wg = s.buffers.grid_size() / types::WORKGROUP_SIZE; // 64 for 256³
 
     // 2. Apply player commands
-    let command_count = commands.len().min(64) as u32;
+    let command_count = commands.len().min(types::MAX_COMMANDS_PER_TICK) as u32;
     if command_count > 0 {
         queue.write_buffer(s.buffers.command_buffer(), 0, bytemuck::bytes_of(&command_count));
-        for (i, cmd) in commands.iter().take(64).enumerate() {
+        for (i, cmd) in commands.iter().take(types::MAX_COMMANDS_PER_TICK).enumerate() {
             let words = cmd.to_words();
-            let byte_offset = 16 + (i as u64) * 64;
+            let byte_offset = types::command_byte_offset(i);
             queue.write_buffer(s.buffers.command_buffer(), byte_offset, bytemuck::cast_slice(&words));
         }
 
@@ -159,14 +277,14 @@ fn tick_sparse(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands
         {
             let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("sparse_apply_commands_pass"),
-                timestamp_writes: None,
+                timestamp_writes: timer.writes(ProfilePass::Commands, true, true),
             });
             pass.set_pipeline(&s.pipelines.apply_commands);
-            pass.set_bind_group(0, apply_cmd_bg, &[]);
+            pass.set_bind_group(0, apply_cmd_bg, &[tick_offset]);
             pass.dispatch_workgroups(wg, wg, wg);
         }
 
-        queue.write_buffer(s.buffers.command_buffer(), 0, bytemuck::bytes_of(&0u32));
+        encoder.clear_buffer(s.buffers.command_buffer(), 0, Some(4));
     }
 
     // 3. Temperature diffusion
@@ -176,15 +294,31 @@ fn tick_sparse(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands
         (&s.temp_diffusion_bg_odd, &s.intent_bg_odd, &s.resolve_bg_odd)
     };
 
-    {
+    // Nothing to diffuse or advance with temperature and both fields off
+    let features = s.pipelines.features;
+    // Sub-steps, when they run, close the temperature timing instead
+    let substepped = features.contains(Feature::Temperature) && substeps > 1;
+    if features.contains(Feature::Temperature) || features.coarse_field() {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("sparse_temperature_diffusion_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Temperature, true, !substepped),
         });
         pass.set_pipeline(&s.pipelines.temperature_diffusion);
         pass.set_bind_group(0, temp_bg, &[]);
         pass.dispatch_workgroups(wg, wg, wg);
     }
+    if features.contains(Feature::Temperature) {
+        encode_temperature_substeps(
+            encoder,
+            &s.pipelines.temperature_substep,
+            temp_bg,
+            s.buffers.current_temp_read(),
+            s.buffers.current_temp_write(),
+            [wg; 3],
+            substeps,
+            timer,
+        );
+    }
 
     // 4. Clear intent pool
     encoder.clear_buffer(s.buffers.intent_pool(), 0, None);
@@ -193,10 +327,10 @@ fn tick_sparse(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("sparse_intent_declaration_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Intent, true, true),
         });
         pass.set_pipeline(&s.pipelines.intent_declaration);
-        pass.set_bind_group(0, intent_bg, &[]);
+        pass.set_bind_group(0, intent_bg, &[0, tick_offset]);
         pass.dispatch_workgroups(wg, wg, wg);
     }
 
@@ -204,14 +338,18 @@ fn tick_sparse(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("sparse_resolve_execute_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Resolve, true, true),
         });
         pass.set_pipeline(&s.pipelines.resolve_execute);
-        pass.set_bind_group(0, resolve_bg, &[]);
+        pass.set_bind_group(0, resolve_bg, &[0, tick_offset]);
         pass.dispatch_workgroups(wg, wg, wg);
     }
 
-    // 7. Stats reduction
+    // 7. Stats reduction (skipped when the host has no readback pending)
+    if !run_stats {
+        return;
+    }
+
     encoder.clear_buffer(s.buffers.stats_buffer(), 0, None);
 
     let stats_bg = if s.buffers.current_read_is_a() {
@@ -223,19 +361,100 @@ fn tick_sparse(encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, commands
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("sparse_stats_reduction_pass"),
-            timestamp_writes: None,
+            timestamp_writes: timer.writes(ProfilePass::Stats, true, false),
         });
         pass.set_pipeline(&s.pipelines.stats_reduction);
         pass.set_bind_group(0, stats_bg, &[]);
-        // For sparse, iterate over pool slots: max_bricks * 512
-        let total_pool_voxels = s.buffers.max_bricks() * 512;
-        let workgroups = (total_pool_voxels + 63) / 64;
-        pass.dispatch_workgroups(workgroups, 1, 1);
+        // Each group strides over its own range of the allocated bricks' cells
+        pass.dispatch_workgroups(types::layout::STATS_PARTIAL_GROUPS, 1, 1);
+    }
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("sparse_stats_finalize_pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&s.pipelines.stats_finalize);
+        pass.set_bind_group(0, stats_bg, &[]);
+        pass.dispatch_workgroups(1, 1, 1);
     }
 
-    encoder.copy_buffer_to_buffer(
-        s.buffers.stats_buffer(), 0,
-        s.buffers.stats_staging_buffer(), 0,
-        128,
+    let field_stats_bg = if s.buffers.current_read_is_a() {
+        &s.field_stats_bg_even
+    } else {
+        &s.field_stats_bg_odd
+    };
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("sparse_field_stats_pass"),
+            timestamp_writes: timer.writes(ProfilePass::Stats, false, true),
+        });
+        pass.set_pipeline(&s.pipelines.field_stats);
+        pass.set_bind_group(0, field_stats_bg, &[]);
+        // Full grid, like the other sparse passes; unallocated bricks exit early
+        pass.dispatch_workgroups(wg, wg, wg);
+    }
+
+    copy_stats_readback(
+        encoder,
+        s.buffers.stats_buffer(),
+        s.buffers.energy_flow_buffer(),
+        s.buffers.stats_staging_buffer(),
     );
 }
+
+/// Diffusion sub-steps 2..=`substeps`: copy the last result back over the
+/// tick's temperature input, which nothing reads after the first sub-step,
+/// and diffuse again with the first sub-step's bind group.
+#[allow(clippy::too_many_arguments)]
+fn encode_temperature_substeps(
+    encoder: &mut wgpu::CommandEncoder,
+    pipeline: &wgpu::ComputePipeline,
+    temp_bg: &wgpu::BindGroup,
+    temp_read: &wgpu::Buffer,
+    temp_write: &wgpu::Buffer,
+    workgroups: [u32; 3],
+    substeps: u32,
+    timer: &mut PassTimer,
+) {
+    for step in 1..substeps {
+        encoder.copy_buffer_to_buffer(temp_write, 0, temp_read, 0, temp_write.size());
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("temperature_substep_pass"),
+            timestamp_writes: timer.writes(ProfilePass::Temperature, false, step + 1 == substeps),
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, temp_bg, &[]);
+        pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
+    }
+}
+
+/// Empty the cells of pool slots allocated since the last tick and reset
+/// them to ambient temperature, in both parities. Everything is recorded
+/// into `encoder`, like the brick table upload: border allocation runs
+/// between ticks of one submission, and queue writes would land before the
+/// earlier ticks. clear_buffer only zeroes, so the temperatures are copied
+/// from the ambient brick.
+fn clear_fresh_bricks(encoder: &mut wgpu::CommandEncoder, s: &mut SparseMode) {
+    // Bytes of one brick's voxels (512 × 32 B) and temperatures (512 × 4 B)
+    let (voxel_bytes, temp_bytes) = (512 * 32, 512 * 4);
+    for slot in s.grid.take_fresh_slots() {
+        let slot = slot as u64;
+        encoder.clear_buffer(s.buffers.pool_a(), slot * voxel_bytes, Some(voxel_bytes));
+        encoder.clear_buffer(s.buffers.pool_b(), slot * voxel_bytes, Some(voxel_bytes));
+        encoder.copy_buffer_to_buffer(s.buffers.ambient_brick(), 0, s.buffers.temp_pool_a(), slot * temp_bytes, temp_bytes);
+        encoder.copy_buffer_to_buffer(s.buffers.ambient_brick(), 0, s.buffers.temp_pool_b(), slot * temp_bytes, temp_bytes);
+    }
+}
+
+/// Stage the stats and the energy-flow counters, then start a new flow window.
+fn copy_stats_readback(
+    encoder: &mut wgpu::CommandEncoder,
+    stats: &wgpu::Buffer,
+    energy_flow: &wgpu::Buffer,
+    staging: &wgpu::Buffer,
+) {
+    use crate::stats::{ENERGY_FLOW_BYTES, STATS_BYTES};
+    encoder.copy_buffer_to_buffer(stats, 0, staging, 0, STATS_BYTES);
+    encoder.copy_buffer_to_buffer(energy_flow, 0, staging, STATS_BYTES, ENERGY_FLOW_BYTES);
+    encoder.clear_buffer(energy_flow, 0, None);
+}
@@ -1,8 +1,30 @@
 use wgpu;
-use types::SimParams;
+use wgpu::util::DeviceExt;
+use types::{RuleParams, SimParams, TickParams};
+
+/// Size of the rule-params uniform: `layout::RULE_PARAMS_WORDS` u32.
+pub const RULES_BYTES: u64 = types::layout::RULE_PARAMS_WORDS as u64 * 4;
+/// `TickParams` in layout.wgsl: four u32.
+pub const TICK_PARAMS_BYTES: u64 = 16;
+/// Stride between tick slots: WebGPU's minimum uniform offset alignment.
+const TICK_SLOT_BYTES: u64 = 256;
+/// Tick slots in the ring. Ticks recorded into one submit each take their
+/// own slot, so a submit may carry at most this many ticks.
+pub const TICK_SLOTS: u32 = 64;
 
 pub struct ParamsUniform {
     pub buffer: wgpu::Buffer,
+    /// `RuleParams` for the resolve pass.
+    pub rules: wgpu::Buffer,
+    /// Ring of `TickParams` slots, bound with a dynamic offset.
+    pub tick: wgpu::Buffer,
+    /// Bytes of the last `buffer` upload; an unchanged config is skipped.
+    uploaded: Vec<u8>,
+    /// Last `rules` upload, likewise.
+    uploaded_rules: RuleParams,
+    /// Slot of the latest `write_tick`.
+    tick_slot: u32,
+    latest_tick: TickParams,
 }
 
 impl ParamsUniform {
@@ -14,10 +36,86 @@ impl ParamsUniform {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
-        Self { buffer }
+        let rules = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("rule_params"),
+            contents: bytemuck::cast_slice(&RuleParams::default().to_words()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        // Zeroed at creation: tick 0, normal overlay in every slot
+        let tick = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("tick_params"),
+            size: TICK_SLOTS as u64 * TICK_SLOT_BYTES,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            rules,
+            tick,
+            uploaded: Vec::new(),
+            uploaded_rules: RuleParams::default(),
+            tick_slot: 0,
+            latest_tick: TickParams::default(),
+        }
+    }
+
+    /// Upload `params` if they differ from the last upload.
+    pub fn upload(&mut self, queue: &wgpu::Queue, params: &SimParams) {
+        let data = params.to_bytes();
+        if data != self.uploaded {
+            queue.write_buffer(&self.buffer, 0, &data);
+            self.uploaded = data;
+        }
+    }
+
+    /// Upload `rules` if they differ from the last upload.
+    pub fn upload_rules(&mut self, queue: &wgpu::Queue, rules: &RuleParams) {
+        if *rules != self.uploaded_rules {
+            queue.write_buffer(&self.rules, 0, bytemuck::cast_slice(&rules.to_words()));
+            self.uploaded_rules = *rules;
+        }
+    }
+
+    /// Write `tick` into the next slot and return its dynamic offset.
+    pub fn write_tick(&mut self, queue: &wgpu::Queue, tick: TickParams) -> u32 {
+        self.tick_slot = (self.tick_slot + 1) % TICK_SLOTS;
+        self.latest_tick = tick;
+        let offset = self.tick_slot * TICK_SLOT_BYTES as u32;
+        queue.write_buffer(&self.tick, offset as u64, bytemuck::cast_slice(&tick.to_words()));
+        offset
+    }
+
+    /// Rewrite the latest slot with a new overlay mode and trait byte (passes
+    /// that read it after the last tick, e.g. the render texture).
+    pub fn set_overlay_mode(&mut self, queue: &wgpu::Queue, overlay_mode: u32, overlay_trait: u32) {
+        if (self.latest_tick.overlay_mode, self.latest_tick.overlay_trait) != (overlay_mode, overlay_trait) {
+            self.latest_tick.overlay_mode = overlay_mode;
+            self.latest_tick.overlay_trait = overlay_trait;
+            let offset = self.tick_slot as u64 * TICK_SLOT_BYTES;
+            queue.write_buffer(&self.tick, offset, bytemuck::cast_slice(&self.latest_tick.to_words()));
+        }
+    }
+
+    pub fn latest_tick(&self) -> TickParams {
+        self.latest_tick
+    }
+
+    /// Binding for slot 0; the tick passes pick their slot with a dynamic
+    /// offset.
+    pub fn tick_binding(&self) -> wgpu::BindingResource<'_> {
+        self.tick_slot_binding(0)
+    }
+
+    /// Static binding for the latest slot.
+    pub fn latest_tick_binding(&self) -> wgpu::BindingResource<'_> {
+        self.tick_slot_binding(self.tick_slot)
     }
 
-    pub fn upload(&self, queue: &wgpu::Queue, params: &SimParams) {
-        queue.write_buffer(&self.buffer, 0, &params.to_bytes());
+    fn tick_slot_binding(&self, slot: u32) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.tick,
+            offset: slot as u64 * TICK_SLOT_BYTES,
+            size: wgpu::BufferSize::new(TICK_PARAMS_BYTES),
+        })
     }
 }
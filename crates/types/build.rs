@@ -0,0 +1,12 @@
+//! Renders `layout.rs` into `$OUT_DIR/layout.wgsl` for `SHADER_SOURCES`.
+
+#[allow(dead_code)]
+#[path = "src/layout.rs"]
+mod layout;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/layout.rs");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let path = std::path::Path::new(&out_dir).join("layout.wgsl");
+    std::fs::write(&path, layout::to_wgsl()).expect("write layout.wgsl");
+}
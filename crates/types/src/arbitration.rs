@@ -0,0 +1,211 @@
+//! Conflict arbitration: how the resolve pass picks one winner among the
+//! neighbours contending for the same cell, and how often it had to.
+
+/// Counter word (in the energy-flow buffer) of cells contested by two or
+/// more REPLICATE/MOVE intents.
+pub const CONFLICT_PLACEMENT_WORD: usize = 6;
+/// Counter word of protocells targeted by two or more predators.
+pub const CONFLICT_PREDATION_WORD: usize = 7;
+/// Counter word of REPLICATE/MOVE intents that lost a contest.
+pub const REJECTED_PLACEMENT_WORD: usize = 8;
+/// Counter word of PREDATE intents that lost a contest.
+pub const REJECTED_PREDATION_WORD: usize = 9;
+
+/// Winner selection among contenders for one cell. The discriminant is
+/// `RuleParams.arbitration` in resolve_execute.wgsl.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArbitrationPolicy {
+    /// Highest bid wins; ties go to the higher voxel index.
+    #[default]
+    HighestBid = 0,
+    /// Random winner with odds proportional to bid.
+    WeightedBid = 1,
+    /// Random winner regardless of bid, by hashed priority.
+    HashedPriority = 2,
+}
+
+/// A neighbour whose intent targets the contested cell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Contender {
+    /// Direction from the contested cell to the contender.
+    pub dir: u32,
+    /// Voxel index of the contender.
+    pub index: u32,
+    pub bid: u32,
+}
+
+/// Matches `pcg_hash` in common.wgsl.
+pub fn pcg_hash(input: u32) -> u32 {
+    let state = input.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+impl ArbitrationPolicy {
+    pub const ALL: [ArbitrationPolicy; 3] =
+        [ArbitrationPolicy::HighestBid, ArbitrationPolicy::WeightedBid, ArbitrationPolicy::HashedPriority];
+
+    pub fn from_u32(v: u32) -> Option<Self> {
+        Self::ALL.get(v as usize).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ArbitrationPolicy::HighestBid => "highest_bid",
+            ArbitrationPolicy::WeightedBid => "weighted_bid",
+            ArbitrationPolicy::HashedPriority => "hashed_priority",
+        }
+    }
+
+    /// Index of the winner among `contenders` (which must all have a
+    /// nonzero bid; zero bids never win). `seed` is the per-cell, per-tick
+    /// hash both sides of a contest derive independently. Matches
+    /// `arbitrate` in resolve_execute.wgsl.
+    pub fn pick(self, contenders: &[Contender], seed: u32) -> Option<usize> {
+        if contenders.is_empty() {
+            return None;
+        }
+        let mut best = 0;
+        match self {
+            ArbitrationPolicy::HighestBid => {
+                for (i, c) in contenders.iter().enumerate().skip(1) {
+                    let b = &contenders[best];
+                    if c.bid > b.bid || (c.bid == b.bid && c.index > b.index) {
+                        best = i;
+                    }
+                }
+            }
+            ArbitrationPolicy::WeightedBid => {
+                let total = contenders.iter().fold(0u32, |t, c| t.wrapping_add(c.bid));
+                let mut r = pcg_hash(seed) % total.max(1);
+                for (i, c) in contenders.iter().enumerate() {
+                    if r < c.bid {
+                        return Some(i);
+                    }
+                    r -= c.bid;
+                }
+                best = contenders.len() - 1;
+            }
+            ArbitrationPolicy::HashedPriority => {
+                let priority = |c: &Contender| pcg_hash(seed ^ c.dir.wrapping_mul(0x9E37_79B9));
+                let mut best_priority = priority(&contenders[0]);
+                for (i, c) in contenders.iter().enumerate().skip(1) {
+                    let p = priority(c);
+                    if p > best_priority {
+                        best_priority = p;
+                        best = i;
+                    }
+                }
+            }
+        }
+        Some(best)
+    }
+}
+
+/// Contested cells and rejected intents over a stats window, counted by
+/// the resolve pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConflictCounts {
+    /// Empty cells with two or more REPLICATE/MOVE contenders.
+    pub placement: u32,
+    /// Protocells with two or more predators.
+    pub predation: u32,
+    /// REPLICATE/MOVE intents that lost to another contender.
+    pub rejected_placement: u32,
+    /// PREDATE intents that lost to another predator.
+    pub rejected_predation: u32,
+}
+
+impl ConflictCounts {
+    /// Parse the energy-flow counter buffer; missing words read as zero.
+    pub fn from_words(words: &[u32]) -> Self {
+        let word = |i: usize| words.get(i).copied().unwrap_or(0);
+        ConflictCounts {
+            placement: word(CONFLICT_PLACEMENT_WORD),
+            predation: word(CONFLICT_PREDATION_WORD),
+            rejected_placement: word(REJECTED_PLACEMENT_WORD),
+            rejected_predation: word(REJECTED_PREDATION_WORD),
+        }
+    }
+
+    /// Record a contest with `contenders` intents for one cell.
+    pub fn add_placement(&mut self, contenders: u32) {
+        if contenders > 1 {
+            self.placement = self.placement.wrapping_add(1);
+            self.rejected_placement = self.rejected_placement.wrapping_add(contenders - 1);
+        }
+    }
+
+    /// Record a contest with `predators` PREDATE intents for one cell.
+    pub fn add_predation(&mut self, predators: u32) {
+        if predators > 1 {
+            self.predation = self.predation.wrapping_add(1);
+            self.rejected_predation = self.rejected_predation.wrapping_add(predators - 1);
+        }
+    }
+
+    /// Cells contested per tick over a window of `ticks` ticks.
+    pub fn contested_per_tick(&self, ticks: u32) -> Option<f32> {
+        (ticks > 0).then(|| (self.placement as f32 + self.predation as f32) / ticks as f32)
+    }
+
+    /// Intents rejected per tick over a window of `ticks` ticks.
+    pub fn rejected_per_tick(&self, ticks: u32) -> Option<f32> {
+        (ticks > 0).then(|| (self.rejected_placement as f32 + self.rejected_predation as f32) / ticks as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contender(dir: u32, index: u32, bid: u32) -> Contender {
+        Contender { dir, index, bid }
+    }
+
+    #[test]
+    fn highest_bid_prefers_bid_then_index() {
+        let policy = ArbitrationPolicy::HighestBid;
+        assert_eq!(policy.pick(&[], 0), None);
+        let cs = [contender(0, 10, 5), contender(1, 30, 9), contender(2, 20, 9)];
+        assert_eq!(policy.pick(&cs, 0), Some(1));
+        assert_eq!(policy.pick(&cs[..1], 0), Some(0));
+        assert_eq!(ArbitrationPolicy::from_u32(2), Some(ArbitrationPolicy::HashedPriority));
+        assert_eq!(ArbitrationPolicy::from_u32(3), None);
+    }
+
+    #[test]
+    fn random_policies_are_seeded_and_weighted() {
+        let cs = [contender(0, 1, 1), contender(3, 2, 99)];
+        for policy in [ArbitrationPolicy::WeightedBid, ArbitrationPolicy::HashedPriority] {
+            for seed in 0..16 {
+                assert_eq!(policy.pick(&cs, seed), policy.pick(&cs, seed));
+            }
+        }
+        let heavy = (0..1000).filter(|&s| ArbitrationPolicy::WeightedBid.pick(&cs, s) == Some(1)).count();
+        assert!(heavy > 950);
+        // Hashed priority ignores the bid
+        let heavy = (0..1000).filter(|&s| ArbitrationPolicy::HashedPriority.pick(&cs, s) == Some(1)).count();
+        assert!((350..650).contains(&heavy));
+    }
+
+    #[test]
+    fn parses_conflict_words() {
+        let counts = ConflictCounts::from_words(&[0, 0, 0, 0, 0, 3, 11, 4, 15, 5, 0, 0]);
+        assert_eq!(counts, ConflictCounts { placement: 11, predation: 4, rejected_placement: 15, rejected_predation: 5 });
+        assert_eq!(counts.contested_per_tick(3), Some(5.0));
+        assert_eq!(counts.rejected_per_tick(4), Some(5.0));
+        assert_eq!(counts.rejected_per_tick(0), None);
+        assert_eq!(ConflictCounts::from_words(&[1]), ConflictCounts::default());
+    }
+
+    #[test]
+    fn contests_count_losers() {
+        let mut counts = ConflictCounts::default();
+        counts.add_placement(1);
+        counts.add_placement(3);
+        counts.add_predation(0);
+        counts.add_predation(2);
+        assert_eq!(counts, ConflictCounts { placement: 1, predation: 1, rejected_placement: 2, rejected_predation: 1 });
+    }
+}
@@ -0,0 +1,211 @@
+//! Per-frame budgeting of the optional GPU work around the sim ticks.
+//! Ticks always run; when frames run over the target, the auxiliary passes
+//! are shed by their measured cost, least important first, until the shed
+//! work covers the overrun. Each pass has a cap on consecutive deferrals so
+//! none starves.
+
+/// Auxiliary work the planner may defer, least important first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuxPass {
+    /// Brick occupancy readback ahead of a sparse upgrade.
+    Occupancy = 0,
+    /// The minimap's top-down density heatmap.
+    Heatmap = 1,
+    /// Stats reduction and readback.
+    Stats = 2,
+    /// Volume texture refresh from the voxel buffers.
+    RenderTexture = 3,
+}
+
+impl AuxPass {
+    pub const ALL: [AuxPass; 4] = [AuxPass::Occupancy, AuxPass::Heatmap, AuxPass::Stats, AuxPass::RenderTexture];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AuxPass::Occupancy => "occupancy",
+            AuxPass::Heatmap => "heatmap",
+            AuxPass::Stats => "stats",
+            AuxPass::RenderTexture => "render_texture",
+        }
+    }
+
+    /// Longest run of frames a wanted pass may be deferred.
+    pub fn max_deferrals(self) -> u32 {
+        match self {
+            AuxPass::Occupancy => 10,
+            AuxPass::Heatmap => 4,
+            AuxPass::Stats => 30,
+            AuxPass::RenderTexture => 2,
+        }
+    }
+}
+
+/// Default target: 60 FPS.
+pub const DEFAULT_TARGET_FRAME_MS: f32 = 1000.0 / 60.0;
+
+/// Frames are considered over budget above this multiple of the target.
+pub const OVER_BUDGET_FACTOR: f32 = 1.5;
+
+/// EMA weight for the newest frame interval or pass cost.
+const AVG_ALPHA: f32 = 0.1;
+
+/// Which auxiliary passes run this frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AuxPlan {
+    run: [bool; 4],
+}
+
+impl AuxPlan {
+    pub fn runs(&self, pass: AuxPass) -> bool {
+        self.run[pass as usize]
+    }
+
+    /// Run `pass` regardless of the budget.
+    pub fn force(&mut self, pass: AuxPass) {
+        self.run[pass as usize] = true;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuxBudget {
+    /// Target frame time in ms. 0 disables the planner (everything runs).
+    pub target_frame_ms: f32,
+    /// Smoothed frame interval in ms.
+    pub avg_frame_ms: f32,
+    /// Smoothed measured cost in ms of each pass when it ran.
+    cost_ms: [f32; 4],
+    /// Consecutive frames each wanted pass was deferred.
+    deferrals: [u32; 4],
+}
+
+impl Default for AuxBudget {
+    fn default() -> Self {
+        Self {
+            target_frame_ms: DEFAULT_TARGET_FRAME_MS,
+            avg_frame_ms: DEFAULT_TARGET_FRAME_MS,
+            cost_ms: [0.0; 4],
+            deferrals: [0; 4],
+        }
+    }
+}
+
+impl AuxBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_target_frame_ms(&mut self, ms: f32) {
+        self.target_frame_ms = ms.max(0.0);
+    }
+
+    pub fn is_over_budget(&self) -> bool {
+        self.target_frame_ms > 0.0 && self.avg_frame_ms > self.target_frame_ms * OVER_BUDGET_FACTOR
+    }
+
+    /// Smoothed cost of `pass` in ms, 0 until it was measured.
+    pub fn cost_ms(&self, pass: AuxPass) -> f32 {
+        self.cost_ms[pass as usize]
+    }
+
+    /// Fold in the measured cost of a run of `pass`. The first measurement
+    /// is taken as is.
+    pub fn record_cost(&mut self, pass: AuxPass, ms: f32) {
+        let ms = ms.clamp(0.0, 1000.0);
+        let cost = &mut self.cost_ms[pass as usize];
+        *cost = if *cost == 0.0 { ms } else { *cost + (ms - *cost) * AVG_ALPHA };
+    }
+
+    /// Plan this frame, `dt` seconds after the last. `wanted` flags the
+    /// passes with work to do, indexed by `AuxPass`. Over budget, wanted
+    /// passes are deferred least important first until their measured cost
+    /// covers the overrun (any unmeasured pass is deferred too), except
+    /// those at their deferral cap.
+    pub fn plan(&mut self, dt: f32, wanted: [bool; 4]) -> AuxPlan {
+        let frame_ms = (dt * 1000.0).clamp(0.0, 1000.0);
+        self.avg_frame_ms += (frame_ms - self.avg_frame_ms) * AVG_ALPHA;
+
+        let mut excess = if self.is_over_budget() { self.avg_frame_ms - self.target_frame_ms } else { 0.0 };
+        let mut plan = AuxPlan { run: wanted };
+        for pass in AuxPass::ALL {
+            let i = pass as usize;
+            if !wanted[i] {
+                self.deferrals[i] = 0;
+                continue;
+            }
+            if excess > 0.0 && self.deferrals[i] < pass.max_deferrals() {
+                plan.run[i] = false;
+                self.deferrals[i] += 1;
+                // Unmeasured passes shed nothing known; keep shedding
+                excess -= self.cost_ms[i];
+            } else {
+                self.deferrals[i] = 0;
+            }
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_WANTED: [bool; 4] = [true; 4];
+
+    fn over_budget() -> AuxBudget {
+        AuxBudget { avg_frame_ms: 40.0, ..AuxBudget::new() }
+    }
+
+    #[test]
+    fn within_budget_everything_wanted_runs() {
+        let mut budget = AuxBudget::new();
+        let plan = budget.plan(1.0 / 60.0, ALL_WANTED);
+        assert!(AuxPass::ALL.iter().all(|&p| plan.runs(p)));
+        let plan = budget.plan(1.0 / 60.0, [false, true, false, true]);
+        assert!(!plan.runs(AuxPass::Stats) && plan.runs(AuxPass::Heatmap));
+        // A disabled planner never defers
+        let mut off = AuxBudget { target_frame_ms: 0.0, ..over_budget() };
+        assert_eq!(off.plan(0.1, ALL_WANTED), AuxPlan { run: ALL_WANTED });
+    }
+
+    #[test]
+    fn over_budget_sheds_by_measured_cost() {
+        let mut budget = over_budget();
+        assert!(budget.is_over_budget());
+        budget.record_cost(AuxPass::Occupancy, 5.0);
+        budget.record_cost(AuxPass::Heatmap, 30.0);
+        budget.record_cost(AuxPass::Stats, 2.0);
+        budget.record_cost(AuxPass::RenderTexture, 4.0);
+        // ~22 ms over: the occupancy readback and heatmap cover it
+        let plan = budget.plan(0.04, ALL_WANTED);
+        assert!(!plan.runs(AuxPass::Occupancy) && !plan.runs(AuxPass::Heatmap));
+        assert!(plan.runs(AuxPass::Stats) && plan.runs(AuxPass::RenderTexture));
+        // Cheap passes are not enough: everything is shed
+        let mut cheap = over_budget();
+        for pass in AuxPass::ALL {
+            cheap.record_cost(pass, 1.0);
+        }
+        assert_eq!(cheap.plan(0.04, ALL_WANTED), AuxPlan::default());
+        cheap.record_cost(AuxPass::Stats, 11.0);
+        assert!((cheap.cost_ms(AuxPass::Stats) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn deferral_caps_force_passes_through() {
+        let mut budget = over_budget();
+        for frame in 1..=AuxPass::RenderTexture.max_deferrals() {
+            let plan = budget.plan(0.04, ALL_WANTED);
+            assert!(!plan.runs(AuxPass::RenderTexture), "frame {frame}");
+        }
+        let plan = budget.plan(0.04, ALL_WANTED);
+        assert!(plan.runs(AuxPass::RenderTexture));
+        assert!(!plan.runs(AuxPass::Stats));
+        // The count restarts after a forced run
+        assert!(!budget.plan(0.04, ALL_WANTED).runs(AuxPass::RenderTexture));
+        // A pass with no work this frame does not accumulate deferrals
+        let mut idle = over_budget();
+        for _ in 0..50 {
+            idle.plan(0.04, [false, false, false, true]);
+        }
+        assert!(!idle.plan(0.04, ALL_WANTED).runs(AuxPass::Stats));
+    }
+}
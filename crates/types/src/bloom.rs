@@ -0,0 +1,66 @@
+//! Scheduled nutrient blooms: periodic FillRegion pulses generated by the
+//! engine rather than the player.
+
+use crate::{Command, VoxelType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NutrientBloom {
+    /// Ticks between pulses (0 disables the bloom).
+    pub period: u32,
+    /// Tick within the period at which the pulse fires.
+    pub phase: u32,
+    /// Fraction (0..1) of empty voxels in the region that become nutrient.
+    pub amplitude: f32,
+    /// Inclusive region corners.
+    pub min: (u32, u32, u32),
+    pub max: (u32, u32, u32),
+}
+
+impl NutrientBloom {
+    /// True if a pulse falls in ticks `start..start + ticks`.
+    pub fn fires_in(&self, start: u32, ticks: u32) -> bool {
+        if self.period == 0 || ticks == 0 {
+            return false;
+        }
+        let phase = self.phase % self.period;
+        let wait = (phase + self.period - start % self.period) % self.period;
+        wait < ticks
+    }
+
+    pub fn command(&self) -> Command {
+        let density = (self.amplitude.clamp(0.0, 1.0) * 255.0) as u8;
+        Command::fill_region(self.min, self.max, VoxelType::Nutrient as u32, density)
+    }
+}
+
+/// FillRegion commands for every bloom pulsing in ticks `start..start + ticks`.
+pub fn bloom_commands(blooms: &[NutrientBloom], start: u32, ticks: u32) -> Vec<Command> {
+    blooms.iter().filter(|b| b.fires_in(start, ticks)).map(NutrientBloom::command).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bloom(period: u32, phase: u32) -> NutrientBloom {
+        NutrientBloom { period, phase, amplitude: 0.5, min: (0, 0, 0), max: (7, 7, 7) }
+    }
+
+    #[test]
+    fn fires_once_per_period() {
+        let b = bloom(100, 30);
+        let hits: Vec<u32> = (0..300).filter(|&t| b.fires_in(t, 1)).collect();
+        assert_eq!(hits, vec![30, 130, 230]);
+        assert!(b.fires_in(25, 10));
+        assert!(!b.fires_in(31, 99));
+        assert!(!bloom(0, 0).fires_in(0, 1000));
+    }
+
+    #[test]
+    fn bloom_command_is_nutrient_fill() {
+        let cmds = bloom_commands(&[bloom(10, 0), bloom(10, 5)], 0, 1);
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].param_0 & 0xFF, VoxelType::Nutrient as u32);
+        assert_eq!(cmds[0].param_0 >> 8, 127);
+    }
+}
@@ -0,0 +1,111 @@
+//! Sparse brick pool pressure. Growth stops silently once the pool is
+//! full (border bricks go unallocated, protocells stop at the edge), so the
+//! engine counts failed allocations and the host warns while few bricks
+//! are left.
+
+/// Occupancy of a sparse engine's brick pool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BrickPoolStats {
+    pub capacity: u32,
+    pub allocated: u32,
+    /// Most bricks allocated at once since the last reset.
+    pub high_water: u32,
+    /// Brick allocations refused for want of a free slot since the last
+    /// reset; border allocation retries count each time.
+    pub failed_allocations: u64,
+}
+
+impl BrickPoolStats {
+    pub fn free(&self) -> u32 {
+        self.capacity.saturating_sub(self.allocated)
+    }
+
+    /// Allocated fraction of the pool, 0..1.
+    pub fn used_fraction(&self) -> f32 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        self.allocated as f32 / self.capacity as f32
+    }
+}
+
+/// Default `PoolAlarm` threshold, as a fraction of the pool.
+pub const DEFAULT_POOL_ALARM_FRACTION: f32 = 0.1;
+
+/// Fires once when free bricks drop below a threshold, then stays quiet
+/// until a quarter of the threshold again is free above it, so a pool
+/// hovering at the line does not warn every stats readback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolAlarm {
+    /// Free bricks below which the alarm fires; `None` uses
+    /// `DEFAULT_POOL_ALARM_FRACTION` of the pool.
+    threshold: Option<u32>,
+    armed: bool,
+}
+
+impl Default for PoolAlarm {
+    fn default() -> Self {
+        Self { threshold: None, armed: true }
+    }
+}
+
+impl PoolAlarm {
+    pub fn new(threshold: Option<u32>) -> Self {
+        Self { threshold, ..Self::default() }
+    }
+
+    /// Change the threshold and rearm.
+    pub fn set_threshold(&mut self, threshold: Option<u32>) {
+        *self = Self::new(threshold);
+    }
+
+    /// Warn again on the next low reading, as for a new pool.
+    pub fn rearm(&mut self) {
+        self.armed = true;
+    }
+
+    /// Free bricks below which `pool` warns.
+    pub fn threshold_for(&self, pool: &BrickPoolStats) -> u32 {
+        self.threshold.unwrap_or((pool.capacity as f32 * DEFAULT_POOL_ALARM_FRACTION).ceil() as u32)
+    }
+
+    /// Whether this reading should warn.
+    pub fn update(&mut self, pool: &BrickPoolStats) -> bool {
+        let threshold = self.threshold_for(pool);
+        let free = pool.free();
+        if self.armed && free < threshold {
+            self.armed = false;
+            return true;
+        }
+        if !self.armed && free >= threshold + threshold.div_ceil(4) {
+            self.armed = true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alarm_fires_once_until_the_pool_recovers() {
+        let pool = |allocated| BrickPoolStats { capacity: 100, allocated, ..BrickPoolStats::default() };
+        let mut alarm = PoolAlarm::default();
+        assert_eq!(alarm.threshold_for(&pool(0)), 10);
+        assert!(!alarm.update(&pool(80)));
+        assert!(alarm.update(&pool(91)));
+        assert!(!alarm.update(&pool(95)));
+        // 11 free is back over the line but within the hysteresis band
+        assert!(!alarm.update(&pool(89)));
+        assert!(!alarm.update(&pool(91)));
+        assert!(!alarm.update(&pool(87)));
+        assert!(alarm.update(&pool(91)));
+
+        let mut fixed = PoolAlarm::new(Some(3));
+        assert!(!fixed.update(&pool(97)));
+        assert!(fixed.update(&pool(98)));
+        assert_eq!(pool(98).free(), 2);
+        assert_eq!(BrickPoolStats::default().used_fraction(), 0.0);
+    }
+}
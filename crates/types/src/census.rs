@@ -0,0 +1,189 @@
+//! Census queries: a GPU filter pass appends the coordinates of every voxel
+//! matching a predicate to a result buffer that is read back asynchronously.
+//!
+//! Result buffer: [0] total matches (keeps counting past the cap),
+//! [1..=max_results] packed coordinates, 10 bits per axis as in
+//! `pack_region_max`.
+
+use crate::{pack_region_max, unpack_region_max, CellBox, Voxel, VoxelType};
+
+/// Coordinates a census readback holds at most.
+pub const MAX_CENSUS_RESULTS: u32 = 4096;
+
+/// Size of the census result buffer and its staging copy.
+pub const CENSUS_RESULT_BYTES: u64 = (1 + MAX_CENSUS_RESULTS as u64) * 4;
+
+/// `voxel_type` word in the query uniform meaning "any type".
+pub const CENSUS_ANY_TYPE: u32 = 0xFFFF_FFFF;
+
+/// Predicate over voxel fields. Every condition must hold; ranges are
+/// inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CensusQuery {
+    /// `None` matches every type, including empty.
+    pub voxel_type: Option<VoxelType>,
+    /// 0 matches any species.
+    pub species_id: u16,
+    pub energy: (u16, u16),
+    pub age: (u16, u16),
+    /// Matches when `flags & flags_mask == flags_value`.
+    pub flags_mask: u8,
+    pub flags_value: u8,
+    /// Coordinates to keep (0..=MAX_CENSUS_RESULTS); 0 only counts.
+    pub max_results: u32,
+    /// Inclusive box searched; `None` searches the whole grid.
+    pub region: Option<CellBox>,
+}
+
+impl Default for CensusQuery {
+    fn default() -> Self {
+        Self {
+            voxel_type: None,
+            species_id: 0,
+            energy: (0, u16::MAX),
+            age: (0, u16::MAX),
+            flags_mask: 0,
+            flags_value: 0,
+            max_results: MAX_CENSUS_RESULTS,
+            region: None,
+        }
+    }
+}
+
+impl CensusQuery {
+    /// All protocells of `species_id`.
+    pub fn species(species_id: u16, max_results: u32) -> Self {
+        Self {
+            voxel_type: Some(VoxelType::Protocell),
+            species_id,
+            ..Self::default()
+        }
+        .with_max_results(max_results)
+    }
+
+    /// Count (without listing) live protocells inside an inclusive box.
+    pub fn live_in_region(min: (u32, u32, u32), max: (u32, u32, u32)) -> Self {
+        Self {
+            voxel_type: Some(VoxelType::Protocell),
+            max_results: 0,
+            region: Some((min, max)),
+            ..Self::default()
+        }
+    }
+
+    /// Box searched, clipped to a grid of `grid_size`.
+    pub fn bounds(&self, grid_size: u32) -> CellBox {
+        let last = grid_size - 1;
+        let (lo, hi) = self.region.unwrap_or(((0, 0, 0), (last, last, last)));
+        (lo, (hi.0.min(last), hi.1.min(last), hi.2.min(last)))
+    }
+
+    pub fn with_max_results(mut self, max_results: u32) -> Self {
+        self.max_results = max_results.min(MAX_CENSUS_RESULTS);
+        self
+    }
+
+    /// CPU evaluation of the predicate, matching census.wgsl.
+    pub fn matches(&self, v: &Voxel) -> bool {
+        self.voxel_type.is_none_or(|t| v.voxel_type == t)
+            && (self.species_id == 0 || v.species_id == self.species_id)
+            && (self.energy.0..=self.energy.1).contains(&v.energy)
+            && (self.age.0..=self.age.1).contains(&v.age)
+            && v.flags & self.flags_mask == self.flags_value
+    }
+
+    /// Query uniform (census.wgsl `CensusParams`).
+    pub fn to_words(&self, grid_size: u32, sparse: bool, brick_grid_dim: u32) -> [u32; 16] {
+        let (lo, hi) = self.bounds(grid_size);
+        [
+            grid_size,
+            sparse as u32,
+            brick_grid_dim,
+            self.max_results,
+            self.voxel_type.map_or(CENSUS_ANY_TYPE, |t| t as u32),
+            self.species_id as u32,
+            self.energy.0 as u32,
+            self.energy.1 as u32,
+            self.age.0 as u32,
+            self.age.1 as u32,
+            self.flags_mask as u32,
+            self.flags_value as u32,
+            pack_region_max(lo.0, lo.1, lo.2),
+            pack_region_max(hi.0, hi.1, hi.2),
+            0,
+            0,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CensusResult {
+    pub query: CensusQuery,
+    /// Matching voxels in the grid, including those past the cap.
+    pub total: u32,
+    pub coords: Vec<(u32, u32, u32)>,
+}
+
+impl CensusResult {
+    pub fn from_words(query: CensusQuery, words: &[u32]) -> Self {
+        let total = words[0];
+        let n = total.min(query.max_results) as usize;
+        let coords = words[1..1 + n].iter().map(|&w| unpack_region_max(w)).collect();
+        Self { query, total, coords }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FLAG_DORMANT;
+
+    #[test]
+    fn species_query_layout() {
+        assert_eq!(CensusQuery::species(7, 0).max_results, 0);
+        assert_eq!(CensusQuery::species(7, 1 << 20).max_results, MAX_CENSUS_RESULTS);
+        let words = CensusQuery::species(7, 10).to_words(128, true, 16);
+        assert_eq!(words[..6], [128, 1, 16, 10, VoxelType::Protocell as u32, 7]);
+        let whole = CensusQuery::default().to_words(64, false, 0);
+        assert_eq!(whole[4], CENSUS_ANY_TYPE);
+        assert_eq!(whole[12..14], [0, pack_region_max(63, 63, 63)]);
+        let boxed = CensusQuery::live_in_region((1, 2, 3), (9, 99, 9)).to_words(64, false, 0);
+        assert_eq!(boxed[12..14], [pack_region_max(1, 2, 3), pack_region_max(9, 63, 9)]);
+    }
+
+    #[test]
+    fn predicate_matches_all_fields() {
+        let mut v = Voxel {
+            voxel_type: VoxelType::Protocell,
+            energy: 300,
+            age: 40,
+            species_id: 9,
+            flags: FLAG_DORMANT,
+            ..Voxel::default()
+        };
+        let q = CensusQuery {
+            voxel_type: Some(VoxelType::Protocell),
+            energy: (200, 400),
+            age: (0, 50),
+            flags_mask: FLAG_DORMANT,
+            flags_value: FLAG_DORMANT,
+            ..CensusQuery::default()
+        };
+        assert!(q.matches(&v));
+        v.energy = 401;
+        assert!(!q.matches(&v));
+        v.energy = 300;
+        v.flags = 0;
+        assert!(!q.matches(&v));
+        assert!(CensusQuery::default().matches(&Voxel::default()));
+    }
+
+    #[test]
+    fn result_truncates_to_cap() {
+        let query = CensusQuery::species(3, 2);
+        let words = [5, pack_region_max(1, 2, 3), pack_region_max(255, 0, 9), 0, 0];
+        let result = CensusResult::from_words(query, &words);
+        assert_eq!(result.total, 5);
+        assert_eq!(result.coords, vec![(1, 2, 3), (255, 0, 9)]);
+    }
+}
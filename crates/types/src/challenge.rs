@@ -0,0 +1,148 @@
+//! Fitness challenges: per-species scores for reaching and holding the goal
+//! zone, accumulated from sampled goal-zone populations.
+
+/// Scoring rules for a goal-zone challenge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChallengeRules {
+    /// Awarded once, the first time a species is seen in the zone.
+    pub reach_points: u32,
+    /// Zone population a species must keep to count as holding it.
+    pub min_population: u32,
+    /// Consecutive ticks of holding that complete the challenge.
+    pub hold_ticks: u32,
+    /// Awarded once on completion.
+    pub completion_points: u32,
+}
+
+impl Default for ChallengeRules {
+    fn default() -> Self {
+        Self { reach_points: 100, min_population: 10, hold_ticks: 1000, completion_points: 1000 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeciesScore {
+    pub species_id: u16,
+    pub score: u32,
+    pub reached_tick: Option<u32>,
+    /// Start of the current hold, if the species is holding the zone.
+    pub held_since: Option<u32>,
+    pub completed_tick: Option<u32>,
+}
+
+/// Scores updated from each stats sample. Holding earns one point per tick
+/// between samples; a species missing from a sample loses its hold.
+#[derive(Debug, Clone, Default)]
+pub struct ChallengeTracker {
+    pub rules: ChallengeRules,
+    scores: Vec<SpeciesScore>,
+    last_tick: Option<u32>,
+}
+
+impl ChallengeTracker {
+    pub fn new(rules: ChallengeRules) -> Self {
+        Self { rules, scores: Vec::new(), last_tick: None }
+    }
+
+    /// Fold in one sample: `in_zone` is (species_id, protocells in zone) at `tick`.
+    pub fn update(&mut self, tick: u32, in_zone: &[(u16, u32)]) {
+        let elapsed = self.last_tick.map_or(0, |t| tick.saturating_sub(t));
+        self.last_tick = Some(tick);
+        let rules = self.rules;
+        for s in &mut self.scores {
+            let count = in_zone.iter().find(|&&(sid, _)| sid == s.species_id).map_or(0, |&(_, c)| c);
+            if count < rules.min_population.max(1) {
+                s.held_since = None;
+            }
+        }
+        for &(sid, count) in in_zone {
+            if sid == 0 || count == 0 {
+                continue;
+            }
+            let pos = match self.scores.iter().position(|s| s.species_id == sid) {
+                Some(pos) => pos,
+                None => {
+                    self.scores.push(SpeciesScore {
+                        species_id: sid,
+                        score: 0,
+                        reached_tick: None,
+                        held_since: None,
+                        completed_tick: None,
+                    });
+                    self.scores.len() - 1
+                }
+            };
+            let s = &mut self.scores[pos];
+            if s.reached_tick.is_none() {
+                s.reached_tick = Some(tick);
+                s.score += rules.reach_points;
+            }
+            if count >= rules.min_population.max(1) {
+                match s.held_since {
+                    Some(start) => {
+                        s.score += elapsed;
+                        if s.completed_tick.is_none() && tick - start >= rules.hold_ticks {
+                            s.completed_tick = Some(tick);
+                            s.score += rules.completion_points;
+                        }
+                    }
+                    None => s.held_since = Some(tick),
+                }
+            }
+        }
+        self.scores.sort_by_key(|s| (std::cmp::Reverse(s.score), s.species_id));
+    }
+
+    /// Scores, highest first.
+    pub fn scores(&self) -> &[SpeciesScore] {
+        &self.scores
+    }
+
+    pub fn reset(&mut self) {
+        self.scores.clear();
+        self.last_tick = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> ChallengeRules {
+        ChallengeRules { reach_points: 100, min_population: 5, hold_ticks: 20, completion_points: 1000 }
+    }
+
+    #[test]
+    fn reach_scores_once() {
+        let mut t = ChallengeTracker::new(rules());
+        t.update(0, &[(7, 1)]);
+        t.update(10, &[(7, 2)]);
+        assert_eq!(t.scores()[0].score, 100);
+        assert_eq!(t.scores()[0].reached_tick, Some(0));
+    }
+
+    #[test]
+    fn hold_completes_and_breaks() {
+        let mut t = ChallengeTracker::new(rules());
+        t.update(0, &[(3, 6)]);
+        t.update(10, &[(3, 6)]);
+        t.update(20, &[(3, 6)]);
+        let s = t.scores()[0];
+        assert_eq!(s.completed_tick, Some(20));
+        assert_eq!(s.score, 100 + 20 + 1000);
+        t.update(30, &[(3, 2)]);
+        assert_eq!(t.scores()[0].held_since, None);
+        t.update(40, &[]);
+        assert_eq!(t.scores()[0].score, 1120);
+    }
+
+    #[test]
+    fn ranked_by_score() {
+        let mut t = ChallengeTracker::new(rules());
+        t.update(0, &[(1, 1)]);
+        t.update(5, &[(2, 9)]);
+        t.update(15, &[(2, 9)]);
+        let ids: Vec<u16> = t.scores().iter().map(|s| s.species_id).collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+}
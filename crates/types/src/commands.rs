@@ -1,14 +1,58 @@
-/// Player command encoding for GPU upload.
-/// Each command is 64 bytes = 16 × u32 words.
+//! Player command encoding for GPU upload.
+//! Each command is 64 bytes = 16 × u32 words (see `layout`).
+
+use crate::layout;
 
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CommandType {
-    Noop = 0,
-    PlaceVoxel = 1,      // param_0 = voxel_type
-    RemoveVoxel = 2,
-    SeedProtocells = 3,   // param_0 = initial_energy
-    ApplyToxin = 4,       // param_0 = toxin_strength (0-255)
+    Noop = layout::CMD_NOOP,
+    PlaceVoxel = layout::CMD_PLACE_VOXEL, // param_0 = voxel_type, param_1 = heat/cold source settings (see pack_thermal_source)
+    RemoveVoxel = layout::CMD_REMOVE_VOXEL,
+    SeedProtocells = layout::CMD_SEED_PROTOCELLS, // param_0 = initial_energy
+    ApplyToxin = layout::CMD_APPLY_TOXIN,         // param_0 = toxin_strength (0-255)
+    FillRegion = layout::CMD_FILL_REGION,         // (x,y,z) = min corner, param_0 = voxel_type | density << 8 | FILL_OVERWRITE, param_1 = packed max corner
+    FreezeRegion = layout::CMD_FREEZE_REGION,     // (x,y,z) = min corner, param_0 = 1 freeze / 0 thaw, param_1 = packed max corner
+}
+
+/// Commands the apply pass reads per tick; the rest are ignored.
+pub const MAX_COMMANDS_PER_TICK: usize = layout::MAX_COMMANDS_PER_TICK as usize;
+
+/// Byte offset of command `index` in the command buffer, past the header
+/// (the command count). `command_byte_offset(MAX_COMMANDS_PER_TICK)` is the
+/// buffer size.
+pub const fn command_byte_offset(index: usize) -> u64 {
+    (layout::COMMAND_HEADER_WORDS as u64 + index as u64 * layout::COMMAND_WORDS as u64) * 4
+}
+
+/// FillRegion `param_0` flag: replace occupied voxels too, not only empty
+/// ones.
+pub const FILL_OVERWRITE: u32 = layout::FILL_OVERWRITE;
+
+/// Pack an inclusive max corner into `param_1` of a FillRegion command
+/// (10 bits per axis).
+pub fn pack_region_max(x: u32, y: u32, z: u32) -> u32 {
+    (x & 0x3FF) | ((y & 0x3FF) << 10) | ((z & 0x3FF) << 20)
+}
+
+/// Pack heat/cold source settings into `param_1` of a PlaceVoxel command:
+/// strength in per-mille (0 or ≥ 1000 = full) and lifetime in ticks
+/// (0 = never depletes).
+pub fn pack_thermal_source(strength_permille: u16, lifetime_ticks: u16) -> u32 {
+    strength_permille as u32 | ((lifetime_ticks as u32) << 16)
+}
+
+/// Inverse of `pack_thermal_source`, with the strength resolved to
+/// 1..=`THERMAL_STRENGTH_FULL`.
+pub fn unpack_thermal_source(packed: u32) -> (u16, u16) {
+    let strength = (packed & 0xFFFF) as u16;
+    let strength = if strength == 0 { crate::THERMAL_STRENGTH_FULL } else { strength.min(crate::THERMAL_STRENGTH_FULL) };
+    (strength, (packed >> 16) as u16)
+}
+
+/// Inverse of `pack_region_max`.
+pub fn unpack_region_max(packed: u32) -> (u32, u32, u32) {
+    (packed & 0x3FF, (packed >> 10) & 0x3FF, (packed >> 20) & 0x3FF)
 }
 
 #[repr(C)]
@@ -38,8 +82,71 @@ impl Command {
         }
     }
 
+    /// Fill the empty voxels of the box `min..=max` with `voxel_type`, each
+    /// with probability `density / 255`.
+    pub fn fill_region(min: (u32, u32, u32), max: (u32, u32, u32), voxel_type: u32, density: u8) -> Self {
+        Self::new(
+            CommandType::FillRegion,
+            min.0,
+            min.1,
+            min.2,
+            0,
+            (voxel_type & 0xFF) | ((density as u32) << 8),
+            pack_region_max(max.0, max.1, max.2),
+        )
+    }
+
+    /// Fill every voxel of the box `min..=max` with `voxel_type`, replacing
+    /// what is there.
+    pub fn fill_box(min: (u32, u32, u32), max: (u32, u32, u32), voxel_type: u32) -> Self {
+        let mut cmd = Self::fill_region(min, max, voxel_type, 255);
+        cmd.param_0 |= FILL_OVERWRITE;
+        cmd
+    }
+
+    /// Freeze (or thaw) every voxel of the box `min..=max`, empty cells
+    /// included; frozen cells are skipped by every tick pass.
+    pub fn freeze_region(min: (u32, u32, u32), max: (u32, u32, u32), frozen: bool) -> Self {
+        Self::new(
+            CommandType::FreezeRegion,
+            min.0,
+            min.1,
+            min.2,
+            0,
+            frozen as u32,
+            pack_region_max(max.0, max.1, max.2),
+        )
+    }
+
+    /// Whether the command acts on the box `(x, y, z)..=unpack_region_max(param_1)`
+    /// rather than a cube of `radius` around `(x, y, z)`.
+    pub fn is_region(&self) -> bool {
+        self.command_type == CommandType::FillRegion as u32 || self.command_type == CommandType::FreezeRegion as u32
+    }
+
+    /// Place heat or cold sources of `strength` (0..=1) that fade out over
+    /// `lifetime_ticks` (0 = permanent).
+    pub fn place_thermal_source(
+        voxel_type: crate::VoxelType,
+        pos: (u32, u32, u32),
+        radius: u32,
+        strength: f32,
+        lifetime_ticks: u16,
+    ) -> Self {
+        let permille = (strength.clamp(0.0, 1.0) * crate::THERMAL_STRENGTH_FULL as f32).round().max(1.0) as u16;
+        Self::new(
+            CommandType::PlaceVoxel,
+            pos.0,
+            pos.1,
+            pos.2,
+            radius,
+            voxel_type as u32,
+            pack_thermal_source(permille, lifetime_ticks),
+        )
+    }
+
     pub fn to_words(&self) -> [u32; 16] {
-        let mut words = [0u32; 16];
+        let mut words = [0u32; layout::COMMAND_WORDS as usize];
         words[0] = self.command_type;
         words[1] = self.x;
         words[2] = self.y;
@@ -72,8 +179,39 @@ mod tests {
         assert_eq!(words[4], 2);
         assert_eq!(words[5], 1);
         assert_eq!(words[6], 0);
-        for i in 7..16 {
-            assert_eq!(words[i], 0, "padding word {} should be 0", i);
+        for (i, &w) in words.iter().enumerate().skip(7) {
+            assert_eq!(w, 0, "padding word {} should be 0", i);
         }
     }
+
+    #[test]
+    fn thermal_source_packing() {
+        let cmd = Command::place_thermal_source(crate::VoxelType::HeatSource, (4, 5, 6), 1, 0.25, 3000);
+        assert_eq!(cmd.param_0, crate::VoxelType::HeatSource as u32);
+        assert_eq!(unpack_thermal_source(cmd.param_1), (250, 3000));
+        // Legacy placements (param_1 = 0) are full strength and permanent
+        assert_eq!(unpack_thermal_source(0), (1000, 0));
+        assert_eq!(unpack_thermal_source(pack_thermal_source(5000, 0)), (1000, 0));
+    }
+
+    #[test]
+    fn fill_region_packing() {
+        let cmd = Command::fill_region((1, 2, 3), (100, 255, 1023), 2, 128);
+        assert_eq!(cmd.command_type, CommandType::FillRegion as u32);
+        assert_eq!((cmd.x, cmd.y, cmd.z), (1, 2, 3));
+        assert_eq!(cmd.param_0, 2 | (128 << 8));
+        assert_eq!(unpack_region_max(cmd.param_1), (100, 255, 1023));
+        assert!(cmd.is_region());
+    }
+
+    #[test]
+    fn freeze_region_packing() {
+        let cmd = Command::freeze_region((4, 5, 6), (7, 8, 9), true);
+        assert_eq!(cmd.command_type, CommandType::FreezeRegion as u32);
+        assert_eq!((cmd.x, cmd.y, cmd.z, cmd.param_0), (4, 5, 6, 1));
+        assert_eq!(unpack_region_max(cmd.param_1), (7, 8, 9));
+        assert!(cmd.is_region());
+        assert_eq!(Command::freeze_region((0, 0, 0), (1, 1, 1), false).param_0, 0);
+        assert!(!Command::new(CommandType::PlaceVoxel, 0, 0, 0, 1, 1, 0).is_region());
+    }
 }
@@ -0,0 +1,96 @@
+//! Worlds seeded from a caller-supplied point list (`seed_custom`), e.g.
+//! patterns generated by an external script.
+
+use crate::{Genome, Voxel, VoxelType};
+
+/// Points one custom seed may hold.
+pub const MAX_SEED_POINTS: usize = 1 << 21;
+
+/// One validated entry: a voxel of `voxel_type` at `pos`. Protocells take
+/// their species from the genome (the default genome when none is given).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedPoint {
+    pub pos: (u32, u32, u32),
+    pub voxel_type: VoxelType,
+    pub energy: u16,
+    pub genome: Option<Genome>,
+}
+
+impl SeedPoint {
+    /// Validate entry `n` from its JSON numbers: `values` is `[x, y, z,
+    /// type]` or `[x, y, z, type, energy]` (type codes as in `VoxelType`),
+    /// `genome` up to 16 bytes, zero-padded.
+    pub fn from_values(n: usize, values: &[f64], genome: Option<&[f64]>, grid_size: u32) -> Result<Self, String> {
+        let int = |what: &str, v: f64, max: u32| {
+            if v.fract() == 0.0 && (0.0..=max as f64).contains(&v) {
+                Ok(v as u32)
+            } else {
+                Err(format!("point {n}: {what} {v} is not an integer in 0..={max}"))
+            }
+        };
+        let [x, y, z, vtype, rest @ ..] = values else {
+            return Err(format!("point {n}: expected x, y, z, type and optional energy"));
+        };
+        let energy = match rest {
+            [] => 0,
+            [energy] => int("energy", *energy, u16::MAX as u32)? as u16,
+            _ => return Err(format!("point {n}: expected x, y, z, type and optional energy")),
+        };
+        let last = grid_size.saturating_sub(1);
+        let pos = (int("x", *x, last)?, int("y", *y, last)?, int("z", *z, last)?);
+        let voxel_type = VoxelType::from_u8(int("type", *vtype, VoxelType::ColdSource as u32)? as u8);
+        let genome = match genome {
+            None => None,
+            Some(_) if voxel_type != VoxelType::Protocell => {
+                return Err(format!("point {n}: only protocells take a genome"));
+            }
+            Some(bytes) if bytes.len() > 16 => return Err(format!("point {n}: genome has {} bytes, max 16", bytes.len())),
+            Some(bytes) => {
+                let mut g = Genome::default();
+                for (i, &b) in bytes.iter().enumerate() {
+                    g.bytes[i] = int("genome byte", b, 255)? as u8;
+                }
+                Some(g)
+            }
+        };
+        Ok(SeedPoint { pos, voxel_type, energy, genome })
+    }
+
+    pub fn to_voxel(&self) -> Voxel {
+        let mut v = Voxel { voxel_type: self.voxel_type, energy: self.energy, ..Default::default() };
+        if self.voxel_type == VoxelType::Protocell {
+            v.genome = self.genome.unwrap_or_default();
+            v.species_id = v.genome.species_id();
+        }
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_entries() {
+        let p = SeedPoint::from_values(0, &[1.0, 2.0, 3.0, 4.0, 500.0], Some(&[9.0, 8.0]), 16).unwrap();
+        assert_eq!((p.pos, p.voxel_type, p.energy), ((1, 2, 3), VoxelType::Protocell, 500));
+        let v = p.to_voxel();
+        assert_eq!(&v.genome.bytes[..3], &[9, 8, 0]);
+        assert_eq!(v.species_id, v.genome.species_id());
+
+        let wall = SeedPoint::from_values(1, &[0.0, 0.0, 15.0, 1.0], None, 16).unwrap();
+        assert_eq!((wall.voxel_type, wall.energy, wall.to_voxel().species_id), (VoxelType::Wall, 0, 0));
+
+        for (values, genome) in [
+            (&[16.0, 0.0, 0.0, 1.0][..], None),
+            (&[0.5, 0.0, 0.0, 1.0][..], None),
+            (&[0.0, 0.0, 0.0, 8.0][..], None),
+            (&[0.0, 0.0, 0.0, 1.0, 70000.0][..], None),
+            (&[0.0, 0.0, 0.0][..], None),
+            (&[0.0, 0.0, 0.0, 1.0][..], Some(&[1.0][..])),
+            (&[0.0, 0.0, 0.0, 4.0][..], Some(&[256.0][..])),
+        ] {
+            assert!(SeedPoint::from_values(2, values, genome, 16).is_err(), "{values:?} {genome:?}");
+        }
+    }
+}
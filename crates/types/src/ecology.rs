@@ -0,0 +1,172 @@
+//! Species emergence and extinction events, found by diffing consecutive
+//! stats samples of the species histogram.
+
+use std::collections::VecDeque;
+
+/// Species a stats histogram holds at most. A full histogram may have left
+/// species out, so an absence from it proves nothing.
+pub const HISTOGRAM_SLOTS: usize = crate::layout::STATS_SPECIES_SLOTS as usize;
+
+/// Events kept; the oldest are dropped first.
+pub const MAX_ECOLOGY_EVENTS: usize = 256;
+
+/// Extinctions of species ranked this high in their last sample are notable.
+pub const NOTABLE_RANK: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcologyEventKind {
+    Emerged,
+    Extinct,
+}
+
+impl EcologyEventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EcologyEventKind::Emerged => "emerged",
+            EcologyEventKind::Extinct => "extinct",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EcologyEvent {
+    /// Tick of the sample the change was seen in.
+    pub tick: u32,
+    pub kind: EcologyEventKind,
+    pub species_id: u16,
+    /// Population in the sample the species was first (emerged) or last
+    /// (extinct) seen in.
+    pub population: u32,
+    /// 1-based rank by population in that sample.
+    pub rank: u32,
+}
+
+impl EcologyEvent {
+    /// Extinction of a species that was in the top `NOTABLE_RANK`.
+    pub fn is_notable(&self) -> bool {
+        self.kind == EcologyEventKind::Extinct && self.rank <= NOTABLE_RANK
+    }
+}
+
+/// Event log fed one species histogram per stats sample.
+#[derive(Debug, Clone, Default)]
+pub struct EcologyLog {
+    events: VecDeque<EcologyEvent>,
+    previous: Option<Vec<(u16, u32)>>,
+}
+
+impl EcologyLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Diff `histogram` ((species_id, count), largest first, as in
+    /// `SimStats`) against the previous sample and log what changed at
+    /// `tick`. Extinctions are only trusted when `histogram` has room to
+    /// spare, emergences when the previous one did. Returns the new events.
+    pub fn update(&mut self, tick: u32, histogram: &[(u16, u32)]) -> Vec<EcologyEvent> {
+        let current: Vec<(u16, u32)> = histogram.iter().copied().filter(|&(sid, n)| sid != 0 && n > 0).collect();
+        let Some(previous) = self.previous.replace(current.clone()) else {
+            return Vec::new();
+        };
+        let event = |kind, rank: usize, &(species_id, population): &(u16, u32)| EcologyEvent {
+            tick,
+            kind,
+            species_id,
+            population,
+            rank: rank as u32 + 1,
+        };
+        let present = |list: &[(u16, u32)], sid: u16| list.iter().any(|&(s, _)| s == sid);
+
+        let mut added = Vec::new();
+        if current.len() < HISTOGRAM_SLOTS {
+            for (rank, entry) in previous.iter().enumerate() {
+                if !present(&current, entry.0) {
+                    added.push(event(EcologyEventKind::Extinct, rank, entry));
+                }
+            }
+        }
+        if previous.len() < HISTOGRAM_SLOTS {
+            for (rank, entry) in current.iter().enumerate() {
+                if !present(&previous, entry.0) {
+                    added.push(event(EcologyEventKind::Emerged, rank, entry));
+                }
+            }
+        }
+        for e in &added {
+            if self.events.len() == MAX_ECOLOGY_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back(*e);
+        }
+        added
+    }
+
+    /// Logged events at or after `since_tick`, oldest first.
+    pub fn since(&self, since_tick: u32) -> impl Iterator<Item = &EcologyEvent> {
+        self.events.iter().filter(move |e| e.tick >= since_tick)
+    }
+
+    /// Drop all events and the previous sample (the world was reset).
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.previous = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_emergence_and_extinction() {
+        let mut log = EcologyLog::new();
+        assert!(log.update(10, &[(7, 50), (3, 20), (9, 5)]).is_empty());
+        let events = log.update(20, &[(7, 60), (4, 8), (9, 4)]);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], EcologyEvent {
+            tick: 20,
+            kind: EcologyEventKind::Extinct,
+            species_id: 3,
+            population: 20,
+            rank: 2,
+        });
+        assert!(events[0].is_notable());
+        assert_eq!((events[1].kind, events[1].species_id, events[1].rank), (EcologyEventKind::Emerged, 4, 2));
+        assert!(!events[1].is_notable());
+        assert_eq!(log.since(20).count(), 2);
+        assert_eq!(log.since(21).count(), 0);
+        log.clear();
+        assert!(log.update(30, &[]).is_empty());
+        assert_eq!(log.since(0).count(), 0);
+    }
+
+    #[test]
+    fn full_histogram_hides_absences() {
+        let n = HISTOGRAM_SLOTS as u16;
+        let full: Vec<(u16, u32)> = (1..=n).map(|sid| (sid, 1000 - sid as u32)).collect();
+        let mut log = EcologyLog::new();
+        log.update(0, &full);
+        // The last species may just have missed a slot: no extinction, and
+        // a new one may have been present before: no emergence either
+        let mut next = full.clone();
+        next[HISTOGRAM_SLOTS - 1] = (n + 1, 1);
+        assert!(log.update(10, &next).is_empty());
+        // With room to spare, absences are real
+        let events = log.update(20, &next[..6]);
+        assert_eq!(events.len(), HISTOGRAM_SLOTS - 6);
+        assert!(events.iter().all(|e| e.kind == EcologyEventKind::Extinct && !e.is_notable()));
+    }
+
+    #[test]
+    fn log_is_bounded() {
+        let mut log = EcologyLog::new();
+        log.update(0, &[]);
+        for t in 1..=MAX_ECOLOGY_EVENTS as u32 {
+            log.update(t, &[(t as u16, 1)]);
+        }
+        // One emergence, then an extinction and an emergence per sample
+        assert_eq!(log.since(0).count(), MAX_ECOLOGY_EVENTS);
+        assert_eq!(log.since(0).next().map(|e| e.tick), Some(129));
+    }
+}
@@ -0,0 +1,130 @@
+//! Engine event log: notable conditions the engine handles silently (a full
+//! brick pool, commands over the per-tick limit, clamped parameters).
+
+use std::collections::VecDeque;
+
+/// Entries kept; the oldest are dropped first.
+pub const MAX_ENGINE_LOG_ENTRIES: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    /// Sparse brick pool allocation.
+    Pool,
+    /// Player and scheduled commands.
+    Commands,
+    /// Sparse brick table bookkeeping.
+    Bricks,
+    /// Sim and rule parameters.
+    Params,
+}
+
+impl Subsystem {
+    pub fn name(self) -> &'static str {
+        match self {
+            Subsystem::Pool => "pool",
+            Subsystem::Commands => "commands",
+            Subsystem::Bricks => "bricks",
+            Subsystem::Params => "params",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Position in the log; keeps counting across resets and dropped entries.
+    pub seq: u64,
+    /// Tick the engine was at when the event happened.
+    pub tick: u32,
+    pub subsystem: Subsystem,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+/// Ring of the latest `MAX_ENGINE_LOG_ENTRIES` entries. Readers poll with
+/// the `seq` after the last entry they saw, since ticks restart on reset.
+#[derive(Debug, Clone, Default)]
+pub struct EngineLog {
+    entries: VecDeque<LogEntry>,
+    next_seq: u64,
+}
+
+impl EngineLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, tick: u32, subsystem: Subsystem, level: LogLevel, message: impl Into<String>) {
+        if self.entries.len() == MAX_ENGINE_LOG_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry { seq: self.next_seq, tick, subsystem, level, message: message.into() });
+        self.next_seq += 1;
+    }
+
+    pub fn info(&mut self, tick: u32, subsystem: Subsystem, message: impl Into<String>) {
+        self.push(tick, subsystem, LogLevel::Info, message);
+    }
+
+    pub fn warn(&mut self, tick: u32, subsystem: Subsystem, message: impl Into<String>) {
+        self.push(tick, subsystem, LogLevel::Warn, message);
+    }
+
+    /// Entries with `seq >= since`, oldest first.
+    pub fn since(&self, since: u64) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().filter(move |e| e.seq >= since)
+    }
+
+    /// `seq` the next entry will get.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_latest_entries() {
+        let mut log = EngineLog::new();
+        for tick in 0..MAX_ENGINE_LOG_ENTRIES as u32 + 10 {
+            log.warn(tick, Subsystem::Commands, format!("dropped at {tick}"));
+        }
+        assert_eq!(log.len(), MAX_ENGINE_LOG_ENTRIES);
+        let first = log.since(0).next().unwrap();
+        assert_eq!((first.seq, first.tick, first.message.as_str()), (10, 10, "dropped at 10"));
+        assert_eq!(log.since(MAX_ENGINE_LOG_ENTRIES as u64 + 5).count(), 5);
+        // A reset restarts ticks, not sequence numbers
+        let next = log.next_seq();
+        log.info(0, Subsystem::Pool, "full");
+        let last: Vec<_> = log.since(next).collect();
+        assert_eq!(last.len(), 1);
+        assert_eq!((last[0].tick, last[0].subsystem.name(), last[0].level.name()), (0, "pool", "info"));
+        assert_eq!(log.next_seq(), next + 1);
+    }
+}
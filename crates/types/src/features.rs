@@ -0,0 +1,109 @@
+//! Engine feature registry: optional simulation subsystems. A disabled
+//! feature's buffers are not allocated, its passes are not encoded and its
+//! bindings are stripped from the shaders (see `preprocess_wgsl`), so
+//! low-end GPUs can drop a subsystem instead of slowing the whole sim.
+
+/// Optional subsystems; the discriminant is the feature's bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Temperature field diffusion and its effects (dormancy, heat shock,
+    /// thermotaxis, temperature-scaled metabolism). Off, every cell sits at
+    /// base_ambient_temp.
+    Temperature = 0,
+    /// Pheromone scent deposits, spread and territorial movement.
+    Signals = 1,
+    /// Toxin secretion, spread and damage.
+    ToxinField = 2,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [Feature::Temperature, Feature::Signals, Feature::ToxinField];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Temperature => "temperature",
+            Feature::Signals => "signals",
+            Feature::ToxinField => "toxin_field",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Shader define set while the feature is on.
+    pub fn define(self) -> &'static str {
+        match self {
+            Feature::Temperature => "TEMPERATURE",
+            Feature::Signals => "SIGNALS",
+            Feature::ToxinField => "TOXIN_FIELD",
+        }
+    }
+}
+
+/// Set while signals or the toxin field is on: both live in the coarse
+/// pheromone field.
+pub const COARSE_FIELD_DEFINE: &str = "COARSE_FIELD";
+
+/// Enabled features; all of them by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FeatureSet {
+    bits: u32,
+}
+
+impl Default for FeatureSet {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl FeatureSet {
+    pub const ALL: FeatureSet = FeatureSet { bits: (1 << Feature::ALL.len()) - 1 };
+    pub const NONE: FeatureSet = FeatureSet { bits: 0 };
+
+    pub fn contains(self, feature: Feature) -> bool {
+        self.bits & (1 << feature as u32) != 0
+    }
+
+    pub fn with(self, feature: Feature, enabled: bool) -> Self {
+        let bit = 1 << feature as u32;
+        Self { bits: if enabled { self.bits | bit } else { self.bits & !bit } }
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Feature> {
+        Feature::ALL.into_iter().filter(move |&f| self.contains(f))
+    }
+
+    /// Whether the coarse pheromone field is allocated and updated.
+    pub fn coarse_field(self) -> bool {
+        self.contains(Feature::Signals) || self.contains(Feature::ToxinField)
+    }
+
+    /// Shader defines for `preprocess_wgsl`.
+    pub fn defines(self) -> Vec<&'static str> {
+        let mut defines: Vec<&'static str> = self.iter().map(Feature::define).collect();
+        if self.coarse_field() {
+            defines.push(COARSE_FIELD_DEFINE);
+        }
+        defines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_sets_toggle_and_derive_defines() {
+        assert_eq!(FeatureSet::default().iter().count(), Feature::ALL.len());
+        let set = FeatureSet::ALL.with(Feature::Signals, false);
+        assert!(!set.contains(Feature::Signals));
+        assert!(set.coarse_field());
+        assert_eq!(set.defines(), vec!["TEMPERATURE", "TOXIN_FIELD", COARSE_FIELD_DEFINE]);
+        let bare = set.with(Feature::ToxinField, false).with(Feature::Temperature, false);
+        assert_eq!(bare, FeatureSet::NONE);
+        assert!(bare.defines().is_empty());
+        assert_eq!(Feature::from_name("toxin_field"), Some(Feature::ToxinField));
+        assert_eq!(Feature::from_name("gravity"), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Min/max/mean of the scalar fields (temperature, pheromone intensity,
+//! protocell energy), reduced by field_stats.wgsl into the stats buffer with
+//! each stats readback.
+
+use crate::{float_from_key, TempRange, PHEROMONE_MAX};
+
+/// Words of the field-stats block in the stats buffer:
+///   [0] max of !float_key(temperature)  [1] max of float_key(temperature)
+///   [2..3] temperature sum, low/high word, in 1/TEMP_SUM_SCALE steps
+///   [4] cells measured
+///   [5] FIELD_MIN_BIAS - min pheromone intensity  [6] max intensity
+///   [7..8] intensity sum, low/high word  [9] pheromone cells measured
+///   [10] FIELD_MIN_BIAS - min protocell energy (0 = no protocells)
+pub const FIELD_STATS_WORDS: usize = crate::layout::StatsSlot::FieldStats.words() as usize;
+/// Minima are stored as `FIELD_MIN_BIAS - min` so atomicMax over a zeroed
+/// buffer finds them and a measured u16 minimum is never zero.
+pub const FIELD_MIN_BIAS: u32 = 0x10000;
+/// Temperatures are summed as fixed point in steps of 1/TEMP_SUM_SCALE...
+pub const TEMP_SUM_SCALE: f32 = 4096.0;
+/// ...clamped to 0..=TEMP_SUM_MAX.
+pub const TEMP_SUM_MAX: f32 = 1024.0;
+
+/// One temperature's contribution to the sum. Matches field_stats.wgsl.
+pub fn temp_sum_fixed(temp: f32) -> u32 {
+    (temp.clamp(0.0, TEMP_SUM_MAX) * TEMP_SUM_SCALE) as u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FieldSummary {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+impl FieldSummary {
+    /// Summary of `values`; `None` if there are none.
+    pub fn of(values: impl IntoIterator<Item = f32>) -> Option<Self> {
+        let (mut min, mut max, mut sum, mut n) = (f32::INFINITY, f32::NEG_INFINITY, 0.0f64, 0u64);
+        for v in values {
+            min = min.min(v);
+            max = max.max(v);
+            sum += v as f64;
+            n += 1;
+        }
+        (n > 0).then(|| Self { min, max, mean: (sum / n as f64) as f32 })
+    }
+
+    pub fn range(&self) -> TempRange {
+        TempRange { min: self.min, max: self.max }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FieldStats {
+    pub temperature: Option<FieldSummary>,
+    /// Pheromone intensity as a fraction of `PHEROMONE_MAX`.
+    pub pheromone: Option<FieldSummary>,
+    /// Lowest protocell energy; with the stats' max and total it makes the
+    /// energy summary.
+    pub energy_min: Option<u32>,
+}
+
+impl FieldStats {
+    /// Parse the field-stats block; missing words read as zero.
+    pub fn from_words(words: &[u32]) -> Self {
+        let word = |i: usize| words.get(i).copied().unwrap_or(0);
+        let wide = |i: usize| word(i) as u64 | (word(i + 1) as u64) << 32;
+        let temperature = (word(4) > 0).then(|| FieldSummary {
+            min: float_from_key(!word(0)),
+            max: float_from_key(word(1)),
+            mean: (wide(2) as f64 / TEMP_SUM_SCALE as f64 / word(4) as f64) as f32,
+        });
+        let level = |v: u32| v as f32 / PHEROMONE_MAX as f32;
+        let pheromone = (word(9) > 0).then(|| FieldSummary {
+            min: level(FIELD_MIN_BIAS - word(5).min(FIELD_MIN_BIAS)),
+            max: level(word(6)),
+            mean: (wide(7) as f64 / PHEROMONE_MAX as f64 / word(9) as f64) as f32,
+        });
+        let energy_min = (word(10) > 0).then(|| FIELD_MIN_BIAS - word(10).min(FIELD_MIN_BIAS));
+        Self { temperature, pheromone, energy_min }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::float_key;
+
+    #[test]
+    fn field_words_round_trip() {
+        let temps = [0.25f32, 0.5, 0.75, 1.0];
+        let sum: u64 = temps.iter().map(|&t| temp_sum_fixed(t) as u64).sum::<u64>() + (5u64 << 32);
+        let words = [
+            !float_key(0.25),
+            float_key(1.0),
+            sum as u32,
+            (sum >> 32) as u32,
+            4,
+            FIELD_MIN_BIAS - 0x1000,
+            0x8000,
+            0x9000,
+            0,
+            2,
+            FIELD_MIN_BIAS - 7,
+        ];
+        let stats = FieldStats::from_words(&words);
+        let temp = stats.temperature.unwrap();
+        assert_eq!((temp.min, temp.max), (0.25, 1.0));
+        // The high word carries 5 × 2³² fixed-point steps
+        let expected = (0.625 * 4.0 + 5.0 * (1u64 << 32) as f64 / TEMP_SUM_SCALE as f64) / 4.0;
+        assert!((temp.mean as f64 - expected).abs() < 1.0);
+        let pher = stats.pheromone.unwrap();
+        assert!((pher.min - 0x1000 as f32 / 65535.0).abs() < 1e-6);
+        assert!((pher.mean - 0x4800 as f32 / 65535.0).abs() < 1e-6);
+        assert_eq!(stats.energy_min, Some(7));
+        assert_eq!(FieldStats::from_words(&[]), FieldStats::default());
+    }
+
+    #[test]
+    fn summaries_of_values() {
+        let s = FieldSummary::of([0.2, 0.6, 0.4]).unwrap();
+        assert_eq!((s.min, s.max), (0.2, 0.6));
+        assert!((s.mean - 0.4).abs() < 1e-6);
+        assert_eq!(s.range(), TempRange { min: 0.2, max: 0.6 });
+        assert_eq!(FieldSummary::of([]), None);
+        assert_eq!(temp_sum_fixed(-1.0), 0);
+        assert_eq!(temp_sum_fixed(0.5), 2048);
+    }
+}
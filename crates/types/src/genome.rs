@@ -1,15 +1,12 @@
+/// Adhesion gene (byte 12) at or above which offspring stay linked to their parent.
+pub const ADHESION_LINK_MIN: u8 = 128;
+
 /// 16-byte genome packed into 4 × u32.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Genome {
     pub bytes: [u8; 16],
 }
 
-impl Default for Genome {
-    fn default() -> Self {
-        Self { bytes: [0; 16] }
-    }
-}
-
 impl Genome {
     // Byte accessors matching genome byte map
     pub fn metabolic_efficiency(&self) -> u8 { self.bytes[0] }
@@ -23,13 +20,63 @@ impl Genome {
     pub fn predation_aggression(&self) -> u8 { self.bytes[8] }
     pub fn photosynthetic_rate(&self) -> u8 { self.bytes[9] }
     pub fn energy_split_ratio(&self) -> u8 { self.bytes[10] }
+    pub fn pheromone_strength(&self) -> u8 { self.bytes[11] }
+    pub fn adhesion(&self) -> u8 { self.bytes[12] }
+    /// Byte 13 high nibble, scaled to 0..255.
+    pub fn cold_tolerance(&self) -> u8 { (self.bytes[13] >> 4) * 17 }
+    /// Byte 13 low nibble (allelopathic toxin secretion), scaled to 0..255.
+    pub fn toxin_secretion(&self) -> u8 { (self.bytes[13] & 0x0F) * 17 }
+    pub fn storage_capacity(&self) -> u8 { self.bytes[14] }
+    pub fn thermotaxis(&self) -> u8 { self.bytes[15] }
+
+    /// Preferred temperature (0..1) from the thermotaxis byte's high nibble.
+    pub fn preferred_temp(&self) -> f32 {
+        (self.bytes[15] >> 4) as f32 / 15.0
+    }
+
+    /// Thermotaxis strength (0..1) from the thermotaxis byte's low nibble.
+    pub fn thermotaxis_strength(&self) -> f32 {
+        (self.bytes[15] & 0x0F) as f32 / 15.0
+    }
+
+    /// Every decoded trait as (name, 0..255 value), in byte order. Byte 13
+    /// contributes two entries (cold tolerance, toxin secretion).
+    pub fn named_traits(&self) -> [(&'static str, u8); 17] {
+        [
+            ("metabolic_efficiency", self.metabolic_efficiency()),
+            ("metabolic_rate", self.metabolic_rate()),
+            ("replication_threshold", self.replication_threshold()),
+            ("mutation_rate", self.mutation_rate()),
+            ("movement_bias", self.movement_bias()),
+            ("chemotaxis_strength", self.chemotaxis_strength()),
+            ("toxin_resistance", self.toxin_resistance()),
+            ("predation_capability", self.predation_capability()),
+            ("predation_aggression", self.predation_aggression()),
+            ("photosynthetic_rate", self.photosynthetic_rate()),
+            ("energy_split_ratio", self.energy_split_ratio()),
+            ("pheromone_strength", self.pheromone_strength()),
+            ("adhesion", self.adhesion()),
+            ("cold_tolerance", self.cold_tolerance()),
+            ("toxin_secretion", self.toxin_secretion()),
+            ("storage_capacity", self.storage_capacity()),
+            ("thermotaxis", self.thermotaxis()),
+        ]
+    }
+
+    /// Bytes that differ from `other`: (byte index, self, other).
+    pub fn diff(&self, other: &Genome) -> Vec<(usize, u8, u8)> {
+        (0..16)
+            .filter(|&i| self.bytes[i] != other.bytes[i])
+            .map(|i| (i, self.bytes[i], other.bytes[i]))
+            .collect()
+    }
 
     /// Pack genome into 4 u32 words (little-endian byte order).
     pub fn to_words(&self) -> [u32; 4] {
         let mut words = [0u32; 4];
-        for i in 0..4 {
+        for (i, word) in words.iter_mut().enumerate() {
             let base = i * 4;
-            words[i] = (self.bytes[base] as u32)
+            *word = (self.bytes[base] as u32)
                 | ((self.bytes[base + 1] as u32) << 8)
                 | ((self.bytes[base + 2] as u32) << 16)
                 | ((self.bytes[base + 3] as u32) << 24);
@@ -40,12 +87,12 @@ impl Genome {
     /// Unpack genome from 4 u32 words (little-endian byte order).
     pub fn from_words(words: [u32; 4]) -> Self {
         let mut bytes = [0u8; 16];
-        for i in 0..4 {
+        for (i, &word) in words.iter().enumerate() {
             let base = i * 4;
-            bytes[base] = (words[i] & 0xFF) as u8;
-            bytes[base + 1] = ((words[i] >> 8) & 0xFF) as u8;
-            bytes[base + 2] = ((words[i] >> 16) & 0xFF) as u8;
-            bytes[base + 3] = ((words[i] >> 24) & 0xFF) as u8;
+            bytes[base] = (word & 0xFF) as u8;
+            bytes[base + 1] = ((word >> 8) & 0xFF) as u8;
+            bytes[base + 2] = ((word >> 16) & 0xFF) as u8;
+            bytes[base + 3] = ((word >> 24) & 0xFF) as u8;
         }
         Self { bytes }
     }
@@ -64,10 +111,50 @@ impl Genome {
     }
 }
 
+/// Range the mutation-rate gene (byte 3) is redrawn within when it
+/// mutates, so evolvability can evolve only between these bounds. The
+/// default range leaves the gene as free as every other byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MutatorBounds {
+    pub min: u8,
+    pub max: u8,
+}
+
+impl Default for MutatorBounds {
+    fn default() -> Self {
+        Self { min: 0, max: 255 }
+    }
+}
+
+impl MutatorBounds {
+    /// Bounds from two ends in either order.
+    pub fn new(a: u8, b: u8) -> Self {
+        Self { min: a.min(b), max: a.max(b) }
+    }
+
+    /// Map a uniformly random byte onto the bounds. Matches the byte-3 case
+    /// of `mutate_genome` in resolve_execute.wgsl.
+    pub fn redraw(&self, byte: u8) -> u8 {
+        let span = self.max as u32 - self.min as u32 + 1;
+        (self.min as u32 + byte as u32 * span / 256) as u8
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn mutator_bounds_redraw_within_range() {
+        let free = MutatorBounds::default();
+        assert!((0..=255u8).all(|b| free.redraw(b) == b));
+        let bounds = MutatorBounds::new(40, 8);
+        assert_eq!((bounds.min, bounds.max), (8, 40));
+        assert_eq!((bounds.redraw(0), bounds.redraw(255)), (8, 40));
+        assert!((0..=255u8).all(|b| (8..=40).contains(&bounds.redraw(b))));
+        assert_eq!(MutatorBounds::new(7, 7).redraw(200), 7);
+    }
+
     #[test]
     fn genome_roundtrip_words() {
         let g = Genome { bytes: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16] };
@@ -92,6 +179,29 @@ mod tests {
         assert_eq!(g.energy_split_ratio(), 200);
     }
 
+    #[test]
+    fn named_traits_follow_byte_map() {
+        let mut g = Genome::default();
+        g.bytes[0] = 172;
+        g.bytes[13] = 0x3A;
+        g.bytes[15] = 9;
+        let traits = g.named_traits();
+        assert_eq!(traits[0], ("metabolic_efficiency", 172));
+        assert_eq!(traits[13], ("cold_tolerance", 3 * 17));
+        assert_eq!(traits[14], ("toxin_secretion", 10 * 17));
+        assert_eq!(traits[16], ("thermotaxis", 9));
+    }
+
+    #[test]
+    fn diff_lists_changed_bytes() {
+        let a = Genome { bytes: [10; 16] };
+        let mut b = a;
+        b.bytes[3] = 11;
+        b.bytes[15] = 0;
+        assert_eq!(a.diff(&b), vec![(3, 10, 11), (15, 10, 0)]);
+        assert!(a.diff(&a).is_empty());
+    }
+
     #[test]
     fn species_id_deterministic() {
         let g = Genome { bytes: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16] };
@@ -120,4 +230,20 @@ mod tests {
         }
         assert!(changed >= 90, "only {changed}/100 single-bit flips changed species_id");
     }
+
+    #[test]
+    fn thermotaxis_nibbles() {
+        let mut g = Genome::default();
+        g.bytes[15] = 0xF5;
+        assert_eq!(g.preferred_temp(), 1.0);
+        assert_eq!(g.thermotaxis_strength(), 5.0 / 15.0);
+    }
+
+    #[test]
+    fn cold_tolerance_and_secretion_nibbles() {
+        let mut g = Genome::default();
+        g.bytes[13] = 0xF1;
+        assert_eq!(g.cold_tolerance(), 255);
+        assert_eq!(g.toxin_secretion(), 17);
+    }
 }
@@ -0,0 +1,161 @@
+//! Genome sampling: a GPU pass keeps each live protocell whose hashed
+//! position falls under a threshold, appending its coordinates and genome to
+//! a compact buffer read back with the stats. The threshold is set so that
+//! about `n` of the last measured population are kept.
+//!
+//! Result buffer: [0] protocells kept (keeps counting past the cap), then
+//! `GENOME_SAMPLE_WORDS` words per sample: packed coordinates (10 bits per
+//! axis as in `pack_region_max`) and the four genome words.
+
+use crate::{grid_coords, unpack_region_max, Genome, Voxel, VoxelType};
+
+/// Genomes a sample readback holds at most.
+pub const MAX_GENOME_SAMPLES: u32 = 1024;
+
+/// Words per sample in the result buffer.
+pub const GENOME_SAMPLE_WORDS: usize = 5;
+
+/// Size of the genome-sample result buffer and its staging copy.
+pub const GENOME_SAMPLE_RESULT_BYTES: u64 = (1 + MAX_GENOME_SAMPLES as u64 * GENOME_SAMPLE_WORDS as u64) * 4;
+
+/// Keep threshold for about `n` of `population` protocells: a protocell is
+/// kept when `sample_hash` is at most this.
+pub fn sample_threshold(n: u32, population: u32) -> u32 {
+    if population <= n {
+        return u32::MAX;
+    }
+    ((n as u64) << 32).div_ceil(population as u64) as u32
+}
+
+/// Hash of the protocell at grid index `idx` in the window seeded by `seed`.
+/// Matches `sample_hash` in genome_sample.wgsl.
+pub fn sample_hash(idx: u32, seed: u32) -> u32 {
+    crate::arbitration::pcg_hash(idx ^ seed.wrapping_mul(0x9E37_79B9) ^ 0x5341_4D50)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenomeSample {
+    pub pos: (u32, u32, u32),
+    pub genome: Genome,
+}
+
+/// Query uniform (genome_sample.wgsl `SampleParams`).
+pub fn genome_sample_words(
+    grid_size: u32,
+    sparse: bool,
+    brick_grid_dim: u32,
+    max_samples: u32,
+    threshold: u32,
+    seed: u32,
+) -> [u32; 8] {
+    [grid_size, sparse as u32, brick_grid_dim, max_samples.min(MAX_GENOME_SAMPLES), threshold, seed, 0, 0]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GenomeSampleSet {
+    /// Tick of the state the genomes were taken from.
+    pub tick: u32,
+    /// Protocells under the threshold, including those past the cap.
+    pub total: u32,
+    pub samples: Vec<GenomeSample>,
+}
+
+impl GenomeSampleSet {
+    pub fn from_words(tick: u32, max_samples: u32, words: &[u32]) -> Self {
+        let total = words.first().copied().unwrap_or(0);
+        let n = total.min(max_samples.min(MAX_GENOME_SAMPLES)) as usize;
+        let samples = words
+            .get(1..)
+            .unwrap_or(&[])
+            .chunks_exact(GENOME_SAMPLE_WORDS)
+            .take(n)
+            .map(|w| GenomeSample {
+                pos: unpack_region_max(w[0]),
+                genome: Genome::from_words([w[1], w[2], w[3], w[4]]),
+            })
+            .collect();
+        Self { tick, total, samples }
+    }
+
+    /// CPU sampling of a dense grid, matching genome_sample.wgsl.
+    pub fn sample(voxels: &[Voxel], grid_size: u32, tick: u32, max_samples: u32, threshold: u32, seed: u32) -> Self {
+        let cap = max_samples.min(MAX_GENOME_SAMPLES) as usize;
+        let mut set = Self { tick, ..Self::default() };
+        for (idx, v) in voxels.iter().enumerate() {
+            if v.voxel_type != VoxelType::Protocell || sample_hash(idx as u32, seed) > threshold {
+                continue;
+            }
+            set.total += 1;
+            if set.samples.len() < cap {
+                set.samples.push(GenomeSample { pos: grid_coords(idx, grid_size), genome: v.genome });
+            }
+        }
+        set
+    }
+
+    /// Mean fraction of genome bits that differ between two samples; `None`
+    /// with fewer than two.
+    pub fn diversity(&self) -> Option<f32> {
+        let n = self.samples.len();
+        if n < 2 {
+            return None;
+        }
+        let mut bits = 0u64;
+        for (i, a) in self.samples.iter().enumerate() {
+            for b in &self.samples[i + 1..] {
+                bits += a.genome.bytes.iter().zip(&b.genome.bytes).map(|(x, y)| (x ^ y).count_ones() as u64).sum::<u64>();
+            }
+        }
+        let pairs = (n * (n - 1) / 2) as f32;
+        Some(bits as f32 / (pairs * 128.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack_region_max;
+
+    #[test]
+    fn threshold_scales_with_population() {
+        assert_eq!(sample_threshold(10, 5), u32::MAX);
+        assert_eq!(sample_threshold(10, 10), u32::MAX);
+        assert_eq!(sample_threshold(1, 2), 1 << 31);
+        assert_eq!(sample_threshold(0, 100), 0);
+        // Roughly n of a large population pass
+        let threshold = sample_threshold(100, 10_000);
+        let kept = (0..10_000).filter(|&i| sample_hash(i, 7) <= threshold).count();
+        assert!((60..140).contains(&kept));
+    }
+
+    #[test]
+    fn parses_and_measures_samples() {
+        let g = Genome { bytes: [0; 16] };
+        let mut h = g;
+        h.bytes[0] = 0xFF;
+        let mut words = vec![3, pack_region_max(1, 2, 3)];
+        words.extend(g.to_words());
+        words.push(pack_region_max(4, 5, 6));
+        words.extend(h.to_words());
+        let set = GenomeSampleSet::from_words(9, 2, &words);
+        assert_eq!(set.total, 3);
+        assert_eq!(set.samples.len(), 2);
+        assert_eq!(set.samples[1].pos, (4, 5, 6));
+        assert_eq!(set.samples[1].genome, h);
+        assert_eq!(set.diversity(), Some(8.0 / 128.0));
+        assert_eq!(GenomeSampleSet::from_words(0, 4, &[0]).diversity(), None);
+    }
+
+    #[test]
+    fn cpu_sampling_takes_live_protocells() {
+        let gs = 4;
+        let mut voxels = vec![Voxel::default(); 64];
+        voxels[5].voxel_type = VoxelType::Protocell;
+        voxels[63].voxel_type = VoxelType::Protocell;
+        voxels[7].voxel_type = VoxelType::Nutrient;
+        let set = GenomeSampleSet::sample(&voxels, gs, 1, 10, u32::MAX, 1);
+        assert_eq!(set.total, 2);
+        assert_eq!(set.samples.iter().map(|s| s.pos).collect::<Vec<_>>(), vec![(1, 1, 0), (3, 3, 3)]);
+        assert!(GenomeSampleSet::sample(&voxels, gs, 1, 1, u32::MAX, 1).samples.len() == 1);
+    }
+}
@@ -0,0 +1,260 @@
+//! Shape tools: sphere shells, cylinders and planes rasterized on the CPU
+//! into as few FillRegion boxes as greedy merging finds.
+
+use std::collections::HashMap;
+
+use crate::{CellBox, Command};
+
+/// Largest radius the shape tools accept.
+pub const MAX_SHAPE_RADIUS: u32 = 256;
+
+/// Shape tool kinds; the discriminant is the `kind` argument of
+/// `place_shape` (tools 9, 10 and 11 use them in order).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeKind {
+    SphereShell = 0,
+    Cylinder = 1,
+    Plane = 2,
+}
+
+impl ShapeKind {
+    pub const ALL: [ShapeKind; 3] = [ShapeKind::SphereShell, ShapeKind::Cylinder, ShapeKind::Plane];
+
+    pub fn from_u32(v: u32) -> Option<Self> {
+        Self::ALL.get(v as usize).copied()
+    }
+}
+
+/// Size and material shared by the shape tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeParams {
+    pub radius: u32,
+    /// Wall thickness in cells (at least 1).
+    pub thickness: u32,
+    /// Cylinder length in cells.
+    pub length: u32,
+    /// Cylinder and plane axis: 0 = x, 1 = y, 2 = z.
+    pub axis: usize,
+    /// Voxel type the shape is built from.
+    pub voxel_type: u32,
+}
+
+impl Default for ShapeParams {
+    fn default() -> Self {
+        Self { radius: 8, thickness: 1, length: 12, axis: 1, voxel_type: crate::VoxelType::Wall as u32 }
+    }
+}
+
+impl ShapeParams {
+    /// The `kind` shape anchored at `at`: a sphere's center, a cylinder's
+    /// base center or a point on the plane.
+    pub fn shape(&self, kind: ShapeKind, at: (u32, u32, u32)) -> Shape {
+        let at = [at.0 as i32, at.1 as i32, at.2 as i32];
+        let (thickness, axis) = (self.thickness.max(1), self.axis.min(2));
+        match kind {
+            ShapeKind::SphereShell => Shape::SphereShell { center: at, radius: self.radius, thickness },
+            ShapeKind::Cylinder => {
+                Shape::Cylinder { base: at, axis, radius: self.radius, length: self.length.max(1), thickness }
+            }
+            ShapeKind::Plane => Shape::Plane { at, axis, thickness },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Cells within `thickness` inside `radius` of `center`.
+    SphereShell { center: [i32; 3], radius: u32, thickness: u32 },
+    /// Open-topped tube from `base` along +`axis` with a floor at the base.
+    Cylinder { base: [i32; 3], axis: usize, radius: u32, length: u32, thickness: u32 },
+    /// Slab across the whole grid, `thickness` cells from `at` along +`axis`.
+    Plane { at: [i32; 3], axis: usize, thickness: u32 },
+}
+
+/// Whether squared distance `d2` lies within a ring of `radius` and
+/// `thickness`, with cell centers rounding to the nearest half cell.
+fn in_ring(d2: i64, radius: u32, thickness: u32) -> bool {
+    let (r, t) = (radius as i64, thickness as i64);
+    let outer = 2 * r + 1;
+    let inner = 2 * (r - t) + 1;
+    4 * d2 <= outer * outer && (t > r || 4 * d2 > inner * inner)
+}
+
+impl Shape {
+    pub fn contains(&self, p: [i32; 3]) -> bool {
+        match *self {
+            Shape::SphereShell { center, radius, thickness } => {
+                let d2 = (0..3).map(|i| ((p[i] - center[i]) as i64).pow(2)).sum();
+                in_ring(d2, radius, thickness)
+            }
+            Shape::Cylinder { base, axis, radius, length, thickness } => {
+                let along = p[axis] - base[axis];
+                if along < 0 || along >= length as i32 {
+                    return false;
+                }
+                let d2 = (0..3).filter(|&i| i != axis).map(|i| ((p[i] - base[i]) as i64).pow(2)).sum();
+                let floor = (along as u32) < thickness;
+                in_ring(d2, radius, if floor { radius + 1 } else { thickness })
+            }
+            Shape::Plane { at, axis, thickness } => (0..thickness as i32).contains(&(p[axis] - at[axis])),
+        }
+    }
+
+    /// Inclusive bounding box, unclipped.
+    fn bounds(&self) -> ([i32; 3], [i32; 3]) {
+        match *self {
+            Shape::SphereShell { center, radius, .. } => {
+                let r = radius as i32;
+                (center.map(|c| c - r), center.map(|c| c + r))
+            }
+            Shape::Cylinder { base, axis, radius, length, .. } => {
+                let r = radius as i32;
+                let (mut lo, mut hi) = (base.map(|c| c - r), base.map(|c| c + r));
+                lo[axis] = base[axis];
+                hi[axis] = base[axis] + length as i32 - 1;
+                (lo, hi)
+            }
+            Shape::Plane { at, axis, thickness } => {
+                let (mut lo, mut hi) = ([i32::MIN / 2; 3], [i32::MAX / 2; 3]);
+                lo[axis] = at[axis];
+                hi[axis] = at[axis] + thickness as i32 - 1;
+                (lo, hi)
+            }
+        }
+    }
+
+    /// Inclusive boxes covering the shape's cells inside a `grid_size`³
+    /// grid: x runs merged over y, then identical rectangles merged over z.
+    pub fn boxes(&self, grid_size: u32) -> Vec<CellBox> {
+        let (lo, hi) = self.bounds();
+        let top = grid_size as i32 - 1;
+        let lo = lo.map(|c| c.clamp(0, top));
+        let hi = hi.map(|c| c.clamp(-1, top));
+        let mut boxes: Vec<([i32; 3], [i32; 3])> = Vec::new();
+        // (x0, x1, y0, y1) -> box still growing along z
+        let mut open_z: HashMap<(i32, i32, i32, i32), usize> = HashMap::new();
+        for z in lo[2]..=hi[2] {
+            // (x0, x1) -> rectangle (y0, y1) still growing along y
+            let mut rects: Vec<(i32, i32, i32, i32)> = Vec::new();
+            let mut open_y: HashMap<(i32, i32), usize> = HashMap::new();
+            for y in lo[1]..=hi[1] {
+                let mut next_open = HashMap::new();
+                let mut x = lo[0];
+                while x <= hi[0] {
+                    if !self.contains([x, y, z]) {
+                        x += 1;
+                        continue;
+                    }
+                    let x0 = x;
+                    while x < hi[0] && self.contains([x + 1, y, z]) {
+                        x += 1;
+                    }
+                    let run = (x0, x);
+                    match open_y.get(&run) {
+                        Some(&i) => {
+                            rects[i].3 = y;
+                            next_open.insert(run, i);
+                        }
+                        None => {
+                            rects.push((x0, x, y, y));
+                            next_open.insert(run, rects.len() - 1);
+                        }
+                    }
+                    x += 1;
+                }
+                open_y = next_open;
+            }
+            let mut next_open = HashMap::new();
+            for rect in rects {
+                match open_z.get(&rect) {
+                    Some(&i) => {
+                        boxes[i].1[2] = z;
+                        next_open.insert(rect, i);
+                    }
+                    None => {
+                        boxes.push(([rect.0, rect.2, z], [rect.1, rect.3, z]));
+                        next_open.insert(rect, boxes.len() - 1);
+                    }
+                }
+            }
+            open_z = next_open;
+        }
+        let cell = |c: [i32; 3]| (c[0] as u32, c[1] as u32, c[2] as u32);
+        boxes.into_iter().map(|(lo, hi)| (cell(lo), cell(hi))).collect()
+    }
+
+    /// FillRegion commands building the shape from `voxel_type`, overwriting
+    /// whatever is there.
+    pub fn fill_commands(&self, voxel_type: u32, grid_size: u32) -> Vec<Command> {
+        self.boxes(grid_size).into_iter().map(|(lo, hi)| Command::fill_box(lo, hi, voxel_type)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every cell the boxes cover, checking they do not overlap.
+    fn covered(shape: &Shape, gs: u32) -> Vec<bool> {
+        let mut cells = vec![false; (gs * gs * gs) as usize];
+        for (lo, hi) in shape.boxes(gs) {
+            for z in lo.2..=hi.2 {
+                for y in lo.1..=hi.1 {
+                    for x in lo.0..=hi.0 {
+                        let i = crate::grid_index(x, y, z, gs);
+                        assert!(!cells[i], "boxes overlap at {x},{y},{z}");
+                        cells[i] = true;
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    fn assert_exact(shape: Shape, gs: u32) {
+        let cells = covered(&shape, gs);
+        for (i, &c) in cells.iter().enumerate() {
+            let (x, y, z) = crate::grid_coords(i, gs);
+            assert_eq!(c, shape.contains([x as i32, y as i32, z as i32]), "cell {x},{y},{z}");
+        }
+    }
+
+    #[test]
+    fn sphere_shell_is_hollow() {
+        let shell = ShapeParams { radius: 5, thickness: 1, ..ShapeParams::default() }.shape(ShapeKind::SphereShell, (8, 8, 8));
+        assert!(shell.contains([13, 8, 8]));
+        assert!(shell.contains([8, 3, 8]));
+        assert!(!shell.contains([8, 8, 8]));
+        assert!(!shell.contains([11, 8, 8]));
+        assert!(!shell.contains([14, 8, 8]));
+        assert_exact(shell, 16);
+        // Clipped at the grid edge
+        assert_exact(Shape::SphereShell { center: [1, 14, 2], radius: 4, thickness: 2 }, 16);
+        // Thicker than the radius: a solid ball
+        assert!(Shape::SphereShell { center: [8, 8, 8], radius: 2, thickness: 5 }.contains([8, 8, 8]));
+    }
+
+    #[test]
+    fn cylinder_has_a_floor_and_open_top() {
+        let params = ShapeParams { radius: 3, thickness: 1, length: 6, axis: 1, ..ShapeParams::default() };
+        let vessel = params.shape(ShapeKind::Cylinder, (8, 2, 8));
+        assert!(vessel.contains([8, 2, 8]));
+        assert!(!vessel.contains([8, 3, 8]));
+        assert!(vessel.contains([11, 7, 8]));
+        assert!(!vessel.contains([11, 8, 8]));
+        assert!(!vessel.contains([8, 7, 8]));
+        assert_exact(vessel, 16);
+        assert_exact(ShapeParams { axis: 0, ..params }.shape(ShapeKind::Cylinder, (12, 1, 3)), 16);
+    }
+
+    #[test]
+    fn plane_is_one_box() {
+        let plane = ShapeParams { thickness: 2, axis: 2, ..ShapeParams::default() }.shape(ShapeKind::Plane, (3, 4, 5));
+        assert_eq!(plane.boxes(16), vec![((0, 0, 5), (15, 15, 6))]);
+        let cmds = plane.fill_commands(crate::VoxelType::Wall as u32, 16);
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].param_1, crate::pack_region_max(15, 15, 6));
+        assert!(Shape::Plane { at: [0, 0, 40], axis: 2, thickness: 1 }.boxes(16).is_empty());
+        assert_eq!(ShapeKind::from_u32(3), None);
+    }
+}
@@ -29,6 +29,46 @@ pub fn neighbor_offsets() -> [(i32, i32, i32); 6] {
     ]
 }
 
+/// Inclusive (min, max) corners of an axis-aligned box of cells.
+pub type CellBox = ((u32, u32, u32), (u32, u32, u32));
+
+/// Straight-line distance between two cells.
+pub fn euclidean_distance(a: (u32, u32, u32), b: (u32, u32, u32)) -> f32 {
+    let d = |p: u32, q: u32| p.abs_diff(q) as f32;
+    (d(a.0, b.0).powi(2) + d(a.1, b.1).powi(2) + d(a.2, b.2).powi(2)).sqrt()
+}
+
+/// Face-step (L1) distance between two cells.
+pub fn manhattan_distance(a: (u32, u32, u32), b: (u32, u32, u32)) -> u32 {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1) + a.2.abs_diff(b.2)
+}
+
+/// Inclusive bounding box (min, max) of `points`, or `None` if empty.
+pub fn bounding_box(points: &[(u32, u32, u32)]) -> Option<CellBox> {
+    let (&first, rest) = points.split_first()?;
+    Some(rest.iter().fold((first, first), |(lo, hi), &p| {
+        ((lo.0.min(p.0), lo.1.min(p.1), lo.2.min(p.2)), (hi.0.max(p.0), hi.1.max(p.1), hi.2.max(p.2)))
+    }))
+}
+
+/// `bounds` grown by `margin` cells on every side, clamped to the grid.
+pub fn expand_box(bounds: CellBox, margin: u32, grid_size: u32) -> CellBox {
+    let (lo, hi) = bounds;
+    let last = grid_size.saturating_sub(1);
+    let grow = |v: u32| v.saturating_add(margin).min(last);
+    (
+        (lo.0.saturating_sub(margin), lo.1.saturating_sub(margin), lo.2.saturating_sub(margin)),
+        (grow(hi.0), grow(hi.1), grow(hi.2)),
+    )
+}
+
+/// Smallest box holding both `a` and `b`.
+pub fn union_box(a: CellBox, b: CellBox) -> CellBox {
+    let lo = (a.0.0.min(b.0.0), a.0.1.min(b.0.1), a.0.2.min(b.0.2));
+    let hi = (a.1.0.max(b.1.0), a.1.1.max(b.1.1), a.1.2.max(b.1.2));
+    (lo, hi)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,6 +93,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn distances_and_bounds() {
+        assert_eq!(euclidean_distance((0, 0, 0), (3, 4, 0)), 5.0);
+        assert_eq!(manhattan_distance((5, 1, 9), (2, 4, 9)), 6);
+        assert_eq!(bounding_box(&[]), None);
+        assert_eq!(bounding_box(&[(4, 0, 7), (1, 5, 7), (3, 2, 9)]), Some(((1, 0, 7), (4, 5, 9))));
+    }
+
+    #[test]
+    fn expand_and_union_boxes() {
+        let b = ((2, 10, 60), (5, 12, 62));
+        assert_eq!(expand_box(b, 3, 64), ((0, 7, 57), (8, 15, 63)));
+        assert_eq!(expand_box(b, 0, 64), b);
+        assert_eq!(union_box(b, ((0, 11, 61), (3, 20, 61))), ((0, 10, 60), (5, 20, 62)));
+    }
+
     #[test]
     fn neighbor_offsets_count() {
         assert_eq!(neighbor_offsets().len(), 6);
@@ -1,10 +1,11 @@
-/// Intent encoding matching the WGSL bit layout.
+use crate::layout;
+
+/// Intent encoding; the bit fields are `layout::INTENT_*`.
 ///
 /// Intent word (u32):
 ///   [0:2]  target_direction (3 bits, 0-6)
 ///   [3:5]  action_type (3 bits, 0-5)
 ///   [6:31] bid (26 bits)
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Direction {
@@ -14,18 +15,18 @@ pub enum Direction {
     NegY = 3,
     PosZ = 4,
     NegZ = 5,
-    Self_ = 6,
+    Self_ = layout::DIR_SELF as u8,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ActionType {
-    NoAction = 0,
-    Die = 1,
-    Predate = 2,
-    Replicate = 3,
-    Move = 4,
-    Idle = 5,
+    NoAction = layout::ACTION_NO_ACTION as u8,
+    Die = layout::ACTION_DIE as u8,
+    Predate = layout::ACTION_PREDATE as u8,
+    Replicate = layout::ACTION_REPLICATE as u8,
+    Move = layout::ACTION_MOVE as u8,
+    Idle = layout::ACTION_IDLE as u8,
 }
 
 impl ActionType {
@@ -59,17 +60,16 @@ impl Direction {
 
 /// Encode an intent into a single u32.
 pub fn intent_encode(action: ActionType, direction: Direction, bid: u32) -> u32 {
-    let dir_bits = (direction as u32) & 0x7;
-    let action_bits = ((action as u32) & 0x7) << 3;
-    let bid_bits = (bid & 0x03FF_FFFF) << 6;
-    dir_bits | action_bits | bid_bits
+    layout::INTENT_DIRECTION.put(direction as u32)
+        | layout::INTENT_ACTION.put(action as u32)
+        | layout::INTENT_BID.put(bid)
 }
 
 /// Decode an intent u32 into (ActionType, Direction, bid).
 pub fn intent_decode(word: u32) -> (ActionType, Direction, u32) {
-    let dir = Direction::from_u8((word & 0x7) as u8);
-    let action = ActionType::from_u8(((word >> 3) & 0x7) as u8);
-    let bid = (word >> 6) & 0x03FF_FFFF;
+    let dir = Direction::from_u8(layout::INTENT_DIRECTION.get(word) as u8);
+    let action = ActionType::from_u8(layout::INTENT_ACTION.get(word) as u8);
+    let bid = layout::INTENT_BID.get(word);
     (action, dir, bid)
 }
 
@@ -0,0 +1,503 @@
+//! GPU buffer layouts shared by the Rust packers and the shaders: voxel and
+//! intent bit fields, command words, SimParams sections, TickParams, stats
+//! slots and the enum values both sides switch on. build.rs renders this
+//! module into layout.wgsl (`to_wgsl`), which common.wgsl includes, so no
+//! shader restates a layout by hand. build.rs compiles this file on its
+//! own: it must not use anything else from the crate.
+
+/// `bits` bits of a u32 word, starting at bit `shift`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitField {
+    pub shift: u32,
+    pub bits: u32,
+}
+
+impl BitField {
+    pub const fn mask(self) -> u32 {
+        if self.bits >= 32 { u32::MAX } else { (1 << self.bits) - 1 }
+    }
+
+    pub const fn get(self, word: u32) -> u32 {
+        (word >> self.shift) & self.mask()
+    }
+
+    /// `value`, truncated to the field, in place for OR-ing into a word.
+    pub const fn put(self, value: u32) -> u32 {
+        (value & self.mask()) << self.shift
+    }
+}
+
+// ---- Voxel: VOXEL_STRIDE words per cell ----
+
+pub const VOXEL_STRIDE: u32 = 8;
+/// Word 0
+pub const VOXEL_TYPE: BitField = BitField { shift: 0, bits: 8 };
+pub const VOXEL_FLAGS: BitField = BitField { shift: 8, bits: 8 };
+pub const VOXEL_ENERGY: BitField = BitField { shift: 16, bits: 16 };
+/// Word 1
+pub const VOXEL_AGE: BitField = BitField { shift: 0, bits: 16 };
+pub const VOXEL_SPECIES: BitField = BitField { shift: 16, bits: 16 };
+/// First of the 4 genome words; the 2 extra words follow them.
+pub const VOXEL_GENOME_WORD: u32 = 2;
+pub const VOXEL_EXTRA_WORD: u32 = 6;
+/// Extra word 0 of heat/cold sources: lifetime in ticks, 0 = permanent.
+pub const THERMAL_LIFETIME: BitField = BitField { shift: 0, bits: 16 };
+
+pub const VOXEL_EMPTY: u32 = 0;
+pub const VOXEL_WALL: u32 = 1;
+pub const VOXEL_NUTRIENT: u32 = 2;
+pub const VOXEL_ENERGY_SOURCE: u32 = 3;
+pub const VOXEL_PROTOCELL: u32 = 4;
+pub const VOXEL_WASTE: u32 = 5;
+pub const VOXEL_HEAT_SOURCE: u32 = 6;
+pub const VOXEL_COLD_SOURCE: u32 = 7;
+
+/// Protocell flags: [0:2] growth direction (parent → cell), [3] linked,
+/// [4] dormant, [5] inside the goal zone.
+pub const FLAG_GROWTH_DIR_MASK: u32 = 0x07;
+pub const FLAG_LINKED: u32 = 0x08;
+pub const FLAG_DORMANT: u32 = 0x10;
+pub const FLAG_IN_GOAL: u32 = 0x20;
+/// Waste flags: [0] killed by field toxin this tick (age 0 only).
+pub const WASTE_FLAG_TOXIN: u32 = 0x01;
+/// Any voxel type, empty included: [7] frozen — every tick pass leaves the
+/// cell as it is and neighbours cannot act on it.
+pub const FLAG_FROZEN: u32 = 0x80;
+
+// ---- Intent: one word per cell ----
+
+pub const INTENT_DIRECTION: BitField = BitField { shift: 0, bits: 3 };
+pub const INTENT_ACTION: BitField = BitField { shift: 3, bits: 3 };
+pub const INTENT_BID: BitField = BitField { shift: 6, bits: 26 };
+
+pub const ACTION_NO_ACTION: u32 = 0;
+pub const ACTION_DIE: u32 = 1;
+pub const ACTION_PREDATE: u32 = 2;
+pub const ACTION_REPLICATE: u32 = 3;
+pub const ACTION_MOVE: u32 = 4;
+pub const ACTION_IDLE: u32 = 5;
+/// Intent direction of actions that stay in place; 0-5 index NEIGHBORS.
+pub const DIR_SELF: u32 = 6;
+
+// ---- Command buffer: a header, then COMMAND_WORDS words per command ----
+
+/// Header words; [0] is the command count.
+pub const COMMAND_HEADER_WORDS: u32 = 4;
+pub const COMMAND_WORDS: u32 = 16;
+pub const MAX_COMMANDS_PER_TICK: u32 = 64;
+
+pub const CMD_NOOP: u32 = 0;
+pub const CMD_PLACE_VOXEL: u32 = 1;
+pub const CMD_REMOVE_VOXEL: u32 = 2;
+pub const CMD_SEED_PROTOCELLS: u32 = 3;
+pub const CMD_APPLY_TOXIN: u32 = 4;
+pub const CMD_FILL_REGION: u32 = 5;
+pub const CMD_FREEZE_REGION: u32 = 6;
+/// FillRegion `param_0` flag: replace occupied voxels too.
+pub const FILL_OVERWRITE: u32 = 1 << 16;
+
+// ---- SimParams uniform: SIM_PARAMS_WORDS f32 words in fixed sections ----
+
+/// Layout revision, stored in the uniform's first word. Bump it when a
+/// field moves; a new field in its section's spare words moves nothing.
+pub const SIM_PARAMS_VERSION: u32 = 3;
+
+/// A fixed run of uniform words: its fields, then zeroed spare words that
+/// later fields of the same kind take without shifting the next section.
+#[derive(Debug, Clone, Copy)]
+pub struct ParamsSection {
+    pub name: &'static str,
+    pub words: u32,
+    pub fields: &'static [&'static str],
+}
+
+/// The uniform, in order. Field names are as in `SimParams`, except
+/// `params_version` (`SIM_PARAMS_VERSION`).
+pub const SIM_PARAMS_SECTIONS: [ParamsSection; 5] = [
+    ParamsSection {
+        name: "frame",
+        words: 16,
+        fields: &[
+            "params_version",
+            "grid_size",
+            "dt",
+            "sparse_mode",
+            "brick_grid_dim",
+            "max_bricks",
+        ],
+    },
+    ParamsSection {
+        name: "cells",
+        words: 32,
+        fields: &[
+            "metabolic_cost_base",
+            "movement_energy_cost",
+            "replication_energy_min",
+            "max_energy",
+            "predation_energy_fraction",
+            "temp_sensitivity",
+            "crowding_threshold",
+            "crowding_cost",
+            "crowding_blocks_replication",
+            "dormancy_temp",
+            "dormancy_cost_scale",
+            "heat_shock_temp",
+            "heat_shock_damage",
+            "storage_capacity_bonus",
+            "storage_metabolic_cost",
+            "thermotaxis_response",
+            "pheromone_response",
+            "toxin_secretion_rate",
+            "toxin_secretion_cost",
+            "toxin_damage",
+        ],
+    },
+    ParamsSection {
+        name: "resources",
+        words: 16,
+        fields: &[
+            "energy_from_nutrient",
+            "energy_from_source",
+            "nutrient_spawn_rate",
+            "nutrient_recycle_rate",
+            "waste_decay_ticks",
+            "nutrient_pattern",
+            "nutrient_capacity",
+            "nutrient_waste_boost",
+            "nutrient_front_speed",
+        ],
+    },
+    ParamsSection {
+        name: "fields",
+        words: 16,
+        fields: &[
+            "base_ambient_temp",
+            "diffusion_rate",
+            "diffusion_substeps",
+            "thermal_boundary",
+            "pheromone_deposit",
+            "pheromone_decay",
+            "pheromone_spread",
+            "toxin_decay",
+        ],
+    },
+    ParamsSection {
+        name: "world",
+        words: 16,
+        fields: &[
+            "goal_min_x",
+            "goal_min_y",
+            "goal_min_z",
+            "goal_max_x",
+            "goal_max_y",
+            "goal_max_z",
+            "dilation_min_x",
+            "dilation_min_y",
+            "dilation_min_z",
+            "dilation_max_x",
+            "dilation_max_y",
+            "dilation_max_z",
+            "dilation_factor",
+        ],
+    },
+];
+
+/// Total uniform words (384 bytes).
+pub const SIM_PARAMS_WORDS: u32 = 96;
+
+const _: () = {
+    let mut total = 0;
+    let mut i = 0;
+    while i < SIM_PARAMS_SECTIONS.len() {
+        let section = SIM_PARAMS_SECTIONS[i];
+        assert!(section.fields.len() as u32 <= section.words);
+        assert!(section.words.is_multiple_of(4), "sections keep 16-byte alignment");
+        total += section.words;
+        i += 1;
+    }
+    assert!(total == SIM_PARAMS_WORDS);
+};
+
+// ---- TickParams uniform: the u32 words that change every tick ----
+
+/// Field names as in `TickParams`; the padding fills the 16-byte binding.
+pub const TICK_PARAMS_FIELDS: [&str; 4] = ["tick_count", "overlay_mode", "overlay_trait", "_pad0"];
+
+// ---- RuleParams uniform: four u32 words, then the protected species ----
+
+/// Species ids the resolve pass keeps alive; 0 marks an unused entry.
+pub const MAX_PROTECTED_SPECIES: u32 = 8;
+/// Mutation-rate min and max, arbitration policy, padding, then
+/// `MAX_PROTECTED_SPECIES` ids (one per word, read as `array<vec4<u32>>`).
+pub const RULE_PARAMS_WORDS: u32 = 4 + MAX_PROTECTED_SPECIES;
+
+// ---- Stats buffer: STATS_WORDS u32 words, one slot per metric ----
+
+/// Stats layout revision, written by stats_reduction.wgsl into
+/// `StatsSlot::Version`; a readback with another value is discarded.
+pub const STATS_LAYOUT_VERSION: u32 = 4;
+/// Words in the stats buffer; the words after the last slot are spare.
+pub const STATS_WORDS: u32 = 256;
+/// Entries of the species histogram (and of the goal-zone counts). Raise
+/// it to track more species; protocells of species that find no entry are
+/// counted in `StatsSlot::OtherSpecies`.
+pub const STATS_SPECIES_SLOTS: u32 = 64;
+/// Entries of each workgroup's species table in stats_reduction.wgsl,
+/// merged into the histogram; overflow goes to `OtherSpecies` too. As large
+/// as the histogram, so no workgroup spills while it has room.
+pub const STATS_WG_SPECIES_SLOTS: u32 = STATS_SPECIES_SLOTS;
+/// Workgroups of the first stats stage; each counts a contiguous range of
+/// cells (`stats_partial_range`) and writes one partial record that the
+/// finalize stage folds into the stats buffer.
+pub const STATS_PARTIAL_GROUPS: u32 = 512;
+/// Scalar words leading a partial record (some spare).
+pub const STATS_PARTIAL_SCALARS: u32 = 24;
+/// Words per partial record: the scalars, then the workgroup species table
+/// as ids, counts and goal-zone counts.
+pub const STATS_PARTIAL_WORDS: u32 = STATS_PARTIAL_SCALARS + STATS_WG_SPECIES_SLOTS * 3;
+
+/// Word of the energy-flow buffer where the speciation log starts, right
+/// after the `trophic::ENERGY_FLOW_WORDS` counters.
+pub const LINEAGE_LOG_OFFSET: u32 = 12;
+/// (parent, child, tick) records the speciation log holds per stats window;
+/// later speciations are only counted.
+pub const LINEAGE_LOG_CAPACITY: u32 = 1024;
+
+/// Every metric of the stats buffer, in buffer order. WGSL gets each
+/// offset as `STATS_<NAME>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsSlot {
+    Version,
+    Population,
+    TotalEnergy,
+    MaxEnergy,
+    /// Protocells killed by field toxin this tick.
+    ToxinDeaths,
+    /// Clustered protocells + 1; 0 = not measured (sparse mode).
+    Clustered,
+    /// Sum of the mutation-rate gene (byte 3) over protocells.
+    MutationRateSum,
+    /// Active bounds max x/y/z + 1 (0 = nothing non-empty; dense only).
+    ActiveMax,
+    /// Active bounds ACTIVE_MIN_BIAS - min x/y/z.
+    ActiveMin,
+    /// (species_id, count) per entry.
+    SpeciesHistogram,
+    /// Protocells inside the goal zone, per histogram entry.
+    GoalHistogram,
+    /// Protocells whose species found no histogram entry, then how many of
+    /// them are in the goal zone.
+    OtherSpecies,
+    /// Sum of protocell ages.
+    AgeSum,
+    /// Nutrient, waste and wall voxels.
+    VoxelCounts,
+    /// Field min/max/sums, written by field_stats.wgsl
+    /// (`FieldStats::from_words`).
+    FieldStats,
+}
+
+impl StatsSlot {
+    pub const ALL: [StatsSlot; 15] = [
+        StatsSlot::Version,
+        StatsSlot::Population,
+        StatsSlot::TotalEnergy,
+        StatsSlot::MaxEnergy,
+        StatsSlot::ToxinDeaths,
+        StatsSlot::Clustered,
+        StatsSlot::MutationRateSum,
+        StatsSlot::ActiveMax,
+        StatsSlot::ActiveMin,
+        StatsSlot::SpeciesHistogram,
+        StatsSlot::GoalHistogram,
+        StatsSlot::OtherSpecies,
+        StatsSlot::AgeSum,
+        StatsSlot::VoxelCounts,
+        StatsSlot::FieldStats,
+    ];
+
+    pub const fn words(self) -> u32 {
+        match self {
+            StatsSlot::ActiveMax | StatsSlot::ActiveMin | StatsSlot::VoxelCounts => 3,
+            StatsSlot::OtherSpecies => 2,
+            StatsSlot::SpeciesHistogram => STATS_SPECIES_SLOTS * 2,
+            StatsSlot::GoalHistogram => STATS_SPECIES_SLOTS,
+            StatsSlot::FieldStats => 11,
+            _ => 1,
+        }
+    }
+
+    /// First word: the slots are packed in `ALL` order.
+    pub const fn offset(self) -> u32 {
+        let mut offset = 0;
+        let mut i = 0;
+        while StatsSlot::ALL[i] as u32 != self as u32 {
+            offset += StatsSlot::ALL[i].words();
+            i += 1;
+        }
+        offset
+    }
+
+    pub const fn range(self) -> std::ops::Range<usize> {
+        self.offset() as usize..(self.offset() + self.words()) as usize
+    }
+
+    /// WGSL name of the offset.
+    pub const fn name(self) -> &'static str {
+        match self {
+            StatsSlot::Version => "STATS_VERSION",
+            StatsSlot::Population => "STATS_POPULATION",
+            StatsSlot::TotalEnergy => "STATS_TOTAL_ENERGY",
+            StatsSlot::MaxEnergy => "STATS_MAX_ENERGY",
+            StatsSlot::ToxinDeaths => "STATS_TOXIN_DEATHS",
+            StatsSlot::Clustered => "STATS_CLUSTERED",
+            StatsSlot::MutationRateSum => "STATS_MUTATION_RATE_SUM",
+            StatsSlot::ActiveMax => "STATS_ACTIVE_MAX",
+            StatsSlot::ActiveMin => "STATS_ACTIVE_MIN",
+            StatsSlot::SpeciesHistogram => "STATS_SPECIES_HISTOGRAM",
+            StatsSlot::GoalHistogram => "STATS_GOAL_HISTOGRAM",
+            StatsSlot::OtherSpecies => "STATS_OTHER_SPECIES",
+            StatsSlot::AgeSum => "STATS_AGE_SUM",
+            StatsSlot::VoxelCounts => "STATS_VOXEL_COUNTS",
+            StatsSlot::FieldStats => "STATS_FIELD_STATS",
+        }
+    }
+}
+
+const _: () = {
+    let last = StatsSlot::ALL[StatsSlot::ALL.len() - 1];
+    assert!(last.offset() + last.words() <= STATS_WORDS);
+};
+
+/// Uniform word of the named SimParams field.
+pub fn sim_params_word(name: &str) -> Option<usize> {
+    let mut start = 0;
+    for section in SIM_PARAMS_SECTIONS {
+        if let Some(i) = section.fields.iter().position(|f| *f == name) {
+            return Some(start + i);
+        }
+        start += section.words as usize;
+    }
+    None
+}
+
+/// (name, value) of each constant, named as in this file.
+macro_rules! named {
+    ($($name:ident),* $(,)?) => {
+        [$((stringify!($name), $name)),*]
+    };
+}
+
+/// The layouts above as WGSL: u32 constants (stats slots as their offsets),
+/// `<FIELD>_SHIFT` / `<FIELD>_MASK`
+/// pairs for each bit field, `struct SimParams` with the spare words as
+/// `_<section>_spare<N>`, and `struct TickParams`.
+pub fn to_wgsl() -> String {
+    let constants = named![
+        VOXEL_STRIDE,
+        VOXEL_GENOME_WORD,
+        VOXEL_EXTRA_WORD,
+        VOXEL_EMPTY,
+        VOXEL_WALL,
+        VOXEL_NUTRIENT,
+        VOXEL_ENERGY_SOURCE,
+        VOXEL_PROTOCELL,
+        VOXEL_WASTE,
+        VOXEL_HEAT_SOURCE,
+        VOXEL_COLD_SOURCE,
+        FLAG_GROWTH_DIR_MASK,
+        FLAG_LINKED,
+        FLAG_DORMANT,
+        FLAG_IN_GOAL,
+        WASTE_FLAG_TOXIN,
+        FLAG_FROZEN,
+        ACTION_NO_ACTION,
+        ACTION_DIE,
+        ACTION_PREDATE,
+        ACTION_REPLICATE,
+        ACTION_MOVE,
+        ACTION_IDLE,
+        DIR_SELF,
+        COMMAND_HEADER_WORDS,
+        COMMAND_WORDS,
+        MAX_COMMANDS_PER_TICK,
+        CMD_NOOP,
+        CMD_PLACE_VOXEL,
+        CMD_REMOVE_VOXEL,
+        CMD_SEED_PROTOCELLS,
+        CMD_APPLY_TOXIN,
+        CMD_FILL_REGION,
+        CMD_FREEZE_REGION,
+        FILL_OVERWRITE,
+        SIM_PARAMS_VERSION,
+        SIM_PARAMS_WORDS,
+        MAX_PROTECTED_SPECIES,
+        STATS_LAYOUT_VERSION,
+        STATS_WORDS,
+        STATS_SPECIES_SLOTS,
+        STATS_WG_SPECIES_SLOTS,
+        STATS_PARTIAL_GROUPS,
+        STATS_PARTIAL_SCALARS,
+        STATS_PARTIAL_WORDS,
+        LINEAGE_LOG_OFFSET,
+        LINEAGE_LOG_CAPACITY,
+    ];
+    let fields = named![
+        VOXEL_TYPE,
+        VOXEL_FLAGS,
+        VOXEL_ENERGY,
+        VOXEL_AGE,
+        VOXEL_SPECIES,
+        THERMAL_LIFETIME,
+        INTENT_DIRECTION,
+        INTENT_ACTION,
+        INTENT_BID,
+    ];
+
+    let mut out = String::from("// Generated by crates/types/build.rs from crates/types/src/layout.rs.\n\n");
+    for (name, value) in constants {
+        out.push_str(&format!("const {name}: u32 = {value}u;\n"));
+    }
+    for slot in StatsSlot::ALL {
+        out.push_str(&format!("const {}: u32 = {}u;\n", slot.name(), slot.offset()));
+    }
+    out.push('\n');
+    for (name, field) in fields {
+        out.push_str(&format!("const {name}_SHIFT: u32 = {}u;\n", field.shift));
+        out.push_str(&format!("const {name}_MASK: u32 = 0x{:X}u;\n", field.mask()));
+    }
+    out.push_str("\nstruct SimParams {\n");
+    for section in SIM_PARAMS_SECTIONS {
+        out.push_str(&format!("    // {} ({} words)\n", section.name, section.words));
+        for name in section.fields {
+            out.push_str(&format!("    {name}: f32,\n"));
+        }
+        for i in section.fields.len() as u32..section.words {
+            out.push_str(&format!("    _{}_spare{i}: f32,\n", section.name));
+        }
+    }
+    out.push_str("};\n\nstruct TickParams {\n");
+    for name in TICK_PARAMS_FIELDS {
+        out.push_str(&format!("    {name}: u32,\n"));
+    }
+    out.push_str("};\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_slots_are_packed_in_order() {
+        let mut next = 0;
+        for slot in StatsSlot::ALL {
+            assert_eq!(slot.offset(), next, "{slot:?}");
+            next += slot.words();
+        }
+        assert_eq!(StatsSlot::Version.range(), 0..1);
+        assert_eq!(StatsSlot::SpeciesHistogram.range().len(), 128);
+        assert_eq!(StatsSlot::OtherSpecies.offset(), StatsSlot::GoalHistogram.offset() + STATS_SPECIES_SLOTS);
+        assert!(to_wgsl().contains("const STATS_FIELD_STATS: u32 = "));
+    }
+}
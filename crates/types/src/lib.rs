@@ -4,6 +4,43 @@ pub mod voxel;
 pub mod params;
 pub mod intent;
 pub mod commands;
+pub mod pheromone;
+pub mod bloom;
+pub mod challenge;
+pub mod census;
+pub mod sonify;
+pub mod render_style;
+pub mod marker;
+pub mod recording;
+pub mod scenario;
+pub mod ecology;
+pub mod engine_log;
+pub mod trophic;
+pub mod lineage;
+pub mod arbitration;
+pub mod genome_sample;
+pub mod png;
+pub mod noise;
+pub mod geometry;
+pub mod terrain;
+pub mod custom_seed;
+pub mod seed_config;
+pub mod symmetry;
+pub mod lod;
+pub mod brick_pool;
+pub mod transfer;
+pub mod slices;
+pub mod vox;
+pub mod view_snapshot;
+pub mod snapshot;
+pub mod overlay;
+pub mod field_stats;
+pub mod features;
+pub mod wgsl;
+pub mod layout;
+pub mod tick_clock;
+pub mod aux_budget;
+pub mod stats_partials;
 
 pub use grid::*;
 pub use genome::*;
@@ -11,3 +48,39 @@ pub use voxel::*;
 pub use params::*;
 pub use intent::*;
 pub use commands::*;
+pub use pheromone::*;
+pub use bloom::*;
+pub use challenge::*;
+pub use census::*;
+pub use sonify::*;
+pub use render_style::*;
+pub use marker::*;
+pub use recording::*;
+pub use scenario::*;
+pub use ecology::*;
+pub use engine_log::*;
+pub use trophic::*;
+pub use lineage::*;
+pub use arbitration::*;
+pub use genome_sample::*;
+pub use png::*;
+pub use noise::*;
+pub use geometry::*;
+pub use terrain::*;
+pub use custom_seed::*;
+pub use seed_config::*;
+pub use symmetry::*;
+pub use lod::*;
+pub use brick_pool::*;
+pub use transfer::*;
+pub use slices::*;
+pub use vox::*;
+pub use view_snapshot::*;
+pub use snapshot::*;
+pub use overlay::*;
+pub use field_stats::*;
+pub use features::*;
+pub use wgsl::*;
+pub use tick_clock::*;
+pub use aux_budget::*;
+pub use stats_partials::*;
@@ -0,0 +1,192 @@
+//! Species ancestry. The resolve pass logs (parent species, child species,
+//! tick) whenever a replication's mutated genome hashes to another species,
+//! in the energy-flow buffer after its counters; each stats readback's log
+//! is folded into a `LineageTree`.
+
+use std::collections::BTreeMap;
+
+use crate::layout::{LINEAGE_LOG_CAPACITY, LINEAGE_LOG_OFFSET};
+
+/// Words of the speciation log: the append counter, then
+/// `LINEAGE_LOG_CAPACITY` (parent, child, tick) records.
+pub const LINEAGE_LOG_WORDS: usize = 1 + LINEAGE_LOG_CAPACITY as usize * 3;
+
+/// Species the tree holds before extinct leaves are pruned.
+pub const MAX_LINEAGE_SPECIES: usize = 4096;
+
+/// One speciation: an offspring of `parent` founded `child`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineageEvent {
+    pub parent: u16,
+    pub child: u16,
+    pub tick: u32,
+}
+
+/// Logged speciations of a stats window, in tick order, and how many more
+/// happened after the log filled.
+pub fn parse_lineage_log(counters: &[u32]) -> (Vec<LineageEvent>, u32) {
+    let log = counters.get(LINEAGE_LOG_OFFSET as usize..).unwrap_or(&[]);
+    let Some((&appended, records)) = log.split_first() else {
+        return (Vec::new(), 0);
+    };
+    let logged = (appended as usize).min(LINEAGE_LOG_CAPACITY as usize);
+    let mut events: Vec<LineageEvent> = records
+        .chunks_exact(3)
+        .take(logged)
+        .map(|r| LineageEvent { parent: r[0] as u16, child: r[1] as u16, tick: r[2] })
+        .filter(|e| e.parent != 0 && e.child != 0 && e.parent != e.child)
+        .collect();
+    events.sort_by_key(|e| e.tick);
+    (events, appended.saturating_sub(LINEAGE_LOG_CAPACITY))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineageNode {
+    pub species_id: u16,
+    /// None for species first seen as a parent (founders, or ancestors
+    /// whose own origin was not logged).
+    pub parent: Option<u16>,
+    /// Tick the species was first seen.
+    pub tick: u32,
+}
+
+/// Phylogeny built from speciation logs. The first record of a species
+/// wins: a genome that re-evolves keeps its original parent.
+#[derive(Debug, Clone, Default)]
+pub struct LineageTree {
+    nodes: BTreeMap<u16, LineageNode>,
+    /// Speciations that did not fit a window's log.
+    pub unlogged: u64,
+    /// Extinct leaves removed to stay within `MAX_LINEAGE_SPECIES`.
+    pub pruned: u64,
+}
+
+impl LineageTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Fold in one window's log; `alive` (species currently counted) guards
+    /// living species from pruning. Returns the number of new species.
+    pub fn record(&mut self, events: &[LineageEvent], unlogged: u32, alive: &[u16]) -> usize {
+        self.unlogged += unlogged as u64;
+        let before = self.nodes.len();
+        for e in events {
+            self.nodes.entry(e.parent).or_insert(LineageNode { species_id: e.parent, parent: None, tick: e.tick });
+            self.nodes.entry(e.child).or_insert(LineageNode { species_id: e.child, parent: Some(e.parent), tick: e.tick });
+        }
+        let added = self.nodes.len() - before;
+        if self.nodes.len() > MAX_LINEAGE_SPECIES {
+            self.prune(alive);
+        }
+        added
+    }
+
+    /// Drop extinct leaves, oldest first, until within `MAX_LINEAGE_SPECIES`
+    /// (or none are left); their ancestors may become leaves in turn.
+    fn prune(&mut self, alive: &[u16]) {
+        while self.nodes.len() > MAX_LINEAGE_SPECIES {
+            let parents: std::collections::HashSet<u16> = self.nodes.values().filter_map(|n| n.parent).collect();
+            let mut leaves: Vec<&LineageNode> = self
+                .nodes
+                .values()
+                .filter(|n| !parents.contains(&n.species_id) && !alive.contains(&n.species_id))
+                .collect();
+            if leaves.is_empty() {
+                return;
+            }
+            leaves.sort_by_key(|n| n.tick);
+            let excess = self.nodes.len() - MAX_LINEAGE_SPECIES;
+            let doomed: Vec<u16> = leaves.iter().take(excess).map(|n| n.species_id).collect();
+            for sid in doomed {
+                self.nodes.remove(&sid);
+                self.pruned += 1;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, species_id: u16) -> Option<&LineageNode> {
+        self.nodes.get(&species_id)
+    }
+
+    /// Every species, by id.
+    pub fn nodes(&self) -> impl Iterator<Item = &LineageNode> {
+        self.nodes.values()
+    }
+
+    pub fn children(&self, species_id: u16) -> impl Iterator<Item = &LineageNode> {
+        self.nodes.values().filter(move |n| n.parent == Some(species_id))
+    }
+
+    /// `species_id`, its parent, and so on up to the oldest known ancestor.
+    pub fn ancestry(&self, species_id: u16) -> Vec<u16> {
+        let mut chain = Vec::new();
+        let mut next = self.nodes.get(&species_id).map(|n| n.species_id);
+        // A chain can't be longer than the tree; guards re-evolved loops
+        while let Some(sid) = next.filter(|_| chain.len() <= self.nodes.len()) {
+            chain.push(sid);
+            next = self.nodes.get(&sid).and_then(|n| n.parent).filter(|p| !chain.contains(p));
+        }
+        chain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(records: &[(u32, u32, u32)], appended: u32) -> Vec<u32> {
+        let mut words = vec![0u32; LINEAGE_LOG_OFFSET as usize + LINEAGE_LOG_WORDS];
+        words[LINEAGE_LOG_OFFSET as usize] = appended;
+        for (i, &(p, c, t)) in records.iter().enumerate() {
+            let at = LINEAGE_LOG_OFFSET as usize + 1 + i * 3;
+            words[at..at + 3].copy_from_slice(&[p, c, t]);
+        }
+        words
+    }
+
+    #[test]
+    fn logs_build_a_first_record_wins_tree() {
+        assert_eq!(LINEAGE_LOG_OFFSET as usize, crate::ENERGY_FLOW_WORDS);
+        let (events, unlogged) = parse_lineage_log(&log(&[(2, 3, 12), (1, 2, 10), (3, 1, 14)], 3));
+        assert_eq!(unlogged, 0);
+        assert_eq!(events.iter().map(|e| e.tick).collect::<Vec<_>>(), [10, 12, 14]);
+
+        let mut tree = LineageTree::new();
+        assert_eq!(tree.record(&events, 0, &[]), 3);
+        assert_eq!(tree.get(1).unwrap().parent, None);
+        assert_eq!(tree.get(3).unwrap().parent, Some(2));
+        // 3 → 1 came after 1 was known: 1 stays a root, so no cycle
+        assert_eq!(tree.ancestry(3), [3, 2, 1]);
+        assert_eq!(tree.children(2).map(|n| n.species_id).collect::<Vec<_>>(), [3]);
+
+        let (_, unlogged) = parse_lineage_log(&log(&[], LINEAGE_LOG_CAPACITY + 5));
+        assert_eq!(unlogged, 5);
+        assert_eq!(parse_lineage_log(&[]), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn pruning_keeps_the_living_and_their_ancestors() {
+        let mut tree = LineageTree::new();
+        let events: Vec<LineageEvent> =
+            (0..MAX_LINEAGE_SPECIES as u32).map(|i| LineageEvent { parent: 1, child: i as u16 + 2, tick: i }).collect();
+        let alive = [2u16, 3];
+        tree.record(&events, 0, &alive);
+        assert_eq!(tree.len(), MAX_LINEAGE_SPECIES);
+        assert_eq!(tree.pruned, 1);
+        assert!(tree.get(2).is_some() && tree.get(3).is_some() && tree.get(1).is_some());
+        assert!(tree.get(4).is_none());
+    }
+}
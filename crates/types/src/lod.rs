@@ -0,0 +1,60 @@
+//! Simulation level of detail for sparse worlds: bricks far from a focus
+//! point tick every `far_interval`-th tick instead of every tick, trading
+//! global synchrony for throughput on large grids.
+
+/// Longest tick interval a far brick may use.
+pub const MAX_LOD_INTERVAL: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimLod {
+    /// Bricks whose center lies within this many voxels of the focus tick
+    /// every tick.
+    pub near_radius: f32,
+    /// Ticks between updates of the other bricks (2..=`MAX_LOD_INTERVAL`).
+    pub far_interval: u32,
+}
+
+impl SimLod {
+    /// `None` (every brick ticks every tick) for intervals of 0 or 1.
+    pub fn new(near_radius: f32, far_interval: u32) -> Option<Self> {
+        let near_radius = if near_radius.is_finite() { near_radius.max(0.0) } else { 0.0 };
+        (far_interval > 1).then(|| SimLod { near_radius, far_interval: far_interval.min(MAX_LOD_INTERVAL) })
+    }
+
+    /// Tick interval of the 8³ brick at brick coordinates `brick`.
+    pub fn brick_interval(&self, brick: (u32, u32, u32), focus: [f32; 3]) -> u32 {
+        let center = [brick.0, brick.1, brick.2].map(|b| b as f32 * 8.0 + 4.0);
+        let dist_sq: f32 = center.iter().zip(focus).map(|(c, f)| (c - f) * (c - f)).sum();
+        if dist_sq <= self.near_radius * self.near_radius { 1 } else { self.far_interval }
+    }
+}
+
+/// Whether a brick with tick `interval` runs tick `tick_count`, as
+/// `brick_skips_tick` in brick_common.wgsl. All bricks run on multiples
+/// of the interval, so far bricks stay in step with each other.
+pub fn brick_ticks(interval: u32, tick_count: u32) -> bool {
+    interval <= 1 || tick_count.is_multiple_of(interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn far_bricks_tick_every_nth_tick() {
+        assert_eq!(SimLod::new(32.0, 1), None);
+        let lod = SimLod::new(20.0, 500).unwrap();
+        assert_eq!(lod.far_interval, MAX_LOD_INTERVAL);
+
+        let lod = SimLod::new(20.0, 4).unwrap();
+        let focus = [4.0, 4.0, 4.0];
+        assert_eq!(lod.brick_interval((0, 0, 0), focus), 1);
+        assert_eq!(lod.brick_interval((2, 0, 0), focus), 1);
+        assert_eq!(lod.brick_interval((3, 0, 0), focus), 4);
+        assert_eq!(lod.brick_interval((2, 2, 0), focus), 4);
+
+        let ran: Vec<u32> = (0..9).filter(|&t| brick_ticks(4, t)).collect();
+        assert_eq!(ran, vec![0, 4, 8]);
+        assert!((0..9).all(|t| brick_ticks(1, t) && brick_ticks(0, t)));
+    }
+}
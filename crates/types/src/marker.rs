@@ -0,0 +1,154 @@
+//! Named in-world markers for annotating experiment regions, with a plain
+//! text export format so they can be saved alongside a session.
+//!
+//! Export format: a `primordium-markers 1` header line, then one line per
+//! marker: `x<TAB>y<TAB>z<TAB>#rrggbb<TAB>label`.
+
+/// Markers the renderer draws at most.
+pub const MAX_MARKERS: usize = 256;
+
+/// Longest label kept, in characters.
+pub const MAX_MARKER_LABEL: usize = 64;
+
+const MARKER_HEADER: &str = "primordium-markers 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    pub id: u32,
+    pub pos: (u32, u32, u32),
+    pub label: String,
+    pub color: [u8; 3],
+}
+
+impl Marker {
+    pub fn color_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.color[0], self.color[1], self.color[2])
+    }
+}
+
+/// Parse `#rrggbb` (the `#` is optional).
+pub fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([byte(0)?, byte(2)?, byte(4)?])
+}
+
+/// Labels are single-line and bounded so they survive the export format.
+fn clean_label(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(MAX_MARKER_LABEL)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarkerSet {
+    markers: Vec<Marker>,
+    next_id: u32,
+}
+
+impl MarkerSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    /// Add a marker and return its id, or `None` once `MAX_MARKERS` exist.
+    pub fn add(&mut self, pos: (u32, u32, u32), label: &str, color: [u8; 3]) -> Option<u32> {
+        if self.markers.len() >= MAX_MARKERS {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.markers.push(Marker { id, pos, label: clean_label(label), color });
+        Some(id)
+    }
+
+    pub fn remove(&mut self, id: u32) -> bool {
+        let before = self.markers.len();
+        self.markers.retain(|m| m.id != id);
+        self.markers.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.markers.clear();
+    }
+
+    pub fn export(&self) -> String {
+        let mut out = String::from(MARKER_HEADER);
+        for m in &self.markers {
+            out.push_str(&format!(
+                "\n{}\t{}\t{}\t{}\t{}",
+                m.pos.0,
+                m.pos.1,
+                m.pos.2,
+                m.color_hex(),
+                m.label
+            ));
+        }
+        out
+    }
+
+    /// Parse an `export` string. Markers are renumbered from 0; positions
+    /// are not checked against any grid.
+    pub fn import(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some(MARKER_HEADER) {
+            return Err(format!("missing '{MARKER_HEADER}' header"));
+        }
+        let mut set = MarkerSet::new();
+        for (n, line) in lines.enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let fields: Vec<&str> = line.splitn(5, '\t').collect();
+            let [x, y, z, color, label] = fields[..] else {
+                return Err(format!("marker line {}: expected 5 tab-separated fields", n + 1));
+            };
+            let coord = |s: &str| s.trim().parse::<u32>().map_err(|_| format!("marker line {}: bad coordinate '{s}'", n + 1));
+            let pos = (coord(x)?, coord(y)?, coord(z)?);
+            let color = parse_hex_color(color.trim()).ok_or_else(|| format!("marker line {}: bad color '{color}'", n + 1))?;
+            set.add(pos, label, color)
+                .ok_or_else(|| format!("more than {MAX_MARKERS} markers"))?;
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_parse_and_format() {
+        assert_eq!(parse_hex_color("#ff8000"), Some([255, 128, 0]));
+        assert_eq!(parse_hex_color("00ff10"), Some([0, 255, 16]));
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#gg0000"), None);
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let mut set = MarkerSet::new();
+        let a = set.add((1, 2, 3), "colony\tA\n", [255, 0, 0]).unwrap();
+        assert_eq!(set.markers()[0].label, "colony A");
+        set.add((40, 5, 6), "cold wall", [0, 128, 255]);
+        assert!(set.remove(a));
+        assert!(!set.remove(a));
+        set.add((7, 8, 9), "front", [1, 2, 3]);
+
+        let text = set.export();
+        let back = MarkerSet::import(&text).unwrap();
+        let labels: Vec<_> = back.markers().iter().map(|m| (m.pos, m.label.as_str(), m.color)).collect();
+        assert_eq!(labels, vec![((40, 5, 6), "cold wall", [0, 128, 255]), ((7, 8, 9), "front", [1, 2, 3])]);
+
+        assert!(MarkerSet::import("1\t2\t3\t#000000\tx").is_err());
+        assert!(MarkerSet::import("primordium-markers 1\n1\t2\t#000000\tx").is_err());
+    }
+}
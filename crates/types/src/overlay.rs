@@ -0,0 +1,346 @@
+//! Overlay color scales as data: the value → color mapping of each overlay
+//! for legends, and the live temperature range the temperature overlay is
+//! stretched over. Colors match update_render_texture.wgsl.
+
+use crate::vox::hsv_to_rgb;
+use crate::{pcg_hash, Palette, CVD_BLUE, CVD_ORANGE, CVD_YELLOW};
+
+/// Narrowest temperature span the overlay stretches over; a flatter field
+/// sits mid-scale instead of amplifying rounding noise.
+pub const MIN_TEMP_SPAN: f32 = 0.02;
+/// Stops in a continuous legend.
+pub const LEGEND_STOPS: usize = 5;
+/// Words in the temperature range buffer (temperature_range.wgsl).
+pub const TEMP_RANGE_WORDS: usize = 2;
+
+/// Order-preserving u32 key for an f32, so atomicMax over keys finds the
+/// largest float. Matches `float_key` in common.wgsl.
+pub fn float_key(v: f32) -> u32 {
+    let bits = v.to_bits();
+    if bits >> 31 == 1 {
+        !bits
+    } else {
+        bits | 0x8000_0000
+    }
+}
+
+pub fn float_from_key(key: u32) -> f32 {
+    if key >> 31 == 1 {
+        f32::from_bits(key & 0x7FFF_FFFF)
+    } else {
+        f32::from_bits(!key)
+    }
+}
+
+/// Lowest and highest temperature of a field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl TempRange {
+    /// The fixed 0–1 mapping.
+    pub const UNIT: TempRange = TempRange { min: 0.0, max: 1.0 };
+
+    /// Parse the range buffer: [0] max of `!float_key(t)`, [1] max of
+    /// `float_key(t)`, both zeroed before the pass. `None` if no cell was
+    /// measured.
+    pub fn from_words(words: &[u32]) -> Option<Self> {
+        let (inv_min, max) = (*words.first()?, *words.get(1)?);
+        (max != 0).then(|| Self { min: float_from_key(!inv_min), max: float_from_key(max) })
+    }
+
+    /// Range of `temps`, skipping NaN; `None` if there are none.
+    pub fn of(temps: impl IntoIterator<Item = f32>) -> Option<Self> {
+        temps.into_iter().filter(|t| !t.is_nan()).fold(None, |range, t| {
+            Some(match range {
+                None => Self { min: t, max: t },
+                Some(r) => Self { min: r.min.min(t), max: r.max.max(t) },
+            })
+        })
+    }
+
+    /// Low end and width of the span mapped onto the color ramp: the range,
+    /// widened about its middle to at least `MIN_TEMP_SPAN`. Matches
+    /// `temp_overlay_scale` in update_render_texture.wgsl.
+    pub fn scale(&self) -> (f32, f32) {
+        let span = self.max - self.min;
+        if span >= MIN_TEMP_SPAN {
+            (self.min, span)
+        } else {
+            ((self.min + self.max) * 0.5 - MIN_TEMP_SPAN * 0.5, MIN_TEMP_SPAN)
+        }
+    }
+
+    /// Position of `temp` on the color ramp (0..=1).
+    pub fn normalize(&self, temp: f32) -> f32 {
+        let (lo, span) = self.scale();
+        ((temp - lo) / span).clamp(0.0, 1.0)
+    }
+}
+
+/// Temperature overlay color at ramp position `t`: blue (cold) to red, or
+/// blue to orange in the color-vision-safe palettes.
+pub fn temperature_color(t: f32, palette: Palette) -> [f32; 4] {
+    let alpha = t.max(1.0 - t);
+    if palette != Palette::Default {
+        let rgb: [f32; 3] = std::array::from_fn(|i| CVD_BLUE[i] + (CVD_ORANGE[i] - CVD_BLUE[i]) * t);
+        return [rgb[0], rgb[1], rgb[2], alpha];
+    }
+    [t, 0.2 * (1.0 - (t * 2.0 - 1.0).abs()), 1.0 - t, alpha]
+}
+
+/// Energy overlay color at `e` = energy / max_energy (0..=1), for
+/// non-empty cells.
+pub fn energy_color(e: f32) -> [f32; 4] {
+    [0.0, e, e * 0.3, e.max(0.2)]
+}
+
+/// Trait overlay ramp stops, low to high byte value (viridis-like, so it
+/// reads under color-vision deficiency too).
+pub const TRAIT_RAMP: [[f32; 3]; 3] = [[0.27, 0.0, 0.33], [0.13, 0.57, 0.55], [0.99, 0.91, 0.14]];
+
+/// Trait overlay color of a protocell whose selected genome byte is
+/// `t` × 255.
+pub fn trait_color(t: f32) -> [f32; 4] {
+    let t = t.clamp(0.0, 1.0) * 2.0;
+    let (a, b, f) = if t < 1.0 { (TRAIT_RAMP[0], TRAIT_RAMP[1], t) } else { (TRAIT_RAMP[1], TRAIT_RAMP[2], t - 1.0) };
+    [a[0] + (b[0] - a[0]) * f, a[1] + (b[1] - a[1]) * f, a[2] + (b[2] - a[2]) * f, 1.0]
+}
+
+/// Overlay modes `set_overlay_mode` cycles through, the plain view included.
+pub const OVERLAY_MODES: u32 = 6;
+
+/// Species overlay hue of `species_id`: hashed, so sibling ids (a species
+/// and its mutants) land far apart on the wheel. Matches `species_hue` in
+/// update_render_texture.wgsl.
+pub fn species_hue(species_id: u16) -> f32 {
+    (pcg_hash(species_id as u32) >> 8) as f32 / 16_777_216.0
+}
+
+/// Species overlay color of `species_id`, or its place on the blue–yellow
+/// axis in the color-vision-safe palettes.
+pub fn species_color(species_id: u16, palette: Palette) -> [f32; 4] {
+    let hue = species_hue(species_id);
+    let rgb = if palette != Palette::Default {
+        std::array::from_fn(|i| CVD_BLUE[i] + (CVD_YELLOW[i] - CVD_BLUE[i]) * hue)
+    } else {
+        hsv_to_rgb(hue, 0.8, 1.0)
+    };
+    [rgb[0], rgb[1], rgb[2], 1.0]
+}
+
+/// Population overlay colors: protocells, then all other matter.
+pub const POPULATION_COLORS: [[f32; 4]; 2] = [[1.0, 1.0, 0.0, 1.0], [0.15, 0.15, 0.15, 0.3]];
+
+/// Violet the time-dilation zone is tinted with in the plain view.
+pub const DILATION_TINT: [f32; 3] = [0.62, 0.42, 1.0];
+
+/// `color` of a cell in the time-dilation zone: matter blends toward
+/// `DILATION_TINT`, empty cells on the zone's faces show as a faint shell.
+/// Matches update_render_texture.wgsl.
+pub fn dilation_tinted(color: [f32; 4], on_face: bool) -> [f32; 4] {
+    if color[3] <= 0.0 {
+        let alpha = if on_face { 0.12 } else { 0.0 };
+        return [DILATION_TINT[0], DILATION_TINT[1], DILATION_TINT[2], alpha];
+    }
+    let rgb: [f32; 3] = std::array::from_fn(|i| color[i] + (DILATION_TINT[i] - color[i]) * 0.35);
+    [rgb[0], rgb[1], rgb[2], color[3]]
+}
+
+/// What the brick debug overlay colors each allocated brick's wireframe
+/// by (sparse mode only).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u32)]
+pub enum BrickOverlay {
+    #[default]
+    Off = 0,
+    /// Visible cells of the brick in the last volume update, of 512.
+    Occupancy = 1,
+    /// Ticks since the brick was allocated, relative to the oldest.
+    Age = 2,
+}
+
+impl BrickOverlay {
+    pub fn from_u32(v: u32) -> Option<Self> {
+        match v {
+            0 => Some(BrickOverlay::Off),
+            1 => Some(BrickOverlay::Occupancy),
+            2 => Some(BrickOverlay::Age),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BrickOverlay::Off => "off",
+            BrickOverlay::Occupancy => "occupancy",
+            BrickOverlay::Age => "age",
+        }
+    }
+}
+
+/// Brick overlay color at ramp position `t`: faint blue for empty or new
+/// bricks to opaque orange for full or old ones. Matches `brick_color` in
+/// wireframe.wgsl.
+pub fn brick_color(t: f32) -> [f32; 4] {
+    let t = t.clamp(0.0, 1.0);
+    [0.2 + 0.8 * t, 0.4 + 0.2 * t, 1.0 - 0.9 * t, 0.25 + 0.6 * t]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegendStop {
+    pub value: f32,
+    pub color: [f32; 4],
+}
+
+/// Color scale of one overlay mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayLegend {
+    pub mode: u32,
+    pub name: &'static str,
+    /// Value range of the data: the live temperature range, 0..max_energy
+    /// or 0..1 (population).
+    pub min: f32,
+    pub max: f32,
+    /// Value → color stops, ascending; colors interpolate between them
+    /// except for the categorical population overlay. Empty for the species
+    /// overlay, whose colors come per species from `species_color`.
+    pub stops: Vec<LegendStop>,
+}
+
+/// Legend for overlay `mode` (1 = temperature, 2 = energy, 3 =
+/// population, 4 = genome trait byte, 5 = species); `None` for the plain view. The temperature ramp spans
+/// `scale` (the live range when auto-scaling, else `TempRange::UNIT`) and
+/// reports `live` as its data range when known.
+pub fn overlay_legend(
+    mode: u32,
+    palette: Palette,
+    scale: TempRange,
+    live: Option<TempRange>,
+    max_energy: f32,
+) -> Option<OverlayLegend> {
+    let ramp = |lo: f32, span: f32, color: &dyn Fn(f32) -> [f32; 4]| -> Vec<LegendStop> {
+        (0..LEGEND_STOPS)
+            .map(|i| {
+                let t = i as f32 / (LEGEND_STOPS - 1) as f32;
+                LegendStop { value: lo + t * span, color: color(t) }
+            })
+            .collect()
+    };
+    match mode {
+        1 => {
+            let (lo, span) = scale.scale();
+            let data = live.unwrap_or(scale);
+            Some(OverlayLegend {
+                mode,
+                name: "temperature",
+                min: data.min,
+                max: data.max,
+                stops: ramp(lo, span, &|t| temperature_color(t, palette)),
+            })
+        }
+        2 => Some(OverlayLegend {
+            mode,
+            name: "energy",
+            min: 0.0,
+            max: max_energy,
+            stops: ramp(0.0, max_energy, &energy_color),
+        }),
+        3 => Some(OverlayLegend {
+            mode,
+            name: "population",
+            min: 0.0,
+            max: 1.0,
+            stops: vec![
+                LegendStop { value: 0.0, color: POPULATION_COLORS[1] },
+                LegendStop { value: 1.0, color: POPULATION_COLORS[0] },
+            ],
+        }),
+        4 => Some(OverlayLegend { mode, name: "trait", min: 0.0, max: 255.0, stops: ramp(0.0, 255.0, &trait_color) }),
+        5 => Some(OverlayLegend { mode, name: "species", min: 0.0, max: u16::MAX as f32, stops: Vec::new() }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_keys_preserve_order() {
+        let values = [-3.5f32, -0.0, 0.0, 1e-6, 0.25, 1.0, 40.0];
+        for pair in values.windows(2) {
+            assert!(float_key(pair[0]) <= float_key(pair[1]), "{pair:?}");
+        }
+        for v in values {
+            assert_eq!(float_from_key(float_key(v)), v);
+        }
+        let words = [!float_key(0.2), float_key(0.7)];
+        assert_eq!(TempRange::from_words(&words), Some(TempRange { min: 0.2, max: 0.7 }));
+        assert_eq!(TempRange::from_words(&[0, 0]), None);
+        assert_eq!(TempRange::of([0.5, f32::NAN, 0.1, 0.9]), Some(TempRange { min: 0.1, max: 0.9 }));
+        assert_eq!(TempRange::of([]), None);
+    }
+
+    #[test]
+    fn auto_scale_stretches_the_live_range() {
+        let range = TempRange { min: 0.4, max: 0.6 };
+        assert_eq!(range.normalize(0.4), 0.0);
+        assert!((range.normalize(0.5) - 0.5).abs() < 1e-6);
+        assert_eq!(range.normalize(0.9), 1.0);
+        // A flat field sits mid-scale
+        let flat = TempRange { min: 0.5, max: 0.5 };
+        assert!((flat.scale().1 - MIN_TEMP_SPAN).abs() < 1e-6);
+        assert!((flat.normalize(0.5) - 0.5).abs() < 1e-6);
+        assert_eq!(TempRange::UNIT.normalize(0.25), 0.25);
+    }
+
+    #[test]
+    fn legends_follow_the_overlay() {
+        let live = TempRange { min: 0.3, max: 0.7 };
+        let temp = overlay_legend(1, Palette::Default, live, Some(live), 1000.0).unwrap();
+        assert_eq!((temp.name, temp.min, temp.max), ("temperature", 0.3, 0.7));
+        assert_eq!(temp.stops.len(), LEGEND_STOPS);
+        assert_eq!(temp.stops[0].value, 0.3);
+        assert!((temp.stops[LEGEND_STOPS - 1].value - 0.7).abs() < 1e-6);
+        assert_eq!(temp.stops[0].color, [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(temp.stops[LEGEND_STOPS - 1].color, [1.0, 0.0, 0.0, 1.0]);
+        let fixed = overlay_legend(1, Palette::Deuteranopia, TempRange::UNIT, Some(live), 1000.0).unwrap();
+        assert_eq!((fixed.stops[0].value, fixed.stops[LEGEND_STOPS - 1].value), (0.0, 1.0));
+        assert_eq!(fixed.stops[0].color, [CVD_BLUE[0], CVD_BLUE[1], CVD_BLUE[2], 1.0]);
+        let energy = overlay_legend(2, Palette::Default, TempRange::UNIT, None, 1000.0).unwrap();
+        assert_eq!(energy.stops[LEGEND_STOPS - 1].value, 1000.0);
+        assert_eq!(overlay_legend(3, Palette::Default, TempRange::UNIT, None, 1000.0).unwrap().stops.len(), 2);
+        let genes = overlay_legend(4, Palette::Default, TempRange::UNIT, None, 1000.0).unwrap();
+        assert_eq!((genes.name, genes.max), ("trait", 255.0));
+        assert_eq!(genes.stops[LEGEND_STOPS - 1].color, trait_color(1.0));
+        assert_eq!(trait_color(0.5), [0.13, 0.57, 0.55, 1.0]);
+        assert!(overlay_legend(5, Palette::Default, TempRange::UNIT, None, 1000.0).unwrap().stops.is_empty());
+        assert_eq!(overlay_legend(0, Palette::Default, TempRange::UNIT, None, 1000.0), None);
+    }
+
+    #[test]
+    fn species_colors_are_stable_and_spread() {
+        assert_eq!(species_color(7, Palette::Default), species_color(7, Palette::Default));
+        assert!((species_hue(7) - species_hue(8)).abs() > 0.01);
+        for sid in [0u16, 1, 500, u16::MAX] {
+            assert!((0.0..1.0).contains(&species_hue(sid)));
+        }
+        let cvd = species_color(7, Palette::Protanopia);
+        let t = species_hue(7);
+        assert!((cvd[0] - (CVD_BLUE[0] + (CVD_YELLOW[0] - CVD_BLUE[0]) * t)).abs() < 1e-6);
+        assert_eq!(cvd[3], 1.0);
+    }
+
+    #[test]
+    fn brick_overlay_modes_and_ramp() {
+        for mode in [BrickOverlay::Off, BrickOverlay::Occupancy, BrickOverlay::Age] {
+            assert_eq!(BrickOverlay::from_u32(mode as u32), Some(mode));
+        }
+        assert_eq!(BrickOverlay::from_u32(3), None);
+        assert_eq!(brick_color(0.0), [0.2, 0.4, 1.0, 0.25]);
+        assert_eq!(brick_color(2.0), brick_color(1.0));
+    }
+}
@@ -1,9 +1,16 @@
+use crate::layout;
+
+/// Most temperature diffusion sub-steps per tick.
+pub const MAX_DIFFUSION_SUBSTEPS: u32 = 8;
+
+/// Largest dilation_factor `set_time_dilation` accepts.
+pub const MAX_DILATION_FACTOR: u32 = 64;
+
 /// Simulation parameters. All f32 for uniform buffer compatibility.
 /// Serialized to bytes and uploaded as a GPU uniform buffer.
 #[derive(Debug, Clone)]
 pub struct SimParams {
     pub grid_size: f32,
-    pub tick_count: f32,
     pub dt: f32,
     pub nutrient_spawn_rate: f32,
     pub waste_decay_ticks: f32,
@@ -18,17 +25,86 @@ pub struct SimParams {
     pub temp_sensitivity: f32,
     pub predation_energy_fraction: f32,
     pub max_energy: f32,
-    pub overlay_mode: f32,   // 0.0=normal, 1.0=temperature
     pub sparse_mode: f32,    // 0.0=dense, 1.0=sparse brick mode
     pub brick_grid_dim: f32, // 32.0 for 256³ with 8³ bricks
     pub max_bricks: f32,     // pool capacity as f32
+    /// Protocell neighbors (of 6) tolerated before crowding applies.
+    pub crowding_threshold: f32,
+    /// Extra energy per tick for each protocell neighbor above the threshold.
+    pub crowding_cost: f32,
+    /// > 0.0: protocells above the threshold don't replicate.
+    pub crowding_blocks_replication: f32,
+    /// Spatial spawn pattern: 0 = uniform, 1 = patchy, 2 = moving front.
+    pub nutrient_pattern: f32,
+    /// Nutrient neighbors (of 6) at which logistic regrowth stops.
+    pub nutrient_capacity: f32,
+    /// Spawn multiplier per adjacent waste voxel (local recycling).
+    pub nutrient_waste_boost: f32,
+    /// Front pattern speed in voxels per tick along +x.
+    pub nutrient_front_speed: f32,
+    /// Scent intensity (0..1) a protocell with gene byte 11 = 255 adds to
+    /// its pheromone cell per tick.
+    pub pheromone_deposit: f32,
+    /// Fraction of pheromone intensity lost per tick.
+    pub pheromone_decay: f32,
+    /// Fraction of a neighboring cell's intensity that spreads into a cell.
+    pub pheromone_spread: f32,
+    /// Scales how strongly gene byte 11 biases movement along the field.
+    pub pheromone_response: f32,
+    /// Temperature below which a protocell with cold tolerance 0 goes
+    /// dormant. Gene byte 13 lowers it toward 0.
+    pub dormancy_temp: f32,
+    /// Metabolic cost multiplier while dormant.
+    pub dormancy_cost_scale: f32,
+    /// Temperature above which protocells take heat-shock damage.
+    pub heat_shock_temp: f32,
+    /// Energy lost per tick above `heat_shock_temp`.
+    pub heat_shock_damage: f32,
+    /// Extra energy capacity, as a fraction of max_energy, at storage gene
+    /// (byte 14) = 255. The stored surplus goes to offspring on replication.
+    pub storage_capacity_bonus: f32,
+    /// Extra base metabolic cost fraction at storage gene = 255.
+    pub storage_metabolic_cost: f32,
+    /// Scales how often gene byte 15's thermotaxis strength steers movement.
+    pub thermotaxis_response: f32,
+    /// Toxin (0..255 scale) a protocell with full secretion gene adds per tick.
+    pub toxin_secretion_rate: f32,
+    /// Energy per tick a protocell with full secretion gene pays to secrete.
+    pub toxin_secretion_cost: f32,
+    /// Energy per tick lost at toxin 255 above the cell's resistance.
+    pub toxin_damage: f32,
+    /// Fraction of field toxin lost per tick.
+    pub toxin_decay: f32,
+    /// Goal zone (inclusive voxel box) for fitness challenges; disabled
+    /// while goal_max_x < goal_min_x.
+    pub goal_min_x: f32,
+    pub goal_min_y: f32,
+    pub goal_min_z: f32,
+    pub goal_max_x: f32,
+    pub goal_max_y: f32,
+    pub goal_max_z: f32,
+    /// Temperature at the grid faces: 0 = insulating (no heat crosses),
+    /// 1 = fixed at base_ambient_temp, 2 = periodic (opposite faces touch).
+    pub thermal_boundary: f32,
+    /// Temperature diffusion runs this many sub-steps per tick, each at
+    /// diffusion_rate / K, so high rates stay stable (1..=MAX_DIFFUSION_SUBSTEPS).
+    pub diffusion_substeps: f32,
+    /// Time-dilation zone (inclusive voxel box), disabled while
+    /// dilation_max_x < dilation_min_x. Cells in it run one tick in every
+    /// dilation_factor, so they live, move and metabolize that much slower.
+    pub dilation_min_x: f32,
+    pub dilation_min_y: f32,
+    pub dilation_min_z: f32,
+    pub dilation_max_x: f32,
+    pub dilation_max_y: f32,
+    pub dilation_max_z: f32,
+    pub dilation_factor: f32,
 }
 
 impl Default for SimParams {
     fn default() -> Self {
         Self {
             grid_size: 128.0,
-            tick_count: 0.0,
             dt: 0.016,
             nutrient_spawn_rate: 0.001,
             waste_decay_ticks: 100.0,
@@ -43,45 +119,245 @@ impl Default for SimParams {
             temp_sensitivity: 1.0,
             predation_energy_fraction: 0.5,
             max_energy: 1000.0,
-            overlay_mode: 0.0,
             sparse_mode: 0.0,
             brick_grid_dim: 0.0,
             max_bricks: 0.0,
+            crowding_threshold: 4.0,
+            crowding_cost: 2.0,
+            crowding_blocks_replication: 1.0,
+            nutrient_pattern: 0.0,
+            nutrient_capacity: 3.0,
+            nutrient_waste_boost: 1.0,
+            nutrient_front_speed: 0.5,
+            pheromone_deposit: 0.05,
+            pheromone_decay: 0.02,
+            pheromone_spread: 0.7,
+            pheromone_response: 1.0,
+            dormancy_temp: 0.2,
+            dormancy_cost_scale: 0.1,
+            heat_shock_temp: 0.85,
+            heat_shock_damage: 20.0,
+            storage_capacity_bonus: 1.0,
+            storage_metabolic_cost: 0.5,
+            thermotaxis_response: 1.0,
+            toxin_secretion_rate: 4.0,
+            toxin_secretion_cost: 2.0,
+            toxin_damage: 20.0,
+            toxin_decay: 0.1,
+            goal_min_x: 0.0,
+            goal_min_y: 0.0,
+            goal_min_z: 0.0,
+            goal_max_x: -1.0,
+            goal_max_y: -1.0,
+            goal_max_z: -1.0,
+            thermal_boundary: 0.0,
+            diffusion_substeps: 1.0,
+            dilation_min_x: 0.0,
+            dilation_min_y: 0.0,
+            dilation_min_z: 0.0,
+            dilation_max_x: -1.0,
+            dilation_max_y: -1.0,
+            dilation_max_z: -1.0,
+            dilation_factor: 1.0,
         }
     }
 }
 
+/// Invoke `$m!($args; field, ...)` with every SimParams field, in uniform
+/// order.
+macro_rules! sim_params_fields {
+    ($m:ident!($($args:tt)*)) => {
+        $m!($($args)*;
+            grid_size,
+            dt,
+            nutrient_spawn_rate,
+            waste_decay_ticks,
+            nutrient_recycle_rate,
+            movement_energy_cost,
+            base_ambient_temp,
+            metabolic_cost_base,
+            replication_energy_min,
+            energy_from_nutrient,
+            energy_from_source,
+            diffusion_rate,
+            temp_sensitivity,
+            predation_energy_fraction,
+            max_energy,
+            sparse_mode,
+            brick_grid_dim,
+            max_bricks,
+            crowding_threshold,
+            crowding_cost,
+            crowding_blocks_replication,
+            nutrient_pattern,
+            nutrient_capacity,
+            nutrient_waste_boost,
+            nutrient_front_speed,
+            pheromone_deposit,
+            pheromone_decay,
+            pheromone_spread,
+            pheromone_response,
+            dormancy_temp,
+            dormancy_cost_scale,
+            heat_shock_temp,
+            heat_shock_damage,
+            storage_capacity_bonus,
+            storage_metabolic_cost,
+            thermotaxis_response,
+            toxin_secretion_rate,
+            toxin_secretion_cost,
+            toxin_damage,
+            toxin_decay,
+            goal_min_x,
+            goal_min_y,
+            goal_min_z,
+            goal_max_x,
+            goal_max_y,
+            goal_max_z,
+            thermal_boundary,
+            diffusion_substeps,
+            dilation_min_x,
+            dilation_min_y,
+            dilation_min_z,
+            dilation_max_x,
+            dilation_max_y,
+            dilation_max_z,
+            dilation_factor
+        )
+    };
+}
+
 impl SimParams {
-    /// Serialize all fields to bytes, padded to 16-byte alignment.
+    /// Diffusion sub-steps per tick, clamped as temperature_diffusion.wgsl does.
+    pub fn substep_count(&self) -> u32 {
+        (self.diffusion_substeps.max(0.0) as u32).clamp(1, MAX_DIFFUSION_SUBSTEPS)
+    }
+
+    /// Whether `pos` lies in the time-dilation zone.
+    pub fn in_dilation_zone(&self, pos: (u32, u32, u32)) -> bool {
+        let (x, y, z) = (pos.0 as f32, pos.1 as f32, pos.2 as f32);
+        x >= self.dilation_min_x
+            && y >= self.dilation_min_y
+            && z >= self.dilation_min_z
+            && x <= self.dilation_max_x
+            && y <= self.dilation_max_y
+            && z <= self.dilation_max_z
+    }
+
+    /// Whether the cell at `pos` sits out tick `tick_count`: inside the
+    /// dilation zone only multiples of dilation_factor run. Matches
+    /// `dilation_skips_tick` in brick_common.wgsl.
+    pub fn dilation_skips_tick(&self, pos: (u32, u32, u32), tick_count: u32) -> bool {
+        let interval = self.dilation_factor.max(1.0) as u32;
+        interval > 1 && !tick_count.is_multiple_of(interval) && self.in_dilation_zone(pos)
+    }
+
+    /// (name, value) of every field; `layout::SIM_PARAMS_SECTIONS` places
+    /// each in the uniform.
+    pub fn named_fields(&self) -> [(&'static str, f32); 55] {
+        macro_rules! fields {
+            ($params:expr; $($field:ident),*) => {
+                [$((stringify!($field), $params.$field)),*]
+            };
+        }
+        sim_params_fields!(fields!(self))
+    }
+
+    /// Set the field called `name`, as listed by `named_fields`. Returns
+    /// false for unknown names.
+    pub fn set_named(&mut self, name: &str, value: f32) -> bool {
+        macro_rules! set {
+            ($params:expr, $name:expr, $value:expr; $($field:ident),*) => {
+                match $name {
+                    $(stringify!($field) => $params.$field = $value,)*
+                    _ => return false,
+                }
+            };
+        }
+        sim_params_fields!(set!(self, name, value));
+        true
+    }
+
+    /// Serialize to the uniform layout: `layout::SIM_PARAMS_WORDS` words
+    /// (384 bytes), the version first, spare words zero.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let fields: [f32; 20] = [
-            self.grid_size,
-            self.tick_count,
-            self.dt,
-            self.nutrient_spawn_rate,
-            self.waste_decay_ticks,
-            self.nutrient_recycle_rate,
-            self.movement_energy_cost,
-            self.base_ambient_temp,
-            self.metabolic_cost_base,
-            self.replication_energy_min,
-            self.energy_from_nutrient,
-            self.energy_from_source,
-            self.diffusion_rate,
-            self.temp_sensitivity,
-            self.predation_energy_fraction,
-            self.max_energy,
-            self.overlay_mode,
-            self.sparse_mode,
-            self.brick_grid_dim,
-            self.max_bricks,
-        ];
-        let mut bytes = Vec::with_capacity(fields.len() * 4);
-        for f in &fields {
-            bytes.extend_from_slice(&f.to_le_bytes());
+        let mut words = vec![0.0f32; layout::SIM_PARAMS_WORDS as usize];
+        words[0] = layout::SIM_PARAMS_VERSION as f32;
+        for (name, value) in self.named_fields() {
+            words[layout::sim_params_word(name).expect("field in layout::SIM_PARAMS_SECTIONS")] = value;
         }
-        // 80 bytes = 20 fields * 4 bytes, which is 16-byte aligned
-        bytes
+        words.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+}
+
+/// The per-tick uniform, uploaded beside SimParams so that a tick rewrites
+/// 16 bytes rather than the whole config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickParams {
+    pub tick_count: u32,
+    /// Render-texture overlay: 0 normal, 1 temperature, 2 energy, 3
+    /// population, 4 genome trait.
+    pub overlay_mode: u32,
+    /// Genome byte (0..16) the trait overlay colors protocells by.
+    pub overlay_trait: u32,
+}
+
+impl TickParams {
+    /// Uniform layout (`layout::TICK_PARAMS_FIELDS`).
+    pub fn to_words(&self) -> [u32; 4] {
+        [self.tick_count, self.overlay_mode, self.overlay_trait, 0]
+    }
+}
+
+/// Species one `RuleParams` can protect.
+pub const MAX_PROTECTED_SPECIES: usize = layout::MAX_PROTECTED_SPECIES as usize;
+
+/// Resolve-pass rules uploaded beside SimParams.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleParams {
+    pub mutator: crate::MutatorBounds,
+    pub arbitration: crate::ArbitrationPolicy,
+    /// Species whose members neither starve nor fall to predators; 0 is
+    /// an unused entry.
+    pub protected_species: [u16; MAX_PROTECTED_SPECIES],
+}
+
+impl RuleParams {
+    /// Uniform layout (`RuleParams` in resolve_execute.wgsl): mutation-rate
+    /// min, max, arbitration policy, padding, then the protected species.
+    pub fn to_words(&self) -> [u32; layout::RULE_PARAMS_WORDS as usize] {
+        let mut words = [0u32; layout::RULE_PARAMS_WORDS as usize];
+        words[..3].copy_from_slice(&[self.mutator.min as u32, self.mutator.max as u32, self.arbitration as u32]);
+        for (word, &id) in words[4..].iter_mut().zip(&self.protected_species) {
+            *word = id as u32;
+        }
+        words
+    }
+
+    pub fn is_protected(&self, species_id: u16) -> bool {
+        species_id != 0 && self.protected_species.contains(&species_id)
+    }
+
+    /// Protect `species_id`, or lift its protection if it has it. Returns
+    /// whether it is protected now.
+    pub fn toggle_protected(&mut self, species_id: u16) -> Result<bool, String> {
+        if species_id == 0 {
+            return Err("species 0 is not a species".into());
+        }
+        if let Some(slot) = self.protected_species.iter_mut().find(|id| **id == species_id) {
+            *slot = 0;
+            return Ok(false);
+        }
+        let Some(slot) = self.protected_species.iter_mut().find(|id| **id == 0) else {
+            return Err(format!("at most {MAX_PROTECTED_SPECIES} species can be protected"));
+        };
+        *slot = species_id;
+        Ok(true)
+    }
+
+    /// Protected species ids, in list order.
+    pub fn protected(&self) -> impl Iterator<Item = u16> + '_ {
+        self.protected_species.iter().copied().filter(|&id| id != 0)
     }
 }
 
@@ -89,20 +365,140 @@ impl SimParams {
 mod tests {
     use super::*;
 
+    #[test]
+    fn protected_species_toggle() {
+        let mut rules = RuleParams::default();
+        assert_eq!(rules.toggle_protected(42), Ok(true));
+        assert!(rules.is_protected(42));
+        assert_eq!(rules.to_words()[4], 42);
+        assert_eq!(rules.toggle_protected(42), Ok(false));
+        assert!(!rules.is_protected(42) && !rules.is_protected(0));
+        assert!(rules.toggle_protected(0).is_err());
+        for id in 1..=MAX_PROTECTED_SPECIES as u16 {
+            assert_eq!(rules.toggle_protected(id), Ok(true));
+        }
+        assert!(rules.toggle_protected(100).is_err());
+        assert_eq!(rules.protected().count(), MAX_PROTECTED_SPECIES);
+        assert_eq!(rules.to_words().len() % 4, 0, "must be 16-byte aligned");
+    }
+
     #[test]
     fn to_bytes_length_aligned() {
         let p = SimParams::default();
         let bytes = p.to_bytes();
-        assert_eq!(bytes.len(), 80); // 20 fields * 4 bytes
+        assert_eq!(bytes.len(), 384); // 5 sections, 96 words
         assert_eq!(bytes.len() % 16, 0, "must be 16-byte aligned");
     }
 
+    fn word(bytes: &[u8], i: usize) -> f32 {
+        f32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap())
+    }
+
     #[test]
-    fn to_bytes_roundtrip_grid_size() {
-        let p = SimParams { grid_size: 64.0, ..Default::default() };
+    fn to_bytes_puts_each_field_at_its_layout_word() {
+        let p = SimParams {
+            grid_size: 64.0,
+            crowding_threshold: 3.0,
+            crowding_cost: 7.5,
+            nutrient_pattern: 2.0,
+            nutrient_front_speed: 1.25,
+            pheromone_deposit: 0.25,
+            pheromone_response: 2.0,
+            dormancy_temp: 0.3,
+            heat_shock_damage: 12.0,
+            storage_capacity_bonus: 0.75,
+            storage_metabolic_cost: 0.25,
+            thermotaxis_response: 1.5,
+            toxin_secretion_rate: 9.0,
+            toxin_decay: 0.5,
+            goal_min_x: 4.0,
+            goal_max_z: 9.0,
+            thermal_boundary: 2.0,
+            ..Default::default()
+        };
         let bytes = p.to_bytes();
-        let val = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-        assert_eq!(val, 64.0);
+        let table = [
+            ("params_version", layout::SIM_PARAMS_VERSION as f32),
+            ("grid_size", 64.0),
+            ("crowding_threshold", 3.0),
+            ("crowding_cost", 7.5),
+            ("nutrient_pattern", 2.0),
+            ("nutrient_front_speed", 1.25),
+            ("pheromone_deposit", 0.25),
+            ("pheromone_response", 2.0),
+            ("dormancy_temp", 0.3),
+            ("heat_shock_damage", 12.0),
+            ("storage_capacity_bonus", 0.75),
+            ("storage_metabolic_cost", 0.25),
+            ("thermotaxis_response", 1.5),
+            ("toxin_secretion_rate", 9.0),
+            ("toxin_decay", 0.5),
+            ("goal_min_x", 4.0),
+            ("goal_max_z", 9.0),
+            ("thermal_boundary", 2.0),
+            ("diffusion_substeps", 1.0),
+        ];
+        for (name, value) in table {
+            let i = layout::sim_params_word(name).unwrap_or_else(|| panic!("{name} not in layout"));
+            assert_eq!(word(&bytes, i), value, "{name} at word {i}");
+        }
+        let mut start = 0;
+        for section in layout::SIM_PARAMS_SECTIONS {
+            let end = start + section.words as usize;
+            let spare = start + section.fields.len()..end;
+            assert!(spare.clone().all(|i| word(&bytes, i) == 0.0), "{} spare words {spare:?}", section.name);
+            start = end;
+        }
+        assert_eq!(bytes.len(), layout::SIM_PARAMS_WORDS as usize * 4);
+        assert!(SimParams::default().goal_max_x < SimParams::default().goal_min_x, "goal zone off by default");
+    }
+
+    #[test]
+    fn dilation_zone_skips_all_but_every_nth_tick() {
+        let p = SimParams {
+            dilation_min_x: 2.0,
+            dilation_min_y: 2.0,
+            dilation_min_z: 2.0,
+            dilation_max_x: 5.0,
+            dilation_max_y: 5.0,
+            dilation_max_z: 5.0,
+            dilation_factor: 4.0,
+            ..Default::default()
+        };
+        assert_eq!(word(&p.to_bytes(), layout::sim_params_word("dilation_factor").unwrap()), 4.0);
+        let skipped: Vec<u32> = (0..8).filter(|&t| p.dilation_skips_tick((3, 4, 5), t)).collect();
+        assert_eq!(skipped, [1, 2, 3, 5, 6, 7]);
+        assert!(!p.dilation_skips_tick((6, 4, 5), 1), "outside the zone");
+        assert!(!SimParams::default().in_dilation_zone((0, 0, 0)), "zone off by default");
+        assert!(!SimParams { dilation_factor: 0.0, ..p }.dilation_skips_tick((3, 3, 3), 1));
+    }
+
+    #[test]
+    fn fields_follow_the_shared_layout() {
+        let mut names: Vec<&str> = SimParams::default().named_fields().map(|(name, _)| name).to_vec();
+        names.insert(0, "params_version");
+        let mut laid_out: Vec<&str> = layout::SIM_PARAMS_SECTIONS.iter().flat_map(|s| s.fields.iter().copied()).collect();
+        names.sort_unstable();
+        laid_out.sort_unstable();
+        assert_eq!(names, laid_out, "every field in exactly one section");
+        assert_eq!(layout::sim_params_word("grid_size"), Some(1));
+        assert_eq!(layout::sim_params_word("goal_min_x"), Some(80));
+        assert_eq!(layout::sim_params_word("missing"), None);
+    }
+
+    #[test]
+    fn tick_params_follow_the_shared_layout() {
+        let words = TickParams { tick_count: 41, overlay_mode: 4, overlay_trait: 7 }.to_words();
+        assert_eq!(words, [41, 4, 7, 0]);
+        assert_eq!(layout::TICK_PARAMS_FIELDS[..3], ["tick_count", "overlay_mode", "overlay_trait"]);
+    }
+
+    #[test]
+    fn substep_count_clamped() {
+        assert_eq!(SimParams::default().substep_count(), 1);
+        assert_eq!(SimParams { diffusion_substeps: 0.0, ..Default::default() }.substep_count(), 1);
+        assert_eq!(SimParams { diffusion_substeps: 4.0, ..Default::default() }.substep_count(), 4);
+        assert_eq!(SimParams { diffusion_substeps: 99.0, ..Default::default() }.substep_count(), MAX_DIFFUSION_SUBSTEPS);
     }
 
     #[test]
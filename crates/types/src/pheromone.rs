@@ -0,0 +1,88 @@
+/// Edge length, in voxels, of one pheromone cell. The scent field is a
+/// coarse dense grid of (grid_size / PHEROMONE_CELL)³ cells in both dense
+/// and sparse mode.
+pub const PHEROMONE_CELL: u32 = 4;
+
+/// Maximum packed intensity (u16).
+pub const PHEROMONE_MAX: u32 = 0xFFFF;
+
+/// Cells per axis for a grid of `grid_size`.
+#[inline]
+pub fn pheromone_dim(grid_size: u32) -> u32 {
+    grid_size.div_ceil(PHEROMONE_CELL)
+}
+
+/// Linear cell index for voxel (x, y, z).
+#[inline]
+pub fn pheromone_index(x: u32, y: u32, z: u32, grid_size: u32) -> usize {
+    let d = pheromone_dim(grid_size);
+    let (cx, cy, cz) = (x / PHEROMONE_CELL, y / PHEROMONE_CELL, z / PHEROMONE_CELL);
+    (cz * d * d + cy * d + cx) as usize
+}
+
+/// Scent bucket for a species: 1..=255. Bucket 0 means "no scent".
+#[inline]
+pub fn pheromone_bucket(species_id: u16) -> u32 {
+    1 + species_id as u32 % 255
+}
+
+/// Cell word: [0:7] bucket, [8:15] toxin, [16:31] intensity.
+/// `pheromone_pack` leaves the toxin byte zero; see `with_toxin`.
+#[inline]
+pub fn pheromone_pack(bucket: u32, intensity: u32) -> u32 {
+    (bucket & 0xFF) | (intensity.min(PHEROMONE_MAX) << 16)
+}
+
+/// Allelopathic toxin level (0..=255) carried in the same cell word.
+#[inline]
+pub fn toxin_level(word: u32) -> u32 {
+    (word >> 8) & 0xFF
+}
+
+/// `word` with its toxin byte replaced by `toxin` (clamped to 255).
+#[inline]
+pub fn with_toxin(word: u32, toxin: u32) -> u32 {
+    (word & !0xFF00) | (toxin.min(0xFF) << 8)
+}
+
+/// Inverse of `pheromone_pack`: (bucket, intensity).
+#[inline]
+pub fn pheromone_unpack(word: u32) -> (u32, u32) {
+    (word & 0xFF, word >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pheromone_bucket_never_zero() {
+        for sid in [0u16, 1, 254, 255, 256, 510, u16::MAX] {
+            let b = pheromone_bucket(sid);
+            assert!((1..=255).contains(&b), "species {sid} -> bucket {b}");
+        }
+    }
+
+    #[test]
+    fn pheromone_pack_roundtrip() {
+        let w = pheromone_pack(37, 12_345);
+        assert_eq!(pheromone_unpack(w), (37, 12_345));
+        assert_eq!(pheromone_unpack(pheromone_pack(1, u32::MAX)).1, PHEROMONE_MAX);
+    }
+
+    #[test]
+    fn toxin_byte_independent_of_scent() {
+        let w = with_toxin(pheromone_pack(9, 500), 300);
+        assert_eq!(toxin_level(w), 255);
+        assert_eq!(pheromone_unpack(w), (9, 500));
+    }
+
+    #[test]
+    fn pheromone_index_coarse() {
+        let gs = 128;
+        assert_eq!(pheromone_dim(gs), 32);
+        assert_eq!(pheromone_index(3, 3, 3, gs), 0);
+        assert_eq!(pheromone_index(4, 0, 0, gs), 1);
+        assert_eq!(pheromone_index(127, 127, 127, gs), 32 * 32 * 32 - 1);
+    }
+}
@@ -0,0 +1,531 @@
+//! Minimal PNG decoder for heightmaps: non-interlaced images of any color
+//! type and bit depth, reduced to 8-bit luminance. Includes the zlib
+//! inflater it needs; CRCs and the Adler checksum are not verified. Also
+//! an uncompressed RGBA encoder for exported images.
+
+/// Decoded images are limited to this many pixels.
+pub const MAX_IMAGE_PIXELS: u64 = 4096 * 4096;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// 8-bit luminance image, rows top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrayImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Nearest pixel to cell (u, v) of an `n`×`n` grid stretched over the
+    /// image.
+    pub fn sample(&self, u: u32, v: u32, n: u32) -> u8 {
+        let x = ((u as u64 * 2 + 1) * self.width as u64 / (n as u64 * 2)) as u32;
+        let y = ((v as u64 * 2 + 1) * self.height as u64 / (n as u64 * 2)) as u32;
+        self.get(x.min(self.width - 1), y.min(self.height - 1))
+    }
+}
+
+/// Decode a PNG to luminance. Color is weighted Rec. 601, alpha darkens
+/// toward black, 16-bit samples keep their high byte.
+pub fn decode_png_gray(bytes: &[u8]) -> Result<GrayImage, String> {
+    if bytes.get(..8) != Some(&SIGNATURE[..]) {
+        return Err("not a PNG file".into());
+    }
+    let mut pos = 8;
+    let mut header = None;
+    let mut palette: Vec<u8> = Vec::new();
+    let mut idat: Vec<u8> = Vec::new();
+    while pos + 8 <= bytes.len() {
+        let len = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let data = (pos + 8).checked_add(len).and_then(|end| bytes.get(pos + 8..end)).ok_or("truncated PNG chunk")?;
+        match kind {
+            b"IHDR" => header = Some(Header::parse(data)?),
+            b"PLTE" => palette = data.to_vec(),
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+    let header = header.ok_or("PNG has no IHDR chunk")?;
+    let stride = header.stride();
+    let raw = zlib_decompress(&idat, header.height as usize * (stride + 1))?;
+    if raw.len() < header.height as usize * (stride + 1) {
+        return Err("PNG image data is truncated".into());
+    }
+    let rows = unfilter(&raw, header.height as usize, stride, header.filter_bytes())?;
+    let mut pixels = Vec::with_capacity(header.width as usize * header.height as usize);
+    for row in rows.chunks_exact(stride) {
+        for x in 0..header.width as usize {
+            pixels.push(header.luma(row, x, &palette)?);
+        }
+    }
+    Ok(GrayImage { width: header.width, height: header.height, pixels })
+}
+
+/// Encode 8-bit RGBA pixels (rows top to bottom) as a PNG. The image data
+/// is stored, not compressed, so encoding is cheap and sizes predictable.
+pub fn encode_png_rgba(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+    let mut raw = Vec::with_capacity(height as usize * (stride + 1));
+    for row in rgba.chunks_exact(stride.max(1)).take(height as usize) {
+        raw.push(0); // filter: none
+        raw.extend_from_slice(row);
+    }
+    // zlib header, then stored deflate blocks of at most 65535 bytes
+    let mut z = vec![0x78, 0x01];
+    let blocks = raw.chunks(0xFFFF).collect::<Vec<_>>();
+    for (i, block) in blocks.iter().enumerate() {
+        z.push((i + 1 == blocks.len()) as u8);
+        z.extend((block.len() as u16).to_le_bytes());
+        z.extend((!(block.len() as u16)).to_le_bytes());
+        z.extend_from_slice(block);
+    }
+    if blocks.is_empty() {
+        z.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    z.extend(adler32(&raw).to_be_bytes());
+
+    let mut ihdr = width.to_be_bytes().to_vec();
+    ihdr.extend(height.to_be_bytes());
+    ihdr.extend([8, 6, 0, 0, 0]);
+    let mut out = SIGNATURE.to_vec();
+    for (kind, data) in [(b"IHDR", &ihdr[..]), (b"IDAT", &z[..]), (b"IEND", &[][..])] {
+        out.extend((data.len() as u32).to_be_bytes());
+        let start = out.len();
+        out.extend(kind);
+        out.extend_from_slice(data);
+        let crc = crc32(&out[start..]);
+        out.extend(crc.to_be_bytes());
+    }
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in bytes {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    depth: u8,
+    color: u8,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 13 {
+            return Err("PNG IHDR is truncated".into());
+        }
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        let (depth, color) = (data[8], data[9]);
+        if width == 0 || height == 0 || width as u64 * height as u64 > MAX_IMAGE_PIXELS {
+            return Err(format!("PNG size {width}x{height} is out of range"));
+        }
+        let valid = match color {
+            0 => matches!(depth, 1 | 2 | 4 | 8 | 16),
+            3 => matches!(depth, 1 | 2 | 4 | 8),
+            2 | 4 | 6 => matches!(depth, 8 | 16),
+            _ => false,
+        };
+        if !valid {
+            return Err(format!("unsupported PNG color type {color} at bit depth {depth}"));
+        }
+        if data[12] != 0 {
+            return Err("interlaced PNGs are not supported".into());
+        }
+        Ok(Header { width, height, depth, color })
+    }
+
+    fn channels(&self) -> usize {
+        match self.color {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.depth as usize
+    }
+
+    fn stride(&self) -> usize {
+        (self.width as usize * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// Byte distance to the corresponding byte of the previous pixel.
+    fn filter_bytes(&self) -> usize {
+        (self.bits_per_pixel() / 8).max(1)
+    }
+
+    /// Sample `c` of pixel `x`, scaled to 8 bits (palette indices unscaled).
+    fn sample(&self, row: &[u8], x: usize, c: usize) -> u8 {
+        let depth = self.depth as usize;
+        match depth {
+            8 => row[x * self.channels() + c],
+            16 => row[(x * self.channels() + c) * 2],
+            _ => {
+                let bit = x * depth;
+                let max = (1u16 << depth) - 1;
+                let v = (row[bit / 8] >> (8 - depth - bit % 8)) as u16 & max;
+                if self.color == 3 {
+                    v as u8
+                } else {
+                    (v * 255 / max) as u8
+                }
+            }
+        }
+    }
+
+    fn luma(&self, row: &[u8], x: usize, palette: &[u8]) -> Result<u8, String> {
+        let rgb = |r: u8, g: u8, b: u8| ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8;
+        let s = |c| self.sample(row, x, c);
+        Ok(match self.color {
+            0 => s(0),
+            2 => rgb(s(0), s(1), s(2)),
+            3 => {
+                let i = s(0) as usize * 3;
+                let entry = palette.get(i..i + 3).ok_or("PNG palette index out of range")?;
+                rgb(entry[0], entry[1], entry[2])
+            }
+            4 => (s(0) as u32 * s(1) as u32 / 255) as u8,
+            _ => (rgb(s(0), s(1), s(2)) as u32 * s(3) as u32 / 255) as u8,
+        })
+    }
+}
+
+/// Undo the per-scanline filters, returning `height` rows of `stride` bytes.
+fn unfilter(raw: &[u8], height: usize, stride: usize, bpp: usize) -> Result<Vec<u8>, String> {
+    let mut out = vec![0u8; height * stride];
+    for y in 0..height {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let (done, rest) = out.split_at_mut(y * stride);
+        let prior = if y > 0 { &done[(y - 1) * stride..] } else { &[][..] };
+        let cur = &mut rest[..stride];
+        for i in 0..stride {
+            let a = if i >= bpp { cur[i - bpp] } else { 0 };
+            let b = prior.get(i).copied().unwrap_or(0);
+            let c = if i >= bpp { prior.get(i - bpp).copied().unwrap_or(0) } else { 0 };
+            let predicted = match line[0] {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                f => return Err(format!("unknown PNG filter type {f}")),
+            };
+            cur[i] = line[i + 1].wrapping_add(predicted);
+        }
+    }
+    Ok(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Inflate a zlib stream, stopping once `limit` bytes are produced.
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 2 || data[0] & 0x0F != 8 || !u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31) {
+        return Err("bad zlib header".into());
+    }
+    if data[1] & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".into());
+    }
+    inflate(&data[2..], limit)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, n: u32) -> Result<u32, String> {
+        let mut v = 0;
+        for i in 0..n {
+            let byte = *self.data.get(self.pos).ok_or("deflate stream is truncated")?;
+            v |= ((byte >> self.bit) as u32 & 1) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(v)
+    }
+
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+/// Canonical Huffman code as symbol counts per length and symbols in code
+/// order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (sym, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbols[offsets[l as usize] as usize] = sym as u16;
+                offsets[l as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, br: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= br.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".into())
+    }
+}
+
+const LENGTH_BASE: [u16; 29] =
+    [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] =
+    [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Inflate a raw deflate stream, stopping once `limit` bytes are produced.
+fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut br = BitReader { data, pos: 0, bit: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = br.bits(1)? == 1;
+        match br.bits(2)? {
+            0 => {
+                br.align();
+                let header = data.get(br.pos..br.pos + 4).ok_or("deflate stream is truncated")?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                let stored = data.get(br.pos + 4..br.pos + 4 + len).ok_or("deflate stream is truncated")?;
+                out.extend_from_slice(stored);
+                br.pos += 4 + len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                inflate_block(&mut br, &mut out, &Huffman::new(&lengths), &Huffman::new(&[5; 30]), limit)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut br)?;
+                inflate_block(&mut br, &mut out, &lit, &dist, limit)?;
+            }
+            _ => return Err("invalid deflate block type".into()),
+        }
+        if last || out.len() >= limit {
+            out.truncate(limit);
+            return Ok(out);
+        }
+    }
+}
+
+fn dynamic_tables(br: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let nlit = br.bits(5)? as usize + 257;
+    let ndist = br.bits(5)? as usize + 1;
+    let ncode = br.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..ncode] {
+        code_lengths[i] = br.bits(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths);
+    let mut lengths = vec![0u8; nlit + ndist];
+    let mut i = 0;
+    while i < lengths.len() {
+        let sym = code.decode(br)?;
+        let (value, repeat) = match sym {
+            0..=15 => (sym as u8, 1),
+            16 => (*lengths.get(i.wrapping_sub(1)).ok_or("deflate repeat with no previous length")?, 3 + br.bits(2)?),
+            17 => (0, 3 + br.bits(3)?),
+            _ => (0, 11 + br.bits(7)?),
+        };
+        for _ in 0..repeat {
+            *lengths.get_mut(i).ok_or("deflate code lengths overflow")? = value;
+            i += 1;
+        }
+    }
+    Ok((Huffman::new(&lengths[..nlit]), Huffman::new(&lengths[nlit..])))
+}
+
+fn inflate_block(
+    br: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+    limit: usize,
+) -> Result<(), String> {
+    loop {
+        let sym = lit.decode(br)? as usize;
+        if sym < 256 {
+            out.push(sym as u8);
+        } else if sym == 256 {
+            return Ok(());
+        } else {
+            let i = sym - 257;
+            let len = *LENGTH_BASE.get(i).ok_or("invalid deflate length code")? as usize
+                + br.bits(LENGTH_EXTRA[i] as u32)? as usize;
+            let d = dist.decode(br)? as usize;
+            let back = *DIST_BASE.get(d).ok_or("invalid deflate distance code")? as usize
+                + br.bits(DIST_EXTRA[d] as u32)? as usize;
+            if back > out.len() {
+                return Err("deflate distance reaches before the output".into());
+            }
+            for _ in 0..len {
+                out.push(out[out.len() - back]);
+            }
+        }
+        if out.len() >= limit {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+        png.extend((data.len() as u32).to_be_bytes());
+        png.extend(kind);
+        png.extend(data);
+        png.extend([0; 4]);
+    }
+
+    /// PNG with the scanlines in one stored deflate block.
+    fn png(width: u32, height: u32, depth: u8, color: u8, scanlines: &[u8], palette: &[u8]) -> Vec<u8> {
+        let mut out = SIGNATURE.to_vec();
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend(height.to_be_bytes());
+        ihdr.extend([depth, color, 0, 0, 0]);
+        chunk(&mut out, b"IHDR", &ihdr);
+        if !palette.is_empty() {
+            chunk(&mut out, b"PLTE", palette);
+        }
+        let mut z = vec![0x78, 0x01, 1];
+        z.extend((scanlines.len() as u16).to_le_bytes());
+        z.extend((!(scanlines.len() as u16)).to_le_bytes());
+        z.extend(scanlines);
+        chunk(&mut out, b"IDAT", &z);
+        chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    #[test]
+    fn inflates_fixed_and_dynamic_blocks() {
+        let fixed = [120, 218, 203, 72, 205, 201, 201, 87, 200, 64, 39, 1, 104, 3, 8, 177];
+        assert_eq!(zlib_decompress(&fixed, 1000).unwrap(), b"hello hello hello hello");
+        let dynamic = [
+            120, 218, 13, 200, 193, 1, 0, 48, 8, 2, 177, 89, 169, 194, 177, 255, 4, 54, 207, 200, 114, 129, 78, 158, 166,
+            107, 12, 97, 35, 255, 57, 183, 116, 11, 212,
+        ];
+        assert_eq!(zlib_decompress(&dynamic, 1000).unwrap(), b"aeaehggghcfbachdegeggfgdfaecfb");
+        assert_eq!(zlib_decompress(&fixed, 5).unwrap(), b"hello");
+        assert!(zlib_decompress(&fixed[..8], 1000).is_err());
+        assert!(zlib_decompress(&[0, 0], 10).is_err());
+    }
+
+    #[test]
+    fn decodes_filtered_gray_rows() {
+        // 3x4 gray: None, Sub, Up, Paeth
+        let lines = [0, 10, 20, 30, 1, 10, 10, 10, 2, 5, 5, 5, 4, 1, 1, 1];
+        let img = decode_png_gray(&png(3, 4, 8, 0, &lines, &[])).unwrap();
+        assert_eq!(img.pixels, vec![10, 20, 30, 10, 20, 30, 15, 25, 35, 16, 26, 36]);
+        assert_eq!(img.sample(0, 0, 2), 10);
+        assert_eq!(img.sample(1, 1, 2), 36);
+        assert!(decode_png_gray(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn reduces_color_and_low_depths_to_luma() {
+        // RGBA: opaque white, half-transparent white
+        let img = decode_png_gray(&png(2, 1, 8, 6, &[0, 255, 255, 255, 255, 255, 255, 255, 128], &[])).unwrap();
+        assert_eq!(img.pixels, vec![255, 128]);
+        // 2-bit gray
+        let img = decode_png_gray(&png(4, 1, 2, 0, &[0, 0b00_01_10_11], &[])).unwrap();
+        assert_eq!(img.pixels, vec![0, 85, 170, 255]);
+        // 1-bit palette
+        let img = decode_png_gray(&png(2, 1, 1, 3, &[0, 0b0100_0000], &[0, 0, 0, 0, 255, 0])).unwrap();
+        assert_eq!(img.pixels, vec![0, 149]);
+        assert!(decode_png_gray(&png(1, 1, 4, 2, &[0, 0], &[])).is_err());
+    }
+
+    #[test]
+    fn encoded_rgba_decodes() {
+        let rgba = [10, 10, 10, 255, 200, 200, 200, 255, 0, 0, 0, 0, 90, 90, 90, 255];
+        let png = encode_png_rgba(2, 2, &rgba);
+        assert_eq!(decode_png_gray(&png).unwrap().pixels, vec![10, 200, 0, 90]);
+        // IEND chunk with its well-known CRC
+        assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        // Rows past one stored block still decode
+        let wide = [7, 7, 7, 255].repeat(200 * 100);
+        assert!(decode_png_gray(&encode_png_rgba(200, 100, &wide)).unwrap().pixels.iter().all(|&p| p == 7));
+    }
+}
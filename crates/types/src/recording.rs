@@ -0,0 +1,79 @@
+//! Tick-synchronized frame recording: frames are due by simulated time,
+//! not wall time, so playback at `fps` shows the run at simulation speed
+//! however fast or slow the browser rendered it.
+
+/// Recorded pixel data kept at most; recording stops adding frames past it.
+pub const MAX_RECORDING_BYTES: usize = 512 * 1024 * 1024;
+
+/// Clamped recording settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordingConfig {
+    /// Output frames per simulated second (1..=60).
+    pub fps: f32,
+    /// Capture size relative to the surface (0.1..=1.0).
+    pub scale: f32,
+}
+
+impl RecordingConfig {
+    pub fn new(fps: f32, scale: f32) -> Self {
+        let fps = if fps.is_finite() { fps.clamp(1.0, 60.0) } else { 30.0 };
+        let scale = if scale.is_finite() { scale.clamp(0.1, 1.0) } else { 1.0 };
+        Self { fps, scale }
+    }
+
+    /// Capture size for a surface of `width` × `height` (at least 1×1).
+    pub fn capture_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let dim = |d: u32| ((d as f32 * self.scale).round() as u32).max(1);
+        (dim(width), dim(height))
+    }
+}
+
+/// Decides how many output frames each capture stands for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordingClock {
+    frame_seconds: f64,
+    /// Simulated time of the next output frame.
+    next: f64,
+}
+
+impl RecordingClock {
+    /// First frame is due at `start` (the simulated time recording began).
+    pub fn new(fps: f32, start: f64) -> Self {
+        Self { frame_seconds: 1.0 / fps as f64, next: start }
+    }
+
+    /// Output frames whose time has been reached by `sim_time`; the caller
+    /// captures once and repeats that image this many times.
+    pub fn frames_due(&mut self, sim_time: f64) -> u32 {
+        if sim_time < self.next {
+            return 0;
+        }
+        let n = ((sim_time - self.next) / self.frame_seconds).floor() as u32 + 1;
+        self.next += n as f64 * self.frame_seconds;
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_clamps() {
+        assert_eq!(RecordingConfig::new(500.0, 0.0), RecordingConfig { fps: 60.0, scale: 0.1 });
+        assert_eq!(RecordingConfig::new(f32::NAN, 0.5).fps, 30.0);
+        assert_eq!(RecordingConfig::new(30.0, 0.5).capture_size(1001, 3), (501, 2));
+    }
+
+    #[test]
+    fn frames_follow_sim_time() {
+        let mut clock = RecordingClock::new(10.0, 1.0);
+        assert_eq!(clock.frames_due(0.95), 0);
+        assert_eq!(clock.frames_due(1.0), 1);
+        assert_eq!(clock.frames_due(1.05), 0);
+        // A long jump owes every frame it skipped
+        assert_eq!(clock.frames_due(1.35), 3);
+        assert_eq!(clock.frames_due(1.39), 0);
+        assert_eq!(clock.frames_due(1.41), 1);
+    }
+}
@@ -0,0 +1,80 @@
+//! Accessibility options for the volume renderer: color-vision-safe
+//! palettes and a reduced-flicker mode.
+
+/// Weight of the newest frame when reduced flicker blends the volume
+/// texture with its history (lower = smoother, slower to respond).
+pub const REDUCED_FLICKER_BLEND: f32 = 0.2;
+
+/// Color-vision-safe ramp ends (Okabe–Ito), as in update_render_texture.wgsl.
+pub const CVD_BLUE: [f32; 3] = [0.0, 0.45, 0.70];
+pub const CVD_YELLOW: [f32; 3] = [0.94, 0.80, 0.10];
+pub const CVD_ORANGE: [f32; 3] = [0.90, 0.62, 0.0];
+
+/// Material and species palette. Matches `palette` in
+/// update_render_texture.wgsl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u32)]
+pub enum Palette {
+    #[default]
+    Default = 0,
+    /// Avoids red/green contrasts; species spread along a blue–yellow axis.
+    Deuteranopia = 1,
+    /// As `Deuteranopia`, with reds lifted toward orange so they stay bright.
+    Protanopia = 2,
+}
+
+impl Palette {
+    pub fn from_u32(v: u32) -> Option<Self> {
+        match v {
+            0 => Some(Palette::Default),
+            1 => Some(Palette::Deuteranopia),
+            2 => Some(Palette::Protanopia),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Default => "default",
+            Palette::Deuteranopia => "deuteranopia",
+            Palette::Protanopia => "protanopia",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderStyle {
+    pub palette: Palette,
+    /// Blend each refresh of the volume texture with the previous one and
+    /// soften bright emissive highlights.
+    pub reduced_flicker: bool,
+    /// Map the temperature overlay over a fixed 0–1 instead of the live
+    /// temperature range.
+    pub fixed_temp_range: bool,
+}
+
+impl RenderStyle {
+    /// Render-style uniform (update_render_texture.wgsl `RenderStyle`):
+    /// palette, blend weight (1.0 = no smoothing), fixed temperature range
+    /// flag, one pad word.
+    pub fn to_words(&self) -> [u32; 4] {
+        let blend = if self.reduced_flicker { REDUCED_FLICKER_BLEND } else { 1.0 };
+        [self.palette as u32, blend.to_bits(), self.fixed_temp_range as u32, 0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_round_trip_and_uniform() {
+        for p in [Palette::Default, Palette::Deuteranopia, Palette::Protanopia] {
+            assert_eq!(Palette::from_u32(p as u32), Some(p));
+        }
+        assert_eq!(Palette::from_u32(3), None);
+        let style = RenderStyle { palette: Palette::Protanopia, reduced_flicker: true, fixed_temp_range: true };
+        assert_eq!(style.to_words(), [2, REDUCED_FLICKER_BLEND.to_bits(), 1, 0]);
+        assert_eq!(f32::from_bits(RenderStyle::default().to_words()[1]), 1.0);
+    }
+}
@@ -0,0 +1,411 @@
+//! Guided scenarios: a script of messages, live-stat conditions and edits,
+//! stepped by the host every frame.
+//!
+//! Scripts are plain text, one step per line after a `primordium-scenario 1`
+//! header (`#` starts a comment):
+//!
+//! ```text
+//! title First life
+//! say Place an energy source, then press Continue.
+//! wait continue
+//! place energy_source 50% 50% 50% 2
+//! seed 50% 45% 50% 3
+//! wait population >= 50
+//! advance 100
+//! ```
+//!
+//! Coordinates are cells or percentages of the grid size. `advance n` runs
+//! exactly `n` ticks (even while paused) and waits for them.
+
+use crate::{Command, CommandType, VoxelType};
+
+const SCENARIO_HEADER: &str = "primordium-scenario 1";
+
+/// Built-in onboarding scenario.
+pub const TUTORIAL_SCENARIO: &str = "primordium-scenario 1
+title First life
+say Welcome to Primordium. Right-drag to orbit, scroll to zoom. Press Continue when ready.
+wait continue
+say Protocells need energy. Pick the Energy tool and click in the volume to place a source, then press Continue.
+wait continue
+say Now choose the Seed tool and click near your energy source to seed protocells.
+wait population >= 1
+say Life! Protocells feed, then divide when they have energy to spare. Watch them replicate.
+wait population >= 200
+say Offspring inherit a mutated genome; new species appear as lineages drift apart.
+wait species >= 3
+say Toxin culls everything it touches. Try it on a crowded region, or press Continue to finish.
+wait continue
+say That's the loop: energy, growth, mutation, selection. Have fun.
+";
+
+/// Live values conditions are checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScenarioStats {
+    pub tick: u32,
+    pub population: u32,
+    pub species_count: u32,
+    pub total_energy: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Population,
+    Species,
+    Energy,
+}
+
+impl Metric {
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Population => "population",
+            Metric::Species => "species",
+            Metric::Energy => "energy",
+        }
+    }
+
+    fn value(self, stats: &ScenarioStats) -> u32 {
+        match self {
+            Metric::Population => stats.population,
+            Metric::Species => stats.species_count,
+            Metric::Energy => stats.total_energy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    /// Ticks elapsed since the step began.
+    Ticks(u32),
+    AtLeast(Metric, u32),
+    AtMost(Metric, u32),
+    /// The player pressed Continue.
+    Continue,
+}
+
+impl Condition {
+    /// Script form, e.g. `population >= 50`.
+    pub fn describe(&self) -> String {
+        match *self {
+            Condition::Ticks(n) => format!("ticks {n}"),
+            Condition::AtLeast(m, n) => format!("{} >= {n}", m.name()),
+            Condition::AtMost(m, n) => format!("{} <= {n}", m.name()),
+            Condition::Continue => "continue".to_string(),
+        }
+    }
+}
+
+/// A cell coordinate or a percentage of the grid size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coord {
+    Cell(u32),
+    Percent(u32),
+}
+
+impl Coord {
+    pub fn resolve(self, grid_size: u32) -> u32 {
+        let max = grid_size.saturating_sub(1);
+        match self {
+            Coord::Cell(c) => c.min(max),
+            Coord::Percent(p) => (grid_size as u64 * p.min(100) as u64 / 100).min(max as u64) as u32,
+        }
+    }
+}
+
+/// A command with grid-relative coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub kind: CommandType,
+    pub pos: [Coord; 3],
+    pub radius: u32,
+    pub param_0: u32,
+}
+
+impl CommandSpec {
+    pub fn resolve(&self, grid_size: u32) -> Command {
+        let [x, y, z] = self.pos.map(|c| c.resolve(grid_size));
+        Command::new(self.kind, x, y, z, self.radius, self.param_0, 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScenarioStep {
+    Say(String),
+    Wait(Condition),
+    Apply(CommandSpec),
+    Advance(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    pub title: String,
+    pub steps: Vec<ScenarioStep>,
+}
+
+fn placeable(name: &str) -> Option<VoxelType> {
+    match name {
+        "wall" => Some(VoxelType::Wall),
+        "nutrient" => Some(VoxelType::Nutrient),
+        "energy_source" => Some(VoxelType::EnergySource),
+        "heat_source" => Some(VoxelType::HeatSource),
+        "cold_source" => Some(VoxelType::ColdSource),
+        _ => None,
+    }
+}
+
+fn parse_coord(s: &str) -> Option<Coord> {
+    match s.strip_suffix('%') {
+        Some(p) => p.parse().ok().map(Coord::Percent),
+        None => s.parse().ok().map(Coord::Cell),
+    }
+}
+
+fn parse_condition(args: &[&str]) -> Option<Condition> {
+    match args {
+        ["continue"] => Some(Condition::Continue),
+        ["ticks", n] => n.parse().ok().map(Condition::Ticks),
+        [metric, op, n] => {
+            let metric = match *metric {
+                "population" => Metric::Population,
+                "species" => Metric::Species,
+                "energy" => Metric::Energy,
+                _ => return None,
+            };
+            let n = n.parse().ok()?;
+            match *op {
+                ">=" => Some(Condition::AtLeast(metric, n)),
+                "<=" => Some(Condition::AtMost(metric, n)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// `x y z radius [param]` after the verb; `param` defaults to `default`.
+fn parse_command(kind: CommandType, default: u32, args: &[&str]) -> Option<CommandSpec> {
+    let (pos, rest) = match args {
+        [x, y, z, r, rest @ ..] if rest.len() <= 1 => ([*x, *y, *z, *r], rest),
+        _ => return None,
+    };
+    let param_0 = match rest {
+        [p] => p.parse().ok()?,
+        _ => default,
+    };
+    Some(CommandSpec {
+        kind,
+        pos: [parse_coord(pos[0])?, parse_coord(pos[1])?, parse_coord(pos[2])?],
+        radius: pos[3].parse().ok()?,
+        param_0,
+    })
+}
+
+impl Scenario {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some(SCENARIO_HEADER) {
+            return Err(format!("missing '{SCENARIO_HEADER}' header"));
+        }
+        let mut scenario = Scenario { title: String::new(), steps: Vec::new() };
+        for (n, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (verb, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            let args: Vec<&str> = rest.split_whitespace().collect();
+            let bad = || format!("scenario line {}: bad '{verb}' step", n + 2);
+            let step = match verb {
+                "title" => {
+                    scenario.title = rest.to_string();
+                    continue;
+                }
+                "say" => ScenarioStep::Say(rest.to_string()),
+                "wait" => ScenarioStep::Wait(parse_condition(&args).ok_or_else(bad)?),
+                "advance" => ScenarioStep::Advance(rest.parse().map_err(|_| bad())?),
+                "place" => {
+                    let (name, args) = args.split_first().ok_or_else(bad)?;
+                    let t = placeable(name).ok_or_else(|| format!("scenario line {}: unknown voxel type '{name}'", n + 2))?;
+                    ScenarioStep::Apply(parse_command(CommandType::PlaceVoxel, t as u32, args).ok_or_else(bad)?)
+                }
+                "remove" => ScenarioStep::Apply(parse_command(CommandType::RemoveVoxel, 0, &args).ok_or_else(bad)?),
+                "seed" => ScenarioStep::Apply(parse_command(CommandType::SeedProtocells, 500, &args).ok_or_else(bad)?),
+                "toxin" => ScenarioStep::Apply(parse_command(CommandType::ApplyToxin, 128, &args).ok_or_else(bad)?),
+                _ => return Err(format!("scenario line {}: unknown step '{verb}'", n + 2)),
+            };
+            scenario.steps.push(step);
+        }
+        Ok(scenario)
+    }
+}
+
+/// Side effects the host performs for the runner.
+#[derive(Debug, Clone, Copy)]
+pub enum ScenarioAction {
+    Apply(Command),
+    /// Run exactly this many ticks.
+    Advance(u32),
+}
+
+/// Steps through a scenario. Message, command and advance steps complete
+/// immediately; the runner stops at the first unmet wait.
+#[derive(Debug, Clone)]
+pub struct ScenarioRunner {
+    scenario: Scenario,
+    index: usize,
+    /// Tick the current wait began, set when the step is first reached.
+    step_tick: Option<u32>,
+    /// Ticks still owed by the current `advance` step.
+    advance_target: Option<u32>,
+    message: String,
+    continue_pressed: bool,
+}
+
+impl ScenarioRunner {
+    pub fn new(scenario: Scenario) -> Self {
+        Self {
+            scenario,
+            index: 0,
+            step_tick: None,
+            advance_target: None,
+            message: String::new(),
+            continue_pressed: false,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.scenario.title
+    }
+
+    /// Latest `say` text.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Index of the step the runner is on.
+    pub fn step(&self) -> usize {
+        self.index
+    }
+
+    pub fn step_count(&self) -> usize {
+        self.scenario.steps.len()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.index >= self.scenario.steps.len()
+    }
+
+    /// The condition being waited on, if any.
+    pub fn waiting_for(&self) -> Option<Condition> {
+        match self.scenario.steps.get(self.index)? {
+            ScenarioStep::Wait(c) => Some(*c),
+            ScenarioStep::Advance(n) => Some(Condition::Ticks(*n)),
+            _ => None,
+        }
+    }
+
+    /// Satisfy the current `wait continue`; ignored by any other step.
+    pub fn press_continue(&mut self) {
+        if self.waiting_for() == Some(Condition::Continue) {
+            self.continue_pressed = true;
+        }
+    }
+
+    /// Run steps against `stats`; returns what the host must do this frame.
+    pub fn update(&mut self, stats: &ScenarioStats, grid_size: u32) -> Vec<ScenarioAction> {
+        let mut actions = Vec::new();
+        while let Some(step) = self.scenario.steps.get(self.index) {
+            let start = *self.step_tick.get_or_insert(stats.tick);
+            let done = match step {
+                ScenarioStep::Say(text) => {
+                    self.message = text.clone();
+                    true
+                }
+                ScenarioStep::Apply(spec) => {
+                    actions.push(ScenarioAction::Apply(spec.resolve(grid_size)));
+                    true
+                }
+                ScenarioStep::Advance(n) => {
+                    if self.advance_target.is_none() {
+                        self.advance_target = Some(start.saturating_add(*n));
+                        actions.push(ScenarioAction::Advance(*n));
+                    }
+                    self.advance_target.is_some_and(|t| stats.tick >= t)
+                }
+                ScenarioStep::Wait(Condition::Continue) => std::mem::take(&mut self.continue_pressed),
+                // A reset tick count (preset load) restarts the count
+                ScenarioStep::Wait(Condition::Ticks(n)) => stats.tick >= start.saturating_add(*n),
+                ScenarioStep::Wait(Condition::AtLeast(m, n)) => m.value(stats) >= *n,
+                ScenarioStep::Wait(Condition::AtMost(m, n)) => m.value(stats) <= *n,
+            };
+            if !done {
+                if stats.tick < start {
+                    self.step_tick = Some(stats.tick);
+                }
+                break;
+            }
+            self.index += 1;
+            self.step_tick = None;
+            self.advance_target = None;
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(tick: u32, population: u32) -> ScenarioStats {
+        ScenarioStats { tick, population, ..Default::default() }
+    }
+
+    #[test]
+    fn parses_steps_and_rejects_bad_lines() {
+        let s = Scenario::parse(
+            "primordium-scenario 1\ntitle Demo\n# comment\nsay Hi there\nwait population >= 5\nplace nutrient 50% 10 0 2\nseed 1 2 3 1 900\nadvance 20\n",
+        )
+        .unwrap();
+        assert_eq!(s.title, "Demo");
+        assert_eq!(s.steps.len(), 5);
+        assert_eq!(s.steps[1], ScenarioStep::Wait(Condition::AtLeast(Metric::Population, 5)));
+        let ScenarioStep::Apply(place) = &s.steps[2] else { panic!() };
+        let cmd = place.resolve(64);
+        assert_eq!((cmd.x, cmd.y, cmd.z, cmd.radius, cmd.param_0), (32, 10, 0, 2, VoxelType::Nutrient as u32));
+        let ScenarioStep::Apply(seed) = &s.steps[3] else { panic!() };
+        assert_eq!(seed.param_0, 900);
+
+        assert!(Scenario::parse("say hi").is_err());
+        assert!(Scenario::parse("primordium-scenario 1\nwait population > 5").is_err());
+        assert!(Scenario::parse("primordium-scenario 1\nplace protocell 1 1 1 1").is_err());
+        assert!(Scenario::parse(TUTORIAL_SCENARIO).is_ok());
+    }
+
+    #[test]
+    fn runner_waits_and_emits_actions() {
+        let s = Scenario::parse(
+            "primordium-scenario 1\nsay one\nwait continue\nseed 1 1 1 1\nsay two\nwait population >= 10\nadvance 5\nwait ticks 3\n",
+        )
+        .unwrap();
+        let mut r = ScenarioRunner::new(s);
+        assert!(r.update(&stats(0, 0), 8).is_empty());
+        assert_eq!((r.message(), r.waiting_for()), ("one", Some(Condition::Continue)));
+
+        r.press_continue();
+        let actions = r.update(&stats(0, 0), 8);
+        assert!(matches!(actions[..], [ScenarioAction::Apply(c)] if c.command_type == CommandType::SeedProtocells as u32));
+        assert_eq!(r.message(), "two");
+
+        let actions = r.update(&stats(10, 12), 8);
+        assert!(matches!(actions[..], [ScenarioAction::Advance(5)]));
+        assert!(r.update(&stats(14, 12), 8).is_empty());
+        assert_eq!(r.waiting_for(), Some(Condition::Ticks(5)));
+        r.update(&stats(15, 12), 8);
+        assert_eq!(r.waiting_for(), Some(Condition::Ticks(3)));
+        r.update(&stats(17, 12), 8);
+        assert!(!r.finished());
+        r.update(&stats(18, 12), 8);
+        assert!(r.finished());
+    }
+}
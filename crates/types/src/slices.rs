@@ -0,0 +1,176 @@
+//! Volume slice export (`export_slices`): the render texture is copied out
+//! one slice per frame and each slice becomes a square RGBA image, giving
+//! an image stack for tools such as ImageJ or napari.
+
+use crate::{transfer_texture_width, TRANSFER_CELLS_PER_TEXEL};
+
+/// Texture copy rows are padded to this many bytes.
+pub const SLICE_ROW_ALIGN: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceAxis {
+    X = 0,
+    Y = 1,
+    Z = 2,
+}
+
+impl SliceAxis {
+    pub fn from_u32(v: u32) -> Option<Self> {
+        match v {
+            0 => Some(SliceAxis::X),
+            1 => Some(SliceAxis::Y),
+            2 => Some(SliceAxis::Z),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SliceAxis::X => "x",
+            SliceAxis::Y => "y",
+            SliceAxis::Z => "z",
+        }
+    }
+}
+
+/// Copy of slice `index` along `axis` from a `grid_size`³ RGBA8 texture.
+/// Images have +y up; Y slices are seen from above, x right and z down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceCopy {
+    pub axis: SliceAxis,
+    pub index: u32,
+    pub grid_size: u32,
+    /// The texture holds transfer inputs, `TRANSFER_CELLS_PER_TEXEL` cells
+    /// per texel along x.
+    pub transfer: bool,
+}
+
+impl SliceCopy {
+    /// Texel column of cell column `x`.
+    fn texel_x(&self, x: u32) -> u32 {
+        if self.transfer { x / TRANSFER_CELLS_PER_TEXEL } else { x }
+    }
+
+    pub fn origin(&self) -> [u32; 3] {
+        let mut origin = [0; 3];
+        origin[self.axis as usize] = self.index;
+        origin[0] = self.texel_x(origin[0]);
+        origin
+    }
+
+    /// Copy size: the texture, one texel thick along the axis.
+    pub fn extent(&self) -> [u32; 3] {
+        let width = if self.transfer { transfer_texture_width(self.grid_size) } else { self.grid_size };
+        let mut extent = [width, self.grid_size, self.grid_size];
+        extent[self.axis as usize] = 1;
+        extent
+    }
+
+    pub fn bytes_per_row(&self) -> u32 {
+        (self.extent()[0] * 4).next_multiple_of(SLICE_ROW_ALIGN)
+    }
+
+    pub fn rows_per_image(&self) -> u32 {
+        self.extent()[1]
+    }
+
+    /// Size of the buffer the slice is copied into.
+    pub fn buffer_bytes(&self) -> u64 {
+        let [_, h, d] = self.extent();
+        self.bytes_per_row() as u64 * h as u64 * d as u64
+    }
+
+    /// Cell shown at image column `col`, row `row` (top down).
+    pub fn cell(&self, col: u32, row: u32) -> (u32, u32, u32) {
+        let flip = self.grid_size - 1 - row;
+        match self.axis {
+            SliceAxis::X => (self.index, flip, col),
+            SliceAxis::Y => (col, self.index, row),
+            SliceAxis::Z => (col, flip, self.index),
+        }
+    }
+
+    /// Byte offset of `cell`'s texel (its two bytes with transfer inputs)
+    /// in the copied buffer.
+    pub fn offset(&self, cell: (u32, u32, u32)) -> usize {
+        let [ox, oy, oz] = self.origin();
+        let (x, y, z) = (self.texel_x(cell.0) - ox, cell.1 - oy, cell.2 - oz);
+        let pair = if self.transfer { cell.0 % TRANSFER_CELLS_PER_TEXEL * 2 } else { 0 };
+        ((z * self.rows_per_image() + y) * self.bytes_per_row() + x * 4 + pair) as usize
+    }
+
+    /// The image's RGBA rows, top to bottom, from the copied bytes, each
+    /// texel mapped through `texel`. Transfer inputs are passed as one
+    /// texel per cell: row, scalar, 0 and 255 unless the row is empty.
+    pub fn gather(&self, data: &[u8], mut texel: impl FnMut([u8; 4]) -> [u8; 4]) -> Vec<u8> {
+        let gs = self.grid_size;
+        let mut out = Vec::with_capacity((gs * gs * 4) as usize);
+        for row in 0..gs {
+            for col in 0..gs {
+                let o = self.offset(self.cell(col, row));
+                let cell = if self.transfer {
+                    [data[o], data[o + 1], 0, if data[o] != 0 { 255 } else { 0 }]
+                } else {
+                    [data[o], data[o + 1], data[o + 2], data[o + 3]]
+                };
+                out.extend(texel(cell));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices_map_cells_to_padded_rows() {
+        let z = SliceCopy { axis: SliceAxis::Z, index: 5, grid_size: 32, transfer: false };
+        assert_eq!((z.origin(), z.extent()), ([0, 0, 5], [32, 32, 1]));
+        assert_eq!((z.bytes_per_row(), z.buffer_bytes()), (256, 256 * 32));
+        assert_eq!(z.cell(3, 0), (3, 31, 5));
+        assert_eq!(z.offset((3, 31, 5)), 31 * 256 + 12);
+
+        let x = SliceCopy { axis: SliceAxis::X, index: 7, grid_size: 32, transfer: false };
+        assert_eq!((x.extent(), x.bytes_per_row(), x.rows_per_image()), ([1, 32, 32], 256, 32));
+        assert_eq!(x.cell(4, 1), (7, 30, 4));
+        assert_eq!(x.offset((7, 30, 4)), (4 * 32 + 30) * 256);
+
+        let y = SliceCopy { axis: SliceAxis::Y, index: 2, grid_size: 4, transfer: false };
+        assert_eq!(y.cell(1, 3), (1, 2, 3));
+        // One texel per cell, each holding its z
+        let mut data = vec![0u8; y.buffer_bytes() as usize];
+        for zi in 0..4 {
+            for xi in 0..4 {
+                data[y.offset((xi, 2, zi))] = zi as u8;
+            }
+        }
+        let image = y.gather(&data, |t| t);
+        assert_eq!(image.len(), 64);
+        assert_eq!((image[0], image[16], image[48]), (0, 1, 3));
+        assert_eq!(SliceAxis::from_u32(3), None);
+    }
+
+    #[test]
+    fn transfer_slices_take_two_cells_per_texel() {
+        let x = SliceCopy { axis: SliceAxis::X, index: 7, grid_size: 32, transfer: true };
+        assert_eq!((x.origin(), x.extent()), ([3, 0, 0], [1, 32, 32]));
+        assert_eq!(x.offset((7, 30, 4)), (4 * 32 + 30) * 256 + 2);
+
+        let z = SliceCopy { axis: SliceAxis::Z, index: 1, grid_size: 4, transfer: true };
+        assert_eq!((z.extent(), z.bytes_per_row()), ([2, 4, 1], 256));
+        assert_eq!((z.offset((2, 0, 1)), z.offset((3, 0, 1))), (4, 6));
+        // Each cell's row is its x, its scalar 9; rows 0 are empty
+        let mut data = vec![0u8; z.buffer_bytes() as usize];
+        for y in 0..4 {
+            for xi in 0..4 {
+                let o = z.offset((xi, y, 1));
+                data[o..o + 2].copy_from_slice(&[xi as u8, 9]);
+            }
+        }
+        let image = z.gather(&data, |t| t);
+        assert_eq!(&image[..8], &[0, 9, 0, 0, 1, 9, 0, 255]);
+        assert_eq!(&image[12..16], &[3, 9, 0, 255]);
+    }
+}
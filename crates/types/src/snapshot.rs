@@ -0,0 +1,213 @@
+//! Versioned save format for whole runs (`export_snapshot` /
+//! `import_snapshot`): voxels, temperature, tick count, simulated time and
+//! params. Storage mirrors the engine that wrote it — the grid in
+//! `grid_index` order, or a sparse engine's brick pool plus brick table —
+//! and either kind loads into either mode.
+//!
+//! Layout, little-endian: magic `PSNP`, u32 version, u32 grid size, u32
+//! tick count, f64 sim time; u32 param count and per param a u8 name
+//! length, the name and an f32; u32 brick table words (0 when dense) and
+//! the table; u64 voxel bytes and the packed voxels (32 bytes each); u64
+//! temperature bytes and one f32 per stored cell.
+
+use crate::layout::VOXEL_STRIDE;
+use crate::{dump_slots, SimParams, Voxel, VoxelType};
+
+pub const SNAPSHOT_MAGIC: &[u8; 4] = b"PSNP";
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// Temperature of cells a sparse snapshot holds no brick for.
+const AMBIENT_TEMP: f32 = 0.5;
+
+/// Params that describe the storage rather than the run; importing keeps
+/// the engine's own.
+const GRID_PARAMS: [&str; 4] = ["grid_size", "sparse_mode", "brick_grid_dim", "max_bricks"];
+
+#[derive(Debug, Clone)]
+pub struct WorldSnapshot {
+    pub grid_size: u32,
+    pub tick_count: u32,
+    pub sim_time: f64,
+    pub params: SimParams,
+    /// Present when `voxels` and `temperatures` are a brick pool.
+    pub brick_table: Option<Vec<u32>>,
+    /// Packed voxels (`Voxel::pack`), little-endian.
+    pub voxels: Vec<u8>,
+    pub temperatures: Vec<f32>,
+}
+
+/// Reads the snapshot fields in order, failing on truncation.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < n {
+            return Err("snapshot is truncated".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+impl WorldSnapshot {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = SNAPSHOT_MAGIC.to_vec();
+        for word in [SNAPSHOT_VERSION, self.grid_size, self.tick_count] {
+            out.extend(word.to_le_bytes());
+        }
+        out.extend(self.sim_time.to_le_bytes());
+        let params = self.params.named_fields();
+        out.extend((params.len() as u32).to_le_bytes());
+        for (name, value) in params {
+            out.push(name.len() as u8);
+            out.extend(name.as_bytes());
+            out.extend(value.to_le_bytes());
+        }
+        let table = self.brick_table.as_deref().unwrap_or(&[]);
+        out.extend((table.len() as u32).to_le_bytes());
+        out.extend(table.iter().flat_map(|w| w.to_le_bytes()));
+        out.extend((self.voxels.len() as u64).to_le_bytes());
+        out.extend(&self.voxels);
+        out.extend((self.temperatures.len() as u64 * 4).to_le_bytes());
+        out.extend(self.temperatures.iter().flat_map(|t| t.to_le_bytes()));
+        out
+    }
+
+    /// Parse `bytes`. Params the snapshot names but this build lacks are
+    /// ignored; ones it lacks keep their defaults.
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut r = Reader { bytes };
+        if r.take(4)? != SNAPSHOT_MAGIC {
+            return Err("not a world snapshot".to_string());
+        }
+        let version = r.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(format!("snapshot version {version} is not supported (expected {SNAPSHOT_VERSION})"));
+        }
+        let grid_size = r.u32()?;
+        let tick_count = r.u32()?;
+        let sim_time = f64::from_le_bytes(r.take(8)?.try_into().unwrap());
+        let mut params = SimParams::default();
+        for _ in 0..r.u32()? {
+            let len = r.take(1)?[0] as usize;
+            let name = String::from_utf8_lossy(r.take(len)?).into_owned();
+            params.set_named(&name, r.f32()?);
+        }
+        let table_words = r.u32()? as usize;
+        let brick_table = (table_words > 0)
+            .then(|| -> Result<Vec<u32>, String> { (0..table_words).map(|_| r.u32()).collect() })
+            .transpose()?;
+        let voxel_bytes = r.u64()? as usize;
+        let voxels = r.take(voxel_bytes)?.to_vec();
+        let temp_bytes = r.u64()? as usize;
+        let temperatures = r.take(temp_bytes)?.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+        if grid_size == 0 || voxels.len() % (VOXEL_STRIDE as usize * 4) != 0 {
+            return Err("snapshot voxel data is malformed".to_string());
+        }
+        Ok(WorldSnapshot { grid_size, tick_count, sim_time, params, brick_table, voxels, temperatures })
+    }
+
+    /// Copy the run's params into `params`, keeping its storage fields.
+    pub fn apply_params(&self, params: &mut SimParams) {
+        for (name, value) in self.params.named_fields() {
+            if !GRID_PARAMS.contains(&name) {
+                params.set_named(name, value);
+            }
+        }
+    }
+
+    /// Non-empty cells as seed voxels, in world coordinates.
+    pub fn seed_voxels(&self) -> Vec<(u32, u32, u32, [u32; 8])> {
+        let stride = VOXEL_STRIDE as usize * 4;
+        dump_slots(self.grid_size, self.brick_table.as_deref(), self.voxels.len() / stride)
+            .into_iter()
+            .filter_map(|((x, y, z), slot)| {
+                let mut words = [0u32; 8];
+                for (w, b) in words.iter_mut().zip(self.voxels[slot * stride..(slot + 1) * stride].chunks_exact(4)) {
+                    *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                }
+                (Voxel::unpack(words).voxel_type != VoxelType::Empty).then_some((x, y, z, words))
+            })
+            .collect()
+    }
+
+    /// Temperature of every cell in `grid_index` order, ambient where the
+    /// snapshot stores none.
+    pub fn dense_temperatures(&self) -> Vec<f32> {
+        let mut temps = vec![AMBIENT_TEMP; (self.grid_size as usize).pow(3)];
+        for ((x, y, z), slot) in dump_slots(self.grid_size, self.brick_table.as_deref(), self.temperatures.len()) {
+            temps[crate::grid_index(x, y, z, self.grid_size)] = self.temperatures[slot];
+        }
+        temps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_round_trip() {
+        let gs = 8;
+        let cell = Voxel { voxel_type: VoxelType::Protocell, energy: 90, ..Default::default() };
+        let mut voxels = vec![Voxel::default(); 512];
+        voxels[crate::grid_index(1, 2, 3, gs)] = cell;
+        let mut temperatures = vec![0.5; 512];
+        temperatures[7] = 0.9;
+        let snapshot = WorldSnapshot {
+            grid_size: gs,
+            tick_count: 1234,
+            sim_time: 61.5,
+            params: SimParams { max_energy: 300.0, grid_size: 8.0, ..Default::default() },
+            brick_table: None,
+            voxels: voxels.iter().flat_map(|v| v.pack()).flat_map(u32::to_le_bytes).collect(),
+            temperatures,
+        };
+        let bytes = snapshot.encode();
+        let back = WorldSnapshot::decode(&bytes).unwrap();
+        assert_eq!((back.grid_size, back.tick_count, back.sim_time), (8, 1234, 61.5));
+        assert_eq!(back.seed_voxels(), vec![(1, 2, 3, cell.pack())]);
+        assert_eq!(back.dense_temperatures()[7], 0.9);
+
+        let mut params = SimParams { grid_size: 64.0, ..Default::default() };
+        back.apply_params(&mut params);
+        assert_eq!((params.max_energy, params.grid_size), (300.0, 64.0));
+
+        assert!(WorldSnapshot::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(WorldSnapshot::decode(b"VOX \x96\0\0\0").is_err());
+    }
+
+    #[test]
+    fn sparse_snapshots_fill_unstored_cells() {
+        // 16³ with only brick (1, 1, 1) stored, in pool slot 0
+        let mut table = vec![u32::MAX; 8];
+        table[7] = 0;
+        let snapshot = WorldSnapshot {
+            grid_size: 16,
+            tick_count: 0,
+            sim_time: 0.0,
+            params: SimParams::default(),
+            brick_table: Some(table),
+            voxels: vec![0; 512 * 32],
+            temperatures: vec![0.8; 512],
+        };
+        let back = WorldSnapshot::decode(&snapshot.encode()).unwrap();
+        let temps = back.dense_temperatures();
+        assert_eq!((temps[0], temps[crate::grid_index(8, 8, 8, 16)]), (AMBIENT_TEMP, 0.8));
+        assert!(back.seed_voxels().is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+//! Sonification: maps successive stats samples to synth parameters so a
+//! long run can be followed by ear. Synthesis itself happens in the page.
+
+/// Lowest drone pitch (empty grid).
+pub const SONIFY_BASE_HZ: f32 = 110.0;
+/// Octaves the drone rises between an empty and a full grid.
+pub const SONIFY_PITCH_OCTAVES: f32 = 3.0;
+/// Fastest pulse rate, reached at high relative birth rates.
+pub const SONIFY_MAX_PULSE_HZ: f32 = 12.0;
+
+/// Values a synth voice follows; every field is ready to use as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SynthParams {
+    /// Master gain, 0..=0.75; silent when nothing is alive.
+    pub gain: f32,
+    /// Drone pitch, rising logarithmically with population.
+    pub pitch_hz: f32,
+    /// Amplitude pulse rate, following the relative birth rate.
+    pub pulse_hz: f32,
+    /// Noise mix, 0..=1, following the relative death rate.
+    pub noise: f32,
+    /// Detune of the second oscillator in cents, following species turnover.
+    pub detune_cents: f32,
+    /// Harmonic voices, one per species (1..=8).
+    pub voices: u32,
+}
+
+/// Turns stats samples into `SynthParams`. Births and deaths are
+/// estimated from the net population change plus toxin deaths, since the
+/// stats buffer carries no direct birth counter.
+#[derive(Debug, Clone, Default)]
+pub struct Sonifier {
+    last: Option<(u32, u32)>,
+    last_species: Vec<u16>,
+}
+
+impl Sonifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold in the sample taken at `tick`. `capacity` is the voxel count of
+    /// the grid; `species` is the stats histogram (species_id, count).
+    pub fn update(
+        &mut self,
+        tick: u32,
+        capacity: u32,
+        population: u32,
+        toxin_deaths: u32,
+        species: &[(u16, u32)],
+    ) -> SynthParams {
+        let (births, deaths) = match self.last {
+            Some((last_tick, last_pop)) if tick > last_tick => {
+                let ticks = (tick - last_tick) as f32;
+                let delta = population as f32 - last_pop as f32;
+                let deaths = (-delta).max(0.0) / ticks + toxin_deaths as f32;
+                (delta.max(0.0) / ticks + toxin_deaths as f32, deaths)
+            }
+            _ => (0.0, toxin_deaths as f32),
+        };
+        self.last = Some((tick, population));
+
+        let ids: Vec<u16> = species.iter().map(|&(sid, _)| sid).collect();
+        let turnover = if ids.is_empty() || self.last_species.is_empty() {
+            0.0
+        } else {
+            ids.iter().filter(|sid| !self.last_species.contains(sid)).count() as f32 / ids.len() as f32
+        };
+        self.last_species = ids;
+
+        if population == 0 {
+            return SynthParams { pitch_hz: SONIFY_BASE_HZ, voices: 1, ..SynthParams::default() };
+        }
+        let pop = population as f32;
+        let fill = (pop.ln_1p() / (capacity.max(1) as f32).ln_1p()).min(1.0);
+        SynthParams {
+            gain: 0.15 + 0.6 * fill,
+            pitch_hz: SONIFY_BASE_HZ * (SONIFY_PITCH_OCTAVES * fill).exp2(),
+            pulse_hz: (0.5 + 400.0 * births / pop).min(SONIFY_MAX_PULSE_HZ),
+            noise: (200.0 * deaths / pop).min(1.0),
+            detune_cents: 50.0 * turnover,
+            voices: (species.len() as u32).clamp(1, 8),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silent_when_extinct() {
+        let params = Sonifier::new().update(10, 4096, 0, 0, &[]);
+        assert_eq!(params.gain, 0.0);
+        assert_eq!(params.pitch_hz, SONIFY_BASE_HZ);
+    }
+
+    #[test]
+    fn tracks_growth_deaths_and_turnover() {
+        let mut s = Sonifier::new();
+        let first = s.update(0, 4096, 100, 0, &[(1, 60), (2, 40)]);
+        assert_eq!(first.pulse_hz, 0.5);
+        assert_eq!(first.detune_cents, 0.0);
+        assert_eq!(first.voices, 2);
+
+        let grown = s.update(10, 4096, 200, 0, &[(1, 150), (3, 50)]);
+        assert!(grown.pitch_hz > first.pitch_hz);
+        assert!(grown.pulse_hz > first.pulse_hz);
+        assert_eq!(grown.noise, 0.0);
+        assert_eq!(grown.detune_cents, 25.0);
+
+        let shrunk = s.update(20, 4096, 100, 5, &[(1, 70), (3, 30)]);
+        assert!(shrunk.noise > 0.0);
+        assert_eq!(shrunk.detune_cents, 0.0);
+        assert!(s.update(30, 4096, 4096, 0, &[(1, 4096)]).pitch_hz <= SONIFY_BASE_HZ * 8.0 + 0.01);
+    }
+}
@@ -0,0 +1,130 @@
+//! The two-stage species count of stats_reduction.wgsl, on the CPU: each
+//! of the `STATS_PARTIAL_GROUPS` first-stage workgroups counts a contiguous
+//! range of cells into a table of `STATS_WG_SPECIES_SLOTS`, and the finalize
+//! stage merges those tables into the `STATS_SPECIES_SLOTS` histogram. A
+//! full table spills into the other-species bucket.
+
+use std::ops::Range;
+
+use crate::layout::{STATS_PARTIAL_GROUPS, STATS_SPECIES_SLOTS, STATS_WG_SPECIES_SLOTS};
+
+/// Cells (or sparse pool cells) first-stage workgroup `group` counts out of
+/// `total`. Matches `stats_reduction_main`.
+pub fn stats_partial_range(group: u32, total: u32) -> Range<u32> {
+    let chunk = total.div_ceil(STATS_PARTIAL_GROUPS);
+    let start = (group * chunk).min(total);
+    start..(start + chunk).min(total)
+}
+
+/// An open-addressed species table, probed linearly from `id % slots`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeciesTable {
+    pub ids: Vec<u32>,
+    pub counts: Vec<u32>,
+    pub in_goal: Vec<u32>,
+    /// Protocells, and those in the goal zone, of species with no entry.
+    pub other: u32,
+    pub other_goal: u32,
+}
+
+impl SpeciesTable {
+    pub fn new(slots: u32) -> Self {
+        let empty = vec![0; slots as usize];
+        Self { ids: empty.clone(), counts: empty.clone(), in_goal: empty, other: 0, other_goal: 0 }
+    }
+
+    /// Add `count` protocells of `species_id`, `in_goal` of them in the goal
+    /// zone.
+    pub fn add(&mut self, species_id: u32, count: u32, in_goal: u32) {
+        let slots = self.ids.len();
+        let start = species_id as usize % slots;
+        for probe in 0..slots {
+            let slot = (start + probe) % slots;
+            if self.ids[slot] == 0 || self.ids[slot] == species_id {
+                self.ids[slot] = species_id;
+                self.counts[slot] += count;
+                self.in_goal[slot] += in_goal;
+                return;
+            }
+        }
+        self.other += count;
+        self.other_goal += in_goal;
+    }
+
+    /// Protocells counted for `species_id` (0 without an entry).
+    pub fn count(&self, species_id: u32) -> u32 {
+        self.ids.iter().position(|&id| id == species_id).map_or(0, |slot| self.counts[slot])
+    }
+}
+
+/// Both stages over `cells`, each a species id (0: not a protocell, or no
+/// species) and whether it lies in the goal zone.
+pub fn reduce_species(cells: &[(u16, bool)]) -> SpeciesTable {
+    let mut histogram = SpeciesTable::new(STATS_SPECIES_SLOTS);
+    for group in 0..STATS_PARTIAL_GROUPS {
+        let mut table = SpeciesTable::new(STATS_WG_SPECIES_SLOTS);
+        let range = stats_partial_range(group, cells.len() as u32);
+        for &(species_id, goal) in &cells[range.start as usize..range.end as usize] {
+            if species_id != 0 {
+                table.add(species_id as u32, 1, goal as u32);
+            }
+        }
+        for slot in 0..table.ids.len() {
+            if table.ids[slot] != 0 && table.counts[slot] != 0 {
+                histogram.add(table.ids[slot], table.counts[slot], table.in_goal[slot]);
+            }
+        }
+        histogram.other += table.other;
+        histogram.other_goal += table.other_goal;
+    }
+    histogram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_ranges_tile_the_cells() {
+        for total in [0, 1, 511, 512, 513, 64 * 64 * 64, 100_003] {
+            let mut next = 0;
+            for group in 0..STATS_PARTIAL_GROUPS {
+                let range = stats_partial_range(group, total);
+                assert_eq!(range.start, next, "total {total}, group {group}");
+                next = range.end;
+            }
+            assert_eq!(next, total);
+        }
+    }
+
+    #[test]
+    fn more_species_than_a_workgroup_table_merge_exactly() {
+        // Every workgroup sees all 60 species, well past a 32-entry table
+        let species = 60;
+        let cells: Vec<(u16, bool)> =
+            (0..64 * 64 * 64u32).map(|i| ((i % species + 1) as u16, i % 7 == 0)).collect();
+        let histogram = reduce_species(&cells);
+        assert_eq!((histogram.other, histogram.other_goal), (0, 0));
+        for sid in 1..=species {
+            let expected = cells.iter().filter(|&&(s, _)| s as u32 == sid).count() as u32;
+            assert_eq!(histogram.count(sid), expected, "species {sid}");
+        }
+        let goal: u32 = histogram.in_goal.iter().sum();
+        assert_eq!(goal, cells.iter().filter(|&&(s, g)| s != 0 && g).count() as u32);
+    }
+
+    #[test]
+    fn overflowing_species_are_counted_as_other() {
+        // Colonies of 200 species in runs of cells: the histogram fills up
+        let cells: Vec<(u16, bool)> = (0..64 * 64 * 64u32).map(|i| ((i / 1024 % 200 + 1) as u16, false)).collect();
+        let histogram = reduce_species(&cells);
+        assert!(histogram.ids.iter().all(|&id| id != 0));
+        assert!(histogram.other > 0);
+        assert_eq!(histogram.counts.iter().sum::<u32>() + histogram.other, cells.len() as u32);
+        // Tracked species are counted in full
+        for (&sid, &count) in histogram.ids.iter().zip(&histogram.counts) {
+            let expected = cells.iter().filter(|&&(s, _)| s as u32 == sid).count() as u32;
+            assert_eq!(count, expected, "species {sid}");
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Edit symmetry: each user edit is repeated at its mirror images and, with
+//! four-fold symmetry, its quarter turns about the vertical (Y) axis through
+//! the grid center, so balanced arenas take one edit per feature.
+
+use crate::{pack_region_max, unpack_region_max, CellBox, Command};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EditSymmetry {
+    /// Mirror across the center plane of each axis (X, Y, Z).
+    pub mirror: [bool; 3],
+    /// Quarter turns about the Y axis.
+    pub rotate4: bool,
+}
+
+impl EditSymmetry {
+    pub const MIRROR_X: u32 = 1;
+    pub const MIRROR_Y: u32 = 2;
+    pub const MIRROR_Z: u32 = 4;
+    pub const ROTATE_4: u32 = 8;
+
+    /// From `MIRROR_*` / `ROTATE_4` bits; unknown bits are ignored.
+    pub fn from_bits(bits: u32) -> Self {
+        EditSymmetry {
+            mirror: [bits & Self::MIRROR_X != 0, bits & Self::MIRROR_Y != 0, bits & Self::MIRROR_Z != 0],
+            rotate4: bits & Self::ROTATE_4 != 0,
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        let flag = |on: bool, bit: u32| if on { bit } else { 0 };
+        flag(self.mirror[0], Self::MIRROR_X)
+            | flag(self.mirror[1], Self::MIRROR_Y)
+            | flag(self.mirror[2], Self::MIRROR_Z)
+            | flag(self.rotate4, Self::ROTATE_4)
+    }
+
+    /// The inclusive cell box `b` and its distinct images in a
+    /// `grid_size`³ grid, `b` first.
+    pub fn images(self, b: CellBox, grid_size: u32) -> Vec<CellBox> {
+        let last = grid_size.saturating_sub(1);
+        let mut out = vec![b];
+        for axis in (0..3).filter(|&a| self.mirror[a]) {
+            for i in 0..out.len() {
+                let (mut lo, mut hi) = (to_array(out[i].0), to_array(out[i].1));
+                (lo[axis], hi[axis]) = (last.saturating_sub(hi[axis]), last.saturating_sub(lo[axis]));
+                out.push((to_tuple(lo), to_tuple(hi)));
+            }
+        }
+        if self.rotate4 {
+            for i in 0..out.len() {
+                let mut cur = out[i];
+                for _ in 0..3 {
+                    // (x, z) → (last - z, x): the z extremes swap into x
+                    let ((x0, y0, z0), (x1, y1, z1)) = cur;
+                    cur = ((last.saturating_sub(z1), y0, x0), (last.saturating_sub(z0), y1, x1));
+                    out.push(cur);
+                }
+            }
+        }
+        let mut distinct = Vec::with_capacity(out.len());
+        for b in out {
+            if !distinct.contains(&b) {
+                distinct.push(b);
+            }
+        }
+        distinct
+    }
+
+    /// `cmd` followed by its images. Region commands map their box, every
+    /// other command its position.
+    pub fn apply(self, cmd: &Command, grid_size: u32) -> Vec<Command> {
+        let pos = (cmd.x, cmd.y, cmd.z);
+        let is_region = cmd.is_region();
+        let max = if is_region { unpack_region_max(cmd.param_1) } else { pos };
+        self.images((pos, max), grid_size)
+            .into_iter()
+            .map(|(lo, hi)| {
+                let mut c = *cmd;
+                (c.x, c.y, c.z) = lo;
+                if is_region {
+                    c.param_1 = pack_region_max(hi.0, hi.1, hi.2);
+                }
+                c
+            })
+            .collect()
+    }
+}
+
+fn to_array(p: (u32, u32, u32)) -> [u32; 3] {
+    [p.0, p.1, p.2]
+}
+
+fn to_tuple(p: [u32; 3]) -> (u32, u32, u32) {
+    (p[0], p[1], p[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommandType;
+
+    #[test]
+    fn mirrors_and_rotates_points() {
+        let none = EditSymmetry::default();
+        assert_eq!(none.images(((1, 2, 3), (1, 2, 3)), 10), vec![((1, 2, 3), (1, 2, 3))]);
+
+        let xz = EditSymmetry::from_bits(EditSymmetry::MIRROR_X | EditSymmetry::MIRROR_Z);
+        assert_eq!(xz.bits(), 5);
+        let cells: Vec<_> = xz.images(((1, 2, 3), (1, 2, 3)), 10).into_iter().map(|b| b.0).collect();
+        assert_eq!(cells, vec![(1, 2, 3), (8, 2, 3), (1, 2, 6), (8, 2, 6)]);
+
+        let rot = EditSymmetry::from_bits(EditSymmetry::ROTATE_4);
+        let cells: Vec<_> = rot.images(((1, 5, 3), (1, 5, 3)), 10).into_iter().map(|b| b.0).collect();
+        assert_eq!(cells, vec![(1, 5, 3), (6, 5, 1), (8, 5, 6), (3, 5, 8)]);
+        // The center column maps onto itself
+        assert_eq!(rot.images(((2, 0, 2), (2, 0, 2)), 5).len(), 1);
+    }
+
+    #[test]
+    fn region_fills_map_their_box() {
+        let cmd = Command::fill_region((0, 1, 2), (3, 4, 5), 1, 255);
+        let images = EditSymmetry::from_bits(EditSymmetry::MIRROR_Y).apply(&cmd, 16);
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[1].x, images[1].y, images[1].z), (0, 11, 2));
+        assert_eq!(unpack_region_max(images[1].param_1), (3, 14, 5));
+        assert_eq!(images[1].param_0, cmd.param_0);
+
+        let place = Command::new(CommandType::PlaceVoxel, 0, 0, 0, 2, 1, 0);
+        let images = EditSymmetry::from_bits(EditSymmetry::ROTATE_4).apply(&place, 8);
+        let cells: Vec<_> = images.iter().map(|c| (c.x, c.y, c.z, c.radius)).collect();
+        assert_eq!(cells, vec![(0, 0, 0, 2), (7, 0, 0, 2), (7, 0, 7, 2), (0, 0, 7, 2)]);
+    }
+}
@@ -0,0 +1,216 @@
+//! Terrain from a grayscale heightmap (`seed_from_image`) or from noise
+//! (`generate_terrain`). A heightmap is stretched over the floor; each
+//! (x, z) column takes the brightness of its nearest pixel, with y as
+//! height.
+
+use crate::noise::fbm;
+use crate::{grid_index, VoxelType};
+
+/// Brightest pixels raise walls to `grid_size / TERRAIN_HEIGHT_DIV`.
+pub const TERRAIN_HEIGHT_DIV: u32 = 4;
+/// Nutrient layers are `grid_size / NUTRIENT_LAYER_DIV` cells thick.
+pub const NUTRIENT_LAYER_DIV: u32 = 8;
+
+/// What brightness controls. The discriminant is the `mapping` argument of
+/// `seed_from_image`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TerrainMapping {
+    /// Wall columns as tall as the brightness, under a nutrient layer as
+    /// dense as it.
+    #[default]
+    Terrain = 0,
+    /// Wall columns only.
+    Walls = 1,
+    /// A nutrient layer on the floor, as dense as the brightness.
+    Nutrients = 2,
+}
+
+/// Wall column height for `brightness`, rounded to the nearest cell.
+pub fn wall_height(brightness: u8, grid_size: u32) -> u32 {
+    (brightness as u32 * (grid_size / TERRAIN_HEIGHT_DIV) + 127) / 255
+}
+
+pub fn nutrient_layer(grid_size: u32) -> u32 {
+    (grid_size / NUTRIENT_LAYER_DIV).max(1)
+}
+
+/// Whether the nutrient layer fills cell (x, y, z) of a column with
+/// `brightness`: a hashed draw with odds `brightness / 255`.
+pub fn keeps_nutrient(x: u32, y: u32, z: u32, grid_size: u32, brightness: u8) -> bool {
+    let h = crate::arbitration::pcg_hash(grid_index(x, y, z, grid_size) as u32 ^ 0x5445_5252);
+    h % 255 < brightness as u32
+}
+
+impl TerrainMapping {
+    pub const ALL: [TerrainMapping; 3] = [TerrainMapping::Terrain, TerrainMapping::Walls, TerrainMapping::Nutrients];
+
+    pub fn from_u32(v: u32) -> Option<Self> {
+        Self::ALL.get(v as usize).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TerrainMapping::Terrain => "terrain",
+            TerrainMapping::Walls => "walls",
+            TerrainMapping::Nutrients => "nutrients",
+        }
+    }
+
+    /// Wall cells from the floor and the y range of the nutrient layer for a
+    /// column of `brightness`.
+    pub fn column(self, brightness: u8, grid_size: u32) -> (u32, std::ops::Range<u32>) {
+        let layer = nutrient_layer(grid_size);
+        match self {
+            TerrainMapping::Terrain => {
+                let walls = wall_height(brightness, grid_size);
+                (walls, walls..(walls + layer).min(grid_size))
+            }
+            TerrainMapping::Walls => (wall_height(brightness, grid_size), 0..0),
+            TerrainMapping::Nutrients => (0, 0..layer.min(grid_size)),
+        }
+    }
+}
+
+/// Procedural layouts. The discriminant is `style` in `generate_terrain`;
+/// presets 3, 4 and 5 use them in order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TerrainStyle {
+    /// Rock riddled with connected caves.
+    #[default]
+    Caves = 0,
+    /// Floor-to-ceiling rock pillars in open space.
+    Pillars = 1,
+    /// Open space threaded with nutrient veins only.
+    Veins = 2,
+}
+
+impl TerrainStyle {
+    pub const ALL: [TerrainStyle; 3] = [TerrainStyle::Caves, TerrainStyle::Pillars, TerrainStyle::Veins];
+
+    pub fn from_u32(v: u32) -> Option<Self> {
+        Self::ALL.get(v as usize).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TerrainStyle::Caves => "caves",
+            TerrainStyle::Pillars => "pillars",
+            TerrainStyle::Veins => "veins",
+        }
+    }
+}
+
+/// Noise terrain parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainConfig {
+    pub seed: u32,
+    pub style: TerrainStyle,
+    /// Feature size in cells (2..=256).
+    pub scale: f32,
+    /// Rough fraction of rock: of the volume for caves, of the floor for
+    /// pillars (0..=1).
+    pub density: f32,
+    /// Nutrient vein thickness (0 = none, 1 = thickest).
+    pub veins: f32,
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self { seed: 1, style: TerrainStyle::Caves, scale: 12.0, density: 0.45, veins: 0.4 }
+    }
+}
+
+const VEIN_SALT: u32 = 0x5645_494E;
+
+impl TerrainConfig {
+    /// Default config for `style`, as its preset uses.
+    pub fn for_style(style: TerrainStyle) -> Self {
+        match style {
+            TerrainStyle::Caves => Self::default(),
+            TerrainStyle::Pillars => Self { style, scale: 6.0, density: 0.25, ..Self::default() },
+            TerrainStyle::Veins => Self { style, veins: 1.0, ..Self::default() },
+        }
+    }
+
+    /// Parameters pulled into range.
+    pub fn clamped(self) -> Self {
+        Self {
+            scale: if self.scale.is_finite() { self.scale.clamp(2.0, 256.0) } else { 12.0 },
+            density: if self.density.is_finite() { self.density.clamp(0.0, 1.0) } else { 0.0 },
+            veins: if self.veins.is_finite() { self.veins.clamp(0.0, 1.0) } else { 0.0 },
+            ..self
+        }
+    }
+
+    /// What fills cell (x, y, z): rock walls, a nutrient vein or nothing.
+    /// Veins cut through rock.
+    pub fn cell(&self, x: u32, y: u32, z: u32) -> Option<VoxelType> {
+        let (px, py, pz) = (x as f32 / self.scale, y as f32 / self.scale, z as f32 / self.scale);
+        if self.veins > 0.0 && (fbm(px, py, pz, self.seed ^ VEIN_SALT, 2) - 0.5).abs() < self.veins * 0.04 {
+            return Some(VoxelType::Nutrient);
+        }
+        // fbm gathers around 0.5; spread densities over its usual range
+        let threshold = 0.3 + self.density * 0.4;
+        let rock = self.density > 0.0
+            && match self.style {
+                TerrainStyle::Caves => fbm(px, py, pz, self.seed, 3) < threshold,
+                TerrainStyle::Pillars => fbm(px, 0.0, pz, self.seed, 2) < threshold,
+                TerrainStyle::Veins => false,
+            };
+        rock.then_some(VoxelType::Wall)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brightness_sets_wall_height() {
+        assert_eq!(wall_height(0, 64), 0);
+        assert_eq!(wall_height(255, 64), 16);
+        assert_eq!(wall_height(128, 64), 8);
+        assert_eq!(TerrainMapping::Terrain.column(255, 64), (16, 16..24));
+        assert_eq!(TerrainMapping::Walls.column(255, 64), (16, 0..0));
+        assert_eq!(TerrainMapping::Nutrients.column(255, 64), (0, 0..8));
+        assert_eq!(TerrainMapping::Terrain.column(0, 4), (0, 0..1));
+        assert_eq!(TerrainMapping::from_u32(2), Some(TerrainMapping::Nutrients));
+        assert_eq!(TerrainMapping::from_u32(3), None);
+    }
+
+    #[test]
+    fn nutrient_density_follows_brightness() {
+        let count = |b| (0..4096).filter(|&i| keeps_nutrient(i % 64, 0, i / 64, 64, b)).count();
+        assert_eq!(count(0), 0);
+        assert_eq!(count(255), 4096);
+        assert!((1700..2400).contains(&count(128)));
+    }
+
+    #[test]
+    fn procedural_styles_shape_rock() {
+        let count = |cfg: TerrainConfig, kind: VoxelType| {
+            (0..32 * 32 * 32).filter(|&i| cfg.cell(i % 32, i / 32 % 32, i / 1024) == Some(kind)).count()
+        };
+        let caves = TerrainConfig { veins: 0.0, ..TerrainConfig::default() };
+        let rock = count(caves, VoxelType::Wall);
+        assert!((4000..26000).contains(&rock), "rock {rock}");
+        assert!(count(TerrainConfig { density: 0.8, ..caves }, VoxelType::Wall) > rock);
+        assert_eq!(count(TerrainConfig { density: 0.0, ..caves }, VoxelType::Wall), 0);
+        assert_eq!(count(caves, VoxelType::Nutrient), 0);
+
+        // Pillars run floor to ceiling
+        let pillars = TerrainConfig { veins: 0.0, ..TerrainConfig::for_style(TerrainStyle::Pillars) };
+        for (x, z) in [(3, 4), (10, 20), (31, 0)] {
+            assert!((0..32).all(|y| pillars.cell(x, y, z) == pillars.cell(x, 0, z)));
+        }
+
+        let veins = TerrainConfig::for_style(TerrainStyle::Veins);
+        assert_eq!(count(veins, VoxelType::Wall), 0);
+        assert!(count(veins, VoxelType::Nutrient) > 0);
+        let reseeded = TerrainConfig { seed: 2, ..veins };
+        assert_ne!(count(veins, VoxelType::Nutrient), count(reseeded, VoxelType::Nutrient));
+        assert_eq!(TerrainStyle::from_u32(1), Some(TerrainStyle::Pillars));
+        let wild = TerrainConfig { scale: f32::NAN, density: 3.0, veins: -1.0, ..veins }.clamped();
+        assert_eq!((wild.scale, wild.density, wild.veins), (12.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,191 @@
+//! Frame-to-tick accounting: how many ticks a frame's elapsed time owes at
+//! a tick rate, what happens to those past the per-frame cap, and the tick
+//! rate actually achieved. The host's `FrameTiming` wraps it with pause,
+//! single-step and background state.
+
+/// Default cap on ticks run in a single frame.
+pub const DEFAULT_MAX_CATCH_UP: u32 = 3;
+
+/// What to give up when the simulation can't keep pace with the tick rate.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimingMode {
+    /// Keep sim time locked to wall clock: ticks beyond the per-frame cap are
+    /// dropped (and counted in `dropped_ticks`).
+    RealTime = 0,
+    /// Never drop ticks: the backlog carries over and is worked off over
+    /// subsequent frames, so sim time may lag wall clock.
+    SimTime = 1,
+}
+
+impl TimingMode {
+    pub fn from_u32(v: u32) -> Self {
+        match v {
+            1 => TimingMode::SimTime,
+            _ => TimingMode::RealTime,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TickClock {
+    /// Seconds owed to ticks not yet run.
+    pub accumulator: f32,
+    pub mode: TimingMode,
+    /// Maximum ticks per frame. 0 = unlimited.
+    pub max_catch_up: u32,
+    /// Total ticks discarded by `TimingMode::RealTime` to stay on wall clock.
+    pub dropped_ticks: u64,
+    /// Wall-clock seconds since the last clock reset (includes paused time).
+    pub wall_elapsed: f64,
+    /// Ticks run since the last clock reset.
+    pub ticks_since_reset: u64,
+    /// Seconds the last `advance` took out of the accumulator.
+    taken: f32,
+}
+
+impl Default for TickClock {
+    fn default() -> Self {
+        Self {
+            accumulator: 0.0,
+            mode: TimingMode::RealTime,
+            max_catch_up: DEFAULT_MAX_CATCH_UP,
+            dropped_ticks: 0,
+            wall_elapsed: 0.0,
+            ticks_since_reset: 0,
+            taken: 0.0,
+        }
+    }
+}
+
+impl TickClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count `dt` wall-clock seconds toward `effective_tps`.
+    pub fn elapse(&mut self, dt: f32) {
+        self.wall_elapsed += dt.max(0.0) as f64;
+    }
+
+    /// Add `dt` seconds at `rate` ticks/s and take the ticks now due, at
+    /// most `max_catch_up`.
+    pub fn advance(&mut self, dt: f32, rate: f32) -> u32 {
+        let interval = 1.0 / rate;
+        self.accumulator += dt.max(0.0);
+
+        let due = (self.accumulator / interval).floor() as u64;
+        let cap = if self.max_catch_up == 0 { u64::MAX } else { self.max_catch_up as u64 };
+        let ticks = due.min(cap);
+        self.taken = ticks as f32 * interval;
+        self.accumulator -= self.taken;
+
+        // Spiral of death prevention: in real-time mode, whole intervals beyond
+        // the cap are discarded but the fractional remainder is kept so the
+        // tick phase stays accurate.
+        if self.mode == TimingMode::RealTime && due > ticks {
+            self.dropped_ticks += due - ticks;
+            self.accumulator = self.accumulator.rem_euclid(interval);
+        }
+
+        ticks as u32
+    }
+
+    /// Settle the last `advance`: its ticks ran, `refund` no longer returns
+    /// them.
+    pub fn commit(&mut self) {
+        self.taken = 0.0;
+    }
+
+    /// Give back the ticks the last `advance` took, for a frame that ran
+    /// none of them; they are owed again next frame.
+    pub fn refund(&mut self) {
+        self.accumulator += self.taken;
+        self.taken = 0.0;
+    }
+
+    /// Ticks owed but not yet run at `rate` (only non-zero in
+    /// `TimingMode::SimTime`).
+    pub fn backlog_ticks(&self, rate: f32) -> u32 {
+        (self.accumulator * rate).floor() as u32
+    }
+
+    pub fn record_ticks(&mut self, ticks: u32) {
+        self.ticks_since_reset += ticks as u64;
+    }
+
+    pub fn reset_clock(&mut self) {
+        self.wall_elapsed = 0.0;
+        self.ticks_since_reset = 0;
+    }
+
+    /// Average ticks per wall-clock second since the last clock reset.
+    pub fn effective_tps(&self) -> f64 {
+        if self.wall_elapsed > 0.0 {
+            self.ticks_since_reset as f64 / self.wall_elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn real_time_drops_ticks_past_the_cap() {
+        let mut clock = TickClock::new();
+        assert_eq!(clock.advance(0.625, 4.0), 2);
+        assert_eq!(clock.accumulator, 0.125);
+        // A stall owing 10 ticks: 3 run, the rest are dropped but the phase
+        // is kept
+        assert_eq!(clock.advance(2.5, 4.0), 3);
+        assert_eq!(clock.dropped_ticks, 7);
+        assert_eq!(clock.accumulator, 0.125);
+        assert_eq!(clock.backlog_ticks(4.0), 0);
+        clock.max_catch_up = 0;
+        assert_eq!(clock.advance(2.5, 4.0), 10);
+    }
+
+    #[test]
+    fn sim_time_carries_the_backlog() {
+        let mut clock = TickClock { mode: TimingMode::SimTime, ..TickClock::new() };
+        assert_eq!(clock.advance(2.5, 4.0), 3);
+        assert_eq!(clock.dropped_ticks, 0);
+        assert_eq!(clock.backlog_ticks(4.0), 7);
+        assert_eq!(clock.advance(0.0, 4.0), 3);
+        assert_eq!(clock.advance(0.0, 4.0), 3);
+        assert_eq!(clock.advance(0.0, 4.0), 1);
+        assert_eq!(TimingMode::from_u32(1), TimingMode::SimTime);
+        assert_eq!(TimingMode::from_u32(9), TimingMode::RealTime);
+    }
+
+    #[test]
+    fn refunded_ticks_are_owed_again() {
+        let mut clock = TickClock { mode: TimingMode::SimTime, ..TickClock::new() };
+        assert_eq!(clock.advance(0.75, 4.0), 3);
+        clock.refund();
+        assert_eq!(clock.backlog_ticks(4.0), 3);
+        assert_eq!(clock.advance(0.25, 4.0), 3);
+        // A second refund gives back nothing more
+        clock.refund();
+        clock.refund();
+        assert_eq!(clock.backlog_ticks(4.0), 4);
+        assert_eq!(clock.advance(0.0, 4.0), 3);
+        clock.commit();
+        clock.refund();
+        assert_eq!(clock.backlog_ticks(4.0), 1);
+    }
+
+    #[test]
+    fn effective_tps_measures_ticks_over_wall_time() {
+        let mut clock = TickClock::new();
+        assert_eq!(clock.effective_tps(), 0.0);
+        clock.elapse(2.0);
+        clock.elapse(-1.0);
+        clock.record_ticks(30);
+        assert_eq!(clock.effective_tps(), 15.0);
+        clock.reset_clock();
+        assert_eq!((clock.ticks_since_reset, clock.effective_tps()), (0, 0.0));
+    }
+}
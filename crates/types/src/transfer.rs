@@ -0,0 +1,225 @@
+//! User-editable transfer functions for the volume renderer. While one is
+//! set, the render texture stores a material and a scalar per cell, two
+//! cells per texel, and the ray marcher maps them through per-material color/opacity ramps, baked
+//! into a lookup table (`transfer_lut` in ray_march.wgsl).
+
+use crate::{Voxel, VoxelType};
+
+/// Entries per material in the lookup table.
+pub const TRANSFER_LUT_SIZE: usize = 256;
+/// Control points one ramp may hold.
+pub const MAX_TRANSFER_POINTS: usize = 16;
+/// Lookup table rows: voxel types 0..=7, then the three overlays.
+pub const TRANSFER_MATERIALS: usize = 11;
+/// Row of overlay mode 1 (temperature); modes 2 and 3 follow.
+pub const TRANSFER_OVERLAY_BASE: usize = 8;
+/// Cells per texel of the render texture while it holds transfer inputs:
+/// row and scalar take two bytes of an RGBA8 texel, r and g for the even
+/// cell along x, b and a for the odd one.
+pub const TRANSFER_CELLS_PER_TEXEL: u32 = 2;
+
+/// Width in texels of the transfer-input render texture of a grid.
+pub fn transfer_texture_width(grid_size: u32) -> u32 {
+    grid_size.div_ceil(TRANSFER_CELLS_PER_TEXEL)
+}
+
+/// Material names, by row, as used in `set_transfer_function` JSON.
+pub const TRANSFER_MATERIAL_NAMES: [&str; TRANSFER_MATERIALS] = [
+    "empty",
+    "wall",
+    "nutrient",
+    "energy_source",
+    "protocell",
+    "waste",
+    "heat_source",
+    "cold_source",
+    "temperature",
+    "energy",
+    "population",
+];
+
+/// A ramp stop: `color` and `opacity` at scalar `at`. Opacity is per cell
+/// of depth; the ray marcher scales it to its step as absorption.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransferPoint {
+    pub at: f32,
+    pub color: [f32; 3],
+    pub opacity: f32,
+}
+
+impl TransferPoint {
+    pub const fn new(at: f32, color: [f32; 3], opacity: f32) -> Self {
+        TransferPoint { at, color, opacity }
+    }
+
+    /// Validate stop `n` of `material`'s ramp from `[at, r, g, b, opacity]`,
+    /// each in 0..=1.
+    pub fn from_values(material: &str, n: usize, values: &[f64]) -> Result<Self, String> {
+        let [at, r, g, b, opacity] = values else {
+            return Err(format!("{material} point {n}: expected [at, r, g, b, opacity]"));
+        };
+        let unit = |what: &str, v: f64| {
+            if (0.0..=1.0).contains(&v) {
+                Ok(v as f32)
+            } else {
+                Err(format!("{material} point {n}: {what} {v} is not in 0..=1"))
+            }
+        };
+        Ok(TransferPoint {
+            at: unit("at", *at)?,
+            color: [unit("r", *r)?, unit("g", *g)?, unit("b", *b)?],
+            opacity: unit("opacity", *opacity)?,
+        })
+    }
+}
+
+/// One ramp per material row. Rows not given by the caller keep defaults
+/// close to the built-in colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferFunction {
+    ramps: [Vec<TransferPoint>; TRANSFER_MATERIALS],
+}
+
+impl Default for TransferFunction {
+    fn default() -> Self {
+        let flat = |color: [f32; 3], opacity: f32| vec![TransferPoint::new(0.0, color, opacity)];
+        TransferFunction {
+            ramps: [
+                flat([0.0; 3], 0.0),
+                flat([0.5, 0.5, 0.5], 1.0),
+                vec![TransferPoint::new(0.0, [0.1, 0.4, 0.1], 0.5), TransferPoint::new(1.0, [0.2, 0.8, 0.2], 0.8)],
+                flat([1.0, 0.95, 0.2], 1.0),
+                vec![TransferPoint::new(0.0, [0.15, 0.05, 0.2], 1.0), TransferPoint::new(1.0, [1.0, 0.45, 0.8], 1.0)],
+                vec![TransferPoint::new(0.0, [0.35, 0.2, 0.1], 0.2), TransferPoint::new(1.0, [0.35, 0.2, 0.1], 0.9)],
+                flat([1.0, 0.4, 0.1], 1.0),
+                flat([0.3, 0.6, 1.0], 1.0),
+                vec![
+                    TransferPoint::new(0.0, [0.0, 0.0, 1.0], 1.0),
+                    TransferPoint::new(0.5, [0.5, 0.2, 0.5], 0.5),
+                    TransferPoint::new(1.0, [1.0, 0.0, 0.0], 1.0),
+                ],
+                vec![TransferPoint::new(0.0, [0.0, 0.0, 0.0], 0.2), TransferPoint::new(1.0, [0.0, 1.0, 0.3], 1.0)],
+                vec![TransferPoint::new(0.0, [0.15, 0.15, 0.15], 0.3), TransferPoint::new(1.0, [1.0, 1.0, 0.0], 1.0)],
+            ],
+        }
+    }
+}
+
+impl TransferFunction {
+    /// Row of a material name; `empty` cannot be styled.
+    pub fn material_index(name: &str) -> Option<usize> {
+        TRANSFER_MATERIAL_NAMES.iter().skip(1).position(|&n| n == name).map(|i| i + 1)
+    }
+
+    /// Replace the ramp of row `material` with `points`, sorted by `at`.
+    pub fn set_ramp(&mut self, material: usize, mut points: Vec<TransferPoint>) -> Result<(), String> {
+        let name = TRANSFER_MATERIAL_NAMES.get(material).filter(|_| material > 0).ok_or("unknown material")?;
+        if points.is_empty() || points.len() > MAX_TRANSFER_POINTS {
+            return Err(format!("{name}: expected 1..={MAX_TRANSFER_POINTS} points, got {}", points.len()));
+        }
+        points.sort_by(|a, b| a.at.total_cmp(&b.at));
+        self.ramps[material] = points;
+        Ok(())
+    }
+
+    pub fn ramp(&self, material: usize) -> &[TransferPoint] {
+        &self.ramps[material]
+    }
+
+    /// RGBA of row `material` at `scalar`, linear between stops and flat
+    /// past the ends.
+    pub fn sample(&self, material: usize, scalar: f32) -> [f32; 4] {
+        let ramp = &self.ramps[material.min(TRANSFER_MATERIALS - 1)];
+        let rgba = |p: &TransferPoint| [p.color[0], p.color[1], p.color[2], p.opacity];
+        let Some(hi) = ramp.iter().position(|p| p.at >= scalar) else {
+            return ramp.last().map_or([0.0; 4], rgba);
+        };
+        if hi == 0 {
+            return rgba(&ramp[0]);
+        }
+        let (a, b) = (&ramp[hi - 1], &ramp[hi]);
+        let t = if b.at > a.at { (scalar - a.at) / (b.at - a.at) } else { 1.0 };
+        let (a, b) = (rgba(a), rgba(b));
+        [0, 1, 2, 3].map(|k| a[k] + (b[k] - a[k]) * t)
+    }
+
+    /// Lookup table: `TRANSFER_LUT_SIZE` packed RGBA8 entries (red in the
+    /// low byte, as `unpack4x8unorm`) per row, scalars spread over 0..=1.
+    pub fn lut_words(&self) -> Vec<u32> {
+        let mut words = Vec::with_capacity(TRANSFER_MATERIALS * TRANSFER_LUT_SIZE);
+        for material in 0..TRANSFER_MATERIALS {
+            for i in 0..TRANSFER_LUT_SIZE {
+                let rgba = self.sample(material, i as f32 / (TRANSFER_LUT_SIZE - 1) as f32);
+                let bytes = rgba.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                words.push(u32::from_le_bytes(bytes));
+            }
+        }
+        words
+    }
+}
+
+/// Lookup row and scalar of a voxel, as update_render_texture.wgsl writes
+/// them: energy fraction for nutrients, energy sources and protocells,
+/// remaining life for waste and normalized temperature (`temp`) for the
+/// rest. Overlays take their own rows.
+pub fn transfer_input(v: &Voxel, temp: f32, overlay: u32, max_energy: f32, waste_decay: f32) -> (usize, f32) {
+    let energy = (v.energy as f32 / max_energy).clamp(0.0, 1.0);
+    let empty = v.voxel_type == VoxelType::Empty;
+    match overlay {
+        1 => (TRANSFER_OVERLAY_BASE, temp.clamp(0.0, 1.0)),
+        2 if !empty => (TRANSFER_OVERLAY_BASE + 1, energy),
+        3 if !empty => (TRANSFER_OVERLAY_BASE + 2, if v.voxel_type == VoxelType::Protocell { 1.0 } else { 0.0 }),
+        2 | 3 => (0, 0.0),
+        _ => {
+            let scalar = match v.voxel_type {
+                VoxelType::Nutrient | VoxelType::EnergySource | VoxelType::Protocell => energy,
+                VoxelType::Waste => (1.0 - v.age as f32 / waste_decay).clamp(0.0, 1.0),
+                _ => temp.clamp(0.0, 1.0),
+            };
+            (v.voxel_type as usize, scalar)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramps_interpolate_and_bake() {
+        let mut tf = TransferFunction::default();
+        let protocell = TransferFunction::material_index("protocell").unwrap();
+        assert_eq!(protocell, VoxelType::Protocell as usize);
+        assert_eq!(TransferFunction::material_index("empty"), None);
+        tf.set_ramp(
+            protocell,
+            vec![TransferPoint::new(1.0, [1.0, 0.0, 0.0], 1.0), TransferPoint::new(0.5, [0.0, 0.0, 1.0], 0.0)],
+        )
+        .unwrap();
+        assert_eq!(tf.sample(protocell, 0.0), [0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(tf.sample(protocell, 0.75), [0.5, 0.0, 0.5, 0.5]);
+        assert_eq!(tf.sample(protocell, 2.0), [1.0, 0.0, 0.0, 1.0]);
+        assert!(tf.set_ramp(protocell, vec![]).is_err());
+        assert!(tf.set_ramp(0, vec![TransferPoint::new(0.0, [1.0; 3], 1.0)]).is_err());
+
+        let lut = tf.lut_words();
+        assert_eq!(lut.len(), TRANSFER_MATERIALS * TRANSFER_LUT_SIZE);
+        assert_eq!(lut[0], 0);
+        assert_eq!(lut[(protocell + 1) * TRANSFER_LUT_SIZE - 1], 0xFF00_00FF);
+
+        assert!(TransferPoint::from_values("wall", 0, &[0.5, 1.0, 1.0, 1.0, 1.5]).is_err());
+        assert!(TransferPoint::from_values("wall", 0, &[0.5, 1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn voxels_map_to_rows() {
+        let cell = Voxel { voxel_type: VoxelType::Protocell, energy: 50, ..Default::default() };
+        assert_eq!(transfer_input(&cell, 0.3, 0, 100.0, 10.0), (4, 0.5));
+        assert_eq!(transfer_input(&cell, 0.3, 1, 100.0, 10.0), (TRANSFER_OVERLAY_BASE, 0.3));
+        assert_eq!(transfer_input(&cell, 0.3, 3, 100.0, 10.0), (TRANSFER_OVERLAY_BASE + 2, 1.0));
+        let waste = Voxel { voxel_type: VoxelType::Waste, age: 4, ..Default::default() };
+        assert_eq!(transfer_input(&waste, 0.3, 0, 100.0, 10.0), (5, 0.6));
+        assert_eq!(transfer_input(&Voxel::default(), 0.3, 2, 100.0, 10.0), (0, 0.0));
+        assert_eq!(transfer_input(&Voxel::default(), 0.3, 1, 100.0, 10.0), (TRANSFER_OVERLAY_BASE, 0.3));
+    }
+}
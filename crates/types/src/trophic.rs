@@ -0,0 +1,177 @@
+//! Trophic accounting: energy moved along each pathway over a stats window,
+//! as counted by the resolve pass.
+
+/// Words in the GPU energy-flow counter buffer: one per `EnergyPath`, the
+/// window's tick count, the conflict counters (`ConflictCounts`), then
+/// births and deaths (`Turnover`).
+pub const ENERGY_FLOW_WORDS: usize = 12;
+/// Counter word holding the number of ticks in the window.
+pub const ENERGY_FLOW_TICKS_WORD: usize = 5;
+/// Counter word holding the offspring placed in the window.
+pub const BIRTHS_WORD: usize = 10;
+/// Counter word holding the protocells that died (starved, chose to die,
+/// or were eaten) in the window.
+pub const DEATHS_WORD: usize = 11;
+
+/// Energy pathways; the discriminant is the counter word (`FLOW_*` in
+/// resolve_execute.wgsl).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnergyPath {
+    /// Gained from adjacent energy sources.
+    Photosynthesis = 0,
+    /// Gained from adjacent nutrients.
+    NutrientConsumption = 1,
+    /// Gained by predators from their prey.
+    Predation = 2,
+    /// Spent on metabolism, movement, crowding, heat shock, toxin and secretion.
+    Metabolism = 3,
+    /// Lost with dying cells: energy held at a DIE and prey energy not
+    /// passed on to the predator.
+    Decay = 4,
+}
+
+impl EnergyPath {
+    pub const ALL: [EnergyPath; 5] = [
+        EnergyPath::Photosynthesis,
+        EnergyPath::NutrientConsumption,
+        EnergyPath::Predation,
+        EnergyPath::Metabolism,
+        EnergyPath::Decay,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EnergyPath::Photosynthesis => "photosynthesis",
+            EnergyPath::NutrientConsumption => "nutrient_consumption",
+            EnergyPath::Predation => "predation",
+            EnergyPath::Metabolism => "metabolism",
+            EnergyPath::Decay => "decay",
+        }
+    }
+
+    /// (source, target) nodes of the pathway in a flow diagram.
+    pub fn nodes(self) -> (&'static str, &'static str) {
+        match self {
+            EnergyPath::Photosynthesis => ("light", "protocells"),
+            EnergyPath::NutrientConsumption => ("nutrients", "protocells"),
+            EnergyPath::Predation => ("prey", "protocells"),
+            EnergyPath::Metabolism => ("protocells", "metabolism"),
+            EnergyPath::Decay => ("protocells", "detritus"),
+        }
+    }
+
+    /// Energy entering the living population (as opposed to leaving it).
+    pub fn is_inflow(self) -> bool {
+        matches!(self, EnergyPath::Photosynthesis | EnergyPath::NutrientConsumption | EnergyPath::Predation)
+    }
+}
+
+/// Energy per pathway summed over `ticks` ticks. Counters are u32 and wrap
+/// like the GPU atomics they mirror.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnergyFlow {
+    pub amounts: [u32; 5],
+    pub ticks: u32,
+}
+
+impl EnergyFlow {
+    /// Parse the counter buffer; missing words read as zero.
+    pub fn from_words(words: &[u32]) -> Self {
+        let word = |i: usize| words.get(i).copied().unwrap_or(0);
+        EnergyFlow {
+            amounts: std::array::from_fn(word),
+            ticks: word(ENERGY_FLOW_TICKS_WORD),
+        }
+    }
+
+    pub fn amount(&self, path: EnergyPath) -> u32 {
+        self.amounts[path as usize]
+    }
+
+    pub fn add(&mut self, path: EnergyPath, amount: u32) {
+        let slot = &mut self.amounts[path as usize];
+        *slot = slot.wrapping_add(amount);
+    }
+
+    /// Credit `absorbed` energy (the gain left after the cell's energy cap)
+    /// to photosynthesis and nutrients in proportion to the raw gains.
+    pub fn add_gain(&mut self, from_light: u32, from_nutrients: u32, absorbed: u32) {
+        let (light, nutrients) = split_gain(from_light, from_nutrients, absorbed);
+        self.add(EnergyPath::Photosynthesis, light);
+        self.add(EnergyPath::NutrientConsumption, nutrients);
+    }
+
+    pub fn inflow(&self) -> u64 {
+        EnergyPath::ALL.iter().filter(|p| p.is_inflow()).map(|&p| self.amount(p) as u64).sum()
+    }
+
+    pub fn outflow(&self) -> u64 {
+        EnergyPath::ALL.iter().filter(|p| !p.is_inflow()).map(|&p| self.amount(p) as u64).sum()
+    }
+}
+
+/// Births and deaths over a stats window, counted by the resolve pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Turnover {
+    pub births: u32,
+    pub deaths: u32,
+}
+
+impl Turnover {
+    /// Parse the energy-flow counter buffer; missing words read as zero.
+    pub fn from_words(words: &[u32]) -> Self {
+        let word = |i: usize| words.get(i).copied().unwrap_or(0);
+        Turnover { births: word(BIRTHS_WORD), deaths: word(DEATHS_WORD) }
+    }
+
+    /// Births minus deaths: the window's population change, less any
+    /// protocells placed or removed by commands.
+    pub fn net(&self) -> i64 {
+        self.births as i64 - self.deaths as i64
+    }
+}
+
+/// Split absorbed gain between light and nutrients, rounding toward
+/// nutrients. Matches `record_gain` in resolve_execute.wgsl.
+pub fn split_gain(from_light: u32, from_nutrients: u32, absorbed: u32) -> (u32, u32) {
+    let total = from_light as u64 + from_nutrients as u64;
+    if total == 0 {
+        return (0, 0);
+    }
+    let light = (absorbed as u64 * from_light as u64 / total) as u32;
+    (light, absorbed - light)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_counter_words() {
+        let flow = EnergyFlow::from_words(&[10, 20, 5, 30, 2, 7, 0, 0]);
+        assert_eq!(flow.amount(EnergyPath::Photosynthesis), 10);
+        assert_eq!(flow.amount(EnergyPath::Decay), 2);
+        assert_eq!(flow.ticks, 7);
+        assert_eq!((flow.inflow(), flow.outflow()), (35, 32));
+        assert_eq!(EnergyFlow::from_words(&[1]), EnergyFlow { amounts: [1, 0, 0, 0, 0], ticks: 0 });
+        for (i, path) in EnergyPath::ALL.iter().enumerate() {
+            assert_eq!(*path as usize, i);
+        }
+        let turnover = Turnover::from_words(&[0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 4, 9]);
+        assert_eq!((turnover, turnover.net()), (Turnover { births: 4, deaths: 9 }, -5));
+        assert_eq!(Turnover::from_words(&[1]), Turnover::default());
+    }
+
+    #[test]
+    fn capped_gain_is_split_proportionally() {
+        assert_eq!(split_gain(30, 10, 40), (30, 10));
+        assert_eq!(split_gain(30, 10, 20), (15, 5));
+        assert_eq!(split_gain(1, 2, 1), (0, 1));
+        assert_eq!(split_gain(0, 0, 0), (0, 0));
+        let mut flow = EnergyFlow::default();
+        flow.add_gain(30, 10, 20);
+        flow.add(EnergyPath::Metabolism, u32::MAX);
+        flow.add(EnergyPath::Metabolism, 2);
+        assert_eq!(flow.amounts, [15, 5, 0, 1, 0]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Spectator view snapshots (`get_view_snapshot`): a small, lossy encoding
+//! of what the volume shows, for streaming to remote viewers. The grid is
+//! downsampled into blocks of `factor`³ cells, each block becomes one color
+//! index (the `.vox` palette's, see `vox_color_index`) and the indices are
+//! run-length encoded. Unrelated to the save and dump formats.
+//!
+//! Layout, little-endian: magic `PVSN`, then u32 tick, grid size, factor
+//! and block grid side `dim`; then `(run, index)` byte pairs covering the
+//! `dim`³ blocks in grid order, runs of 1..=255.
+
+use crate::{grid_index, vox_color_index, Voxel, VoxelType};
+
+pub const VIEW_SNAPSHOT_MAGIC: &[u8; 4] = b"PVSN";
+/// Highest quality level: full resolution.
+pub const MAX_VIEW_QUALITY: u32 = 3;
+
+/// Block side for `quality` 0..=`MAX_VIEW_QUALITY`: 8, 4, 2, then 1.
+pub fn view_snapshot_factor(quality: u32) -> u32 {
+    8 >> quality.min(MAX_VIEW_QUALITY)
+}
+
+/// Encode the non-empty `cells` of a `grid_size`³ world. A block takes the
+/// index most of its non-empty cells have, so thin structures survive
+/// downsampling; it is empty only if all its cells are.
+pub fn encode_view_snapshot(
+    grid_size: u32,
+    factor: u32,
+    tick: u32,
+    cells: impl IntoIterator<Item = ((u32, u32, u32), Voxel)>,
+) -> Vec<u8> {
+    let factor = factor.max(1);
+    let dim = grid_size.div_ceil(factor);
+    let mut indexed: Vec<(u32, u8)> = cells
+        .into_iter()
+        .filter(|&((x, y, z), v)| v.voxel_type != VoxelType::Empty && x < grid_size && y < grid_size && z < grid_size)
+        .map(|((x, y, z), v)| (grid_index(x / factor, y / factor, z / factor, dim) as u32, vox_color_index(&v)))
+        .collect();
+    indexed.sort_unstable();
+
+    let mut out = VIEW_SNAPSHOT_MAGIC.to_vec();
+    for word in [tick, grid_size, factor, dim] {
+        out.extend(word.to_le_bytes());
+    }
+    let mut runs = RunLength::default();
+    let mut next_block = 0;
+    for group in indexed.chunk_by(|a, b| a.0 == b.0) {
+        let block = group[0].0;
+        runs.push_many(0, block - next_block, &mut out);
+        // Most common index; the sort makes equal indices adjacent
+        let index = group.chunk_by(|a, b| a.1 == b.1).max_by_key(|same| (same.len(), std::cmp::Reverse(same[0].1))).unwrap()[0].1;
+        runs.push_many(index, 1, &mut out);
+        next_block = block + 1;
+    }
+    runs.push_many(0, dim.pow(3) - next_block, &mut out);
+    runs.finish(&mut out);
+    out
+}
+
+/// Pending `(run, index)` pair of the encoder.
+#[derive(Default)]
+struct RunLength {
+    current: Option<(u8, u8)>,
+}
+
+impl RunLength {
+    fn push_many(&mut self, index: u8, mut count: u32, out: &mut Vec<u8>) {
+        while count > 0 {
+            match self.current {
+                Some((len, value)) if value == index && len < u8::MAX => {
+                    let take = count.min((u8::MAX - len) as u32);
+                    self.current = Some((len + take as u8, value));
+                    count -= take;
+                }
+                _ => {
+                    self.finish(out);
+                    self.current = Some((0, index));
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        if let Some((len, value)) = self.current.take() {
+            if len > 0 {
+                out.extend([len, value]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_downsample_and_run_length_encode() {
+        assert_eq!((view_snapshot_factor(0), view_snapshot_factor(3), view_snapshot_factor(9)), (8, 1, 1));
+        let wall = Voxel { voxel_type: VoxelType::Wall, ..Default::default() };
+        let waste = Voxel { voxel_type: VoxelType::Waste, ..Default::default() };
+        // 16³ at factor 4: one wall and two waste in block 0, one wall in the last block
+        let cells = [((0, 0, 0), wall), ((1, 0, 0), waste), ((2, 1, 3), waste), ((15, 15, 15), wall)];
+        let snap = encode_view_snapshot(16, 4, 42, cells);
+        assert_eq!(&snap[..4], VIEW_SNAPSHOT_MAGIC);
+        let word = |i: usize| u32::from_le_bytes(snap[4 + i * 4..8 + i * 4].try_into().unwrap());
+        assert_eq!((word(0), word(1), word(2), word(3)), (42, 16, 4, 4));
+        let waste_index = VoxelType::Waste as u8;
+        assert_eq!(&snap[20..], &[1, waste_index, 62, 0, 1, VoxelType::Wall as u8]);
+
+        // Runs split at 255 blocks
+        let empty = encode_view_snapshot(32, 4, 0, []);
+        assert_eq!(&empty[20..], &[255, 0, 255, 0, 2, 0]);
+    }
+}
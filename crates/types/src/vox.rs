@@ -0,0 +1,186 @@
+//! MagicaVoxel `.vox` export (`export_vox`) for rendering colonies in
+//! external tools: the whole grid as one model, non-empty cells colored by
+//! material and protocells by species.
+
+use crate::layout::VOXEL_STRIDE;
+use crate::{grid_coords, Voxel, VoxelType};
+
+/// Largest model side the format allows.
+pub const VOX_MAX_SIZE: u32 = 256;
+/// First palette index used for protocells; indices below are materials.
+const VOX_SPECIES_BASE: u8 = 8;
+
+/// Palette index (1..=255) of a non-empty voxel: its type for materials,
+/// one of 248 species bins for protocells.
+pub fn vox_color_index(v: &Voxel) -> u8 {
+    match v.voxel_type {
+        VoxelType::Protocell => VOX_SPECIES_BASE + (v.species_id % (256 - VOX_SPECIES_BASE as u16)) as u8,
+        t => t as u8,
+    }
+}
+
+/// RGBA palette; entry `i` is color index `i + 1`. Materials use the
+/// renderer's default colors, species bins spread hues by the golden ratio.
+pub fn vox_palette() -> [[u8; 4]; 256] {
+    let mut palette = [[0, 0, 0, 255]; 256];
+    let materials: [[f32; 3]; 7] = [
+        [0.5, 0.5, 0.5],
+        [0.2, 0.8, 0.2],
+        [1.0, 0.95, 0.2],
+        [0.8, 0.8, 0.8],
+        [0.35, 0.2, 0.1],
+        [1.0, 0.4, 0.1],
+        [0.3, 0.6, 1.0],
+    ];
+    let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    for (i, [r, g, b]) in materials.into_iter().enumerate() {
+        palette[i] = [byte(r), byte(g), byte(b), 255];
+    }
+    for bin in 0..(256 - VOX_SPECIES_BASE as usize) {
+        let [r, g, b] = hsv_to_rgb((bin as f32 * 0.618_034).fract(), 0.7, 0.9);
+        palette[VOX_SPECIES_BASE as usize - 1 + bin] = [byte(r), byte(g), byte(b), 255];
+    }
+    palette
+}
+
+pub(crate) fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let c = v * s;
+    let hp = h * 6.0;
+    let x = c * (1.0 - (hp % 2.0 - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match hp as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    [r + m, g + m, b + m]
+}
+
+/// Encode the non-empty `cells` of a `grid_size`³ world. MagicaVoxel is
+/// z-up, so the sim's y becomes the file's z.
+pub fn encode_vox(grid_size: u32, cells: impl IntoIterator<Item = ((u32, u32, u32), Voxel)>) -> Result<Vec<u8>, String> {
+    if grid_size == 0 || grid_size > VOX_MAX_SIZE {
+        return Err(format!("grid size {grid_size} does not fit a .vox model (max {VOX_MAX_SIZE})"));
+    }
+    let mut xyzi = Vec::new();
+    let mut count = 0u32;
+    for ((x, y, z), v) in cells {
+        if v.voxel_type == VoxelType::Empty {
+            continue;
+        }
+        xyzi.extend([x as u8, z as u8, y as u8, vox_color_index(&v)]);
+        count += 1;
+    }
+
+    let chunk = |out: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: u32| {
+        out.extend(id);
+        out.extend((content.len() as u32).to_le_bytes());
+        out.extend(children.to_le_bytes());
+        out.extend_from_slice(content);
+    };
+    let mut children = Vec::new();
+    let size: Vec<u8> = [grid_size; 3].iter().flat_map(|s| s.to_le_bytes()).collect();
+    chunk(&mut children, b"SIZE", &size, 0);
+    let mut body = count.to_le_bytes().to_vec();
+    body.extend(xyzi);
+    chunk(&mut children, b"XYZI", &body, 0);
+    chunk(&mut children, b"RGBA", &vox_palette().concat(), 0);
+
+    let mut out = b"VOX ".to_vec();
+    out.extend(150u32.to_le_bytes());
+    out.extend(b"MAIN");
+    out.extend(0u32.to_le_bytes());
+    out.extend((children.len() as u32).to_le_bytes());
+    out.extend(children);
+    Ok(out)
+}
+
+/// Non-empty cells of a world dump's voxel bytes (`dump_world_async`
+/// layout: packed voxels in grid order, or with `brick_table` the brick
+/// pool of 8³ slots).
+pub fn dump_cells(voxel_bytes: &[u8], grid_size: u32, brick_table: Option<&[u32]>) -> Vec<((u32, u32, u32), Voxel)> {
+    let stride = VOXEL_STRIDE as usize * 4;
+    dump_slots(grid_size, brick_table, voxel_bytes.len() / stride)
+        .into_iter()
+        .filter_map(|(pos, slot)| {
+            let mut words = [0u32; 8];
+            for (w, b) in words.iter_mut().zip(voxel_bytes[slot * stride..(slot + 1) * stride].chunks_exact(4)) {
+                *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+            }
+            let v = Voxel::unpack(words);
+            (v.voxel_type != VoxelType::Empty).then_some((pos, v))
+        })
+        .collect()
+}
+
+/// Every stored cell of a dump and its slot: all `grid_size`³ cells in grid
+/// order, or with `brick_table` those of the allocated bricks at their pool
+/// slots. Slots past `slots` are left out.
+pub fn dump_slots(grid_size: u32, brick_table: Option<&[u32]>, slots: usize) -> Vec<((u32, u32, u32), usize)> {
+    match brick_table {
+        None => (0..(grid_size as usize).pow(3).min(slots)).map(|i| (grid_coords(i, grid_size), i)).collect(),
+        Some(table) => {
+            let dim = grid_size.div_ceil(8);
+            let mut out = Vec::new();
+            for (b, &brick) in table.iter().enumerate().take((dim as usize).pow(3)) {
+                if brick == u32::MAX || (brick as usize + 1) * 512 > slots {
+                    continue;
+                }
+                let b = b as u32;
+                let (bx, by, bz) = (b % dim, b / dim % dim, b / (dim * dim));
+                for local in 0..512u32 {
+                    let pos = (bx * 8 + local % 8, by * 8 + local / 8 % 8, bz * 8 + local / 64);
+                    out.push((pos, (brick * 512 + local) as usize));
+                }
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voxel_bytes(voxels: &[Voxel]) -> Vec<u8> {
+        voxels.iter().flat_map(|v| v.pack()).flat_map(u32::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn encodes_one_model() {
+        let wall = Voxel { voxel_type: VoxelType::Wall, ..Default::default() };
+        let cell = Voxel { voxel_type: VoxelType::Protocell, species_id: 300, ..Default::default() };
+        let vox = encode_vox(16, [((1, 2, 3), wall), ((4, 5, 6), cell), ((0, 0, 0), Voxel::default())]).unwrap();
+        assert_eq!(&vox[..8], b"VOX \x96\0\0\0");
+        assert_eq!(&vox[8..12], b"MAIN");
+        let children = u32::from_le_bytes(vox[16..20].try_into().unwrap()) as usize;
+        assert_eq!(vox.len(), 20 + children);
+        // SIZE, then XYZI with y and z swapped
+        assert_eq!(&vox[20..24], b"SIZE");
+        assert_eq!(&vox[32..44], &[16, 0, 0, 0, 16, 0, 0, 0, 16, 0, 0, 0]);
+        assert_eq!(&vox[44..48], b"XYZI");
+        assert_eq!(&vox[56..68], &[2, 0, 0, 0, 1, 3, 2, 1, 4, 6, 5, 8 + 52]);
+        assert_eq!(&vox[68..72], b"RGBA");
+        assert_eq!(vox.len(), 68 + 12 + 1024);
+        assert!(encode_vox(512, []).is_err());
+    }
+
+    #[test]
+    fn reads_dense_and_sparse_dumps() {
+        let gs = 16;
+        let nutrient = Voxel { voxel_type: VoxelType::Nutrient, energy: 7, ..Default::default() };
+        let mut dense = vec![Voxel::default(); (gs as usize).pow(3)];
+        dense[crate::grid_index(9, 1, 2, gs)] = nutrient;
+        assert_eq!(dump_cells(&voxel_bytes(&dense), gs, None), vec![((9, 1, 2), nutrient)]);
+
+        // Brick (1, 0, 0) in pool slot 0
+        let mut pool = vec![Voxel::default(); 512];
+        pool[2 * 64 + 8 + 1] = nutrient;
+        let mut table = vec![u32::MAX; 8];
+        table[1] = 0;
+        assert_eq!(dump_cells(&voxel_bytes(&pool), gs, Some(&table)), vec![((9, 1, 2), nutrient)]);
+    }
+}
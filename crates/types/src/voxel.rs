@@ -1,17 +1,18 @@
 use crate::genome::Genome;
+use crate::layout;
 
-/// Voxel types matching WGSL constants.
+/// Voxel types; the values are `layout::VOXEL_*`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum VoxelType {
-    Empty = 0,
-    Wall = 1,
-    Nutrient = 2,
-    EnergySource = 3,
-    Protocell = 4,
-    Waste = 5,
-    HeatSource = 6,
-    ColdSource = 7,
+    Empty = layout::VOXEL_EMPTY as u8,
+    Wall = layout::VOXEL_WALL as u8,
+    Nutrient = layout::VOXEL_NUTRIENT as u8,
+    EnergySource = layout::VOXEL_ENERGY_SOURCE as u8,
+    Protocell = layout::VOXEL_PROTOCELL as u8,
+    Waste = layout::VOXEL_WASTE as u8,
+    HeatSource = layout::VOXEL_HEAT_SOURCE as u8,
+    ColdSource = layout::VOXEL_COLD_SOURCE as u8,
 }
 
 impl VoxelType {
@@ -30,7 +31,23 @@ impl VoxelType {
     }
 }
 
-/// A single voxel: 32 bytes = 8 × u32.
+/// Protocell flags: [0:2] growth direction (parent → this cell), [3] linked,
+/// [4] dormant (set by resolve from this tick's temperature), [5] inside the
+/// goal zone (set by resolve).
+pub const FLAG_GROWTH_DIR_MASK: u8 = layout::FLAG_GROWTH_DIR_MASK as u8;
+pub const FLAG_LINKED: u8 = layout::FLAG_LINKED as u8;
+pub const FLAG_DORMANT: u8 = layout::FLAG_DORMANT as u8;
+pub const FLAG_IN_GOAL: u8 = layout::FLAG_IN_GOAL as u8;
+/// Waste flags: [0] killed by allelopathic toxin this tick (age 0).
+pub const WASTE_FLAG_TOXIN: u8 = layout::WASTE_FLAG_TOXIN as u8;
+/// Any voxel type: [7] frozen by a FreezeRegion command.
+pub const FLAG_FROZEN: u8 = layout::FLAG_FROZEN as u8;
+
+/// Heat/cold source energy meaning full strength (a fixed-temperature
+/// boundary); lower values pull their cell only part way each tick.
+pub const THERMAL_STRENGTH_FULL: u16 = 1000;
+
+/// A single voxel: 32 bytes = 8 × u32 (see `layout`).
 ///
 /// Word 0: [0:7] type  [8:15] flags  [16:31] energy (u16)
 /// Word 1: [0:15] age (u16)  [16:31] species_id (u16)
@@ -64,35 +81,88 @@ impl Default for Voxel {
 impl Voxel {
     /// Pack voxel into 8 u32 words matching the GPU buffer layout.
     pub fn pack(&self) -> [u32; 8] {
-        let mut words = [0u32; 8];
-        // Word 0: [0:7] type | [8:15] flags | [16:31] energy
-        words[0] = (self.voxel_type as u32)
-            | ((self.flags as u32) << 8)
-            | ((self.energy as u32) << 16);
-        // Word 1: [0:15] age | [16:31] species_id
-        words[1] = (self.age as u32)
-            | ((self.species_id as u32) << 16);
-        // Words 2-5: genome
-        let gw = self.genome.to_words();
-        words[2] = gw[0];
-        words[3] = gw[1];
-        words[4] = gw[2];
-        words[5] = gw[3];
-        // Words 6-7: extra
-        words[6] = self.extra[0];
-        words[7] = self.extra[1];
+        let mut words = [0u32; layout::VOXEL_STRIDE as usize];
+        words[0] = layout::VOXEL_TYPE.put(self.voxel_type as u32)
+            | layout::VOXEL_FLAGS.put(self.flags as u32)
+            | layout::VOXEL_ENERGY.put(self.energy as u32);
+        words[1] = layout::VOXEL_AGE.put(self.age as u32) | layout::VOXEL_SPECIES.put(self.species_id as u32);
+        let genome = layout::VOXEL_GENOME_WORD as usize;
+        words[genome..genome + 4].copy_from_slice(&self.genome.to_words());
+        let extra = layout::VOXEL_EXTRA_WORD as usize;
+        words[extra..extra + 2].copy_from_slice(&self.extra);
         words
     }
 
+    /// Protocell still attached to the parent that budded it.
+    pub fn is_linked(&self) -> bool {
+        self.flags & FLAG_LINKED != 0
+    }
+
+    /// Direction (0-5) from parent to this cell; meaningful only if linked.
+    pub fn growth_dir(&self) -> u8 {
+        self.flags & FLAG_GROWTH_DIR_MASK
+    }
+
+    pub fn is_dormant(&self) -> bool {
+        self.flags & FLAG_DORMANT != 0
+    }
+
+    pub fn in_goal_zone(&self) -> bool {
+        self.flags & FLAG_IN_GOAL != 0
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.flags & FLAG_FROZEN != 0
+    }
+
+    /// Heat/cold source: `strength` per-mille in energy, `lifetime` ticks
+    /// (0 = permanent) in extra[0], ticks elapsed in age.
+    pub fn thermal_source(voxel_type: VoxelType, strength: u16, lifetime: u16) -> Self {
+        Self {
+            voxel_type,
+            energy: strength.min(THERMAL_STRENGTH_FULL),
+            extra: [layout::THERMAL_LIFETIME.put(lifetime as u32), 0],
+            ..Default::default()
+        }
+    }
+
+    /// Fraction (0..=1) of the way a heat/cold source pulls its cell toward
+    /// 1.0 / 0.0 each tick: stored strength, faded linearly over the lifetime.
+    /// Matches `thermal_strength` in temperature_diffusion.wgsl.
+    pub fn thermal_strength(&self) -> f32 {
+        let strength = self.energy.min(THERMAL_STRENGTH_FULL) as f32 / THERMAL_STRENGTH_FULL as f32;
+        let lifetime = layout::THERMAL_LIFETIME.get(self.extra[0]);
+        if lifetime == 0 {
+            return strength;
+        }
+        strength * (1.0 - self.age as f32 / lifetime as f32).clamp(0.0, 1.0)
+    }
+
+    /// Heat/cold source after one tick: aged, or gone once depleted.
+    pub fn deplete_thermal_source(&self) -> Self {
+        let lifetime = layout::THERMAL_LIFETIME.get(self.extra[0]);
+        if lifetime == 0 {
+            return *self;
+        }
+        let age = self.age.saturating_add(1);
+        if age as u32 >= lifetime {
+            Voxel::default()
+        } else {
+            Voxel { age, ..*self }
+        }
+    }
+
     /// Unpack voxel from 8 u32 words.
     pub fn unpack(words: [u32; 8]) -> Self {
-        let voxel_type = VoxelType::from_u8((words[0] & 0xFF) as u8);
-        let flags = ((words[0] >> 8) & 0xFF) as u8;
-        let energy = ((words[0] >> 16) & 0xFFFF) as u16;
-        let age = (words[1] & 0xFFFF) as u16;
-        let species_id = ((words[1] >> 16) & 0xFFFF) as u16;
-        let genome = Genome::from_words([words[2], words[3], words[4], words[5]]);
-        let extra = [words[6], words[7]];
+        let voxel_type = VoxelType::from_u8(layout::VOXEL_TYPE.get(words[0]) as u8);
+        let flags = layout::VOXEL_FLAGS.get(words[0]) as u8;
+        let energy = layout::VOXEL_ENERGY.get(words[0]) as u16;
+        let age = layout::VOXEL_AGE.get(words[1]) as u16;
+        let species_id = layout::VOXEL_SPECIES.get(words[1]) as u16;
+        let g = layout::VOXEL_GENOME_WORD as usize;
+        let genome = Genome::from_words([words[g], words[g + 1], words[g + 2], words[g + 3]]);
+        let e = layout::VOXEL_EXTRA_WORD as usize;
+        let extra = [words[e], words[e + 1]];
         Self {
             voxel_type,
             flags,
@@ -214,4 +284,27 @@ mod tests {
             assert_eq!(v2.genome.bytes[i], (i as u8) * 17, "genome byte {i} mismatch");
         }
     }
+
+    #[test]
+    fn linked_flags_roundtrip() {
+        let v = Voxel { voxel_type: VoxelType::Protocell, flags: FLAG_LINKED | 3, ..Default::default() };
+        let v2 = Voxel::unpack(v.pack());
+        assert!(v2.is_linked());
+        assert_eq!(v2.growth_dir(), 3);
+        assert!(!Voxel::default().is_linked());
+    }
+
+    #[test]
+    fn thermal_source_fades_and_depletes() {
+        let permanent = Voxel::thermal_source(VoxelType::HeatSource, THERMAL_STRENGTH_FULL, 0);
+        assert_eq!(permanent.thermal_strength(), 1.0);
+        assert_eq!(permanent.deplete_thermal_source(), permanent);
+
+        let mut v = Voxel::unpack(Voxel::thermal_source(VoxelType::ColdSource, 500, 4).pack());
+        assert_eq!(v.thermal_strength(), 0.5);
+        v = v.deplete_thermal_source().deplete_thermal_source();
+        assert_eq!(v.thermal_strength(), 0.25);
+        v = v.deplete_thermal_source().deplete_thermal_source();
+        assert_eq!(v.voxel_type, VoxelType::Empty);
+    }
 }
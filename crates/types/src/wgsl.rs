@@ -0,0 +1,270 @@
+//! WGSL preprocessing shared by every sim and render pipeline:
+//!   `#include "file.wgsl"`  splice in another file of `SHADER_SOURCES`
+//!                           (once per module)
+//!   `#define NAME`          set a mode flag for the lines that follow
+//!   `#ifdef NAME`, `#ifndef NAME`, `#else`, `#endif`
+//!                           keep or drop lines by mode flag (nestable)
+//! and `ShaderDefs` injects named constants such as WORKGROUP_SIZE ahead
+//! of the module. Directive and dropped lines become blank lines, so line
+//! numbers within each file still match its source.
+//! `set_shader_override` swaps a file's source at runtime for hot reload.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt::Display;
+
+/// Edge of the cubic workgroups of the per-cell passes; injected into every
+/// module as WORKGROUP_SIZE.
+pub const WORKGROUP_SIZE: u32 = 4;
+
+/// Mode flag: compiled for a brick pool. Without it brick_common.wgsl
+/// resolves positions densely and binds no brick table.
+pub const SPARSE_DEFINE: &str = "SPARSE";
+
+/// Every shader file, by the name `load_wgsl` and `#include` use.
+/// layout.wgsl is generated from `layout` by build.rs.
+pub const SHADER_SOURCES: &[(&str, &str)] = &[
+    ("layout.wgsl", include_str!(concat!(env!("OUT_DIR"), "/layout.wgsl"))),
+    ("common.wgsl", include_str!("../../../shaders/common.wgsl")),
+    ("brick_common.wgsl", include_str!("../../../shaders/brick_common.wgsl")),
+    ("intent_declaration.wgsl", include_str!("../../../shaders/intent_declaration.wgsl")),
+    ("resolve_execute.wgsl", include_str!("../../../shaders/resolve_execute.wgsl")),
+    ("apply_commands.wgsl", include_str!("../../../shaders/apply_commands.wgsl")),
+    ("temperature_diffusion.wgsl", include_str!("../../../shaders/temperature_diffusion.wgsl")),
+    ("stats_reduction.wgsl", include_str!("../../../shaders/stats_reduction.wgsl")),
+    ("field_stats.wgsl", include_str!("../../../shaders/field_stats.wgsl")),
+    ("census.wgsl", include_str!("../../../shaders/census.wgsl")),
+    ("genome_sample.wgsl", include_str!("../../../shaders/genome_sample.wgsl")),
+    ("migrate.wgsl", include_str!("../../../shaders/migrate.wgsl")),
+    ("temperature_range.wgsl", include_str!("../../../shaders/temperature_range.wgsl")),
+    ("update_render_texture.wgsl", include_str!("../../../shaders/update_render_texture.wgsl")),
+    ("ray_march.wgsl", include_str!("../../../shaders/ray_march.wgsl")),
+    ("wireframe.wgsl", include_str!("../../../shaders/wireframe.wgsl")),
+    ("graph.wgsl", include_str!("../../../shaders/graph.wgsl")),
+    ("minimap.wgsl", include_str!("../../../shaders/minimap.wgsl")),
+    ("marker.wgsl", include_str!("../../../shaders/marker.wgsl")),
+    ("pick.wgsl", include_str!("../../../shaders/pick.wgsl")),
+];
+
+pub fn shader_source(name: &str) -> Option<&'static str> {
+    SHADER_SOURCES.iter().find(|(n, _)| *n == name).map(|(_, s)| *s)
+}
+
+thread_local! {
+    /// Development hot reload: edited sources that replace their built-in
+    /// `SHADER_SOURCES` entry in later loads and includes.
+    static OVERRIDES: RefCell<Vec<(&'static str, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Load `source` in place of the named file from now on, or restore the
+/// built-in source with `None`. False for a name not in `SHADER_SOURCES`.
+pub fn set_shader_override(name: &str, source: Option<&str>) -> bool {
+    let Some(&(name, _)) = SHADER_SOURCES.iter().find(|(n, _)| *n == name) else {
+        return false;
+    };
+    OVERRIDES.with(|o| {
+        let mut o = o.borrow_mut();
+        o.retain(|(n, _)| *n != name);
+        if let Some(source) = source {
+            o.push((name, source.to_string()));
+        }
+    });
+    true
+}
+
+/// The override set for the named file, if any.
+pub fn shader_override(name: &str) -> Option<String> {
+    OVERRIDES.with(|o| o.borrow().iter().find(|(n, _)| *n == name).map(|(_, s)| s.clone()))
+}
+
+/// The source `load_wgsl` uses: the override if set, else the built-in.
+fn current_source(name: &str) -> Option<Cow<'static, str>> {
+    shader_override(name).map(Cow::Owned).or_else(|| shader_source(name).map(Cow::Borrowed))
+}
+
+/// Err naming the first `#include` of `source` (in any branch) that is not
+/// in `SHADER_SOURCES`; `load_wgsl` would panic on it.
+pub fn check_wgsl_includes(source: &str) -> Result<(), String> {
+    for (i, line) in source.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        if words.next() == Some("#include") {
+            let name = words.next().unwrap_or_default().trim_matches('"');
+            if shader_source(name).is_none() {
+                return Err(format!("line {}: unknown include '{name}'", i + 1));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Mode flags and injected constants for one shader module. Every module
+/// gets WORKGROUP_SIZE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderDefs {
+    defines: Vec<String>,
+    constants: Vec<(String, String)>,
+}
+
+impl Default for ShaderDefs {
+    fn default() -> Self {
+        Self { defines: Vec::new(), constants: Vec::new() }.constant("WORKGROUP_SIZE", WORKGROUP_SIZE)
+    }
+}
+
+impl ShaderDefs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn define(mut self, name: &str) -> Self {
+        if !self.defines.iter().any(|d| d == name) {
+            self.defines.push(name.to_string());
+        }
+        self
+    }
+
+    pub fn define_if(self, name: &str, enabled: bool) -> Self {
+        if enabled { self.define(name) } else { self }
+    }
+
+    /// Define each of `names`, e.g. `FeatureSet::defines`.
+    pub fn define_all<'a>(self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        names.into_iter().fold(self, Self::define)
+    }
+
+    /// Inject `const name = value;` (value as WGSL source); a later value
+    /// for the same name replaces the earlier one.
+    pub fn constant(mut self, name: &str, value: impl Display) -> Self {
+        let value = value.to_string();
+        match self.constants.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = value,
+            None => self.constants.push((name.to_string(), value)),
+        }
+        self
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.iter().any(|d| d == name)
+    }
+}
+
+/// The named file of `SHADER_SOURCES` (or its override), preprocessed with
+/// `defs`. Panics on an unknown file: shader names are fixed at compile time.
+pub fn load_wgsl(name: &str, defs: &ShaderDefs) -> String {
+    let source = current_source(name).unwrap_or_else(|| panic!("unknown shader '{name}'"));
+    preprocess_wgsl(&source, defs)
+}
+
+/// Preprocess `source` with `defs`, resolving includes from `SHADER_SOURCES`.
+pub fn preprocess_wgsl(source: &str, defs: &ShaderDefs) -> String {
+    let mut out = String::with_capacity(source.len() * 2);
+    for (name, value) in &defs.constants {
+        out.push_str(&format!("const {name} = {value};\n"));
+    }
+    let mut defines = defs.defines.clone();
+    let mut included = Vec::new();
+    expand(source, &mut defines, &mut included, &mut out);
+    out
+}
+
+fn expand(source: &str, defines: &mut Vec<String>, included: &mut Vec<&'static str>, out: &mut String) {
+    // One entry per open #ifdef: (this branch active, parent active)
+    let mut stack: Vec<(bool, bool)> = Vec::new();
+    for line in source.lines() {
+        let active = stack.last().is_none_or(|&(on, _)| on);
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some(d @ ("#ifdef" | "#ifndef")) => {
+                let name = words.next().unwrap_or_default();
+                let defined = defines.iter().any(|n| n == name);
+                stack.push((active && defined == (d == "#ifdef"), active));
+            }
+            Some("#else") => {
+                if let Some((on, parent)) = stack.pop() {
+                    stack.push((parent && !on, parent));
+                }
+            }
+            Some("#endif") => {
+                stack.pop();
+            }
+            Some("#define") if active => {
+                let name = words.next().unwrap_or_default();
+                if !defines.iter().any(|n| n == name) {
+                    defines.push(name.to_string());
+                }
+            }
+            Some("#include") if active => {
+                let name = words.next().unwrap_or_default().trim_matches('"');
+                let (name, _) = SHADER_SOURCES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .copied()
+                    .unwrap_or_else(|| panic!("unknown include '{name}'"));
+                if !included.contains(&name) {
+                    included.push(name);
+                    let file = current_source(name).unwrap_or_default();
+                    expand(&file, defines, included, out);
+                }
+            }
+            Some("#define" | "#include") => {}
+            _ if active => out.push_str(line),
+            _ => {}
+        }
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The module text after the injected constants.
+    fn body(source: &str, defs: &ShaderDefs) -> String {
+        preprocess_wgsl(source, defs).split_once('\n').unwrap().1.to_string()
+    }
+
+    #[test]
+    fn conditionals_keep_line_numbers() {
+        let src = "a\n#ifdef X\nb\n#ifndef Y\nc\n#else\nd\n#endif\n#else\ne\n#endif\nf";
+        assert_eq!(body(src, &ShaderDefs::new().define("X")), "a\n\nb\n\nc\n\n\n\n\n\n\nf\n");
+        assert_eq!(body(src, &ShaderDefs::new().define_all(["X", "Y"])), "a\n\nb\n\n\n\nd\n\n\n\n\nf\n");
+        assert_eq!(body(src, &ShaderDefs::new()), "a\n\n\n\n\n\n\n\n\ne\n\nf\n");
+        assert_eq!(body("#define Y\n#ifdef Y\ny\n#endif", &ShaderDefs::new()), "\n\ny\n\n");
+    }
+
+    #[test]
+    fn includes_once_and_injects_constants() {
+        let defs = ShaderDefs::new().constant("LANES", "8u").constant("LANES", "16u");
+        let out = preprocess_wgsl("#include \"common.wgsl\"\n#include \"common.wgsl\"\nx", &defs);
+        assert!(out.starts_with("const WORKGROUP_SIZE = 4;\nconst LANES = 16u;\n"));
+        assert_eq!(out.matches("fn grid_index(").count(), 1);
+        assert!(out.ends_with("\n\nx\n"));
+        // Includes inside a dropped branch are not followed
+        assert!(!body("#ifdef SPARSE\n#include \"common.wgsl\"\n#endif", &ShaderDefs::new()).contains("fn"));
+    }
+
+    #[test]
+    fn overrides_replace_loads_and_includes() {
+        let defs = ShaderDefs::new();
+        assert!(!set_shader_override("missing.wgsl", Some("x")));
+        assert!(set_shader_override("common.wgsl", Some("fn edited() {}")));
+        assert!(load_wgsl("common.wgsl", &defs).contains("fn edited()"));
+        assert!(load_wgsl("census.wgsl", &defs.clone().define(SPARSE_DEFINE)).contains("fn edited()"));
+        assert!(set_shader_override("common.wgsl", None));
+        assert_eq!(shader_override("common.wgsl"), None);
+        assert!(load_wgsl("common.wgsl", &defs).contains("fn grid_index("));
+        assert!(check_wgsl_includes("#ifdef X\n#include \"common.wgsl\"\n#endif").is_ok());
+        assert_eq!(check_wgsl_includes("\n#include \"nope.wgsl\"").unwrap_err(), "line 2: unknown include 'nope.wgsl'");
+    }
+
+    #[test]
+    fn every_shader_resolves() {
+        for sparse in [false, true] {
+            let defs = ShaderDefs::new().define_if(SPARSE_DEFINE, sparse);
+            for (name, _) in SHADER_SOURCES {
+                let out = load_wgsl(name, &defs);
+                assert!(!out.lines().any(|l| l.trim_start().starts_with('#')), "{name}");
+            }
+        }
+    }
+}
@@ -2,47 +2,22 @@
 // apply_commands.wgsl — M4: Apply player commands.
 // Modifies the current READ buffer in-place.
 // Runs BEFORE intent_declaration each tick.
-// Prepended with common.wgsl at pipeline creation.
+// Includes common.wgsl and brick_common.wgsl.
 //
 // Bind group 0:
 //   [0] voxel_buf:   storage<array<u32>, read_write>  — current read buffer
 //   [1] command_buf: storage<array<u32>, read>         — command count + data
 //   [2] params:      uniform<SimParams>
+//   [14] tick:       uniform<TickParams>, dynamic offset (this tick's slot)
 // ============================================================
 
-struct SimParams {
-    grid_size: f32,
-    tick_count: f32,
-    dt: f32,
-    nutrient_spawn_rate: f32,
-    waste_decay_ticks: f32,
-    nutrient_recycle_rate: f32,
-    movement_energy_cost: f32,
-    base_ambient_temp: f32,
-    metabolic_cost_base: f32,
-    replication_energy_min: f32,
-    energy_from_nutrient: f32,
-    energy_from_source: f32,
-    diffusion_rate: f32,
-    temp_sensitivity: f32,
-    predation_energy_fraction: f32,
-    max_energy: f32,
-    overlay_mode: f32,
-    sparse_mode: f32,
-    brick_grid_dim: f32,
-    max_bricks: f32,
-};
+#include "common.wgsl"
+#include "brick_common.wgsl"
 
 @group(0) @binding(0) var<storage, read_write> voxel_buf: array<u32>;
 @group(0) @binding(1) var<storage, read> command_buf: array<u32>;
 @group(0) @binding(2) var<uniform> params: SimParams;
-
-// Command types
-const CMD_NOOP: u32 = 0u;
-const CMD_PLACE_VOXEL: u32 = 1u;
-const CMD_REMOVE_VOXEL: u32 = 2u;
-const CMD_SEED_PROTOCELLS: u32 = 3u;
-const CMD_APPLY_TOXIN: u32 = 4u;
+@group(0) @binding(14) var<uniform> tick: TickParams;
 
 fn write_voxel_inplace(idx: u32, w0: u32, w1: u32, w2: u32, w3: u32, w4: u32, w5: u32, w6: u32, w7: u32) {
     let base = idx * VOXEL_STRIDE;
@@ -58,17 +33,17 @@ fn write_voxel_inplace(idx: u32, w0: u32, w1: u32, w2: u32, w3: u32, w4: u32, w5
 
 fn read_voxel_type_rw(idx: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return voxel_buf[base] & 0xFFu;
+    return (voxel_buf[base] >> VOXEL_TYPE_SHIFT) & VOXEL_TYPE_MASK;
 }
 
-@compute @workgroup_size(4, 4, 4)
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn apply_commands_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let gs = u32(params.grid_size);
     if gid.x >= gs || gid.y >= gs || gid.z >= gs {
         return;
     }
 
-    let command_count = min(command_buf[0], 64u);
+    let command_count = min(command_buf[0], MAX_COMMANDS_PER_TICK);
     if command_count == 0u {
         return;
     }
@@ -83,79 +58,118 @@ fn apply_commands_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let my_pos = vec3<i32>(gid);
 
     for (var c: u32 = 0u; c < command_count; c++) {
-        let cmd_base = 4u + c * 16u;
+        let cmd_base = COMMAND_HEADER_WORDS + c * COMMAND_WORDS;
         let cmd_type = command_buf[cmd_base];
         let cmd_x = command_buf[cmd_base + 1u];
         let cmd_y = command_buf[cmd_base + 2u];
         let cmd_z = command_buf[cmd_base + 3u];
         let cmd_radius = command_buf[cmd_base + 4u];
         let cmd_param_0 = command_buf[cmd_base + 5u];
+        let cmd_param_1 = command_buf[cmd_base + 6u];
 
         if cmd_type == CMD_NOOP {
             continue;
         }
 
-        // Chebyshev distance for cube-shaped brush
-        let cmd_pos = vec3<i32>(i32(cmd_x), i32(cmd_y), i32(cmd_z));
-        let diff = abs(my_pos - cmd_pos);
-        let dist = max(diff.x, max(diff.y, diff.z));
-        if dist > i32(cmd_radius) {
+        if cmd_type == CMD_FILL_REGION || cmd_type == CMD_FREEZE_REGION {
+            // Axis-aligned box: (x, y, z) min corner, param_1 packed max corner
+            let lo = vec3<u32>(cmd_x, cmd_y, cmd_z);
+            let hi = vec3<u32>(cmd_param_1 & 0x3FFu, (cmd_param_1 >> 10u) & 0x3FFu, (cmd_param_1 >> 20u) & 0x3FFu);
+            if any(gid < lo) || any(gid > hi) {
+                continue;
+            }
+        } else {
+            // Chebyshev distance for cube-shaped brush
+            let cmd_pos = vec3<i32>(i32(cmd_x), i32(cmd_y), i32(cmd_z));
+            let diff = abs(my_pos - cmd_pos);
+            let dist = max(diff.x, max(diff.y, diff.z));
+            if dist > i32(cmd_radius) {
+                continue;
+            }
+        }
+
+        if cmd_type == CMD_FREEZE_REGION {
+            // param_0: 1 = freeze, 0 = thaw; only the flag bit changes
+            let base = idx * VOXEL_STRIDE;
+            let frozen_bit = FLAG_FROZEN << VOXEL_FLAGS_SHIFT;
+            voxel_buf[base] = select(voxel_buf[base] & ~frozen_bit, voxel_buf[base] | frozen_bit, cmd_param_0 != 0u);
+            continue;
+        }
+        // Frozen cells ignore every other command
+        if ((voxel_buf[idx * VOXEL_STRIDE] >> VOXEL_FLAGS_SHIFT) & FLAG_FROZEN) != 0u {
             continue;
         }
 
         let current_type = read_voxel_type_rw(idx);
 
         switch cmd_type {
-            case 1u: { // CMD_PLACE_VOXEL
+            case CMD_PLACE_VOXEL: {
                 let vtype = cmd_param_0;
                 var energy: u32 = 0u;
+                var lifetime: u32 = 0u;
                 if vtype == VOXEL_ENERGY_SOURCE {
                     energy = 500u;
                 } else if vtype == VOXEL_NUTRIENT {
                     energy = u32(params.energy_from_nutrient);
                 } else if vtype == VOXEL_HEAT_SOURCE || vtype == VOXEL_COLD_SOURCE {
-                    energy = 1000u;
+                    // param_1: strength ‰ (0 = full) | lifetime ticks << 16 (0 = permanent)
+                    energy = cmd_param_1 & 0xFFFFu;
+                    energy = select(min(energy, 1000u), 1000u, energy == 0u);
+                    lifetime = cmd_param_1 >> 16u;
                 }
                 write_voxel_inplace(idx,
-                    (vtype & 0xFFu) | ((energy & 0xFFFFu) << 16u),
-                    0u, 0u, 0u, 0u, 0u, 0u, 0u);
+                    voxel_pack_word0(vtype, 0u, energy),
+                    0u, 0u, 0u, 0u, 0u, lifetime, 0u);
             }
-            case 2u: { // CMD_REMOVE_VOXEL
+            case CMD_REMOVE_VOXEL: {
                 write_voxel_inplace(idx, 0u, 0u, 0u, 0u, 0u, 0u, 0u, 0u);
             }
-            case 3u: { // CMD_SEED_PROTOCELLS
+            case CMD_SEED_PROTOCELLS: {
                 if current_type == VOXEL_EMPTY {
                     // PRNG for random genome generation
-                    var rng = prng_seed(idx, u32(params.tick_count), gs, 0x3u);
+                    var rng = prng_seed(idx, tick.tick_count, gs, 0x3u);
                     let g0 = pcg_next(&rng);
                     let g1 = pcg_next(&rng);
                     let g2 = pcg_next(&rng);
                     let g3 = pcg_next(&rng);
                     let species_id = compute_species_id(g0, g1, g2, g3);
-                    let energy = min(cmd_param_0, 0xFFFFu);
+                    let energy = min(cmd_param_0, VOXEL_ENERGY_MASK);
                     write_voxel_inplace(idx,
-                        (VOXEL_PROTOCELL & 0xFFu) | ((energy & 0xFFFFu) << 16u),
-                        (species_id & 0xFFFFu) << 16u,
+                        voxel_pack_word0(VOXEL_PROTOCELL, 0u, energy),
+                        voxel_pack_word1(0u, species_id),
                         g0, g1, g2, g3, 0u, 0u);
                 }
             }
-            case 4u: { // CMD_APPLY_TOXIN
+            case CMD_APPLY_TOXIN: {
                 if current_type == VOXEL_PROTOCELL {
                     let base = idx * VOXEL_STRIDE;
-                    let g0 = voxel_buf[base + 2u];
-                    let g1 = voxel_buf[base + 3u];
-                    let g2 = voxel_buf[base + 4u];
-                    let g3 = voxel_buf[base + 5u];
+                    let g0 = voxel_buf[base + VOXEL_GENOME_WORD];
+                    let g1 = voxel_buf[base + VOXEL_GENOME_WORD + 1u];
+                    let g2 = voxel_buf[base + VOXEL_GENOME_WORD + 2u];
+                    let g3 = voxel_buf[base + VOXEL_GENOME_WORD + 3u];
                     let toxin_resistance = genome_get_byte_from_words(g0, g1, g2, g3, 6u);
                     if toxin_resistance < cmd_param_0 {
-                        let species_id = (voxel_buf[base + 1u] >> 16u) & 0xFFFFu;
+                        let species_id = (voxel_buf[base + 1u] >> VOXEL_SPECIES_SHIFT) & VOXEL_SPECIES_MASK;
                         write_voxel_inplace(idx,
-                            VOXEL_WASTE & 0xFFu,
-                            (species_id & 0xFFFFu) << 16u,
+                            voxel_pack_word0(VOXEL_WASTE, 0u, 0u),
+                            voxel_pack_word1(0u, species_id),
                             0u, 0u, 0u, 0u, 0u, 0u);
                     }
                 }
             }
+            case CMD_FILL_REGION: {
+                // param_0: voxel_type | density << 8 (255 = every cell) | FILL_OVERWRITE
+                let density = (cmd_param_0 >> 8u) & 0xFFu;
+                let roll = prng_seed(idx, tick.tick_count, gs, 0x5u) & 0xFFu;
+                let overwrite = (cmd_param_0 & FILL_OVERWRITE) != 0u;
+                if (current_type == VOXEL_EMPTY || overwrite) && (roll < density || density == 255u) {
+                    let vtype = cmd_param_0 & 0xFFu;
+                    let energy = select(0u, u32(params.energy_from_nutrient), vtype == VOXEL_NUTRIENT);
+                    write_voxel_inplace(idx,
+                        voxel_pack_word0(vtype, 0u, energy),
+                        0u, 0u, 0u, 0u, 0u, 0u, 0u);
+                }
+            }
             default: {
                 // Unknown command, skip
             }
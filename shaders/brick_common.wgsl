@@ -1,11 +1,17 @@
 // ============================================================
 // brick_common.wgsl — Brick-aware indexing for sparse 256³ mode.
-// Prepended between common.wgsl and each shader in sparse pipelines.
+// Included after common.wgsl by every per-cell shader. Without SPARSE the
+// lookups resolve densely and no brick table is bound, so dense pipelines
+// compile the same runtime `params.sparse_mode` branches.
 // NO entry points.
 // ============================================================
 
+#ifdef SPARSE
+
 // Brick table: maps brick coordinates to pool slot indices.
-// 0xFFFFFFFF = unallocated brick.
+// 0xFFFFFFFF = unallocated brick. The table is followed by one tick
+// interval per pool slot (sparse LOD, types::SimLod), then the number of
+// allocated bricks and their pool slots (read by stats_reduction.wgsl).
 @group(0) @binding(10) var<storage, read> brick_table: array<u32>;
 
 // Brick coordinate to table index.
@@ -39,3 +45,24 @@ fn sparse_neighbor(pos: vec3<u32>, dir: u32, gs: u32) -> u32 {
     }
     return sparse_voxel_index(vec3<u32>(np), gs);
 }
+
+// Whether the brick holding pool index idx sits out tick tick_count: it
+// runs only on multiples of its interval (types::brick_ticks).
+fn brick_skips_tick(idx: u32, tick_count: u32) -> bool {
+    let bgd = u32(params.brick_grid_dim);
+    let interval = brick_table[bgd * bgd * bgd + idx / 512u];
+    return interval > 1u && tick_count % interval != 0u;
+}
+#else
+fn sparse_voxel_index(pos: vec3<u32>, gs: u32) -> u32 {
+    return grid_index(pos, gs);
+}
+
+fn sparse_neighbor(pos: vec3<u32>, dir: u32, gs: u32) -> u32 {
+    return neighbor_in_direction(pos, dir, gs);
+}
+
+fn brick_skips_tick(idx: u32, tick_count: u32) -> bool {
+    return false;
+}
+#endif
@@ -0,0 +1,97 @@
+// ============================================================
+// census.wgsl — Locate every voxel matching a census predicate.
+// Includes common.wgsl and brick_common.wgsl. The query uniform
+// is named `params` because brick_common reads `params.brick_grid_dim`.
+//
+// One invocation per position in the query box; voxels passing every condition
+// (type, species, energy range, age range, flag bits) are appended to
+// the result buffer with an atomic counter.
+//
+// Bind group 0:
+//   [0] voxel_buf: storage<array<u32>, read>
+//   [1] result_buf: storage<array<atomic<u32>>, read_write>
+//       [0] total matches, [1..=max_results] packed x | y<<10 | z<<20
+//   [2] params: uniform<CensusParams>
+//   [10] brick_table (sparse; a 1-word placeholder in dense mode)
+// ============================================================
+
+#include "common.wgsl"
+#include "brick_common.wgsl"
+
+const CENSUS_ANY_TYPE: u32 = 0xFFFFFFFFu;
+
+struct CensusParams {
+    grid_size: u32,
+    sparse_mode: u32,
+    brick_grid_dim: u32,
+    max_results: u32,        // 0 = count only
+    voxel_type: u32,         // CENSUS_ANY_TYPE = any
+    species_id: u32,         // 0 = any
+    energy_min: u32,
+    energy_max: u32,
+    age_min: u32,
+    age_max: u32,
+    flags_mask: u32,         // match when flags & mask == value
+    flags_value: u32,
+    region_min: u32,         // inclusive box, x | y<<10 | z<<20
+    region_max: u32,
+    _pad0: u32,
+    _pad1: u32,
+};
+
+fn unpack_coord(packed: u32) -> vec3<u32> {
+    return vec3<u32>(packed & 0x3FFu, (packed >> 10u) & 0x3FFu, (packed >> 20u) & 0x3FFu);
+}
+
+@group(0) @binding(0) var<storage, read> voxel_buf: array<u32>;
+@group(0) @binding(1) var<storage, read_write> result_buf: array<atomic<u32>>;
+@group(0) @binding(2) var<uniform> params: CensusParams;
+
+// Unallocated sparse bricks are skipped, so empty-voxel counts cover
+// allocated bricks only.
+fn census_matches(idx: u32) -> bool {
+    if params.voxel_type != CENSUS_ANY_TYPE && voxel_get_type(&voxel_buf, idx) != params.voxel_type {
+        return false;
+    }
+    if params.species_id != 0u && voxel_get_species_id(&voxel_buf, idx) != params.species_id {
+        return false;
+    }
+    let energy = voxel_get_energy(&voxel_buf, idx);
+    if energy < params.energy_min || energy > params.energy_max {
+        return false;
+    }
+    let age = voxel_get_age(&voxel_buf, idx);
+    if age < params.age_min || age > params.age_max {
+        return false;
+    }
+    return (voxel_get_flags(&voxel_buf, idx) & params.flags_mask) == params.flags_value;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn census_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let gs = params.grid_size;
+    let pos = unpack_coord(params.region_min) + gid;
+    let hi = unpack_coord(params.region_max);
+    if pos.x > hi.x || pos.y > hi.y || pos.z > hi.z {
+        return;
+    }
+
+    var idx: u32;
+    if params.sparse_mode != 0u {
+        idx = sparse_voxel_index(pos, gs);
+        if idx == 0xFFFFFFFFu {
+            return;
+        }
+    } else {
+        idx = grid_index(pos, gs);
+    }
+
+    if !census_matches(idx) {
+        return;
+    }
+
+    let slot = atomicAdd(&result_buf[0], 1u);
+    if slot < params.max_results {
+        atomicStore(&result_buf[1u + slot], pos.x | (pos.y << 10u) | (pos.z << 20u));
+    }
+}
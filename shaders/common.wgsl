@@ -1,20 +1,17 @@
 // ============================================================
 // common.wgsl — Shared constants, accessors, and helpers.
-// Prepended to all compute shaders. NO entry points.
+// Included by all compute shaders. NO entry points.
+// Includes layout.wgsl: voxel, intent and command layouts, voxel
+// types, flags, actions and struct SimParams, generated from
+// types::layout. SimParams is SIM_PARAMS_VERSION: fixed sections
+// (frame, cells, resources, fields, world) with spare words, so a new
+// parameter only renames a spare word.
 // ============================================================
 
-// Voxel type constants
-const VOXEL_EMPTY: u32 = 0u;
-const VOXEL_WALL: u32 = 1u;
-const VOXEL_NUTRIENT: u32 = 2u;
-const VOXEL_ENERGY_SOURCE: u32 = 3u;
-const VOXEL_PROTOCELL: u32 = 4u;
-const VOXEL_WASTE: u32 = 5u;
-const VOXEL_HEAT_SOURCE: u32 = 6u;
-const VOXEL_COLD_SOURCE: u32 = 7u;
+#include "layout.wgsl"
 
-// Each voxel is 8 × u32 = 32 bytes
-const VOXEL_STRIDE: u32 = 8u;
+// Adhesion gene (byte 12) at or above which offspring stay linked
+const ADHESION_LINK_MIN: u32 = 128u;
 
 // Von Neumann neighborhood (6 face-adjacent offsets)
 const NEIGHBORS = array<vec3<i32>, 6>(
@@ -39,45 +36,83 @@ fn grid_coords(index: u32, grid_size: u32) -> vec3<u32> {
     return vec3<u32>(x, y, z);
 }
 
+// Box of grid positions a pass is dispatched over: global_invocation_id
+// is relative to `origin`, and positions at or past `end` exit.
+struct DispatchRegion {
+    origin: vec4<u32>,
+    end: vec4<u32>,
+};
+
 // ---- Voxel accessors (array<u32>, NOT struct) ----
 // Word 0: [0:7] type  [8:15] flags  [16:31] energy (u16)
 // Word 1: [0:15] age (u16)  [16:31] species_id (u16)
 // Words 2-5: genome (4 × u32)
 // Words 6-7: extra
+// Protocell flags: [0:2] growth direction (parent → cell)  [3] linked  [4] dormant
 
 fn voxel_get_type(buf: ptr<storage, array<u32>, read>, idx: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return (*buf)[base] & 0xFFu;
+    return ((*buf)[base] >> VOXEL_TYPE_SHIFT) & VOXEL_TYPE_MASK;
 }
 
 fn voxel_get_flags(buf: ptr<storage, array<u32>, read>, idx: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return ((*buf)[base] >> 8u) & 0xFFu;
+    return ((*buf)[base] >> VOXEL_FLAGS_SHIFT) & VOXEL_FLAGS_MASK;
+}
+
+// FreezeRegion marks cells of any type, empty included, with FLAG_FROZEN
+fn voxel_is_frozen(buf: ptr<storage, array<u32>, read>, idx: u32) -> bool {
+    return (voxel_get_flags(buf, idx) & FLAG_FROZEN) != 0u;
+}
+
+// Whether pos lies in the time-dilation zone (empty box when disabled).
+fn in_dilation_zone(p: SimParams, pos: vec3<u32>) -> bool {
+    let v = vec3<f32>(pos);
+    let lo = vec3<f32>(p.dilation_min_x, p.dilation_min_y, p.dilation_min_z);
+    let hi = vec3<f32>(p.dilation_max_x, p.dilation_max_y, p.dilation_max_z);
+    return all(v >= lo) && all(v <= hi);
+}
+
+// Whether the cell at pos sits out tick tick_count: inside the dilation
+// zone only multiples of dilation_factor run (SimParams::dilation_skips_tick).
+fn dilation_skips_tick(p: SimParams, pos: vec3<u32>, tick_count: u32) -> bool {
+    let interval = u32(max(p.dilation_factor, 1.0));
+    return interval > 1u && tick_count % interval != 0u && in_dilation_zone(p, pos);
 }
 
 fn voxel_get_energy(buf: ptr<storage, array<u32>, read>, idx: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return ((*buf)[base] >> 16u) & 0xFFFFu;
+    return ((*buf)[base] >> VOXEL_ENERGY_SHIFT) & VOXEL_ENERGY_MASK;
 }
 
 fn voxel_get_age(buf: ptr<storage, array<u32>, read>, idx: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return (*buf)[base + 1u] & 0xFFFFu;
+    return ((*buf)[base + 1u] >> VOXEL_AGE_SHIFT) & VOXEL_AGE_MASK;
 }
 
 fn voxel_get_species_id(buf: ptr<storage, array<u32>, read>, idx: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return ((*buf)[base + 1u] >> 16u) & 0xFFFFu;
+    return ((*buf)[base + 1u] >> VOXEL_SPECIES_SHIFT) & VOXEL_SPECIES_MASK;
 }
 
 fn voxel_get_genome_word(buf: ptr<storage, array<u32>, read>, idx: u32, word: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return (*buf)[base + 2u + word];
+    return (*buf)[base + VOXEL_GENOME_WORD + word];
 }
 
 fn voxel_get_extra(buf: ptr<storage, array<u32>, read>, idx: u32, word: u32) -> u32 {
     let base = idx * VOXEL_STRIDE;
-    return (*buf)[base + 6u + word];
+    return (*buf)[base + VOXEL_EXTRA_WORD + word];
+}
+
+fn voxel_pack_word0(vtype: u32, flags: u32, energy: u32) -> u32 {
+    return ((vtype & VOXEL_TYPE_MASK) << VOXEL_TYPE_SHIFT)
+        | ((flags & VOXEL_FLAGS_MASK) << VOXEL_FLAGS_SHIFT)
+        | ((energy & VOXEL_ENERGY_MASK) << VOXEL_ENERGY_SHIFT);
+}
+
+fn voxel_pack_word1(age: u32, species_id: u32) -> u32 {
+    return ((age & VOXEL_AGE_MASK) << VOXEL_AGE_SHIFT) | ((species_id & VOXEL_SPECIES_MASK) << VOXEL_SPECIES_SHIFT);
 }
 
 // ---- PCG-RXS-M-XS-32 PRNG ----
@@ -117,33 +152,25 @@ fn genome_get_byte_from_words(g0: u32, g1: u32, g2: u32, g3: u32, byte_index: u3
     return (words[word_i] >> shift) & 0xFFu;
 }
 
-// ---- Intent constants ----
-
-const ACTION_NO_ACTION: u32 = 0u;
-const ACTION_DIE: u32 = 1u;
-const ACTION_PREDATE: u32 = 2u;
-const ACTION_REPLICATE: u32 = 3u;
-const ACTION_MOVE: u32 = 4u;
-const ACTION_IDLE: u32 = 5u;
-const DIR_SELF: u32 = 6u;
-
 // ---- Intent encode/decode ----
 // Intent word (u32): [0:2] direction, [3:5] action, [6:31] bid
 
 fn intent_encode(action: u32, direction: u32, bid: u32) -> u32 {
-    return (direction & 7u) | ((action & 7u) << 3u) | ((bid & 0x03FFFFFFu) << 6u);
+    return ((direction & INTENT_DIRECTION_MASK) << INTENT_DIRECTION_SHIFT)
+        | ((action & INTENT_ACTION_MASK) << INTENT_ACTION_SHIFT)
+        | ((bid & INTENT_BID_MASK) << INTENT_BID_SHIFT);
 }
 
 fn intent_get_direction(intent: u32) -> u32 {
-    return intent & 7u;
+    return (intent >> INTENT_DIRECTION_SHIFT) & INTENT_DIRECTION_MASK;
 }
 
 fn intent_get_action(intent: u32) -> u32 {
-    return (intent >> 3u) & 7u;
+    return (intent >> INTENT_ACTION_SHIFT) & INTENT_ACTION_MASK;
 }
 
 fn intent_get_bid(intent: u32) -> u32 {
-    return (intent >> 6u) & 0x03FFFFFFu;
+    return (intent >> INTENT_BID_SHIFT) & INTENT_BID_MASK;
 }
 
 // ---- Species ID hash (matches Genome::species_id() in Rust) ----
@@ -197,6 +224,116 @@ fn hsv_to_rgb(h: f32, s: f32, v: f32) -> vec3<f32> {
     return rgb + vec3<f32>(m, m, m);
 }
 
+// ---- Float ordering keys (matches types::float_key) ----
+// atomicMax over keys finds the largest float; over their complements, the
+// smallest.
+
+fn float_key(v: f32) -> u32 {
+    let bits = bitcast<u32>(v);
+    return select(bits | 0x80000000u, ~bits, (bits >> 31u) == 1u);
+}
+
+fn float_from_key(key: u32) -> f32 {
+    return bitcast<f32>(select(~key, key & 0x7FFFFFFFu, (key >> 31u) == 1u));
+}
+
 fn compute_temp_modifier(local_temp: f32, sensitivity: f32) -> f32 {
     return max(1.0 + sensitivity * (local_temp - 0.5), 0.1);
 }
+
+// Cold dormancy: below dormancy_temp, lowered by cold tolerance (gene 13
+// high nibble, scaled to 0..255 — see genome_cold_tolerance).
+fn genome_cold_tolerance(byte13: u32) -> u32 {
+    return (byte13 >> 4u) * 17u;
+}
+
+// Allelopathic toxin secretion: gene 13 low nibble, scaled to 0..255.
+fn genome_toxin_secretion(byte13: u32) -> u32 {
+    return (byte13 & 0x0Fu) * 17u;
+}
+
+fn is_cold_dormant(local_temp: f32, cold_tolerance: u32, dormancy_temp: f32) -> bool {
+    return local_temp < dormancy_temp * (1.0 - f32(cold_tolerance) / 255.0);
+}
+
+// ---- Nutrient regrowth patterns ----
+// Spawn weight at pos: 0 = uniform (1.0), 1 = patchy (smooth value noise on a
+// 16-voxel lattice, barren between patches), 2 = front (band sweeping along +x
+// at front_speed voxels/tick, wrapping at the grid edge).
+
+const NUTRIENT_PATCH_CELL: f32 = 16.0;
+
+fn lattice_value(c: vec3<u32>) -> f32 {
+    let h = pcg_hash(c.x ^ (c.y * 0x9E3779B9u) ^ (c.z * 0x85EBCA6Bu) ^ 0x4E555452u);
+    return f32(h) / 4294967295.0;
+}
+
+fn patch_noise(pos: vec3<u32>) -> f32 {
+    let p = vec3<f32>(pos) / NUTRIENT_PATCH_CELL;
+    let c = vec3<u32>(floor(p));
+    let f = fract(p);
+    let t = f * f * (3.0 - 2.0 * f);
+    let x00 = mix(lattice_value(c), lattice_value(c + vec3<u32>(1u, 0u, 0u)), t.x);
+    let x10 = mix(lattice_value(c + vec3<u32>(0u, 1u, 0u)), lattice_value(c + vec3<u32>(1u, 1u, 0u)), t.x);
+    let x01 = mix(lattice_value(c + vec3<u32>(0u, 0u, 1u)), lattice_value(c + vec3<u32>(1u, 0u, 1u)), t.x);
+    let x11 = mix(lattice_value(c + vec3<u32>(0u, 1u, 1u)), lattice_value(c + vec3<u32>(1u, 1u, 1u)), t.x);
+    return mix(mix(x00, x10, t.y), mix(x01, x11, t.y), t.z);
+}
+
+fn nutrient_pattern_weight(pos: vec3<u32>, gs: u32, tick: u32, pattern: u32, front_speed: f32) -> f32 {
+    if pattern == 1u {
+        return clamp((patch_noise(pos) - 0.5) * 4.0, 0.0, 2.0);
+    }
+    if pattern == 2u {
+        let g = f32(gs);
+        let front = fract(f32(tick) * front_speed / g) * g;
+        let d = abs(f32(pos.x) - front);
+        let dist = min(d, g - d);
+        let width = max(g / 16.0, 1.0);
+        return max(1.0 - dist / width, 0.0) * 4.0;
+    }
+    return 1.0;
+}
+
+// ---- Pheromone field ----
+// Coarse dense grid of pheromone_dim(gs)³ cells (PHEROMONE_CELL voxels per
+// edge) in both dense and sparse mode. One u32 per cell:
+// [0:7] species bucket (0 = no scent)  [8:15] toxin  [16:31] intensity (u16)
+
+const PHEROMONE_CELL: u32 = 4u;
+const PHEROMONE_MAX: u32 = 0xFFFFu;
+
+fn pheromone_dim(gs: u32) -> u32 {
+    return (gs + PHEROMONE_CELL - 1u) / PHEROMONE_CELL;
+}
+
+fn pheromone_index(pos: vec3<u32>, gs: u32) -> u32 {
+    let d = pheromone_dim(gs);
+    let c = pos / PHEROMONE_CELL;
+    return c.z * d * d + c.y * d + c.x;
+}
+
+fn pheromone_bucket(species_id: u32) -> u32 {
+    return 1u + species_id % 255u;
+}
+
+fn pheromone_pack(bucket: u32, intensity: u32) -> u32 {
+    return (bucket & 0xFFu) | (min(intensity, PHEROMONE_MAX) << 16u);
+}
+
+fn pheromone_get_bucket(word: u32) -> u32 {
+    return word & 0xFFu;
+}
+
+fn pheromone_get_intensity(word: u32) -> u32 {
+    return word >> 16u;
+}
+
+fn pheromone_get_toxin(word: u32) -> u32 {
+    return (word >> 8u) & 0xFFu;
+}
+
+// 0..1 factor as 16-bit fixed point (1.0 = 65536)
+fn pheromone_q16(f: f32) -> u32 {
+    return u32(clamp(f, 0.0, 1.0) * 65536.0);
+}
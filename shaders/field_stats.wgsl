@@ -0,0 +1,135 @@
+// ============================================================
+// field_stats.wgsl — Min/max/sum of the scalar fields for the stats
+// readback: temperature, pheromone intensity and protocell energy.
+// Runs after stats_reduction on stats ticks, over the tick's output.
+// Includes common.wgsl and brick_common.wgsl. Temperature is
+// measured only with TEMPERATURE, pheromone only with SIGNALS.
+//
+// One invocation per grid position (unallocated bricks are skipped); the
+// invocation at a pheromone cell's min corner also measures that cell.
+// Each workgroup reduces in shared memory, then folds into stats_buf with
+// one atomic per word.
+//
+// Bind group 0:
+//   [0] voxel_buf: storage<array<u32>, read>
+//   [1] stats_buf: storage<array<atomic<u32>>, read_write>
+//   [2] params: uniform<SimParams>
+//   [3] temp_buf: storage<array<f32>, read> (TEMPERATURE)
+//   [4] pher_buf: storage<array<u32>, read> (SIGNALS)
+//   [10] brick_table (sparse; a 1-word placeholder in dense mode)
+//
+// Writes stats_buf[STATS_FIELD_STATS..+11] (types::FieldStats::from_words):
+//   [0] max of ~float_key(temp)  [1] max of float_key(temp)
+//   [2..3] temperature sum (TEMP_SUM_SCALE fixed point), low/high word
+//   [4] cells measured
+//   [5] FIELD_MIN_BIAS - min intensity  [6] max intensity
+//   [7..8] intensity sum, low/high word  [9] pheromone cells measured
+//   [10] FIELD_MIN_BIAS - min protocell energy (0 = no protocells)
+// ============================================================
+
+#include "common.wgsl"
+#include "brick_common.wgsl"
+
+@group(0) @binding(0) var<storage, read> voxel_buf: array<u32>;
+@group(0) @binding(1) var<storage, read_write> stats_buf: array<atomic<u32>>;
+@group(0) @binding(2) var<uniform> params: SimParams;
+#ifdef TEMPERATURE
+@group(0) @binding(3) var<storage, read> temp_buf: array<f32>;
+#endif
+#ifdef SIGNALS
+@group(0) @binding(4) var<storage, read> pher_buf: array<u32>;
+#endif
+
+// Mirror types::FIELD_MIN_BIAS, TEMP_SUM_SCALE and TEMP_SUM_MAX
+const FIELD_MIN_BIAS: u32 = 0x10000u;
+const TEMP_SUM_SCALE: f32 = 4096.0;
+const TEMP_SUM_MAX: f32 = 1024.0;
+
+var<workgroup> wg_temp_min: atomic<u32>;
+var<workgroup> wg_temp_max: atomic<u32>;
+var<workgroup> wg_temp_sum: atomic<u32>;
+var<workgroup> wg_cells: atomic<u32>;
+var<workgroup> wg_pher_min: atomic<u32>;
+var<workgroup> wg_pher_max: atomic<u32>;
+var<workgroup> wg_pher_sum: atomic<u32>;
+var<workgroup> wg_pher_cells: atomic<u32>;
+var<workgroup> wg_energy_min: atomic<u32>;
+
+// Add `v` to the 64-bit counter at stats_buf[word] (low) / [word + 1] (high)
+fn add_wide(word: u32, v: u32) {
+    let old = atomicAdd(&stats_buf[word], v);
+    if old > 0xFFFFFFFFu - v {
+        atomicAdd(&stats_buf[word + 1u], 1u);
+    }
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn field_stats_main(
+    @builtin(global_invocation_id) pos: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+) {
+    if lid == 0u {
+        atomicStore(&wg_temp_min, 0u);
+        atomicStore(&wg_temp_max, 0u);
+        atomicStore(&wg_temp_sum, 0u);
+        atomicStore(&wg_cells, 0u);
+        atomicStore(&wg_pher_min, 0u);
+        atomicStore(&wg_pher_max, 0u);
+        atomicStore(&wg_pher_sum, 0u);
+        atomicStore(&wg_pher_cells, 0u);
+        atomicStore(&wg_energy_min, 0u);
+    }
+    workgroupBarrier();
+
+    let gs = u32(params.grid_size);
+    if all(pos < vec3<u32>(gs)) {
+        var idx: u32;
+        if params.sparse_mode > 0.0 {
+            idx = sparse_voxel_index(pos, gs);
+        } else {
+            idx = grid_index(pos, gs);
+        }
+        if idx != 0xFFFFFFFFu {
+#ifdef TEMPERATURE
+            let temp = temp_buf[idx];
+            let key = float_key(temp);
+            atomicMax(&wg_temp_min, ~key);
+            atomicMax(&wg_temp_max, key);
+            atomicAdd(&wg_temp_sum, u32(clamp(temp, 0.0, TEMP_SUM_MAX) * TEMP_SUM_SCALE));
+            atomicAdd(&wg_cells, 1u);
+#endif
+            if voxel_get_type(&voxel_buf, idx) == VOXEL_PROTOCELL {
+                atomicMax(&wg_energy_min, FIELD_MIN_BIAS - voxel_get_energy(&voxel_buf, idx));
+            }
+        }
+#ifdef SIGNALS
+        if all(pos % PHEROMONE_CELL == vec3<u32>(0u)) {
+            let level = pheromone_get_intensity(pher_buf[pheromone_index(pos, gs)]);
+            atomicMax(&wg_pher_min, FIELD_MIN_BIAS - level);
+            atomicMax(&wg_pher_max, level);
+            atomicAdd(&wg_pher_sum, level);
+            atomicAdd(&wg_pher_cells, 1u);
+        }
+#endif
+    }
+    workgroupBarrier();
+
+    if lid == 0u {
+        let base = STATS_FIELD_STATS;
+        let cells = atomicLoad(&wg_cells);
+        if cells > 0u {
+            atomicMax(&stats_buf[base], atomicLoad(&wg_temp_min));
+            atomicMax(&stats_buf[base + 1u], atomicLoad(&wg_temp_max));
+            add_wide(base + 2u, atomicLoad(&wg_temp_sum));
+            atomicAdd(&stats_buf[base + 4u], cells);
+        }
+        atomicMax(&stats_buf[base + 10u], atomicLoad(&wg_energy_min));
+        let pher_cells = atomicLoad(&wg_pher_cells);
+        if pher_cells > 0u {
+            atomicMax(&stats_buf[base + 5u], atomicLoad(&wg_pher_min));
+            atomicMax(&stats_buf[base + 6u], atomicLoad(&wg_pher_max));
+            add_wide(base + 7u, atomicLoad(&wg_pher_sum));
+            atomicAdd(&stats_buf[base + 9u], pher_cells);
+        }
+    }
+}
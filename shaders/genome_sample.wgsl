@@ -0,0 +1,77 @@
+// ============================================================
+// genome_sample.wgsl — Sample live protocell genomes for readback.
+// Includes common.wgsl and brick_common.wgsl. The query uniform
+// is named `params` because brick_common reads `params.brick_grid_dim`.
+//
+// One invocation per grid position; protocells whose hashed index is at
+// most `threshold` are appended to the result buffer with an atomic
+// counter (types::GenomeSampleSet).
+//
+// Bind group 0:
+//   [0] voxel_buf: storage<array<u32>, read>
+//   [1] result_buf: storage<array<atomic<u32>>, read_write>
+//       [0] protocells kept, then 5 words per sample:
+//       packed x | y<<10 | z<<20, genome words 0..3
+//   [2] params: uniform<SampleParams>
+//   [10] brick_table (sparse; a 1-word placeholder in dense mode)
+// ============================================================
+
+#include "common.wgsl"
+#include "brick_common.wgsl"
+
+const SAMPLE_WORDS: u32 = 5u;
+
+struct SampleParams {
+    grid_size: u32,
+    sparse_mode: u32,
+    brick_grid_dim: u32,
+    max_samples: u32,
+    threshold: u32,
+    seed: u32,
+    _pad0: u32,
+    _pad1: u32,
+};
+
+@group(0) @binding(0) var<storage, read> voxel_buf: array<u32>;
+@group(0) @binding(1) var<storage, read_write> result_buf: array<atomic<u32>>;
+@group(0) @binding(2) var<uniform> params: SampleParams;
+
+// Matches types::sample_hash
+fn sample_hash(idx: u32, seed: u32) -> u32 {
+    return pcg_hash(idx ^ (seed * 0x9E3779B9u) ^ 0x53414D50u);
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn genome_sample_main(@builtin(global_invocation_id) pos: vec3<u32>) {
+    let gs = params.grid_size;
+    if pos.x >= gs || pos.y >= gs || pos.z >= gs {
+        return;
+    }
+    // Decided on the logical index so dense and sparse keep the same cells
+    let logical_idx = grid_index(pos, gs);
+    if sample_hash(logical_idx, params.seed) > params.threshold {
+        return;
+    }
+
+    var idx: u32;
+    if params.sparse_mode != 0u {
+        idx = sparse_voxel_index(pos, gs);
+        if idx == 0xFFFFFFFFu {
+            return;
+        }
+    } else {
+        idx = logical_idx;
+    }
+    if voxel_get_type(&voxel_buf, idx) != VOXEL_PROTOCELL {
+        return;
+    }
+
+    let slot = atomicAdd(&result_buf[0], 1u);
+    if slot < params.max_samples {
+        let base = 1u + slot * SAMPLE_WORDS;
+        atomicStore(&result_buf[base], pos.x | (pos.y << 10u) | (pos.z << 20u));
+        for (var w: u32 = 0u; w < 4u; w++) {
+            atomicStore(&result_buf[base + 1u + w], voxel_get_genome_word(&voxel_buf, idx, w));
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// ============================================================
+// graph.wgsl — Population / species-count / mean mutation-rate history plot.
+// Standalone shader (common.wgsl NOT prepended); includes layout.wgsl.
+//
+// Every stats readback copies the whole stats buffer into a ring of
+// samples; fs_plot redraws the graph texture from that ring (oldest
+// sample on the left, each series scaled to its own maximum) and
+// fs_composite draws the texture into a corner viewport of the surface.
+//
+// Samples are whole stats buffers (STATS_WORDS × u32); the plot reads
+// STATS_POPULATION, STATS_SPECIES_HISTOGRAM and STATS_MUTATION_RATE_SUM.
+//
+// Bind group 0 (fs_plot):
+//   [0] history: storage<array<u32>, read>
+//   [1] graph: uniform<GraphUniform>
+// Bind group 0 (fs_composite):
+//   [1] graph: uniform<GraphUniform>
+//   [2] graph_tex: texture_2d<f32>
+// ============================================================
+
+#include "layout.wgsl"
+
+const SAMPLE_WORDS: u32 = STATS_WORDS;
+
+struct GraphUniform {
+    info: vec4<f32>,           // x = head (next slot), y = sample count, z = capacity, w = output_transfer
+    size: vec4<f32>,           // xy = graph texture size in pixels, zw = unused
+};
+
+@group(0) @binding(0) var<storage, read> history: array<u32>;
+@group(0) @binding(1) var<uniform> graph: GraphUniform;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// Full-screen triangle over the viewport
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(vi & 1u)) * 4.0 - 1.0;
+    let y = f32(i32(vi >> 1u)) * 4.0 - 1.0;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>(x, -y) * 0.5 + 0.5;
+    return out;
+}
+
+// Ring slot of the i-th oldest sample
+fn slot(i: u32) -> u32 {
+    let capacity = u32(graph.info.z);
+    let count = u32(graph.info.y);
+    return (u32(graph.info.x) + capacity - count + i) % capacity;
+}
+
+fn population(i: u32) -> f32 {
+    return f32(history[slot(i) * SAMPLE_WORDS + STATS_POPULATION]);
+}
+
+fn species(i: u32) -> f32 {
+    let base = slot(i) * SAMPLE_WORDS;
+    var n = 0u;
+    for (var k = 0u; k < STATS_SPECIES_SLOTS; k = k + 1u) {
+        let entry = base + STATS_SPECIES_HISTOGRAM + k * 2u;
+        if history[entry] != 0u && history[entry + 1u] > 0u {
+            n = n + 1u;
+        }
+    }
+    return f32(n);
+}
+
+// Population-mean mutation-rate gene, 0..1 (absolute scale)
+fn mean_mutation_rate(i: u32) -> f32 {
+    let base = slot(i) * SAMPLE_WORDS;
+    return f32(history[base + STATS_MUTATION_RATE_SUM]) / (max(f32(history[base + STATS_POPULATION]), 1.0) * 255.0);
+}
+
+@fragment
+fn fs_plot(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = vec4<f32>(0.03, 0.03, 0.05, 0.75);
+    let count = u32(graph.info.y);
+    if count == 0u {
+        return color;
+    }
+
+    var max_pop = 1.0;
+    var max_species = 1.0;
+    for (var i = 0u; i < count; i = i + 1u) {
+        max_pop = max(max_pop, population(i));
+        max_species = max(max_species, species(i));
+    }
+
+    // Right-aligned: a short history fills the rightmost columns
+    let capacity = u32(graph.info.z);
+    let col = min(u32(in.uv.x * f32(capacity)), capacity - 1u);
+    let first = capacity - count;
+    let h = 1.0 - in.uv.y;
+    // ~1.5 px lines, in plot units
+    let line = 1.5 / graph.size.y;
+
+    // Faint gridlines at quarters
+    if abs(fract(h * 4.0 + 0.5) - 0.5) < line * 2.0 {
+        color = vec4<f32>(0.15, 0.15, 0.2, 0.8);
+    }
+    if col >= first {
+        let i = col - first;
+        let p = population(i) / max_pop;
+        if h <= p {
+            color = vec4<f32>(0.15, 0.55, 0.3, 0.85);
+        }
+        if abs(h - p) < line {
+            color = vec4<f32>(0.3, 0.9, 0.5, 1.0);
+        }
+        let s = species(i) / max_species;
+        if abs(h - s) < line {
+            color = vec4<f32>(1.0, 0.6, 0.2, 1.0);
+        }
+        if abs(h - mean_mutation_rate(i)) < line {
+            color = vec4<f32>(0.4, 0.7, 1.0, 1.0);
+        }
+    }
+    return color;
+}
+
+@group(0) @binding(2) var graph_tex: texture_2d<f32>;
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let lo = c / 12.92;
+    let hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(hi, lo, c <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(graph_tex));
+    let texel = clamp(vec2<i32>(in.uv * size), vec2<i32>(0), vec2<i32>(size) - 1);
+    var c = textureLoad(graph_tex, texel, 0);
+    if graph.info.w > 0.5 {
+        c = vec4<f32>(srgb_to_linear(c.rgb), c.a);
+    }
+    return c;
+}
@@ -1,47 +1,73 @@
 // ============================================================
 // intent_declaration.wgsl — M6: Intent declaration pass.
 // Each protocell declares one intent (DIE, PREDATE, REPLICATE, MOVE, or IDLE).
-// Prepended with common.wgsl at pipeline creation.
+// Includes common.wgsl and brick_common.wgsl.
 //
 // Bind group 0:
 //   [0] voxel_read:  storage<array<u32>, read>
 //   [1] intent_buf:  storage<array<u32>, read_write>
 //   [2] params:      uniform<SimParams>
-//   [3] temp_read:   storage<array<f32>, read>
+//   [3] temp_read:   storage<array<f32>, read>  (TEMPERATURE)
+//   [4] pher_read:   storage<array<u32>, read>  (SIGNALS; pheromone field after this tick's update)
+//   [11] region:     uniform<DispatchRegion>, dynamic offset (active region in dense mode)
+//   [14] tick:       uniform<TickParams>, dynamic offset (this tick's slot)
 // ============================================================
 
-struct SimParams {
-    grid_size: f32,
-    tick_count: f32,
-    dt: f32,
-    nutrient_spawn_rate: f32,
-    waste_decay_ticks: f32,
-    nutrient_recycle_rate: f32,
-    movement_energy_cost: f32,
-    base_ambient_temp: f32,
-    metabolic_cost_base: f32,
-    replication_energy_min: f32,
-    energy_from_nutrient: f32,
-    energy_from_source: f32,
-    diffusion_rate: f32,
-    temp_sensitivity: f32,
-    predation_energy_fraction: f32,
-    max_energy: f32,
-    overlay_mode: f32,
-    sparse_mode: f32,
-    brick_grid_dim: f32,
-    max_bricks: f32,
-};
+#include "common.wgsl"
+#include "brick_common.wgsl"
 
 @group(0) @binding(0) var<storage, read> voxel_read: array<u32>;
 @group(0) @binding(1) var<storage, read_write> intent_buf: array<u32>;
 @group(0) @binding(2) var<uniform> params: SimParams;
+#ifdef TEMPERATURE
 @group(0) @binding(3) var<storage, read> temp_read: array<f32>;
+#endif
+#ifdef SIGNALS
+@group(0) @binding(4) var<storage, read> pher_read: array<u32>;
+#endif
+@group(0) @binding(11) var<uniform> region: DispatchRegion;
+@group(0) @binding(14) var<uniform> tick: TickParams;
 
-@compute @workgroup_size(4, 4, 4)
-fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+// Temperature of the cell at idx; ambient everywhere without TEMPERATURE.
+fn cell_temp(idx: u32) -> f32 {
+#ifdef TEMPERATURE
+    return temp_read[idx];
+#else
+    return params.base_ambient_temp;
+#endif
+}
+
+// Scent at the voxel one step from pos in dir: +intensity for our own
+// bucket, -intensity for a competitor's, 0 when unmarked (always without
+// SIGNALS, which leaves the field flat).
+fn pheromone_score(pos: vec3<u32>, dir: u32, gs: u32, own_bucket: u32) -> i32 {
+#ifdef SIGNALS
+    let np = vec3<u32>(vec3<i32>(pos) + NEIGHBORS[dir]);
+    let w = pher_read[pheromone_index(np, gs)];
+    let level = i32(pheromone_get_intensity(w));
+    let bucket = pheromone_get_bucket(w);
+    if bucket == 0u {
+        return 0;
+    }
+    return select(-level, level, bucket == own_bucket);
+#else
+    return 0;
+#endif
+}
+
+// Frozen cells, cells whose brick sits out this tick (sparse LOD) and
+// dilation-zone cells between the zone's ticks (pos is the cell's)
+// keep their state: they act on nothing and nothing acts on them.
+fn cell_held(idx: u32, pos: vec3<u32>) -> bool {
+    return voxel_is_frozen(&voxel_read, idx) || brick_skips_tick(idx, tick.tick_count)
+        || dilation_skips_tick(params, pos, tick.tick_count);
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn intent_declaration_main(@builtin(global_invocation_id) rel: vec3<u32>) {
     let gs = u32(params.grid_size);
-    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
+    let gid = region.origin.xyz + rel;
+    if any(gid >= region.end.xyz) {
         return;
     }
 
@@ -55,8 +81,8 @@ fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     }
     let vtype = voxel_get_type(&voxel_read, idx);
 
-    // Non-protocells: write NO_ACTION and return
-    if vtype != VOXEL_PROTOCELL {
+    // Non-protocells and held cells: write NO_ACTION and return
+    if vtype != VOXEL_PROTOCELL || cell_held(idx, gid) {
         intent_buf[idx] = 0u;
         return;
     }
@@ -64,7 +90,7 @@ fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     // ---- Protocell intent declaration ----
     // PRNG with dispatch salt 0x1 for intent pass
     // Use logical index for PRNG, not pool index, to preserve determinism
-    var rng = prng_seed(logical_idx, u32(params.tick_count), gs, 0x1u);
+    var rng = prng_seed(logical_idx, tick.tick_count, gs, 0x1u);
 
     let energy = voxel_get_energy(&voxel_read, idx);
 
@@ -86,12 +112,22 @@ fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
         return;
     }
 
+    // Cold dormancy: no actions until it warms up
+    let cold_tolerance = genome_cold_tolerance(genome_get_byte(&voxel_read, idx, 13u));
+    if is_cold_dormant(cell_temp(idx), cold_tolerance, params.dormancy_temp) {
+        intent_buf[idx] = intent_encode(ACTION_IDLE, DIR_SELF, 0u);
+        return;
+    }
+
     // Scan neighbors once: collect empty dirs, food dirs, prey dirs
     var empty_count: u32 = 0u;
     var empty_dirs: array<u32, 6>;
     var food_dir_mask: u32 = 0u; // bit d set if direction d has food neighbor
     var prey_count: u32 = 0u;
     var prey_dirs: array<u32, 6>;
+    var crowd: u32 = 0u; // protocell neighbors
+    var empty_mask: u32 = 0u;
+    var cell_mask: u32 = 0u; // bit d set if direction d has a protocell
 
     for (var d: u32 = 0u; d < 6u; d++) {
         var ni: u32;
@@ -100,18 +136,22 @@ fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
         } else {
             ni = neighbor_in_direction(gid, d, gs);
         }
-        if ni == 0xFFFFFFFFu {
+        // Held neighbors are out of reach, like the grid edge
+        if ni == 0xFFFFFFFFu || cell_held(ni, vec3<u32>(vec3<i32>(gid) + NEIGHBORS[d])) {
             continue;
         }
         let ntype = voxel_get_type(&voxel_read, ni);
         if ntype == VOXEL_EMPTY {
             empty_dirs[empty_count] = d;
             empty_count++;
+            empty_mask |= (1u << d);
         } else if ntype == VOXEL_NUTRIENT || ntype == VOXEL_ENERGY_SOURCE {
             food_dir_mask |= (1u << d);
-        } else if ntype == VOXEL_PROTOCELL && predation_capability > 0u {
+        } else if ntype == VOXEL_PROTOCELL {
+            crowd++;
+            cell_mask |= (1u << d);
             let n_energy = voxel_get_energy(&voxel_read, ni);
-            if n_energy < prey_threshold {
+            if predation_capability > 0u && n_energy < prey_threshold {
                 prey_dirs[prey_count] = d;
                 prey_count++;
             }
@@ -128,12 +168,41 @@ fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     }
 
     // Priority 3: REPLICATE — energy > threshold AND empty neighbor exists
+    // AND not suppressed by crowding
     let replication_threshold_byte = genome_get_byte(&voxel_read, idx, 2u);
     let threshold = (u32(params.replication_energy_min) * replication_threshold_byte) / 255u;
+    let crowded = f32(crowd) > params.crowding_threshold && params.crowding_blocks_replication > 0.0;
 
-    if energy > threshold && empty_count > 0u {
+    // Adhesion: linked cells keep their growth axis and stay anchored while
+    // the cell behind them (toward the parent) is still a protocell.
+    let flags = voxel_get_flags(&voxel_read, idx);
+    let linked = (flags & FLAG_LINKED) != 0u;
+    let growth_dir = flags & FLAG_GROWTH_DIR_MASK;
+    let adhesion = genome_get_byte(&voxel_read, idx, 12u);
+    let anchored = linked && (cell_mask & (1u << opposite_direction(growth_dir))) != 0u;
+
+    if energy > threshold && empty_count > 0u && !crowded {
         let chosen = roll_replication_target % empty_count;
-        let target_dir = empty_dirs[chosen];
+        var target_dir = empty_dirs[chosen];
+        if linked && ((roll_replication_target >> 16u) % 255u) < adhesion {
+            if (empty_mask & (1u << growth_dir)) != 0u {
+                // Filament: extend along the growth axis
+                target_dir = growth_dir;
+            } else {
+                // Blocked: branch sideways (sheets), never back toward the parent
+                var side_count: u32 = 0u;
+                var side_dirs: array<u32, 6>;
+                for (var e: u32 = 0u; e < empty_count; e++) {
+                    if empty_dirs[e] != opposite_direction(growth_dir) {
+                        side_dirs[side_count] = empty_dirs[e];
+                        side_count++;
+                    }
+                }
+                if side_count > 0u {
+                    target_dir = side_dirs[roll_replication_target % side_count];
+                }
+            }
+        }
         let bid = roll_bid % (energy + 1u);
         intent_buf[idx] = intent_encode(ACTION_REPLICATE, target_dir, bid);
         return;
@@ -142,8 +211,10 @@ fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     // Priority 4: MOVE — movement_bias check, then pick direction (with chemotaxis)
     let movement_bias = genome_get_byte(&voxel_read, idx, 4u);
     let chemotaxis_strength = genome_get_byte(&voxel_read, idx, 5u);
+    let pheromone_strength = genome_get_byte(&voxel_read, idx, 11u);
+    let pheromone_follow = u32(clamp(f32(pheromone_strength) * params.pheromone_response, 0.0, 255.0));
 
-    if (roll_movement_decision % 256u) < movement_bias && empty_count > 0u {
+    if (roll_movement_decision % 256u) < movement_bias && empty_count > 0u && !anchored {
         // Chemotaxis: prefer empty neighbors in food directions
         var food_empty_count: u32 = 0u;
         var food_empty_dirs: array<u32, 6>;
@@ -158,10 +229,72 @@ fn intent_declaration_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             }
         }
 
+        // Territoriality: best-scented empty neighbors (own scent up,
+        // competitors' scent down). Only used if the field isn't flat.
+        var scent_count: u32 = 0u;
+        var scent_dirs: array<u32, 6>;
+        if pheromone_follow > 0u {
+            let own_bucket = pheromone_bucket(voxel_get_species_id(&voxel_read, idx));
+            var best: i32 = -0x7FFFFFFF;
+            var worst: i32 = 0x7FFFFFFF;
+            for (var e: u32 = 0u; e < empty_count; e++) {
+                let score = pheromone_score(gid, empty_dirs[e], gs, own_bucket);
+                worst = min(worst, score);
+                if score > best {
+                    best = score;
+                    scent_count = 0u;
+                }
+                if score == best {
+                    scent_dirs[scent_count] = empty_dirs[e];
+                    scent_count++;
+                }
+            }
+            if best == worst {
+                scent_count = 0u;
+            }
+        }
+
+        // Thermotaxis (gene 15): [4:7] preferred temp, [0:3] strength.
+        // Candidates are the empty neighbors closest to the preferred
+        // temperature, and only if they're closer than where we are.
+        let thermo = genome_get_byte(&voxel_read, idx, 15u);
+        let preferred_temp = f32(thermo >> 4u) / 15.0;
+        let thermo_follow = u32(clamp(f32(thermo & 0xFu) / 15.0 * params.thermotaxis_response, 0.0, 1.0) * 255.0);
+        var thermo_count: u32 = 0u;
+        var thermo_dirs: array<u32, 6>;
+        if thermo_follow > 0u {
+            let own_dist = abs(cell_temp(idx) - preferred_temp);
+            var best = own_dist;
+            for (var e: u32 = 0u; e < empty_count; e++) {
+                var ni: u32;
+                if params.sparse_mode > 0.0 {
+                    ni = sparse_neighbor(gid, empty_dirs[e], gs);
+                } else {
+                    ni = neighbor_in_direction(gid, empty_dirs[e], gs);
+                }
+                let dist = abs(cell_temp(ni) - preferred_temp);
+                if dist < best {
+                    best = dist;
+                    thermo_count = 0u;
+                }
+                if dist == best {
+                    thermo_dirs[thermo_count] = empty_dirs[e];
+                    thermo_count++;
+                }
+            }
+            if best >= own_dist {
+                thermo_count = 0u;
+            }
+        }
+
         var chosen_dir: u32;
         if food_empty_count > 0u && (roll_movement_direction % 255u) < chemotaxis_strength {
             // Bias toward food direction
             chosen_dir = food_empty_dirs[roll_movement_direction % food_empty_count];
+        } else if thermo_count > 0u && ((roll_movement_direction >> 8u) % 255u) < thermo_follow {
+            chosen_dir = thermo_dirs[roll_movement_direction % thermo_count];
+        } else if scent_count > 0u && ((roll_movement_direction >> 16u) % 255u) < pheromone_follow {
+            chosen_dir = scent_dirs[roll_movement_direction % scent_count];
         } else {
             // Random from all empties
             chosen_dir = empty_dirs[roll_movement_direction % empty_count];
@@ -0,0 +1,53 @@
+// ============================================================
+// marker.wgsl — User markers drawn as screen-facing billboards.
+// Standalone shader (common.wgsl NOT prepended).
+//
+// One instance per marker; each draws a 4-vertex triangle strip sized in
+// pixels, so markers stay legible at any zoom. Labels are drawn by the
+// page on top of the canvas.
+//
+// Bind group 0:
+//   [0] uniforms: uniform<MarkerUniform>
+// Vertex buffer (per instance): center.xyz (cell center), color.rgb
+// ============================================================
+
+struct MarkerUniform {
+    view_proj: mat4x4<f32>,
+    viewport: vec4<f32>,       // xy = surface size in pixels, z = marker size in pixels
+};
+
+@group(0) @binding(0) var<uniform> uniforms: MarkerUniform;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vi: u32,
+    @location(0) center: vec3<f32>,
+    @location(1) color: vec3<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = vec2<f32>(f32(vi & 1u), f32(vi >> 1u)) * 2.0 - 1.0;
+    let clip = uniforms.view_proj * vec4<f32>(center, 1.0);
+    // Offset in NDC, scaled by w so the size is constant on screen
+    let offset = corner * uniforms.viewport.z / uniforms.viewport.xy;
+    out.position = vec4<f32>(clip.xy + offset * clip.w, clip.zw);
+    out.corner = corner;
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Filled disc with a dark rim
+    let r = length(in.corner);
+    if r > 1.0 {
+        discard;
+    }
+    let rgb = select(in.color, in.color * 0.3, r > 0.75);
+    return vec4<f32>(rgb, 0.9);
+}
@@ -0,0 +1,89 @@
+// ============================================================
+// migrate.wgsl — Copy the world between dense buffers and a brick pool.
+// Includes common.wgsl and brick_common.wgsl. The uniform is named
+// `params` because brick_common reads `params.brick_grid_dim`.
+//
+// One invocation per grid position for every entry point:
+//   brick_occupancy_main: count non-empty or frozen dense voxels per brick
+//   dense_to_sparse_main: dense → pool for positions in allocated bricks
+//   sparse_to_dense_main: pool → dense; unallocated bricks become EMPTY
+//                         at the ambient temperature
+//
+// Bind group 0:
+//   [0] dense_voxels: storage<array<u32>, read_write>
+//   [1] dense_temps:  storage<array<f32>, read_write>
+//   [2] pool_voxels:  storage<array<u32>, read_write>
+//   [3] pool_temps:   storage<array<f32>, read_write>
+//   [4] params:       uniform<MigrateParams>
+//   [5] occupancy:    storage<array<atomic<u32>>, read_write>, one per brick
+//   [10] brick_table (read)
+// Bindings a pass doesn't touch hold 4-byte placeholders.
+// ============================================================
+
+#include "common.wgsl"
+#include "brick_common.wgsl"
+
+struct MigrateParams {
+    grid_size: u32,
+    brick_grid_dim: u32,
+    ambient_temp: f32,
+    _pad: u32,
+};
+
+@group(0) @binding(0) var<storage, read_write> dense_voxels: array<u32>;
+@group(0) @binding(1) var<storage, read_write> dense_temps: array<f32>;
+@group(0) @binding(2) var<storage, read_write> pool_voxels: array<u32>;
+@group(0) @binding(3) var<storage, read_write> pool_temps: array<f32>;
+@group(0) @binding(4) var<uniform> params: MigrateParams;
+@group(0) @binding(5) var<storage, read_write> occupancy: array<atomic<u32>>;
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn brick_occupancy_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let gs = params.grid_size;
+    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
+        return;
+    }
+    let idx = grid_index(gid, gs);
+    // word0 != 0: non-empty, or an empty cell that is frozen
+    if dense_voxels[idx * VOXEL_STRIDE] != 0u {
+        atomicAdd(&occupancy[brick_coord_index(gid.x / 8u, gid.y / 8u, gid.z / 8u)], 1u);
+    }
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn dense_to_sparse_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let gs = params.grid_size;
+    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
+        return;
+    }
+    let pidx = sparse_voxel_index(gid, gs);
+    if pidx == 0xFFFFFFFFu {
+        return;
+    }
+    let idx = grid_index(gid, gs);
+    for (var w = 0u; w < VOXEL_STRIDE; w++) {
+        pool_voxels[pidx * VOXEL_STRIDE + w] = dense_voxels[idx * VOXEL_STRIDE + w];
+    }
+    pool_temps[pidx] = dense_temps[idx];
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn sparse_to_dense_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let gs = params.grid_size;
+    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
+        return;
+    }
+    let idx = grid_index(gid, gs);
+    let pidx = sparse_voxel_index(gid, gs);
+    if pidx == 0xFFFFFFFFu {
+        for (var w = 0u; w < VOXEL_STRIDE; w++) {
+            dense_voxels[idx * VOXEL_STRIDE + w] = 0u;
+        }
+        dense_temps[idx] = params.ambient_temp;
+        return;
+    }
+    for (var w = 0u; w < VOXEL_STRIDE; w++) {
+        dense_voxels[idx * VOXEL_STRIDE + w] = pool_voxels[pidx * VOXEL_STRIDE + w];
+    }
+    dense_temps[idx] = pool_temps[pidx];
+}
@@ -0,0 +1,120 @@
+// ============================================================
+// minimap.wgsl — Top-down orthographic projection of the volume,
+// drawn into a corner viewport.
+// Standalone shader (common.wgsl NOT prepended).
+//
+// fs_density renders the density heatmap into a cached texture: each
+// pixel composites its (x, z) column from the top down, stepping `stride`
+// cells in y so the cost stays flat on large grids. fs_main draws the
+// cache into the corner every frame, marking the camera target with a
+// cross and the eye direction with a dot, so the heatmap itself can be
+// refreshed less often than frames are drawn.
+//
+// Bind group 0 (fs_density):
+//   [0] volume_tex: texture_3d<f32>
+//   [1] minimap: uniform<MinimapUniform>
+//   [2] transfer_lut: storage<array<u32>, read> — as in ray_march.wgsl
+// Bind group 0 (fs_main):
+//   [1] minimap: uniform<MinimapUniform>
+//   [3] heatmap_tex: texture_2d<f32> — fs_density's output
+// ============================================================
+
+struct MinimapUniform {
+    info: vec4<f32>,           // x = grid_size, y = y stride, z = output_transfer, w = 1 for transfer-function texels
+    view: vec4<f32>,           // xy = camera target (x, z), zw = eye (x, z)
+};
+
+@group(0) @binding(0) var volume_tex: texture_3d<f32>;
+@group(0) @binding(1) var<uniform> minimap: MinimapUniform;
+@group(0) @binding(2) var<storage, read> transfer_lut: array<u32>;
+@group(0) @binding(3) var heatmap_tex: texture_2d<f32>;
+
+// Matches types::TRANSFER_LUT_SIZE
+const TRANSFER_LUT_SIZE: u32 = 256u;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// Full-screen triangle over the viewport
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(vi & 1u)) * 4.0 - 1.0;
+    let y = f32(i32(vi >> 1u)) * 4.0 - 1.0;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>(x, -y) * 0.5 + 0.5;
+    return out;
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let lo = c / 12.92;
+    let hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(hi, lo, c <= vec3<f32>(0.04045));
+}
+
+// Column composite of the volume under each heatmap pixel
+@fragment
+fn fs_density(in: VertexOutput) -> @location(0) vec4<f32> {
+    let gs = minimap.info.x;
+    let stride = max(i32(minimap.info.y), 1);
+    let cell_xz = in.uv * gs;
+    let max_cell = i32(gs) - 1;
+    let x = clamp(i32(cell_xz.x), 0, max_cell);
+    let z = clamp(i32(cell_xz.y), 0, max_cell);
+
+    var accum = vec4<f32>(0.0);
+    for (var y = max_cell; y >= 0; y = y - stride) {
+        var sample: vec4<f32>;
+        if minimap.info.w > 0.5 {
+            // Two cells per texel, as in ray_march.wgsl
+            let texel = textureLoad(volume_tex, vec3<i32>(x / 2, y, z), 0);
+            let input = select(texel.rg, texel.ba, (x & 1) == 1);
+            let row = u32(round(input.x * 255.0));
+            let entry = u32(round(input.y * f32(TRANSFER_LUT_SIZE - 1u)));
+            sample = select(vec4<f32>(0.0), unpack4x8unorm(transfer_lut[row * TRANSFER_LUT_SIZE + entry]), row != 0u);
+        } else {
+            sample = textureLoad(volume_tex, vec3<i32>(x, y, z), 0);
+        }
+        if sample.a > 0.01 {
+            let src_alpha = sample.a * (1.0 - accum.a);
+            accum = vec4<f32>(accum.rgb + sample.rgb * src_alpha, accum.a + src_alpha);
+            if accum.a >= 0.95 {
+                break;
+            }
+        }
+    }
+    return vec4<f32>(accum.rgb + vec3<f32>(0.05, 0.05, 0.08) * (1.0 - accum.a), 1.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let gs = minimap.info.x;
+    let cell_xz = in.uv * gs;
+    let dims = textureDimensions(heatmap_tex);
+    let texel = min(vec2<u32>(in.uv * vec2<f32>(dims)), dims - 1u);
+    var rgb = textureLoad(heatmap_tex, texel, 0).rgb;
+
+    // Camera target cross and eye dot, sized in cells relative to the grid
+    let px = gs / 60.0;
+    let d_target = abs(cell_xz - minimap.view.xy);
+    if (d_target.x < px * 0.5 && d_target.y < px * 3.0) || (d_target.y < px * 0.5 && d_target.x < px * 3.0) {
+        rgb = vec3<f32>(1.0, 1.0, 1.0);
+    }
+    let eye = clamp(minimap.view.zw, vec2<f32>(0.0), vec2<f32>(gs));
+    if length(cell_xz - eye) < px * 2.0 {
+        rgb = vec3<f32>(0.27, 0.67, 1.0);
+    }
+
+    // Frame
+    let edge = min(min(in.uv.x, in.uv.y), min(1.0 - in.uv.x, 1.0 - in.uv.y));
+    if edge < 0.015 {
+        rgb = vec3<f32>(0.27, 0.67, 1.0);
+    }
+
+    if minimap.info.z > 0.5 {
+        rgb = srgb_to_linear(rgb);
+    }
+    return vec4<f32>(rgb, 1.0);
+}
@@ -0,0 +1,100 @@
+// ============================================================
+// pick.wgsl — Find the first visible voxel along a click ray.
+// Standalone shader (common.wgsl NOT prepended).
+//
+// Walks the volume texture cell by cell (Amanatides–Woo DDA) and
+// records the first texel the ray marcher would composite, honoring
+// the clip plane.
+//
+// Bind group 0:
+//   [0] volume_tex: texture_3d<f32>
+//   [1] pick: uniform<PickUniform>
+//   [2] pick_out: storage<array<u32, 4>> — found, x, y, z
+// ============================================================
+
+struct PickUniform {
+    origin: vec4<f32>,         // xyz = ray origin, w = grid_size
+    dir: vec4<f32>,            // xyz = unit ray direction, w = alpha threshold
+    clip: vec4<f32>,           // x = clip_axis (-1 = off), y = clip_position
+};
+
+@group(0) @binding(0) var volume_tex: texture_3d<f32>;
+@group(0) @binding(1) var<uniform> pick: PickUniform;
+@group(0) @binding(2) var<storage, read_write> pick_out: array<u32, 4>;
+
+// Whether the ray marcher would composite `cell`. A texture narrower than
+// the grid holds transfer-function inputs, two cells per texel, and a cell
+// shows unless its row is empty.
+fn visible(cell: vec3<i32>, gs: f32) -> bool {
+    if i32(textureDimensions(volume_tex).x) < i32(gs) {
+        let texel = textureLoad(volume_tex, vec3<i32>(cell.x / 2, cell.y, cell.z), 0);
+        return select(texel.r, texel.b, (cell.x & 1) == 1) > 0.0;
+    }
+    return textureLoad(volume_tex, cell, 0).a > pick.dir.w;
+}
+
+fn clipped(cell: vec3<i32>, gs: f32) -> bool {
+    let axis = i32(pick.clip.x);
+    if axis < 0 {
+        return false;
+    }
+    let uvw = (vec3<f32>(cell) + 0.5) / gs;
+    return uvw[axis] > pick.clip.y;
+}
+
+@compute @workgroup_size(1)
+fn main() {
+    pick_out[0] = 0u;
+    let gs = pick.origin.w;
+    let origin = pick.origin.xyz;
+    let dir = pick.dir.xyz;
+
+    // Ray-AABB slab test against [0, gs]^3
+    let inv_dir = 1.0 / dir;
+    let t0 = (vec3<f32>(0.0) - origin) * inv_dir;
+    let t1 = (vec3<f32>(gs) - origin) * inv_dir;
+    let t_enter = max(max(min(t0.x, t1.x), min(t0.y, t1.y)), min(t0.z, t1.z));
+    let t_exit = min(min(max(t0.x, t1.x), max(t0.y, t1.y)), max(t0.z, t1.z));
+    if t_enter > t_exit || t_exit < 0.0 {
+        return;
+    }
+
+    let t_start = max(t_enter, 0.0);
+    let entry = origin + dir * t_start;
+    let max_cell = i32(gs) - 1;
+    var cell = clamp(vec3<i32>(floor(entry)), vec3<i32>(0), vec3<i32>(max_cell));
+
+    let step = vec3<i32>(sign(dir));
+    let delta = abs(inv_dir);
+    // Distance along the ray to the next cell boundary on each axis
+    let next_edge = vec3<f32>(cell) + select(vec3<f32>(0.0), vec3<f32>(1.0), dir > vec3<f32>(0.0));
+    var t_max = select(
+        (next_edge - origin) * inv_dir,
+        vec3<f32>(1e30),
+        dir == vec3<f32>(0.0),
+    );
+
+    let max_steps = 3 * i32(gs);
+    for (var i = 0; i < max_steps; i = i + 1) {
+        if any(cell < vec3<i32>(0)) || any(cell > vec3<i32>(max_cell)) {
+            return;
+        }
+        if !clipped(cell, gs) && visible(cell, gs) {
+            pick_out[0] = 1u;
+            pick_out[1] = u32(cell.x);
+            pick_out[2] = u32(cell.y);
+            pick_out[3] = u32(cell.z);
+            return;
+        }
+        if t_max.x < t_max.y && t_max.x < t_max.z {
+            cell.x += step.x;
+            t_max.x += delta.x;
+        } else if t_max.y < t_max.z {
+            cell.y += step.y;
+            t_max.y += delta.y;
+        } else {
+            cell.z += step.z;
+            t_max.z += delta.z;
+        }
+    }
+}
@@ -6,6 +6,10 @@
 //   [0] volume_tex: texture_3d<f32>
 //   [1] tex_sampler: sampler
 //   [2] camera: uniform<CameraUniform>
+//   [3] transfer_lut: storage<array<u32>, read> — per-row RGBA8 ramps
+//       (types::TransferFunction::lut_words), used while `transfer` is set
+//   [4] occupancy: storage<array<u32>, read> — nonzero per OCCUPANCY_CELL³
+//       block holding a visible texel (update_render_texture.wgsl)
 // ============================================================
 
 struct CameraUniform {
@@ -14,12 +18,25 @@ struct CameraUniform {
     grid_size: f32,
     clip_axis: f32,            // -1 = no clip, 0/1/2 = X/Y/Z
     clip_position: f32,        // [0, 1] along axis
-    _padding: f32,
+    output_transfer: f32,      // 0 = write as-is, 1 = sRGB surface (decode first)
+    reduced_flicker: f32,      // 1 = soften bright highlights
+    transfer: f32,             // 1 = texels hold transfer-function inputs, two cells each
+    _pad1: f32,
+    _pad2: f32,
+    region_min: vec4<f32>,     // xyz = lower corner of the box holding every
+    region_max: vec4<f32>,     // visible texel (the active region); w unused
 };
 
 @group(0) @binding(0) var volume_tex: texture_3d<f32>;
 @group(0) @binding(1) var tex_sampler: sampler;
 @group(0) @binding(2) var<uniform> camera: CameraUniform;
+@group(0) @binding(3) var<storage, read> transfer_lut: array<u32>;
+@group(0) @binding(4) var<storage, read> occupancy: array<u32>;
+
+// Matches types::TRANSFER_LUT_SIZE
+const TRANSFER_LUT_SIZE: u32 = 256u;
+// Matches renderer::render_texture::OCCUPANCY_CELL
+const OCCUPANCY_CELL: u32 = 8u;
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -38,6 +55,42 @@ fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
     return out;
 }
 
+// Colors below are sRGB display values. An *-srgb surface re-encodes on
+// write, so decode to linear first; other formats take them as-is.
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let lo = c / 12.92;
+    let hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(hi, lo, c <= vec3<f32>(0.04045));
+}
+
+// Soft knee above 0.7 so emissive sources don't flare at full intensity
+fn soften_highlights(rgb: vec3<f32>) -> vec3<f32> {
+    let knee = 0.7;
+    let over = max(rgb - vec3<f32>(knee), vec3<f32>(0.0));
+    return min(rgb, vec3<f32>(knee)) + over / (1.0 + 2.0 * over);
+}
+
+fn encode_output(rgb: vec3<f32>) -> vec4<f32> {
+    if camera.output_transfer > 0.5 {
+        return vec4<f32>(srgb_to_linear(rgb), 1.0);
+    }
+    return vec4<f32>(rgb, 1.0);
+}
+
+// Color and per-cell opacity of the cell at `pos` from its transfer-function
+// inputs, two cells per texel (see update_render_texture.wgsl)
+fn apply_transfer(pos: vec3<f32>) -> vec4<f32> {
+    let cell = vec3<u32>(clamp(pos, vec3<f32>(0.0), vec3<f32>(camera.grid_size - 0.5)));
+    let texel = textureLoad(volume_tex, vec3<u32>(cell.x / 2u, cell.y, cell.z), 0);
+    let input = select(texel.rg, texel.ba, (cell.x & 1u) == 1u);
+    let row = u32(round(input.x * 255.0));
+    if row == 0u {
+        return vec4<f32>(0.0);
+    }
+    let entry = u32(round(input.y * f32(TRANSFER_LUT_SIZE - 1u)));
+    return unpack4x8unorm(transfer_lut[row * TRANSFER_LUT_SIZE + entry]);
+}
+
 // Ray-AABB intersection: returns (tmin, tmax) or tmin > tmax if no hit
 fn intersect_aabb(origin: vec3<f32>, inv_dir: vec3<f32>, box_min: vec3<f32>, box_max: vec3<f32>) -> vec2<f32> {
     let t0 = (box_min - origin) * inv_dir;
@@ -63,23 +116,27 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let ray_end = world_far.xyz / world_far.w;
     let ray_dir = normalize(ray_end - ray_origin);
 
-    // Intersect with volume AABB [0, grid_size]
+    // Intersect with the active region inside the volume [0, grid_size];
+    // everything outside it is transparent
     let inv_dir = 1.0 / ray_dir;
-    let hit = intersect_aabb(ray_origin, inv_dir, vec3<f32>(0.0), vec3<f32>(gs));
+    let volume_hit = intersect_aabb(ray_origin, inv_dir, vec3<f32>(0.0), vec3<f32>(gs));
+    let hit = intersect_aabb(ray_origin, inv_dir, camera.region_min.xyz, camera.region_max.xyz);
 
     if hit.x > hit.y {
         // No intersection
-        return vec4<f32>(0.02, 0.02, 0.04, 1.0); // dark background
+        return encode_output(vec3<f32>(0.02, 0.02, 0.04)); // dark background
     }
 
-    let t_start = max(hit.x, 0.0);
-    let t_end = hit.y;
-
-    // March through volume
+    // March through volume. Samples stay on the whole-volume step lattice
+    // so the image doesn't shift as the region changes.
     let step_size = 0.5;
     let max_steps = 384;
+    let lattice = max(volume_hit.x, 0.0);
+    let t_start = lattice + floor(max(max(hit.x, 0.0) - lattice, 0.0) / step_size) * step_size;
+    let t_end = hit.y;
     var accum = vec4<f32>(0.0);
     var t = t_start;
+    let od = (u32(gs) + OCCUPANCY_CELL - 1u) / OCCUPANCY_CELL;
 
     for (var i = 0; i < max_steps; i = i + 1) {
         if t >= t_end || accum.a >= 0.95 {
@@ -98,8 +155,25 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             if axis == 2 && uvw.z > clip_val { t += step_size; continue; }
         }
 
+        // Empty-space skipping: jump past blocks with nothing visible,
+        // landing on the step lattice
+        let cell = vec3<u32>(clamp(pos, vec3<f32>(0.0), vec3<f32>(gs - 0.5))) / OCCUPANCY_CELL;
+        if occupancy[(cell.z * od + cell.y) * od + cell.x] == 0u {
+            let lo = vec3<f32>(cell * OCCUPANCY_CELL);
+            let exit = intersect_aabb(ray_origin, inv_dir, lo, lo + vec3<f32>(f32(OCCUPANCY_CELL))).y;
+            t = max(lattice + ceil((exit - lattice) / step_size) * step_size, t + step_size);
+            continue;
+        }
+
         // Sample volume texture
-        let sample = textureSampleLevel(volume_tex, tex_sampler, uvw, 0.0);
+        var sample: vec4<f32>;
+        if camera.transfer > 0.5 {
+            // Absorption: ramp opacity is per cell, scaled to the step
+            sample = apply_transfer(pos);
+            sample.a = 1.0 - pow(1.0 - sample.a, step_size);
+        } else {
+            sample = textureSampleLevel(volume_tex, tex_sampler, uvw, 0.0);
+        }
 
         // Front-to-back compositing
         if sample.a > 0.01 {
@@ -115,6 +189,9 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     // Blend with background
     let bg = vec3<f32>(0.02, 0.02, 0.04);
-    let final_rgb = accum.rgb + bg * (1.0 - accum.a);
-    return vec4<f32>(final_rgb, 1.0);
+    var final_rgb = accum.rgb + bg * (1.0 - accum.a);
+    if camera.reduced_flicker > 0.5 {
+        final_rgb = soften_highlights(final_rgb);
+    }
+    return encode_output(final_rgb);
 }
@@ -2,15 +2,24 @@
 // resolve_execute.wgsl — M6: Intent-aware resolve + execute.
 // Metabolism, death, nutrient cycling, replication, movement, AND predation.
 // Temperature modulates metabolism cost and mutation rate.
-// Prepended with common.wgsl at pipeline creation.
+// Includes common.wgsl and brick_common.wgsl.
 //
 // Bind group 0:
 //   [0] voxel_read:   storage<array<u32>, read>
 //   [1] voxel_write:  storage<array<u32>, read_write>
 //   [2] params:       uniform<SimParams>
 //   [3] intent_read:  storage<array<u32>, read>
-//   [4] temp_read:    storage<array<f32>, read>
+//   [4] temp_read:    storage<array<f32>, read> (TEMPERATURE)
+//   [5] chem_read:    storage<array<u32>, read> (TOXIN_FIELD)
+//   [11] region:      uniform<DispatchRegion>, dynamic offset (active region in dense mode)
+//   [12] energy_flow: storage<array<atomic<u32>>, read_write> (per-pathway, conflict and turnover counters,
+//                     then the speciation log at LINEAGE_LOG_OFFSET)
+//   [13] rules:       uniform<RuleParams> (mutation-rate gene bounds, arbitration policy, protected species)
+//   [14] tick:        uniform<TickParams>, dynamic offset (this tick's slot)
 // ============================================================
+
+#include "common.wgsl"
+#include "brick_common.wgsl"
 //
 // ---- CASE ENUMERATION (SH-1: mandatory before implementation) ----
 //
@@ -18,7 +27,7 @@
 //   E1: No contenders → nutrient spawn roll or stay empty
 //   E2: Exactly one REPLICATE contender → write offspring
 //   E3: Exactly one MOVE contender → copy mover's state, apply movement cost + metabolism
-//   E4: Multiple contenders (any mix of REPLICATE/MOVE) → highest bid wins
+//   E4: Multiple contenders (any mix of REPLICATE/MOVE) → rules.arbitration picks
 //       If winner is REPLICATE → apply E2
 //       If winner is MOVE → apply E3
 //
@@ -36,6 +45,10 @@
 //   P4b: own MOVE lost → keep position, metabolism
 //   All P cases: if energy reaches 0 after metabolism → WASTE
 //
+//   Protected species (rules.protected_species): never prey (PP1b, and
+//   their predators lose, P5b), DIE falls through to IDLE and metabolism
+//   leaves at least 1 energy, here and at a MOVE destination (E3).
+//
 //   MOVE ghost prevention:
 //   E3+: If MOVE winner is being predated (PP1 at mover's position), write EMPTY instead
 //
@@ -48,47 +61,98 @@
 //   W1: age < waste_decay_ticks → age++, copy
 //   W2: age >= waste_decay_ticks → roll for nutrient recycle or EMPTY
 //
-// Others (WALL, ENERGY_SOURCE, HEAT_SOURCE, COLD_SOURCE):
+// HEAT_SOURCE / COLD_SOURCE voxel at position P:
+//   T1: lifetime (extra[0]) == 0 → copy unchanged
+//   T2: age + 1 < lifetime → age++, copy
+//   T3: age + 1 >= lifetime → depleted, convert to EMPTY
+//
+// Others (WALL, ENERGY_SOURCE):
 //   X1: copy unchanged
+//
+// HELD voxel (FLAG_FROZEN, any type, in a brick that sits out this tick
+// under sparse LOD, or in the dilation zone between its ticks) at
+// position P:
+//   F1: copy unchanged. Held cells declare no intent and neighbors see
+//       them as absent, so nothing moves, replicates or predates into P.
 // ============================================================
 
-struct SimParams {
-    grid_size: f32,
-    tick_count: f32,
-    dt: f32,
-    nutrient_spawn_rate: f32,
-    waste_decay_ticks: f32,
-    nutrient_recycle_rate: f32,
-    movement_energy_cost: f32,
-    base_ambient_temp: f32,
-    metabolic_cost_base: f32,
-    replication_energy_min: f32,
-    energy_from_nutrient: f32,
-    energy_from_source: f32,
-    diffusion_rate: f32,
-    temp_sensitivity: f32,
-    predation_energy_fraction: f32,
-    max_energy: f32,
-    overlay_mode: f32,
-    sparse_mode: f32,
-    brick_grid_dim: f32,
-    max_bricks: f32,
-};
-
 @group(0) @binding(0) var<storage, read> voxel_read: array<u32>;
 @group(0) @binding(1) var<storage, read_write> voxel_write: array<u32>;
 @group(0) @binding(2) var<uniform> params: SimParams;
 @group(0) @binding(3) var<storage, read> intent_read: array<u32>;
+#ifdef TEMPERATURE
 @group(0) @binding(4) var<storage, read> temp_read: array<f32>;
+#endif
+#ifdef TOXIN_FIELD
+// Pheromone/toxin field as written by this tick's temperature pass
+@group(0) @binding(5) var<storage, read> chem_read: array<u32>;
+#endif
+@group(0) @binding(11) var<uniform> region: DispatchRegion;
+// Energy moved per pathway and contested cells since the last stats pass
+// (types::EnergyFlow, types::ConflictCounts)
+@group(0) @binding(12) var<storage, read_write> energy_flow: array<atomic<u32>>;
+
+// Resolve rules kept outside SimParams, as types::RuleParams
+struct RuleParams {
+    mutation_min: u32,  // bounds of the mutation-rate gene (byte 3)
+    mutation_max: u32,
+    arbitration: u32,   // types::ArbitrationPolicy
+    _pad: u32,
+    // MAX_PROTECTED_SPECIES ids, one per component; 0 = unused
+    protected_species: array<vec4<u32>, MAX_PROTECTED_SPECIES / 4u>,
+};
+@group(0) @binding(13) var<uniform> rules: RuleParams;
+@group(0) @binding(14) var<uniform> tick: TickParams;
+
+// Counter words, matching types::EnergyPath
+const FLOW_PHOTOSYNTHESIS: u32 = 0u;
+const FLOW_NUTRIENT: u32 = 1u;
+const FLOW_PREDATION: u32 = 2u;
+const FLOW_METABOLISM: u32 = 3u;
+const FLOW_DECAY: u32 = 4u;
+const FLOW_TICKS: u32 = 5u;
+// types::CONFLICT_*_WORD / REJECTED_*_WORD
+const FLOW_CONFLICT_PLACEMENT: u32 = 6u;
+const FLOW_CONFLICT_PREDATION: u32 = 7u;
+const FLOW_REJECTED_PLACEMENT: u32 = 8u;
+const FLOW_REJECTED_PREDATION: u32 = 9u;
+// types::BIRTHS_WORD / DEATHS_WORD
+const FLOW_BIRTHS: u32 = 10u;
+const FLOW_DEATHS: u32 = 11u;
+const FLOW_WORDS: u32 = 12u;
+
+// This invocation's counters, summed per workgroup before touching the
+// buffer. FLOW_TICKS stays zero here.
+var<private> flow: array<u32, FLOW_WORDS>;
+var<workgroup> wg_flow: array<atomic<u32>, FLOW_WORDS>;
+
+// Contenders seen by the latest find_*_winner call
+var<private> contest_size: u32;
 
 // ---- Local helpers ----
 
-fn pack_word0(vtype: u32, flags: u32, energy: u32) -> u32 {
-    return (vtype & 0xFFu) | ((flags & 0xFFu) << 8u) | ((energy & 0xFFFFu) << 16u);
+// Credit gain absorbed under the energy cap to light and nutrients in
+// proportion to the raw gains (types::split_gain).
+fn record_gain(from_light: u32, from_nutrients: u32, absorbed: u32) {
+    let total = from_light + from_nutrients;
+    if total == 0u {
+        return;
+    }
+    let light = absorbed * from_light / total;
+    flow[FLOW_PHOTOSYNTHESIS] += light;
+    flow[FLOW_NUTRIENT] += absorbed - light;
 }
 
-fn pack_word1(age: u32, species_id: u32) -> u32 {
-    return (age & 0xFFFFu) | ((species_id & 0xFFFFu) << 16u);
+// Append (parent, child, tick) to the speciation log (types::LineageEvent);
+// past LINEAGE_LOG_CAPACITY records only the append counter grows.
+fn log_speciation(parent_species: u32, child_species: u32) {
+    let slot = atomicAdd(&energy_flow[LINEAGE_LOG_OFFSET], 1u);
+    if slot < LINEAGE_LOG_CAPACITY {
+        let base = LINEAGE_LOG_OFFSET + 1u + slot * 3u;
+        atomicStore(&energy_flow[base], parent_species);
+        atomicStore(&energy_flow[base + 1u], child_species);
+        atomicStore(&energy_flow[base + 2u], tick.tick_count);
+    }
 }
 
 fn write_voxel(idx: u32, w0: u32, w1: u32, w2: u32, w3: u32, w4: u32, w5: u32, w6: u32, w7: u32) {
@@ -124,27 +188,150 @@ fn neighbor_pos(pos: vec3<u32>, d: u32) -> vec3<u32> {
     return vec3<u32>(vec3<i32>(pos) + NEIGHBORS[d]);
 }
 
-// Get buffer index for a neighbor, sparse-aware.
+// Frozen cells, cells whose brick sits out this tick (sparse LOD) and
+// dilation-zone cells between the zone's ticks (pos is the cell's)
+// keep their state.
+fn cell_held(idx: u32, pos: vec3<u32>) -> bool {
+    return voxel_is_frozen(&voxel_read, idx) || brick_skips_tick(idx, tick.tick_count)
+        || dilation_skips_tick(params, pos, tick.tick_count);
+}
+
+// Get buffer index for a neighbor, sparse-aware. Held neighbors are
+// absent (0xFFFFFFFF): they neither feed, crowd nor contend.
 fn get_neighbor(pos: vec3<u32>, d: u32, gs: u32) -> u32 {
+    var ni: u32;
     if params.sparse_mode > 0.0 {
-        return sparse_neighbor(pos, d, gs);
+        ni = sparse_neighbor(pos, d, gs);
     } else {
-        return neighbor_in_direction(pos, d, gs);
+        ni = neighbor_in_direction(pos, d, gs);
     }
+    if ni != 0xFFFFFFFFu && cell_held(ni, neighbor_pos(pos, d)) {
+        return 0xFFFFFFFFu;
+    }
+    return ni;
+}
+
+// Extra metabolic cost for protocell neighbors beyond crowding_threshold.
+fn crowding_penalty(crowd: u32) -> u32 {
+    let excess = max(f32(crowd) - params.crowding_threshold, 0.0);
+    return u32(excess * params.crowding_cost);
+}
+
+// Per-cell energy ceiling: storage organelles (gene 14) raise max_energy,
+// up to the u16 energy field.
+fn energy_cap(storage: u32) -> u32 {
+    let bonus = f32(storage) / 255.0 * max(params.storage_capacity_bonus, 0.0);
+    return min(u32(params.max_energy * (1.0 + bonus)), VOXEL_ENERGY_MASK);
+}
+
+// Base metabolic cost, raised by storage capacity.
+fn base_metabolic_cost(storage: u32) -> u32 {
+    return u32(params.metabolic_cost_base * (1.0 + f32(storage) / 255.0 * max(params.storage_metabolic_cost, 0.0)));
+}
+
+// Temperature of the cell at idx; ambient everywhere without TEMPERATURE.
+fn cell_temp(idx: u32) -> f32 {
+#ifdef TEMPERATURE
+    return temp_read[idx];
+#else
+    return params.base_ambient_temp;
+#endif
+}
+
+// Energy lost to heat shock at this temperature.
+fn heat_shock(local_temp: f32) -> u32 {
+    return select(0u, u32(params.heat_shock_damage), local_temp > params.heat_shock_temp);
+}
+
+// Energy lost to field toxin above this cell's resistance (gene 6).
+// None without TOXIN_FIELD.
+fn toxin_damage(pos: vec3<u32>, resistance: u32, gs: u32) -> u32 {
+#ifdef TOXIN_FIELD
+    let toxin = pheromone_get_toxin(chem_read[pheromone_index(pos, gs)]);
+    return u32(max(params.toxin_damage, 0.0) * f32(max(toxin, resistance) - resistance) / 255.0);
+#else
+    return 0u;
+#endif
+}
+
+// Energy spent secreting toxin into the field; none without TOXIN_FIELD.
+fn secretion_cost(byte13: u32) -> u32 {
+#ifdef TOXIN_FIELD
+    return u32(max(params.toxin_secretion_cost, 0.0) * f32(genome_toxin_secretion(byte13)) / 255.0);
+#else
+    return 0u;
+#endif
+}
+
+// FLAG_IN_GOAL if pos lies in the goal zone (empty box when disabled).
+fn goal_flag(pos: vec3<u32>) -> u32 {
+    let p = vec3<f32>(pos);
+    let lo = vec3<f32>(params.goal_min_x, params.goal_min_y, params.goal_min_z);
+    let hi = vec3<f32>(params.goal_max_x, params.goal_max_y, params.goal_max_z);
+    return select(0u, FLAG_IN_GOAL, all(p >= lo) && all(p <= hi));
+}
+
+// ---- Conflict arbitration ----
+// Picks one of n contenders for the cell at target_pos by rules.arbitration
+// (types::ArbitrationPolicy::pick). Every bid is nonzero. The seed depends
+// only on the cell and tick, so the cell and each contender agree on the
+// winner. Returns the winning slot.
+
+fn arbitrate(n: u32, bids: ptr<function, array<u32, 6>>, idxs: ptr<function, array<u32, 6>>,
+             dirs: ptr<function, array<u32, 6>>, target_pos: vec3<u32>, gs: u32) -> u32 {
+    let seed = prng_seed(grid_index(target_pos, gs), tick.tick_count, gs, 0x6u);
+    var best: u32 = 0u;
+    switch rules.arbitration {
+        case 1u: { // Weighted random by bid
+            var total: u32 = 0u;
+            for (var i: u32 = 0u; i < n; i++) {
+                total += (*bids)[i];
+            }
+            var r = pcg_hash(seed) % max(total, 1u);
+            for (var i: u32 = 0u; i < n; i++) {
+                if r < (*bids)[i] {
+                    return i;
+                }
+                r -= (*bids)[i];
+            }
+            best = n - 1u;
+        }
+        case 2u: { // Hashed priority, ignoring bids
+            var best_priority = pcg_hash(seed ^ ((*dirs)[0] * 0x9E3779B9u));
+            for (var i: u32 = 1u; i < n; i++) {
+                let priority = pcg_hash(seed ^ ((*dirs)[i] * 0x9E3779B9u));
+                if priority > best_priority {
+                    best_priority = priority;
+                    best = i;
+                }
+            }
+        }
+        default: { // Highest bid; tie-break: higher voxel index
+            for (var i: u32 = 1u; i < n; i++) {
+                let bid = (*bids)[i];
+                if bid > (*bids)[best] || (bid == (*bids)[best] && (*idxs)[i] > (*idxs)[best]) {
+                    best = i;
+                }
+            }
+        }
+    }
+    return best;
 }
 
 // ---- Contender winner resolution ----
 // Reads 6 neighbors of target_pos. For each: check if intent action is REPLICATE
 // or MOVE and direction points toward target_pos (using opposite_direction).
 // Returns vec4(winner_voxel_index, winner_bid, winner_action, winner_direction).
-// If no winner, returns (0xFFFFFFFF, 0, 0, 0).
+// If no winner, returns (0xFFFFFFFF, 0, 0, 0). Zero bids never win.
 // winner_direction = the direction from target_pos to the winner.
+// Sets contest_size to the number of contenders.
 
 fn find_contender_winner(target_pos: vec3<u32>, gs: u32) -> vec4<u32> {
-    var best_idx: u32 = 0xFFFFFFFFu;
-    var best_bid: u32 = 0u;
-    var best_action: u32 = 0u;
-    var best_dir: u32 = 0u;
+    var bids: array<u32, 6>;
+    var idxs: array<u32, 6>;
+    var dirs: array<u32, 6>;
+    var actions: array<u32, 6>;
+    var n: u32 = 0u;
 
     for (var d: u32 = 0u; d < 6u; d++) {
         let ni = get_neighbor(target_pos, d, gs);
@@ -164,34 +351,62 @@ fn find_contender_winner(target_pos: vec3<u32>, gs: u32) -> vec4<u32> {
             continue;
         }
         let bid = intent_get_bid(intent);
-        // Highest bid wins; tie-break: higher voxel index
-        if bid > best_bid || (bid == best_bid && ni > best_idx) {
-            best_bid = bid;
-            best_idx = ni;
-            best_action = action;
-            best_dir = d;
+        if bid == 0u {
+            continue;
         }
+        bids[n] = bid;
+        idxs[n] = ni;
+        dirs[n] = d;
+        actions[n] = action;
+        n++;
     }
 
-    return vec4<u32>(best_idx, best_bid, best_action, best_dir);
+    contest_size = n;
+    if n == 0u {
+        return vec4<u32>(0xFFFFFFFFu, 0u, 0u, 0u);
+    }
+    let w = arbitrate(n, &bids, &idxs, &dirs, target_pos, gs);
+    return vec4<u32>(idxs[w], bids[w], actions[w], dirs[w]);
+}
+
+// Whether members of species_id neither starve nor fall to predators
+fn species_protected(species_id: u32) -> bool {
+    if species_id == 0u {
+        return false;
+    }
+    for (var i: u32 = 0u; i < MAX_PROTECTED_SPECIES; i++) {
+        if rules.protected_species[i / 4u][i % 4u] == species_id {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Energy after metabolism; protected cells keep at least 1.
+fn keep_alive(energy: u32, species_id: u32) -> u32 {
+    return select(energy, max(energy, 1u), species_protected(species_id));
 }
 
 // ---- Predation winner resolution ----
 // Reads 6 neighbors of target_pos for PREDATE intents targeting it.
 // Returns vec2(winner_voxel_index, winner_bid).
-// If no predator, returns (0xFFFFFFFF, 0).
-
-fn find_predation_winner(target_pos: vec3<u32>, gs: u32) -> vec2<u32> {
-    var best_idx: u32 = 0xFFFFFFFFu;
-    var best_bid: u32 = 0u;
+// If no predator, or the prey (of target_species) is protected, returns
+// (0xFFFFFFFF, 0). Zero bids never win.
+// Sets contest_size to the number of predators (0 for protected prey).
+
+fn find_predation_winner(target_pos: vec3<u32>, target_species: u32, gs: u32) -> vec2<u32> {
+    var bids: array<u32, 6>;
+    var idxs: array<u32, 6>;
+    var dirs: array<u32, 6>;
+    var n: u32 = 0u;
+
+    if species_protected(target_species) {
+        contest_size = 0u;
+        return vec2<u32>(0xFFFFFFFFu, 0u);
+    }
 
     for (var d: u32 = 0u; d < 6u; d++) {
-        var ni: u32;
-        if params.sparse_mode > 0.0 {
-            ni = sparse_neighbor(target_pos, d, gs);
-        } else {
-            ni = neighbor_in_direction(target_pos, d, gs);
-        }
+        let ni = get_neighbor(target_pos, d, gs);
         if ni == 0xFFFFFFFFu {
             continue;
         }
@@ -205,13 +420,21 @@ fn find_predation_winner(target_pos: vec3<u32>, gs: u32) -> vec2<u32> {
             continue;
         }
         let bid = intent_get_bid(intent);
-        if bid > best_bid || (bid == best_bid && ni > best_idx) {
-            best_bid = bid;
-            best_idx = ni;
+        if bid == 0u {
+            continue;
         }
+        bids[n] = bid;
+        idxs[n] = ni;
+        dirs[n] = d;
+        n++;
     }
 
-    return vec2<u32>(best_idx, best_bid);
+    contest_size = n;
+    if n == 0u {
+        return vec2<u32>(0xFFFFFFFFu, 0u);
+    }
+    let w = arbitrate(n, &bids, &idxs, &dirs, target_pos, gs);
+    return vec2<u32>(idxs[w], bids[w]);
 }
 
 // ---- Mutation ----
@@ -227,7 +450,12 @@ fn mutate_genome(rng_ptr: ptr<function, u32>, mutation_rate: u32,
         let word_i = byte_i / 4u;
         let shift = (byte_i % 4u) * 8u;
         if (roll & 0xFFu) < mutation_rate {
-            let new_byte = (roll >> 8u) & 0xFFu;
+            var new_byte = (roll >> 8u) & 0xFFu;
+            // Meta-mutation: the mutation-rate gene stays within its bounds
+            if byte_i == 3u {
+                let span = rules.mutation_max - rules.mutation_min + 1u;
+                new_byte = rules.mutation_min + new_byte * span / 256u;
+            }
             // Clear old byte and set new one
             words[word_i] = (words[word_i] & ~(0xFFu << shift)) | (new_byte << shift);
         }
@@ -240,13 +468,41 @@ fn mutate_genome(rng_ptr: ptr<function, u32>, mutation_rate: u32,
 
 // ---- Entry point ----
 
-@compute @workgroup_size(4, 4, 4)
-fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
-    let gs = u32(params.grid_size);
-    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
-        return;
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn resolve_execute_main(
+    @builtin(global_invocation_id) rel: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(workgroup_id) wid: vec3<u32>,
+) {
+    if lid < FLOW_WORDS {
+        atomicStore(&wg_flow[lid], 0u);
+    }
+    workgroupBarrier();
+
+    let gid = region.origin.xyz + rel;
+    if all(gid < region.end.xyz) {
+        resolve_cell(gid);
     }
 
+    for (var p: u32 = 0u; p < FLOW_WORDS; p++) {
+        if flow[p] > 0u {
+            atomicAdd(&wg_flow[p], flow[p]);
+        }
+    }
+    workgroupBarrier();
+    if lid < FLOW_WORDS {
+        let total = atomicLoad(&wg_flow[lid]);
+        if total > 0u {
+            atomicAdd(&energy_flow[lid], total);
+        }
+    }
+    if lid == 0u && all(wid == vec3<u32>(0u)) {
+        atomicAdd(&energy_flow[FLOW_TICKS], 1u);
+    }
+}
+
+fn resolve_cell(gid: vec3<u32>) {
+    let gs = u32(params.grid_size);
     let logical_idx = grid_index(gid, gs);
     var idx: u32;
     if params.sparse_mode > 0.0 {
@@ -257,26 +513,59 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     }
     let vtype = voxel_get_type(&voxel_read, idx);
 
+    // F1: held cells are copied unchanged
+    if cell_held(idx, gid) {
+        copy_voxel(idx);
+        return;
+    }
+
     // Initialize PRNG with dispatch salt 0x2
     // Use logical index for PRNG, not pool index, to preserve determinism
-    var rng = prng_seed(logical_idx, u32(params.tick_count), gs, 0x2u);
+    var rng = prng_seed(logical_idx, tick.tick_count, gs, 0x2u);
 
     switch vtype {
         case 0u: { // EMPTY — cases E1, E2, E3, E4
             // Check if any neighbor wants to replicate or move into this cell
             let winner = find_contender_winner(gid, gs);
+            if contest_size > 1u {
+                flow[FLOW_CONFLICT_PLACEMENT] += 1u;
+                flow[FLOW_REJECTED_PLACEMENT] += contest_size - 1u;
+            }
             let winner_idx = winner.x;
             let winner_action = winner.z;
 
             if winner_idx == 0xFFFFFFFFu {
-                // E1: No contenders — nutrient spawn or stay empty
+                // E1: No contenders — nutrient regrowth or stay empty.
+                // Logistic in adjacent nutrients (seeds new patches, saturates
+                // at nutrient_capacity), boosted by adjacent waste, shaped by
+                // the spatial pattern.
                 let roll = pcg_next(&rng);
-                let threshold = u32(params.nutrient_spawn_rate * 4294967295.0);
+                var nutrients: u32 = 0u;
+                var wastes: u32 = 0u;
+                for (var d: u32 = 0u; d < 6u; d++) {
+                    let ni = get_neighbor(gid, d, gs);
+                    if ni == 0xFFFFFFFFu {
+                        continue;
+                    }
+                    let ntype = voxel_get_type(&voxel_read, ni);
+                    if ntype == VOXEL_NUTRIENT {
+                        nutrients++;
+                    } else if ntype == VOXEL_WASTE {
+                        wastes++;
+                    }
+                }
+                let capacity = max(params.nutrient_capacity, 1.0);
+                let logistic = f32(1u + nutrients) * max(1.0 - f32(nutrients) / capacity, 0.0);
+                let boost = 1.0 + params.nutrient_waste_boost * f32(wastes);
+                let weight = nutrient_pattern_weight(gid, gs, tick.tick_count,
+                    u32(params.nutrient_pattern), params.nutrient_front_speed);
+                let spawn_p = clamp(params.nutrient_spawn_rate * logistic * boost * weight, 0.0, 1.0);
+                let threshold = u32(spawn_p * 4294967295.0);
                 if roll < threshold {
                     let energy = u32(params.energy_from_nutrient);
                     write_voxel(idx,
-                        pack_word0(VOXEL_NUTRIENT, 0u, energy),
-                        pack_word1(0u, 0u),
+                        voxel_pack_word0(VOXEL_NUTRIENT, 0u, energy),
+                        voxel_pack_word1(0u, 0u),
                         0u, 0u, 0u, 0u, 0u, 0u);
                 } else {
                     write_empty(idx);
@@ -286,9 +575,13 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
                 let parent_energy = voxel_get_energy(&voxel_read, winner_idx);
                 let split_ratio_byte = genome_get_byte(&voxel_read, winner_idx, 10u);
                 let mutation_rate = genome_get_byte(&voxel_read, winner_idx, 3u);
+                let parent_adhesion = genome_get_byte(&voxel_read, winner_idx, 12u);
 
-                // Offspring energy = parent_energy * (255 - split_ratio) / 255
-                let offspring_energy = (parent_energy * (255u - split_ratio_byte)) / 255u;
+                // Offspring energy = base * (255 - split_ratio) / 255, plus the
+                // parent's whole stored surplus above max_energy
+                let parent_base = min(parent_energy, u32(params.max_energy));
+                let stored = parent_energy - parent_base;
+                var offspring_energy = (parent_base * (255u - split_ratio_byte)) / 255u + stored;
 
                 // Copy parent genome
                 var g0 = voxel_get_genome_word(&voxel_read, winner_idx, 0u);
@@ -297,7 +590,7 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
                 var g3 = voxel_get_genome_word(&voxel_read, winner_idx, 3u);
 
                 // Temperature-modulated mutation rate
-                let local_temp = temp_read[idx];
+                let local_temp = cell_temp(idx);
                 let temp_mod = compute_temp_modifier(local_temp, params.temp_sensitivity);
                 let effective_mutation_rate = min(u32(f32(mutation_rate) * temp_mod), 255u);
 
@@ -306,16 +599,27 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
 
                 // Compute species_id from MUTATED genome (SIM-5: never 0)
                 let species_id = compute_species_id(g0, g1, g2, g3);
+                let parent_species = voxel_get_species_id(&voxel_read, winner_idx);
+                if species_id != parent_species {
+                    log_speciation(parent_species, species_id);
+                }
+                flow[FLOW_BIRTHS] += 1u;
+                offspring_energy = min(offspring_energy, energy_cap(genome_get_byte_from_words(g0, g1, g2, g3, 14u)));
+
+                // Adhesive parents keep the offspring linked, remembering the
+                // parent → offspring direction as its growth axis
+                let offspring_flags = select(0u, FLAG_LINKED | opposite_direction(winner.w),
+                    parent_adhesion >= ADHESION_LINK_MIN);
 
                 // Write offspring: age=0, offspring energy, mutated genome
                 write_voxel(idx,
-                    pack_word0(VOXEL_PROTOCELL, 0u, offspring_energy),
-                    pack_word1(0u, species_id),
+                    voxel_pack_word0(VOXEL_PROTOCELL, offspring_flags | goal_flag(gid), offspring_energy),
+                    voxel_pack_word1(0u, species_id),
                     g0, g1, g2, g3, 0u, 0u);
             } else {
                 // E3/E4 (MOVE winner): Check if mover is being predated
                 let mover_pos = neighbor_pos(gid, winner.w);
-                let pred_check = find_predation_winner(mover_pos, gs);
+                let pred_check = find_predation_winner(mover_pos, voxel_get_species_id(&voxel_read, winner.x), gs);
                 if pred_check.x != 0xFFFFFFFFu {
                     // Mover is being predated — don't copy, stay EMPTY
                     write_empty(idx);
@@ -333,9 +637,14 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
                 let metabolic_efficiency = genome_get_byte_from_words(g0, g1, g2, g3, 0u);
                 let metabolic_rate = genome_get_byte_from_words(g0, g1, g2, g3, 1u);
                 let photosynthetic_rate = genome_get_byte_from_words(g0, g1, g2, g3, 9u);
+                let storage_move = genome_get_byte_from_words(g0, g1, g2, g3, 14u);
+                let toxin_move = toxin_damage(gid, genome_get_byte_from_words(g0, g1, g2, g3, 6u), gs);
 
                 // Metabolism at destination: scan OWN neighbors for energy gain
-                var gain: u32 = 0u;
+                // and crowding (the mover's vacated source doesn't count)
+                var from_light: u32 = 0u;
+                var from_nutrients: u32 = 0u;
+                var crowd: u32 = 0u;
                 for (var d: u32 = 0u; d < 6u; d++) {
                     let ni = get_neighbor(gid, d, gs);
                     if ni == 0xFFFFFFFFu {
@@ -343,36 +652,47 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
                     }
                     let ntype = voxel_get_type(&voxel_read, ni);
                     if ntype == VOXEL_ENERGY_SOURCE {
-                        gain += (photosynthetic_rate * u32(params.energy_from_source)) / 255u;
+                        from_light += (photosynthetic_rate * u32(params.energy_from_source)) / 255u;
                     } else if ntype == VOXEL_NUTRIENT {
-                        gain += (metabolic_efficiency * u32(params.energy_from_nutrient)) / 255u;
+                        from_nutrients += (metabolic_efficiency * u32(params.energy_from_nutrient)) / 255u;
+                    } else if ntype == VOXEL_PROTOCELL && d != winner.w {
+                        crowd++;
                     }
                 }
+                let gain = from_light + from_nutrients;
 
-                let cost = u32(params.metabolic_cost_base) * (255u + metabolic_rate) / 255u;
-                let local_temp_move = temp_read[idx];
+                let cost = base_metabolic_cost(storage_move) * (255u + metabolic_rate) / 255u;
+                let local_temp_move = cell_temp(idx);
                 let temp_mod_move = compute_temp_modifier(local_temp_move, params.temp_sensitivity);
-                let effective_cost_move = u32(f32(cost) * temp_mod_move);
+                let effective_cost_move = u32(f32(cost) * temp_mod_move) + crowding_penalty(crowd)
+                    + heat_shock(local_temp_move) + toxin_move
+                    + secretion_cost(genome_get_byte_from_words(g0, g1, g2, g3, 13u));
                 let movement_cost = u32(params.movement_energy_cost);
 
-                var new_energy = min(mover_energy + gain, u32(params.max_energy));
+                let fed_energy = min(mover_energy + gain, energy_cap(storage_move));
+                var new_energy = fed_energy;
                 // Saturating subtract movement cost (SIM-4)
                 new_energy = select(0u, new_energy - movement_cost, new_energy >= movement_cost);
                 // Saturating subtract metabolic cost (SIM-4)
                 new_energy = select(0u, new_energy - effective_cost_move, new_energy >= effective_cost_move);
+                new_energy = keep_alive(new_energy, mover_species);
+                record_gain(from_light, from_nutrients, select(0u, fed_energy - mover_energy, fed_energy >= mover_energy));
+                // A protected cell kept at 1 energy may end above fed_energy
+                flow[FLOW_METABOLISM] += select(0u, fed_energy - new_energy, fed_energy >= new_energy);
 
-                let new_age = min(mover_age + 1u, 0xFFFFu);
+                let new_age = min(mover_age + 1u, VOXEL_AGE_MASK);
 
                 if new_energy == 0u {
                     // Death at destination → WASTE
+                    flow[FLOW_DEATHS] += 1u;
                     write_voxel(idx,
-                        pack_word0(VOXEL_WASTE, 0u, 0u),
-                        pack_word1(0u, mover_species),
+                        voxel_pack_word0(VOXEL_WASTE, select(0u, WASTE_FLAG_TOXIN, toxin_move > 0u), 0u),
+                        voxel_pack_word1(0u, mover_species),
                         0u, 0u, 0u, 0u, 0u, 0u);
                 } else {
                     write_voxel(idx,
-                        pack_word0(VOXEL_PROTOCELL, 0u, new_energy),
-                        pack_word1(new_age, mover_species),
+                        voxel_pack_word0(VOXEL_PROTOCELL, goal_flag(gid), new_energy),
+                        voxel_pack_word1(new_age, mover_species),
                         g0, g1, g2, g3, 0u, 0u);
                 }
                 } // end pred_check else
@@ -382,6 +702,7 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             let energy = voxel_get_energy(&voxel_read, idx);
             let age = voxel_get_age(&voxel_read, idx);
             let species_id = voxel_get_species_id(&voxel_read, idx);
+            let flags = voxel_get_flags(&voxel_read, idx);
 
             // Read genome
             let g0 = voxel_get_genome_word(&voxel_read, idx, 0u);
@@ -393,6 +714,7 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             let metabolic_rate = genome_get_byte(&voxel_read, idx, 1u);
             let photosynthetic_rate = genome_get_byte(&voxel_read, idx, 9u);
             let split_ratio_byte = genome_get_byte(&voxel_read, idx, 10u);
+            let storage = genome_get_byte(&voxel_read, idx, 14u);
 
             // Always consume 16 PRNG advances for determinism (mutation slots)
             for (var i: u32 = 0u; i < 16u; i++) {
@@ -400,12 +722,20 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             }
 
             // PP1: Check if this protocell is being predated
-            let pred_winner = find_predation_winner(gid, gs);
+            let pred_winner = find_predation_winner(gid, species_id, gs);
+            if contest_size > 1u {
+                flow[FLOW_CONFLICT_PREDATION] += 1u;
+                flow[FLOW_REJECTED_PREDATION] += contest_size - 1u;
+            }
             if pred_winner.x != 0xFFFFFFFFu {
                 // PP1a: We're prey — become WASTE. Own intent cancelled.
+                // What the predator doesn't take is lost.
+                let taken = min(u32(f32(energy) * params.predation_energy_fraction), energy);
+                flow[FLOW_DECAY] += energy - taken;
+                flow[FLOW_DEATHS] += 1u;
                 write_voxel(idx,
-                    pack_word0(VOXEL_WASTE, 0u, 0u),
-                    pack_word1(0u, species_id),
+                    voxel_pack_word0(VOXEL_WASTE, 0u, 0u),
+                    voxel_pack_word1(0u, species_id),
                     0u, 0u, 0u, 0u, 0u, 0u);
                 return;
             }
@@ -414,11 +744,13 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             let my_intent = intent_read[idx];
             let my_action = intent_get_action(my_intent);
 
-            // P1: DIE
-            if my_action == ACTION_DIE {
+            // P1: DIE (protected species idle on at zero energy instead)
+            if my_action == ACTION_DIE && !species_protected(species_id) {
+                flow[FLOW_DECAY] += energy;
+                flow[FLOW_DEATHS] += 1u;
                 write_voxel(idx,
-                    pack_word0(VOXEL_WASTE, 0u, 0u),
-                    pack_word1(0u, species_id),
+                    voxel_pack_word0(VOXEL_WASTE, 0u, 0u),
+                    voxel_pack_word1(0u, species_id),
                     0u, 0u, 0u, 0u, 0u, 0u);
                 return;
             }
@@ -433,13 +765,14 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
 
                 if target_ni != 0xFFFFFFFFu {
                     let target_pos = neighbor_pos(gid, my_dir);
-                    let pred_win = find_predation_winner(target_pos, gs);
+                    let pred_win = find_predation_winner(target_pos, voxel_get_species_id(&voxel_read, target_ni), gs);
 
                     if pred_win.x == idx {
                         // P5a: Won predation — gain energy fraction from prey
                         let prey_energy = voxel_get_energy(&voxel_read, target_ni);
                         let gained = u32(f32(prey_energy) * params.predation_energy_fraction);
-                        work_energy = min(energy + gained, u32(params.max_energy));
+                        work_energy = min(energy + gained, energy_cap(storage));
+                        flow[FLOW_PREDATION] += select(0u, work_energy - energy, work_energy >= energy);
                     }
                     // P5b: Lost — work_energy stays as full energy (idle fallback)
                 }
@@ -454,8 +787,9 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
 
                     if winner.x == idx {
                         // P2a: Won the replication contest
-                        // Parent keeps: energy * split_ratio / 255
-                        work_energy = (energy * split_ratio_byte) / 255u;
+                        // Parent keeps: base * split_ratio / 255 (stored surplus
+                        // goes to the offspring)
+                        work_energy = (min(energy, u32(params.max_energy)) * split_ratio_byte) / 255u;
                     }
                     // P2b: Lost — work_energy stays as full energy
                 }
@@ -482,8 +816,10 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
                 return;
             }
 
-            // Metabolism: scan neighbors for energy gain
-            var gain: u32 = 0u;
+            // Metabolism: scan neighbors for energy gain and crowding
+            var from_light: u32 = 0u;
+            var from_nutrients: u32 = 0u;
+            var crowd: u32 = 0u;
             for (var d: u32 = 0u; d < 6u; d++) {
                 let ni = get_neighbor(gid, d, gs);
                 if ni == 0xFFFFFFFFu {
@@ -491,36 +827,53 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
                 }
                 let ntype = voxel_get_type(&voxel_read, ni);
                 if ntype == VOXEL_ENERGY_SOURCE {
-                    gain += (photosynthetic_rate * u32(params.energy_from_source)) / 255u;
+                    from_light += (photosynthetic_rate * u32(params.energy_from_source)) / 255u;
                 } else if ntype == VOXEL_NUTRIENT {
-                    gain += (metabolic_efficiency * u32(params.energy_from_nutrient)) / 255u;
+                    from_nutrients += (metabolic_efficiency * u32(params.energy_from_nutrient)) / 255u;
+                } else if ntype == VOXEL_PROTOCELL {
+                    crowd++;
                 }
             }
+            let gain = from_light + from_nutrients;
 
-            // Metabolic cost: base * (1 + metabolic_rate/255)
-            let cost = u32(params.metabolic_cost_base) * (255u + metabolic_rate) / 255u;
-            let local_temp_p = temp_read[idx];
+            // Metabolic cost: base * (1 + metabolic_rate/255), plus crowding
+            let cost = base_metabolic_cost(storage) * (255u + metabolic_rate) / 255u;
+            let local_temp_p = cell_temp(idx);
+            let gene13 = genome_get_byte(&voxel_read, idx, 13u);
             let temp_mod_p = compute_temp_modifier(local_temp_p, params.temp_sensitivity);
-            let effective_cost_p = u32(f32(cost) * temp_mod_p);
+            var effective_cost_p = u32(f32(cost) * temp_mod_p) + crowding_penalty(crowd);
+            // Dormant cells (same rule as the intent pass) idle at reduced cost
+            let dormant = is_cold_dormant(local_temp_p, genome_cold_tolerance(gene13), params.dormancy_temp);
+            if dormant {
+                effective_cost_p = u32(f32(effective_cost_p) * max(params.dormancy_cost_scale, 0.0));
+            }
+            let toxin_p = toxin_damage(gid, genome_get_byte(&voxel_read, idx, 6u), gs);
+            effective_cost_p += heat_shock(local_temp_p) + toxin_p + secretion_cost(gene13);
+            let kept_flags = flags & ~(FLAG_DORMANT | FLAG_IN_GOAL);
+            let new_flags = kept_flags | select(0u, FLAG_DORMANT, dormant) | goal_flag(gid);
 
-            // Apply gain, clamp to max_energy
-            var new_energy = min(work_energy + gain, u32(params.max_energy));
+            // Apply gain, clamp to this cell's capacity
+            let fed_energy = min(work_energy + gain, energy_cap(storage));
 
             // Saturating subtract cost (SIM-4)
-            new_energy = select(0u, new_energy - effective_cost_p, new_energy >= effective_cost_p);
+            let new_energy = keep_alive(select(0u, fed_energy - effective_cost_p, fed_energy >= effective_cost_p), species_id);
+            record_gain(from_light, from_nutrients, select(0u, fed_energy - work_energy, fed_energy >= work_energy));
+            // A protected cell kept at 1 energy may end above fed_energy
+            flow[FLOW_METABOLISM] += select(0u, fed_energy - new_energy, fed_energy >= new_energy);
 
-            let new_age = min(age + 1u, 0xFFFFu);
+            let new_age = min(age + 1u, VOXEL_AGE_MASK);
 
             if new_energy == 0u {
                 // Death after metabolism → WASTE
+                flow[FLOW_DEATHS] += 1u;
                 write_voxel(idx,
-                    pack_word0(VOXEL_WASTE, 0u, 0u),
-                    pack_word1(0u, species_id),
+                    voxel_pack_word0(VOXEL_WASTE, select(0u, WASTE_FLAG_TOXIN, toxin_p > 0u), 0u),
+                    voxel_pack_word1(0u, species_id),
                     0u, 0u, 0u, 0u, 0u, 0u);
             } else {
                 write_voxel(idx,
-                    pack_word0(VOXEL_PROTOCELL, 0u, new_energy),
-                    pack_word1(new_age, species_id),
+                    voxel_pack_word0(VOXEL_PROTOCELL, new_flags, new_energy),
+                    voxel_pack_word1(new_age, species_id),
                     g0, g1, g2, g3, 0u, 0u);
             }
         }
@@ -540,21 +893,21 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             }
 
             let new_energy = select(0u, energy - adj_protocells, energy >= adj_protocells);
-            let new_age = min(age + 1u, 0xFFFFu);
+            let new_age = min(age + 1u, VOXEL_AGE_MASK);
 
             if new_energy == 0u {
                 write_empty(idx);
             } else {
                 write_voxel(idx,
-                    pack_word0(VOXEL_NUTRIENT, 0u, new_energy),
-                    pack_word1(new_age, 0u),
+                    voxel_pack_word0(VOXEL_NUTRIENT, 0u, new_energy),
+                    voxel_pack_word1(new_age, 0u),
                     0u, 0u, 0u, 0u, 0u, 0u);
             }
         }
         case 5u: { // WASTE — cases W1, W2
             let age = voxel_get_age(&voxel_read, idx);
             let species_id = voxel_get_species_id(&voxel_read, idx);
-            let new_age = min(age + 1u, 0xFFFFu);
+            let new_age = min(age + 1u, VOXEL_AGE_MASK);
 
             if new_age >= u32(params.waste_decay_ticks) {
                 let roll = pcg_next(&rng);
@@ -562,21 +915,38 @@ fn resolve_execute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
                 if roll < threshold {
                     let energy = u32(params.energy_from_nutrient);
                     write_voxel(idx,
-                        pack_word0(VOXEL_NUTRIENT, 0u, energy),
-                        pack_word1(0u, 0u),
+                        voxel_pack_word0(VOXEL_NUTRIENT, 0u, energy),
+                        voxel_pack_word1(0u, 0u),
                         0u, 0u, 0u, 0u, 0u, 0u);
                 } else {
                     write_empty(idx);
                 }
             } else {
                 write_voxel(idx,
-                    pack_word0(VOXEL_WASTE, 0u, 0u),
-                    pack_word1(new_age, species_id),
+                    voxel_pack_word0(VOXEL_WASTE, 0u, 0u),
+                    voxel_pack_word1(new_age, species_id),
                     0u, 0u, 0u, 0u, 0u, 0u);
             }
         }
+        case 6u, 7u: { // HEAT_SOURCE, COLD_SOURCE — cases T1, T2, T3
+            let lifetime = (voxel_get_extra(&voxel_read, idx, 0u) >> THERMAL_LIFETIME_SHIFT) & THERMAL_LIFETIME_MASK;
+            if lifetime == 0u {
+                copy_voxel(idx);
+            } else {
+                let new_age = voxel_get_age(&voxel_read, idx) + 1u;
+                if new_age >= lifetime {
+                    write_empty(idx);
+                } else {
+                    let base = idx * VOXEL_STRIDE;
+                    write_voxel(idx,
+                        voxel_read[base],
+                        voxel_pack_word1(new_age, 0u),
+                        0u, 0u, 0u, 0u, voxel_read[base + 6u], voxel_read[base + 7u]);
+                }
+            }
+        }
         default: {
-            // WALL, ENERGY_SOURCE, HEAT_SOURCE, COLD_SOURCE — copy unchanged (X1)
+            // WALL, ENERGY_SOURCE — copy unchanged (X1)
             copy_voxel(idx);
         }
     }
@@ -1,146 +1,324 @@
 // ============================================================
-// stats_reduction.wgsl — M7: Single-stage reduction with global atomics.
-// Counts population, total energy, max energy, and species histogram.
-// Prepended with common.wgsl at pipeline creation.
+// stats_reduction.wgsl — M7: Two-stage reduction without global atomics.
+// Counts population, total energy, max energy, age, species histogram and
+// nutrient/waste/wall voxels;
+// protocells of species that find no table entry (workgroup or global) are
+// counted at STATS_OTHER_SPECIES instead of dropped.
+//   stats_reduction_main: STATS_PARTIAL_GROUPS workgroups each stride over a
+//     contiguous range of the cells (types::stats_partial_range), so a
+//     workgroup's species table sees neighbouring colonies, tree-reduce their threads' sums in shared memory and write
+//     one partial record each (STATS_PARTIAL_WORDS words: the scalars, then
+//     the workgroup species table's ids, counts and goal counts).
+//   stats_finalize_main: one workgroup folds the partials into stats_buf.
+// Sparse mode visits only the allocated bricks' pool slots (the brick
+// table's allocated-slot list). Dense mode also bounds the non-empty or frozen voxels of this tick's
+// input and output states (the active region) and counts clustered protocells.
+// Includes common.wgsl and brick_common.wgsl.
 //
 // Bind group 0:
 //   [0] voxel_buf: storage<array<u32>, read>
-//   [1] stats_buf: storage<array<atomic<u32>>, read_write>
+//   [1] stats_buf: storage<array<u32>, read_write> — finalize only
 //   [2] params: uniform<SimParams>
+//   [3] prev_voxel_buf: storage<array<u32>, read> — the tick's input state
+//   [4] partials: storage<array<u32>, read_write> — STATS_PARTIAL_GROUPS
+//       records
 //
-// Stats buffer layout (32 × u32):
-//   [0] population
-//   [1] total_energy
-//   [2] species_count (unused)
-//   [3] max_energy
-//   [4..27] species histogram: 12 entries × 2 words (species_id, count)
-//   [28..31] reserved
+// Stats buffer layout: STATS_WORDS × u32, one STATS_<NAME> offset per
+// metric (types::layout::StatsSlot). Clustered protocells have
+// >= CLUSTER_MIN_NEIGHBORS same-species face neighbors. The field
+// min/max/sums at STATS_FIELD_STATS are written by field_stats.wgsl.
 // ============================================================
 
-struct SimParams {
-    grid_size: f32,
-    tick_count: f32,
-    dt: f32,
-    nutrient_spawn_rate: f32,
-    waste_decay_ticks: f32,
-    nutrient_recycle_rate: f32,
-    movement_energy_cost: f32,
-    base_ambient_temp: f32,
-    metabolic_cost_base: f32,
-    replication_energy_min: f32,
-    energy_from_nutrient: f32,
-    energy_from_source: f32,
-    diffusion_rate: f32,
-    temp_sensitivity: f32,
-    predation_energy_fraction: f32,
-    max_energy: f32,
-    overlay_mode: f32,
-    sparse_mode: f32,
-    brick_grid_dim: f32,
-    max_bricks: f32,
-};
+#include "common.wgsl"
+#include "brick_common.wgsl"
 
 @group(0) @binding(0) var<storage, read> voxel_buf: array<u32>;
-@group(0) @binding(1) var<storage, read_write> stats_buf: array<atomic<u32>>;
+@group(0) @binding(1) var<storage, read_write> stats_buf: array<u32>;
 @group(0) @binding(2) var<uniform> params: SimParams;
+@group(0) @binding(3) var<storage, read> prev_voxel_buf: array<u32>;
+@group(0) @binding(4) var<storage, read_write> partials: array<u32>;
 
-var<workgroup> wg_pop: atomic<u32>;
-var<workgroup> wg_energy: atomic<u32>;
-var<workgroup> wg_max_energy: atomic<u32>;
-var<workgroup> wg_species_id: array<atomic<u32>, 16>;
-var<workgroup> wg_species_count: array<atomic<u32>, 16>;
+// Mirrors sim_core::stats::ACTIVE_MIN_BIAS
+const ACTIVE_MIN_BIAS: u32 = 0xFFFFu;
+// Mirrors sim_core::stats::CLUSTER_MIN_NEIGHBORS
+const CLUSTER_MIN_NEIGHBORS: u32 = 3u;
+
+// Scalar words of a partial record, combined by sum unless noted
+const P_POP: u32 = 0u;
+const P_ENERGY: u32 = 1u;
+const P_MAX_ENERGY: u32 = 2u;      // max
+const P_TOXIN_DEATHS: u32 = 3u;
+const P_CLUSTERED: u32 = 4u;
+const P_MUTATION_RATE: u32 = 5u;
+const P_BOUNDS: u32 = 6u;          // max x/y/z + 1, then ACTIVE_MIN_BIAS - min; max
+const P_OTHER: u32 = 12u;
+const P_OTHER_GOAL: u32 = 13u;
+const P_AGE: u32 = 14u;
+const P_NUTRIENT: u32 = 15u;
+const P_WASTE: u32 = 16u;
+const P_WALL: u32 = 17u;
+const P_USED: u32 = 18u;
+
+// Per-thread scalars, tree-reduced by both entry points
+var<workgroup> wg_sums: array<array<u32, P_USED>, 64>;
+// Workgroup species table of the first stage
+var<workgroup> wg_species_id: array<atomic<u32>, STATS_WG_SPECIES_SLOTS>;
+var<workgroup> wg_species_count: array<atomic<u32>, STATS_WG_SPECIES_SLOTS>;
+var<workgroup> wg_species_in_goal: array<atomic<u32>, STATS_WG_SPECIES_SLOTS>;
+// The histogram, built by the finalize workgroup
+var<workgroup> fin_species_id: array<atomic<u32>, STATS_SPECIES_SLOTS>;
+var<workgroup> fin_species_count: array<atomic<u32>, STATS_SPECIES_SLOTS>;
+var<workgroup> fin_species_in_goal: array<atomic<u32>, STATS_SPECIES_SLOTS>;
+
+// Allocated bricks and the pool slot of the i-th, from the list after the
+// brick table's intervals (SparseGrid::upload_if_dirty)
+#ifdef SPARSE
+fn allocated_brick_count() -> u32 {
+    let bgd = u32(params.brick_grid_dim);
+    return brick_table[bgd * bgd * bgd + u32(params.max_bricks)];
+}
+
+fn allocated_brick_slot(i: u32) -> u32 {
+    let bgd = u32(params.brick_grid_dim);
+    return brick_table[bgd * bgd * bgd + u32(params.max_bricks) + 1u + i];
+}
+#else
+fn allocated_brick_count() -> u32 {
+    return 0u;
+}
+
+fn allocated_brick_slot(i: u32) -> u32 {
+    return i;
+}
+#endif
+
+fn combine_sums(a: ptr<function, array<u32, P_USED>>, b: array<u32, P_USED>) {
+    for (var k = 0u; k < P_USED; k += 1u) {
+        if k == P_MAX_ENERGY || (k >= P_BOUNDS && k < P_BOUNDS + 6u) {
+            (*a)[k] = max((*a)[k], b[k]);
+        } else {
+            (*a)[k] += b[k];
+        }
+    }
+}
+
+// Tree-reduce the threads' wg_sums into wg_sums[0]
+fn reduce_sums(lid: u32, sums: array<u32, P_USED>) {
+    wg_sums[lid] = sums;
+    workgroupBarrier();
+    for (var stride = 32u; stride > 0u; stride >>= 1u) {
+        if lid < stride {
+            var merged = wg_sums[lid];
+            combine_sums(&merged, wg_sums[lid + stride]);
+            wg_sums[lid] = merged;
+        }
+        workgroupBarrier();
+    }
+}
 
 @compute @workgroup_size(64, 1, 1)
-fn stats_reduction_main(@builtin(global_invocation_id) gid: vec3<u32>,
-                         @builtin(local_invocation_id) lid: vec3<u32>) {
+fn stats_reduction_main(@builtin(local_invocation_id) lid: vec3<u32>,
+                         @builtin(workgroup_id) wid: vec3<u32>) {
     let gs = u32(params.grid_size);
     var total_voxels: u32;
     if params.sparse_mode > 0.0 {
-        total_voxels = u32(params.max_bricks) * 512u;
+        // Only allocated bricks: free pool slots hold stale voxels
+        total_voxels = allocated_brick_count() * 512u;
     } else {
         total_voxels = gs * gs * gs;
     }
-    let num_workgroups = (total_voxels + 63u) / 64u;
-    let total_threads = num_workgroups * 64u;
+    // Mirrors types::stats_partial_range
+    let chunk = (total_voxels + STATS_PARTIAL_GROUPS - 1u) / STATS_PARTIAL_GROUPS;
+    let range_start = min(wid.x * chunk, total_voxels);
+    let range_end = min(range_start + chunk, total_voxels);
 
-    // Initialize shared memory
-    if lid.x == 0u {
-        atomicStore(&wg_pop, 0u);
-        atomicStore(&wg_energy, 0u);
-        atomicStore(&wg_max_energy, 0u);
-    }
-    if lid.x < 16u {
-        atomicStore(&wg_species_id[lid.x], 0u);
-        atomicStore(&wg_species_count[lid.x], 0u);
+    for (var s = lid.x; s < STATS_WG_SPECIES_SLOTS; s += 64u) {
+        atomicStore(&wg_species_id[s], 0u);
+        atomicStore(&wg_species_count[s], 0u);
+        atomicStore(&wg_species_in_goal[s], 0u);
     }
     workgroupBarrier();
 
-    // Grid stride loop: each thread accumulates locally
-    var local_pop = 0u;
-    var local_energy = 0u;
-    var local_max_energy = 0u;
+    // Stride over the workgroup's range: each thread accumulates locally
+    var sums: array<u32, P_USED>;
+    let dense = params.sparse_mode == 0.0;
+    var local_hi = vec3<u32>(0u);
+    var local_lo = vec3<u32>(ACTIVE_MIN_BIAS);
 
-    var vi = gid.x;
+    var vi = range_start + lid.x;
     loop {
-        if vi >= total_voxels { break; }
+        if vi >= range_end { break; }
 
-        let base = vi * VOXEL_STRIDE;
+        var cell = vi;
+        if !dense {
+            cell = allocated_brick_slot(vi / 512u) * 512u + vi % 512u;
+        }
+        let base = cell * VOXEL_STRIDE;
         let word0 = voxel_buf[base];
-        let vtype = word0 & 0xFFu;
+        let vtype = (word0 >> VOXEL_TYPE_SHIFT) & VOXEL_TYPE_MASK;
+
+        // word0 != 0: non-empty, or an empty cell that is frozen
+        if dense && (word0 != 0u || prev_voxel_buf[base] != 0u) {
+            let pos = grid_coords(vi, gs);
+            local_hi = max(local_hi, pos + 1u);
+            local_lo = min(local_lo, pos);
+        }
 
         if vtype == VOXEL_PROTOCELL {
-            local_pop += 1u;
-            let energy = (word0 >> 16u) & 0xFFFFu;
-            local_energy += energy;
-            local_max_energy = max(local_max_energy, energy);
+            sums[P_POP] += 1u;
+            let energy = (word0 >> VOXEL_ENERGY_SHIFT) & VOXEL_ENERGY_MASK;
+            sums[P_ENERGY] += energy;
+            sums[P_MAX_ENERGY] = max(sums[P_MAX_ENERGY], energy);
+            // Genome byte 3 is the mutation rate
+            sums[P_MUTATION_RATE] += genome_get_byte(&voxel_buf, cell, 3u);
 
             // Species tracking via open-addressing hash in shared memory
             let word1 = voxel_buf[base + 1u];
-            let species_id = (word1 >> 16u) & 0xFFFFu;
+            sums[P_AGE] += (word1 >> VOXEL_AGE_SHIFT) & VOXEL_AGE_MASK;
+            let species_id = (word1 >> VOXEL_SPECIES_SHIFT) & VOXEL_SPECIES_MASK;
+            if dense && species_id != 0u {
+                let pos = grid_coords(vi, gs);
+                var same = 0u;
+                for (var d = 0u; d < 6u; d += 1u) {
+                    let ni = neighbor_in_direction(pos, d, gs);
+                    if ni == 0xFFFFFFFFu { continue; }
+                    if voxel_get_type(&voxel_buf, ni) == VOXEL_PROTOCELL
+                        && voxel_get_species_id(&voxel_buf, ni) == species_id {
+                        same += 1u;
+                    }
+                }
+                if same >= CLUSTER_MIN_NEIGHBORS {
+                    sums[P_CLUSTERED] += 1u;
+                }
+            }
             if species_id != 0u {
-                let hash_start = species_id % 16u;
-                for (var probe = 0u; probe < 16u; probe += 1u) {
-                    let slot = (hash_start + probe) % 16u;
+                let flags = (word0 >> VOXEL_FLAGS_SHIFT) & VOXEL_FLAGS_MASK;
+                let in_goal = select(0u, 1u, (flags & FLAG_IN_GOAL) != 0u);
+                let hash_start = species_id % STATS_WG_SPECIES_SLOTS;
+                var placed = false;
+                for (var probe = 0u; probe < STATS_WG_SPECIES_SLOTS; probe += 1u) {
+                    let slot = (hash_start + probe) % STATS_WG_SPECIES_SLOTS;
                     let prev = atomicCompareExchangeWeak(&wg_species_id[slot], 0u, species_id);
                     if prev.exchanged || prev.old_value == species_id {
                         atomicAdd(&wg_species_count[slot], 1u);
+                        atomicAdd(&wg_species_in_goal[slot], in_goal);
+                        placed = true;
                         break;
                     }
                 }
+                if !placed {
+                    sums[P_OTHER] += 1u;
+                    sums[P_OTHER_GOAL] += in_goal;
+                }
+            }
+        } else if vtype == VOXEL_WASTE {
+            sums[P_WASTE] += 1u;
+            // Fresh corpses (age 0) flagged as killed by field toxin
+            let flags = (word0 >> VOXEL_FLAGS_SHIFT) & VOXEL_FLAGS_MASK;
+            let age = (voxel_buf[base + 1u] >> VOXEL_AGE_SHIFT) & VOXEL_AGE_MASK;
+            if age == 0u && (flags & WASTE_FLAG_TOXIN) != 0u {
+                sums[P_TOXIN_DEATHS] += 1u;
             }
         }
 
-        vi += total_threads;
+        sums[P_NUTRIENT] += select(0u, 1u, vtype == VOXEL_NUTRIENT);
+        sums[P_WALL] += select(0u, 1u, vtype == VOXEL_WALL);
+
+        vi += 64u;
+    }
+    if local_hi.x > 0u {
+        for (var a = 0u; a < 3u; a += 1u) {
+            sums[P_BOUNDS + a] = local_hi[a];
+            sums[P_BOUNDS + 3u + a] = ACTIVE_MIN_BIAS - local_lo[a];
+        }
+    }
+
+    // Shared-memory tree reduction, then one partial record per workgroup
+    reduce_sums(lid.x, sums);
+    let record = wid.x * STATS_PARTIAL_WORDS;
+    if lid.x < P_USED {
+        partials[record + lid.x] = wg_sums[0][lid.x];
+    }
+    let table = record + STATS_PARTIAL_SCALARS;
+    for (var s = lid.x; s < STATS_WG_SPECIES_SLOTS; s += 64u) {
+        partials[table + s] = atomicLoad(&wg_species_id[s]);
+        partials[table + STATS_WG_SPECIES_SLOTS + s] = atomicLoad(&wg_species_count[s]);
+        partials[table + STATS_WG_SPECIES_SLOTS * 2u + s] = atomicLoad(&wg_species_in_goal[s]);
     }
+}
 
-    // Reduce local counts into workgroup shared memory
-    atomicAdd(&wg_pop, local_pop);
-    atomicAdd(&wg_energy, local_energy);
-    atomicMax(&wg_max_energy, local_max_energy);
+@compute @workgroup_size(64, 1, 1)
+fn stats_finalize_main(@builtin(local_invocation_id) lid: vec3<u32>) {
+    for (var s = lid.x; s < STATS_SPECIES_SLOTS; s += 64u) {
+        atomicStore(&fin_species_id[s], 0u);
+        atomicStore(&fin_species_count[s], 0u);
+        atomicStore(&fin_species_in_goal[s], 0u);
+    }
     workgroupBarrier();
 
-    // Thread 0 of each workgroup atomically adds to global stats_buf
-    if lid.x == 0u {
-        atomicAdd(&stats_buf[0], atomicLoad(&wg_pop));
-        atomicAdd(&stats_buf[1], atomicLoad(&wg_energy));
-        atomicMax(&stats_buf[3], atomicLoad(&wg_max_energy));
-
-        // Merge workgroup species table into global 12-entry table
-        for (var s = 0u; s < 16u; s += 1u) {
-            let sid = atomicLoad(&wg_species_id[s]);
-            let cnt = atomicLoad(&wg_species_count[s]);
+    // Each thread folds every 64th partial: scalars into its sums, species
+    // into the shared histogram (overflow into the other-species bucket)
+    var sums: array<u32, P_USED>;
+    for (var g = lid.x; g < STATS_PARTIAL_GROUPS; g += 64u) {
+        let record = g * STATS_PARTIAL_WORDS;
+        var part: array<u32, P_USED>;
+        for (var k = 0u; k < P_USED; k += 1u) {
+            part[k] = partials[record + k];
+        }
+        combine_sums(&sums, part);
+
+        let table = record + STATS_PARTIAL_SCALARS;
+        for (var e = 0u; e < STATS_WG_SPECIES_SLOTS; e += 1u) {
+            let sid = partials[table + e];
+            let cnt = partials[table + STATS_WG_SPECIES_SLOTS + e];
+            let in_goal = partials[table + STATS_WG_SPECIES_SLOTS * 2u + e];
             if sid == 0u || cnt == 0u { continue; }
 
-            let ghash = sid % 12u;
-            for (var gp = 0u; gp < 12u; gp += 1u) {
-                let gslot = (ghash + gp) % 12u;
-                let goffset = 4u + gslot * 2u;
-                let prev = atomicCompareExchangeWeak(&stats_buf[goffset], 0u, sid);
+            let hash_start = sid % STATS_SPECIES_SLOTS;
+            var placed = false;
+            for (var probe = 0u; probe < STATS_SPECIES_SLOTS; probe += 1u) {
+                let slot = (hash_start + probe) % STATS_SPECIES_SLOTS;
+                let prev = atomicCompareExchangeWeak(&fin_species_id[slot], 0u, sid);
                 if prev.exchanged || prev.old_value == sid {
-                    atomicAdd(&stats_buf[goffset + 1u], cnt);
+                    atomicAdd(&fin_species_count[slot], cnt);
+                    atomicAdd(&fin_species_in_goal[slot], in_goal);
+                    placed = true;
                     break;
                 }
             }
+            if !placed {
+                sums[P_OTHER] += cnt;
+                sums[P_OTHER_GOAL] += in_goal;
+            }
+        }
+    }
+    reduce_sums(lid.x, sums);
+
+    // Plain stores: this workgroup is the only writer of these slots
+    for (var s = lid.x; s < STATS_SPECIES_SLOTS; s += 64u) {
+        stats_buf[STATS_SPECIES_HISTOGRAM + s * 2u] = atomicLoad(&fin_species_id[s]);
+        stats_buf[STATS_SPECIES_HISTOGRAM + s * 2u + 1u] = atomicLoad(&fin_species_count[s]);
+        stats_buf[STATS_GOAL_HISTOGRAM + s] = atomicLoad(&fin_species_in_goal[s]);
+    }
+    if lid.x == 0u {
+        let total = wg_sums[0];
+        stats_buf[STATS_VERSION] = STATS_LAYOUT_VERSION;
+        stats_buf[STATS_POPULATION] = total[P_POP];
+        stats_buf[STATS_TOTAL_ENERGY] = total[P_ENERGY];
+        stats_buf[STATS_MAX_ENERGY] = total[P_MAX_ENERGY];
+        stats_buf[STATS_TOXIN_DEATHS] = total[P_TOXIN_DEATHS];
+        // +1 marks the count as measured
+        stats_buf[STATS_CLUSTERED] = total[P_CLUSTERED] + select(0u, 1u, params.sparse_mode == 0.0);
+        stats_buf[STATS_MUTATION_RATE_SUM] = total[P_MUTATION_RATE];
+        for (var a = 0u; a < 3u; a += 1u) {
+            stats_buf[STATS_ACTIVE_MAX + a] = total[P_BOUNDS + a];
+            stats_buf[STATS_ACTIVE_MIN + a] = total[P_BOUNDS + 3u + a];
         }
+        stats_buf[STATS_OTHER_SPECIES] = total[P_OTHER];
+        stats_buf[STATS_OTHER_SPECIES + 1u] = total[P_OTHER_GOAL];
+        stats_buf[STATS_AGE_SUM] = total[P_AGE];
+        stats_buf[STATS_VOXEL_COUNTS] = total[P_NUTRIENT];
+        stats_buf[STATS_VOXEL_COUNTS + 1u] = total[P_WASTE];
+        stats_buf[STATS_VOXEL_COUNTS + 2u] = total[P_WALL];
     }
 }
@@ -1,51 +1,136 @@
 // ============================================================
 // temperature_diffusion.wgsl — M5: Temperature field diffusion.
-// Reads temp_read, writes temp_write. Heat/cold sources are
-// Dirichlet boundaries. Walls are insulators.
-// Prepended with common.wgsl at pipeline creation.
+// Reads temp_read, writes temp_write, in params.diffusion_substeps
+// sub-steps (temperature_substep_main runs all but the first). Heat/cold
+// sources pull their cell toward 1.0 / 0.0 by their strength (energy ‰,
+// faded over the lifetime in extra[0]); at full strength they are
+// Dirichlet boundaries. Walls are insulators. Grid faces follow
+// params.thermal_boundary: insulating, fixed ambient or periodic.
+// Also advances the coarse pheromone field (pher_read → pher_write):
+// one thread per pheromone cell, anchored at the cell's min corner.
+// Includes common.wgsl and brick_common.wgsl. Without TEMPERATURE
+// only the pheromone update remains, without COARSE_FIELD only diffusion.
 //
 // Bind group 0:
-//   [0] temp_read:   storage<array<f32>, read>
-//   [1] temp_write:  storage<array<f32>, read_write>
+//   [0] temp_read:   storage<array<f32>, read>        (TEMPERATURE)
+//   [1] temp_write:  storage<array<f32>, read_write>  (TEMPERATURE)
 //   [2] voxel_read:  storage<array<u32>, read>
 //   [3] params:      uniform<SimParams>
+//   [4] pher_read:   storage<array<u32>, read>        (COARSE_FIELD)
+//   [5] pher_write:  storage<array<u32>, read_write>  (COARSE_FIELD)
 // ============================================================
 
-struct SimParams {
-    grid_size: f32,
-    tick_count: f32,
-    dt: f32,
-    nutrient_spawn_rate: f32,
-    waste_decay_ticks: f32,
-    nutrient_recycle_rate: f32,
-    movement_energy_cost: f32,
-    base_ambient_temp: f32,
-    metabolic_cost_base: f32,
-    replication_energy_min: f32,
-    energy_from_nutrient: f32,
-    energy_from_source: f32,
-    diffusion_rate: f32,
-    temp_sensitivity: f32,
-    predation_energy_fraction: f32,
-    max_energy: f32,
-    overlay_mode: f32,
-    sparse_mode: f32,
-    brick_grid_dim: f32,
-    max_bricks: f32,
-};
+#include "common.wgsl"
+#include "brick_common.wgsl"
 
+#ifdef TEMPERATURE
 @group(0) @binding(0) var<storage, read> temp_read: array<f32>;
 @group(0) @binding(1) var<storage, read_write> temp_write: array<f32>;
+#endif
 @group(0) @binding(2) var<storage, read> voxel_read: array<u32>;
 @group(0) @binding(3) var<uniform> params: SimParams;
+#ifdef COARSE_FIELD
+@group(0) @binding(4) var<storage, read> pher_read: array<u32>;
+@group(0) @binding(5) var<storage, read_write> pher_write: array<u32>;
+#endif
 
-@compute @workgroup_size(4, 4, 4)
-fn temperature_diffusion_main(@builtin(global_invocation_id) gid: vec3<u32>) {
-    let gs = u32(params.grid_size);
-    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
-        return;
+#ifdef COARSE_FIELD
+
+// Pheromone cell whose min corner is `base`: take the strongest neighbor
+// cell's scent (scaled by pheromone_spread) if it beats our own, decay, then
+// add deposits from the protocells inside. Depositors of another bucket wear
+// the scent down and take the cell over once it reaches zero.
+fn update_pheromone_cell(base: vec3<u32>, gs: u32) {
+    let pd = pheromone_dim(gs);
+    let c = vec3<i32>(base / PHEROMONE_CELL);
+    let own = pher_read[pheromone_index(base, gs)];
+    var bucket = pheromone_get_bucket(own);
+    var level = pheromone_get_intensity(own);
+    var toxin = pheromone_get_toxin(own);
+
+    let spread_q = pheromone_q16(params.pheromone_spread);
+    for (var d: u32 = 0u; d < 6u; d++) {
+        let n = c + NEIGHBORS[d];
+        if any(n < vec3<i32>(0)) || any(n >= vec3<i32>(i32(pd))) {
+            continue;
+        }
+        let nw = pher_read[u32(n.z) * pd * pd + u32(n.y) * pd + u32(n.x)];
+        let spread = (pheromone_get_intensity(nw) * spread_q) >> 16u;
+        if spread > level {
+            level = spread;
+            bucket = pheromone_get_bucket(nw);
+        }
+        // Toxin seeps into neighbours at half strength
+        toxin = max(toxin, pheromone_get_toxin(nw) / 2u);
     }
+    level -= (level * pheromone_q16(params.pheromone_decay)) >> 16u;
+    // Round decay up so isolated toxin clears instead of lingering at 1
+    toxin -= min(toxin, (toxin * pheromone_q16(params.toxin_decay) + 0xFFFFu) >> 16u);
+    var secreted: f32 = 0.0;
+
+    let deposit = u32(f32(PHEROMONE_MAX) * clamp(params.pheromone_deposit, 0.0, 1.0));
+    var signed_level = i32(level);
+    for (var i: u32 = 0u; i < PHEROMONE_CELL * PHEROMONE_CELL * PHEROMONE_CELL; i++) {
+        let pos = base + vec3<u32>(i % PHEROMONE_CELL, (i / PHEROMONE_CELL) % PHEROMONE_CELL, i / (PHEROMONE_CELL * PHEROMONE_CELL));
+        if any(pos >= vec3<u32>(gs)) {
+            continue;
+        }
+        var vi: u32;
+        if params.sparse_mode > 0.0 {
+            vi = sparse_voxel_index(pos, gs);
+            if vi == 0xFFFFFFFFu { continue; }
+        } else {
+            vi = grid_index(pos, gs);
+        }
+        if voxel_get_type(&voxel_read, vi) != VOXEL_PROTOCELL || voxel_is_frozen(&voxel_read, vi) {
+            continue;
+        }
+#ifdef TOXIN_FIELD
+        secreted += f32(genome_toxin_secretion(genome_get_byte(&voxel_read, vi, 13u))) / 255.0;
+#endif
+#ifdef SIGNALS
+        let amount = i32(deposit * genome_get_byte(&voxel_read, vi, 11u) / 255u);
+        let b = pheromone_bucket(voxel_get_species_id(&voxel_read, vi));
+        if bucket == 0u || b == bucket {
+            bucket = b;
+            signed_level += amount;
+        } else {
+            signed_level -= amount;
+            if signed_level < 0 {
+                bucket = b;
+                signed_level = -signed_level;
+            }
+        }
+#endif
+    }
+    level = u32(clamp(signed_level, 0, i32(PHEROMONE_MAX)));
+    toxin = min(toxin + u32(secreted * max(params.toxin_secretion_rate, 0.0)), 255u);
+    pher_write[pheromone_index(base, gs)] = pheromone_pack(select(bucket, 0u, level == 0u), level) | (toxin << 8u);
+}
+#endif
+
+#ifdef TEMPERATURE
+// Heat/cold source pull in [0, 1]: energy per-mille, faded linearly over
+// the lifetime (THERMAL_LIFETIME of extra[0], 0 = permanent) as the source ages.
+// Matches Voxel::thermal_strength.
+fn thermal_strength(idx: u32) -> f32 {
+    let strength = f32(min(voxel_get_energy(&voxel_read, idx), 1000u)) / 1000.0;
+    let lifetime = (voxel_get_extra(&voxel_read, idx, 0u) >> THERMAL_LIFETIME_SHIFT) & THERMAL_LIFETIME_MASK;
+    if lifetime == 0u {
+        return strength;
+    }
+    return strength * clamp(1.0 - f32(voxel_get_age(&voxel_read, idx)) / f32(lifetime), 0.0, 1.0);
+}
 
+const THERMAL_BOUNDARY_AMBIENT: u32 = 1u;
+const THERMAL_BOUNDARY_PERIODIC: u32 = 2u;
+// Mirrors types::MAX_DIFFUSION_SUBSTEPS
+const MAX_DIFFUSION_SUBSTEPS: u32 = 8u;
+
+// One temperature sub-step for the cell at `gid`: temp_read → temp_write at
+// diffusion_rate / K, sources pulling with the K-th root of their per-tick
+// strength so K sub-steps add up to one tick.
+fn diffuse_cell(gid: vec3<u32>, gs: u32) {
     var idx: u32;
     if params.sparse_mode > 0.0 {
         idx = sparse_voxel_index(gid, gs);
@@ -56,54 +141,100 @@ fn temperature_diffusion_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let vtype = voxel_get_type(&voxel_read, idx);
     let own_temp = temp_read[idx];
 
-    // WALL: insulator, keep own temperature unchanged
-    if vtype == VOXEL_WALL {
+    // WALL or frozen: insulator, keep own temperature unchanged
+    if vtype == VOXEL_WALL || voxel_is_frozen(&voxel_read, idx) {
         temp_write[idx] = own_temp;
         return;
     }
 
-    // HEAT_SOURCE: Dirichlet boundary at 1.0
-    if vtype == VOXEL_HEAT_SOURCE {
-        temp_write[idx] = 1.0;
-        return;
-    }
-
-    // COLD_SOURCE: Dirichlet boundary at 0.0
-    if vtype == VOXEL_COLD_SOURCE {
-        temp_write[idx] = 0.0;
-        return;
-    }
-
-    // All others: diffuse from non-wall, in-bounds neighbors
+    // All others (sources included): diffuse from non-wall, unfrozen neighbors. Past
+    // a grid face the neighbor is a ghost cell: our own temperature
+    // (insulating), the ambient temperature, or the cell on the opposite face.
     var neighbor_sum: f32 = 0.0;
     var neighbor_count: f32 = 0.0;
+    let boundary = u32(params.thermal_boundary);
 
     for (var d: u32 = 0u; d < 6u; d++) {
+        var n = vec3<i32>(gid) + NEIGHBORS[d];
+        if any(n < vec3<i32>(0)) || any(n >= vec3<i32>(i32(gs))) {
+            if boundary == THERMAL_BOUNDARY_PERIODIC {
+                n = (n + vec3<i32>(i32(gs))) % vec3<i32>(i32(gs));
+            } else {
+                if boundary == THERMAL_BOUNDARY_AMBIENT {
+                    neighbor_sum += params.base_ambient_temp;
+                } else {
+                    neighbor_sum += own_temp;
+                }
+                neighbor_count += 1.0;
+                continue;
+            }
+        }
         var ni: u32;
         if params.sparse_mode > 0.0 {
-            ni = sparse_neighbor(gid, d, gs);
+            ni = sparse_voxel_index(vec3<u32>(n), gs);
         } else {
-            ni = neighbor_in_direction(gid, d, gs);
+            ni = grid_index(vec3<u32>(n), gs);
         }
+        // Unallocated brick
         if ni == 0xFFFFFFFFu {
             continue;
         }
         let ntype = voxel_get_type(&voxel_read, ni);
-        if ntype == VOXEL_WALL {
+        if ntype == VOXEL_WALL || voxel_is_frozen(&voxel_read, ni) {
             continue;
         }
         neighbor_sum += temp_read[ni];
         neighbor_count += 1.0;
     }
 
+    let substeps = f32(clamp(u32(params.diffusion_substeps), 1u, MAX_DIFFUSION_SUBSTEPS));
     var t_new: f32;
     if neighbor_count > 0.0 {
         let t_avg = neighbor_sum / neighbor_count;
-        t_new = own_temp + params.diffusion_rate * (t_avg - own_temp);
+        t_new = own_temp + params.diffusion_rate / substeps * (t_avg - own_temp);
     } else {
         t_new = own_temp;
     }
 
+    // HEAT_SOURCE / COLD_SOURCE: pull toward 1.0 / 0.0 (full strength = Dirichlet)
+    if vtype == VOXEL_HEAT_SOURCE || vtype == VOXEL_COLD_SOURCE {
+        let pull = 1.0 - pow(1.0 - thermal_strength(idx), 1.0 / substeps);
+        t_new = mix(t_new, select(0.0, 1.0, vtype == VOXEL_HEAT_SOURCE), pull);
+    }
+
     // SIM-6: clamp to [0.0, 1.0]
     temp_write[idx] = clamp(t_new, 0.0, 1.0);
 }
+#endif
+
+// First sub-step of the tick: also advances the pheromone field.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn temperature_diffusion_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let gs = u32(params.grid_size);
+    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
+        return;
+    }
+
+#ifdef COARSE_FIELD
+    // Pheromone cells are dense, so update them before the sparse early-out
+    if all(gid % PHEROMONE_CELL == vec3<u32>(0u)) {
+        update_pheromone_cell(gid, gs);
+    }
+#endif
+#ifdef TEMPERATURE
+    diffuse_cell(gid, gs);
+#endif
+}
+
+// Further sub-steps: the host copies temp_write back into temp_read first.
+// Never dispatched without TEMPERATURE.
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn temperature_substep_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let gs = u32(params.grid_size);
+    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
+        return;
+    }
+#ifdef TEMPERATURE
+    diffuse_cell(gid, gs);
+#endif
+}
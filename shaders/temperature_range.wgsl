@@ -0,0 +1,70 @@
+// ============================================================
+// temperature_range.wgsl — Min/max of the temperature field, for the
+// auto-scaled temperature overlay and its legend.
+// Includes common.wgsl and brick_common.wgsl. The uniform is named
+// `params` because brick_common reads `params.brick_grid_dim`.
+//
+// One invocation per grid position (unallocated bricks are skipped). Each
+// workgroup reduces its cells in shared memory, then folds them into the
+// range buffer with one atomic per word (types::TempRange::from_words).
+//
+// Bind group 0:
+//   [0] temp_buf: storage<array<f32>, read>
+//   [1] range_buf: storage<array<atomic<u32>>, read_write>, zeroed first
+//       [0] max of ~float_key(temp)  [1] max of float_key(temp)
+//   [2] params: uniform<RangeParams>
+//   [10] brick_table (sparse; a 1-word placeholder in dense mode)
+// ============================================================
+
+#include "common.wgsl"
+#include "brick_common.wgsl"
+
+struct RangeParams {
+    grid_size: u32,
+    sparse_mode: u32,
+    brick_grid_dim: u32,
+    _pad0: u32,
+};
+
+@group(0) @binding(0) var<storage, read> temp_buf: array<f32>;
+@group(0) @binding(1) var<storage, read_write> range_buf: array<atomic<u32>>;
+@group(0) @binding(2) var<uniform> params: RangeParams;
+
+var<workgroup> wg_min: atomic<u32>;
+var<workgroup> wg_max: atomic<u32>;
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn temperature_range_main(
+    @builtin(global_invocation_id) pos: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+) {
+    if lid == 0u {
+        atomicStore(&wg_min, 0u);
+        atomicStore(&wg_max, 0u);
+    }
+    workgroupBarrier();
+
+    let gs = params.grid_size;
+    var idx = 0xFFFFFFFFu;
+    if all(pos < vec3<u32>(gs)) {
+        if params.sparse_mode != 0u {
+            idx = sparse_voxel_index(pos, gs);
+        } else {
+            idx = grid_index(pos, gs);
+        }
+    }
+    if idx != 0xFFFFFFFFu {
+        let key = float_key(temp_buf[idx]);
+        atomicMax(&wg_min, ~key);
+        atomicMax(&wg_max, key);
+    }
+    workgroupBarrier();
+
+    if lid == 0u {
+        let hi = atomicLoad(&wg_max);
+        if hi != 0u {
+            atomicMax(&range_buf[0], atomicLoad(&wg_min));
+            atomicMax(&range_buf[1], hi);
+        }
+    }
+}
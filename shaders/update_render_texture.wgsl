@@ -1,47 +1,188 @@
 // ============================================================
 // update_render_texture.wgsl — M6: Maps voxel data to RGBA in 3D texture.
-// Supports temperature overlay mode.
-// Prepended with common.wgsl at pipeline creation.
+// Supports temperature overlay mode, color-vision-safe palettes and
+// reduced-flicker temporal smoothing.
+// Includes common.wgsl and brick_common.wgsl.
 //
 // Bind group 0:
 //   [0] voxel_buf: storage<array<u32>, read>
 //   [1] render_tex: texture_storage_3d<rgba8unorm, write>
 //   [2] params: uniform<SimParams>
 //   [3] temp_buf: storage<array<f32>, read>
+//   [4] style: uniform<RenderStyle> — with `transfer` set, the texture is
+//       half as wide and each texel holds the transfer-function row and
+//       scalar of two cells (r = row / 255, g = scalar for the even x,
+//       b and a for the odd one) for ray_march.wgsl instead of a color
+//   [5] history_buf: storage<array<u32>, read_write> — last written color
+//       per grid position (packed rgba8); a 1-word placeholder unless
+//       reduced flicker is on
+//   [6] region: uniform<DispatchRegion> — positions this update covers;
+//       texels outside keep their last color
+//   [7] temp_range: storage<array<u32>, read> — live temperature min/max
+//       keys from temperature_range.wgsl; the overlay stretches over them
+//       unless `fixed_temp_range` is set
+//   [8] occupancy: storage<array<atomic<u32>>, read_write> — one word per
+//       OCCUPANCY_CELL³ block, cleared before the pass; counts the visible
+//       texels written, for ray_march.wgsl's empty-space skipping and the
+//       brick overlay
+//   [14] tick: uniform<TickParams> — the sim's latest tick slot (overlay_mode,
+//       overlay_trait)
 // ============================================================
 
-struct SimParams {
-    grid_size: f32,
-    tick_count: f32,
-    dt: f32,
-    nutrient_spawn_rate: f32,
-    waste_decay_ticks: f32,
-    nutrient_recycle_rate: f32,
-    movement_energy_cost: f32,
-    base_ambient_temp: f32,
-    metabolic_cost_base: f32,
-    replication_energy_min: f32,
-    energy_from_nutrient: f32,
-    energy_from_source: f32,
-    diffusion_rate: f32,
-    temp_sensitivity: f32,
-    predation_energy_fraction: f32,
-    max_energy: f32,
-    overlay_mode: f32,
-    sparse_mode: f32,
-    brick_grid_dim: f32,
-    max_bricks: f32,
-};
+#include "common.wgsl"
+#include "brick_common.wgsl"
 
 @group(0) @binding(0) var<storage, read> voxel_buf: array<u32>;
 @group(0) @binding(1) var render_tex: texture_storage_3d<rgba8unorm, write>;
 @group(0) @binding(2) var<uniform> params: SimParams;
 @group(0) @binding(3) var<storage, read> temp_buf: array<f32>;
 
-@compute @workgroup_size(4, 4, 4)
-fn update_render_texture_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+// Mirrors types::RenderStyle
+struct RenderStyle {
+    palette: u32,            // 0 = default, 1 = deuteranopia, 2 = protanopia
+    blend: f32,              // weight of the new color; 1.0 = no smoothing
+    fixed_temp_range: u32,   // 1 = temperature overlay maps 0..1, not the live range
+    transfer: u32,           // 1 = write transfer-function inputs, not colors
+};
+
+@group(0) @binding(4) var<uniform> style: RenderStyle;
+@group(0) @binding(5) var<storage, read_write> history_buf: array<u32>;
+@group(0) @binding(6) var<uniform> region: DispatchRegion;
+@group(0) @binding(7) var<storage, read> temp_range: array<u32>;
+@group(0) @binding(8) var<storage, read_write> occupancy: array<atomic<u32>>;
+@group(0) @binding(14) var<uniform> tick: TickParams;
+
+// Matches renderer::render_texture::OCCUPANCY_CELL
+const OCCUPANCY_CELL: u32 = 8u;
+
+// Store a texel, counting it in its block if it can be seen
+fn store_texel(gid: vec3<u32>, gs: u32, texel: vec4<f32>) {
+    textureStore(render_tex, gid, texel);
+    if texel.a > 0.01 {
+        let od = (gs + OCCUPANCY_CELL - 1u) / OCCUPANCY_CELL;
+        let cell = gid / OCCUPANCY_CELL;
+        atomicAdd(&occupancy[(cell.z * od + cell.y) * od + cell.x], 1u);
+    }
+}
+
+// Matches types::MIN_TEMP_SPAN
+const MIN_TEMP_SPAN: f32 = 0.02;
+
+// Low end and width of the live temperature range, widened about its
+// middle to at least MIN_TEMP_SPAN (types::TempRange::scale).
+fn temp_overlay_scale() -> vec2<f32> {
+    let lo = float_from_key(~temp_range[0]);
+    let hi = float_from_key(temp_range[1]);
+    if hi - lo >= MIN_TEMP_SPAN {
+        return vec2<f32>(lo, hi - lo);
+    }
+    return vec2<f32>((lo + hi) * 0.5 - MIN_TEMP_SPAN * 0.5, MIN_TEMP_SPAN);
+}
+
+const CVD_BLUE: vec3<f32> = vec3<f32>(0.0, 0.45, 0.70);
+const CVD_YELLOW: vec3<f32> = vec3<f32>(0.94, 0.80, 0.10);
+const CVD_ORANGE: vec3<f32> = vec3<f32>(0.90, 0.62, 0.0);
+
+// Color-vision-safe material colors (Okabe–Ito based). Protanopia lifts
+// the reds, which otherwise read as near-black.
+fn cvd_material_rgb(vtype: u32, palette: u32) -> vec3<f32> {
+    switch vtype {
+        case 2u: { return vec3<f32>(0.0, 0.62, 0.45); }
+        case 3u: { return select(vec3<f32>(0.94, 0.89, 0.26), vec3<f32>(1.0, 0.96, 0.55), palette == 2u); }
+        case 5u: { return vec3<f32>(0.40, 0.26, 0.36); }
+        case 6u: { return select(vec3<f32>(0.84, 0.37, 0.0), CVD_ORANGE, palette == 2u); }
+        case 7u: { return CVD_BLUE; }
+        default: { return vec3<f32>(0.5, 0.5, 0.5); }
+    }
+}
+
+// Temperature at `idx` over the live range, or over 0..1 when fixed or
+// before a range was measured
+fn normalized_temp(idx: u32) -> f32 {
+    var temp = temp_buf[idx];
+    if style.fixed_temp_range == 0u && temp_range[1] != 0u {
+        let scale = temp_overlay_scale();
+        temp = clamp((temp - scale.x) / scale.y, 0.0, 1.0);
+    }
+    return temp;
+}
+
+// Species overlay hue; matches types::species_hue
+fn species_hue(species_id: u32) -> f32 {
+    return f32(pcg_hash(species_id) >> 8u) / 16777216.0;
+}
+
+// Matches types::TRANSFER_OVERLAY_BASE
+const TRANSFER_OVERLAY_BASE: u32 = 8u;
+
+// Lookup row and scalar of a cell (types::transfer_input), row / 255 in x;
+// (0, 0) outside the grid or an allocated brick
+fn transfer_input(gid: vec3<u32>, gs: u32) -> vec2<f32> {
+    if gid.x >= gs {
+        return vec2<f32>(0.0);
+    }
+    var idx: u32;
+    if params.sparse_mode > 0.0 {
+        idx = sparse_voxel_index(gid, gs);
+        if idx == 0xFFFFFFFFu {
+            return vec2<f32>(0.0);
+        }
+    } else {
+        idx = grid_index(gid, gs);
+    }
+    let word0 = voxel_buf[idx * VOXEL_STRIDE];
+    let vtype = (word0 >> VOXEL_TYPE_SHIFT) & VOXEL_TYPE_MASK;
+    let energy = (word0 >> VOXEL_ENERGY_SHIFT) & VOXEL_ENERGY_MASK;
+    let age = (voxel_buf[idx * VOXEL_STRIDE + 1u] >> VOXEL_AGE_SHIFT) & VOXEL_AGE_MASK;
+    let e = clamp(f32(energy) / params.max_energy, 0.0, 1.0);
+    var row = vtype;
+    var scalar = 0.0;
+    let overlay = tick.overlay_mode;
+    if overlay == 1u {
+        row = TRANSFER_OVERLAY_BASE;
+        scalar = normalized_temp(idx);
+    } else if overlay == 2u || overlay == 3u {
+        row = select(0u, TRANSFER_OVERLAY_BASE + overlay - 1u, vtype != 0u);
+        scalar = select(e, select(0.0, 1.0, vtype == 4u), overlay == 3u);
+    } else if vtype == 2u || vtype == 3u || vtype == 4u {
+        scalar = e;
+    } else if vtype == 5u {
+        scalar = clamp(1.0 - f32(age) / params.waste_decay_ticks, 0.0, 1.0);
+    } else {
+        scalar = clamp(normalized_temp(idx), 0.0, 1.0);
+    }
+    return vec2<f32>(f32(row) / 255.0, scalar);
+}
+
+// Transfer inputs of the cell pair holding `gid`, two cells per texel
+// (types::TRANSFER_CELLS_PER_TEXEL). The even cell writes the pair, or the
+// odd one on the region's low face, whose even cell is outside it.
+fn store_transfer_pair(gid: vec3<u32>, gs: u32) {
+    if (gid.x & 1u) == 1u && gid.x != region.origin.x {
+        return;
+    }
+    let even = vec3<u32>(gid.x & ~1u, gid.y, gid.z);
+    let a = transfer_input(even, gs);
+    let b = transfer_input(even + vec3<u32>(1u, 0u, 0u), gs);
+    textureStore(render_tex, vec3<u32>(even.x / 2u, even.y, even.z), vec4<f32>(a, b));
+    let visible = u32(a.x > 0.0) + u32(b.x > 0.0);
+    if visible > 0u {
+        // Both cells share a block: OCCUPANCY_CELL is even
+        let od = (gs + OCCUPANCY_CELL - 1u) / OCCUPANCY_CELL;
+        let cell = even / OCCUPANCY_CELL;
+        atomicAdd(&occupancy[(cell.z * od + cell.y) * od + cell.x], visible);
+    }
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn update_render_texture_main(@builtin(global_invocation_id) rel: vec3<u32>) {
     let gs = u32(params.grid_size);
-    if gid.x >= gs || gid.y >= gs || gid.z >= gs {
+    let gid = region.origin.xyz + rel;
+    if any(gid >= region.end.xyz) {
+        return;
+    }
+    if style.transfer != 0u {
+        store_transfer_pair(gid, gs);
         return;
     }
 
@@ -59,10 +200,11 @@ fn update_render_texture_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let word0 = voxel_buf[base];
     let word1 = voxel_buf[base + 1u];
 
-    let vtype = word0 & 0xFFu;
-    let energy = (word0 >> 16u) & 0xFFFFu;
-    let age = word1 & 0xFFFFu;
-    let species_id = (word1 >> 16u) & 0xFFFFu;
+    let vtype = (word0 >> VOXEL_TYPE_SHIFT) & VOXEL_TYPE_MASK;
+    let flags = (word0 >> VOXEL_FLAGS_SHIFT) & VOXEL_FLAGS_MASK;
+    let energy = (word0 >> VOXEL_ENERGY_SHIFT) & VOXEL_ENERGY_MASK;
+    let age = (word1 >> VOXEL_AGE_SHIFT) & VOXEL_AGE_MASK;
+    let species_id = (word1 >> VOXEL_SPECIES_SHIFT) & VOXEL_SPECIES_MASK;
 
     var color: vec4<f32>;
 
@@ -89,7 +231,16 @@ fn update_render_texture_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             let val = clamp(f32(energy) / params.max_energy, 0.1, 1.0);
             let predation_cap = genome_get_byte(&voxel_buf, idx, 7u);
             let sat = select(0.7, 1.0, predation_cap > 128u);
-            let rgb = hsv_to_rgb(hue, sat, val);
+            var rgb = hsv_to_rgb(hue, sat, val);
+            if style.palette != 0u {
+                // Species spread along the blue–yellow axis all viewers see
+                let ramp = mix(CVD_BLUE, CVD_YELLOW, hue);
+                rgb = mix(vec3<f32>((ramp.r + ramp.g + ramp.b) / 3.0), ramp, sat) * val;
+            }
+            // Dormant cells are frosted
+            if (flags & FLAG_DORMANT) != 0u {
+                rgb = mix(rgb, vec3<f32>(0.6, 0.75, 0.95), 0.6);
+            }
             color = vec4<f32>(rgb, 1.0);
         }
         case 5u: {
@@ -109,13 +260,21 @@ fn update_render_texture_main(@builtin(global_invocation_id) gid: vec3<u32>) {
             color = vec4<f32>(1.0, 0.0, 1.0, 1.0); // magenta = error
         }
     }
+    if style.palette != 0u && vtype != 0u && vtype != 4u && vtype <= 7u {
+        color = vec4<f32>(cvd_material_rgb(vtype, style.palette), color.a);
+    }
 
-    // Overlay modes: 1=Temperature, 2=Energy density, 3=Population density
-    let overlay = u32(params.overlay_mode);
+    // Overlay modes: 1=Temperature, 2=Energy density, 3=Population density,
+    // 4=Genome trait, 5=Species
+    let overlay = tick.overlay_mode;
     if overlay == 1u {
-        // Temperature: blue (cold=0) to red (hot=1)
-        let temp = temp_buf[idx];
+        // Temperature: blue (cold) to red (hot)
+        let temp = normalized_temp(idx);
         color = vec4<f32>(temp, 0.2 * (1.0 - abs(temp * 2.0 - 1.0)), 1.0 - temp, max(temp, 1.0 - temp));
+        if style.palette != 0u {
+            // Blue to orange instead of blue to red
+            color = vec4<f32>(mix(CVD_BLUE, CVD_ORANGE, temp), color.a);
+        }
     } else if overlay == 2u {
         // Energy density: black (0) to bright green (max_energy)
         let e = f32(energy) / params.max_energy;
@@ -127,7 +286,52 @@ fn update_render_texture_main(@builtin(global_invocation_id) gid: vec3<u32>) {
         } else if vtype != 0u {
             color = vec4<f32>(0.15, 0.15, 0.15, 0.3);
         }
+    } else if overlay == 4u {
+        // Genome trait: protocells on a purple-teal-yellow ramp by the
+        // selected byte (types::trait_color), everything else dimmed
+        if vtype == 4u {
+            let t = f32(genome_get_byte(&voxel_buf, idx, min(tick.overlay_trait, 15u))) / 255.0 * 2.0;
+            let lo = select(vec3<f32>(0.13, 0.57, 0.55), vec3<f32>(0.27, 0.0, 0.33), t < 1.0);
+            let hi = select(vec3<f32>(0.99, 0.91, 0.14), vec3<f32>(0.13, 0.57, 0.55), t < 1.0);
+            color = vec4<f32>(mix(lo, hi, select(t - 1.0, t, t < 1.0)), 1.0);
+        } else if vtype != 0u {
+            color = vec4<f32>(0.15, 0.15, 0.15, 0.3);
+        }
+    } else if overlay == 5u {
+        // Species: protocells at full brightness in their species' hashed
+        // hue (types::species_color), everything else dimmed
+        if vtype == 4u {
+            let hue = species_hue(species_id);
+            var rgb = hsv_to_rgb(hue, 0.8, 1.0);
+            if style.palette != 0u {
+                rgb = mix(CVD_BLUE, CVD_YELLOW, hue);
+            }
+            color = vec4<f32>(rgb, 1.0);
+        } else if vtype != 0u {
+            color = vec4<f32>(0.15, 0.15, 0.15, 0.3);
+        }
+    }
+
+    // Time-dilation zone in the plain view: matter tinted violet, the zone's
+    // faces a faint shell (types::dilation_tinted)
+    if overlay == 0u && in_dilation_zone(params, gid) {
+        let tint = vec3<f32>(0.62, 0.42, 1.0);
+        if color.a > 0.0 {
+            color = vec4<f32>(mix(color.rgb, tint, 0.35), color.a);
+        } else {
+            let lo = vec3<u32>(vec3<f32>(params.dilation_min_x, params.dilation_min_y, params.dilation_min_z));
+            let hi = vec3<u32>(vec3<f32>(params.dilation_max_x, params.dilation_max_y, params.dilation_max_z));
+            let on_face = any(gid == lo) || any(gid == hi);
+            color = vec4<f32>(tint, select(0.0, 0.12, on_face));
+        }
+    }
+
+    // Reduced flicker: ease toward the new color instead of snapping
+    if style.blend < 1.0 {
+        let h = grid_index(gid, gs);
+        color = mix(unpack4x8unorm(history_buf[h]), color, style.blend);
+        history_buf[h] = pack4x8unorm(color);
     }
 
-    textureStore(render_tex, gid, color);
+    store_texel(gid, gs, color);
 }
@@ -1,20 +1,24 @@
 // ============================================================
-// wireframe.wgsl — Bounding box wireframe rendering.
+// wireframe.wgsl — Bounding box wireframe rendering, and the brick debug
+// overlay: the same cube edges instanced per allocated brick.
 // Standalone shader (common.wgsl NOT prepended).
 //
 // Bind group 0:
 //   [0] uniforms: uniform<WireframeUniform>
+//   [1] occupancy: storage<array<u32>, read> — visible texels per 8³ block
+//       of the volume texture (brick pipeline only)
 // ============================================================
 
 struct WireframeUniform {
     view_proj: mat4x4<f32>,
     grid_size: f32,
-    _pad0: f32,
-    _pad1: f32,
-    _pad2: f32,
+    brick_overlay: f32,  // types::BrickOverlay: 1 = occupancy, 2 = age
+    max_age: f32,        // age of the oldest brick, in ticks
+    _pad: f32,
 };
 
 @group(0) @binding(0) var<uniform> uniforms: WireframeUniform;
+@group(0) @binding(1) var<storage, read> occupancy: array<u32>;
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -32,3 +36,34 @@ fn vs_main(@location(0) pos: vec3<f32>) -> VertexOutput {
 fn fs_main() -> @location(0) vec4<f32> {
     return vec4<f32>(0.4, 0.4, 0.4, 0.6);
 }
+
+// Matches types::brick_color
+fn brick_color(t: f32) -> vec4<f32> {
+    let c = clamp(t, 0.0, 1.0);
+    return vec4<f32>(0.2 + 0.8 * c, 0.4 + 0.2 * c, 1.0 - 0.9 * c, 0.25 + 0.6 * c);
+}
+
+struct BrickOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+// One instance per allocated brick: its brick coordinates and age in ticks
+@vertex
+fn vs_brick(@location(0) pos: vec3<f32>, @location(1) brick: vec4<f32>) -> BrickOutput {
+    var out: BrickOutput;
+    out.position = uniforms.view_proj * vec4<f32>((brick.xyz + pos) * 8.0, 1.0);
+    var t = brick.w / max(uniforms.max_age, 1.0);
+    if uniforms.brick_overlay < 1.5 {
+        let od = u32(ceil(uniforms.grid_size / 8.0));
+        let b = vec3<u32>(brick.xyz);
+        t = f32(occupancy[(b.z * od + b.y) * od + b.x]) / 512.0;
+    }
+    out.color = brick_color(t);
+    return out;
+}
+
+@fragment
+fn fs_brick(in: BrickOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
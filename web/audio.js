@@ -0,0 +1,87 @@
+// ============================================================
+// audio.js — WebAudio voice for simulation sonification.
+// Follows the synth parameters computed host-side (get_audio_params):
+// a detuned oscillator pair per species voice at the population pitch,
+// amplitude-pulsed at the birth rate, with noise mixed in for deaths.
+// ============================================================
+
+const MAX_VOICES = 8;
+const RAMP_SECONDS = 0.5;
+
+export class Sonification {
+    constructor() {
+        const ctx = new AudioContext();
+        this.ctx = ctx;
+
+        this.master = ctx.createGain();
+        this.master.gain.value = 0;
+        this.master.connect(ctx.destination);
+
+        // Pulse: LFO modulates a gain stage between 0.5 and 1.0
+        this.pulse = ctx.createGain();
+        this.pulse.gain.value = 0.75;
+        this.pulse.connect(this.master);
+        this.lfo = ctx.createOscillator();
+        this.lfo.frequency.value = 0.5;
+        const lfoDepth = ctx.createGain();
+        lfoDepth.gain.value = 0.25;
+        this.lfo.connect(lfoDepth).connect(this.pulse.gain);
+        this.lfo.start();
+
+        // Harmonic voices: voice i plays harmonic i + 1, faded in by species count
+        this.voices = [];
+        for (let i = 0; i < MAX_VOICES; i++) {
+            const gain = ctx.createGain();
+            gain.gain.value = 0;
+            gain.connect(this.pulse);
+            const a = ctx.createOscillator();
+            const b = ctx.createOscillator();
+            a.type = 'sine';
+            b.type = 'triangle';
+            a.connect(gain);
+            b.connect(gain);
+            a.start();
+            b.start();
+            this.voices.push({ gain, a, b });
+        }
+
+        // Looping white noise for deaths
+        const buffer = ctx.createBuffer(1, ctx.sampleRate, ctx.sampleRate);
+        const samples = buffer.getChannelData(0);
+        for (let i = 0; i < samples.length; i++) samples[i] = Math.random() * 2 - 1;
+        this.noise = ctx.createBufferSource();
+        this.noise.buffer = buffer;
+        this.noise.loop = true;
+        this.noiseGain = ctx.createGain();
+        this.noiseGain.gain.value = 0;
+        const lowpass = ctx.createBiquadFilter();
+        lowpass.type = 'lowpass';
+        lowpass.frequency.value = 1200;
+        this.noise.connect(lowpass).connect(this.noiseGain).connect(this.master);
+        this.noise.start();
+    }
+
+    /** Glide every node toward `params` from get_audio_params(). */
+    update(params) {
+        const t = this.ctx.currentTime;
+        const ramp = (param, value) => param.setTargetAtTime(value, t, RAMP_SECONDS / 3);
+        ramp(this.master.gain, params.gain * 0.3);
+        ramp(this.lfo.frequency, params.pulse_hz);
+        ramp(this.noiseGain.gain, params.noise * 0.5);
+        this.voices.forEach((v, i) => {
+            const freq = params.pitch_hz * (i + 1);
+            ramp(v.a.frequency, freq);
+            ramp(v.b.frequency, freq);
+            ramp(v.b.detune, params.detune_cents);
+            ramp(v.gain.gain, i < params.voices ? 0.5 / (i + 1) : 0);
+        });
+    }
+
+    resume() {
+        return this.ctx.resume();
+    }
+
+    suspend() {
+        return this.ctx.suspend();
+    }
+}
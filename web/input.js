@@ -1,4 +1,45 @@
-import wasmInit, { init, frame, on_mouse_move, on_scroll, on_key_down, set_paused, single_step, set_tick_rate, set_tool, set_brush_radius, set_overlay_mode, on_mouse_down, request_pick, get_pick_result, get_stats, set_param, load_preset, run_benchmark, get_grid_size } from '../crates/host/pkg/host.js';
+import wasmInit, { init_with_options, frame, on_mouse_move, on_scroll, on_key_down, set_paused, single_step, step_ticks, set_tick_rate, set_timing_mode, set_max_catch_up, set_frame_budget_ms, set_frame_pacing, set_frame_skip, set_async_overlap, set_submit_chunk_ticks, set_stats_interval, get_timing_info, on_visibility_change, set_background_mode, set_background_tick_rate, set_tool, set_brush_radius, set_thermal_source, set_shape_params, set_edit_symmetry, place_shape, freeze_region, set_sim_lod, set_overlay_mode, set_overlay_trait, set_overlay_auto_scale, get_overlay_legend, get_species_colors, set_palette, set_reduced_flicker, set_transfer_function, set_minimap_enabled, set_graph_enabled, set_brick_overlay, set_sparse_mode, is_over_minimap, on_mouse_down, request_pick, get_pick_result, get_pick_history, compare_picks, set_pick_region_radius, watch_voxel, unwatch_voxel, get_watch_series, find_species, census_query, get_census_result, sample_genomes, dump_world_async, export_slices, export_vox, get_view_snapshot, set_measure_mode, get_measurement, set_audio_enabled, get_audio_params, load_scenario, start_tutorial, scenario_continue, stop_scenario, get_scenario_state, start_recording, stop_recording, get_recording_info, add_marker, remove_marker, clear_markers, get_markers, export_markers, import_markers, get_stats, get_ecology_events, get_lineage_tree, get_engine_log, get_energy_flow, set_pool_alarm_threshold, get_clock_info, get_capability_report, get_gpu_report, set_profiling, get_profile, get_memory_usage, set_memory_budget_mb, set_present_mode, set_surface_format, get_surface_info, sim_time_seconds, set_param, protect_species, get_protected_species, load_preset, load_preset_with_seed, seed_from_image, seed_custom, export_snapshot, import_snapshot, set_keyframes, get_keyframes, rewind_to_keyframe, generate_terrain, run_benchmark, get_grid_size, add_nutrient_bloom, clear_nutrient_blooms, set_goal_zone, clear_goal_zone, set_time_dilation, clear_time_dilation, get_shader_names, reload_shader } from '../crates/host/pkg/host.js';
+
+// Adapter override for multi-GPU machines, e.g. ?adapter=1 or ?power=low-power
+function adapterOptionsFromUrl() {
+    const params = new URLSearchParams(window.location.search);
+    const options = {};
+    if (params.has('power')) options.power_preference = params.get('power');
+    if (params.has('fallback')) options.force_fallback = params.get('fallback') !== '0';
+    if (params.has('adapter')) options.adapter_index = Number(params.get('adapter'));
+    // Optional subsystems to drop on low-end GPUs, e.g. ?disable=signals,toxin_field
+    if (params.has('disable')) options.disable_features = params.get('disable').split(',').filter(Boolean);
+    return options;
+}
+
+// Development hot reload (?dev): poll shaders/ and hand edited files to the
+// engine, which recompiles the sim pipelines or keeps the old ones on error.
+function watchShaders() {
+    const sources = new Map();
+    async function poll() {
+        for (const name of get_shader_names()) {
+            let text;
+            try {
+                const res = await fetch(`../shaders/${name}`, { cache: 'no-store' });
+                if (!res.ok) continue;
+                text = await res.text();
+            } catch (e) {
+                continue;
+            }
+            const previous = sources.get(name);
+            sources.set(name, text);
+            if (previous === undefined || previous === text) continue;
+            try {
+                const rebuilt = await reload_shader(name, text);
+                console.log(`[shaders] ${name} reloaded${rebuilt ? ', sim pipelines rebuilt' : ''}`);
+            } catch (e) {
+                console.error(`[shaders] ${name} rejected, keeping the previous pipelines:`, e);
+            }
+        }
+        setTimeout(poll, 1000);
+    }
+    poll();
+}
 
 async function main() {
     const errorDiv = document.getElementById('error-msg');
@@ -7,11 +48,11 @@ async function main() {
 
     try {
         await wasmInit();
-        await init();
+        await init_with_options(adapterOptionsFromUrl());
     } catch (e) {
         console.error('Init failed:', e);
         if (loadingScreen) loadingScreen.remove();
-        errorDiv.textContent = 'WebGPU initialization failed.\nPlease use Chrome 113+ with WebGPU enabled.\n\n' + e;
+        errorDiv.textContent = 'Initialization failed (WebGPU and CPU fallback).\nPlease use Chrome 113+ with WebGPU enabled.\n\n' + e;
         errorDiv.style.display = 'block';
         return;
     }
@@ -44,7 +85,10 @@ async function main() {
     }, { passive: false });
 
     canvas.addEventListener('mousedown', (e) => {
-        if (e.button === 0 && e.shiftKey) {
+        if (e.button === 0 && is_over_minimap(e.offsetX, e.offsetY, canvas.clientWidth, canvas.clientHeight)) {
+            // Minimap click: recenter the camera (handled in on_mouse_down)
+            on_mouse_down(e.offsetX, e.offsetY, canvas.clientWidth, canvas.clientHeight);
+        } else if (e.button === 0 && e.shiftKey) {
             // Shift+click: voxel inspector
             request_pick(e.offsetX, e.offsetY, canvas.clientWidth, canvas.clientHeight);
         } else if (e.button === 0) {
@@ -63,24 +107,132 @@ async function main() {
 
     // Store grid size for UI
     window._gridSize = get_grid_size();
+    window._capabilities = get_capability_report();
+    if (window._capabilities) {
+        console.log(`[init] ${window._capabilities.mode} ${window._capabilities.grid_size}\u00b3 \u2014 ${window._capabilities.reason}`);
+    }
+    if (window._capabilities && window._capabilities.degraded) {
+        const banner = document.createElement('div');
+        banner.id = 'degraded-banner';
+        banner.textContent = `Degraded mode: WebGPU unavailable, running a ${window._capabilities.grid_size}\u00b3 CPU simulation (top-down view)`;
+        document.body.appendChild(banner);
+    }
 
     // Expose bridge functions for ui.js
     window._bridge = {
         set_tool,
         set_brush_radius,
+        set_thermal_source,
+        set_shape_params,
+        set_edit_symmetry,
+        place_shape,
+        freeze_region,
+        set_sim_lod,
         set_overlay_mode,
+        set_overlay_trait,
+        set_overlay_auto_scale,
+        get_overlay_legend,
+        get_species_colors,
+        set_palette,
+        set_reduced_flicker,
+        set_transfer_function,
+        set_minimap_enabled,
+        set_graph_enabled,
+        set_brick_overlay,
+        set_sparse_mode,
         set_paused,
         single_step,
+        step_ticks,
         set_tick_rate,
+        set_timing_mode,
+        set_max_catch_up,
+        set_frame_budget_ms,
+        set_frame_pacing,
+        set_frame_skip,
+        set_async_overlap,
+        set_submit_chunk_ticks,
+        set_stats_interval,
+        get_timing_info,
+        set_background_mode,
+        set_background_tick_rate,
         get_stats,
+        get_ecology_events,
+        get_lineage_tree,
+        get_engine_log,
+        get_energy_flow,
+        set_pool_alarm_threshold,
+        get_clock_info,
+        sim_time_seconds,
+        get_capability_report,
+        get_gpu_report,
+        set_profiling,
+        get_profile,
+        get_memory_usage,
+        set_memory_budget_mb,
+        set_present_mode,
+        set_surface_format,
+        get_surface_info,
         get_pick_result,
+        get_pick_history,
+        compare_picks,
         request_pick,
+        set_pick_region_radius,
+        watch_voxel,
+        unwatch_voxel,
+        get_watch_series,
+        find_species,
+        census_query,
+        set_measure_mode,
+        get_measurement,
+        get_census_result,
+        sample_genomes,
+        dump_world_async,
+        export_slices,
+        export_vox,
+        get_view_snapshot,
+        set_audio_enabled,
+        get_audio_params,
+        load_scenario,
+        start_tutorial,
+        scenario_continue,
+        stop_scenario,
+        get_scenario_state,
+        start_recording,
+        stop_recording,
+        get_recording_info,
+        add_marker,
+        remove_marker,
+        clear_markers,
+        get_markers,
+        export_markers,
+        import_markers,
         set_param,
+        protect_species,
+        get_protected_species,
         load_preset,
+        load_preset_with_seed,
+        seed_from_image,
+        seed_custom,
+        export_snapshot,
+        import_snapshot,
+        set_keyframes,
+        get_keyframes,
+        rewind_to_keyframe,
+        generate_terrain,
         run_benchmark,
         get_grid_size,
+        add_nutrient_bloom,
+        clear_nutrient_blooms,
+        set_goal_zone,
+        clear_goal_zone,
+        set_time_dilation,
+        clear_time_dilation,
+        get_shader_names,
+        reload_shader,
     };
 
+    if (new URLSearchParams(window.location.search).has('dev')) watchShaders();
+
     // Notify ui.js that bridge is ready
     window.dispatchEvent(new Event('bridge-ready'));
 
@@ -120,6 +272,15 @@ async function main() {
             if (stats && window._onStats) {
                 window._onStats(stats);
             }
+            pollAudio();
+            if (window._onScenario) {
+                window._onScenario(get_scenario_state());
+            }
+        }
+
+        // Keep marker labels on their billboards
+        if (window._onMarkers) {
+            window._onMarkers(get_markers());
         }
 
         // Check for pick result
@@ -132,6 +293,44 @@ async function main() {
     }
     requestAnimationFrame(loop);
 
+    // Background handling: rAF stops while hidden, so headless mode drives
+    // frame() from a timer instead. Browsers throttle hidden timers to ~1 Hz;
+    // the Rust accumulator turns the longer dt into the right tick count.
+    // Audio keeps following the run while hidden (the timer below still fires)
+    function pollAudio() {
+        const audio = get_audio_params();
+        if (audio && window._onAudio) {
+            window._onAudio(audio);
+        }
+    }
+
+    let backgroundTimer = null;
+    document.addEventListener('visibilitychange', () => {
+        const keepTicking = on_visibility_change(document.hidden);
+        if (backgroundTimer !== null) {
+            clearInterval(backgroundTimer);
+            backgroundTimer = null;
+        }
+        if (keepTicking) {
+            let bgLast = performance.now();
+            backgroundTimer = setInterval(() => {
+                const now = performance.now();
+                frame((now - bgLast) / 1000.0);
+                bgLast = now;
+                pollAudio();
+            }, 100);
+        }
+        // Avoid a huge dt on the first visible frame
+        lastTime = performance.now();
+    });
+
+    // Console helper for bug reports
+    window.gpuReport = function() {
+        const report = get_gpu_report();
+        console.log(JSON.stringify(report, null, 2));
+        return report;
+    };
+
     // Expose benchmark function
     window.benchmark = function() {
         console.log('[benchmark] Seeding 30% occupancy...');
@@ -149,11 +348,18 @@ async function main() {
         console.log(`[benchmark] 100 ticks in ${simMs.toFixed(0)}ms = ${ticksPerSec.toFixed(0)} ticks/sec`);
 
         console.log('[benchmark] Running 300 render frames...');
+        // Force a redraw every frame so pacing doesn't skip unchanged frames,
+        // and drop vsync where the surface allows it
+        set_frame_pacing(false);
+        const presentMode = set_present_mode(1);
+        if (presentMode) console.log(`[benchmark] Present mode: ${presentMode}`);
         const rt0 = performance.now();
         for (let i = 0; i < 300; i++) {
             frame(1/60);
         }
         const rt1 = performance.now();
+        set_present_mode(0);
+        set_frame_pacing(true);
         const renderMs = rt1 - rt0;
         const renderFps = (300 / renderMs) * 1000;
         console.log(`[benchmark] 300 frames in ${renderMs.toFixed(0)}ms = ${renderFps.toFixed(0)} FPS`);
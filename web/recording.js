@@ -0,0 +1,65 @@
+// ============================================================
+// recording.js — Encode a stop_recording() result to WebM.
+// Frames are replayed onto an offscreen canvas at the recording's fps and
+// captured with MediaRecorder; each capture is held for `repeat` frames,
+// so the video runs at simulation speed. Encoding takes as long as the
+// video plays.
+// ============================================================
+
+const MIME_TYPES = ['video/webm;codecs=vp9', 'video/webm;codecs=vp8', 'video/webm'];
+
+export function recordingToWebM(rec) {
+    if (!rec || rec.frames.length === 0) return Promise.reject(new Error('empty recording'));
+    if (typeof MediaRecorder === 'undefined') return Promise.reject(new Error('MediaRecorder unavailable'));
+
+    const canvas = document.createElement('canvas');
+    canvas.width = rec.width;
+    canvas.height = rec.height;
+    const ctx = canvas.getContext('2d');
+    const stream = canvas.captureStream(0);
+    const track = stream.getVideoTracks()[0];
+    const mimeType = MIME_TYPES.find((t) => MediaRecorder.isTypeSupported(t)) || '';
+    const recorder = new MediaRecorder(stream, mimeType ? { mimeType } : undefined);
+    const chunks = [];
+    recorder.ondataavailable = (e) => { if (e.data.size > 0) chunks.push(e.data); };
+
+    // One entry per output frame
+    const sequence = [];
+    for (const f of rec.frames) {
+        const image = new ImageData(f.data, rec.width, rec.height);
+        for (let i = 0; i < f.repeat; i++) sequence.push(image);
+    }
+    const frameMs = 1000 / rec.fps;
+
+    return new Promise((resolve, reject) => {
+        recorder.onstop = () => resolve(new Blob(chunks, { type: 'video/webm' }));
+        recorder.onerror = (e) => reject(e.error || new Error('MediaRecorder error'));
+        recorder.start();
+        const start = performance.now();
+        let next = 0;
+        const pump = () => {
+            // Catch up on wall-clock time so timer jitter doesn't stretch the video
+            const due = Math.min(sequence.length, Math.floor((performance.now() - start) / frameMs) + 1);
+            if (next < due) {
+                next = due;
+                ctx.putImageData(sequence[next - 1], 0, 0);
+                track.requestFrame();
+            }
+            if (next < sequence.length) {
+                setTimeout(pump, frameMs / 2);
+            } else {
+                setTimeout(() => recorder.stop(), frameMs);
+            }
+        };
+        pump();
+    });
+}
+
+export function downloadBlob(blob, filename) {
+    const url = URL.createObjectURL(blob);
+    const a = document.createElement('a');
+    a.href = url;
+    a.download = filename;
+    a.click();
+    setTimeout(() => URL.revokeObjectURL(url), 1000);
+}
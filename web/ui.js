@@ -5,6 +5,9 @@
 // genome interpretation, stats enhancements, camera hint, tutorial.
 // ============================================================
 
+import { Sonification } from './audio.js';
+import { recordingToWebM, downloadBlob } from './recording.js';
+
 // ---- Tool palette ----
 const tools = [
     { id: 1, name: 'Wall', key: '1', desc: 'Place barriers that block movement and temperature' },
@@ -15,19 +18,34 @@ const tools = [
     { id: 6, name: 'Remove', key: '6', desc: 'Erase any voxel back to empty space' },
     { id: 7, name: 'Heat', key: '7', desc: 'Place heat sources that raise local temperature' },
     { id: 8, name: 'Cold', key: '8', desc: 'Place cold sources that lower local temperature' },
+    { id: 9, name: 'Shell', key: '9', desc: 'Build a hollow sphere of walls around the clicked cell' },
+    { id: 10, name: 'Vessel', key: '0', desc: 'Build an open-topped cylinder of walls standing on the clicked cell' },
+    { id: 11, name: 'Plane', key: '-', desc: 'Build a wall slab across the world through the clicked cell' },
 ];
 
 const OVERLAY_DESCS = {
     'Normal': 'Standard material view',
-    'Temp': 'Temperature field (blue=cold, red=hot)',
+    'Temp': 'Temperature field (blue=coldest, red=hottest in the live range)',
     'Energy': 'Protocell energy levels (dark=low, bright=high)',
     'Pop': 'Species coloring by population',
+    'Trait': 'Protocells colored by the selected genome byte (purple=0, yellow=255)',
 };
 
+// Genome bytes for the trait overlay, in Genome byte order
+const TRAIT_NAMES = [
+    'Metabolic efficiency', 'Metabolic rate', 'Replication threshold', 'Mutation rate',
+    'Movement bias', 'Chemotaxis', 'Toxin resistance', 'Predation capability',
+    'Predation aggression', 'Photosynthesis', 'Energy split', 'Pheromone strength',
+    'Adhesion', 'Cold tolerance / toxin secretion', 'Storage capacity', 'Thermotaxis',
+];
+
 const PRESET_DESCS = {
     'Petri Dish': 'Central colony surrounded by nutrients',
     'Gradient': 'Temperature gradient with hot and cold zones',
     'Arena': 'Walled arena with energy sources at corners',
+    'Caves': 'Noise-carved rock caves threaded with nutrient veins',
+    'Pillars': 'Rock pillars rising through open space',
+    'Veins': 'Open space laced with nutrient veins',
 };
 
 let activeTool = 0;
@@ -66,11 +84,84 @@ function createToolbar() {
     toolbar.appendChild(sliderLabel);
     toolbar.appendChild(slider);
 
+    // Heat/cold source strength and lifetime (0 = permanent)
+    const strengthLabel = document.createElement('label');
+    strengthLabel.className = 'brush-label';
+    strengthLabel.textContent = 'Source: 100%';
+    const strengthSlider = document.createElement('input');
+    strengthSlider.type = 'range';
+    strengthSlider.min = '5';
+    strengthSlider.max = '100';
+    strengthSlider.step = '5';
+    strengthSlider.value = '100';
+    strengthSlider.dataset.tooltip = 'Heat/cold source strength';
+    const lifetimeInput = document.createElement('input');
+    lifetimeInput.type = 'number';
+    lifetimeInput.min = '0';
+    lifetimeInput.max = '65535';
+    lifetimeInput.value = '0';
+    lifetimeInput.style.width = '5em';
+    lifetimeInput.dataset.tooltip = 'Heat/cold source lifetime in ticks (0 = permanent)';
+    const updateThermal = () => {
+        const strength = parseInt(strengthSlider.value) / 100;
+        const lifetime = Math.max(0, parseInt(lifetimeInput.value) || 0);
+        strengthLabel.textContent = `Source: ${strengthSlider.value}%`;
+        if (window._bridge) window._bridge.set_thermal_source(strength, lifetime);
+    };
+    strengthSlider.addEventListener('input', updateThermal);
+    lifetimeInput.addEventListener('change', updateThermal);
+    toolbar.appendChild(strengthLabel);
+    toolbar.appendChild(strengthSlider);
+    toolbar.appendChild(lifetimeInput);
+
+    // Shape tool size: radius and wall thickness
+    const shapeLabel = document.createElement('label');
+    shapeLabel.className = 'brush-label';
+    shapeLabel.textContent = 'Shape: r8 t1';
+    const shapeRadius = document.createElement('input');
+    shapeRadius.type = 'range';
+    shapeRadius.min = '2';
+    shapeRadius.max = '64';
+    shapeRadius.value = '8';
+    shapeRadius.dataset.tooltip = 'Shell and vessel radius (vessels are 1.5x as tall)';
+    const shapeThickness = document.createElement('input');
+    shapeThickness.type = 'number';
+    shapeThickness.min = '1';
+    shapeThickness.max = '8';
+    shapeThickness.value = '1';
+    shapeThickness.style.width = '3em';
+    shapeThickness.dataset.tooltip = 'Shape wall thickness in cells';
+    const updateShape = () => {
+        const radius = parseInt(shapeRadius.value);
+        const thickness = Math.max(1, parseInt(shapeThickness.value) || 1);
+        shapeLabel.textContent = `Shape: r${radius} t${thickness}`;
+        if (window._bridge) window._bridge.set_shape_params(radius, thickness, Math.round(radius * 1.5), 1, 1);
+    };
+    shapeRadius.addEventListener('input', updateShape);
+    shapeThickness.addEventListener('change', updateShape);
+    toolbar.appendChild(shapeLabel);
+    toolbar.appendChild(shapeRadius);
+    toolbar.appendChild(shapeThickness);
+
+    // Edit symmetry: bits as in set_edit_symmetry
+    const symmetrySelect = document.createElement('select');
+    symmetrySelect.dataset.tooltip = 'Repeat edits at mirrored / rotated positions';
+    [['No symmetry', 0], ['Mirror X', 1], ['Mirror Z', 4], ['Mirror X+Z', 5], ['Mirror XYZ', 7], ['4-fold', 8]].forEach(([name, bits]) => {
+        const opt = document.createElement('option');
+        opt.value = String(bits);
+        opt.textContent = name;
+        symmetrySelect.appendChild(opt);
+    });
+    symmetrySelect.addEventListener('change', () => {
+        if (window._bridge) window._bridge.set_edit_symmetry(parseInt(symmetrySelect.value));
+    });
+    toolbar.appendChild(symmetrySelect);
+
     // ---- Overlay mode buttons ----
     const overlayDiv = document.createElement('div');
     overlayDiv.id = 'overlay-group';
     overlayDiv.style.marginTop = '8px';
-    const overlayModes = ['Normal', 'Temp', 'Energy', 'Pop'];
+    const overlayModes = ['Normal', 'Temp', 'Energy', 'Pop', 'Trait', 'Species'];
     let currentOverlay = 0;
     overlayModes.forEach((name, i) => {
         const btn = document.createElement('button');
@@ -83,9 +174,45 @@ function createToolbar() {
                 b.classList.toggle('active', j === i);
             });
             if (window._bridge) window._bridge.set_overlay_mode(i);
+            updateLegend();
         });
         overlayDiv.appendChild(btn);
     });
+    const traitSelect = document.createElement('select');
+    traitSelect.dataset.tooltip = 'Genome byte the Trait overlay colors protocells by';
+    TRAIT_NAMES.forEach((name, i) => {
+        const opt = document.createElement('option');
+        opt.value = String(i);
+        opt.textContent = name;
+        traitSelect.appendChild(opt);
+    });
+    traitSelect.value = '7';
+    if (window._bridge) window._bridge.set_overlay_trait(7);
+    traitSelect.addEventListener('change', () => {
+        if (window._bridge) window._bridge.set_overlay_trait(parseInt(traitSelect.value));
+    });
+    overlayDiv.appendChild(traitSelect);
+    const autoScale = document.createElement('label');
+    autoScale.className = 'brush-label';
+    autoScale.dataset.tooltip = 'Stretch the temperature overlay over the live temperature range instead of 0–1';
+    const autoScaleBox = document.createElement('input');
+    autoScaleBox.type = 'checkbox';
+    autoScaleBox.checked = true;
+    autoScaleBox.addEventListener('change', () => {
+        if (window._bridge) window._bridge.set_overlay_auto_scale(autoScaleBox.checked);
+        updateLegend();
+    });
+    autoScale.appendChild(autoScaleBox);
+    autoScale.appendChild(document.createTextNode(' Auto range'));
+    overlayDiv.appendChild(autoScale);
+    // Color scale of the active overlay (filled by updateLegend)
+    const legend = document.createElement('div');
+    legend.id = 'overlay-legend';
+    legend.style.display = 'none';
+    legend.style.fontSize = '11px';
+    legend.innerHTML = '<div class="legend-bar" style="height:8px;border-radius:2px"></div>'
+        + '<div style="display:flex;justify-content:space-between"><span class="legend-lo"></span><span class="legend-hi"></span></div>';
+    overlayDiv.appendChild(legend);
     toolbar.appendChild(overlayDiv);
 
     // ---- Tick rate controls ----
@@ -127,10 +254,92 @@ function createToolbar() {
         if (window._bridge) window._bridge.single_step();
     });
 
+    // Timing priority: real-time drops ticks when behind, sim-time catches up
+    const modeBtn = document.createElement('button');
+    modeBtn.className = 'tool-btn';
+    modeBtn.textContent = 'Real-time';
+    modeBtn.dataset.tooltip = 'Real-time: drop ticks when the GPU falls behind. Sim-time: never drop ticks, catch up over later frames.';
+    let simTimePriority = false;
+    modeBtn.addEventListener('click', () => {
+        simTimePriority = !simTimePriority;
+        modeBtn.textContent = simTimePriority ? 'Sim-time' : 'Real-time';
+        modeBtn.classList.toggle('active', simTimePriority);
+        if (window._bridge) window._bridge.set_timing_mode(simTimePriority ? 1 : 0);
+    });
+
     tickDiv.appendChild(tickLabel);
     tickDiv.appendChild(tickSlider);
     tickDiv.appendChild(pauseBtn);
     tickDiv.appendChild(stepBtn);
+    tickDiv.appendChild(modeBtn);
+
+    // Background behavior when the tab is hidden
+    const bgBtn = document.createElement('button');
+    bgBtn.className = 'tool-btn';
+    bgBtn.textContent = 'BG: Pause';
+    bgBtn.dataset.tooltip = 'When the tab is hidden: pause the simulation, or keep running headless at a reduced tick rate.';
+    let bgHeadless = false;
+    bgBtn.addEventListener('click', () => {
+        bgHeadless = !bgHeadless;
+        bgBtn.textContent = bgHeadless ? 'BG: Run' : 'BG: Pause';
+        bgBtn.classList.toggle('active', bgHeadless);
+        if (window._bridge) window._bridge.set_background_mode(bgHeadless ? 1 : 0);
+    });
+    tickDiv.appendChild(bgBtn);
+
+    // Sonification; the AudioContext is created on first click (user gesture)
+    const audioBtn = document.createElement('button');
+    audioBtn.className = 'tool-btn';
+    audioBtn.textContent = 'Audio: Off';
+    audioBtn.dataset.tooltip = 'Listen to the run: pitch follows population, pulse rate births, noise deaths, detune species turnover.';
+    let sonification = null;
+    audioBtn.addEventListener('click', () => {
+        const enable = !audioBtn.classList.contains('active');
+        if (enable && !sonification) {
+            sonification = new Sonification();
+            window._onAudio = (params) => sonification.update(params);
+        }
+        if (sonification) {
+            if (enable) sonification.resume(); else sonification.suspend();
+        }
+        audioBtn.textContent = enable ? 'Audio: On' : 'Audio: Off';
+        audioBtn.classList.toggle('active', enable);
+        if (window._bridge) window._bridge.set_audio_enabled(enable);
+    });
+    tickDiv.appendChild(audioBtn);
+
+    // Recording at 30 frames per simulated second, half canvas size
+    const recBtn = document.createElement('button');
+    recBtn.className = 'tool-btn';
+    recBtn.textContent = 'Rec';
+    recBtn.dataset.tooltip = 'Record the view to WebM, timed by simulation ticks: pausing pauses the video, faster ticking plays faster.';
+    recBtn.addEventListener('click', () => {
+        if (!window._bridge || recBtn.disabled) return;
+        if (!recBtn.classList.contains('active')) {
+            if (window._bridge.start_recording(30, 0.5)) {
+                recBtn.classList.add('active');
+                recBtn.textContent = 'Stop';
+            }
+            return;
+        }
+        recBtn.classList.remove('active');
+        const rec = window._bridge.stop_recording();
+        if (!rec || rec.frames.length === 0) {
+            recBtn.textContent = 'Rec';
+            return;
+        }
+        if (rec.truncated) console.warn('Recording hit its memory cap; later frames were dropped');
+        recBtn.disabled = true;
+        recBtn.textContent = 'Encoding\u2026';
+        recordingToWebM(rec)
+            .then((blob) => downloadBlob(blob, 'primordium.webm'))
+            .catch((e) => console.warn('Recording export failed:', e))
+            .finally(() => {
+                recBtn.disabled = false;
+                recBtn.textContent = 'Rec';
+            });
+    });
+    tickDiv.appendChild(recBtn);
     toolbar.appendChild(tickDiv);
 
     // ---- Preset buttons ----
@@ -146,6 +355,9 @@ function createToolbar() {
         { id: 0, name: 'Petri Dish' },
         { id: 1, name: 'Gradient' },
         { id: 2, name: 'Arena' },
+        { id: 3, name: 'Caves' },
+        { id: 4, name: 'Pillars' },
+        { id: 5, name: 'Veins' },
     ];
     presets.forEach(p => {
         const btn = document.createElement('button');
@@ -157,7 +369,139 @@ function createToolbar() {
         });
         presetDiv.appendChild(btn);
     });
+
+    const tutorialBtn = document.createElement('button');
+    tutorialBtn.className = 'preset-btn';
+    tutorialBtn.textContent = 'Tutorial';
+    tutorialBtn.dataset.tooltip = 'Guided walkthrough: place energy, seed protocells and watch them replicate.';
+    tutorialBtn.addEventListener('click', () => {
+        if (window._bridge) window._bridge.start_tutorial();
+    });
+    presetDiv.appendChild(tutorialBtn);
+
+    const heightmapInput = document.createElement('input');
+    heightmapInput.type = 'file';
+    heightmapInput.accept = 'image/png';
+    heightmapInput.style.display = 'none';
+    heightmapInput.addEventListener('change', async () => {
+        const file = heightmapInput.files[0];
+        heightmapInput.value = '';
+        if (!file || !window._bridge) return;
+        try {
+            window._bridge.seed_from_image(new Uint8Array(await file.arrayBuffer()), 0);
+        } catch (e) {
+            console.warn('Heightmap import failed:', e);
+        }
+    });
+    const heightmapBtn = document.createElement('button');
+    heightmapBtn.className = 'preset-btn';
+    heightmapBtn.textContent = 'Heightmap';
+    heightmapBtn.dataset.tooltip = 'Build terrain from a grayscale PNG: brighter pixels raise taller walls under denser nutrients.';
+    heightmapBtn.addEventListener('click', () => heightmapInput.click());
+    presetDiv.appendChild(heightmapBtn);
+    presetDiv.appendChild(heightmapInput);
     toolbar.appendChild(presetDiv);
+
+    // ---- Accessibility ----
+    const a11yDiv = document.createElement('div');
+    a11yDiv.id = 'a11y-group';
+    a11yDiv.style.marginTop = '8px';
+    const a11yLabel = document.createElement('label');
+    a11yLabel.className = 'brush-label';
+    a11yLabel.textContent = 'View';
+    a11yDiv.appendChild(a11yLabel);
+
+    const palettes = ['Palette: Default', 'Palette: Deutan', 'Palette: Protan'];
+    let paletteIdx = 0;
+    const paletteBtn = document.createElement('button');
+    paletteBtn.className = 'preset-btn';
+    paletteBtn.textContent = palettes[0];
+    paletteBtn.dataset.tooltip = 'Cycle color palettes: default, deuteranopia-safe, protanopia-safe.';
+    paletteBtn.addEventListener('click', () => {
+        paletteIdx = (paletteIdx + 1) % palettes.length;
+        paletteBtn.textContent = palettes[paletteIdx];
+        paletteBtn.classList.toggle('active', paletteIdx !== 0);
+        if (window._bridge) window._bridge.set_palette(paletteIdx);
+    });
+    a11yDiv.appendChild(paletteBtn);
+
+    const flickerBtn = document.createElement('button');
+    flickerBtn.className = 'preset-btn';
+    flickerBtn.textContent = 'Reduce flicker';
+    flickerBtn.dataset.tooltip = 'Ease the volume between frames and soften bright highlights.';
+    const setFlicker = (on) => {
+        flickerBtn.classList.toggle('active', on);
+        if (window._bridge) window._bridge.set_reduced_flicker(on);
+    };
+    flickerBtn.addEventListener('click', () => setFlicker(!flickerBtn.classList.contains('active')));
+    // Follow the OS preference once the bridge is up
+    if (window.matchMedia && window.matchMedia('(prefers-reduced-motion: reduce)').matches) {
+        if (window._bridge) setFlicker(true);
+        else window.addEventListener('bridge-ready', () => setFlicker(true), { once: true });
+    }
+    a11yDiv.appendChild(flickerBtn);
+
+    const minimapBtn = document.createElement('button');
+    minimapBtn.className = 'preset-btn active';
+    minimapBtn.textContent = 'Minimap';
+    minimapBtn.dataset.tooltip = 'Top-down overview in the bottom-right corner; click it to move the camera there.';
+    minimapBtn.addEventListener('click', () => {
+        const on = !minimapBtn.classList.contains('active');
+        minimapBtn.classList.toggle('active', on);
+        if (window._bridge) window._bridge.set_minimap_enabled(on);
+    });
+    a11yDiv.appendChild(minimapBtn);
+
+    const graphBtn = document.createElement('button');
+    graphBtn.className = 'preset-btn';
+    graphBtn.textContent = 'Graph';
+    graphBtn.dataset.tooltip = 'Population (green) and species count (orange) history, drawn on the GPU in the bottom-left corner.';
+    graphBtn.addEventListener('click', () => {
+        const on = !graphBtn.classList.contains('active');
+        graphBtn.classList.toggle('active', on);
+        if (window._bridge) window._bridge.set_graph_enabled(on);
+    });
+    a11yDiv.appendChild(graphBtn);
+
+    const sparseBtn = document.createElement('button');
+    sparseBtn.className = 'preset-btn';
+    sparseBtn.textContent = 'Sparse';
+    sparseBtn.dataset.tooltip = 'Store the world in 8\u00b3 bricks so only occupied regions use memory. Migrates the running world in place; click again to go back to dense.';
+    sparseBtn.addEventListener('click', () => {
+        if (!window._bridge) return;
+        const caps = window._bridge.get_capability_report();
+        const on = !(caps && caps.mode === 'sparse');
+        if (window._bridge.set_sparse_mode(on, 0)) sparseBtn.classList.toggle('active', on);
+    });
+    a11yDiv.appendChild(sparseBtn);
+    toolbar.appendChild(a11yDiv);
+}
+
+// Redraw the overlay legend from get_overlay_legend()
+function updateLegend() {
+    const el = document.getElementById('overlay-legend');
+    if (!el || !window._bridge) return;
+    const legend = window._bridge.get_overlay_legend();
+    if (!legend) {
+        el.style.display = 'none';
+        return;
+    }
+    el.style.display = 'block';
+    const css = ([r, g, b, a]) => `rgba(${Math.round(r * 255)},${Math.round(g * 255)},${Math.round(b * 255)},${Math.max(a, 0.3)})`;
+    const stops = legend.stops;
+    const lo = stops[0].value;
+    const hi = stops[stops.length - 1].value;
+    const gradient = stops.map(s => `${css(s.color)} ${hi > lo ? ((s.value - lo) / (hi - lo)) * 100 : 0}%`);
+    el.querySelector('.legend-bar').style.background = `linear-gradient(to right, ${gradient.join(', ')})`;
+    const fmt = v => (Math.abs(v) >= 10 ? v.toFixed(0) : v.toFixed(2));
+    if (legend.name === 'population') {
+        el.querySelector('.legend-lo').textContent = 'other';
+        el.querySelector('.legend-hi').textContent = 'protocell';
+    } else {
+        el.querySelector('.legend-lo').textContent = fmt(lo);
+        el.querySelector('.legend-hi').textContent = fmt(hi);
+    }
+    el.title = `${legend.name}: ${fmt(legend.min)} – ${fmt(legend.max)}` + (legend.name === 'temperature' && legend.auto_scale ? ' (auto range)' : '');
 }
 
 function selectTool(id) {
@@ -243,13 +587,51 @@ function updateStats(stats) {
         divTag = '<span class="stat-indicator diverse">diverse</span>';
     }
 
+    // Simulation clock vs wall clock
+    const clock = window._bridge && window._bridge.get_clock_info ? window._bridge.get_clock_info() : null;
+    const clockRows = clock
+        ? `<span class="stat-label">Ticks</span><span class="stat-value">${clock.tick_count}</span><br>` +
+          `<span class="stat-label">Sim Time</span><span class="stat-value">${clock.sim_seconds.toFixed(1)}s</span><br>` +
+          `<span class="stat-label">Ticks/s</span><span class="stat-value">${clock.effective_tps.toFixed(1)}</span><br>`
+        : '';
+
+    // Fraction of protocells with >= 3 same-species neighbors (dense GPU and CPU only)
+    const clusterRow = stats.clustering !== undefined
+        ? `<span class="stat-label">Clustering</span><span class="stat-value">${(stats.clustering * 100).toFixed(1)}%</span><br>`
+        : '';
+
+    // Population-mean mutation-rate gene (0..255); drifts as evolvability evolves
+    const mutationRow = stats.mean_mutation_rate !== undefined
+        ? `<span class="stat-label">Mean Mut. Rate</span><span class="stat-value">${stats.mean_mutation_rate.toFixed(1)}</span><br>`
+        : '';
+
+    // Cells contested by 2+ movers/replicators and prey with 2+ predators,
+    // and the intents that lost those contests, per tick
+    const conflicts = stats.conflicts;
+    const conflictRow = conflicts && conflicts.ticks > 0
+        ? `<span class="stat-label">Conflicts/Tick</span><span class="stat-value">${(conflicts.placement / conflicts.ticks).toFixed(1)} / ${(conflicts.predation / conflicts.ticks).toFixed(1)}</span><br>` +
+          `<span class="stat-label">Rejected/Tick</span><span class="stat-value">${(conflicts.rejected_placement / conflicts.ticks).toFixed(1)} / ${(conflicts.rejected_predation / conflicts.ticks).toFixed(1)}</span><br>`
+        : '';
+
+    // Goal-zone challenge leader (only while a goal zone is set)
+    const leader = stats.challenge && stats.challenge[0];
+    const goalRow = leader
+        ? `<span class="stat-label">Goal Leader</span><span class="stat-value">#${leader.species} (${leader.score}${leader.completed_tick !== null ? ' ✓' : ''})</span><br>`
+        : '';
+
     panel.innerHTML =
         `<span class="stat-label">Grid</span><span class="stat-value">${gs || '?'}³</span><br>` +
         `<span class="stat-label">Population</span><span class="stat-value">${stats.population}</span><br>` +
         `<span class="stat-label">Occupancy</span><span class="stat-value">${occupancy}%</span><br>` +
-        `<span class="stat-label">Species</span><span class="stat-value">${stats.species_count}</span>${divTag}<br>` +
+        `<span class="stat-label">Species</span><span class="stat-value">${stats.species_count}${stats.other_species > 0 ? '+' : ''}</span>${divTag}<br>` +
         `<span class="stat-label">Avg Energy</span><span class="stat-value">${avgEnergy}</span>${healthTag}<br>` +
         `<span class="stat-label">Max Energy</span><span class="stat-value">${stats.max_energy}</span><br>` +
+        `<span class="stat-label">Toxin Deaths</span><span class="stat-value">${stats.toxin_deaths ?? 0}</span><br>` +
+        clusterRow +
+        mutationRow +
+        conflictRow +
+        goalRow +
+        clockRows +
         `<span class="stat-label">FPS</span><span class="stat-value">${lastFps}</span>`;
 }
 
@@ -271,6 +653,12 @@ function updateGraph(stats) {
     if (!canvas) return;
     const ctx = canvas.getContext('2d');
 
+    // Graph species in their species-overlay colors
+    const overlayColors = window._bridge && window._bridge.get_species_colors();
+    for (const { species_id, color } of overlayColors || []) {
+        speciesColors.set(species_id, `rgb(${color.slice(0, 3).map(c => Math.round(c * 255)).join(', ')})`);
+    }
+
     // Record data point
     const speciesMap = new Map();
     if (stats.species) {
@@ -378,29 +766,54 @@ function showInspector(pick, screenX, screenY) {
     let html = `<div class="pick-header">${typeName} (${pick.x}, ${pick.y}, ${pick.z})</div>`;
     html += `Energy: ${pick.energy}<br>`;
     html += `Age: ${pick.age}<br>`;
+    html += `Temperature: ${pick.temperature.toFixed(2)}<br>`;
+    if (pick.toxin > 0) html += `Toxin: ${pick.toxin}<br>`;
+    if (pick.scent > 0) html += `Scent: ${pick.scent}<br>`;
 
     if (pick.voxel_type === 4) {
-        html += `Species: ${pick.species_id}<br><br>`;
+        const guarded = window._bridge && window._bridge.get_protected_species().includes(pick.species_id);
+        html += `Species: ${pick.species_id}${guarded ? ' (protected)' : ''}<br><br>`;
         const genome = pick.genome;
         for (let i = 0; i < GENOME_INFO.length; i++) {
             const gi = GENOME_INFO[i];
             const v = genome[i];
             html += `<span class="genome-row">${gi.name}: ${v} <span class="genome-interp">(${gi.fn(v)})</span></span><br>`;
         }
-        // Reserved bytes 11-15: show only if non-zero
-        for (let i = 11; i < 16; i++) {
-            if (genome[i] !== 0) {
-                html += `<span class="genome-row">reserved_${i}: ${genome[i]}</span><br>`;
-            }
+        // Traits past the annotated set, decoded by the host
+        for (const [name, v] of Object.entries(pick.traits).slice(GENOME_INFO.length)) {
+            html += `<span class="genome-row">${name}: ${v}</span><br>`;
         }
     }
 
+    if (pick.region) {
+        html += regionSummary(pick.region);
+    }
+
     tip.innerHTML = html;
     tip.style.display = 'block';
     tip.style.left = Math.min(screenX + 16, window.innerWidth - 340) + 'px';
     tip.style.top = Math.min(screenY + 16, window.innerHeight - 300) + 'px';
 }
 
+// One line per non-empty voxel type in the pick neighborhood, plus its
+// mean temperature.
+function regionSummary(region) {
+    const counts = {};
+    let tempSum = 0;
+    for (const c of region.cells) {
+        if (c.voxel_type !== 0) counts[c.voxel_type] = (counts[c.voxel_type] || 0) + 1;
+        tempSum += c.temperature;
+    }
+    const [w, h, d] = region.dims;
+    let html = `<br><div class="pick-header">Neighborhood ${w}\u00d7${h}\u00d7${d}</div>`;
+    for (const [type, n] of Object.entries(counts)) {
+        html += `${VOXEL_TYPE_NAMES[type] || 'Unknown'}: ${n}<br>`;
+    }
+    const meanTemp = region.cells.length ? tempSum / region.cells.length : 0;
+    html += `Mean temperature: ${meanTemp.toFixed(2)}<br>`;
+    return html;
+}
+
 function hideInspector() {
     const tip = document.getElementById('inspector-tooltip');
     if (tip) tip.style.display = 'none';
@@ -411,6 +824,10 @@ const PARAMS = [
     { name: 'nutrient_spawn_rate', min: 0, max: 0.01, step: 0.0001, default: 0.001, group: 'Resources', desc: 'Rate new nutrients appear in empty voxels' },
     { name: 'waste_decay_ticks', min: 10, max: 500, step: 1, default: 100, group: 'Resources', desc: 'Ticks before waste decomposes' },
     { name: 'nutrient_recycle_rate', min: 0, max: 1, step: 0.01, default: 0.5, group: 'Resources', desc: 'Fraction of waste that becomes nutrients' },
+    { name: 'nutrient_pattern', min: 0, max: 2, step: 1, default: 0, group: 'Resources', desc: 'Where nutrients regrow: 0 = uniform, 1 = patchy, 2 = moving front' },
+    { name: 'nutrient_capacity', min: 1, max: 6, step: 1, default: 3, group: 'Resources', desc: 'Nutrient neighbors at which regrowth stops (logistic cap)' },
+    { name: 'nutrient_waste_boost', min: 0, max: 5, step: 0.1, default: 1, group: 'Resources', desc: 'Extra regrowth per adjacent waste voxel' },
+    { name: 'nutrient_front_speed', min: 0, max: 4, step: 0.1, default: 0.5, group: 'Resources', desc: 'Speed of the moving front pattern (voxels per tick)' },
     { name: 'energy_from_nutrient', min: 10, max: 500, step: 5, default: 50, group: 'Energy', desc: 'Energy gained by consuming a nutrient' },
     { name: 'energy_from_source', min: 10, max: 500, step: 5, default: 10, group: 'Energy', desc: 'Energy gained from energy source voxels' },
     { name: 'max_energy', min: 100, max: 65535, step: 100, default: 1000, group: 'Energy', desc: 'Maximum energy a protocell can store' },
@@ -418,13 +835,36 @@ const PARAMS = [
     { name: 'movement_energy_cost', min: 0, max: 20, step: 0.5, default: 5, group: 'Energy', desc: 'Energy spent per movement action' },
     { name: 'replication_energy_min', min: 50, max: 1000, step: 10, default: 200, group: 'Energy', desc: 'Minimum energy needed to replicate' },
     { name: 'base_ambient_temp', min: 0, max: 1, step: 0.01, default: 0.5, group: 'Temperature', desc: 'Background temperature (0=cold, 1=hot)' },
-    { name: 'diffusion_rate', min: 0, max: 0.25, step: 0.005, default: 0.1, group: 'Temperature', desc: 'How fast temperature spreads (max 0.25)' },
+    { name: 'diffusion_rate', min: 0, max: 1, step: 0.005, default: 0.1, group: 'Temperature', desc: 'How fast temperature spreads; above 0.25 add sub-steps to keep it stable' },
+    { name: 'diffusion_substeps', min: 1, max: 8, step: 1, default: 1, group: 'Temperature', desc: 'Diffusion sub-steps per tick, each at rate / sub-steps' },
+    { name: 'thermal_boundary', min: 0, max: 2, step: 1, default: 0, group: 'Temperature', desc: 'Grid edges: 0 = insulating, 1 = held at ambient, 2 = periodic (wrap around)' },
     { name: 'temp_sensitivity', min: 0, max: 2, step: 0.05, default: 1, group: 'Temperature', desc: 'How much temperature affects metabolism' },
+    { name: 'thermotaxis_response', min: 0, max: 2, step: 0.05, default: 1, group: 'Temperature', desc: 'How strongly cells steer toward their preferred temperature' },
+    { name: 'dormancy_temp', min: 0, max: 1, step: 0.01, default: 0.2, group: 'Temperature', desc: 'Below this (minus cold tolerance) protocells go dormant' },
+    { name: 'dormancy_cost_scale', min: 0, max: 1, step: 0.05, default: 0.1, group: 'Temperature', desc: 'Metabolic cost multiplier while dormant' },
+    { name: 'heat_shock_temp', min: 0, max: 1, step: 0.01, default: 0.85, group: 'Temperature', desc: 'Above this protocells take heat-shock damage' },
+    { name: 'heat_shock_damage', min: 0, max: 100, step: 1, default: 20, group: 'Temperature', desc: 'Energy lost per tick to heat shock' },
+    { name: 'storage_capacity_bonus', min: 0, max: 4, step: 0.1, default: 1, group: 'Energy', desc: 'Extra energy capacity (x max energy) at full storage gene; surplus goes to offspring' },
+    { name: 'storage_metabolic_cost', min: 0, max: 2, step: 0.05, default: 0.5, group: 'Energy', desc: 'Extra base metabolism at full storage gene' },
     { name: 'predation_energy_fraction', min: 0, max: 1, step: 0.05, default: 0.5, group: 'Combat', desc: 'Fraction of prey energy gained by predator' },
+    { name: 'toxin_secretion_rate', min: 0, max: 32, step: 0.5, default: 4, group: 'Combat', desc: 'Toxin a full-strength secretor adds to its cell per tick' },
+    { name: 'toxin_secretion_cost', min: 0, max: 20, step: 0.5, default: 2, group: 'Combat', desc: 'Energy per tick a full-strength secretor spends on toxin' },
+    { name: 'toxin_damage', min: 0, max: 200, step: 1, default: 20, group: 'Combat', desc: 'Energy lost per tick at full toxin above a cell\'s resistance' },
+    { name: 'toxin_decay', min: 0, max: 1, step: 0.01, default: 0.1, group: 'Combat', desc: 'Fraction of field toxin lost per tick' },
+    { name: 'crowding_threshold', min: 0, max: 6, step: 1, default: 4, group: 'Crowding', desc: 'Protocell neighbors tolerated before crowding applies (6 = off)' },
+    { name: 'crowding_cost', min: 0, max: 20, step: 0.5, default: 2, group: 'Crowding', desc: 'Extra energy per tick for each neighbor over the threshold' },
+    { name: 'crowding_blocks_replication', min: 0, max: 1, step: 1, default: 1, group: 'Crowding', desc: 'Crowded protocells cannot replicate (1 = on)' },
+    { name: 'pheromone_deposit', min: 0, max: 0.5, step: 0.01, default: 0.05, group: 'Territory', desc: 'Scent a full-strength protocell adds to its cell per tick' },
+    { name: 'pheromone_decay', min: 0, max: 0.5, step: 0.01, default: 0.02, group: 'Territory', desc: 'Fraction of scent lost per tick' },
+    { name: 'pheromone_spread', min: 0, max: 1, step: 0.05, default: 0.7, group: 'Territory', desc: 'Fraction of a neighboring cell\'s scent that spreads in' },
+    { name: 'pheromone_response', min: 0, max: 2, step: 0.05, default: 1, group: 'Territory', desc: 'How strongly the scent gene steers movement (own scent attracts, rival scent repels)' },
+    { name: 'mutation_rate_min', min: 0, max: 255, step: 1, default: 0, group: 'Evolution', desc: 'Lowest value the mutation-rate gene can mutate to' },
+    { name: 'mutation_rate_max', min: 0, max: 255, step: 1, default: 255, group: 'Evolution', desc: 'Highest value the mutation-rate gene can mutate to' },
+    { name: 'arbitration_policy', min: 0, max: 2, step: 1, default: 0, group: 'Simulation', desc: 'Who wins a contested cell: 0 = highest bid, 1 = random weighted by bid, 2 = random (hashed priority)' },
     { name: 'dt', min: 0.01, max: 1.0, step: 0.01, default: 0.016, group: 'Simulation', desc: 'Time step per tick (lower = more precise)' },
 ];
 
-const PARAM_GROUP_ORDER = ['Resources', 'Energy', 'Temperature', 'Combat', 'Simulation'];
+const PARAM_GROUP_ORDER = ['Resources', 'Energy', 'Temperature', 'Combat', 'Crowding', 'Territory', 'Evolution', 'Simulation'];
 
 function createParamsPanel() {
     const panel = document.getElementById('params-panel');
@@ -660,6 +1100,73 @@ function positionTutorialCard(targetRect) {
 window._onStats = (stats) => {
     updateStats(stats);
     updateGraph(stats);
+    updateLegend();
+};
+
+// Marker labels (called every frame by input.js), placed over the
+// GPU-drawn billboards
+window._onMarkers = (markers) => {
+    let layer = document.getElementById('marker-labels');
+    if (!layer) {
+        if (markers.length === 0) return;
+        layer = document.createElement('div');
+        layer.id = 'marker-labels';
+        document.body.appendChild(layer);
+    }
+    const canvas = document.getElementById('gpu-canvas');
+    const rect = canvas.getBoundingClientRect();
+    while (layer.children.length > markers.length) layer.lastChild.remove();
+    while (layer.children.length < markers.length) {
+        const el = document.createElement('div');
+        el.className = 'marker-label';
+        layer.appendChild(el);
+    }
+    markers.forEach((m, i) => {
+        const el = layer.children[i];
+        if (!m.screen) {
+            el.style.display = 'none';
+            return;
+        }
+        el.style.display = '';
+        el.textContent = m.label || `#${m.id}`;
+        el.style.borderColor = m.color;
+        el.style.left = (rect.left + m.screen[0] * rect.width + 10) + 'px';
+        el.style.top = (rect.top + m.screen[1] * rect.height - 8) + 'px';
+    });
+};
+
+// Guided scenario panel (called with each stats poll; null hides it)
+window._onScenario = (state) => {
+    let panel = document.getElementById('scenario-panel');
+    if (!state) {
+        if (panel) panel.remove();
+        return;
+    }
+    if (!panel) {
+        panel = document.createElement('div');
+        panel.id = 'scenario-panel';
+        panel.innerHTML = '<div class="scenario-title"></div><div class="scenario-message"></div>'
+            + '<div class="scenario-waiting"></div><div class="scenario-actions"></div>';
+        const continueBtn = document.createElement('button');
+        continueBtn.className = 'preset-btn scenario-continue';
+        continueBtn.textContent = 'Continue';
+        continueBtn.addEventListener('click', () => window._bridge && window._bridge.scenario_continue());
+        const closeBtn = document.createElement('button');
+        closeBtn.className = 'preset-btn';
+        closeBtn.textContent = 'Close';
+        closeBtn.addEventListener('click', () => {
+            if (window._bridge) window._bridge.stop_scenario();
+            panel.remove();
+        });
+        panel.querySelector('.scenario-actions').append(continueBtn, closeBtn);
+        document.body.appendChild(panel);
+    }
+    const step = Math.min(state.step + 1, state.steps);
+    panel.querySelector('.scenario-title').textContent = `${state.title || 'Scenario'} (${step}/${state.steps})`;
+    panel.querySelector('.scenario-message').textContent = state.message;
+    const waiting = state.waiting && state.waiting !== 'continue' ? `Waiting for ${state.waiting}` : '';
+    panel.querySelector('.scenario-waiting').textContent = state.finished ? 'Finished' : waiting;
+    panel.querySelector('.scenario-continue').style.display = state.waiting === 'continue' ? '' : 'none';
 };
 
 // Pick callback (called by input.js animation loop)